@@ -1,7 +1,10 @@
 use std::env;
 
 use gents::FileGroup;
-use logisheets_controller::controller::display::{DisplayRequest, DisplayResponse};
+use logisheets_controller::controller::display::{
+    CircularRef, Dependency, DisplayRequest, DisplayResponse, EvalTrace, FormulaDiagnostic,
+    FormulaToken, FunctionInfo,
+};
 use logisheets_controller::controller::edit_action::{ActionEffect, EditAction};
 
 fn main() {
@@ -12,5 +15,11 @@ fn main() {
     file_group.add::<DisplayResponse>();
     file_group.add::<EditAction>();
     file_group.add::<ActionEffect>();
+    file_group.add::<EvalTrace>();
+    file_group.add::<Dependency>();
+    file_group.add::<CircularRef>();
+    file_group.add::<FormulaDiagnostic>();
+    file_group.add::<FormulaToken>();
+    file_group.add::<FunctionInfo>();
     file_group.gen_files(path, true);
 }
@@ -0,0 +1,252 @@
+use crate::{ArgCount, ArgDef};
+
+lazy_static! {
+    static ref BUILTIN_FUNCS: Vec<FuncInfo> = include_str!("signatures.txt")
+        .lines()
+        .filter(|l| !l.is_empty() && !l.starts_with('#'))
+        .map(parse_func_info)
+        .collect();
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArgType {
+    Any,
+    Number,
+    Text,
+    Logical,
+    /// Only a reference is accepted, not a constant.
+    Reference,
+    /// A reference or an array constant.
+    Array,
+    /// A LAMBDA.
+    Function,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArgInfo {
+    pub name: String,
+    pub arg_type: ArgType,
+    pub optional: bool,
+    /// Whether the argument is in the group repeated at the end.
+    pub repeated: bool,
+}
+
+/// The signature and the description of a function, for the autocomplete
+/// and the parameter tooltips.
+#[derive(Debug, Clone)]
+pub struct FuncInfo {
+    pub name: String,
+    /// The prefix of the name in files, like `_xlfn.`, or empty for the
+    /// functions of Excel 2007.
+    pub prefix: String,
+    pub description: String,
+    pub args: Vec<ArgInfo>,
+    pub arg_count: ArgCount,
+}
+
+impl FuncInfo {
+    /// The argument definitions for `FuncSignature`.
+    pub fn get_arg_defs(&self) -> Vec<ArgDef> {
+        let repeat_start = self.args.iter().position(|a| a.repeated);
+        self.args
+            .iter()
+            .enumerate()
+            .map(|(i, a)| ArgDef {
+                arg_name: a.name.clone(),
+                ref_only: Some(a.arg_type == ArgType::Reference),
+                start_repeated: Some(repeat_start == Some(i)),
+            })
+            .collect()
+    }
+}
+
+/// The built-in functions, sorted by name.
+pub fn get_builtin_funcs() -> &'static [FuncInfo] {
+    &BUILTIN_FUNCS
+}
+
+/// Find a built-in function by its name, which is case-insensitive. The
+/// names saved with the `_xlfn.` prefix are found too.
+pub fn get_builtin_func(name: &str) -> Option<&'static FuncInfo> {
+    let name = name.to_uppercase();
+    let name = name.strip_prefix("_XLFN.").unwrap_or(&name);
+    let name = name.strip_prefix("_XLWS.").unwrap_or(name);
+    BUILTIN_FUNCS
+        .binary_search_by(|f| f.name.as_str().cmp(name))
+        .ok()
+        .map(|i| &BUILTIN_FUNCS[i])
+}
+
+/// The prefix of a built-in function in files, or empty for the others.
+pub fn get_func_prefix(name: &str) -> &'static str {
+    get_builtin_func(name).map_or("", |f| f.prefix.as_str())
+}
+
+// Parse a line like `SUMIF(range: reference, criteria, [sum_range]) Adds...`.
+fn parse_func_info(line: &str) -> FuncInfo {
+    let open = line.find('(').unwrap();
+    let close = line.find(')').unwrap();
+    let (args, depths) = parse_args(&line[open + 1..close]);
+    let arg_count = get_arg_count(&args, &depths);
+    let name = line[..open]
+        .trim_start_matches("_xlfn.")
+        .trim_start_matches("_xlws.");
+    FuncInfo {
+        name: name.to_string(),
+        prefix: line[..open - name.len()].to_string(),
+        description: line[close + 1..].trim().to_string(),
+        args,
+        arg_count,
+    }
+}
+
+// The arguments and how deep they are in the brackets.
+fn parse_args(s: &str) -> (Vec<ArgInfo>, Vec<usize>) {
+    let mut args: Vec<ArgInfo> = vec![];
+    let mut depths = vec![];
+    let mut depth = 0;
+    let mut group_start = 0;
+    let mut rest = s;
+    while let Some(c) = rest.chars().next() {
+        match c {
+            '[' => {
+                if depth == 0 {
+                    group_start = args.len();
+                }
+                depth += 1;
+                rest = &rest[1..];
+            }
+            ']' => {
+                depth -= 1;
+                rest = &rest[1..];
+                if depth == 0 {
+                    if let Some(r) = rest.strip_prefix("...") {
+                        args[group_start..]
+                            .iter_mut()
+                            .for_each(|a| a.repeated = true);
+                        rest = r;
+                    }
+                }
+            }
+            ',' | ' ' => rest = &rest[1..],
+            _ => {
+                let end = rest.find(&[',', '[', ']'][..]).unwrap_or(rest.len());
+                let (name, arg_type) = match rest[..end].split_once(':') {
+                    Some((name, t)) => (name, parse_arg_type(t.trim())),
+                    None => (&rest[..end], ArgType::Any),
+                };
+                args.push(ArgInfo {
+                    name: name.trim().to_string(),
+                    arg_type,
+                    optional: depth > 0,
+                    repeated: false,
+                });
+                depths.push(depth);
+                rest = &rest[end..];
+            }
+        }
+    }
+    (args, depths)
+}
+
+fn parse_arg_type(s: &str) -> ArgType {
+    match s {
+        "number" => ArgType::Number,
+        "text" => ArgType::Text,
+        "logical" => ArgType::Logical,
+        "reference" => ArgType::Reference,
+        "array" => ArgType::Array,
+        "function" => ArgType::Function,
+        _ => ArgType::Any,
+    }
+}
+
+// The arguments in a repeated pair come in pairs, if they are all required
+// in the pair and the arguments before them are required.
+fn get_arg_count(args: &[ArgInfo], depths: &[usize]) -> ArgCount {
+    let required = args.iter().filter(|a| !a.optional).count() as u8;
+    let repeated = args
+        .iter()
+        .zip(depths.iter())
+        .filter(|(a, _)| a.repeated)
+        .map(|(_, d)| *d)
+        .collect::<Vec<_>>();
+    let paired = repeated.len() == 2
+        && repeated.iter().all(|d| *d == 1)
+        && args.iter().take_while(|a| !a.repeated).all(|a| !a.optional);
+    let before_pair = (args.len() - repeated.len()) % 2;
+    ArgCount {
+        le: if repeated.is_empty() {
+            Some(args.len() as u8)
+        } else {
+            None
+        },
+        ge: Some(required),
+        eq: None,
+        odd: Some(true).filter(|_| paired && before_pair == 1),
+        even: Some(true).filter(|_| paired && before_pair == 0),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builtin_funcs_test() {
+        let funcs = get_builtin_funcs();
+        assert!(funcs.windows(2).all(|w| w[0].name < w[1].name));
+        funcs.iter().for_each(|f| {
+            assert!(!f.description.is_empty(), "{}", f.name);
+            assert!(
+                ["", "_xlfn.", "_xlfn._xlws."].contains(&f.prefix.as_str()),
+                "{}",
+                f.name
+            );
+            // The optional arguments come after the required ones.
+            let required = f.args.iter().take_while(|a| !a.optional).count();
+            assert!(f.args[required..].iter().all(|a| a.optional), "{}", f.name);
+            // Only the arguments at the end are repeated.
+            let repeated = f.args.iter().skip_while(|a| !a.repeated).count();
+            assert_eq!(
+                repeated,
+                f.args.iter().filter(|a| a.repeated).count(),
+                "{}",
+                f.name
+            );
+        });
+    }
+
+    #[test]
+    fn get_builtin_func_test() {
+        let f = get_builtin_func("sumifs").unwrap();
+        assert_eq!(f.args.len(), 5);
+        assert_eq!(f.args[1].arg_type, ArgType::Reference);
+        assert!(!f.args[2].optional && f.args[3].optional && f.args[3].repeated);
+        assert!(f.arg_count.check(3));
+        assert!(!f.arg_count.check(4));
+        assert!(f.arg_count.check(7));
+        let defs = f.get_arg_defs();
+        assert_eq!(defs[3].start_repeated, Some(true));
+        assert_eq!(defs[0].ref_only, Some(true));
+
+        let f = get_builtin_func("_xlfn.IFS").unwrap();
+        assert!(!f.arg_count.check(3));
+        assert!(f.arg_count.check(4));
+        let f = get_builtin_func("SWITCH").unwrap();
+        assert!(!f.arg_count.check(2));
+        assert!(f.arg_count.check(4));
+        assert!(f.arg_count.check(5));
+        let f = get_builtin_func("ROUND").unwrap();
+        assert!(!f.arg_count.check(1));
+        assert!(!f.arg_count.check(3));
+        assert!(get_builtin_func("RAND").unwrap().arg_count.check(0));
+        assert!(get_builtin_func("FOO").is_none());
+
+        assert_eq!(get_func_prefix("_xlfn.XLOOKUP"), "_xlfn.");
+        assert_eq!(get_func_prefix("ceiling.math"), "_xlfn.");
+        assert_eq!(get_func_prefix("FILTER"), "_xlfn._xlws.");
+        assert_eq!(get_func_prefix("CEILING"), "");
+        assert_eq!(get_func_prefix("FOO"), "");
+    }
+}
@@ -1,30 +1,104 @@
+#[macro_use]
+extern crate lazy_static;
+
 use std::collections::HashMap;
 
 use logisheets_base::FuncId;
 use logisheets_parser::ast::{self, Node};
 
+mod builtin;
+pub use builtin::{
+    get_builtin_func, get_builtin_funcs, get_func_prefix, ArgInfo, ArgType, FuncInfo,
+};
+
+lazy_static! {
+    static ref BUILTIN_CHECKER: AstChecker = AstChecker::new(
+        get_builtin_funcs()
+            .iter()
+            .map(|f| {
+                let signature = FuncSignature {
+                    arg_count: f.arg_count.clone(),
+                    args: f.get_arg_defs(),
+                };
+                (f.name.clone(), signature)
+            })
+            .collect()
+    );
+}
+
+/// The checker of the built-in functions, which is built once.
+pub fn get_builtin_checker() -> &'static AstChecker {
+    &BUILTIN_CHECKER
+}
+
+#[derive(Debug, Clone)]
 pub struct AstChecker {
-    registry: HashMap<FuncId, FuncSignature>,
+    registry: HashMap<String, FuncSignature>,
 }
 
 impl AstChecker {
-    pub fn func_check(&self, node: &ast::Node) -> Result<(), FuncCheckError> {
+    /// The signatures are keyed by the names of the functions, which are
+    /// case-insensitive.
+    pub fn new(registry: HashMap<String, FuncSignature>) -> Self {
+        let registry = registry
+            .into_iter()
+            .map(|(name, sign)| (name.to_uppercase(), sign))
+            .collect();
+        AstChecker { registry }
+    }
+
+    /// Check the function with the signature, replacing the one before.
+    pub fn insert(&mut self, name: &str, signature: FuncSignature) {
+        self.registry.insert(name.to_uppercase(), signature);
+    }
+
+    /// Stop checking the function, e.g. when it is replaced by one whose
+    /// arguments are checked elsewhere.
+    pub fn remove(&mut self, name: &str) -> Option<FuncSignature> {
+        self.registry.remove(&name.to_uppercase())
+    }
+
+    /// Check the arguments of the functions in the formula, including the
+    /// ones in the arguments. The names of the functions are found by
+    /// `fetch_func_name`.
+    pub fn func_check<F>(&self, node: &ast::Node, fetch_func_name: &F) -> Result<(), FuncCheckError>
+    where
+        F: Fn(&FuncId) -> Option<String>,
+    {
         match &node.pure {
-            ast::PureNode::Func(f) => match &f.op {
-                ast::Operator::Function(id) => {
-                    if let Some(sign) = self.registry.get(id) {
-                        let args = &f.args;
-                        args_check(sign, args)
-                    } else {
-                        Ok(())
+            ast::PureNode::Func(f) => {
+                if let ast::Operator::Function(id) = &f.op {
+                    let sign = fetch_func_name(id).and_then(|name| self.get_signature(&name));
+                    if let Some(sign) = sign {
+                        args_check(*id, sign, &f.args)?;
                     }
                 }
-                _ => Ok(()),
-            },
+                f.args
+                    .iter()
+                    .try_for_each(|arg| self.func_check(arg, fetch_func_name))
+            }
             ast::PureNode::Value(_) => Ok(()),
             ast::PureNode::Reference(_) => Ok(()),
+            ast::PureNode::Local(_) => Ok(()),
         }
     }
+
+    /// Whether the function can be called with `count` arguments. It is true
+    /// if the signature of the function is unknown.
+    pub fn check_arg_count(&self, name: &str, count: usize) -> bool {
+        match self.get_signature(name) {
+            Some(sign) => sign.arg_count.check(count),
+            None => true,
+        }
+    }
+
+    // The names saved with the `_xlfn.` prefix are found too.
+    fn get_signature(&self, name: &str) -> Option<&FuncSignature> {
+        let name = name.to_uppercase();
+        let name = name.strip_prefix("_XLFN.").unwrap_or(&name);
+        let name = name.strip_prefix("_XLWS.").unwrap_or(name);
+        self.registry.get(name)
+    }
 }
 
 pub struct ArgDefIter<'a> {
@@ -65,9 +139,8 @@ impl<'a> Iterator for ArgDefIter<'a> {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct FuncSignature {
-    pub id: FuncId,
     pub arg_count: ArgCount,
     pub args: Vec<ArgDef>,
 }
@@ -81,6 +154,13 @@ pub struct ArgCount {
     pub even: Option<bool>,
 }
 
+impl ArgCount {
+    /// Whether a function can be called with `count` arguments.
+    pub fn check(&self, count: usize) -> bool {
+        count <= u8::MAX as usize && arg_count_check(self, count as u8)
+    }
+}
+
 fn arg_count_check(count_rule: &ArgCount, count: u8) -> bool {
     if let Some(limit) = count_rule.le {
         if !(count <= limit) {
@@ -134,12 +214,11 @@ pub struct FuncCheckError {
     pub break_args: Option<(usize, ArgDef)>,
 }
 
-fn args_check(sign: &FuncSignature, args: &Vec<Node>) -> Result<(), FuncCheckError> {
+fn args_check(id: FuncId, sign: &FuncSignature, args: &Vec<Node>) -> Result<(), FuncCheckError> {
     let count_rule = &sign.arg_count;
-    let count = args.len() as u8;
-    if !arg_count_check(count_rule, count) {
+    if !count_rule.check(args.len()) {
         return Err(FuncCheckError {
-            id: sign.id,
+            id,
             break_args: None,
             break_arg_count: Some(count_rule.clone()),
         });
@@ -151,7 +230,7 @@ fn args_check(sign: &FuncSignature, args: &Vec<Node>) -> Result<(), FuncCheckErr
         let rule = arg_rule_iter.next();
         if rule.is_none() {
             return Err(FuncCheckError {
-                id: sign.id,
+                id,
                 break_arg_count: None,
                 break_args: Some((idx, ArgDef::unexpected_arg())),
             });
@@ -159,14 +238,14 @@ fn args_check(sign: &FuncSignature, args: &Vec<Node>) -> Result<(), FuncCheckErr
         let arg_def = rule.unwrap();
         if !arg_def_check(arg_def, arg) {
             return Err(FuncCheckError {
-                id: sign.id,
+                id,
                 break_arg_count: None,
                 break_args: Some((idx, arg_def.clone())),
             });
         }
         idx += 1;
     }
-    todo!()
+    Ok(())
 }
 
 fn arg_def_check(arg_def: &ArgDef, arg: &Node) -> bool {
@@ -240,6 +319,21 @@ mod tests {
         assert!(arg_count_check(&arg_count_rule, 6));
     }
 
+    #[test]
+    fn arg_count_check_test5() {
+        let arg_count_rule = ArgCount {
+            le: Some(5),
+            ge: None,
+            eq: None,
+            odd: None,
+            even: None,
+        };
+        assert!(arg_count_rule.check(5));
+        // The count does not wrap around.
+        assert!(!arg_count_rule.check(256));
+        assert!(!arg_count_rule.check(257));
+    }
+
     #[test]
     fn arg_def_iter_check() {
         let arg_def1 = ArgDef {
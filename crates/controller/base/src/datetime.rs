@@ -182,6 +182,53 @@ pub fn get_time_by_decimal_num(t: f64) -> Time {
     }
 }
 
+/// Get the serial number of a date and time. The 1900 date system counts the
+/// nonexistent 1900-02-29 as Excel does, and the 1904 date system starts at
+/// 1904-01-01.
+pub fn get_serial_num_by_datetime(dt: &NaiveDateTime, date1904: bool) -> f64 {
+    let date = dt.date();
+    let days = if date1904 {
+        date.signed_duration_since(NaiveDate::from_ymd(1904, 1, 1))
+            .num_days()
+    } else {
+        let d = date
+            .signed_duration_since(NaiveDate::from_ymd(1899, 12, 31))
+            .num_days();
+        if d >= 60 {
+            d + 1
+        } else {
+            d
+        }
+    };
+    let time = dt.time();
+    let seconds =
+        time.num_seconds_from_midnight() as f64 + time.nanosecond() as f64 / 1_000_000_000.;
+    days as f64 + seconds / 86400.
+}
+
+/// Parse the value of a cell whose type is `d`. It is in ISO 8601 and the
+/// offset, the time or the date can be omitted.
+pub fn parse_iso_datetime(s: &str, date1904: bool) -> Option<DateTime<FixedOffset>> {
+    if let Ok(dt) = DateTime::parse_from_rfc3339(s) {
+        return Some(dt);
+    }
+    let naive = NaiveDateTime::parse_from_str(s, "%Y-%m-%dT%H:%M:%S%.f")
+        .or_else(|_| NaiveDateTime::parse_from_str(s, "%Y-%m-%dT%H:%M"))
+        .or_else(|_| NaiveDate::parse_from_str(s, "%Y-%m-%d").map(|d| d.and_hms(0, 0, 0)))
+        .or_else(|_| {
+            // A time without date is the time of the day 0.
+            let day0 = if date1904 {
+                NaiveDate::from_ymd(1904, 1, 1)
+            } else {
+                NaiveDate::from_ymd(1899, 12, 31)
+            };
+            NaiveTime::parse_from_str(s.trim_start_matches('T'), "%H:%M:%S%.f")
+                .map(|t| day0.and_time(t))
+        })
+        .ok()?;
+    FixedOffset::east(0).from_local_datetime(&naive).single()
+}
+
 pub fn get_decimal_num_by_time(hour: u32, minute: u32, second: u32) -> Option<f64> {
     if hour > 32767 || minute > 32767 || second > 32767 {
         return None;
@@ -196,7 +243,9 @@ mod tests {
     use super::get_date_by_serial_num_1900;
     use super::get_decimal_num_by_time;
     use super::get_serial_num_by_date_1900;
+    use super::get_serial_num_by_datetime;
     use super::get_time_by_decimal_num;
+    use super::parse_iso_datetime;
     use super::EasyDate;
 
     #[test]
@@ -246,6 +295,25 @@ mod tests {
         assert_eq!(r.to_triple(), (19, 36, 29));
     }
 
+    #[test]
+    fn get_serial_num_by_datetime_test() {
+        let dt = parse_iso_datetime("2012-11-10T12:00:00", false).unwrap();
+        assert_eq!(
+            get_serial_num_by_datetime(&dt.naive_local(), false),
+            41223.5
+        );
+        assert_eq!(get_serial_num_by_datetime(&dt.naive_local(), true), 39761.5);
+        let dt = parse_iso_datetime("1900-02-28", false).unwrap();
+        assert_eq!(get_serial_num_by_datetime(&dt.naive_local(), false), 59.);
+        let dt = parse_iso_datetime("1900-03-01T00:00:00+08:00", false).unwrap();
+        assert_eq!(get_serial_num_by_datetime(&dt.naive_local(), false), 61.);
+        let dt = parse_iso_datetime("06:00:00", false).unwrap();
+        assert_eq!(get_serial_num_by_datetime(&dt.naive_local(), false), 0.25);
+        let dt = parse_iso_datetime("06:00:00", true).unwrap();
+        assert_eq!(get_serial_num_by_datetime(&dt.naive_local(), true), 0.25);
+        assert!(parse_iso_datetime("2012/11/10", false).is_none());
+    }
+
     #[test]
     fn date_add_months1() {
         let mut date = EasyDate {
@@ -1,4 +1,4 @@
-use crate::{Cube, CubeId, ExtRef, ExtRefId, Range, RangeId};
+use crate::{Cube, CubeId, ExtRef, ExtRefId, Range, RangeId, Table};
 use anyhow::Result;
 
 use super::{CellId, ColId, ExtBookId, FuncId, NameId, RowId, SheetId, TextId};
@@ -21,6 +21,10 @@ pub trait IdFetcherTrait {
 
     fn fetch_name_id(&mut self, workbook: &Option<&str>, name: &str) -> NameId;
 
+    /// Find the name only used in the sheet. In that sheet, it goes before
+    /// the name of the workbook with the same text.
+    fn fetch_local_name_id(&mut self, sheet_id: &SheetId, name: &str) -> Option<NameId>;
+
     fn fetch_ext_book_id(&mut self, book: &str) -> ExtBookId;
 
     fn fetch_text_id(&mut self, text: &str) -> TextId;
@@ -34,4 +38,8 @@ pub trait VertexFetcherTrait {
     fn fetch_cube_id(&mut self, cube: &Cube) -> CubeId;
 
     fn fetch_ext_ref_id(&mut self, ext_ref: &ExtRef) -> ExtRefId;
+
+    /// Find the table by its name. If the name is omitted, find the table
+    /// where the formula is.
+    fn fetch_table(&mut self, name: Option<&str>) -> Option<Table>;
 }
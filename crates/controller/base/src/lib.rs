@@ -30,7 +30,11 @@ pub type TextId = u32;
 pub type NameId = u8;
 pub type FuncId = u16;
 pub type BlockId = u16;
+pub type TableId = u32;
 pub const CURR_BOOK: ExtBookId = 0;
+/// The size of a sheet in the spreadsheet applications.
+pub const MAX_ROWS: usize = 1_048_576;
+pub const MAX_COLS: usize = 16_384;
 pub type ExtBookId = u8;
 pub type AuthorId = u8;
 pub type StyleId = u32;
@@ -161,6 +165,20 @@ pub struct Addr {
     pub col: usize,
 }
 
+/// A table in a sheet, like the ones created by Insert > Table in Excel.
+/// `start` and `end` are its corners, including the header rows and the
+/// totals rows.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Table {
+    pub id: TableId,
+    pub sheet_id: SheetId,
+    pub start: Addr,
+    pub end: Addr,
+    pub header_row_count: usize,
+    pub totals_row_count: usize,
+    pub columns: Vec<String>,
+}
+
 #[derive(Debug, Clone)]
 pub enum Error {
     Unspecified,
@@ -172,6 +190,8 @@ pub enum Error {
     Ref,         // #REF!
     Value,       // #VALUE!
     GettingData, // #GETTING_DATA
+    Spill,       // #SPILL!
+    Calc,        // #CALC!
 }
 
 impl Error {
@@ -185,6 +205,8 @@ impl Error {
             Error::Ref => "#REF!",
             Error::Value => "#VALUE!",
             Error::GettingData => "#GETTING_DATA",
+            Error::Spill => "#SPILL!",
+            Error::Calc => "#CALC!",
             Error::Unspecified => "#UNKNOWN!",
         };
         String::from(s)
@@ -239,6 +261,7 @@ impl CellValue {
         t: &StCellType,
         value: Option<&PlainTextString>,
         is: Option<&CtRst>,
+        date1904: bool,
         mut f: F,
     ) -> CellValue
     where
@@ -269,21 +292,15 @@ impl CellValue {
                     let id = f(idx);
                     CellValue::String(id)
                 }
-                StCellType::InlineStr => {
-                    if let Some(is) = is {
-                        CellValue::InlineStr(is.clone())
-                    } else {
-                        CellValue::Blank
-                    }
-                }
+                StCellType::InlineStr => match is {
+                    Some(is) => CellValue::InlineStr(is.clone()),
+                    None => CellValue::FormulaStr(text.value.clone()),
+                },
                 StCellType::Str => CellValue::FormulaStr(text.value.clone()),
-                StCellType::D => {
-                    let dt = DateTime::parse_from_rfc3339(&text.value);
-                    match dt {
-                        Ok(d) => CellValue::Date(d),
-                        Err(_) => CellValue::Blank,
-                    }
-                }
+                StCellType::D => match datetime::parse_iso_datetime(&text.value, date1904) {
+                    Some(d) => CellValue::Date(d),
+                    None => CellValue::Blank,
+                },
                 StCellType::E => {
                     let e = {
                         if &text.value == "#DIV/0!" {
@@ -300,6 +317,10 @@ impl CellValue {
                             Error::Value
                         } else if &text.value == "#GETTING_DATA" {
                             Error::GettingData
+                        } else if &text.value == "#SPILL!" {
+                            Error::Spill
+                        } else if &text.value == "#CALC!" {
+                            Error::Calc
                         } else {
                             Error::Value
                         }
@@ -307,16 +328,28 @@ impl CellValue {
                     CellValue::Error(e)
                 }
             }
+        } else if let (StCellType::InlineStr, Some(is)) = (t, is) {
+            // Inline strings are in `is` and have no `v`.
+            CellValue::InlineStr(is.clone())
         } else {
             CellValue::Blank
         }
     }
 
-    pub fn from_cell<F>(c: &CtCell, f: F) -> CellValue
+    /// A time without date in a date cell is the time of the day 0 in the
+    /// date system.
+    pub fn from_cell<F>(c: &CtCell, date1904: bool, f: F) -> CellValue
     where
         F: FnMut(usize) -> TextId,
     {
-        CellValue::get_value(&c.t, c.v.as_ref(), c.is.as_ref(), f)
+        CellValue::get_value(&c.t, c.v.as_ref(), c.is.as_ref(), date1904, f)
+    }
+}
+
+pub fn rst_to_plain_text(rst: &CtRst) -> String {
+    match &rst.t {
+        Some(p) => p.value.to_string(),
+        None => rst.r.iter().map(|relt| relt.t.value.as_str()).collect(),
     }
 }
 
@@ -341,7 +374,35 @@ pub fn index_to_column_label(index: usize) -> String {
 
 #[cfg(test)]
 mod tests {
-    use super::{column_label_to_index, index_to_column_label};
+    use super::{column_label_to_index, index_to_column_label, rst_to_plain_text, CellValue};
+    use logisheets_workbook::prelude::{CtRst, PlainTextString, StCellType};
+
+    fn plain_text(s: &str) -> PlainTextString {
+        PlainTextString {
+            value: s.to_string(),
+            space: None,
+        }
+    }
+
+    #[test]
+    fn date_and_inline_str_value() {
+        let v = plain_text("2023-01-01T12:00:00");
+        match CellValue::get_value(&StCellType::D, Some(&v), None, false, |_| 0) {
+            CellValue::Date(d) => assert_eq!(d.to_rfc3339(), "2023-01-01T12:00:00+00:00"),
+            _ => panic!(),
+        }
+        let is = CtRst {
+            t: Some(plain_text("inline")),
+            r: vec![],
+            r_ph: vec![],
+            phonetic_pr: None,
+        };
+        match CellValue::get_value(&StCellType::InlineStr, None, Some(&is), false, |_| 0) {
+            CellValue::InlineStr(rst) => assert_eq!(rst_to_plain_text(&rst), "inline"),
+            _ => panic!(),
+        }
+    }
+
     #[test]
     fn label_to_index() {
         let label = String::from("AA");
@@ -7,7 +7,7 @@ where
     size: (usize, usize),
     avail_size: (usize, usize),
     data: HashMap<(usize, usize), T>,
-    default_value: Vec<Box<dyn Fn(&T) -> T>>,
+    default_value: Vec<Box<dyn Fn(&T) -> T + Send + Sync>>,
 }
 
 impl<T> std::fmt::Debug for MatrixValue<T>
@@ -25,8 +25,8 @@ where
 {
     fn clone(&self) -> Self {
         let avail_size = self.avail_size;
-        let data = cross_product_usize(0, avail_size.0, 0, avail_size.1)
-            .into_iter()
+        let data = (0..avail_size.0)
+            .flat_map(|i| (0..avail_size.1).map(move |j| (i, j)))
             .map(|(i, j)| {
                 let key = (i, j);
                 let v = match self.visit(i, j) {
@@ -80,7 +80,7 @@ where
 
     pub fn map<F>(self, func: F) -> Self
     where
-        F: 'static + Fn(&T) -> T,
+        F: 'static + Fn(&T) -> T + Send + Sync,
     {
         let row = self.avail_size.0;
         let col = self.avail_size.1;
@@ -105,7 +105,15 @@ where
         }
     }
 
-    pub fn calc_scalar(self, scalar: T, func: Box<dyn Fn(&T, &T) -> T>, scalar_lhs: bool) -> Self {
+    pub fn calc_scalar(
+        self,
+        scalar: T,
+        func: Box<dyn Fn(&T, &T) -> T + Send + Sync>,
+        scalar_lhs: bool,
+    ) -> Self
+    where
+        T: Send + Sync,
+    {
         let f = func;
         let data: HashMap<(usize, usize), T> = self
             .data
@@ -138,7 +146,7 @@ where
     pub fn calc_range(
         self,
         range: MatrixValue<T>,
-        func: Box<dyn Fn(&T, &T) -> T>,
+        func: Box<dyn Fn(&T, &T) -> T + Send + Sync>,
         range_lhs: bool,
     ) -> Self {
         let this_avail_size = self.get_avail_size();
@@ -198,6 +206,22 @@ where
         self.avail_size
     }
 
+    /// The size of the area that holds the stored values. Cells beyond it
+    /// are blank, so whole rows and columns stay small.
+    pub fn get_used_size(&self) -> (usize, usize) {
+        if !self.default_value.is_empty() {
+            return self.avail_size;
+        }
+        let (rows, cols) = self.avail_size;
+        self.data.keys().fold((0, 0), |(r, c), (i, j)| {
+            if *i < rows && *j < cols {
+                (r.max(i + 1), c.max(j + 1))
+            } else {
+                (r, c)
+            }
+        })
+    }
+
     pub fn visit(&self, i: usize, j: usize) -> Result<&T, T> {
         match self.raw_visit(i, j) {
             Some(r) => Ok(r),
@@ -313,4 +337,23 @@ mod tests {
             ]
         );
     }
+
+    #[test]
+    fn used_size_test() {
+        use super::MatrixValue;
+        let mut m = MatrixValue::<i32>::new(65535, 2);
+        assert_eq!(m.get_used_size(), (0, 0));
+        m.insert(3, 0, 1);
+        m.insert(1, 1, 2);
+        assert_eq!(m.get_used_size(), (4, 2));
+    }
+
+    #[test]
+    fn clone_test() {
+        use super::MatrixValue;
+        let m = MatrixValue::from(vec![vec![1, 2]]);
+        let v = m.clone().into_iter().collect::<Vec<_>>();
+        assert_eq!(v.len(), 2);
+        assert_eq!(v.iter().sum::<i32>(), 3);
+    }
 }
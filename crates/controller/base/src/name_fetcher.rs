@@ -1,6 +1,6 @@
 use crate::{
     CellId, ColId, Cube, CubeId, ExtBookId, ExtRef, ExtRefId, FuncId, NameId, Range, RangeId,
-    RowId, SheetId, TextId,
+    RowId, SheetId, TableId, TextId,
 };
 
 pub trait NameFetcherTrait {
@@ -8,7 +8,14 @@ pub trait NameFetcherTrait {
     fn fetch_func_name(&self, func_id: &FuncId) -> String;
     fn fetch_sheet_name(&self, sheet_id: &SheetId) -> String;
     fn fetch_book_name(&self, book_id: &ExtBookId) -> String;
+    /// The 1-based index of the external book in the file, like the `1` in
+    /// `[1]Sheet1!A1`.
+    fn fetch_book_idx(&self, book_id: &ExtBookId) -> usize;
     fn fetch_defined_name(&self, nid: &NameId) -> String;
+    /// The sheet that the name is only used in, or none for a name of the
+    /// workbook.
+    fn fetch_name_scope(&self, nid: &NameId) -> Option<SheetId>;
+    fn fetch_table_name(&self, table_id: &TableId) -> String;
     fn fetch_cell_idx(&mut self, sheet_id: &SheetId, cell_id: &CellId) -> (usize, usize);
     fn fetch_row_idx(&mut self, sheet_id: &SheetId, row_id: &RowId) -> usize;
     fn fetch_col_idx(&mut self, sheet_id: &SheetId, col_id: &ColId) -> usize;
@@ -1,28 +1,145 @@
+use pest::error::{ErrorVariant, InputLocation};
 use pest::Parser;
 use pest_derive::Parser;
 use tracing::error;
 
+mod token;
+pub use token::{tokenize, tokenize_recoverable, Token, TokenKind};
+
 #[derive(Parser)]
 #[grammar = "grammar.pest"]
 pub struct FormulaParser;
 
+/// Why a formula can not be lexed. `start` and `end` are the byte offsets of
+/// the part of the formula where the error is.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LexError {
+    pub start: usize,
+    pub end: usize,
+    /// The rules which could have been matched at `start`.
+    pub expected: Vec<Rule>,
+    pub message: String,
+}
+
 pub fn lex(s: &str) -> Option<pest::iterators::Pair<Rule>> {
-    let result = FormulaParser::parse(Rule::start, s);
-    match result {
-        Ok(mut r) => {
-            let tokens = r.next().unwrap();
-            Some(tokens)
-        }
+    match try_lex(s) {
+        Ok(tokens) => Some(tokens),
         Err(e) => {
-            error!("parse formula failed: {}\nMeet error: {}", s, e);
+            error!("parse formula failed: {}\nMeet error: {:?}", s, e);
             None
         }
     }
 }
 
+pub fn try_lex(s: &str) -> Result<pest::iterators::Pair<'_, Rule>, LexError> {
+    match FormulaParser::parse(Rule::start, s) {
+        Ok(mut r) => Ok(r.next().unwrap()),
+        Err(e) => Err(to_lex_error(s, e)),
+    }
+}
+
+/// Lex a reference in a formula written in the R1C1 notation, like
+/// `Sheet2!R[-1]C:RC`. It is none if the whole text is not such a reference.
+pub fn lex_r1c1_reference(s: &str) -> Option<pest::iterators::Pair<'_, Rule>> {
+    FormulaParser::parse(Rule::r1c1_start, s).ok()?.next()
+}
+
+fn to_lex_error(s: &str, e: pest::error::Error<Rule>) -> LexError {
+    let expected = match e.variant {
+        ErrorVariant::ParsingError { positives, .. } => positives,
+        ErrorVariant::CustomError { .. } => vec![],
+    };
+    if let Some((start, end, message)) = find_unclosed(s) {
+        return LexError {
+            start,
+            end,
+            expected,
+            message: message.to_string(),
+        };
+    }
+    let start = match e.location {
+        InputLocation::Pos(p) => p,
+        InputLocation::Span((start, _)) => start,
+    };
+    match s[start..].chars().next() {
+        Some(c) => LexError {
+            start,
+            end: start + c.len_utf8(),
+            expected,
+            message: format!("unexpected character '{}'", c),
+        },
+        None => LexError {
+            start,
+            end: start,
+            expected,
+            message: String::from("unexpected end of the formula"),
+        },
+    }
+}
+
+// Find the parenthesis or the quote that is not closed, or the closing
+// parenthesis that is not opened.
+fn find_unclosed(s: &str) -> Option<(usize, usize, &'static str)> {
+    let openers = match find_openers(s) {
+        Ok(openers) => openers,
+        Err(i) => return Some((i, i + 1, "unbalanced parenthesis")),
+    };
+    match openers.last() {
+        Some((i, '"')) => Some((*i, i + 1, "unterminated string")),
+        Some((i, '\'')) => Some((*i, i + 1, "unterminated sheet name")),
+        _ => openers
+            .iter()
+            .rev()
+            .find(|(_, c)| *c == '(')
+            .map(|(i, _)| (*i, i + 1, "unbalanced parenthesis")),
+    }
+}
+
+// The parentheses, the brackets and the quote which are not closed, from the
+// outermost one, or the position of the closing parenthesis which is not
+// opened. The parentheses and the quotes in the brackets of the workbook
+// names and the table references are skipped.
+fn find_openers(s: &str) -> Result<Vec<(usize, char)>, usize> {
+    let mut openers = Vec::<(usize, char)>::new();
+    let mut chars = s.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        match openers.last().map(|(_, o)| *o) {
+            // A doubled quote is an escaped one.
+            Some(q @ ('"' | '\'')) if c == q => {
+                if matches!(chars.peek(), Some((_, n)) if *n == q) {
+                    chars.next();
+                } else {
+                    openers.pop();
+                }
+            }
+            Some('"' | '\'') => {}
+            Some('[') => match c {
+                '[' => openers.push((i, c)),
+                ']' => {
+                    openers.pop();
+                }
+                // The special characters in the column names are escaped.
+                '\'' => {
+                    chars.next();
+                }
+                _ => {}
+            },
+            top => match c {
+                '"' | '\'' | '[' | '(' => openers.push((i, c)),
+                ')' if top.is_none() => return Err(i),
+                ')' => {
+                    openers.pop();
+                }
+                _ => {}
+            },
+        }
+    }
+    Ok(openers)
+}
+
 #[cfg(test)]
 mod tests {
-    use super::lex;
+    use super::{lex, lex_r1c1_reference, try_lex, Rule};
 
     #[test]
     fn constant() {
@@ -58,6 +175,14 @@ mod tests {
         println!("{:?}", r);
     }
 
+    #[test]
+    fn string_like_reference() {
+        let r = lex("\"Sheet1!A1\"");
+        assert_ne!(r, None);
+        let r = lex("INDIRECT(\"Sheet1!A1\")");
+        assert_ne!(r, None);
+    }
+
     #[test]
     fn func_call() {
         let r = lex("B2+1").unwrap();
@@ -127,4 +252,106 @@ mod tests {
         let r = lex("WEEKDAY( ,2)").unwrap();
         println!("{:?}", r);
     }
+
+    #[test]
+    fn table_reference() {
+        // The table references in the formula, with their names and columns.
+        fn tables(f: &str) -> Vec<(String, Vec<(Rule, String)>)> {
+            lex(f)
+                .unwrap()
+                .into_inner()
+                .flatten()
+                .filter(|p| p.as_rule() == Rule::table_reference)
+                .map(|p| {
+                    let inner = p
+                        .clone()
+                        .into_inner()
+                        .flatten()
+                        .filter(|p| p.as_rule() != Rule::table_column_range)
+                        .map(|p| (p.as_rule(), p.as_str().to_string()))
+                        .collect();
+                    (p.as_str().to_string(), inner)
+                })
+                .collect()
+        }
+        let col = |s: &str| (Rule::table_column, s.to_string());
+        let item = |s: &str| (Rule::table_item, s.to_string());
+        let name = |s: &str| (Rule::table_name, s.to_string());
+        let this_row = |s: &str| (Rule::table_this_row, s.to_string());
+
+        let r = tables("Table1[Col]");
+        assert_eq!(
+            r,
+            vec![(
+                String::from("Table1[Col]"),
+                vec![name("Table1"), col("Col")]
+            )]
+        );
+        let r = tables("SUM(Table1[[#Headers],[Col1]:[Col2]])");
+        assert_eq!(
+            r,
+            vec![(
+                String::from("Table1[[#Headers],[Col1]:[Col2]]"),
+                vec![name("Table1"), item("#Headers"), col("Col1"), col("Col2")]
+            )]
+        );
+        let r = tables("Table1[[#This Row],[Sales Amount]]");
+        assert_eq!(
+            r[0].1,
+            vec![name("Table1"), item("#This Row"), col("Sales Amount")]
+        );
+        let r = tables("[@Col]*2");
+        assert_eq!(r[0].0, "[@Col]");
+        assert_eq!(r[0].1, vec![this_row("@Col"), col("Col")]);
+        let r = tables("[@[Col '#1]]");
+        assert_eq!(r[0].1, vec![this_row("@[Col '#1]"), col("Col '#1")]);
+        let r = tables("Table1[#Totals]+Table1[]");
+        assert_eq!(r.len(), 2);
+        assert_eq!(r[0].1, vec![name("Table1"), item("#Totals")]);
+        assert_eq!(r[1].1, vec![name("Table1")]);
+        // A book prefix is not a table reference.
+        assert!(tables("[Book1]Sheet1!A1").is_empty());
+    }
+
+    #[test]
+    fn r1c1_reference() {
+        let areas = |s: &str| {
+            lex_r1c1_reference(s).map(|p| {
+                p.into_inner()
+                    .map(|p| (p.as_rule(), p.as_str().to_string()))
+                    .collect::<Vec<_>>()
+            })
+        };
+        let area = |s: &str| (Rule::r1c1_area, s.to_string());
+        assert_eq!(areas("RC1"), Some(vec![area("RC1")]));
+        assert_eq!(areas("r2:R3"), Some(vec![area("r2"), area("R3")]));
+        assert_eq!(areas("C"), Some(vec![area("C")]));
+        assert_eq!(
+            areas("'R1'!R[-1]C:RC").unwrap()[1..],
+            [area("R[-1]C"), area("RC")]
+        );
+        assert!(areas("A1").is_none());
+        assert!(areas("RC1x").is_none());
+        assert!(areas("Table1[RC]").is_none());
+    }
+
+    #[test]
+    fn lex_error() {
+        let e = try_lex("SUM(A1, (B2)").unwrap_err();
+        assert_eq!((e.start, e.end), (3, 4));
+        assert_eq!(e.message, "unbalanced parenthesis");
+        let e = try_lex("SUM(A1))").unwrap_err();
+        assert_eq!((e.start, e.end), (7, 8));
+        assert_eq!(e.message, "unbalanced parenthesis");
+        let e = try_lex("CONCAT(\"a)\", \"b)").unwrap_err();
+        assert_eq!((e.start, e.end), (13, 14));
+        assert_eq!(e.message, "unterminated string");
+        let e = try_lex("A1+").unwrap_err();
+        assert_eq!((e.start, e.end), (3, 3));
+        assert_eq!(e.message, "unexpected end of the formula");
+        assert!(!e.expected.is_empty());
+        let e = try_lex("A1+;").unwrap_err();
+        assert_eq!((e.start, e.end), (3, 4));
+        assert_eq!(e.message, "unexpected character ';'");
+    }
 }
@@ -0,0 +1,266 @@
+use pest::iterators::Pair;
+
+use crate::{find_openers, try_lex, LexError, Rule};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenKind {
+    Function,
+    /// A cell reference or a table reference.
+    Reference,
+    /// A defined name, or a parameter of LET and LAMBDA.
+    Name,
+    Number,
+    Text,
+    Boolean,
+    Error,
+    Operator,
+    /// The `,` between the arguments or the array items, and the `;` between
+    /// the array rows.
+    Separator,
+    OpenParen,
+    CloseParen,
+    OpenBrace,
+    CloseBrace,
+    /// The part of a partially typed formula which can not be lexed.
+    Unknown,
+}
+
+/// A token of a formula. `start` and `end` are the byte offsets of `text`
+/// in the formula. The spaces between the tokens are skipped.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Token {
+    pub kind: TokenKind,
+    pub text: String,
+    pub start: usize,
+    pub end: usize,
+}
+
+pub fn tokenize(s: &str) -> Result<Vec<Token>, LexError> {
+    let pair = try_lex(s)?;
+    Ok(get_tokens(s, pair, s.len()))
+}
+
+const MAX_RECOVERY_ATTEMPTS: usize = 16;
+
+/// Tokenize a formula which may be partially typed, like `SUM(A1:B2, "a`.
+/// The parentheses, the brackets and the quote which are not closed are
+/// closed, and the longest part of the formula which can be lexed then is
+/// tokenized. The rest of it is split into the operators, the separators,
+/// the parentheses and the unknown tokens.
+pub fn tokenize_recoverable(s: &str) -> Vec<Token> {
+    if let Ok(tokens) = tokenize(s) {
+        return tokens;
+    }
+    // Each attempt cuts the formula before the position where the previous
+    // one failed, so the attempts are bounded by the number of errors rather
+    // than by the length of the formula.
+    let mut end = s.len();
+    for _ in 0..MAX_RECOVERY_ATTEMPTS {
+        if end == 0 {
+            break;
+        }
+        let prefix = &s[..end];
+        let openers = match find_openers(prefix) {
+            Ok(openers) => openers,
+            Err(i) => {
+                end = i;
+                continue;
+            }
+        };
+        let closers = openers
+            .iter()
+            .rev()
+            .map(|(_, c)| match c {
+                '(' => ')',
+                '[' => ']',
+                q => *q,
+            })
+            .collect::<String>();
+        let completed = format!("{}{}", prefix, closers);
+        match try_lex(&completed) {
+            Ok(pair) => return get_tokens(s, pair, end),
+            Err(e) => {
+                end = e.start.min(end - 1);
+                while !s.is_char_boundary(end) {
+                    end -= 1;
+                }
+            }
+        }
+    }
+    get_tokens_in_gap(s, 0, s.len())
+}
+
+// The tokens of the formula before `end`. The tokens after `end` are the
+// closers added to a partial formula, and they are dropped.
+fn get_tokens(s: &str, pair: Pair<'_, Rule>, end: usize) -> Vec<Token> {
+    let mut tokens = vec![];
+    collect_tokens(pair, &mut tokens);
+    let mut result = vec![];
+    let mut pos = 0;
+    for mut t in tokens.into_iter().filter(|t| t.start < end) {
+        result.extend(get_tokens_in_gap(s, pos, t.start));
+        if t.end > end {
+            t.end = end;
+            t.text = s[t.start..end].to_string();
+        }
+        pos = t.end;
+        result.push(t);
+    }
+    result.extend(get_tokens_in_gap(s, pos, s.len()));
+    result
+}
+
+fn collect_tokens(pair: Pair<'_, Rule>, tokens: &mut Vec<Token>) {
+    let kind = match pair.as_rule() {
+        Rule::function_name => TokenKind::Function,
+        Rule::cell_reference | Rule::table_reference => TokenKind::Reference,
+        Rule::name => TokenKind::Name,
+        Rule::numerical_constant => TokenKind::Number,
+        Rule::string_constant => TokenKind::Text,
+        Rule::logical_constant => TokenKind::Boolean,
+        Rule::error_constant => TokenKind::Error,
+        Rule::comma => TokenKind::Separator,
+        Rule::colon_op
+        | Rule::multiply_op
+        | Rule::div_op
+        | Rule::plus_op
+        | Rule::minus_op
+        | Rule::concat_op
+        | Rule::eq_op
+        | Rule::neq_op
+        | Rule::gt_op
+        | Rule::ge_op
+        | Rule::lt_op
+        | Rule::le_op
+        | Rule::exp_op
+        | Rule::percent_op
+        | Rule::spill_op
+        | Rule::space_op => TokenKind::Operator,
+        _ => {
+            pair.into_inner().for_each(|p| collect_tokens(p, tokens));
+            return;
+        }
+    };
+    let span = pair.as_span();
+    tokens.push(Token {
+        kind,
+        text: span.as_str().to_string(),
+        start: span.start(),
+        end: span.end(),
+    });
+}
+
+// The parentheses, the separators and the operators between the tokens are
+// not in the syntax tree. The other characters there are unknown.
+fn get_tokens_in_gap(s: &str, start: usize, end: usize) -> Vec<Token> {
+    let mut result: Vec<Token> = vec![];
+    for (i, c) in s[start..end].char_indices() {
+        let i = start + i;
+        let kind = match c {
+            ' ' => continue,
+            '(' => TokenKind::OpenParen,
+            ')' => TokenKind::CloseParen,
+            '{' => TokenKind::OpenBrace,
+            '}' => TokenKind::CloseBrace,
+            ',' | ';' => TokenKind::Separator,
+            '+' | '-' | '*' | '/' | '^' | '&' | '=' | '<' | '>' | '%' | ':' => TokenKind::Operator,
+            _ => TokenKind::Unknown,
+        };
+        let end = i + c.len_utf8();
+        match result.last_mut() {
+            Some(last) if last.kind == TokenKind::Unknown && kind == last.kind && last.end == i => {
+                last.text.push(c);
+                last.end = end;
+            }
+            _ => result.push(Token {
+                kind,
+                text: c.to_string(),
+                start: i,
+                end,
+            }),
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{tokenize, tokenize_recoverable, TokenKind};
+
+    fn kinds(tokens: &[super::Token]) -> Vec<(TokenKind, &str)> {
+        tokens.iter().map(|t| (t.kind, t.text.as_str())).collect()
+    }
+
+    #[test]
+    fn tokenize_test() {
+        let tokens = tokenize("SUM(Sheet2!A1:B2, 3)*-{1,\"a\";TRUE,#N/A}").unwrap();
+        assert_eq!(
+            kinds(&tokens),
+            vec![
+                (TokenKind::Function, "SUM"),
+                (TokenKind::OpenParen, "("),
+                (TokenKind::Reference, "Sheet2!A1:B2"),
+                (TokenKind::Separator, ","),
+                (TokenKind::Number, "3"),
+                (TokenKind::CloseParen, ")"),
+                (TokenKind::Operator, "*"),
+                (TokenKind::Operator, "-"),
+                (TokenKind::OpenBrace, "{"),
+                (TokenKind::Number, "1"),
+                (TokenKind::Separator, ","),
+                (TokenKind::Text, "\"a\""),
+                (TokenKind::Separator, ";"),
+                (TokenKind::Boolean, "TRUE"),
+                (TokenKind::Separator, ","),
+                (TokenKind::Error, "#N/A"),
+                (TokenKind::CloseBrace, "}"),
+            ]
+        );
+        assert_eq!((tokens[2].start, tokens[2].end), (4, 16));
+        assert!(tokenize("SUM(A1").is_err());
+    }
+
+    #[test]
+    fn tokenize_recoverable_test() {
+        let tokens = tokenize_recoverable("SUM(A1, \"ab");
+        assert_eq!(
+            kinds(&tokens),
+            vec![
+                (TokenKind::Function, "SUM"),
+                (TokenKind::OpenParen, "("),
+                (TokenKind::Reference, "A1"),
+                (TokenKind::Separator, ","),
+                (TokenKind::Text, "\"ab"),
+            ]
+        );
+        let tokens = tokenize_recoverable("A1+B2*");
+        assert_eq!(
+            kinds(&tokens),
+            vec![
+                (TokenKind::Reference, "A1"),
+                (TokenKind::Operator, "+"),
+                (TokenKind::Reference, "B2"),
+                (TokenKind::Operator, "*"),
+            ]
+        );
+        let tokens = tokenize_recoverable("IF(A1>0,)) ?x");
+        assert_eq!(
+            kinds(&tokens),
+            vec![
+                (TokenKind::Function, "IF"),
+                (TokenKind::OpenParen, "("),
+                (TokenKind::Reference, "A1"),
+                (TokenKind::Operator, ">"),
+                (TokenKind::Number, "0"),
+                (TokenKind::Separator, ","),
+                (TokenKind::CloseParen, ")"),
+                (TokenKind::CloseParen, ")"),
+                (TokenKind::Unknown, "?x"),
+            ]
+        );
+        let formula = format!("{}1 ?", "A1+".repeat(1000));
+        let tokens = tokenize_recoverable(&formula);
+        assert_eq!(tokens.len(), 2002);
+        assert_eq!(tokens.last().unwrap().kind, TokenKind::Unknown);
+    }
+}
@@ -1,6 +1,7 @@
 use chrono::{DateTime, FixedOffset};
 use logisheets_base::{
     CellId, ColId, CubeId, ExtBookId, ExtRefId, FuncId, NameId, RangeId, RefAbs, RowId, SheetId,
+    TableId,
 };
 use std::hash::{Hash, Hasher};
 
@@ -15,6 +16,8 @@ pub enum Error {
     Ref,
     Value,
     GettingData,
+    Spill,
+    Calc,
 }
 
 impl Error {
@@ -29,6 +32,8 @@ impl Error {
             Error::Ref => "#REF!",
             Error::Value => "#VALUE!",
             Error::GettingData => "#GETTING_DATA",
+            Error::Spill => "#SPILL!",
+            Error::Calc => "#CALC!",
         }
     }
 
@@ -43,6 +48,8 @@ impl Error {
             "#REF!" => Error::Ref,
             "#VALUE!" => Error::Value,
             "#GETTING_DATA" => Error::GettingData,
+            "#SPILL!" => Error::Spill,
+            "#CALC!" => Error::Calc,
             _ => Error::Unspecified,
         }
     }
@@ -75,6 +82,9 @@ pub enum PrefixOperator {
 #[derive(Debug, Clone)]
 pub enum PostfixOperator {
     Percent,
+    // The `#` after a reference to the formula cell of a dynamic array,
+    // like `A1#`, refers to the cells the array spills into.
+    Spill,
 }
 
 #[derive(Debug, Clone)]
@@ -94,6 +104,9 @@ pub enum Operator {
     Postfix(PostfixOperator),
     Prefix(PrefixOperator),
     Comma,
+    // Call a LAMBDA. The first argument is the function and the others are
+    // passed to it.
+    Call,
 }
 
 #[derive(Debug, Clone)]
@@ -107,6 +120,20 @@ pub enum PureNode {
     Func(Func),
     Value(Value),
     Reference(CellReference),
+    // A name bound by LET or a parameter of LAMBDA, as it is written.
+    Local(String),
+}
+
+/// Whether the names bound by LET or LAMBDA are the same. They are
+/// case-insensitive and Excel saves them with a `_xlpm.` prefix.
+pub fn is_same_local(lhs: &str, rhs: &str) -> bool {
+    fn strip_prefix(name: &str) -> &str {
+        match name.get(..6) {
+            Some(prefix) if prefix.eq_ignore_ascii_case("_xlpm.") => &name[6..],
+            _ => name,
+        }
+    }
+    strip_prefix(lhs).eq_ignore_ascii_case(strip_prefix(rhs))
 }
 
 #[derive(Debug, Clone)]
@@ -195,6 +222,7 @@ pub enum CellReference {
     UnMut(CubeDisplay),
     Ext(ExtRefDisplay),
     Name(NameId),
+    Table(TableReference),
 }
 
 #[derive(Debug, Clone, Hash, PartialEq, Eq)]
@@ -204,6 +232,55 @@ pub struct RangeDisplay {
     pub ref_abs: RefAbs,
 }
 
+/// A structured reference to a table, like `Table1[[#Headers],[Col1]:[Col2]]`.
+/// The cells referred to are found when parsing, so that they grow with the
+/// table. For `#This Row`, the row of the formula is picked from them when
+/// calculating.
+#[derive(Debug, Clone, Hash, PartialEq, Eq)]
+pub struct TableReference {
+    pub table_id: TableId,
+    pub specifiers: Vec<TableSpecifier>,
+    /// The first and the last columns referred to. All the columns are
+    /// referred to if it is none.
+    pub columns: Option<(String, String)>,
+    pub sheet_id: SheetId,
+    pub range_id: RangeId,
+}
+
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub enum TableSpecifier {
+    All,
+    Data,
+    Headers,
+    Totals,
+    ThisRow,
+}
+
+impl TableSpecifier {
+    pub fn get_specifier_str(&self) -> &'static str {
+        match self {
+            TableSpecifier::All => "#All",
+            TableSpecifier::Data => "#Data",
+            TableSpecifier::Headers => "#Headers",
+            TableSpecifier::Totals => "#Totals",
+            TableSpecifier::ThisRow => "#This Row",
+        }
+    }
+
+    pub fn from_specifier_str(s: &str) -> Option<Self> {
+        [
+            TableSpecifier::All,
+            TableSpecifier::Data,
+            TableSpecifier::Headers,
+            TableSpecifier::Totals,
+            TableSpecifier::ThisRow,
+        ]
+        .iter()
+        .copied()
+        .find(|t| t.get_specifier_str().eq_ignore_ascii_case(s))
+    }
+}
+
 #[derive(Debug, Clone, Hash, PartialEq, Eq)]
 pub struct CubeDisplay {
     pub cube_id: CubeId,
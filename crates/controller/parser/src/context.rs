@@ -3,7 +3,8 @@ use logisheets_base::get_active_sheet::GetActiveSheetTrait;
 use logisheets_base::get_book_name::GetBookNameTrait;
 use logisheets_base::id_fetcher::{IdFetcherTrait, VertexFetcherTrait};
 use logisheets_base::{
-    Cube, CubeId, ExtBookId, ExtRef, ExtRefId, FuncId, NameId, Range, RangeId, SheetId, TextId,
+    Cube, CubeId, ExtBookId, ExtRef, ExtRefId, FuncId, NameId, Range, RangeId, SheetId, Table,
+    TextId,
 };
 
 pub trait ContextTrait:
@@ -45,6 +46,10 @@ where
     fn fetch_ext_ref_id(&mut self, ext_ref: &ExtRef) -> ExtRefId {
         self.vertex_fetcher.fetch_ext_ref_id(ext_ref)
     }
+
+    fn fetch_table(&mut self, name: Option<&str>) -> Option<Table> {
+        self.vertex_fetcher.fetch_table(name)
+    }
 }
 
 impl<'a, T, F> GetActiveSheetTrait for Context<'a, T, F>
@@ -105,6 +110,10 @@ where
         self.id_fetcher.fetch_name_id(workbook, name)
     }
 
+    fn fetch_local_name_id(&mut self, sheet_id: &SheetId, name: &str) -> Option<NameId> {
+        self.id_fetcher.fetch_local_name_id(sheet_id, name)
+    }
+
     fn fetch_ext_book_id(&mut self, book: &str) -> ExtBookId {
         self.id_fetcher.fetch_ext_book_id(book)
     }
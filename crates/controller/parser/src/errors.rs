@@ -6,4 +6,8 @@ pub enum ParseError {
     ParseColFailed(String),
     #[error("parse row error: {0}")]
     ParseRowFailed(String),
+    #[error("parse table reference error: {0}")]
+    ParseTableRefFailed(String),
+    #[error("R1C1 reference in an A1 formula: {0}")]
+    R1C1Reference(String),
 }
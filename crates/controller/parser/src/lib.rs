@@ -2,6 +2,7 @@ pub mod ast;
 mod climber;
 pub mod context;
 pub mod errors;
+mod r1c1;
 mod reference;
 #[cfg(test)]
 mod test_utils;
@@ -11,10 +12,11 @@ pub mod unparse;
 extern crate lazy_static;
 use crate::climber::{Assoc, Climber, ClimberBuilder, Operator};
 use context::ContextTrait;
-use logisheets_base::id_fetcher::IdFetcherTrait;
 use logisheets_lexer::*;
 use pest::iterators::Pair;
-use reference::build_cell_reference;
+pub use r1c1::{a1_to_r1c1, r1c1_to_a1};
+use reference::{build_cell_reference, build_table_reference};
+pub use reference::{resolve_r1c1_reference, resolve_reference, ReferenceArea};
 use regex::Regex;
 
 lazy_static! {
@@ -54,16 +56,23 @@ impl Parser {
     {
         let pair = lex(f.trim())?;
         let formula = pair.into_inner().next()?;
-        Some(self.parse_from_pair(formula, context, false))
+        Some(self.parse_from_pair(formula, context, false, &[]))
     }
 
-    fn parse_from_pair<T>(&self, formula: Pair<Rule>, context: &mut T, bracket: bool) -> ast::Node
+    // `scope` has the names bound by the LET and LAMBDA functions around.
+    fn parse_from_pair<T>(
+        &self,
+        formula: Pair<Rule>,
+        context: &mut T,
+        bracket: bool,
+        scope: &[String],
+    ) -> ast::Node
     where
         T: ContextTrait,
     {
         let ast = CLIMBER.climb(
             formula.into_inner(),
-            |pair: Pair<Rule>| self.primary(pair, context),
+            |pair: Pair<Rule>| self.primary(pair, context, scope),
             |lhs: ast::Node, pair: Pair<Rule>, rhs: ast::Node| -> ast::Node {
                 let infix_op = match pair.as_rule() {
                     Rule::colon_op => ast::InfixOperator::Colon,
@@ -106,7 +115,10 @@ impl Parser {
             |lhs: ast::Node, pair: Pair<Rule>| -> ast::Node {
                 match pair.as_rule() {
                     Rule::postfix_op => {
-                        let op = ast::Operator::Postfix(ast::PostfixOperator::Percent);
+                        let op = match pair.as_str() {
+                            "#" => ast::Operator::Postfix(ast::PostfixOperator::Spill),
+                            _ => ast::Operator::Postfix(ast::PostfixOperator::Percent),
+                        };
                         let args = vec![lhs];
                         let func = ast::Func { op, args };
                         let pure = ast::PureNode::Func(func);
@@ -119,12 +131,12 @@ impl Parser {
         ast
     }
 
-    fn primary<T>(&self, pair: Pair<Rule>, context: &mut T) -> ast::Node
+    fn primary<T>(&self, pair: Pair<Rule>, context: &mut T, scope: &[String]) -> ast::Node
     where
         T: ContextTrait,
     {
         match pair.as_rule() {
-            Rule::expression => self.parse_from_pair(pair, context, false),
+            Rule::expression => self.parse_from_pair(pair, context, false, scope),
             Rule::logical_constant => {
                 let pure = build_bool(pair);
                 ast::Node {
@@ -161,22 +173,32 @@ impl Parser {
                     bracket: false,
                 }
             }
+            Rule::table_reference => {
+                let pure = build_table_reference(pair, context)
+                    .unwrap_or(ast::PureNode::Value(ast::Value::Error(ast::Error::Ref)));
+                ast::Node {
+                    pure,
+                    bracket: false,
+                }
+            }
             Rule::expression_bracket => {
                 let rule = pair.into_inner().next().unwrap();
-                self.parse_from_pair(rule, context, true)
+                self.parse_from_pair(rule, context, true, scope)
             }
             Rule::function_call => {
-                let pure = self.build_func_call(pair, context);
+                let pure = self.build_func_call(pair, context, scope);
                 ast::Node {
                     pure,
                     bracket: false,
                 }
             }
             Rule::name => {
-                let n = build_name_with_prefix(pair, context);
-                let pure = match n {
-                    Some(r) => ast::PureNode::Reference(r),
-                    None => ast::PureNode::Value(ast::Value::Error(ast::Error::Ref)),
+                let pure = match find_local(scope, pair.as_str()) {
+                    Some(local) => ast::PureNode::Local(local),
+                    None => match build_name_with_prefix(pair, context) {
+                        Some(r) => ast::PureNode::Reference(r),
+                        None => ast::PureNode::Value(ast::Value::Error(ast::Error::Ref)),
+                    },
                 };
                 ast::Node {
                     pure,
@@ -200,38 +222,110 @@ impl Parser {
         }
     }
 
-    fn build_func_call<T>(&self, pair: Pair<Rule>, context: &mut T) -> ast::PureNode
+    fn build_func_call<T>(
+        &self,
+        pair: Pair<Rule>,
+        context: &mut T,
+        scope: &[String],
+    ) -> ast::PureNode
     where
         T: ContextTrait,
     {
         let mut iter = pair.into_inner();
         let func_name = iter.next().unwrap().as_str().to_string();
-        let mut args: Vec<ast::Node> = vec![];
-        while iter.peek().is_some() {
-            let arg = iter.next().unwrap();
-            let arg_node = self.build_arg(arg, context);
-            args.push(arg_node);
-        }
-        let op = ast::Operator::Function(context.fetch_func_id(&func_name));
-        let func = ast::Func { op, args };
-        ast::PureNode::Func(func)
+        let (calls, arg_pairs): (Vec<_>, Vec<_>) =
+            iter.partition(|p| p.as_rule() == Rule::call_args);
+        let upper = func_name.to_uppercase();
+        let args = match upper.strip_prefix("_XLFN.").unwrap_or(&upper) {
+            // LET(name1, value1, [name2, value2, ...], calculation)
+            "LET" => self.build_binding_args(arg_pairs, context, scope, |i, cnt| {
+                i % 2 == 0 && i + 1 < cnt
+            }),
+            // LAMBDA([parameter1, parameter2, ...], calculation)
+            "LAMBDA" => self.build_binding_args(arg_pairs, context, scope, |i, cnt| i + 1 < cnt),
+            _ => arg_pairs
+                .into_iter()
+                .map(|arg| self.build_arg(arg, context, scope))
+                .collect(),
+        };
+        // A name bound to a LAMBDA is called like a function.
+        let pure = match find_local(scope, &func_name) {
+            Some(local) => {
+                let mut call_args = vec![ast::Node {
+                    pure: ast::PureNode::Local(local),
+                    bracket: false,
+                }];
+                call_args.extend(args);
+                let op = ast::Operator::Call;
+                ast::PureNode::Func(ast::Func {
+                    op,
+                    args: call_args,
+                })
+            }
+            None => {
+                let op = ast::Operator::Function(context.fetch_func_id(&func_name));
+                ast::PureNode::Func(ast::Func { op, args })
+            }
+        };
+        calls.into_iter().fold(pure, |callee, call| {
+            let mut args = vec![ast::Node {
+                pure: callee,
+                bracket: false,
+            }];
+            call.into_inner()
+                .for_each(|arg| args.push(self.build_arg(arg, context, scope)));
+            let op = ast::Operator::Call;
+            ast::PureNode::Func(ast::Func { op, args })
+        })
+    }
+
+    // Build the arguments of LET or LAMBDA. The names to bind, chosen by
+    // their positions, are in the scope of the arguments after them.
+    fn build_binding_args<T>(
+        &self,
+        pairs: Vec<Pair<Rule>>,
+        context: &mut T,
+        scope: &[String],
+        is_binding: fn(usize, usize) -> bool,
+    ) -> Vec<ast::Node>
+    where
+        T: ContextTrait,
+    {
+        let mut scope = scope.to_vec();
+        let cnt = pairs.len();
+        pairs
+            .into_iter()
+            .enumerate()
+            .map(|(i, p)| {
+                if is_binding(i, cnt) {
+                    if let Some(name) = get_local_name(&p) {
+                        scope.push(name.clone());
+                        return ast::Node {
+                            pure: ast::PureNode::Local(name),
+                            bracket: false,
+                        };
+                    }
+                }
+                self.build_arg(p, context, &scope)
+            })
+            .collect()
     }
 
-    fn build_arg<T>(&self, pair: Pair<Rule>, context: &mut T) -> ast::Node
+    fn build_arg<T>(&self, pair: Pair<Rule>, context: &mut T, scope: &[String]) -> ast::Node
     where
         T: ContextTrait,
     {
         match pair.as_rule() {
-            Rule::expression => self.parse_from_pair(pair, context, false),
+            Rule::expression => self.parse_from_pair(pair, context, false, scope),
             Rule::comma_node => {
                 let mut args = Vec::<ast::Node>::new();
                 pair.into_inner().for_each(|p| match p.as_rule() {
                     Rule::expression => {
-                        let n = self.build_arg(p, context);
+                        let n = self.build_arg(p, context, scope);
                         args.push(n);
                     }
                     Rule::comma_node => {
-                        let n = self.build_arg(p, context);
+                        let n = self.build_arg(p, context, scope);
                         args.push(n);
                     }
                     _ => {}
@@ -252,6 +346,26 @@ impl Parser {
     }
 }
 
+// The argument of LET or LAMBDA which is a single name to bind.
+fn get_local_name(pair: &Pair<Rule>) -> Option<String> {
+    if pair.as_rule() != Rule::expression {
+        return None;
+    }
+    let mut inner = pair.clone().into_inner();
+    match (inner.next(), inner.next()) {
+        (Some(p), None) if p.as_rule() == Rule::name => Some(p.as_str().trim().to_string()),
+        _ => None,
+    }
+}
+
+fn find_local(scope: &[String], name: &str) -> Option<String> {
+    let name = name.trim();
+    scope
+        .iter()
+        .any(|n| ast::is_same_local(n, name))
+        .then(|| name.to_string())
+}
+
 fn parse_number(s: &str) -> Option<f64> {
     let caps = NUM_REGEX.captures_iter(s.trim()).next()?;
     let integer = caps
@@ -268,22 +382,31 @@ fn parse_number(s: &str) -> Option<f64> {
     Some(result)
 }
 
-fn build_name_with_prefix<T>(pair: Pair<Rule>, id_fetcher: &mut T) -> Option<ast::CellReference>
+fn build_name_with_prefix<T>(pair: Pair<Rule>, context: &mut T) -> Option<ast::CellReference>
 where
-    T: IdFetcherTrait,
+    T: ContextTrait,
 {
     let mut p_iter = pair.into_inner();
     let first = p_iter.next().unwrap();
     match first.as_rule() {
+        // The prefix is a sheet if the name is only used in it. Otherwise,
+        // it is a workbook.
         Rule::workbook_name => {
-            let book_name = &Some(first.as_str());
             let second = p_iter.next().unwrap().as_str();
-            let name_id = id_fetcher.fetch_name_id(book_name, second);
+            let sheet_id = context.fetch_sheet_id(first.as_str());
+            let name_id = match context.fetch_local_name_id(&sheet_id, second) {
+                Some(id) => id,
+                None => context.fetch_name_id(&Some(first.as_str()), second),
+            };
             Some(ast::CellReference::Name(name_id))
         }
         Rule::name_characters => {
             let n = first.as_str();
-            let name_id = id_fetcher.fetch_name_id(&None, n);
+            let sheet_id = context.get_active_sheet();
+            let name_id = match context.fetch_local_name_id(&sheet_id, n) {
+                Some(id) => id,
+                None => context.fetch_name_id(&None, n),
+            };
             Some(ast::CellReference::Name(name_id))
         }
         _ => unreachable!(),
@@ -311,6 +434,8 @@ fn build_error(pair: Pair<Rule>) -> ast::PureNode {
         "#REF!" => ast::Error::Ref,
         "#VALUE!" => ast::Error::Value,
         "#GETTING_DATA" => ast::Error::GettingData,
+        "#SPILL!" => ast::Error::Spill,
+        "#CALC!" => ast::Error::Calc,
         _ => unreachable!(),
     };
     ast::PureNode::Value(ast::Value::Error(error))
@@ -475,6 +600,20 @@ mod tests {
         let f1 = "3.14%";
         let r1 = parser.parse(f1, &mut context).unwrap().pure;
         assert!(matches!(r1, ast::PureNode::Func(_)));
+        let f2 = "SUM(A1#)";
+        match parser.parse(f2, &mut context).unwrap().pure {
+            ast::PureNode::Func(f) => {
+                let arg = f.args.into_iter().next().unwrap();
+                match arg.pure {
+                    ast::PureNode::Func(f) => assert!(matches!(
+                        f.op,
+                        ast::Operator::Postfix(ast::PostfixOperator::Spill)
+                    )),
+                    _ => panic!(),
+                }
+            }
+            _ => panic!(),
+        }
     }
 
     #[test]
@@ -537,6 +676,60 @@ mod tests {
         }
     }
 
+    #[test]
+    fn let_and_lambda() {
+        let mut id_fetcher = TestIdFetcher {};
+        let mut vertext_fetcher = TestVertexFetcher {};
+        let mut context = Context {
+            sheet_id: 1,
+            book_name: "book",
+            id_fetcher: &mut id_fetcher,
+            vertex_fetcher: &mut vertext_fetcher,
+        };
+        let parser = Parser {};
+        let f = "LET(x, 1, _xlpm.y, X+1, y*2)";
+        match parser.parse(f, &mut context).unwrap().pure {
+            ast::PureNode::Func(f) => {
+                assert_eq!(f.args.len(), 5);
+                assert!(matches!(&f.args[0].pure, ast::PureNode::Local(n) if n == "x"));
+                assert!(matches!(&f.args[2].pure, ast::PureNode::Local(n) if n == "_xlpm.y"));
+                match &f.args[3].pure {
+                    ast::PureNode::Func(add) => {
+                        assert!(matches!(&add.args[0].pure, ast::PureNode::Local(_)))
+                    }
+                    _ => panic!(),
+                }
+            }
+            _ => panic!(),
+        }
+        // `x` is not bound outside the LAMBDA.
+        let f = "LAMBDA(x, x+1)(3)+x";
+        match parser.parse(f, &mut context).unwrap().pure {
+            ast::PureNode::Func(add) => {
+                match &add.args[0].pure {
+                    ast::PureNode::Func(call) => {
+                        assert!(matches!(call.op, ast::Operator::Call));
+                        assert_eq!(call.args.len(), 2);
+                    }
+                    _ => panic!(),
+                }
+                assert!(matches!(&add.args[1].pure, ast::PureNode::Reference(_)));
+            }
+            _ => panic!(),
+        }
+        let f = "LET(f, LAMBDA(x, x*2), f(3))";
+        match parser.parse(f, &mut context).unwrap().pure {
+            ast::PureNode::Func(f) => match &f.args[2].pure {
+                ast::PureNode::Func(call) => {
+                    assert!(matches!(call.op, ast::Operator::Call));
+                    assert!(matches!(&call.args[0].pure, ast::PureNode::Local(n) if n == "f"));
+                }
+                _ => panic!(),
+            },
+            _ => panic!(),
+        }
+    }
+
     #[test]
     fn prec() {
         let mut id_fetcher = TestIdFetcher {};
@@ -721,4 +914,55 @@ mod tests {
         let f = "NORM.S.DIST(2,TRUE)";
         let _ = parser.parse(f, &mut context).unwrap().pure;
     }
+
+    #[test]
+    fn table_reference_test() {
+        use crate::unparse::Stringify;
+        let parser = Parser {};
+        let mut vertext_fetcher = TestVertexFetcher {};
+        let mut id_fetcher = TestIdFetcher {};
+        let mut context = Context {
+            sheet_id: 1,
+            book_name: "book",
+            id_fetcher: &mut id_fetcher,
+            vertex_fetcher: &mut vertext_fetcher,
+        };
+        let mut check = |f: &str, expected: &str| {
+            let r = parser.parse(f, &mut context).unwrap();
+            assert!(
+                matches!(
+                    r.pure,
+                    ast::PureNode::Reference(ast::CellReference::Table(_))
+                ),
+                "{}",
+                f
+            );
+            assert_eq!(r.unparse(&mut TestIdFetcher {}, 1), expected);
+        };
+        check("Table1[]", "Table1[]");
+        check("table1[Col2]", "Table1[Col2]");
+        check("Table1[[Col1]:[Col2]]", "Table1[[Col1]:[Col2]]");
+        check("Table1[#Totals]", "Table1[#Totals]");
+        check(
+            "Table1[[#Headers],[#Data],[Col'#3]]",
+            "Table1[[#Headers],[#Data],[Col'#3]]",
+        );
+        check("[@Col1]", "Table1[[#This Row],[Col1]]");
+        check("Table1[@]", "Table1[#This Row]");
+        let f = "SUM(Table1[[#Headers],[#Totals]])";
+        let r = parser.parse(f, &mut context).unwrap().pure;
+        match r {
+            ast::PureNode::Func(f) => assert!(matches!(
+                f.args[0].pure,
+                ast::PureNode::Value(ast::Value::Error(ast::Error::Ref))
+            )),
+            _ => panic!(),
+        }
+        let f = "Table2[Col1]";
+        let r = parser.parse(f, &mut context).unwrap().pure;
+        assert!(matches!(
+            r,
+            ast::PureNode::Value(ast::Value::Error(ast::Error::Ref))
+        ));
+    }
 }
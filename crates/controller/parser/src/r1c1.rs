@@ -0,0 +1,212 @@
+use logisheets_base::{column_label_to_index, index_to_column_label};
+use logisheets_lexer::{lex_r1c1_reference, tokenize, Rule, TokenKind};
+use pest::iterators::Pair;
+use regex::Regex;
+
+use crate::reference::{get_r1c1_reference, R1C1Coord as Coord};
+
+lazy_static! {
+    static ref A1_CELL_REGEX: Regex = Regex::new(r"^(\$?)([A-Za-z]{1,3})(\$?)([0-9]+)$").unwrap();
+    static ref A1_COL_REGEX: Regex = Regex::new(r"^(\$?)([A-Za-z]{1,3})$").unwrap();
+    static ref A1_ROW_REGEX: Regex = Regex::new(r"^(\$?)([0-9]+)$").unwrap();
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Part {
+    Cell(Coord, Coord),
+    Row(Coord),
+    Col(Coord),
+}
+
+/// Convert the references in a formula, which has SKIPPED the '=', from
+/// the A1 notation to the R1C1 notation relative to the cell at `row` and
+/// `col`. It is none if the formula can not be lexed.
+pub fn a1_to_r1c1(formula: &str, row: usize, col: usize) -> Option<String> {
+    let tokens = tokenize(formula).ok()?;
+    let mut result = String::with_capacity(formula.len());
+    let mut pos = 0;
+    for t in tokens.iter().filter(|t| t.kind == TokenKind::Reference) {
+        if let Some(r1c1) = convert_a1_reference(&t.text, row, col) {
+            result.push_str(&formula[pos..t.start]);
+            result.push_str(&r1c1);
+            pos = t.end;
+        }
+    }
+    result.push_str(&formula[pos..]);
+    Some(result)
+}
+
+/// Convert the references in a formula, which has SKIPPED the '=', from
+/// the R1C1 notation relative to the cell at `row` and `col` to the A1
+/// notation. It is none if the formula can not be lexed or a reference is
+/// out of the sheet.
+pub fn r1c1_to_a1(formula: &str, row: usize, col: usize) -> Option<String> {
+    let tokens = tokenize(formula).ok()?;
+    let mut result = String::with_capacity(formula.len());
+    let mut pos = 0;
+    // `R` and `C` alone are lexed as names, and `R1` or `RC1` as A1
+    // references.
+    let tokens = tokens
+        .iter()
+        .filter(|t| matches!(t.kind, TokenKind::Reference | TokenKind::Name));
+    for t in tokens {
+        if let Some(pair) = lex_r1c1_reference(&t.text) {
+            result.push_str(&formula[pos..t.start]);
+            result.push_str(&convert_r1c1_reference(pair, row, col)?);
+            pos = t.end;
+        }
+    }
+    result.push_str(&formula[pos..]);
+    Some(result)
+}
+
+// A reference like `Sheet1!$A$1:B2`. It is none if it is not in the A1
+// notation, like a table reference.
+fn convert_a1_reference(text: &str, row: usize, col: usize) -> Option<String> {
+    let (prefix, area) = match text.rfind('!') {
+        Some(i) => text.split_at(i + 1),
+        None => ("", text),
+    };
+    let parts = match area.split_once(':') {
+        Some((first, second)) => {
+            let first = parse_a1_part(first.trim())?;
+            let second = parse_a1_part(second.trim())?;
+            if first == second {
+                vec![first]
+            } else {
+                vec![first, second]
+            }
+        }
+        None => vec![parse_a1_part(area)?],
+    };
+    let area = parts
+        .into_iter()
+        .map(|p| format_r1c1_part(p, row, col))
+        .collect::<Vec<_>>()
+        .join(":");
+    Some(format!("{}{}", prefix, area))
+}
+
+fn parse_a1_part(s: &str) -> Option<Part> {
+    let col_coord = |absolute: &str, label: &str| Coord {
+        absolute: !absolute.is_empty(),
+        idx: column_label_to_index(&label.to_uppercase()),
+    };
+    let row_coord = |absolute: &str, n: &str| {
+        Some(Coord {
+            absolute: !absolute.is_empty(),
+            idx: n.parse::<usize>().ok()?.checked_sub(1)?,
+        })
+    };
+    if let Some(caps) = A1_CELL_REGEX.captures(s) {
+        let c = col_coord(&caps[1], &caps[2]);
+        let r = row_coord(&caps[3], &caps[4])?;
+        Some(Part::Cell(r, c))
+    } else if let Some(caps) = A1_COL_REGEX.captures(s) {
+        Some(Part::Col(col_coord(&caps[1], &caps[2])))
+    } else {
+        let caps = A1_ROW_REGEX.captures(s)?;
+        Some(Part::Row(row_coord(&caps[1], &caps[2])?))
+    }
+}
+
+fn format_r1c1_part(part: Part, row: usize, col: usize) -> String {
+    let format = |prefix: char, coord: Coord, curr: usize| {
+        if coord.absolute {
+            format!("{}{}", prefix, coord.idx + 1)
+        } else if coord.idx == curr {
+            prefix.to_string()
+        } else {
+            format!("{}[{}]", prefix, coord.idx as i64 - curr as i64)
+        }
+    };
+    match part {
+        Part::Cell(r, c) => format!("{}{}", format('R', r, row), format('C', c, col)),
+        Part::Row(r) => format('R', r, row),
+        Part::Col(c) => format('C', c, col),
+    }
+}
+
+// A reference like `Sheet1!R[-1]C:RC`. It is none if it is out of the
+// sheet, or it is from a row to a column.
+fn convert_r1c1_reference(pair: Pair<Rule>, row: usize, col: usize) -> Option<String> {
+    let mut prefix = "";
+    let mut parts = vec![];
+    for p in pair.into_inner() {
+        match p.as_rule() {
+            Rule::work_sheet_prefix => prefix = p.as_str(),
+            _ => {
+                let part = match get_r1c1_reference(p, row, col)? {
+                    (Some(r), Some(c)) => Part::Cell(r, c),
+                    (Some(r), None) => Part::Row(r),
+                    (None, Some(c)) => Part::Col(c),
+                    (None, None) => return None,
+                };
+                parts.push(part);
+            }
+        }
+    }
+    let same_kind = match parts.as_slice() {
+        [Part::Row(_), Part::Col(_)] | [Part::Col(_), Part::Row(_)] => false,
+        [Part::Cell(..), p] | [p, Part::Cell(..)] => matches!(p, Part::Cell(..)),
+        _ => true,
+    };
+    if !same_kind {
+        return None;
+    }
+    Some(format!("{}{}", prefix, format_a1(parts)))
+}
+
+fn format_a1(parts: Vec<Part>) -> String {
+    let dollar = |c: Coord| if c.absolute { "$" } else { "" };
+    let format_row = |r: Coord| format!("{}{}", dollar(r), r.idx + 1);
+    let format_col = |c: Coord| format!("{}{}", dollar(c), index_to_column_label(c.idx));
+    match parts.as_slice() {
+        [Part::Row(r)] => format!("{}:{}", format_row(*r), format_row(*r)),
+        [Part::Col(c)] => format!("{}:{}", format_col(*c), format_col(*c)),
+        _ => parts
+            .iter()
+            .map(|p| match p {
+                Part::Cell(r, c) => format!("{}{}", format_col(*c), format_row(*r)),
+                Part::Row(r) => format_row(*r),
+                Part::Col(c) => format_col(*c),
+            })
+            .collect::<Vec<_>>()
+            .join(":"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{a1_to_r1c1, r1c1_to_a1};
+
+    #[test]
+    fn a1_to_r1c1_test() {
+        let f = a1_to_r1c1("SUM(A1:B2, $C$3, Sheet2!D1)", 1, 1).unwrap();
+        assert_eq!(f, "SUM(R[-1]C[-1]:RC, R3C3, Sheet2!R[-1]C[2])");
+        let f = a1_to_r1c1("COUNT(A:A, $2:$3) & \"A1\"", 0, 0).unwrap();
+        assert_eq!(f, "COUNT(C, R2:R3) & \"A1\"");
+        assert_eq!(
+            a1_to_r1c1("Table1[Col] + 1", 0, 0).unwrap(),
+            "Table1[Col] + 1"
+        );
+        assert!(a1_to_r1c1("SUM(", 0, 0).is_none());
+    }
+
+    #[test]
+    fn r1c1_to_a1_test() {
+        let f = r1c1_to_a1("SUM(R[-1]C[-1]:RC, R3C3, Sheet2!R[-1]C[2])", 1, 1).unwrap();
+        assert_eq!(f, "SUM(A1:B2, $C$3, Sheet2!D1)");
+        let f = r1c1_to_a1("COUNT(C, r2:r3, C[1]) & \"RC\"", 0, 0).unwrap();
+        assert_eq!(f, "COUNT(A:A, $2:$3, B:B) & \"RC\"");
+        let f = r1c1_to_a1("ROUND(RC2, 1) + 'R1'!R1C1 + Table1[RC]", 0, 0).unwrap();
+        assert_eq!(f, "ROUND($B1, 1) + 'R1'!$A$1 + Table1[RC]");
+        let f = r1c1_to_a1("IF(ISERROR(R1C1), #REF!, R1C1)", 0, 0).unwrap();
+        assert_eq!(f, "IF(ISERROR($A$1), #REF!, $A$1)");
+        assert!(r1c1_to_a1("R[-1]C", 0, 0).is_none());
+        assert!(r1c1_to_a1("R2000000C1", 0, 0).is_none());
+        assert!(r1c1_to_a1("R1:C1", 0, 0).is_none());
+        let f = r1c1_to_a1("SUM(Sheet2!C, RC1x, R16384C16384)", 0, 0).unwrap();
+        assert_eq!(f, "SUM(Sheet2!A:A, RC1x, $XFD$16384)");
+    }
+}
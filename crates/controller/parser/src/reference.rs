@@ -8,7 +8,7 @@ use anyhow::Result;
 use super::ast;
 use logisheets_base::{
     column_label_to_index, id_fetcher::IdFetcherTrait, Addr, BlockRange, CellId, Cube, CubeCross,
-    ExtRef, NormalRange, Range, RefAbs, SheetId,
+    ExtRef, NormalRange, Range, RefAbs, SheetId, Table, MAX_COLS, MAX_ROWS,
 };
 use logisheets_lexer::*;
 use pest::iterators::Pair;
@@ -36,11 +36,181 @@ where
         Rule::a1_reference_range_with_prefix => {
             build_a1_reference_range_with_prefix(p, curr_sheet, id_fetcher)
         }
-        _ => unreachable!(),
+        // The formulas are stored in A1, and the ones in R1C1 are converted
+        // before they are parsed.
+        _ => Err(ParseError::R1C1Reference(p.as_str().to_string()).into()),
     }?;
     Ok(ast::PureNode::Reference(r))
 }
 
+pub fn build_table_reference<T>(pair: Pair<Rule>, context: &mut T) -> Result<ast::PureNode>
+where
+    T: ContextTrait,
+{
+    let (name, specifiers, columns) = parse_table_selector(pair)?;
+    let table = context.fetch_table(name).ok_or_else(|| {
+        ParseError::ParseTableRefFailed(format!("table not found: {}", name.unwrap_or("")))
+    })?;
+    let (row_start, row_end, col_start, col_end) = get_table_area(&table, &specifiers, &columns)?;
+    let sheet_id = table.sheet_id;
+    let start = context.fetch_cell_id(&sheet_id, row_start, col_start)?;
+    let end = context.fetch_cell_id(&sheet_id, row_end, col_end)?;
+    let range = match (start, end) {
+        (CellId::NormalCell(s), CellId::NormalCell(e)) if s == e => {
+            Range::Normal(NormalRange::Single(s))
+        }
+        (CellId::NormalCell(s), CellId::NormalCell(e)) => {
+            Range::Normal(NormalRange::AddrRange(s, e))
+        }
+        (CellId::BlockCell(s), CellId::BlockCell(e)) if s == e => {
+            Range::Block(BlockRange::Single(s))
+        }
+        (CellId::BlockCell(s), CellId::BlockCell(e)) if s.block_id == e.block_id => {
+            Range::Block(BlockRange::AddrRange(s, e))
+        }
+        _ => {
+            return Err(ParseError::ParseTableRefFailed(
+                "the table is partly covered by a block".to_string(),
+            )
+            .into())
+        }
+    };
+    let range_id = context.fetch_range_id(&sheet_id, &range);
+    Ok(ast::PureNode::Reference(ast::CellReference::Table(
+        ast::TableReference {
+            table_id: table.id,
+            specifiers,
+            columns,
+            sheet_id,
+            range_id,
+        },
+    )))
+}
+
+type TableColumns = Option<(String, String)>;
+
+// The table name, the specifiers and the columns of a table reference.
+fn parse_table_selector(
+    pair: Pair<'_, Rule>,
+) -> Result<(Option<&str>, Vec<ast::TableSpecifier>, TableColumns)> {
+    let mut name = None;
+    let mut specifiers = vec![];
+    let mut columns = None;
+    for p in pair.into_inner() {
+        match p.as_rule() {
+            Rule::table_name => name = Some(p.as_str()),
+            Rule::table_this_row => {
+                specifiers.push(ast::TableSpecifier::ThisRow);
+                if let Some(c) = p.into_inner().next() {
+                    let col = unescape_table_column(c.as_str());
+                    columns = Some((col.clone(), col));
+                }
+            }
+            Rule::table_item => {
+                let s = ast::TableSpecifier::from_specifier_str(p.as_str())
+                    .ok_or_else(|| ParseError::ParseTableRefFailed(p.as_str().to_string()))?;
+                if !specifiers.contains(&s) {
+                    specifiers.push(s);
+                }
+            }
+            Rule::table_column_range | Rule::table_column => {
+                if columns.is_some() {
+                    return Err(ParseError::ParseTableRefFailed(p.as_str().to_string()).into());
+                }
+                let (start, end) = match p.as_rule() {
+                    Rule::table_column => (p.as_str(), p.as_str()),
+                    _ => {
+                        let mut iter = p.into_inner();
+                        let start = iter.next().unwrap().as_str();
+                        let end = iter.next().map_or(start, |e| e.as_str());
+                        (start, end)
+                    }
+                };
+                columns = Some((unescape_table_column(start), unescape_table_column(end)));
+            }
+            _ => unreachable!(),
+        }
+    }
+    Ok((name, specifiers, columns))
+}
+
+// The first row, the last row, the first column and the last column of the
+// cells in the table referred to.
+fn get_table_area(
+    table: &Table,
+    specifiers: &[ast::TableSpecifier],
+    columns: &TableColumns,
+) -> Result<(usize, usize, usize, usize)> {
+    let (row_start, row_end) = get_table_rows(table, specifiers)?;
+    let (col_start, col_end) = match columns {
+        Some((start, end)) => {
+            let start = get_table_col(table, start)?;
+            let end = get_table_col(table, end)?;
+            (start.min(end), start.max(end))
+        }
+        None => (table.start.col, table.end.col),
+    };
+    Ok((row_start, row_end, col_start, col_end))
+}
+
+// The first and the last rows of the table referred to by the specifiers.
+// The data rows are referred to if there are no specifiers.
+fn get_table_rows(table: &Table, specifiers: &[ast::TableSpecifier]) -> Result<(usize, usize)> {
+    let data_start = table.start.row + table.header_row_count;
+    let totals_start = table.end.row + 1 - table.totals_row_count;
+    let mut bands = specifiers
+        .iter()
+        .map(|s| match s {
+            ast::TableSpecifier::All => (table.start.row, table.end.row + 1),
+            ast::TableSpecifier::Data | ast::TableSpecifier::ThisRow => (data_start, totals_start),
+            ast::TableSpecifier::Headers => (table.start.row, data_start),
+            ast::TableSpecifier::Totals => (totals_start, table.end.row + 1),
+        })
+        .collect::<Vec<_>>();
+    if bands.is_empty() {
+        bands.push((data_start, totals_start));
+    }
+    bands.sort_unstable();
+    let (start, mut end) = bands[0];
+    for (s, e) in bands.into_iter().skip(1) {
+        if s > end {
+            return Err(
+                ParseError::ParseTableRefFailed("the rows are not adjacent".to_string()).into(),
+            );
+        }
+        end = end.max(e);
+    }
+    if start >= end {
+        return Err(ParseError::ParseTableRefFailed("no rows are referred to".to_string()).into());
+    }
+    Ok((start, end - 1))
+}
+
+fn get_table_col(table: &Table, column: &str) -> Result<usize> {
+    table
+        .columns
+        .iter()
+        .position(|c| c.eq_ignore_ascii_case(column))
+        .map(|i| table.start.col + i)
+        .filter(|c| *c <= table.end.col)
+        .ok_or_else(|| {
+            ParseError::ParseTableRefFailed(format!("column not found: {}", column)).into()
+        })
+}
+
+// The special characters in a column name are escaped by a `'`.
+fn unescape_table_column(s: &str) -> String {
+    let mut result = String::new();
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\'' => result.extend(chars.next()),
+            _ => result.push(c),
+        }
+    }
+    result
+}
+
 fn build_a1_reference_range_with_prefix<T>(
     pair: Pair<Rule>,
     curr_sheet: SheetId,
@@ -61,7 +231,7 @@ where
             match (start.cell_id, end.cell_id) {
                 (CellId::NormalCell(s), CellId::NormalCell(e)) => {
                     let range = Range::Normal(NormalRange::AddrRange(s, e));
-                    let range_id = id_fetcher.fetch_range_id(&curr_sheet, &range);
+                    let range_id = id_fetcher.fetch_range_id(&sheet_id, &range);
                     Ok(ast::CellReference::Mut(RangeDisplay {
                         range_id,
                         ref_abs,
@@ -74,7 +244,7 @@ where
                     }
 
                     let range = Range::Block(BlockRange::AddrRange(s, e));
-                    let range_id = id_fetcher.fetch_range_id(&curr_sheet, &range);
+                    let range_id = id_fetcher.fetch_range_id(&sheet_id, &range);
                     Ok(ast::CellReference::Mut(RangeDisplay {
                         range_id,
                         ref_abs,
@@ -595,8 +765,9 @@ fn parse_row(row: &str) -> Result<(bool, usize)> {
                 .as_str();
             let idx = label
                 .parse::<usize>()
-                .or(Err(ParseError::ParseRowFailed(row.to_string())))?
-                - 1;
+                .ok()
+                .and_then(|r| r.checked_sub(1))
+                .ok_or(ParseError::ParseRowFailed(row.to_string()))?;
             Ok((abs, idx))
         },
     )?;
@@ -619,6 +790,261 @@ fn parse_column(col: &str) -> Result<(bool, usize)> {
     Ok(result)
 }
 
+/// The cells denoted by a reference. The rows are none for whole columns,
+/// and so are the columns for whole rows.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReferenceArea {
+    pub workbook: Option<String>,
+    /// The first sheet of a 3D reference.
+    pub from_sheet: Option<String>,
+    /// None if the reference has no sheet prefix.
+    pub sheet: Option<String>,
+    pub rows: Option<(usize, usize)>,
+    pub cols: Option<(usize, usize)>,
+}
+
+/// Resolve the text of a reference token, like `Sheet2!A1:B2`, `R[-1]C` or
+/// `Table1[@Price]`, to the cells it denotes. Nothing is registered in the
+/// workbook. `fetch_table` returns the name of the sheet of the table and
+/// the table, which is the one the formula is in if no name is given. The
+/// relative R1C1 references and the references to this row of a table are
+/// resolved with the cell at `curr_row` and `curr_col`. Return `None` if
+/// the text is not a single reference or it can not be resolved.
+pub fn resolve_reference<F>(
+    text: &str,
+    curr_row: usize,
+    curr_col: usize,
+    fetch_table: F,
+) -> Option<ReferenceArea>
+where
+    F: FnOnce(Option<&str>) -> Option<(String, Table)>,
+{
+    let formula = try_lex(text).ok()?;
+    let mut iter = formula.into_inner().next()?.into_inner();
+    let pair = iter.next()?;
+    if iter.next().is_some() {
+        return None;
+    }
+    match pair.as_rule() {
+        Rule::cell_reference => resolve_cell_reference(pair, curr_row, curr_col),
+        Rule::table_reference => resolve_table_reference(pair, curr_row, fetch_table),
+        _ => None,
+    }
+}
+
+fn resolve_cell_reference(
+    pair: Pair<Rule>,
+    curr_row: usize,
+    curr_col: usize,
+) -> Option<ReferenceArea> {
+    let mut prefix = None;
+    let mut bounds = None;
+    for p in pair.into_inner().next()?.into_inner() {
+        match p.as_rule() {
+            Rule::work_sheet_prefix => prefix = Some(build_work_sheet_prefix(p)?),
+            Rule::a1_reference => bounds = Some(get_a1_reference_bounds(p)?),
+            Rule::a1_reference_range => {
+                let mut iter = p.into_inner();
+                let (r1, c1) = get_a1_addr(iter.next()?)?;
+                let (r2, c2) = get_a1_addr(iter.next()?)?;
+                bounds = Some((
+                    Some((r1.min(r2), r1.max(r2))),
+                    Some((c1.min(c2), c1.max(c2))),
+                ));
+            }
+            Rule::r1c1_reference => bounds = Some(get_r1c1_bounds(p, None, curr_row, curr_col)?),
+            Rule::r1c1_reference_range => {
+                let mut iter = p.into_inner();
+                let first = iter.next()?;
+                bounds = Some(get_r1c1_bounds(first, iter.next(), curr_row, curr_col)?);
+            }
+            _ => return None,
+        }
+    }
+    let (rows, cols) = bounds?;
+    Some(build_reference_area(prefix, rows, cols))
+}
+
+/// Resolve the text of a reference in a formula written in the R1C1
+/// notation, like `Sheet2!R[-1]C:RC` or `C`, against the cell at `curr_row`
+/// and `curr_col`. Return `None` if the text is not such a reference or it
+/// is out of the sheet.
+pub fn resolve_r1c1_reference(
+    text: &str,
+    curr_row: usize,
+    curr_col: usize,
+) -> Option<ReferenceArea> {
+    let mut prefix = None;
+    let mut areas = vec![];
+    for p in lex_r1c1_reference(text)?.into_inner() {
+        match p.as_rule() {
+            Rule::work_sheet_prefix => prefix = Some(build_work_sheet_prefix(p)?),
+            _ => areas.push(p),
+        }
+    }
+    let mut areas = areas.into_iter();
+    let first = areas.next()?;
+    let (rows, cols) = get_r1c1_bounds(first, areas.next(), curr_row, curr_col)?;
+    Some(build_reference_area(prefix, rows, cols))
+}
+
+// The rows and the columns from `first` to `second`. They are like
+// `R1C1:R2C2` or `R1:R2`, but not `R1:C2`.
+fn get_r1c1_bounds(
+    first: Pair<Rule>,
+    second: Option<Pair<Rule>>,
+    curr_row: usize,
+    curr_col: usize,
+) -> Option<(Bounds, Bounds)> {
+    let (r1, c1) = get_r1c1_reference(first, curr_row, curr_col)?;
+    let (r2, c2) = match second {
+        Some(p) => get_r1c1_reference(p, curr_row, curr_col)?,
+        None => (r1, c1),
+    };
+    let merge = |a: Option<R1C1Coord>, b: Option<R1C1Coord>| match (a, b) {
+        (Some(a), Some(b)) => Some(Some((a.idx.min(b.idx), a.idx.max(b.idx)))),
+        (None, None) => Some(None),
+        _ => None,
+    };
+    Some((merge(r1, r2)?, merge(c1, c2)?))
+}
+
+fn build_reference_area(
+    prefix: Option<ReferencePrefix>,
+    rows: Bounds,
+    cols: Bounds,
+) -> ReferenceArea {
+    let (workbook, from_sheet, sheet) = match prefix {
+        Some(p) => (p.workbook, p.from_sheet, Some(p.sheet)),
+        None => (None, None, None),
+    };
+    ReferenceArea {
+        workbook,
+        from_sheet,
+        sheet,
+        rows,
+        cols,
+    }
+}
+
+type Bounds = Option<(usize, usize)>;
+
+fn get_a1_reference_bounds(pair: Pair<Rule>) -> Option<(Bounds, Bounds)> {
+    let p = pair.into_inner().next()?;
+    match p.as_rule() {
+        Rule::a1_addr => {
+            let (row, col) = get_a1_addr(p)?;
+            Some((Some((row, row)), Some((col, col))))
+        }
+        Rule::a1_column_range => {
+            let mut iter = p.into_inner();
+            let (_, start) = build_column(iter.next()?).ok()?;
+            let (_, end) = build_column(iter.next()?).ok()?;
+            Some((None, Some((start.min(end), start.max(end)))))
+        }
+        Rule::a1_row_range => {
+            let mut iter = p.into_inner();
+            let (_, start) = build_row(iter.next()?).ok()?;
+            let (_, end) = build_row(iter.next()?).ok()?;
+            Some((Some((start.min(end), start.max(end))), None))
+        }
+        _ => None,
+    }
+}
+
+fn get_a1_addr(pair: Pair<Rule>) -> Option<(usize, usize)> {
+    let mut iter = pair.into_inner();
+    let (_, col) = build_column(iter.next()?).ok()?;
+    let (_, row) = build_row(iter.next()?).ok()?;
+    Some((row, col))
+}
+
+/// A row or a column of an R1C1 reference, resolved against the anchor cell.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct R1C1Coord {
+    pub absolute: bool,
+    pub idx: usize,
+}
+
+/// The row and the column of an `r1c1_reference` or an `r1c1_area`. The row is none for a
+/// whole column, and so is the column for a whole row. It is none if the
+/// reference is out of the sheet.
+pub fn get_r1c1_reference(
+    pair: Pair<Rule>,
+    curr_row: usize,
+    curr_col: usize,
+) -> Option<(Option<R1C1Coord>, Option<R1C1Coord>)> {
+    let mut row = None;
+    let mut col = None;
+    for p in pair.into_inner() {
+        match p.as_rule() {
+            Rule::r1c1_row | Rule::r1c1_row_only => {
+                row = Some(get_r1c1_coord(p, curr_row, MAX_ROWS)?)
+            }
+            Rule::r1c1_column | Rule::r1c1_column_only => {
+                col = Some(get_r1c1_coord(p, curr_col, MAX_COLS)?)
+            }
+            _ => return None,
+        }
+    }
+    Some((row, col))
+}
+
+// `R` alone is the current row, `R[n]` is relative to it and `Rn` is
+// absolute. So are the columns.
+fn get_r1c1_coord(pair: Pair<Rule>, curr: usize, max: usize) -> Option<R1C1Coord> {
+    let (absolute, idx) = match pair.as_rule() {
+        Rule::r1c1_row | Rule::r1c1_column | Rule::r1c1_row_only | Rule::r1c1_column_only => {
+            return get_r1c1_coord(pair.into_inner().next()?, curr, max)
+        }
+        Rule::r1c1_relative_row | Rule::r1c1_relative_column => {
+            let delta = pair.into_inner().next()?.as_str().parse::<i64>().ok()?;
+            (false, curr as i64 + delta)
+        }
+        Rule::r1c1_absolute_row | Rule::r1c1_absolute_column => match pair.into_inner().next() {
+            Some(n) => (true, n.as_str().parse::<i64>().ok()? - 1),
+            None => (false, curr as i64),
+        },
+        Rule::r1c1_absolute_number => (true, pair.as_str().parse::<i64>().ok()? - 1),
+        _ => return None,
+    };
+    if idx < 0 || idx >= max as i64 {
+        return None;
+    }
+    Some(R1C1Coord {
+        absolute,
+        idx: idx as usize,
+    })
+}
+
+fn resolve_table_reference<F>(
+    pair: Pair<Rule>,
+    curr_row: usize,
+    fetch_table: F,
+) -> Option<ReferenceArea>
+where
+    F: FnOnce(Option<&str>) -> Option<(String, Table)>,
+{
+    let (name, specifiers, columns) = parse_table_selector(pair).ok()?;
+    let (sheet, table) = fetch_table(name)?;
+    let (mut row_start, mut row_end, col_start, col_end) =
+        get_table_area(&table, &specifiers, &columns).ok()?;
+    if specifiers.contains(&ast::TableSpecifier::ThisRow) {
+        if curr_row < row_start || curr_row > row_end {
+            return None;
+        }
+        row_start = curr_row;
+        row_end = curr_row;
+    }
+    Some(ReferenceArea {
+        workbook: None,
+        from_sheet: None,
+        sheet: Some(sheet),
+        rows: Some((row_start, row_end)),
+        cols: Some((col_start, col_end)),
+    })
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct ReferencePrefix {
     pub sheet: String,
@@ -631,17 +1057,18 @@ fn build_work_sheet_prefix(pair: Pair<Rule>) -> Option<ReferencePrefix> {
         .captures_iter(pair.as_str())
         .next()
         .map_or(None, |c| {
+            // The apostrophes in the quoted names are doubled.
             let sheet = c.get(5).map_or(None, |m| {
                 let name = m.as_str();
-                Some(name.to_string())
+                Some(name.replace("''", "'"))
             })?;
             let workbook = c.get(2).map_or(None, |m| {
                 let name = m.as_str();
-                Some(name.to_string())
+                Some(name.replace("''", "'"))
             });
             let from_sheet = c.get(4).map_or(None, |m| {
                 let name = m.as_str();
-                Some(name.to_string())
+                Some(name.replace("''", "'"))
             });
             Some(ReferencePrefix {
                 sheet,
@@ -653,7 +1080,44 @@ fn build_work_sheet_prefix(pair: Pair<Rule>) -> Option<ReferencePrefix> {
 
 #[cfg(test)]
 mod tests {
-    use super::parse_column;
+    use super::{get_table_rows, parse_column, resolve_reference, ReferenceArea};
+    use crate::ast::TableSpecifier;
+    use logisheets_base::{Addr, Table};
+
+    #[test]
+    fn get_table_rows_test() {
+        let table = Table {
+            id: 1,
+            sheet_id: 1,
+            start: Addr { row: 2, col: 0 },
+            end: Addr { row: 7, col: 1 },
+            header_row_count: 1,
+            totals_row_count: 1,
+            columns: vec![String::from("a"), String::from("b")],
+        };
+        assert_eq!(get_table_rows(&table, &[]).unwrap(), (3, 6));
+        assert_eq!(
+            get_table_rows(&table, &[TableSpecifier::All]).unwrap(),
+            (2, 7)
+        );
+        assert_eq!(
+            get_table_rows(&table, &[TableSpecifier::Headers]).unwrap(),
+            (2, 2)
+        );
+        assert_eq!(
+            get_table_rows(&table, &[TableSpecifier::Totals, TableSpecifier::Data]).unwrap(),
+            (3, 7)
+        );
+        assert!(
+            get_table_rows(&table, &[TableSpecifier::Headers, TableSpecifier::Totals]).is_err()
+        );
+        let table = Table {
+            totals_row_count: 0,
+            ..table
+        };
+        assert!(get_table_rows(&table, &[TableSpecifier::Totals]).is_err());
+    }
+
     #[test]
     fn parse_column_test() {
         let col = "B";
@@ -663,4 +1127,80 @@ mod tests {
         let result = parse_column(col);
         assert!(matches!(result, Ok((true, 27))));
     }
+
+    #[test]
+    fn resolve_reference_test() {
+        let table = Table {
+            id: 1,
+            sheet_id: 1,
+            start: Addr { row: 2, col: 1 },
+            end: Addr { row: 7, col: 2 },
+            header_row_count: 1,
+            totals_row_count: 1,
+            columns: vec![String::from("a"), String::from("b")],
+        };
+        let fetch_table = |name: Option<&str>| match name {
+            Some(n) if n != "Table1" => None,
+            _ => Some((String::from("Sheet2"), table.clone())),
+        };
+        let area = |sheet: Option<&str>, rows, cols| ReferenceArea {
+            workbook: None,
+            from_sheet: None,
+            sheet: sheet.map(|s| s.to_string()),
+            rows,
+            cols,
+        };
+        assert_eq!(
+            resolve_reference("B3", 0, 0, fetch_table),
+            Some(area(None, Some((2, 2)), Some((1, 1))))
+        );
+        assert_eq!(
+            resolve_reference("Sheet2!$C$4:A1", 0, 0, fetch_table),
+            Some(area(Some("Sheet2"), Some((0, 3)), Some((0, 2))))
+        );
+        assert_eq!(
+            resolve_reference("B:A", 0, 0, fetch_table),
+            Some(area(None, None, Some((0, 1))))
+        );
+        assert_eq!(
+            resolve_reference("3:4", 0, 0, fetch_table),
+            Some(area(None, Some((2, 3)), None))
+        );
+        let r = resolve_reference("[Book1]Sheet3!A1", 0, 0, fetch_table).unwrap();
+        assert_eq!(r.workbook.as_deref(), Some("Book1"));
+        assert_eq!(r.sheet.as_deref(), Some("Sheet3"));
+        assert_eq!(
+            resolve_reference("Table1[b]", 0, 0, fetch_table),
+            Some(area(Some("Sheet2"), Some((3, 6)), Some((2, 2))))
+        );
+        assert_eq!(
+            resolve_reference("[@a]", 4, 0, fetch_table),
+            Some(area(Some("Sheet2"), Some((4, 4)), Some((1, 1))))
+        );
+        assert_eq!(resolve_reference("[@a]", 7, 0, fetch_table), None);
+        assert_eq!(resolve_reference("Table2[a]", 4, 0, fetch_table), None);
+        assert_eq!(resolve_reference("A1+B2", 0, 0, fetch_table), None);
+        assert_eq!(resolve_reference("A0", 0, 0, fetch_table), None);
+
+        // The R1C1 references are relative to the cell at (4, 2).
+        assert_eq!(
+            resolve_reference("R1C1", 4, 2, fetch_table),
+            Some(area(None, Some((0, 0)), Some((0, 0))))
+        );
+        assert_eq!(
+            resolve_reference("Sheet2!R[-1]C[-2]:RC", 4, 2, fetch_table),
+            Some(area(Some("Sheet2"), Some((3, 4)), Some((0, 2))))
+        );
+        assert_eq!(
+            resolve_reference("C[1]", 4, 2, fetch_table),
+            Some(area(None, None, Some((3, 3))))
+        );
+        assert_eq!(
+            resolve_reference("R[-2]:R[1]", 4, 2, fetch_table),
+            Some(area(None, Some((2, 5)), None))
+        );
+        assert_eq!(resolve_reference("R[-5]C", 4, 2, fetch_table), None);
+        assert_eq!(resolve_reference("R2000000C1", 4, 2, fetch_table), None);
+        assert_eq!(resolve_reference("R[1]:C[1]", 4, 2, fetch_table), None);
+    }
 }
@@ -2,8 +2,8 @@ use anyhow::Result;
 use logisheets_base::id_fetcher::{IdFetcherTrait, VertexFetcherTrait};
 use logisheets_base::name_fetcher::NameFetcherTrait;
 use logisheets_base::{
-    CellId, ColId, Cube, CubeId, ExtBookId, ExtRef, ExtRefId, FuncId, NameId, NormalCellId, Range,
-    RangeId, RowId, SheetId, TextId,
+    Addr, CellId, ColId, Cube, CubeId, ExtBookId, ExtRef, ExtRefId, FuncId, NameId, NormalCellId,
+    Range, RangeId, RowId, SheetId, Table, TableId, TextId,
 };
 
 pub struct TestIdFetcher {}
@@ -34,6 +34,10 @@ impl IdFetcherTrait for TestIdFetcher {
         name.len() as NameId
     }
 
+    fn fetch_local_name_id(&mut self, _: &SheetId, _: &str) -> Option<NameId> {
+        None
+    }
+
     fn fetch_ext_book_id(&mut self, book: &str) -> ExtBookId {
         book.len() as ExtBookId
     }
@@ -64,10 +68,22 @@ impl NameFetcherTrait for TestIdFetcher {
         book_id.to_string()
     }
 
+    fn fetch_book_idx(&self, book_id: &ExtBookId) -> usize {
+        *book_id as usize + 1
+    }
+
     fn fetch_defined_name(&self, nid: &NameId) -> String {
         nid.to_string()
     }
 
+    fn fetch_name_scope(&self, _: &NameId) -> Option<SheetId> {
+        None
+    }
+
+    fn fetch_table_name(&self, _table_id: &TableId) -> String {
+        String::from("Table1")
+    }
+
     fn fetch_cell_idx(&mut self, _sheet_id: &SheetId, cell_id: &CellId) -> (usize, usize) {
         if let CellId::NormalCell(NormalCellId {
             row,
@@ -117,4 +133,24 @@ impl VertexFetcherTrait for TestVertexFetcher {
     fn fetch_ext_ref_id(&mut self, _ext_ref: &ExtRef) -> ExtRefId {
         1
     }
+
+    // Table1 is at A1:C6 of the sheet 1, with a header row and a totals row.
+    fn fetch_table(&mut self, name: Option<&str>) -> Option<Table> {
+        match name {
+            Some(n) if !n.eq_ignore_ascii_case("Table1") => None,
+            _ => Some(Table {
+                id: 1,
+                sheet_id: 1,
+                start: Addr { row: 0, col: 0 },
+                end: Addr { row: 5, col: 2 },
+                header_row_count: 1,
+                totals_row_count: 1,
+                columns: vec![
+                    String::from("Col1"),
+                    String::from("Col2"),
+                    String::from("Col#3"),
+                ],
+            }),
+        }
+    }
 }
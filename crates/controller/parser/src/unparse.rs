@@ -5,10 +5,11 @@ use logisheets_base::{
 
 use crate::ast::{
     CellReference, CubeDisplay, Error, ExtRefDisplay, Func, InfixOperator, Operator,
-    PostfixOperator, PrefixOperator, PureNode, RangeDisplay, Value,
+    PostfixOperator, PrefixOperator, PureNode, RangeDisplay, TableReference, Value,
 };
 
 use super::ast::Node;
+use regex::Regex;
 
 pub fn unparse<T>(node: &Node, fetcher: &mut T, curr_sheet: SheetId) -> String
 where
@@ -17,40 +18,133 @@ where
     node.unparse(fetcher, curr_sheet)
 }
 
+/// Unparse the formula as it is stored in files. The functions introduced
+/// after Excel 2007 are written with their prefixes, like `_xlfn.UNIQUE`,
+/// the names bound by LET and LAMBDA have the `_xlpm.` prefix and the spill
+/// operator is written as `_xlfn.ANCHORARRAY`.
+pub fn unparse_for_file<T>(
+    node: &Node,
+    fetcher: &mut T,
+    curr_sheet: SheetId,
+    func_prefix: FuncPrefix,
+) -> String
+where
+    T: NameFetcherTrait,
+{
+    let opts = UnparseOptions {
+        curr_sheet,
+        for_file: Some(func_prefix),
+        r1c1_anchor: None,
+    };
+    node.unparse_with(fetcher, &opts)
+}
+
+/// Unparse the formula with the references in the R1C1 notation, relative
+/// to the cell at `row` and `col`.
+pub fn unparse_r1c1<T>(
+    node: &Node,
+    fetcher: &mut T,
+    curr_sheet: SheetId,
+    row: usize,
+    col: usize,
+) -> String
+where
+    T: NameFetcherTrait,
+{
+    let opts = UnparseOptions {
+        curr_sheet,
+        for_file: None,
+        r1c1_anchor: Some((row, col)),
+    };
+    node.unparse_with(fetcher, &opts)
+}
+
+/// Finds the prefix of a function in files, like `_xlfn.`, by its name
+/// without the prefix. It is empty if the function has none.
+pub type FuncPrefix = fn(&str) -> &'static str;
+
+#[derive(Debug, Clone, Copy)]
+pub struct UnparseOptions {
+    /// The references in this sheet are written without the sheet name.
+    pub curr_sheet: SheetId,
+    /// Unparse as it is stored in files, with the prefixes of the functions.
+    pub for_file: Option<FuncPrefix>,
+    /// The row and the column which the references in the R1C1 notation are
+    /// relative to. The references are in the A1 notation if it is none.
+    pub r1c1_anchor: Option<(usize, usize)>,
+}
+
 pub trait Stringify {
     fn unparse<T>(&self, fetcher: &mut T, curr_sheet: SheetId) -> String
+    where
+        T: NameFetcherTrait,
+    {
+        let opts = UnparseOptions {
+            curr_sheet,
+            for_file: None,
+            r1c1_anchor: None,
+        };
+        self.unparse_with(fetcher, &opts)
+    }
+
+    fn unparse_with<T>(&self, fetcher: &mut T, opts: &UnparseOptions) -> String
     where
         T: NameFetcherTrait;
 }
 
+// The names are stored as they are typed or loaded, so the prefix may be
+// there already.
+fn get_file_func_name(name: &str, func_prefix: FuncPrefix) -> String {
+    let upper = name.to_uppercase();
+    let mut bare = upper.as_str();
+    for prefix in ["_XLFN.", "_XLWS."] {
+        bare = bare.strip_prefix(prefix).unwrap_or(bare);
+    }
+    match func_prefix(bare) {
+        "" => name.to_string(),
+        prefix => format!("{}{}", prefix, bare),
+    }
+}
+
 impl Stringify for Node {
-    fn unparse<T>(&self, fetcher: &mut T, curr_sheet: SheetId) -> String
+    fn unparse_with<T>(&self, fetcher: &mut T, opts: &UnparseOptions) -> String
     where
         T: NameFetcherTrait,
     {
         if self.bracket {
-            format!("({})", self.pure.unparse(fetcher, curr_sheet))
+            format!("({})", self.pure.unparse_with(fetcher, opts))
         } else {
-            self.pure.unparse(fetcher, curr_sheet)
+            self.pure.unparse_with(fetcher, opts)
         }
     }
 }
 
 impl Stringify for PureNode {
-    fn unparse<T>(&self, fetcher: &mut T, curr_sheet: SheetId) -> String
+    fn unparse_with<T>(&self, fetcher: &mut T, opts: &UnparseOptions) -> String
     where
         T: NameFetcherTrait,
     {
         match self {
-            PureNode::Func(func) => func.unparse(fetcher, curr_sheet),
-            PureNode::Value(v) => v.unparse(fetcher, curr_sheet),
-            PureNode::Reference(cr) => cr.unparse(fetcher, curr_sheet),
+            PureNode::Func(func) => func.unparse_with(fetcher, opts),
+            PureNode::Value(v) => v.unparse_with(fetcher, opts),
+            PureNode::Reference(cr) => cr.unparse_with(fetcher, opts),
+            PureNode::Local(name) => {
+                let prefixed = name
+                    .get(..6)
+                    .filter(|p| p.eq_ignore_ascii_case("_xlpm."))
+                    .is_some();
+                if opts.for_file.is_some() && !prefixed {
+                    format!("_xlpm.{}", name)
+                } else {
+                    name.clone()
+                }
+            }
         }
     }
 }
 
 impl Stringify for Func {
-    fn unparse<T>(&self, fetcher: &mut T, curr_sheet: SheetId) -> String
+    fn unparse_with<T>(&self, fetcher: &mut T, opts: &UnparseOptions) -> String
     where
         T: NameFetcherTrait,
     {
@@ -58,6 +152,10 @@ impl Stringify for Func {
         match &self.op {
             Operator::Function(fid) => {
                 let func_name = fetcher.fetch_func_name(fid);
+                let func_name = match opts.for_file {
+                    Some(func_prefix) => get_file_func_name(&func_name, func_prefix),
+                    None => func_name,
+                };
                 let args_str =
                     args.iter()
                         .enumerate()
@@ -65,7 +163,7 @@ impl Stringify for Func {
                             if idx > 0 {
                                 prev.push_str(", ");
                             }
-                            let arg_str = arg.unparse(fetcher, curr_sheet);
+                            let arg_str = arg.unparse_with(fetcher, opts);
                             prev.push_str(&arg_str);
                             prev
                         });
@@ -74,23 +172,29 @@ impl Stringify for Func {
             Operator::Infix(op) => {
                 format!(
                     "{} {} {}",
-                    args.get(0).unwrap().unparse(fetcher, curr_sheet),
-                    op.unparse(fetcher, curr_sheet),
-                    args.get(1).unwrap().unparse(fetcher, curr_sheet),
+                    args.get(0).unwrap().unparse_with(fetcher, opts),
+                    op.unparse_with(fetcher, opts),
+                    args.get(1).unwrap().unparse_with(fetcher, opts),
+                )
+            }
+            Operator::Postfix(PostfixOperator::Spill) if opts.for_file.is_some() => {
+                format!(
+                    "_xlfn.ANCHORARRAY({})",
+                    args.first().unwrap().unparse_with(fetcher, opts),
                 )
             }
             Operator::Postfix(op) => {
                 format!(
                     "{}{}",
-                    args.get(0).unwrap().unparse(fetcher, curr_sheet),
-                    op.unparse(fetcher, curr_sheet),
+                    args.get(0).unwrap().unparse_with(fetcher, opts),
+                    op.unparse_with(fetcher, opts),
                 )
             }
             Operator::Prefix(op) => {
                 format!(
                     "{}{}",
-                    op.unparse(fetcher, curr_sheet),
-                    args.get(0).unwrap().unparse(fetcher, curr_sheet),
+                    op.unparse_with(fetcher, opts),
+                    args.get(0).unwrap().unparse_with(fetcher, opts),
                 )
             }
             Operator::Comma => {
@@ -101,80 +205,133 @@ impl Stringify for Func {
                             if idx > 0 {
                                 prev.push_str(", ");
                             }
-                            let arg_str = arg.unparse(fetcher, curr_sheet);
+                            let arg_str = arg.unparse_with(fetcher, opts);
                             prev.push_str(&arg_str);
                             prev
                         });
                 format!("({})", args_str)
             }
+            Operator::Call => {
+                let callee = args.first().unwrap().unparse_with(fetcher, opts);
+                let args_str = args.iter().skip(1).enumerate().fold(
+                    String::from(""),
+                    |mut prev, (idx, arg)| {
+                        if idx > 0 {
+                            prev.push_str(", ");
+                        }
+                        let arg_str = arg.unparse_with(fetcher, opts);
+                        prev.push_str(&arg_str);
+                        prev
+                    },
+                );
+                format!("{}({})", callee, args_str)
+            }
         }
     }
 }
 
 impl Stringify for CellReference {
-    fn unparse<T>(&self, fetcher: &mut T, curr_sheet: SheetId) -> String
+    fn unparse_with<T>(&self, fetcher: &mut T, opts: &UnparseOptions) -> String
     where
         T: NameFetcherTrait,
     {
         match self {
-            CellReference::Mut(mutref) => mutref.unparse(fetcher, curr_sheet),
-            CellReference::UnMut(unmut_ref) => unmut_ref.unparse(fetcher, curr_sheet),
-            CellReference::Name(nid) => fetcher.fetch_defined_name(nid),
-            CellReference::Ext(ext_ref) => ext_ref.unparse(fetcher, curr_sheet),
+            CellReference::Mut(mutref) => mutref.unparse_with(fetcher, opts),
+            CellReference::UnMut(unmut_ref) => unmut_ref.unparse_with(fetcher, opts),
+            CellReference::Name(nid) => {
+                let name = fetcher.fetch_defined_name(nid);
+                match fetcher.fetch_name_scope(nid) {
+                    Some(sheet_id) if sheet_id != opts.curr_sheet => {
+                        let sheet = fetcher.fetch_sheet_name(&sheet_id);
+                        format!("{}!{}", quote_sheet_prefix(&sheet), name)
+                    }
+                    _ => name,
+                }
+            }
+            CellReference::Ext(ext_ref) => ext_ref.unparse_with(fetcher, opts),
+            CellReference::Table(table_ref) => table_ref.unparse_with(fetcher, opts),
         }
     }
 }
 
+impl Stringify for TableReference {
+    fn unparse_with<T>(&self, fetcher: &mut T, _: &UnparseOptions) -> String
+    where
+        T: NameFetcherTrait,
+    {
+        let name = fetcher.fetch_table_name(&self.table_id);
+        let mut items = self
+            .specifiers
+            .iter()
+            .map(|s| format!("[{}]", s.get_specifier_str()))
+            .collect::<Vec<_>>();
+        if let Some((start, end)) = &self.columns {
+            if start == end {
+                items.push(format!("[{}]", escape_table_column(start)));
+            } else {
+                items.push(format!(
+                    "[{}]:[{}]",
+                    escape_table_column(start),
+                    escape_table_column(end)
+                ));
+            }
+        }
+        // A single item is written without the inner brackets if possible,
+        // like `Table1[Col1]` or `Table1[#Totals]`.
+        let inner = match (self.specifiers.as_slice(), &self.columns) {
+            ([], None) => String::new(),
+            ([s], None) => s.get_specifier_str().to_string(),
+            ([], Some((start, end))) if start == end && is_plain_table_column(start) => {
+                start.clone()
+            }
+            _ => items.join(","),
+        };
+        format!("{}[{}]", name, inner)
+    }
+}
+
+fn is_plain_table_column(column: &str) -> bool {
+    column
+        .chars()
+        .all(|c| c.is_alphanumeric() || c == '_' || c == '.')
+}
+
+fn escape_table_column(column: &str) -> String {
+    let mut result = String::new();
+    column.chars().for_each(|c| {
+        if matches!(c, '[' | ']' | '#' | '\'') {
+            result.push('\'');
+        }
+        result.push(c);
+    });
+    result
+}
+
 impl Stringify for CubeDisplay {
-    fn unparse<T>(&self, fetcher: &mut T, _: SheetId) -> String
+    fn unparse_with<T>(&self, fetcher: &mut T, opts: &UnparseOptions) -> String
     where
         T: NameFetcherTrait,
     {
         let cube = fetcher.fetch_cube(&self.cube_id);
         let from_sheet = fetcher.fetch_sheet_name(&cube.from_sheet);
         let to_sheet = fetcher.fetch_sheet_name(&cube.to_sheet);
-        let prefix = format!("{}:{}", from_sheet, to_sheet);
-        let RefAbs {
-            start_row,
-            start_col,
-            end_row,
-            end_col,
-        } = self.ref_abs;
-        let cross_str = match cube.cross {
-            CubeCross::Single(row, col) => {
-                let row_str = get_row_string(start_row, row);
-                let col_str = get_col_string(start_col, col);
-                format!("{}{}", col_str, row_str)
-            }
-            CubeCross::RowRange(start, end) => {
-                let start_str = get_row_string(start_row, start);
-                let end_str = get_col_string(end_row, end);
-                format!("{}:{}", start_str, end_str)
-            }
-            CubeCross::ColRange(start, end) => {
-                let start_str = get_row_string(start_col, start);
-                let end_str = get_col_string(end_col, end);
-                format!("{}:{}", start_str, end_str)
-            }
-            CubeCross::AddrRange(start, end) => {
-                let sr = get_row_string(start_row, start.row);
-                let sc = get_col_string(start_col, start.col);
-                let er = get_row_string(end_row, end.row);
-                let ec = get_col_string(end_col, end.col);
-                format!("{}{}:{}{}", sc, sr, ec, er)
-            }
-        };
+        let prefix = quote_sheet_prefix(&format!("{}:{}", from_sheet, to_sheet));
+        let cross_str = get_cross_string(&cube.cross, &self.ref_abs, opts);
         format!("{}!{}", prefix, cross_str)
     }
 }
 
 impl Stringify for ExtRefDisplay {
-    fn unparse<T>(&self, fetcher: &mut T, _: SheetId) -> String
+    fn unparse_with<T>(&self, fetcher: &mut T, opts: &UnparseOptions) -> String
     where
         T: NameFetcherTrait,
     {
         let ext_ref = fetcher.fetch_ext_ref(&self.ext_ref_id);
-        let workbook_name = fetcher.fetch_book_name(&ext_ref.ext_book);
+        // The books are referred to by their indexes in files.
+        let workbook_name = match opts.for_file {
+            Some(_) => fetcher.fetch_book_idx(&ext_ref.ext_book).to_string(),
+            None => fetcher.fetch_book_name(&ext_ref.ext_book),
+        };
         let sheet = {
             let to_sheet = fetcher.fetch_sheet_name(&ext_ref.to_sheet);
             match ext_ref.from_sheet {
@@ -185,41 +342,77 @@ impl Stringify for ExtRefDisplay {
                 None => to_sheet,
             }
         };
-        let RefAbs {
-            start_row,
-            start_col,
-            end_row,
-            end_col,
-        } = self.ref_abs;
-        let cross_str = match ext_ref.cross {
-            CubeCross::Single(row, col) => {
-                let row_str = get_row_string(start_row, row);
-                let col_str = get_col_string(start_col, col);
-                format!("{}{}", col_str, row_str)
-            }
-            CubeCross::RowRange(start, end) => {
-                let start_str = get_row_string(start_row, start);
-                let end_str = get_col_string(end_row, end);
-                format!("{}:{}", start_str, end_str)
-            }
-            CubeCross::ColRange(start, end) => {
-                let start_str = get_row_string(start_col, start);
-                let end_str = get_col_string(end_col, end);
-                format!("{}:{}", start_str, end_str)
-            }
-            CubeCross::AddrRange(start, end) => {
-                let sr = get_row_string(start_row, start.row);
-                let sc = get_col_string(start_col, start.col);
-                let er = get_row_string(end_row, end.row);
-                let ec = get_col_string(end_col, end.col);
-                format!("{}{}:{}{}", sc, sr, ec, er)
-            }
-        };
-        format!("[{}]{}!{}", workbook_name, sheet, cross_str)
+        let cross_str = get_cross_string(&ext_ref.cross, &self.ref_abs, opts);
+        let prefix = quote_sheet_prefix(&format!("[{}]{}", workbook_name, sheet));
+        format!("{}!{}", prefix, cross_str)
     }
 }
 
-fn get_row_string(abs: bool, idx: usize) -> String {
+fn get_cross_string(cross: &CubeCross, ref_abs: &RefAbs, opts: &UnparseOptions) -> String {
+    let RefAbs {
+        start_row,
+        start_col,
+        end_row,
+        end_col,
+    } = *ref_abs;
+    match cross {
+        CubeCross::Single(row, col) => get_cell_string((start_row, *row), (start_col, *col), opts),
+        CubeCross::RowRange(start, end) => {
+            let start_str = get_row_string(start_row, *start, opts);
+            let end_str = get_row_string(end_row, *end, opts);
+            get_area_string(start_str, end_str, opts)
+        }
+        CubeCross::ColRange(start, end) => {
+            let start_str = get_col_string(start_col, *start, opts);
+            let end_str = get_col_string(end_col, *end, opts);
+            get_area_string(start_str, end_str, opts)
+        }
+        CubeCross::AddrRange(start, end) => {
+            let start_str = get_cell_string((start_row, start.row), (start_col, start.col), opts);
+            let end_str = get_cell_string((end_row, end.row), (end_col, end.col), opts);
+            get_area_string(start_str, end_str, opts)
+        }
+    }
+}
+
+/// Quote the prefix of a reference, like `Sheet1`, `Sheet1:Sheet3` or
+/// `[Book1]Sheet1`, if a sheet or book name in it has spaces or other
+/// characters a reference can't have, or looks like a cell reference.
+/// The apostrophes in the names are doubled.
+pub fn quote_sheet_prefix(prefix: &str) -> String {
+    let (book, sheets) = match prefix.strip_prefix('[').and_then(|p| p.split_once(']')) {
+        Some((book, sheets)) => (Some(book), sheets),
+        None => (None, prefix),
+    };
+    // The books are written as their indexes in files, like `[1]Sheet1`.
+    let needs_quote = matches!(book, Some(b) if !b.chars().all(is_plain_name_char))
+        || sheets.split(':').any(|s| !is_plain_sheet_name(s));
+    if needs_quote {
+        format!("'{}'", prefix.replace('\'', "''"))
+    } else {
+        prefix.to_string()
+    }
+}
+
+fn is_plain_sheet_name(name: &str) -> bool {
+    lazy_static! {
+        static ref CELL_REGEX: Regex =
+            Regex::new(r#"^([A-Za-z]{1,3}[0-9]+|[Rr][0-9]*([Cc][0-9]*)?|[Cc][0-9]*)$"#).unwrap();
+    }
+    !name.is_empty()
+        && !name.starts_with(|c: char| c.is_ascii_digit())
+        && name.chars().all(is_plain_name_char)
+        && !CELL_REGEX.is_match(name)
+}
+
+fn is_plain_name_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_' || c == '.'
+}
+
+fn get_row_string(abs: bool, idx: usize, opts: &UnparseOptions) -> String {
+    if let Some((row, _)) = opts.r1c1_anchor {
+        return get_r1c1_string('R', abs, idx, row);
+    }
     let r = (idx + 1).to_string();
     if abs {
         format!("${}", r)
@@ -228,7 +421,10 @@ fn get_row_string(abs: bool, idx: usize) -> String {
     }
 }
 
-fn get_col_string(abs: bool, idx: usize) -> String {
+fn get_col_string(abs: bool, idx: usize, opts: &UnparseOptions) -> String {
+    if let Some((_, col)) = opts.r1c1_anchor {
+        return get_r1c1_string('C', abs, idx, col);
+    }
     let c = index_to_column_label(idx);
     if abs {
         format!("${}", c)
@@ -237,17 +433,48 @@ fn get_col_string(abs: bool, idx: usize) -> String {
     }
 }
 
+// `R` alone is the anchor row, `R[n]` is relative to it and `Rn` is
+// absolute. So are the columns.
+fn get_r1c1_string(prefix: char, abs: bool, idx: usize, anchor: usize) -> String {
+    if abs {
+        format!("{}{}", prefix, idx + 1)
+    } else if idx == anchor {
+        prefix.to_string()
+    } else {
+        format!("{}[{}]", prefix, idx as i64 - anchor as i64)
+    }
+}
+
+fn get_cell_string(row: (bool, usize), col: (bool, usize), opts: &UnparseOptions) -> String {
+    let row_str = get_row_string(row.0, row.1, opts);
+    let col_str = get_col_string(col.0, col.1, opts);
+    match opts.r1c1_anchor {
+        Some(_) => format!("{}{}", row_str, col_str),
+        None => format!("{}{}", col_str, row_str),
+    }
+}
+
+// An area from a cell, a row or a column to the same one is written as it
+// in the R1C1 notation, like `R1` for `1:1`.
+fn get_area_string(start: String, end: String, opts: &UnparseOptions) -> String {
+    if opts.r1c1_anchor.is_some() && start == end {
+        start
+    } else {
+        format!("{}:{}", start, end)
+    }
+}
+
 impl Stringify for RangeDisplay {
-    fn unparse<T>(&self, fetcher: &mut T, curr_sheet: SheetId) -> String
+    fn unparse_with<T>(&self, fetcher: &mut T, opts: &UnparseOptions) -> String
     where
         T: NameFetcherTrait,
     {
-        let prefix = if self.sheet_id == curr_sheet {
+        let prefix = if self.sheet_id == opts.curr_sheet {
             String::new()
         } else {
             let sheet_name = fetcher.fetch_sheet_name(&self.sheet_id);
             if sheet_name.len() > 0 {
-                format!("{}!", sheet_name)
+                format!("{}!", quote_sheet_prefix(&sheet_name))
             } else {
                 String::from("")
             }
@@ -271,33 +498,31 @@ impl Stringify for RangeDisplay {
                 let mut get_normal_cell_str =
                     |sheet: &SheetId, id: NormalCellId, row_abs: bool, col_abs: bool| -> String {
                         let (row, col) = fetcher.fetch_cell_idx(sheet, &CellId::NormalCell(id));
-                        let row_str = get_row_string(row_abs, row);
-                        let col_str = get_col_string(col_abs, col);
-                        format!("{}{}", col_str, row_str)
+                        get_cell_string((row_abs, row), (col_abs, col), opts)
                     };
                 match normal_range {
                     NormalRange::Single(normal_cell) => {
-                        get_normal_cell_str(&curr_sheet, normal_cell, start_row, start_col)
+                        get_normal_cell_str(&self.sheet_id, normal_cell, start_row, start_col)
                     }
                     NormalRange::RowRange(start, end) => {
-                        let start_idx = fetcher.fetch_row_idx(&curr_sheet, &start);
-                        let end_idx = fetcher.fetch_row_idx(&curr_sheet, &end);
-                        let start_str = get_row_string(start_row, start_idx);
-                        let end_str = get_row_string(end_row, end_idx);
-                        format!("{}:{}", start_str, end_str)
+                        let start_idx = fetcher.fetch_row_idx(&self.sheet_id, &start);
+                        let end_idx = fetcher.fetch_row_idx(&self.sheet_id, &end);
+                        let start_str = get_row_string(start_row, start_idx, opts);
+                        let end_str = get_row_string(end_row, end_idx, opts);
+                        get_area_string(start_str, end_str, opts)
                     }
                     NormalRange::ColRange(start, end) => {
-                        let start_idx = fetcher.fetch_col_idx(&curr_sheet, &start);
-                        let end_idx = fetcher.fetch_col_idx(&curr_sheet, &end);
-                        let start_str = get_col_string(start_row, start_idx);
-                        let end_str = get_col_string(end_row, end_idx);
-                        format!("{}:{}", start_str, end_str)
+                        let start_idx = fetcher.fetch_col_idx(&self.sheet_id, &start);
+                        let end_idx = fetcher.fetch_col_idx(&self.sheet_id, &end);
+                        let start_str = get_col_string(start_col, start_idx, opts);
+                        let end_str = get_col_string(end_col, end_idx, opts);
+                        get_area_string(start_str, end_str, opts)
                     }
                     NormalRange::AddrRange(start, end) => {
                         let start_str =
-                            get_normal_cell_str(&curr_sheet, start, start_row, start_col);
-                        let end_str = get_normal_cell_str(&curr_sheet, end, end_row, end_col);
-                        format!("{}:{}", start_str, end_str)
+                            get_normal_cell_str(&self.sheet_id, start, start_row, start_col);
+                        let end_str = get_normal_cell_str(&self.sheet_id, end, end_row, end_col);
+                        get_area_string(start_str, end_str, opts)
                     }
                 }
             }
@@ -305,19 +530,17 @@ impl Stringify for RangeDisplay {
                 let mut get_block_cell_str =
                     |sheet: &SheetId, id: BlockCellId, row_abs: bool, col_abs: bool| -> String {
                         let (row, col) = fetcher.fetch_cell_idx(sheet, &CellId::BlockCell(id));
-                        let row_str = get_row_string(row_abs, row);
-                        let col_str = get_col_string(col_abs, col);
-                        format!("{}{}", col_str, row_str)
+                        get_cell_string((row_abs, row), (col_abs, col), opts)
                     };
                 match block_range {
                     BlockRange::Single(block_cell_id) => {
-                        get_block_cell_str(&curr_sheet, block_cell_id, start_row, start_col)
+                        get_block_cell_str(&self.sheet_id, block_cell_id, start_row, start_col)
                     }
                     BlockRange::AddrRange(start, end) => {
                         let start_str =
-                            get_block_cell_str(&curr_sheet, start, start_row, start_col);
-                        let end_str = get_block_cell_str(&curr_sheet, end, end_row, end_col);
-                        format!("{}:{}", start_str, end_str)
+                            get_block_cell_str(&self.sheet_id, start, start_row, start_col);
+                        let end_str = get_block_cell_str(&self.sheet_id, end, end_row, end_col);
+                        get_area_string(start_str, end_str, opts)
                     }
                 }
             }
@@ -327,7 +550,7 @@ impl Stringify for RangeDisplay {
 }
 
 impl Stringify for Value {
-    fn unparse<T>(&self, fetcher: &mut T, curr_sheet: SheetId) -> String
+    fn unparse_with<T>(&self, fetcher: &mut T, opts: &UnparseOptions) -> String
     where
         T: NameFetcherTrait,
     {
@@ -340,14 +563,14 @@ impl Stringify for Value {
                 false => "FALSE",
             }
             .to_string(),
-            Value::Error(e) => e.unparse(fetcher, curr_sheet),
+            Value::Error(e) => e.unparse_with(fetcher, opts),
             Value::Date(_) => todo!(),
         }
     }
 }
 
 impl Stringify for Error {
-    fn unparse<T>(&self, _: &mut T, _: SheetId) -> String
+    fn unparse_with<T>(&self, _: &mut T, _: &UnparseOptions) -> String
     where
         T: NameFetcherTrait,
     {
@@ -356,7 +579,7 @@ impl Stringify for Error {
 }
 
 impl Stringify for InfixOperator {
-    fn unparse<T>(&self, _: &mut T, _: SheetId) -> String
+    fn unparse_with<T>(&self, _: &mut T, _: &UnparseOptions) -> String
     where
         T: NameFetcherTrait,
     {
@@ -380,7 +603,7 @@ impl Stringify for InfixOperator {
 }
 
 impl Stringify for PrefixOperator {
-    fn unparse<T>(&self, _: &mut T, _: SheetId) -> String
+    fn unparse_with<T>(&self, _: &mut T, _: &UnparseOptions) -> String
     where
         T: NameFetcherTrait,
     {
@@ -392,19 +615,20 @@ impl Stringify for PrefixOperator {
 }
 
 impl Stringify for PostfixOperator {
-    fn unparse<T>(&self, _: &mut T, _: SheetId) -> String
+    fn unparse_with<T>(&self, _: &mut T, _: &UnparseOptions) -> String
     where
         T: NameFetcherTrait,
     {
         match self {
             PostfixOperator::Percent => String::from("%"),
+            PostfixOperator::Spill => String::from("#"),
         }
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::unparse;
+    use super::{quote_sheet_prefix, unparse};
     use crate::context::Context;
     use crate::test_utils::{TestIdFetcher, TestVertexFetcher};
     use crate::Parser;
@@ -442,4 +666,17 @@ mod tests {
         let a = unparse(&node, &mut id_fetcher, 0);
         assert_eq!(a, "1 * (3 - 2)")
     }
+
+    #[test]
+    fn quote_sheet_prefix_test() {
+        assert_eq!(quote_sheet_prefix("Sheet1"), "Sheet1");
+        assert_eq!(quote_sheet_prefix("My Sheet"), "'My Sheet'");
+        assert_eq!(quote_sheet_prefix("It's"), "'It''s'");
+        assert_eq!(quote_sheet_prefix("2024"), "'2024'");
+        assert_eq!(quote_sheet_prefix("A1"), "'A1'");
+        assert_eq!(quote_sheet_prefix("R1C1"), "'R1C1'");
+        assert_eq!(quote_sheet_prefix("Sheet1:My Sheet"), "'Sheet1:My Sheet'");
+        assert_eq!(quote_sheet_prefix("[1]Sheet1"), "[1]Sheet1");
+        assert_eq!(quote_sheet_prefix("[1]Sheet 1"), "'[1]Sheet 1'");
+    }
 }
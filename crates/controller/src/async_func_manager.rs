@@ -1,29 +1,49 @@
+use chrono::{DateTime, Utc};
 use logisheets_base::{
     async_func::{AsyncCalcResult, Task},
     CellId,
 };
+use std::collections::{HashMap, HashSet};
+use std::time::Duration;
 
 use crate::SheetId;
 
 #[derive(Default)]
 pub struct AsyncFuncManager {
-    pub values: std::collections::HashMap<Task, AsyncCalcResult>,
+    // The results and the time they are received.
+    pub values: HashMap<Task, (AsyncCalcResult, DateTime<Utc>)>,
     pub queue: Vec<Task>,
     pub dirties: Vec<(SheetId, CellId)>,
+    // The cells that read the results, which are calculated again when the
+    // results expire.
+    pub readers: HashMap<Task, HashSet<(SheetId, CellId)>>,
+    // Use the results even if they are expired. It is set when calculating
+    // on a copy only to show the values.
+    pub keep_expired: bool,
 }
 
 impl AsyncFuncManager {
     pub fn add_value(&mut self, t: Task, v: AsyncCalcResult) {
-        self.values.insert(t, v);
+        self.values.insert(t, (v, Utc::now()));
     }
 
+    /// Get the result of the task, or commit it if there is no result or the
+    /// result is older than `ttl` and `keep_expired` is not set.
     pub fn query_or_commit(
         &mut self,
         t: Task,
         sheet_id: SheetId,
         cell_id: CellId,
+        ttl: Option<Duration>,
     ) -> Option<AsyncCalcResult> {
-        if let Some(res) = self.values.get(&t) {
+        if !self.keep_expired && self.is_expired(&t, ttl) {
+            self.values.remove(&t);
+        }
+        self.readers
+            .entry(t.clone())
+            .or_default()
+            .insert((sheet_id, cell_id));
+        if let Some((res, _)) = self.values.get(&t) {
             Some(res.clone())
         } else {
             self.dirties.push((sheet_id, cell_id));
@@ -32,6 +52,36 @@ impl AsyncFuncManager {
         }
     }
 
+    /// Remove the expired results and return the cells reading them, so that
+    /// they can be calculated again without waiting for other edits.
+    pub fn take_expired<F>(&mut self, get_ttl: F) -> Vec<(SheetId, CellId)>
+    where
+        F: Fn(&Task) -> Option<Duration>,
+    {
+        let expired = self
+            .values
+            .keys()
+            .filter(|t| self.is_expired(t, get_ttl(t)))
+            .cloned()
+            .collect::<Vec<_>>();
+        expired
+            .into_iter()
+            .flat_map(|t| {
+                self.values.remove(&t);
+                self.readers.remove(&t).unwrap_or_default()
+            })
+            .collect()
+    }
+
+    fn is_expired(&self, t: &Task, ttl: Option<Duration>) -> bool {
+        match (self.values.get(t), ttl) {
+            (Some((_, received)), Some(ttl)) => {
+                matches!((Utc::now() - *received).to_std(), Ok(d) if d > ttl)
+            }
+            _ => false,
+        }
+    }
+
     pub fn get_calc_tasks(&mut self) -> (Vec<Task>, Vec<(SheetId, CellId)>) {
         let mut empty_tasks = Vec::<Task>::new();
         let mut empty_dirty = Vec::<(SheetId, CellId)>::new();
@@ -40,3 +90,71 @@ impl AsyncFuncManager {
         (empty_tasks, empty_dirty)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::controller::Controller;
+    use crate::test_utils::{get_value, input};
+    use logisheets_base::CellValue;
+
+    #[test]
+    fn async_func_test() {
+        use crate::{AsyncErr, AsyncReturnType};
+        use logisheets_base::Error;
+        let mut wb = Controller::default();
+        wb.register_async_func("fetchnum", AsyncReturnType::Number, None);
+        wb.register_async_func("fetcharray", AsyncReturnType::Matrix, None);
+        input(&mut wb, 0, 0, 0, "1");
+        input(&mut wb, 0, 0, 1, "a");
+        let effect = input(&mut wb, 0, 1, 0, "=FETCHNUM(A1:B1)").unwrap();
+        assert_eq!(effect.async_tasks.len(), 1);
+        assert_eq!(effect.async_tasks[0].args, vec![String::from("{1,\"a\"}")]);
+        assert!(matches!(
+            get_value(&mut wb, 0, 1, 0),
+            CellValue::Error(Error::GettingData)
+        ));
+        wb.handle_async_calc_results(
+            effect.async_tasks,
+            vec![Err(AsyncErr::TimeOut)],
+            effect.dirtys,
+        );
+        assert!(matches!(
+            get_value(&mut wb, 0, 1, 0),
+            CellValue::Error(Error::NA)
+        ));
+
+        let effect = input(&mut wb, 0, 2, 0, "=FETCHARRAY()").unwrap();
+        wb.handle_async_calc_results(
+            effect.async_tasks,
+            vec![Ok(String::from("{1,2;3,4}"))],
+            effect.dirtys,
+        );
+        assert!(matches!(get_value(&mut wb, 0, 3, 1), CellValue::Number(n) if n == 4.));
+
+        wb.register_async_func(
+            "fetchnow",
+            AsyncReturnType::Number,
+            Some(std::time::Duration::from_millis(1)),
+        );
+        let effect = input(&mut wb, 0, 4, 0, "=FETCHNOW()").unwrap();
+        wb.handle_async_calc_results(
+            effect.async_tasks,
+            vec![Ok(String::from("1"))],
+            effect.dirtys,
+        );
+        assert!(matches!(get_value(&mut wb, 0, 4, 0), CellValue::Number(n) if n == 1.));
+        std::thread::sleep(std::time::Duration::from_millis(5));
+        let effect = wb.recalc_expired_async_funcs().unwrap();
+        assert_eq!(effect.async_tasks.len(), 1);
+        assert_eq!(effect.async_tasks[0].async_func, "FETCHNOW");
+        assert!(matches!(
+            get_value(&mut wb, 0, 4, 0),
+            CellValue::Error(Error::GettingData)
+        ));
+        assert!(wb
+            .settings
+            .func_registry
+            .get_async("BaiduHotSearch")
+            .is_some());
+    }
+}
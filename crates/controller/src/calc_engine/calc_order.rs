@@ -82,7 +82,8 @@ where
                                 None => break,
                             }
                         }
-                        if curr_scc.len() > 1 {
+                        // A formula referring to itself is a cycle too.
+                        if curr_scc.len() > 1 || rdeps_fetcher(&u).contains(&u) {
                             curr_scc.iter().for_each(|s| {
                                 scc_map.insert(s.clone(), scc_id);
                             });
@@ -103,9 +104,11 @@ where
         }
         calc_order_desc
     });
+    // The units are found in reverse topological order, and a later DFS tree
+    // can depend on an earlier one but never the other way round.
     orders.fold(VecDeque::<CalcUnit<V>>::new(), |mut prev, c| {
-        c.into_iter().rev().for_each(|e| {
-            prev.push_back(e);
+        c.into_iter().for_each(|e| {
+            prev.push_front(e);
         });
         prev
     })
@@ -263,6 +266,37 @@ mod tests {
         }
     }
 
+    #[test]
+    fn multiple_dirty_nodes_test() {
+        //   1
+        //   |
+        //   2
+        //   |
+        //   3
+        let mut graph = Graph::<u32>::new();
+        graph.add_dep(1, 2);
+        graph.add_dep(2, 3);
+        let rdeps_fetcher = |r: &u32| -> Vec<u32> {
+            match graph.get_rdeps(r) {
+                Some(s) => s.clone().into_iter().collect_vec(),
+                None => vec![],
+            }
+        };
+        // Every HashSet has its own random state, so the dirty nodes are
+        // visited in different orders.
+        (0..20).for_each(|_| {
+            let dirty_nodes = vec![1, 2, 3].into_iter().collect::<HashSet<u32>>();
+            let order = calc_order(&rdeps_fetcher, dirty_nodes)
+                .into_iter()
+                .map(|u| match u {
+                    CalcUnit::Cycle(_) => panic!(),
+                    CalcUnit::Node(n) => n,
+                })
+                .collect_vec();
+            assert_eq!(order, vec![3, 2, 1]);
+        });
+    }
+
     #[test]
     fn node_order_test() {
         //    1    5
@@ -1,7 +1,9 @@
 use chrono::DateTime;
 use chrono::FixedOffset;
 use logisheets_base::cube_value::CubeValue;
+use logisheets_base::datetime::get_serial_num_by_datetime;
 use logisheets_base::matrix_value::MatrixValue;
+use logisheets_base::rst_to_plain_text;
 use logisheets_base::Addr;
 use logisheets_base::CellValue;
 use logisheets_base::SheetId;
@@ -13,8 +15,21 @@ pub enum CalcVertex {
     Value(CalcValue),
     Reference(CalcReference),
     Union(Vec<Box<CalcVertex>>), // comma operator
+    Lambda(Lambda),
 }
 
+/// A function made by LAMBDA. It keeps the names bound where it is made.
+#[derive(Debug, Clone)]
+pub struct Lambda {
+    pub params: Vec<String>,
+    pub body: ast::Node,
+    pub scope: Scope,
+}
+
+/// The names bound by LET and the parameters of LAMBDA. The inner one is
+/// at the end.
+pub type Scope = Vec<(String, CalcVertex)>;
+
 impl CalcVertex {
     pub fn from_error(e: ast::Error) -> Self {
         CalcVertex::Value(CalcValue::Scalar(Value::Error(e)))
@@ -45,30 +60,6 @@ pub enum CalcValue {
     Union(Vec<Box<CalcValue>>), // comma operator
 }
 
-impl CalcValue {
-    pub fn to_async_arg(self) -> String {
-        match self {
-            CalcValue::Scalar(v) => match v {
-                Value::Blank => String::from(""),
-                Value::Number(n) => n.to_string(),
-                Value::Text(t) => t,
-                Value::Boolean(b) => {
-                    if b {
-                        String::from("TRUE")
-                    } else {
-                        String::from("FALSE")
-                    }
-                }
-                Value::Error(e) => e.get_err_str().to_string(),
-                Value::Date(_) => todo!(),
-            },
-            CalcValue::Range(_) => todo!(),
-            CalcValue::Cube(_) => todo!(),
-            CalcValue::Union(_) => todo!(),
-        }
-    }
-}
-
 #[derive(Debug, Clone)]
 pub enum Value {
     Blank,
@@ -97,18 +88,22 @@ impl Value {
         }
     }
 
-    pub fn from_cell_value<F>(v: CellValue, fetcher: &F) -> Self
+    /// Dates are converted to the serial numbers in the date system of the
+    /// workbook.
+    pub fn from_cell_value<F>(v: CellValue, date1904: bool, fetcher: &F) -> Self
     where
         F: Fn(&TextId) -> Option<String>,
     {
         match v {
             CellValue::Blank => Value::Blank,
             CellValue::Boolean(b) => Value::Boolean(b),
-            CellValue::Date(_) => todo!(),
+            CellValue::Date(d) => {
+                Value::Number(get_serial_num_by_datetime(&d.naive_local(), date1904))
+            }
             CellValue::Error(e) => Value::Error(ast::Error::from_err_str(&e.to_string())),
             CellValue::String(tid) => Value::Text(fetcher(&tid).unwrap_or(String::from(""))),
             CellValue::Number(f) => Value::Number(f),
-            CellValue::InlineStr(_) => todo!(),
+            CellValue::InlineStr(rst) => Value::Text(rst_to_plain_text(&rst)),
             CellValue::FormulaStr(s) => Value::Text(s),
         }
     }
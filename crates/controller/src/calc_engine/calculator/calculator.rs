@@ -1,38 +1,183 @@
+use std::cell::{Cell, RefCell};
+
 use logisheets_parser::ast;
 
-use super::calc_vertex::{CalcValue, CalcVertex, Value};
+use super::calc_vertex::{CalcReference, CalcValue, CalcVertex, Lambda, Reference, Scope, Value};
 
 use super::super::connector::Connector;
 use super::funcs;
 use super::infix;
 
+// Defined names and LAMBDAs can refer to themselves. Stop them before the
+// stack overflows.
+const MAX_CALL_DEPTH: usize = 256;
+
+// The values of larger references are not fetched when tracing.
+const MAX_TRACED_CELLS: usize = 1000;
+
+thread_local! {
+    static CALL_DEPTH: Cell<usize> = const { Cell::new(0) };
+    // The traces of the expressions calculated so far, grouped by the
+    // expressions containing them. It is `None` when not tracing.
+    static TRACES: RefCell<Option<Vec<Vec<CalcTrace>>>> = const { RefCell::new(None) };
+    // The number of the volatile functions called so far.
+    static VOLATILE_CALLS: Cell<usize> = const { Cell::new(0) };
+}
+
+/// The result of an expression and the expressions calculated for it,
+/// including the formulas of the defined names and the bodies of the
+/// LAMBDAs called.
+#[derive(Debug, Clone)]
+pub struct CalcTrace {
+    pub node: ast::Node,
+    /// The reference the expression is resolved to.
+    pub reference: Option<CalcReference>,
+    /// The value of the expression. A LAMBDA has no value, and neither has
+    /// a reference with more than `MAX_TRACED_CELLS` cells.
+    pub value: Option<CalcValue>,
+    /// Whether volatile functions are called for the value, which may be
+    /// different from the one calculated before.
+    pub volatile: bool,
+    pub children: Vec<CalcTrace>,
+}
+
 pub fn calc<C>(ast: &ast::Node, fetcher: &mut C) -> CalcValue
 where
     C: Connector,
 {
-    let v = calc_node(ast, fetcher);
+    let v = calc_node(ast, &vec![], fetcher);
     fetcher.get_calc_value(v)
 }
 
-fn calc_node<C>(node: &ast::Node, fetcher: &mut C) -> CalcVertex
+/// Calculate the formula and record the value of every sub-expression.
+pub fn trace<C>(ast: &ast::Node, fetcher: &mut C) -> CalcTrace
+where
+    C: Connector,
+{
+    TRACES.with(|t| *t.borrow_mut() = Some(vec![vec![]]));
+    calc_node(ast, &vec![], fetcher);
+    let traces = TRACES.with(|t| t.borrow_mut().take());
+    traces
+        .and_then(|mut t| t.pop())
+        .and_then(|mut t| t.pop())
+        .unwrap()
+}
+
+/// Call the LAMBDA with the calculated arguments. The number of the arguments
+/// should be the same as its parameters.
+pub fn call_lambda<C>(lambda: &Lambda, args: Vec<CalcVertex>, fetcher: &mut C) -> CalcVertex
+where
+    C: Connector,
+{
+    if lambda.params.len() != args.len() {
+        return CalcVertex::from_error(ast::Error::Value);
+    }
+    let mut scope = lambda.scope.clone();
+    scope.extend(lambda.params.iter().cloned().zip(args));
+    calc_deeper(|| calc_node(&lambda.body, &scope, fetcher))
+}
+
+fn calc_node<C>(node: &ast::Node, scope: &Scope, fetcher: &mut C) -> CalcVertex
+where
+    C: Connector,
+{
+    let tracing = TRACES.with(|t| match t.borrow_mut().as_mut() {
+        Some(traces) => {
+            traces.push(vec![]);
+            true
+        }
+        None => false,
+    });
+    let volatile_calls = VOLATILE_CALLS.with(|c| c.get());
+    let result = calc_pure_node(node, scope, fetcher);
+    if !tracing {
+        return result;
+    }
+    let volatile = VOLATILE_CALLS.with(|c| c.get()) > volatile_calls;
+    let (reference, value) = match &result {
+        CalcVertex::Lambda(_) => (None, None),
+        CalcVertex::Reference(r) if is_large_reference(r) => (Some(r.clone()), None),
+        CalcVertex::Reference(r) => (
+            Some(r.clone()),
+            Some(fetcher.get_calc_value(result.clone())),
+        ),
+        _ => (None, Some(fetcher.get_calc_value(result.clone()))),
+    };
+    TRACES.with(|t| {
+        if let Some(traces) = t.borrow_mut().as_mut() {
+            let children = traces.pop().unwrap_or_default();
+            if let Some(parent) = traces.last_mut() {
+                parent.push(CalcTrace {
+                    node: node.clone(),
+                    reference,
+                    value,
+                    volatile,
+                    children,
+                });
+            }
+        }
+    });
+    result
+}
+
+fn is_large_reference(r: &CalcReference) -> bool {
+    match &r.reference {
+        Reference::Addr(_) => false,
+        Reference::ColumnRange(_) | Reference::RowRange(_) => true,
+        Reference::Range(start, end) => {
+            let rows = end.row.saturating_sub(start.row) + 1;
+            let cols = end.col.saturating_sub(start.col) + 1;
+            rows * cols > MAX_TRACED_CELLS
+        }
+    }
+}
+
+fn calc_pure_node<C>(node: &ast::Node, scope: &Scope, fetcher: &mut C) -> CalcVertex
 where
     C: Connector,
 {
     match &node.pure {
         ast::PureNode::Value(v) => CalcVertex::Value(CalcValue::Scalar(Value::from_ast_value(v))),
-        ast::PureNode::Func(f) => calc_func(f, fetcher),
+        ast::PureNode::Func(f) => calc_func(f, scope, fetcher),
+        ast::PureNode::Reference(ast::CellReference::Name(nid)) => {
+            match fetcher.get_defined_name(nid) {
+                Some(n) => calc_deeper(|| calc_node(&n, &vec![], fetcher)),
+                None => CalcVertex::from_error(ast::Error::Name),
+            }
+        }
         ast::PureNode::Reference(r) => fetcher.convert(r),
+        ast::PureNode::Local(name) => {
+            match scope
+                .iter()
+                .rev()
+                .find(|(n, _)| ast::is_same_local(n, name))
+            {
+                Some((_, v)) => v.clone(),
+                None => CalcVertex::from_error(ast::Error::Name),
+            }
+        }
     }
 }
 
-fn calc_func<C>(func: &ast::Func, fetcher: &mut C) -> CalcVertex
+fn calc_func<C>(func: &ast::Func, scope: &Scope, fetcher: &mut C) -> CalcVertex
 where
     C: Connector,
 {
+    // LET and LAMBDA bind names rather than calculate all their arguments.
+    if let ast::Operator::Function(fid) = &func.op {
+        if let Ok(name) = fetcher.get_func_name(fid) {
+            let name = name.to_uppercase();
+            match name.trim_start_matches("_XLFN.") {
+                "LET" => return calc_let(&func.args, scope, fetcher),
+                "LAMBDA" => return calc_lambda(&func.args, scope),
+                _ => {}
+            }
+        }
+    }
     let args = func
         .args
         .iter()
-        .map(|arg| calc_node(arg, fetcher))
+        .map(|arg| calc_node(arg, scope, fetcher))
         .collect::<Vec<_>>();
     let op = &func.op;
     match op {
@@ -52,7 +197,11 @@ where
             };
             infix::calc_infix(lhs, &op, rhs, fetcher)
         }
-        ast::Operator::Postfix(_) => {
+        // `A1#` is saved as `ANCHORARRAY(A1)` in the files.
+        ast::Operator::Postfix(ast::PostfixOperator::Spill) => {
+            funcs::function_calculate("ANCHORARRAY", args, fetcher)
+        }
+        ast::Operator::Postfix(ast::PostfixOperator::Percent) => {
             let mut iter = args.into_iter();
             let lhs = iter.next().unwrap();
             let rhs = CalcVertex::Value(CalcValue::Scalar(Value::Number(100_f64)));
@@ -62,10 +211,23 @@ where
         ast::Operator::Function(fid) => {
             let name = fetcher.get_func_name(fid);
             match name {
-                Ok(func) => funcs::function_calculate(&func, args, fetcher),
+                Ok(func) if funcs::is_volatile_func(&func) => {
+                    fetcher.add_volatile();
+                    VOLATILE_CALLS.with(|c| c.set(c.get() + 1));
+                    funcs::function_calculate(&func, args, fetcher)
+                }
+                Ok(func) => match get_user_func(&func, fetcher) {
+                    Some(callee) => call(callee, args, fetcher),
+                    None => funcs::function_calculate(&func, args, fetcher),
+                },
                 Err(_) => CalcVertex::from_error(ast::Error::Unspecified),
             }
         }
+        ast::Operator::Call => {
+            let mut iter = args.into_iter();
+            let callee = iter.next().unwrap();
+            call(callee, iter.collect(), fetcher)
+        }
         ast::Operator::Comma => {
             let mut new_args = Vec::new();
             args.into_iter().for_each(|cv| match cv {
@@ -78,3 +240,90 @@ where
         }
     }
 }
+
+// LET(name1, value1, [name2, value2, ...], calculation)
+fn calc_let<C>(args: &[ast::Node], scope: &Scope, fetcher: &mut C) -> CalcVertex
+where
+    C: Connector,
+{
+    let (calculation, bindings) = match args.split_last() {
+        Some(r) => r,
+        None => return CalcVertex::from_error(ast::Error::Value),
+    };
+    let bindings = bindings.chunks_exact(2);
+    if bindings.len() == 0 || !bindings.remainder().is_empty() {
+        return CalcVertex::from_error(ast::Error::Value);
+    }
+    let mut scope = scope.clone();
+    for binding in bindings {
+        let name = match &binding[0].pure {
+            ast::PureNode::Local(name) => name.clone(),
+            _ => return CalcVertex::from_error(ast::Error::Value),
+        };
+        let value = calc_node(&binding[1], &scope, fetcher);
+        scope.push((name, value));
+    }
+    calc_node(calculation, &scope, fetcher)
+}
+
+// LAMBDA([parameter1, parameter2, ...], calculation)
+fn calc_lambda(args: &[ast::Node], scope: &Scope) -> CalcVertex {
+    let (body, params) = match args.split_last() {
+        Some(r) => r,
+        None => return CalcVertex::from_error(ast::Error::Value),
+    };
+    let params = params
+        .iter()
+        .map(|p| match &p.pure {
+            ast::PureNode::Local(name) => Some(name.clone()),
+            _ => None,
+        })
+        .collect::<Option<Vec<_>>>();
+    match params {
+        Some(params) => CalcVertex::Lambda(Lambda {
+            params,
+            body: body.clone(),
+            scope: scope.clone(),
+        }),
+        None => CalcVertex::from_error(ast::Error::Value),
+    }
+}
+
+// A defined name holding a LAMBDA is called like a function. It does not
+// shadow the built-in and registered functions.
+fn get_user_func<C>(name: &str, fetcher: &mut C) -> Option<CalcVertex>
+where
+    C: Connector,
+{
+    if funcs::has_func(name, fetcher) {
+        return None;
+    }
+    let name_id = fetcher.get_name_id(name)?;
+    let node = fetcher.get_defined_name(&name_id)?;
+    Some(calc_deeper(|| calc_node(&node, &vec![], fetcher)))
+}
+
+fn call<C>(callee: CalcVertex, args: Vec<CalcVertex>, fetcher: &mut C) -> CalcVertex
+where
+    C: Connector,
+{
+    match callee {
+        CalcVertex::Lambda(lambda) => call_lambda(&lambda, args, fetcher),
+        CalcVertex::Value(CalcValue::Scalar(Value::Error(e))) => CalcVertex::from_error(e),
+        _ => CalcVertex::from_error(ast::Error::Value),
+    }
+}
+
+fn calc_deeper<F>(f: F) -> CalcVertex
+where
+    F: FnOnce() -> CalcVertex,
+{
+    let depth = CALL_DEPTH.with(|d| d.get());
+    if depth >= MAX_CALL_DEPTH {
+        return CalcVertex::from_error(ast::Error::Num);
+    }
+    CALL_DEPTH.with(|d| d.set(depth + 1));
+    let result = f();
+    CALL_DEPTH.with(|d| d.set(depth));
+    result
+}
@@ -0,0 +1,213 @@
+use super::condition::{get_condition, match_conditions, ConditionRange};
+use crate::calc_engine::connector::Connector;
+use logisheets_parser::ast;
+
+use super::{CalcVertex, Value};
+use crate::calc_engine::calculator::calc_vertex::{ColRange, Reference, RowRange};
+use logisheets_base::Addr;
+
+pub fn calc_sumif<C>(args: Vec<CalcVertex>, fetcher: &mut C) -> CalcVertex
+where
+    C: Connector,
+{
+    calc_if(args, fetcher, &sum)
+}
+
+pub fn calc_sumifs<C>(args: Vec<CalcVertex>, fetcher: &mut C) -> CalcVertex
+where
+    C: Connector,
+{
+    calc_ifs(args, fetcher, &sum)
+}
+
+pub fn calc_averageif<C>(args: Vec<CalcVertex>, fetcher: &mut C) -> CalcVertex
+where
+    C: Connector,
+{
+    calc_if(args, fetcher, &average)
+}
+
+pub fn calc_averageifs<C>(args: Vec<CalcVertex>, fetcher: &mut C) -> CalcVertex
+where
+    C: Connector,
+{
+    calc_ifs(args, fetcher, &average)
+}
+
+pub fn calc_maxifs<C>(args: Vec<CalcVertex>, fetcher: &mut C) -> CalcVertex
+where
+    C: Connector,
+{
+    calc_ifs(args, fetcher, &|nums| {
+        Ok(nums
+            .into_iter()
+            .fold(None, |prev: Option<f64>, n| match prev {
+                Some(p) if p >= n => Some(p),
+                _ => Some(n),
+            })
+            .unwrap_or(0.))
+    })
+}
+
+pub fn calc_minifs<C>(args: Vec<CalcVertex>, fetcher: &mut C) -> CalcVertex
+where
+    C: Connector,
+{
+    calc_ifs(args, fetcher, &|nums| {
+        Ok(nums
+            .into_iter()
+            .fold(None, |prev: Option<f64>, n| match prev {
+                Some(p) if p <= n => Some(p),
+                _ => Some(n),
+            })
+            .unwrap_or(0.))
+    })
+}
+
+fn sum(nums: Vec<f64>) -> Result<f64, ast::Error> {
+    Ok(nums.into_iter().sum())
+}
+
+fn average(nums: Vec<f64>) -> Result<f64, ast::Error> {
+    if nums.is_empty() {
+        return Err(ast::Error::Div0);
+    }
+    let cnt = nums.len() as f64;
+    Ok(nums.into_iter().sum::<f64>() / cnt)
+}
+
+// Functions like SUMIF(range, criteria, [sum_range]). The values are taken
+// from the range if the sum_range is absent. Otherwise they are taken from
+// the sum_range resized to the size of the range.
+fn calc_if<C, F>(args: Vec<CalcVertex>, fetcher: &mut C, aggregate: &F) -> CalcVertex
+where
+    C: Connector,
+    F: Fn(Vec<f64>) -> Result<f64, ast::Error>,
+{
+    assert_or_return!(args.len() == 2 || args.len() == 3, ast::Error::Unspecified);
+    let mut iter = args.into_iter();
+    let range = iter.next().unwrap();
+    let criteria = fetcher.get_calc_value(iter.next().unwrap());
+    let target = iter.next().map(|arg| resize_reference(arg, &range));
+    let range = match ConditionRange::from_calc_value(fetcher.get_calc_value(range)) {
+        Ok(r) => r,
+        Err(e) => return CalcVertex::from_error(e),
+    };
+    let target = match target {
+        Some(arg) => match ConditionRange::from_calc_value(fetcher.get_calc_value(arg)) {
+            Ok(r) => Some(r),
+            Err(e) => return CalcVertex::from_error(e),
+        },
+        None => None,
+    };
+    let cond = match get_condition(criteria) {
+        Some(c) => c,
+        None => return CalcVertex::from_error(ast::Error::Value),
+    };
+    let conditions = vec![(range, cond)];
+    let positions = match match_conditions(&conditions) {
+        Ok(p) => p,
+        Err(e) => return CalcVertex::from_error(e),
+    };
+    let target = match target {
+        Some(t) => t,
+        None => conditions.into_iter().next().unwrap().0,
+    };
+    aggregate_values(&target, positions, aggregate)
+}
+
+// Functions like SUMIFS(sum_range, criteria_range1, criteria1, ...). All
+// the ranges should be of the same size.
+fn calc_ifs<C, F>(args: Vec<CalcVertex>, fetcher: &mut C, aggregate: &F) -> CalcVertex
+where
+    C: Connector,
+    F: Fn(Vec<f64>) -> Result<f64, ast::Error>,
+{
+    assert_or_return!(
+        args.len() >= 3 && args.len() % 2 == 1,
+        ast::Error::Unspecified
+    );
+    let mut iter = args.into_iter();
+    let target = match ConditionRange::from_calc_value(fetcher.get_calc_value(iter.next().unwrap()))
+    {
+        Ok(r) => r,
+        Err(e) => return CalcVertex::from_error(e),
+    };
+    let mut conditions = vec![];
+    while let (Some(range), Some(criteria)) = (iter.next(), iter.next()) {
+        let range = ConditionRange::from_calc_value(fetcher.get_calc_value(range));
+        let cond = get_condition(fetcher.get_calc_value(criteria));
+        match (range, cond) {
+            (Ok(r), Some(c)) => {
+                if r.size != target.size {
+                    return CalcVertex::from_error(ast::Error::Value);
+                }
+                conditions.push((r, c))
+            }
+            (Err(e), _) => return CalcVertex::from_error(e),
+            (_, None) => return CalcVertex::from_error(ast::Error::Value),
+        }
+    }
+    let positions = match match_conditions(&conditions) {
+        Ok(p) => p,
+        Err(e) => return CalcVertex::from_error(e),
+    };
+    aggregate_values(&target, positions, aggregate)
+}
+
+// Resize the reference to the size of the range from its top-left cell, like
+// `SUMIF(A1:A3, ">1", B1)` takes the values from B1:B3.
+fn resize_reference(target: CalcVertex, range: &CalcVertex) -> CalcVertex {
+    let (mut target, range) = match (target, range) {
+        (CalcVertex::Reference(t), CalcVertex::Reference(r)) => (t, &r.reference),
+        (t, _) => return t,
+    };
+    let reference = match (target.reference, range) {
+        (Reference::Addr(start), Reference::Addr(_))
+        | (Reference::Range(start, _), Reference::Addr(_)) => Reference::Addr(start),
+        (Reference::Addr(start), Reference::Range(s, e))
+        | (Reference::Range(start, _), Reference::Range(s, e)) => {
+            let end = Addr {
+                row: start.row + e.row - s.row,
+                col: start.col + e.col - s.col,
+            };
+            Reference::Range(start, end)
+        }
+        (Reference::ColumnRange(t), Reference::ColumnRange(r)) => {
+            Reference::ColumnRange(ColRange {
+                start: t.start,
+                end: t.start + r.end - r.start,
+            })
+        }
+        (Reference::RowRange(t), Reference::RowRange(r)) => Reference::RowRange(RowRange {
+            start: t.start,
+            end: t.start + r.end - r.start,
+        }),
+        (t, _) => t,
+    };
+    target.reference = reference;
+    CalcVertex::Reference(target)
+}
+
+// Only the numbers are aggregated and errors are returned directly.
+fn aggregate_values<F>(
+    target: &ConditionRange,
+    positions: Vec<(usize, usize)>,
+    aggregate: &F,
+) -> CalcVertex
+where
+    F: Fn(Vec<f64>) -> Result<f64, ast::Error>,
+{
+    let mut nums = Vec::<f64>::with_capacity(positions.len());
+    for (row, col) in positions.into_iter() {
+        match target.get(row, col) {
+            Value::Number(n) => nums.push(n),
+            Value::Error(e) => return CalcVertex::from_error(e),
+            _ => {}
+        }
+    }
+    match aggregate(nums) {
+        Ok(n) => CalcVertex::from_number(n),
+        Err(e) => CalcVertex::from_error(e),
+    }
+}
@@ -0,0 +1,51 @@
+use super::utils::get_f64_args;
+use crate::calc_engine::calculator::calc_vertex::CalcVertex;
+use crate::calc_engine::connector::Connector;
+use logisheets_parser::ast;
+
+pub fn calc_mod<C>(args: Vec<CalcVertex>, fetcher: &mut C) -> CalcVertex
+where
+    C: Connector,
+{
+    let nums = match get_f64_args(args, fetcher, &[None, None]) {
+        Ok(nums) => nums,
+        Err(e) => return CalcVertex::from_error(e),
+    };
+    let (n, divisor) = (nums[0], nums[1]);
+    assert_or_return!(divisor != 0., ast::Error::Div0);
+    // The result has the same sign as the divisor.
+    let res = n - divisor * (n / divisor).floor();
+    CalcVertex::from_number(res)
+}
+
+pub fn calc_power<C>(args: Vec<CalcVertex>, fetcher: &mut C) -> CalcVertex
+where
+    C: Connector,
+{
+    let nums = match get_f64_args(args, fetcher, &[None, None]) {
+        Ok(nums) => nums,
+        Err(e) => return CalcVertex::from_error(e),
+    };
+    let (base, exp) = (nums[0], nums[1]);
+    if base == 0. {
+        assert_or_return!(exp != 0., ast::Error::Num);
+        assert_or_return!(exp > 0., ast::Error::Div0);
+    }
+    let res = base.powf(exp);
+    assert_or_return!(res.is_finite(), ast::Error::Num);
+    CalcVertex::from_number(res)
+}
+
+pub fn calc_log<C>(args: Vec<CalcVertex>, fetcher: &mut C) -> CalcVertex
+where
+    C: Connector,
+{
+    let nums = match get_f64_args(args, fetcher, &[None, Some(10.)]) {
+        Ok(nums) => nums,
+        Err(e) => return CalcVertex::from_error(e),
+    };
+    let (n, base) = (nums[0], nums[1]);
+    assert_or_return!(n > 0. && base > 0., ast::Error::Num);
+    assert_or_return!(base != 1., ast::Error::Div0);
+    CalcVertex::from_number(n.log(base))
+}
@@ -1,12 +1,18 @@
-use logisheets_base::async_func::{AsyncCalcResult, Task};
+use logisheets_base::async_func::{AsyncCalcResult, AsyncErr, Task};
+use logisheets_base::matrix_value::MatrixValue;
 use logisheets_parser::ast;
 
 use crate::calc_engine::{
     calculator::calc_vertex::{CalcValue, CalcVertex, Value},
     connector::Connector,
 };
+use crate::settings::func_registry::{AsyncFunc, AsyncReturnType};
 
-pub fn calc<C>(name: &str, args: Vec<CalcVertex>, fetcher: &mut C) -> CalcVertex
+// The arguments and the results are sent as strings. Ranges and matrices
+// are written like array constants, e.g. `{1,"a";TRUE,2}`, where the texts
+// are quoted. A single text is sent as it is.
+
+pub fn calc<C>(name: &str, func: &AsyncFunc, args: Vec<CalcVertex>, fetcher: &mut C) -> CalcVertex
 where
     C: Connector,
 {
@@ -15,10 +21,13 @@ where
     let cid = fetcher.get_cell_id(sheet_id, curr_cell.row, curr_cell.col);
     assert_or_return!(cid.is_ok(), ast::Error::Unspecified);
     let cid = cid.unwrap();
-    let async_args = args
-        .into_iter()
-        .map(|arg| fetcher.get_calc_value(arg).to_async_arg())
-        .collect::<Vec<_>>();
+    let mut async_args = Vec::with_capacity(args.len());
+    for arg in args {
+        match encode_arg(fetcher.get_calc_value(arg)) {
+            Some(s) => async_args.push(s),
+            None => return CalcVertex::from_error(ast::Error::Value),
+        }
+    }
     let res = fetcher.query_or_commit_task(
         sheet_id,
         cid,
@@ -27,29 +36,171 @@ where
             args: async_args,
         },
     );
-    CalcVertex::Value(CalcValue::Scalar(decode_result(res)))
+    CalcVertex::Value(decode_result(res, func.return_type))
 }
 
-fn decode_result(result: Option<AsyncCalcResult>) -> Value {
-    if let Some(result) = result {
-        match result {
-            Ok(s) => {
-                if let Ok(num_res) = s.parse::<f64>() {
-                    Value::Number(num_res)
-                } else {
-                    let upper = s.to_uppercase();
-                    if upper == "TRUE" {
-                        Value::Boolean(true)
-                    } else if upper == "FALSE" {
-                        Value::Boolean(false)
-                    } else {
-                        Value::Text(s)
+fn encode_arg(value: CalcValue) -> Option<String> {
+    match value {
+        CalcValue::Scalar(v) => Some(encode_value(v, false)),
+        CalcValue::Range(r) => {
+            let (rows, cols) = r.get_used_size();
+            let rows = (0..rows)
+                .map(|i| {
+                    (0..cols)
+                        .map(|j| match r.visit(i, j) {
+                            Ok(v) => encode_value(v.clone(), true),
+                            Err(v) => encode_value(v, true),
+                        })
+                        .collect::<Vec<_>>()
+                        .join(",")
+                })
+                .collect::<Vec<_>>();
+            Some(format!("{{{}}}", rows.join(";")))
+        }
+        CalcValue::Cube(_) | CalcValue::Union(_) => None,
+    }
+}
+
+// Texts in arrays are quoted so that they can be told from the other values.
+fn encode_value(value: Value, in_array: bool) -> String {
+    match value {
+        Value::Blank => String::from(""),
+        Value::Number(n) => n.to_string(),
+        Value::Text(t) if in_array => format!("\"{}\"", t.replace('"', "\"\"")),
+        Value::Text(t) => t,
+        Value::Boolean(b) => {
+            if b {
+                String::from("TRUE")
+            } else {
+                String::from("FALSE")
+            }
+        }
+        Value::Error(e) => e.get_err_str().to_string(),
+        Value::Date(d) => encode_value(Value::Text(d.to_rfc3339()), in_array),
+    }
+}
+
+fn decode_result(result: Option<AsyncCalcResult>, return_type: AsyncReturnType) -> CalcValue {
+    let s = match result {
+        Some(Ok(s)) => s,
+        Some(Err(AsyncErr::ArgErr)) => return CalcValue::Scalar(Value::Error(ast::Error::Value)),
+        Some(Err(AsyncErr::TimeOut)) => return CalcValue::Scalar(Value::Error(ast::Error::Na)),
+        None => return CalcValue::Scalar(Value::Error(ast::Error::GettingData)),
+    };
+    let value = match return_type {
+        AsyncReturnType::Number => s.trim().parse::<f64>().map(Value::Number).ok(),
+        AsyncReturnType::Text => Some(Value::Text(s)),
+        AsyncReturnType::Boolean => decode_bool(s.trim()),
+        AsyncReturnType::Matrix => {
+            return match decode_matrix(&s) {
+                Some(m) => CalcValue::Range(MatrixValue::from(m)),
+                None => CalcValue::Scalar(Value::Error(ast::Error::Value)),
+            }
+        }
+    };
+    CalcValue::Scalar(value.unwrap_or(Value::Error(ast::Error::Value)))
+}
+
+fn decode_bool(s: &str) -> Option<Value> {
+    match s.to_uppercase().as_str() {
+        "TRUE" => Some(Value::Boolean(true)),
+        "FALSE" => Some(Value::Boolean(false)),
+        _ => None,
+    }
+}
+
+fn decode_matrix(s: &str) -> Option<Vec<Vec<Value>>> {
+    let s = s.trim().strip_prefix('{')?.strip_suffix('}')?;
+    let mut rows = vec![vec![]];
+    let mut chars = s.chars().peekable();
+    let mut element = String::new();
+    let mut quoted = false;
+    loop {
+        let c = chars.next();
+        match c {
+            Some('"') if element.trim().is_empty() && !quoted => {
+                let mut text = String::new();
+                loop {
+                    match chars.next()? {
+                        '"' if chars.peek() == Some(&'"') => {
+                            chars.next();
+                            text.push('"');
+                        }
+                        '"' => break,
+                        c => text.push(c),
                     }
                 }
+                element = text;
+                quoted = true;
+            }
+            Some(',') | Some(';') | None => {
+                let value = if quoted {
+                    Value::Text(std::mem::take(&mut element))
+                } else {
+                    decode_element(std::mem::take(&mut element).trim())?
+                };
+                quoted = false;
+                rows.last_mut()?.push(value);
+                match c {
+                    Some(';') => rows.push(vec![]),
+                    None => break,
+                    _ => {}
+                }
             }
-            Err(_) => Value::Error(ast::Error::Null), // To specify
+            // Only spaces can follow the closing quote.
+            Some(c) if quoted => {
+                if !c.is_whitespace() {
+                    return None;
+                }
+            }
+            Some(c) => element.push(c),
         }
-    } else {
-        Value::Error(ast::Error::GettingData)
+    }
+    let cols = rows[0].len();
+    if rows.iter().any(|r| r.len() != cols) {
+        return None;
+    }
+    Some(rows)
+}
+
+fn decode_element(s: &str) -> Option<Value> {
+    if s.is_empty() {
+        return Some(Value::Blank);
+    }
+    if let Ok(n) = s.parse::<f64>() {
+        return Some(Value::Number(n));
+    }
+    if let Some(b) = decode_bool(s) {
+        return Some(b);
+    }
+    match ast::Error::from_err_str(s) {
+        ast::Error::Unspecified if s != "#UNKNOWN!" => None,
+        e => Some(Value::Error(e)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{decode_matrix, encode_arg};
+    use crate::calc_engine::calculator::calc_vertex::{CalcValue, Value};
+    use logisheets_base::matrix_value::MatrixValue;
+
+    #[test]
+    fn matrix_test() {
+        let m = MatrixValue::from(vec![
+            vec![Value::Number(1.), Value::Text(String::from("a\"b"))],
+            vec![Value::Boolean(true), Value::Blank],
+        ]);
+        let s = encode_arg(CalcValue::Range(m)).unwrap();
+        assert_eq!(s, "{1,\"a\"\"b\";TRUE,}");
+        let m = decode_matrix(&s).unwrap();
+        assert!(matches!(m[0][0], Value::Number(n) if n == 1.));
+        assert!(matches!(&m[0][1], Value::Text(t) if t == "a\"b"));
+        assert!(matches!(m[1][0], Value::Boolean(true)));
+        assert!(matches!(m[1][1], Value::Blank));
+        assert!(decode_matrix("{1,2;3}").is_none());
+        assert!(decode_matrix("{1,ab}").is_none());
+        let s = encode_arg(CalcValue::Scalar(Value::Text(String::from("a\"b")))).unwrap();
+        assert_eq!(s, "a\"b");
     }
 }
@@ -0,0 +1,99 @@
+use super::utils::{get_f64_args, get_scalar_text};
+use crate::calc_engine::calculator::calc_vertex::CalcVertex;
+use crate::calc_engine::connector::Connector;
+use logisheets_parser::ast;
+
+// CHAR and CODE work on the Windows-1252 code page. These are the chars of
+// 0x80 to 0x9f, where it is different from Latin-1. 0 means undefined.
+const CP1252_HIGH: [u32; 32] = [
+    0x20ac, 0, 0x201a, 0x0192, 0x201e, 0x2026, 0x2020, 0x2021, 0x02c6, 0x2030, 0x0160, 0x2039,
+    0x0152, 0, 0x017d, 0, 0, 0x2018, 0x2019, 0x201c, 0x201d, 0x2022, 0x2013, 0x2014, 0x02dc,
+    0x2122, 0x0161, 0x203a, 0x0153, 0, 0x017e, 0x0178,
+];
+
+pub fn calc_char<C>(args: Vec<CalcVertex>, fetcher: &mut C) -> CalcVertex
+where
+    C: Connector,
+{
+    let n = match get_f64_args(args, fetcher, &[None]) {
+        Ok(nums) => nums[0].trunc(),
+        Err(e) => return CalcVertex::from_error(e),
+    };
+    assert_or_return!((1. ..=255.).contains(&n), ast::Error::Value);
+    let code = n as u32;
+    let code = match code {
+        0x80..=0x9f => match CP1252_HIGH[(code - 0x80) as usize] {
+            0 => code,
+            c => c,
+        },
+        _ => code,
+    };
+    CalcVertex::from_string(char::from_u32(code).unwrap().to_string())
+}
+
+pub fn calc_code<C>(args: Vec<CalcVertex>, fetcher: &mut C) -> CalcVertex
+where
+    C: Connector,
+{
+    let c = match get_first_char(args, fetcher) {
+        Ok(c) => c as u32,
+        Err(e) => return CalcVertex::from_error(e),
+    };
+    let code = match c {
+        0..=0xff => c,
+        _ => match CP1252_HIGH.iter().position(|h| *h == c) {
+            Some(idx) => 0x80 + idx as u32,
+            // Chars out of the code page turn into `?`.
+            None => 0x3f,
+        },
+    };
+    CalcVertex::from_number(code as f64)
+}
+
+pub fn calc_unicode<C>(args: Vec<CalcVertex>, fetcher: &mut C) -> CalcVertex
+where
+    C: Connector,
+{
+    match get_first_char(args, fetcher) {
+        Ok(c) => CalcVertex::from_number(c as u32 as f64),
+        Err(e) => CalcVertex::from_error(e),
+    }
+}
+
+pub fn calc_unichar<C>(args: Vec<CalcVertex>, fetcher: &mut C) -> CalcVertex
+where
+    C: Connector,
+{
+    let n = match get_f64_args(args, fetcher, &[None]) {
+        Ok(nums) => nums[0].trunc(),
+        Err(e) => return CalcVertex::from_error(e),
+    };
+    assert_or_return!(n >= 1. && n <= 0x10ffff as f64, ast::Error::Value);
+    // Surrogates are not chars.
+    match char::from_u32(n as u32) {
+        Some(c) => CalcVertex::from_string(c.to_string()),
+        None => CalcVertex::from_error(ast::Error::Na),
+    }
+}
+
+fn get_first_char<C>(args: Vec<CalcVertex>, fetcher: &mut C) -> Result<char, ast::Error>
+where
+    C: Connector,
+{
+    if args.len() != 1 {
+        return Err(ast::Error::Unspecified);
+    }
+    let text = get_scalar_text(fetcher, args.into_iter().next().unwrap())?;
+    text.chars().next().ok_or(ast::Error::Value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::CP1252_HIGH;
+
+    #[test]
+    fn cp1252_test() {
+        assert_eq!(char::from_u32(CP1252_HIGH[0]), Some('€'));
+        assert_eq!(char::from_u32(CP1252_HIGH[0x99 - 0x80]), Some('™'));
+    }
+}
@@ -1,11 +1,14 @@
-use regex::Regex;
+use logisheets_parser::ast;
 
-use crate::calc_engine::calculator::calc_vertex::Value;
+use crate::calc_engine::calculator::calc_vertex::{CalcValue, Value};
+
+// The criteria engine used by the conditional functions like COUNTIF,
+// SUMIFS and MAXIFS.
 
 pub enum Condition {
     Logical(LogicalCondition),
-    TextPattern(String),
 }
+
 pub struct LogicalCondition {
     pub op: Op,
     pub value: ConditionValue,
@@ -23,119 +26,253 @@ pub enum Op {
 pub enum ConditionValue {
     Number(f64),
     Text(String),
+    Boolean(bool),
 }
 
+/// Parse the criteria like `">=5"`, `"<>"` or `"a*c"`. A criteria without
+/// any operator means equal.
 pub fn parse_condition(text: &str) -> Option<Condition> {
     let text = text.trim();
     let mut chars = text.chars().peekable();
-    let first = chars.next()?;
-    let op: Option<Op> = match first {
-        '>' => {
+    let op = match chars.peek() {
+        Some('>') => {
+            chars.next();
             if let Some('=') = chars.peek() {
                 chars.next();
-                Some(Op::Ge)
+                Op::Ge
             } else {
-                Some(Op::Gt)
+                Op::Gt
             }
         }
-        '<' => {
+        Some('<') => {
+            chars.next();
             let next = chars.peek();
             if let Some('=') = next {
                 chars.next();
-                Some(Op::Le)
+                Op::Le
             } else if let Some('>') = next {
                 chars.next();
-                Some(Op::Neq)
+                Op::Neq
             } else {
-                Some(Op::Lt)
+                Op::Lt
             }
         }
-        '=' => Some(Op::Eq),
-        _ => None,
-    };
-    if let Some(op) = op {
-        let s = chars.collect::<String>();
-        let num = s.parse::<f64>();
-        match num {
-            Ok(n) => Some(Condition::Logical(LogicalCondition {
-                op,
-                value: ConditionValue::Number(n),
-            })),
-            Err(_) => Some(Condition::Logical(LogicalCondition {
-                op,
-                value: ConditionValue::Text(s),
-            })),
+        Some('=') => {
+            chars.next();
+            Op::Eq
         }
-    } else {
-        Some(Condition::TextPattern(chars.collect::<String>()))
-    }
+        _ => Op::Eq,
+    };
+    let s = chars.collect::<String>();
+    let value = match s.parse::<f64>() {
+        Ok(n) => ConditionValue::Number(n),
+        Err(_) if s.eq_ignore_ascii_case("TRUE") => ConditionValue::Boolean(true),
+        Err(_) if s.eq_ignore_ascii_case("FALSE") => ConditionValue::Boolean(false),
+        Err(_) => ConditionValue::Text(s),
+    };
+    Some(Condition::Logical(LogicalCondition { op, value }))
 }
 
-pub fn get_condition_value(value: &Value) -> ConditionValue {
-    match value {
-        Value::Blank => ConditionValue::Text(String::from("")),
-        Value::Number(n) => ConditionValue::Number(n.clone()),
-        Value::Text(t) => ConditionValue::Text(t.clone()),
-        Value::Boolean(b) => {
-            let value = if *b {
-                String::from("TRUE")
-            } else {
-                String::from("FALSE")
-            };
-            ConditionValue::Text(value)
-        }
-        Value::Error(e) => ConditionValue::Text(e.get_err_str().to_string()),
-        Value::Date(_) => todo!(),
-    }
+/// Get the condition from the criteria argument. Texts are parsed as
+/// criteria strings and the other values mean equal to them.
+pub fn get_condition(criteria: CalcValue) -> Option<Condition> {
+    let value = match criteria {
+        CalcValue::Scalar(Value::Text(t)) => return parse_condition(&t),
+        CalcValue::Scalar(Value::Blank) => ConditionValue::Number(0.),
+        CalcValue::Scalar(Value::Number(n)) => ConditionValue::Number(n),
+        CalcValue::Scalar(Value::Boolean(b)) => ConditionValue::Boolean(b),
+        CalcValue::Scalar(Value::Error(e)) => ConditionValue::Text(e.get_err_str().to_string()),
+        _ => return None,
+    };
+    Some(Condition::Logical(LogicalCondition { op: Op::Eq, value }))
 }
 
 pub fn match_condition(cond: &Condition, value: &Value) -> bool {
-    let v = get_condition_value(value);
-    match (cond, v) {
-        (Condition::Logical(l), ConditionValue::Number(lhs)) => match &l.value {
-            ConditionValue::Number(rhs) => match &l.op {
-                Op::Eq => lhs == *rhs,
-                Op::Ge => lhs >= *rhs,
-                Op::Gt => lhs > *rhs,
-                Op::Le => lhs <= *rhs,
-                Op::Lt => lhs < *rhs,
-                Op::Neq => lhs != *rhs,
-            },
-            ConditionValue::Text(_) => false,
+    let Condition::Logical(l) = cond;
+    let matched = match (&l.value, value) {
+        (ConditionValue::Number(rhs), Value::Number(lhs)) => compare(&l.op, lhs, rhs),
+        // Texts that look like numbers are equal to the numbers.
+        (ConditionValue::Number(rhs), Value::Text(t)) => match (&l.op, t.trim().parse::<f64>()) {
+            (Op::Eq, Ok(lhs)) | (Op::Neq, Ok(lhs)) => compare(&l.op, &lhs, rhs),
+            _ => None,
         },
-        (Condition::Logical(l), ConditionValue::Text(lhs)) => match &l.value {
-            ConditionValue::Number(_) => false,
-            ConditionValue::Text(rhs) => match &l.op {
-                Op::Eq => lhs == *rhs,
-                Op::Ge => lhs >= *rhs,
-                Op::Gt => lhs > *rhs,
-                Op::Le => lhs <= *rhs,
-                Op::Lt => lhs < *rhs,
-                Op::Neq => lhs != *rhs,
-            },
+        (ConditionValue::Number(_), _) => None,
+        (ConditionValue::Boolean(rhs), Value::Boolean(lhs)) => compare(&l.op, lhs, rhs),
+        (ConditionValue::Boolean(_), _) => None,
+        (ConditionValue::Text(rhs), _) if rhs.is_empty() => match (&l.op, value) {
+            (Op::Eq, Value::Blank) => Some(true),
+            (Op::Eq, Value::Text(t)) => Some(t.is_empty()),
+            (Op::Neq, Value::Blank) => Some(false),
+            (Op::Neq, _) => Some(true),
+            _ => None,
         },
-        (Condition::TextPattern(_), ConditionValue::Number(_)) => false,
-        (Condition::TextPattern(p), ConditionValue::Text(t)) => match_text_pattern(p, &t),
+        (ConditionValue::Text(rhs), Value::Text(lhs)) => match &l.op {
+            Op::Eq => Some(match_text_pattern(rhs, lhs)),
+            Op::Neq => Some(!match_text_pattern(rhs, lhs)),
+            op => compare(op, &lhs.to_lowercase(), &rhs.to_lowercase()),
+        },
+        (ConditionValue::Text(rhs), Value::Error(e)) => {
+            let lhs = e.get_err_str();
+            match &l.op {
+                Op::Eq => Some(lhs.eq_ignore_ascii_case(rhs)),
+                Op::Neq => Some(!lhs.eq_ignore_ascii_case(rhs)),
+                _ => None,
+            }
+        }
+        (ConditionValue::Text(_), _) => None,
+    };
+    // Values of different types are never equal.
+    matched.unwrap_or(matches!(l.op, Op::Neq))
+}
+
+fn compare<T: PartialOrd>(op: &Op, lhs: &T, rhs: &T) -> Option<bool> {
+    let r = match op {
+        Op::Eq => lhs == rhs,
+        Op::Ge => lhs >= rhs,
+        Op::Gt => lhs > rhs,
+        Op::Le => lhs <= rhs,
+        Op::Lt => lhs < rhs,
+        Op::Neq => lhs != rhs,
+    };
+    Some(r)
+}
+
+/// Match the text with the wildcards `*` and `?`. `~` is used to escape them.
+/// The comparison is case-insensitive.
+pub fn match_text_pattern(pattern: &str, text: &str) -> bool {
+    let matched = match_pattern_prefixes(pattern, text);
+    matched[matched.len() - 1]
+}
+
+/// Like `match_text_pattern`, but only a prefix of the text needs to match.
+pub fn match_text_pattern_prefix(pattern: &str, text: &str) -> bool {
+    match_pattern_prefixes(pattern, text).into_iter().any(|m| m)
+}
+
+// Returns whether the pattern matches the first j chars of the text, for
+// every j.
+fn match_pattern_prefixes(pattern: &str, text: &str) -> Vec<bool> {
+    let pattern = parse_pattern(&pattern.to_lowercase());
+    let text = text.to_lowercase().chars().collect::<Vec<_>>();
+    // `matched[j]` means whether the first j chars of the text match the
+    // pattern visited.
+    let mut matched = vec![false; text.len() + 1];
+    matched[0] = true;
+    pattern.iter().for_each(|p| {
+        let mut next = vec![false; text.len() + 1];
+        match p {
+            PatternChar::Any => {
+                let mut any = false;
+                (0..=text.len()).for_each(|j| {
+                    any = any || matched[j];
+                    next[j] = any;
+                });
+            }
+            PatternChar::One => {
+                (1..=text.len()).for_each(|j| next[j] = matched[j - 1]);
+            }
+            PatternChar::Char(c) => {
+                (1..=text.len()).for_each(|j| next[j] = matched[j - 1] && text[j - 1] == *c);
+            }
+        }
+        matched = next;
+    });
+    matched
+}
+
+enum PatternChar {
+    Any,
+    One,
+    Char(char),
+}
+
+fn parse_pattern(pattern: &str) -> Vec<PatternChar> {
+    let mut result = vec![];
+    let mut chars = pattern.chars();
+    while let Some(c) = chars.next() {
+        let p = match c {
+            '*' => PatternChar::Any,
+            '?' => PatternChar::One,
+            '~' => match chars.next() {
+                Some(n) => PatternChar::Char(n),
+                None => PatternChar::Char('~'),
+            },
+            _ => PatternChar::Char(c),
+        };
+        result.push(p);
     }
+    result
 }
 
-fn match_text_pattern(pattern: &str, text: &str) -> bool {
-    let regex_str = format!("^{}$", pattern.replace("?", ".{1}").replace("*", ".*"));
-    let regex = Regex::new(&regex_str);
-    match regex {
-        Ok(r) => match r.captures_iter(text).next() {
-            Some(_) => true,
-            None => false,
-        },
-        Err(_) => false,
+/// A range in the conditional functions. Values are stored row by row.
+pub struct ConditionRange {
+    pub size: (usize, usize),
+    pub values: Vec<Value>,
+}
+
+impl ConditionRange {
+    pub fn from_calc_value(value: CalcValue) -> Result<Self, ast::Error> {
+        match value {
+            CalcValue::Scalar(v) => Ok(ConditionRange {
+                size: (1, 1),
+                values: vec![v],
+            }),
+            CalcValue::Range(r) => {
+                let size = r.get_avail_size();
+                let mut values = Vec::with_capacity(size.0 * size.1);
+                (0..size.0).for_each(|i| {
+                    (0..size.1).for_each(|j| {
+                        let v = match r.visit(i, j) {
+                            Ok(v) => v.clone(),
+                            Err(v) => v,
+                        };
+                        values.push(v);
+                    })
+                });
+                Ok(ConditionRange { size, values })
+            }
+            _ => Err(ast::Error::Value),
+        }
+    }
+
+    /// Get the value at the position of another range. Positions out of this
+    /// range get blanks.
+    pub fn get(&self, row: usize, col: usize) -> Value {
+        if row >= self.size.0 || col >= self.size.1 {
+            return Value::Blank;
+        }
+        self.values[row * self.size.1 + col].clone()
+    }
+}
+
+/// Find the positions that match all the conditions in their ranges. The
+/// ranges should be of the same size.
+pub fn match_conditions(
+    conditions: &[(ConditionRange, Condition)],
+) -> Result<Vec<(usize, usize)>, ast::Error> {
+    let size = match conditions.first() {
+        Some((r, _)) => r.size,
+        None => return Err(ast::Error::Unspecified),
+    };
+    if conditions.iter().any(|(r, _)| r.size != size) {
+        return Err(ast::Error::Value);
     }
+    let result = (0..size.0)
+        .flat_map(|i| (0..size.1).map(move |j| (i, j)))
+        .filter(|(i, j)| {
+            conditions
+                .iter()
+                .all(|(r, c)| match_condition(c, &r.values[i * size.1 + j]))
+        })
+        .collect();
+    Ok(result)
 }
 
 #[cfg(test)]
 mod tests {
     use super::{
-        match_condition, match_text_pattern, parse_condition, Condition, ConditionValue,
-        LogicalCondition, Op,
+        match_condition, match_text_pattern, match_text_pattern_prefix, parse_condition, Condition,
+        ConditionValue, LogicalCondition, Op,
     };
     use crate::calc_engine::calculator::calc_vertex::Value;
 
@@ -244,6 +381,8 @@ mod tests {
         let t = "444123657";
         let r = match_text_pattern(p, t);
         assert!(!r);
+        assert!(match_text_pattern_prefix(p, t));
+        assert!(!match_text_pattern_prefix("a?c", "abd"));
     }
 
     #[test]
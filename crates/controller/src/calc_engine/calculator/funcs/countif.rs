@@ -1,5 +1,5 @@
 use super::condition::{
-    get_condition_value, match_condition, parse_condition, Condition, LogicalCondition, Op,
+    get_condition, match_condition, match_conditions, Condition, ConditionRange,
 };
 use crate::calc_engine::connector::Connector;
 use logisheets_parser::ast;
@@ -10,29 +10,41 @@ pub fn calc<C>(args: Vec<CalcVertex>, fetcher: &mut C) -> CalcVertex
 where
     C: Connector,
 {
-    if args.len() != 2 {
-        return CalcVertex::from_error(ast::Error::Unspecified);
-    }
+    assert_or_return!(args.len() == 2, ast::Error::Unspecified);
     let mut iter = args.into_iter();
     let first = fetcher.get_calc_value(iter.next().unwrap());
     let second = fetcher.get_calc_value(iter.next().unwrap());
-    let condition = match second {
-        CalcValue::Scalar(v) => match v {
-            Value::Text(t) => parse_condition(&t),
-            _ => {
-                let value = get_condition_value(&v);
-                let condition = Condition::Logical(LogicalCondition { op: Op::Eq, value });
-                Some(condition)
-            }
-        },
-        _ => None,
-    };
-    match condition {
+    match get_condition(second) {
         Some(cond) => CalcVertex::from_number(count_if_calc_value(&cond, first) as f64),
         None => CalcVertex::from_error(ast::Error::Unspecified),
     }
 }
 
+pub fn calc_countifs<C>(args: Vec<CalcVertex>, fetcher: &mut C) -> CalcVertex
+where
+    C: Connector,
+{
+    assert_or_return!(
+        !args.is_empty() && args.len() % 2 == 0,
+        ast::Error::Unspecified
+    );
+    let mut conditions = Vec::with_capacity(args.len() / 2);
+    let mut iter = args.into_iter();
+    while let (Some(range), Some(criteria)) = (iter.next(), iter.next()) {
+        let range = ConditionRange::from_calc_value(fetcher.get_calc_value(range));
+        let cond = get_condition(fetcher.get_calc_value(criteria));
+        match (range, cond) {
+            (Ok(r), Some(c)) => conditions.push((r, c)),
+            (Err(e), _) => return CalcVertex::from_error(e),
+            (_, None) => return CalcVertex::from_error(ast::Error::Value),
+        }
+    }
+    match match_conditions(&conditions) {
+        Ok(positions) => CalcVertex::from_number(positions.len() as f64),
+        Err(e) => CalcVertex::from_error(e),
+    }
+}
+
 fn count_if_calc_value(cond: &Condition, value: CalcValue) -> u32 {
     match value {
         CalcValue::Scalar(v) => count_if_value(cond, &v),
@@ -0,0 +1,388 @@
+use std::cmp::Ordering;
+
+use super::condition::ConditionRange;
+use super::utils::{convert_f64, get_f64_args};
+use crate::calc_engine::calculator::calc_vertex::{
+    CalcReference, CalcValue, CalcVertex, Reference, Value,
+};
+use crate::calc_engine::connector::Connector;
+use logisheets_base::matrix_value::MatrixValue;
+use logisheets_base::Addr;
+use logisheets_parser::ast;
+use rand::{thread_rng, Rng};
+
+// The functions returning arrays which spill into the cells around.
+
+const MAX_ROWS: f64 = 1048576.;
+const MAX_COLS: f64 = 16384.;
+
+/// ANCHORARRAY(reference), which is the `A1#` operator.
+///
+/// Refer to the cells spilled from the formula cell.
+pub fn calc_anchorarray<C>(args: Vec<CalcVertex>, fetcher: &mut C) -> CalcVertex
+where
+    C: Connector,
+{
+    assert_or_return!(args.len() == 1, ast::Error::Unspecified);
+    let (sheet, addr) = match args.into_iter().next().unwrap() {
+        CalcVertex::Reference(CalcReference {
+            from_sheet: None,
+            sheet,
+            reference: Reference::Addr(addr),
+        }) => (sheet, addr),
+        _ => return CalcVertex::from_error(ast::Error::Ref),
+    };
+    let spill = fetcher
+        .get_cell_id(sheet, addr.row, addr.col)
+        .ok()
+        .and_then(|cell_id| fetcher.get_spill(sheet, &cell_id));
+    match spill {
+        Some((rows, cols)) => {
+            let end = Addr {
+                row: addr.row + rows - 1,
+                col: addr.col + cols - 1,
+            };
+            let reference = CalcReference {
+                from_sheet: None,
+                sheet,
+                reference: Reference::Range(addr, end),
+            };
+            fetcher.add_dynamic_dep(&reference);
+            CalcVertex::Reference(reference)
+        }
+        None => CalcVertex::from_error(ast::Error::Ref),
+    }
+}
+
+/// FILTER(array, include, [if_empty])
+///
+/// `include` is a column to filter the rows or a row to filter the columns.
+pub fn calc_filter<C>(args: Vec<CalcVertex>, fetcher: &mut C) -> CalcVertex
+where
+    C: Connector,
+{
+    assert_or_return!(args.len() == 2 || args.len() == 3, ast::Error::Unspecified);
+    let mut args_iter = args.into_iter();
+    let array = match get_array(fetcher, args_iter.next().unwrap()) {
+        Ok(a) => a,
+        Err(e) => return CalcVertex::from_error(e),
+    };
+    let include = match get_array(fetcher, args_iter.next().unwrap()) {
+        Ok(a) => a,
+        Err(e) => return CalcVertex::from_error(e),
+    };
+    let (rows, cols) = size(&array);
+    let by_col = if size(&include) == (rows, 1) {
+        false
+    } else if size(&include) == (1, cols) {
+        true
+    } else {
+        return CalcVertex::from_error(ast::Error::Value);
+    };
+    let flags = include
+        .into_iter()
+        .flatten()
+        .map(|v| convert_f64(v).map(|n| n != 0.))
+        .collect::<Result<Vec<_>, _>>();
+    let flags = match flags {
+        Ok(f) => f,
+        Err(e) => return CalcVertex::from_error(e),
+    };
+    let array = if by_col { transpose(array) } else { array };
+    let result = array
+        .into_iter()
+        .zip(flags)
+        .filter_map(|(row, keep)| if keep { Some(row) } else { None })
+        .collect::<Vec<_>>();
+    if result.is_empty() {
+        return match args_iter.next() {
+            Some(if_empty) => CalcVertex::Value(fetcher.get_calc_value(if_empty)),
+            None => CalcVertex::from_error(ast::Error::Calc),
+        };
+    }
+    let result = if by_col { transpose(result) } else { result };
+    to_vertex(result)
+}
+
+/// SORT(array, [sort_index], [sort_order], [by_col])
+pub fn calc_sort<C>(args: Vec<CalcVertex>, fetcher: &mut C) -> CalcVertex
+where
+    C: Connector,
+{
+    assert_or_return!(!args.is_empty() && args.len() <= 4, ast::Error::Unspecified);
+    let mut args_iter = args.into_iter();
+    let array = match get_array(fetcher, args_iter.next().unwrap()) {
+        Ok(a) => a,
+        Err(e) => return CalcVertex::from_error(e),
+    };
+    let nums = match get_f64_args(
+        args_iter.collect(),
+        fetcher,
+        &[Some(1.), Some(1.), Some(0.)],
+    ) {
+        Ok(nums) => nums,
+        Err(e) => return CalcVertex::from_error(e),
+    };
+    let by_col = nums[2] != 0.;
+    let descending = match get_descending(nums[1]) {
+        Ok(d) => d,
+        Err(e) => return CalcVertex::from_error(e),
+    };
+    let mut array = if by_col { transpose(array) } else { array };
+    let idx = nums[0].trunc() as usize;
+    let width = array.first().map(|r| r.len()).unwrap_or(0);
+    assert_or_return!(idx >= 1 && idx <= width, ast::Error::Value);
+    array.sort_by(|a, b| sort_cmp(&a[idx - 1], &b[idx - 1], descending));
+    let array = if by_col { transpose(array) } else { array };
+    to_vertex(array)
+}
+
+/// SORTBY(array, by_array1, [sort_order1], [by_array2, sort_order2], ...)
+///
+/// The by arrays are all columns to sort the rows or all rows to sort the
+/// columns.
+pub fn calc_sortby<C>(args: Vec<CalcVertex>, fetcher: &mut C) -> CalcVertex
+where
+    C: Connector,
+{
+    assert_or_return!(args.len() >= 2, ast::Error::Unspecified);
+    let mut args_iter = args.into_iter();
+    let array = match get_array(fetcher, args_iter.next().unwrap()) {
+        Ok(a) => a,
+        Err(e) => return CalcVertex::from_error(e),
+    };
+    let (rows, cols) = size(&array);
+    let mut keys = Vec::<(Vec<Value>, bool)>::new();
+    let mut by_col = None;
+    while let Some(arg) = args_iter.next() {
+        let by_array = match get_array(fetcher, arg) {
+            Ok(a) => a,
+            Err(e) => return CalcVertex::from_error(e),
+        };
+        let col = if size(&by_array) == (rows, 1) {
+            false
+        } else if size(&by_array) == (1, cols) {
+            true
+        } else {
+            return CalcVertex::from_error(ast::Error::Value);
+        };
+        assert_or_return!(*by_col.get_or_insert(col) == col, ast::Error::Value);
+        let descending = match args_iter.next() {
+            Some(order) => {
+                match get_f64_args(vec![order], fetcher, &[None]).and_then(|n| get_descending(n[0]))
+                {
+                    Ok(d) => d,
+                    Err(e) => return CalcVertex::from_error(e),
+                }
+            }
+            None => false,
+        };
+        keys.push((by_array.into_iter().flatten().collect(), descending));
+    }
+    let by_col = by_col.unwrap_or(false);
+    let array = if by_col { transpose(array) } else { array };
+    let mut indices = (0..array.len()).collect::<Vec<_>>();
+    indices.sort_by(|a, b| {
+        keys.iter()
+            .map(|(values, descending)| sort_cmp(&values[*a], &values[*b], *descending))
+            .find(|o| *o != Ordering::Equal)
+            .unwrap_or(Ordering::Equal)
+    });
+    let result = indices
+        .into_iter()
+        .map(|i| array[i].clone())
+        .collect::<Vec<_>>();
+    let result = if by_col { transpose(result) } else { result };
+    to_vertex(result)
+}
+
+/// UNIQUE(array, [by_col], [exactly_once])
+pub fn calc_unique<C>(args: Vec<CalcVertex>, fetcher: &mut C) -> CalcVertex
+where
+    C: Connector,
+{
+    assert_or_return!(!args.is_empty() && args.len() <= 3, ast::Error::Unspecified);
+    let mut args_iter = args.into_iter();
+    let array = match get_array(fetcher, args_iter.next().unwrap()) {
+        Ok(a) => a,
+        Err(e) => return CalcVertex::from_error(e),
+    };
+    let nums = match get_f64_args(args_iter.collect(), fetcher, &[Some(0.), Some(0.)]) {
+        Ok(nums) => nums,
+        Err(e) => return CalcVertex::from_error(e),
+    };
+    let (by_col, exactly_once) = (nums[0] != 0., nums[1] != 0.);
+    let array = if by_col { transpose(array) } else { array };
+    // The distinct rows in the order they first appear, with their counts.
+    let mut distinct = Vec::<(Vec<Value>, usize)>::new();
+    array.into_iter().for_each(|row| {
+        match distinct.iter_mut().find(|(r, _)| is_same_row(r, &row)) {
+            Some((_, cnt)) => *cnt += 1,
+            None => distinct.push((row, 1)),
+        }
+    });
+    let result = distinct
+        .into_iter()
+        .filter(|(_, cnt)| !exactly_once || *cnt == 1)
+        .map(|(row, _)| row)
+        .collect::<Vec<_>>();
+    assert_or_return!(!result.is_empty(), ast::Error::Calc);
+    let result = if by_col { transpose(result) } else { result };
+    to_vertex(result)
+}
+
+/// SEQUENCE(rows, [columns], [start], [step])
+pub fn calc_sequence<C>(args: Vec<CalcVertex>, fetcher: &mut C) -> CalcVertex
+where
+    C: Connector,
+{
+    let nums = match get_f64_args(args, fetcher, &[None, Some(1.), Some(1.), Some(1.)]) {
+        Ok(nums) => nums,
+        Err(e) => return CalcVertex::from_error(e),
+    };
+    let (rows, cols) = match get_size(nums[0], nums[1]) {
+        Ok(s) => s,
+        Err(e) => return CalcVertex::from_error(e),
+    };
+    let (start, step) = (nums[2], nums[3]);
+    let result = (0..rows)
+        .map(|i| {
+            (0..cols)
+                .map(|j| Value::Number(start + step * (i * cols + j) as f64))
+                .collect()
+        })
+        .collect();
+    to_vertex(result)
+}
+
+/// RANDARRAY([rows], [columns], [min], [max], [integer])
+pub fn calc_randarray<C>(args: Vec<CalcVertex>, fetcher: &mut C) -> CalcVertex
+where
+    C: Connector,
+{
+    let defaults = [Some(1.), Some(1.), Some(0.), Some(1.), Some(0.)];
+    let nums = match get_f64_args(args, fetcher, &defaults) {
+        Ok(nums) => nums,
+        Err(e) => return CalcVertex::from_error(e),
+    };
+    let (rows, cols) = match get_size(nums[0], nums[1]) {
+        Ok(s) => s,
+        Err(e) => return CalcVertex::from_error(e),
+    };
+    let (min, max, integer) = (nums[2], nums[3], nums[4] != 0.);
+    assert_or_return!(min <= max, ast::Error::Value);
+    let (min, max) = if integer {
+        (min.ceil(), max.floor())
+    } else {
+        (min, max)
+    };
+    assert_or_return!(min <= max, ast::Error::Value);
+    let mut rng = thread_rng();
+    let result = (0..rows)
+        .map(|_| {
+            (0..cols)
+                .map(|_| {
+                    let n = if integer {
+                        rng.gen_range(min as i64..=max as i64) as f64
+                    } else {
+                        min + (max - min) * rng.gen::<f64>()
+                    };
+                    Value::Number(n)
+                })
+                .collect()
+        })
+        .collect();
+    to_vertex(result)
+}
+
+pub(super) fn get_array<C>(fetcher: &mut C, arg: CalcVertex) -> Result<Vec<Vec<Value>>, ast::Error>
+where
+    C: Connector,
+{
+    let range = ConditionRange::from_calc_value(fetcher.get_calc_value(arg))?;
+    let (rows, cols) = range.size;
+    Ok((0..rows)
+        .map(|i| (0..cols).map(|j| range.get(i, j)).collect())
+        .collect())
+}
+
+pub(super) fn size(array: &[Vec<Value>]) -> (usize, usize) {
+    (array.len(), array.first().map(|r| r.len()).unwrap_or(0))
+}
+
+pub(super) fn transpose(array: Vec<Vec<Value>>) -> Vec<Vec<Value>> {
+    let (rows, cols) = size(&array);
+    (0..cols)
+        .map(|j| (0..rows).map(|i| array[i][j].clone()).collect())
+        .collect()
+}
+
+pub(super) fn to_vertex(array: Vec<Vec<Value>>) -> CalcVertex {
+    CalcVertex::Value(CalcValue::Range(MatrixValue::from(array)))
+}
+
+pub(super) fn get_size(rows: f64, cols: f64) -> Result<(usize, usize), ast::Error> {
+    let (rows, cols) = (rows.trunc(), cols.trunc());
+    if rows < 0. || cols < 0. || rows > MAX_ROWS || cols > MAX_COLS {
+        return Err(ast::Error::Value);
+    }
+    if rows == 0. || cols == 0. {
+        return Err(ast::Error::Calc);
+    }
+    Ok((rows as usize, cols as usize))
+}
+
+fn get_descending(order: f64) -> Result<bool, ast::Error> {
+    match order as i64 {
+        1 => Ok(false),
+        -1 => Ok(true),
+        _ => Err(ast::Error::Value),
+    }
+}
+
+// Numbers go before texts, and texts before booleans. Errors and blanks are
+// at the end in both orders.
+fn sort_cmp(lhs: &Value, rhs: &Value, descending: bool) -> Ordering {
+    fn rank(v: &Value) -> u8 {
+        match v {
+            Value::Number(_) | Value::Date(_) => 0,
+            Value::Text(_) => 1,
+            Value::Boolean(_) => 2,
+            Value::Error(_) => 3,
+            Value::Blank => 4,
+        }
+    }
+    let (l, r) = (rank(lhs), rank(rhs));
+    if l != r {
+        return if l < 3 && r < 3 && descending {
+            r.cmp(&l)
+        } else {
+            l.cmp(&r)
+        };
+    }
+    let ordering = match (lhs, rhs) {
+        (Value::Number(l), Value::Number(r)) => l.partial_cmp(r).unwrap_or(Ordering::Equal),
+        (Value::Date(l), Value::Date(r)) => l.cmp(r),
+        (Value::Text(l), Value::Text(r)) => l.to_lowercase().cmp(&r.to_lowercase()),
+        (Value::Boolean(l), Value::Boolean(r)) => l.cmp(r),
+        _ => Ordering::Equal,
+    };
+    if descending {
+        ordering.reverse()
+    } else {
+        ordering
+    }
+}
+
+// Texts are compared case-insensitively.
+fn is_same_row(lhs: &[Value], rhs: &[Value]) -> bool {
+    lhs.iter().zip(rhs.iter()).all(|(l, r)| match (l, r) {
+        (Value::Blank, Value::Blank) => true,
+        (Value::Number(l), Value::Number(r)) => l == r,
+        (Value::Date(l), Value::Date(r)) => l == r,
+        (Value::Text(l), Value::Text(r)) => l.to_lowercase() == r.to_lowercase(),
+        (Value::Boolean(l), Value::Boolean(r)) => l == r,
+        (Value::Error(l), Value::Error(r)) => l == r,
+        _ => false,
+    })
+}
@@ -0,0 +1,94 @@
+use super::condition::match_text_pattern_prefix;
+use super::len::char_bytes;
+use super::utils::{get_scalar_f64, get_scalar_text};
+use crate::calc_engine::calculator::calc_vertex::CalcVertex;
+use crate::calc_engine::connector::Connector;
+use logisheets_parser::ast;
+use unicode_segmentation::UnicodeSegmentation;
+
+/// FIND(find_text, within_text, [start_num]) is case-sensitive and does not
+/// allow wildcards.
+pub fn calc_find<C>(args: Vec<CalcVertex>, fetcher: &mut C) -> CalcVertex
+where
+    C: Connector,
+{
+    calc(args, fetcher, |find, within, start| {
+        let find = find.graphemes(true).collect::<Vec<_>>();
+        let within = within.graphemes(true).collect::<Vec<_>>();
+        if start > within.len() {
+            return None;
+        }
+        (start..=within.len() - find.len().min(within.len()))
+            .find(|i| within[*i..].starts_with(&find))
+    })
+}
+
+/// FINDB works like FIND but counts the positions in bytes.
+pub fn calc_findb<C>(args: Vec<CalcVertex>, fetcher: &mut C) -> CalcVertex
+where
+    C: Connector,
+{
+    calc(args, fetcher, |find, within, start| {
+        let find = find.chars().collect::<Vec<_>>();
+        let within = within.chars().collect::<Vec<_>>();
+        let mut pos = 0;
+        for i in 0..=within.len() {
+            if pos >= start && within[i..].starts_with(&find) {
+                return Some(pos);
+            }
+            if let Some(c) = within.get(i) {
+                pos += char_bytes(*c);
+            }
+        }
+        None
+    })
+}
+
+/// SEARCH(find_text, within_text, [start_num]) is case-insensitive and
+/// `find_text` can contain the wildcards `?` and `*`.
+pub fn calc_search<C>(args: Vec<CalcVertex>, fetcher: &mut C) -> CalcVertex
+where
+    C: Connector,
+{
+    calc(args, fetcher, |find, within, start| {
+        let within = within.graphemes(true).collect::<Vec<_>>();
+        if start > within.len() {
+            return None;
+        }
+        if find.is_empty() {
+            return Some(start);
+        }
+        (start..within.len()).find(|i| match_text_pattern_prefix(&find, &within[*i..].concat()))
+    })
+}
+
+// `func` takes the 0-based start position and returns the 0-based position
+// found.
+fn calc<C, F>(args: Vec<CalcVertex>, fetcher: &mut C, func: F) -> CalcVertex
+where
+    C: Connector,
+    F: Fn(String, String, usize) -> Option<usize>,
+{
+    assert_or_return!(args.len() == 2 || args.len() == 3, ast::Error::Unspecified);
+    let mut args_iter = args.into_iter();
+    let find = match get_scalar_text(fetcher, args_iter.next().unwrap()) {
+        Ok(t) => t,
+        Err(e) => return CalcVertex::from_error(e),
+    };
+    let within = match get_scalar_text(fetcher, args_iter.next().unwrap()) {
+        Ok(t) => t,
+        Err(e) => return CalcVertex::from_error(e),
+    };
+    let start = match args_iter.next() {
+        Some(arg) => match get_scalar_f64(fetcher, arg) {
+            Ok(n) => n.trunc(),
+            Err(e) => return CalcVertex::from_error(e),
+        },
+        None => 1.,
+    };
+    assert_or_return!(start >= 1., ast::Error::Value);
+    match func(find, within, start as usize - 1) {
+        Some(pos) => CalcVertex::from_number((pos + 1) as f64),
+        None => CalcVertex::from_error(ast::Error::Value),
+    }
+}
@@ -0,0 +1,78 @@
+use super::round::round_to_digits;
+use super::utils::get_f64_args;
+use crate::calc_engine::calculator::calc_vertex::CalcVertex;
+use crate::calc_engine::connector::Connector;
+use logisheets_parser::ast;
+
+/// FIXED(number, [decimals], [no_commas])
+pub fn calc_fixed<C>(args: Vec<CalcVertex>, fetcher: &mut C) -> CalcVertex
+where
+    C: Connector,
+{
+    let nums = match get_f64_args(args, fetcher, &[None, Some(2.), Some(0.)]) {
+        Ok(nums) => nums,
+        Err(e) => return CalcVertex::from_error(e),
+    };
+    let (n, decimals, no_commas) = (nums[0], nums[1].trunc(), nums[2] != 0.);
+    assert_or_return!(decimals <= 127., ast::Error::Value);
+    let res = format_number(n.abs(), decimals, !no_commas);
+    if n < 0. && res.chars().any(|c| c.is_ascii_digit() && c != '0') {
+        CalcVertex::from_string(format!("-{}", res))
+    } else {
+        CalcVertex::from_string(res)
+    }
+}
+
+/// DOLLAR(number, [decimals]) uses the currency format `$#,##0.00_);($#,##0.00)`.
+pub fn calc_dollar<C>(args: Vec<CalcVertex>, fetcher: &mut C) -> CalcVertex
+where
+    C: Connector,
+{
+    let nums = match get_f64_args(args, fetcher, &[None, Some(2.)]) {
+        Ok(nums) => nums,
+        Err(e) => return CalcVertex::from_error(e),
+    };
+    let (n, decimals) = (nums[0], nums[1].trunc());
+    assert_or_return!(decimals <= 127., ast::Error::Value);
+    let res = format_number(n.abs(), decimals, true);
+    if n < 0. && res.chars().any(|c| c.is_ascii_digit() && c != '0') {
+        CalcVertex::from_string(format!("(${})", res))
+    } else {
+        CalcVertex::from_string(format!("${}", res))
+    }
+}
+
+// Rounds a non-negative number to the decimals, which can be negative, and
+// formats it.
+fn format_number(n: f64, decimals: f64, commas: bool) -> String {
+    let rounded = round_to_digits(n, decimals, f64::round);
+    let s = format!("{:.*}", decimals.max(0.) as usize, rounded);
+    if !commas {
+        return s;
+    }
+    let (int_part, frac_part) = match s.find('.') {
+        Some(idx) => s.split_at(idx),
+        None => (s.as_str(), ""),
+    };
+    let groups = int_part
+        .as_bytes()
+        .rchunks(3)
+        .rev()
+        .map(|g| std::str::from_utf8(g).unwrap())
+        .collect::<Vec<_>>();
+    groups.join(",") + frac_part
+}
+
+#[cfg(test)]
+mod tests {
+    use super::format_number;
+
+    #[test]
+    fn format_number_test() {
+        assert_eq!(format_number(1234.567, 1., true), "1,234.6");
+        assert_eq!(format_number(1234.567, -1., true), "1,230");
+        assert_eq!(format_number(1234567.0, 2., false), "1234567.00");
+        assert_eq!(format_number(2.675, 2., true), "2.68");
+        assert_eq!(format_number(0.4, 0., true), "0");
+    }
+}
@@ -93,6 +93,7 @@ fn _index(cv: CalcVertex, r: usize, c: usize) -> CalcVertex {
         },
         CalcVertex::Reference(_) => todo!(),
         CalcVertex::Union(_) => unreachable!(),
+        CalcVertex::Lambda(_) => CalcVertex::from_error(ast::Error::Value),
     }
 }
 
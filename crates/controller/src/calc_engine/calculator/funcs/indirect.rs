@@ -0,0 +1,282 @@
+use super::utils::convert_f64;
+use crate::calc_engine::calculator::calc_vertex::{
+    CalcReference, CalcValue, CalcVertex, ColRange, Reference, RowRange, Value,
+};
+use crate::calc_engine::connector::Connector;
+use logisheets_base::{column_label_to_index, Addr};
+use logisheets_parser::ast;
+use regex::Regex;
+
+lazy_static! {
+    static ref A1_CELL_REGEX: Regex = Regex::new(r"^\$?([A-Z]{1,3})\$?([0-9]+)$").unwrap();
+    static ref A1_COL_REGEX: Regex = Regex::new(r"^\$?([A-Z]{1,3})$").unwrap();
+    static ref A1_ROW_REGEX: Regex = Regex::new(r"^\$?([0-9]+)$").unwrap();
+    static ref R1C1_REGEX: Regex =
+        Regex::new(r"^(R(\[-?[0-9]+\]|[0-9]+)?)?(C(\[-?[0-9]+\]|[0-9]+)?)?$").unwrap();
+}
+
+/// INDIRECT(ref_text, [a1])
+///
+/// Like OFFSET, the reference is registered to the connector as a dynamic
+/// dependency.
+pub fn calc<C>(args: Vec<CalcVertex>, fetcher: &mut C) -> CalcVertex
+where
+    C: Connector,
+{
+    assert_or_return!(args.len() == 1 || args.len() == 2, ast::Error::Unspecified);
+    let mut args_iter = args.into_iter();
+    let text = match fetcher.get_calc_value(args_iter.next().unwrap()) {
+        CalcValue::Scalar(Value::Text(t)) => t,
+        CalcValue::Scalar(Value::Error(e)) => return CalcVertex::from_error(e),
+        _ => return CalcVertex::from_error(ast::Error::Ref),
+    };
+    let a1 = match args_iter.next() {
+        Some(arg) => match fetcher.get_calc_value(arg) {
+            CalcValue::Scalar(v) => match convert_f64(v) {
+                Ok(n) => n != 0.,
+                Err(e) => return CalcVertex::from_error(e),
+            },
+            _ => return CalcVertex::from_error(ast::Error::Value),
+        },
+        None => true,
+    };
+    let (sheet_name, ref_text) = split_sheet_name(text.trim());
+    let sheet = match sheet_name {
+        Some(name) => match fetcher.get_sheet_id_by_name(&name) {
+            Some(id) => id,
+            None => return CalcVertex::from_error(ast::Error::Ref),
+        },
+        None => fetcher.get_active_sheet(),
+    };
+    let ref_text = ref_text.to_uppercase();
+    let reference = if a1 {
+        parse_a1(&ref_text)
+    } else {
+        parse_r1c1(&ref_text, fetcher.get_curr_addr())
+    };
+    match reference {
+        Some(reference) => {
+            let r = CalcReference {
+                from_sheet: None,
+                sheet,
+                reference,
+            };
+            fetcher.add_dynamic_dep(&r);
+            CalcVertex::Reference(r)
+        }
+        None => CalcVertex::from_error(ast::Error::Ref),
+    }
+}
+
+fn split_sheet_name(text: &str) -> (Option<String>, &str) {
+    match text.rfind('!') {
+        Some(idx) => {
+            let sheet = &text[..idx];
+            let sheet = if sheet.len() >= 2 && sheet.starts_with('\'') && sheet.ends_with('\'') {
+                sheet[1..sheet.len() - 1].replace("''", "'")
+            } else {
+                sheet.to_string()
+            };
+            (Some(sheet), &text[idx + 1..])
+        }
+        None => (None, text),
+    }
+}
+
+fn parse_a1(text: &str) -> Option<Reference> {
+    let mut parts = text.split(':');
+    let first = parts.next()?;
+    let second = parts.next();
+    if parts.next().is_some() {
+        return None;
+    }
+    match second {
+        None => parse_a1_cell(first).map(Reference::Addr),
+        Some(second) => {
+            if let (Some(s), Some(e)) = (parse_a1_cell(first), parse_a1_cell(second)) {
+                return Some(build_range(s, e));
+            }
+            if let (Some(s), Some(e)) =
+                (A1_COL_REGEX.captures(first), A1_COL_REGEX.captures(second))
+            {
+                let s = column_label_to_index(&s[1]);
+                let e = column_label_to_index(&e[1]);
+                return Some(Reference::ColumnRange(ColRange {
+                    start: s.min(e),
+                    end: s.max(e),
+                }));
+            }
+            if let (Some(s), Some(e)) =
+                (A1_ROW_REGEX.captures(first), A1_ROW_REGEX.captures(second))
+            {
+                let s = s[1].parse::<usize>().ok()?.checked_sub(1)?;
+                let e = e[1].parse::<usize>().ok()?.checked_sub(1)?;
+                return Some(Reference::RowRange(RowRange {
+                    start: s.min(e),
+                    end: s.max(e),
+                }));
+            }
+            None
+        }
+    }
+}
+
+fn parse_a1_cell(text: &str) -> Option<Addr> {
+    let caps = A1_CELL_REGEX.captures(text)?;
+    let col = column_label_to_index(&caps[1]);
+    let row = caps[2].parse::<usize>().ok()?.checked_sub(1)?;
+    Some(Addr { row, col })
+}
+
+enum R1C1Part {
+    Cell(Addr),
+    Row(usize),
+    Col(usize),
+}
+
+fn parse_r1c1(text: &str, curr: Addr) -> Option<Reference> {
+    let mut parts = text.split(':');
+    let first = parse_r1c1_part(parts.next()?, curr)?;
+    let second = match parts.next() {
+        Some(p) => Some(parse_r1c1_part(p, curr)?),
+        None => None,
+    };
+    if parts.next().is_some() {
+        return None;
+    }
+    match (first, second) {
+        (R1C1Part::Cell(addr), None) => Some(Reference::Addr(addr)),
+        (R1C1Part::Row(r), None) => Some(Reference::RowRange(RowRange { start: r, end: r })),
+        (R1C1Part::Col(c), None) => Some(Reference::ColumnRange(ColRange { start: c, end: c })),
+        (R1C1Part::Cell(s), Some(R1C1Part::Cell(e))) => Some(build_range(s, e)),
+        (R1C1Part::Row(s), Some(R1C1Part::Row(e))) => Some(Reference::RowRange(RowRange {
+            start: s.min(e),
+            end: s.max(e),
+        })),
+        (R1C1Part::Col(s), Some(R1C1Part::Col(e))) => Some(Reference::ColumnRange(ColRange {
+            start: s.min(e),
+            end: s.max(e),
+        })),
+        _ => None,
+    }
+}
+
+fn parse_r1c1_part(text: &str, curr: Addr) -> Option<R1C1Part> {
+    if text.is_empty() {
+        return None;
+    }
+    let caps = R1C1_REGEX.captures(text)?;
+    let row = match caps.get(1) {
+        Some(_) => Some(parse_r1c1_index(caps.get(2).map(|m| m.as_str()), curr.row)?),
+        None => None,
+    };
+    let col = match caps.get(3) {
+        Some(_) => Some(parse_r1c1_index(caps.get(4).map(|m| m.as_str()), curr.col)?),
+        None => None,
+    };
+    match (row, col) {
+        (Some(row), Some(col)) => Some(R1C1Part::Cell(Addr { row, col })),
+        (Some(row), None) => Some(R1C1Part::Row(row)),
+        (None, Some(col)) => Some(R1C1Part::Col(col)),
+        (None, None) => None,
+    }
+}
+
+// `R` means the current row, `R[n]` is relative to it and `Rn` is absolute.
+fn parse_r1c1_index(text: Option<&str>, curr: usize) -> Option<usize> {
+    match text {
+        None => Some(curr),
+        Some(t) if t.starts_with('[') => {
+            let delta = t[1..t.len() - 1].parse::<i64>().ok()?;
+            let r = curr as i64 + delta;
+            if r < 0 {
+                None
+            } else {
+                Some(r as usize)
+            }
+        }
+        Some(t) => t.parse::<usize>().ok()?.checked_sub(1),
+    }
+}
+
+fn build_range(s: Addr, e: Addr) -> Reference {
+    let start = Addr {
+        row: s.row.min(e.row),
+        col: s.col.min(e.col),
+    };
+    let end = Addr {
+        row: s.row.max(e.row),
+        col: s.col.max(e.col),
+    };
+    if start.row == end.row && start.col == end.col {
+        Reference::Addr(start)
+    } else {
+        Reference::Range(start, end)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_a1, parse_r1c1, split_sheet_name, Reference};
+    use logisheets_base::Addr;
+
+    #[test]
+    fn parse_a1_test() {
+        assert!(matches!(
+            parse_a1("$B$3"),
+            Some(Reference::Addr(Addr { row: 2, col: 1 }))
+        ));
+        assert!(matches!(
+            parse_a1("C5:A1"),
+            Some(Reference::Range(
+                Addr { row: 0, col: 0 },
+                Addr { row: 4, col: 2 }
+            ))
+        ));
+        assert!(matches!(
+            parse_a1("B:D"),
+            Some(Reference::ColumnRange(r)) if r.start == 1 && r.end == 3
+        ));
+        assert!(matches!(
+            parse_a1("2:2"),
+            Some(Reference::RowRange(r)) if r.start == 1 && r.end == 1
+        ));
+        assert!(parse_a1("A0").is_none());
+        assert!(parse_a1("hello").is_none());
+    }
+
+    #[test]
+    fn parse_r1c1_test() {
+        let curr = Addr { row: 4, col: 4 };
+        assert!(matches!(
+            parse_r1c1("R1C2", curr),
+            Some(Reference::Addr(Addr { row: 0, col: 1 }))
+        ));
+        assert!(matches!(
+            parse_r1c1("R[-1]C", curr),
+            Some(Reference::Addr(Addr { row: 3, col: 4 }))
+        ));
+        assert!(matches!(
+            parse_r1c1("R1C1:R[1]C[1]", curr),
+            Some(Reference::Range(
+                Addr { row: 0, col: 0 },
+                Addr { row: 5, col: 5 }
+            ))
+        ));
+        assert!(matches!(
+            parse_r1c1("C2", curr),
+            Some(Reference::ColumnRange(r)) if r.start == 1 && r.end == 1
+        ));
+        assert!(parse_r1c1("R[-9]C", curr).is_none());
+    }
+
+    #[test]
+    fn split_sheet_name_test() {
+        let (sheet, r) = split_sheet_name("'It''s'!A1");
+        assert_eq!(sheet.unwrap(), "It's");
+        assert_eq!(r, "A1");
+        let (sheet, r) = split_sheet_name("B2");
+        assert!(sheet.is_none());
+        assert_eq!(r, "B2");
+    }
+}
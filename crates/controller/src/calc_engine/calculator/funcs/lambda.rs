@@ -0,0 +1,233 @@
+use super::dynamic_array::{get_array, get_size, size, to_vertex, transpose};
+use super::utils::get_f64_args;
+use crate::calc_engine::calculator::calc_vertex::{CalcValue, CalcVertex, Lambda, Value};
+use crate::calc_engine::calculator::calculator::call_lambda;
+use crate::calc_engine::connector::Connector;
+use logisheets_parser::ast;
+
+// The functions calling a LAMBDA with the elements of arrays.
+
+/// MAP(array1, [array2, ...], lambda)
+///
+/// The elements out of the smaller arrays are #N/A.
+pub fn calc_map<C>(args: Vec<CalcVertex>, fetcher: &mut C) -> CalcVertex
+where
+    C: Connector,
+{
+    assert_or_return!(args.len() >= 2, ast::Error::Unspecified);
+    let mut args = args;
+    let lambda = match get_lambda(args.pop().unwrap()) {
+        Ok(l) => l,
+        Err(e) => return CalcVertex::from_error(e),
+    };
+    let mut arrays = Vec::with_capacity(args.len());
+    for arg in args {
+        match get_array(fetcher, arg) {
+            Ok(array) => arrays.push(array),
+            Err(e) => return CalcVertex::from_error(e),
+        }
+    }
+    let (rows, cols) = arrays.iter().fold((0, 0), |(rows, cols), array| {
+        let (r, c) = size(array);
+        (rows.max(r), cols.max(c))
+    });
+    let result = (0..rows)
+        .map(|i| {
+            (0..cols)
+                .map(|j| {
+                    let elements = arrays
+                        .iter()
+                        .map(|array| array.get(i).and_then(|row| row.get(j)))
+                        .collect::<Option<Vec<_>>>();
+                    match elements {
+                        Some(elements) => {
+                            let args = elements.into_iter().cloned().map(to_scalar).collect();
+                            call_for_value(&lambda, args, fetcher)
+                        }
+                        None => Value::Error(ast::Error::Na),
+                    }
+                })
+                .collect()
+        })
+        .collect();
+    to_vertex(result)
+}
+
+/// REDUCE([initial_value], array, lambda)
+///
+/// The accumulator can be an array.
+pub fn calc_reduce<C>(args: Vec<CalcVertex>, fetcher: &mut C) -> CalcVertex
+where
+    C: Connector,
+{
+    let (initial, array, lambda) = match get_accumulate_args(args, fetcher) {
+        Ok(r) => r,
+        Err(e) => return CalcVertex::from_error(e),
+    };
+    array.into_iter().flatten().fold(initial, |acc, v| {
+        call_lambda(&lambda, vec![acc, to_scalar(v)], fetcher)
+    })
+}
+
+/// SCAN([initial_value], array, lambda)
+///
+/// Return the accumulator after each element, in the shape of the array.
+pub fn calc_scan<C>(args: Vec<CalcVertex>, fetcher: &mut C) -> CalcVertex
+where
+    C: Connector,
+{
+    let (initial, array, lambda) = match get_accumulate_args(args, fetcher) {
+        Ok(r) => r,
+        Err(e) => return CalcVertex::from_error(e),
+    };
+    let mut acc = initial;
+    let result = array
+        .into_iter()
+        .map(|row| {
+            row.into_iter()
+                .map(|v| {
+                    let value = call_for_value(&lambda, vec![acc.clone(), to_scalar(v)], fetcher);
+                    acc = to_scalar(value.clone());
+                    value
+                })
+                .collect()
+        })
+        .collect();
+    to_vertex(result)
+}
+
+/// BYROW(array, lambda)
+pub fn calc_byrow<C>(args: Vec<CalcVertex>, fetcher: &mut C) -> CalcVertex
+where
+    C: Connector,
+{
+    let (array, lambda) = match get_array_and_lambda(args, fetcher) {
+        Ok(r) => r,
+        Err(e) => return CalcVertex::from_error(e),
+    };
+    let result = array
+        .into_iter()
+        .map(|row| vec![call_for_value(&lambda, vec![to_vertex(vec![row])], fetcher)])
+        .collect();
+    to_vertex(result)
+}
+
+/// BYCOL(array, lambda)
+pub fn calc_bycol<C>(args: Vec<CalcVertex>, fetcher: &mut C) -> CalcVertex
+where
+    C: Connector,
+{
+    let (array, lambda) = match get_array_and_lambda(args, fetcher) {
+        Ok(r) => r,
+        Err(e) => return CalcVertex::from_error(e),
+    };
+    let result = transpose(array)
+        .into_iter()
+        .map(|col| {
+            let col = col.into_iter().map(|v| vec![v]).collect();
+            call_for_value(&lambda, vec![to_vertex(col)], fetcher)
+        })
+        .collect();
+    to_vertex(vec![result])
+}
+
+/// MAKEARRAY(rows, columns, lambda)
+///
+/// The LAMBDA is called with the 1-based row and column indexes.
+pub fn calc_makearray<C>(args: Vec<CalcVertex>, fetcher: &mut C) -> CalcVertex
+where
+    C: Connector,
+{
+    assert_or_return!(args.len() == 3, ast::Error::Unspecified);
+    let mut args = args;
+    let lambda = match get_lambda(args.pop().unwrap()) {
+        Ok(l) => l,
+        Err(e) => return CalcVertex::from_error(e),
+    };
+    let nums = match get_f64_args(args, fetcher, &[None, None]) {
+        Ok(nums) => nums,
+        Err(e) => return CalcVertex::from_error(e),
+    };
+    let (rows, cols) = match get_size(nums[0], nums[1]) {
+        Ok(s) => s,
+        Err(e) => return CalcVertex::from_error(e),
+    };
+    let result = (1..rows + 1)
+        .map(|i| {
+            (1..cols + 1)
+                .map(|j| {
+                    let args = vec![
+                        CalcVertex::from_number(i as f64),
+                        CalcVertex::from_number(j as f64),
+                    ];
+                    call_for_value(&lambda, args, fetcher)
+                })
+                .collect()
+        })
+        .collect();
+    to_vertex(result)
+}
+
+fn get_lambda(arg: CalcVertex) -> Result<Lambda, ast::Error> {
+    match arg {
+        CalcVertex::Lambda(lambda) => Ok(lambda),
+        CalcVertex::Value(CalcValue::Scalar(Value::Error(e))) => Err(e),
+        _ => Err(ast::Error::Value),
+    }
+}
+
+fn get_array_and_lambda<C>(
+    args: Vec<CalcVertex>,
+    fetcher: &mut C,
+) -> Result<(Vec<Vec<Value>>, Lambda), ast::Error>
+where
+    C: Connector,
+{
+    if args.len() != 2 {
+        return Err(ast::Error::Unspecified);
+    }
+    let mut iter = args.into_iter();
+    let array = get_array(fetcher, iter.next().unwrap())?;
+    let lambda = get_lambda(iter.next().unwrap())?;
+    Ok((array, lambda))
+}
+
+// The initial value of the accumulator is blank if omitted.
+fn get_accumulate_args<C>(
+    args: Vec<CalcVertex>,
+    fetcher: &mut C,
+) -> Result<(CalcVertex, Vec<Vec<Value>>, Lambda), ast::Error>
+where
+    C: Connector,
+{
+    let (initial, args) = match args.len() {
+        2 => (CalcVertex::Value(CalcValue::Scalar(Value::Blank)), args),
+        3 => {
+            let mut iter = args.into_iter();
+            (iter.next().unwrap(), iter.collect())
+        }
+        _ => return Err(ast::Error::Unspecified),
+    };
+    let (array, lambda) = get_array_and_lambda(args, fetcher)?;
+    Ok((initial, array, lambda))
+}
+
+fn to_scalar(v: Value) -> CalcVertex {
+    CalcVertex::Value(CalcValue::Scalar(v))
+}
+
+// Each call for an element of the result should return a single value.
+fn call_for_value<C>(lambda: &Lambda, args: Vec<CalcVertex>, fetcher: &mut C) -> Value
+where
+    C: Connector,
+{
+    let result = call_lambda(lambda, args, fetcher);
+    match fetcher.get_calc_value(result) {
+        CalcValue::Scalar(v) => v,
+        CalcValue::Range(r) if r.get_size() == (1, 1) => match r.visit(0, 0) {
+            Ok(v) => v.clone(),
+            Err(v) => v,
+        },
+        _ => Value::Error(ast::Error::Calc),
+    }
+}
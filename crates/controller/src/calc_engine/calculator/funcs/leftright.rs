@@ -1,4 +1,6 @@
-use crate::calc_engine::calculator::calc_vertex::{CalcValue, CalcVertex, Value};
+use super::len::{char_bytes, take_bytes};
+use super::utils::{get_scalar_f64, get_scalar_text};
+use crate::calc_engine::calculator::calc_vertex::CalcVertex;
 use crate::calc_engine::connector::Connector;
 use logisheets_parser::ast;
 use unicode_segmentation::UnicodeSegmentation;
@@ -7,9 +9,9 @@ pub fn calc_left<C>(args: Vec<CalcVertex>, fetcher: &mut C) -> CalcVertex
 where
     C: Connector,
 {
-    let func = |s: String, n: u8| -> String {
+    let func = |s: String, n: usize| -> String {
         s.graphemes(true)
-            .take(n as usize)
+            .take(n)
             .fold(String::from(""), |mut prev, g| {
                 prev.push_str(g);
                 prev
@@ -22,16 +24,31 @@ pub fn calc_right<C>(args: Vec<CalcVertex>, fetcher: &mut C) -> CalcVertex
 where
     C: Connector,
 {
-    let func = |s: String, n: u8| -> String {
+    let func = |s: String, n: usize| -> String {
         let graphemes = s.graphemes(true);
-        graphemes
-            .rev()
-            .take(n as usize)
-            .fold(String::from(""), |prev, g| {
-                let mut curr = g.to_string();
-                curr = curr + &prev;
-                curr
-            })
+        graphemes.rev().take(n).fold(String::from(""), |prev, g| {
+            let mut curr = g.to_string();
+            curr = curr + &prev;
+            curr
+        })
+    };
+    calc(args, fetcher, func)
+}
+
+pub fn calc_leftb<C>(args: Vec<CalcVertex>, fetcher: &mut C) -> CalcVertex
+where
+    C: Connector,
+{
+    calc(args, fetcher, |s, n| take_bytes(&s, 0, n))
+}
+
+pub fn calc_rightb<C>(args: Vec<CalcVertex>, fetcher: &mut C) -> CalcVertex
+where
+    C: Connector,
+{
+    let func = |s: String, n: usize| -> String {
+        let total = s.chars().map(char_bytes).sum::<usize>();
+        take_bytes(&s, total.saturating_sub(n), total)
     };
     calc(args, fetcher, func)
 }
@@ -39,14 +56,22 @@ where
 fn calc<C, F>(args: Vec<CalcVertex>, fetcher: &mut C, func: F) -> CalcVertex
 where
     C: Connector,
-    F: Fn(String, u8) -> String,
+    F: Fn(String, usize) -> String,
 {
-    assert_or_return!(args.len() == 2, ast::Error::Unspecified);
+    assert_or_return!(args.len() == 1 || args.len() == 2, ast::Error::Unspecified);
     let mut args_iter = args.into_iter();
-    let first = fetcher.get_calc_value(args_iter.next().unwrap());
-    assert_text_from_calc_value!(s, first);
-    let second = fetcher.get_calc_value(args_iter.next().unwrap());
-    assert_f64_from_calc_value!(n, second);
-    let res = func(s, n as u8);
+    let s = match get_scalar_text(fetcher, args_iter.next().unwrap()) {
+        Ok(s) => s,
+        Err(e) => return CalcVertex::from_error(e),
+    };
+    let n = match args_iter.next() {
+        Some(arg) => match get_scalar_f64(fetcher, arg) {
+            Ok(n) => n.trunc(),
+            Err(e) => return CalcVertex::from_error(e),
+        },
+        None => 1.,
+    };
+    assert_or_return!(n >= 0., ast::Error::Value);
+    let res = func(s, n as usize);
     CalcVertex::from_string(res)
 }
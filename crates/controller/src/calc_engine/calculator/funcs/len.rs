@@ -21,7 +21,7 @@ where
 fn calc<C, F>(args: Vec<CalcVertex>, fetcher: &mut C, func: F) -> CalcVertex
 where
     C: Connector,
-    F: 'static + Fn(&str) -> usize,
+    F: 'static + Fn(&str) -> usize + Send + Sync,
 {
     if args.len() != 1 {
         return CalcVertex::from_error(ast::Error::Unspecified);
@@ -50,7 +50,33 @@ fn len(t: &str) -> usize {
 }
 
 fn lenb(t: &str) -> usize {
-    t.len()
+    t.chars().map(char_bytes).sum()
+}
+
+/// The byte count of a char in a DBCS code page. ASCII and half-width
+/// katakana take 1 byte and the others take 2.
+pub fn char_bytes(c: char) -> usize {
+    match c as u32 {
+        0..=0x7f | 0xff61..=0xff9f => 1,
+        _ => 2,
+    }
+}
+
+/// Take the chars in the byte window `[start, end)`, 0-based. A double-byte
+/// char cut by the border of the window turns into a space.
+pub fn take_bytes(t: &str, start: usize, end: usize) -> String {
+    let mut result = String::new();
+    let mut pos = 0;
+    t.chars().for_each(|c| {
+        let next = pos + char_bytes(c);
+        if pos >= start && next <= end {
+            result.push(c);
+        } else if pos < end && next > start {
+            result.push(' ');
+        }
+        pos = next;
+    });
+    result
 }
 
 fn len_value<F>(value: &Value, func: &F) -> usize
@@ -75,7 +101,7 @@ where
 
 #[cfg(test)]
 mod tests {
-    use super::{len, lenb};
+    use super::{len, lenb, take_bytes};
 
     #[test]
     fn len_test() {
@@ -87,5 +113,14 @@ mod tests {
     #[test]
     fn lenb_test() {
         assert_eq!(lenb("abcdefg"), 7);
+        assert_eq!(lenb("逻辑汇"), 6);
+        assert_eq!(lenb("a逻b"), 4);
+    }
+
+    #[test]
+    fn take_bytes_test() {
+        assert_eq!(take_bytes("a逻辑b", 1, 5), "逻辑");
+        assert_eq!(take_bytes("a逻辑b", 2, 6), " 辑b");
+        assert_eq!(take_bytes("a逻辑b", 0, 2), "a ");
     }
 }
@@ -0,0 +1,550 @@
+use super::condition::{match_text_pattern, ConditionRange};
+use super::utils::convert_f64;
+use crate::calc_engine::calculator::calc_vertex::{CalcValue, CalcVertex, Value};
+use crate::calc_engine::connector::Connector;
+use logisheets_base::matrix_value::MatrixValue;
+use logisheets_parser::ast;
+use std::cmp::Ordering;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum MatchMode {
+    Exact,
+    ExactOrSmaller,
+    ExactOrLarger,
+    Wildcard,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum SearchMode {
+    FirstToLast,
+    LastToFirst,
+    BinaryAscending,
+    BinaryDescending,
+}
+
+/// MATCH(lookup_value, lookup_array, [match_type])
+pub fn calc_match<C>(args: Vec<CalcVertex>, fetcher: &mut C) -> CalcVertex
+where
+    C: Connector,
+{
+    assert_or_return!(args.len() == 2 || args.len() == 3, ast::Error::Unspecified);
+    let mut args_iter = args.into_iter();
+    let lookup_value = match get_lookup_value(fetcher, args_iter.next().unwrap()) {
+        Ok(v) => v,
+        Err(e) => return CalcVertex::from_error(e),
+    };
+    let array = match get_vector(fetcher, args_iter.next().unwrap()) {
+        Ok(v) => v,
+        Err(e) => return CalcVertex::from_error(e),
+    };
+    let match_type = match args_iter.next() {
+        Some(arg) => match get_num(fetcher, arg) {
+            Ok(n) => n,
+            Err(e) => return CalcVertex::from_error(e),
+        },
+        None => 1.,
+    };
+    let pos = if match_type > 0. {
+        approximate_search(&array, &lookup_value, false)
+    } else if match_type < 0. {
+        approximate_search(&array, &lookup_value, true)
+    } else {
+        linear_search(&array, &lookup_value, MatchMode::Wildcard, false)
+    };
+    match pos {
+        Some(p) => CalcVertex::from_number((p + 1) as f64),
+        None => CalcVertex::from_error(ast::Error::Na),
+    }
+}
+
+/// XMATCH(lookup_value, lookup_array, [match_mode], [search_mode])
+pub fn calc_xmatch<C>(args: Vec<CalcVertex>, fetcher: &mut C) -> CalcVertex
+where
+    C: Connector,
+{
+    assert_or_return!(args.len() >= 2 && args.len() <= 4, ast::Error::Unspecified);
+    let mut args_iter = args.into_iter();
+    let lookup_value = match get_lookup_value(fetcher, args_iter.next().unwrap()) {
+        Ok(v) => v,
+        Err(e) => return CalcVertex::from_error(e),
+    };
+    let array = match get_vector(fetcher, args_iter.next().unwrap()) {
+        Ok(v) => v,
+        Err(e) => return CalcVertex::from_error(e),
+    };
+    let modes = get_modes(fetcher, args_iter.next(), args_iter.next());
+    let (match_mode, search_mode) = match modes {
+        Ok(m) => m,
+        Err(e) => return CalcVertex::from_error(e),
+    };
+    match search(&array, &lookup_value, match_mode, search_mode) {
+        Some(p) => CalcVertex::from_number((p + 1) as f64),
+        None => CalcVertex::from_error(ast::Error::Na),
+    }
+}
+
+/// XLOOKUP(lookup_value, lookup_array, return_array, [if_not_found],
+/// [match_mode], [search_mode])
+pub fn calc_xlookup<C>(args: Vec<CalcVertex>, fetcher: &mut C) -> CalcVertex
+where
+    C: Connector,
+{
+    assert_or_return!(args.len() >= 3 && args.len() <= 6, ast::Error::Unspecified);
+    let mut args_iter = args.into_iter();
+    let lookup_value = match get_lookup_value(fetcher, args_iter.next().unwrap()) {
+        Ok(v) => v,
+        Err(e) => return CalcVertex::from_error(e),
+    };
+    let lookup_array = fetcher.get_calc_value(args_iter.next().unwrap());
+    let lookup_array = match ConditionRange::from_calc_value(lookup_array) {
+        Ok(r) => r,
+        Err(e) => return CalcVertex::from_error(e),
+    };
+    let return_array = fetcher.get_calc_value(args_iter.next().unwrap());
+    let return_array = match ConditionRange::from_calc_value(return_array) {
+        Ok(r) => r,
+        Err(e) => return CalcVertex::from_error(e),
+    };
+    let if_not_found = args_iter.next();
+    let modes = get_modes(fetcher, args_iter.next(), args_iter.next());
+    let (match_mode, search_mode) = match modes {
+        Ok(m) => m,
+        Err(e) => return CalcVertex::from_error(e),
+    };
+    let (rows, cols) = lookup_array.size;
+    assert_or_return!(rows == 1 || cols == 1, ast::Error::Value);
+    let vertical = cols == 1 && (rows > 1 || return_array.size.0 == 1);
+    if vertical {
+        assert_or_return!(return_array.size.0 == rows, ast::Error::Value);
+    } else {
+        assert_or_return!(return_array.size.1 == cols, ast::Error::Value);
+    }
+    match search(&lookup_array.values, &lookup_value, match_mode, search_mode) {
+        Some(p) => {
+            let (r_rows, r_cols) = return_array.size;
+            let values = if vertical {
+                (0..r_cols)
+                    .map(|j| return_array.get(p, j))
+                    .collect::<Vec<_>>()
+            } else {
+                (0..r_rows)
+                    .map(|i| return_array.get(i, p))
+                    .collect::<Vec<_>>()
+            };
+            if values.len() == 1 {
+                return get_result(values.into_iter().next().unwrap());
+            }
+            let matrix = if vertical {
+                vec![values]
+            } else {
+                values.into_iter().map(|v| vec![v]).collect()
+            };
+            CalcVertex::Value(CalcValue::Range(MatrixValue::from(matrix)))
+        }
+        None => match if_not_found {
+            Some(v) => CalcVertex::Value(fetcher.get_calc_value(v)),
+            None => CalcVertex::from_error(ast::Error::Na),
+        },
+    }
+}
+
+/// VLOOKUP(lookup_value, table_array, col_index_num, [range_lookup])
+pub fn calc_vlookup<C>(args: Vec<CalcVertex>, fetcher: &mut C) -> CalcVertex
+where
+    C: Connector,
+{
+    calc_table_lookup(args, fetcher, true)
+}
+
+/// HLOOKUP(lookup_value, table_array, row_index_num, [range_lookup])
+pub fn calc_hlookup<C>(args: Vec<CalcVertex>, fetcher: &mut C) -> CalcVertex
+where
+    C: Connector,
+{
+    calc_table_lookup(args, fetcher, false)
+}
+
+fn calc_table_lookup<C>(args: Vec<CalcVertex>, fetcher: &mut C, vertical: bool) -> CalcVertex
+where
+    C: Connector,
+{
+    assert_or_return!(args.len() == 3 || args.len() == 4, ast::Error::Unspecified);
+    let mut args_iter = args.into_iter();
+    let lookup_value = match get_lookup_value(fetcher, args_iter.next().unwrap()) {
+        Ok(v) => v,
+        Err(e) => return CalcVertex::from_error(e),
+    };
+    let table = fetcher.get_calc_value(args_iter.next().unwrap());
+    let table = match ConditionRange::from_calc_value(table) {
+        Ok(r) => r,
+        Err(e) => return CalcVertex::from_error(e),
+    };
+    let idx = match get_num(fetcher, args_iter.next().unwrap()) {
+        Ok(n) => n.trunc(),
+        Err(e) => return CalcVertex::from_error(e),
+    };
+    let approximate = match args_iter.next() {
+        Some(arg) => match get_num(fetcher, arg) {
+            Ok(n) => n != 0.,
+            Err(e) => return CalcVertex::from_error(e),
+        },
+        None => true,
+    };
+    let (rows, cols) = table.size;
+    let limit = if vertical { cols } else { rows };
+    assert_or_return!(idx >= 1., ast::Error::Value);
+    assert_or_return!(idx <= limit as f64, ast::Error::Ref);
+    let idx = idx as usize - 1;
+    let keys = if vertical {
+        (0..rows).map(|i| table.get(i, 0)).collect::<Vec<_>>()
+    } else {
+        (0..cols).map(|j| table.get(0, j)).collect::<Vec<_>>()
+    };
+    let pos = if approximate {
+        approximate_search(&keys, &lookup_value, false)
+    } else {
+        linear_search(&keys, &lookup_value, MatchMode::Wildcard, false)
+    };
+    match pos {
+        Some(p) if vertical => get_result(table.get(p, idx)),
+        Some(p) => get_result(table.get(idx, p)),
+        None => CalcVertex::from_error(ast::Error::Na),
+    }
+}
+
+/// LOOKUP(lookup_value, lookup_vector, [result_vector]) and
+/// LOOKUP(lookup_value, array)
+pub fn calc_lookup<C>(args: Vec<CalcVertex>, fetcher: &mut C) -> CalcVertex
+where
+    C: Connector,
+{
+    assert_or_return!(args.len() == 2 || args.len() == 3, ast::Error::Unspecified);
+    let mut args_iter = args.into_iter();
+    let lookup_value = match get_lookup_value(fetcher, args_iter.next().unwrap()) {
+        Ok(v) => v,
+        Err(e) => return CalcVertex::from_error(e),
+    };
+    let array = fetcher.get_calc_value(args_iter.next().unwrap());
+    let array = match ConditionRange::from_calc_value(array) {
+        Ok(r) => r,
+        Err(e) => return CalcVertex::from_error(e),
+    };
+    let (rows, cols) = array.size;
+    match args_iter.next() {
+        Some(result_vector) => {
+            assert_or_return!(rows == 1 || cols == 1, ast::Error::Na);
+            let result = match get_vector(fetcher, result_vector) {
+                Ok(v) => v,
+                Err(e) => return CalcVertex::from_error(e),
+            };
+            match approximate_search(&array.values, &lookup_value, false) {
+                Some(p) if p < result.len() => get_result(result[p].clone()),
+                _ => CalcVertex::from_error(ast::Error::Na),
+            }
+        }
+        None => {
+            // The array form searches the first row when the array is wider
+            // than it is tall, and the first column otherwise. The result
+            // comes from the last row or column.
+            let by_row = cols > rows;
+            let keys = if by_row {
+                (0..cols).map(|j| array.get(0, j)).collect::<Vec<_>>()
+            } else {
+                (0..rows).map(|i| array.get(i, 0)).collect::<Vec<_>>()
+            };
+            match approximate_search(&keys, &lookup_value, false) {
+                Some(p) if by_row => get_result(array.get(rows - 1, p)),
+                Some(p) => get_result(array.get(p, cols - 1)),
+                None => CalcVertex::from_error(ast::Error::Na),
+            }
+        }
+    }
+}
+
+/// CHOOSE(index_num, value1, [value2], ...). The chosen argument is returned
+/// as it is, so that a chosen reference can still be used as a reference.
+pub fn calc_choose<C>(args: Vec<CalcVertex>, fetcher: &mut C) -> CalcVertex
+where
+    C: Connector,
+{
+    assert_or_return!(args.len() >= 2, ast::Error::Unspecified);
+    let mut args_iter = args.into_iter();
+    let idx = match get_num(fetcher, args_iter.next().unwrap()) {
+        Ok(n) => n.trunc(),
+        Err(e) => return CalcVertex::from_error(e),
+    };
+    assert_or_return!(idx >= 1., ast::Error::Value);
+    match args_iter.nth(idx as usize - 1) {
+        Some(v) => v,
+        None => CalcVertex::from_error(ast::Error::Value),
+    }
+}
+
+fn get_lookup_value<C>(fetcher: &mut C, arg: CalcVertex) -> Result<Value, ast::Error>
+where
+    C: Connector,
+{
+    let value = match fetcher.get_calc_value(arg) {
+        CalcValue::Scalar(v) => v,
+        CalcValue::Range(r) if r.get_avail_size() == (1, 1) => match r.visit(0, 0) {
+            Ok(v) => v.clone(),
+            Err(v) => v,
+        },
+        _ => return Err(ast::Error::Value),
+    };
+    match value {
+        Value::Error(e) => Err(e),
+        _ => Ok(value),
+    }
+}
+
+fn get_num<C>(fetcher: &mut C, arg: CalcVertex) -> Result<f64, ast::Error>
+where
+    C: Connector,
+{
+    match fetcher.get_calc_value(arg) {
+        CalcValue::Scalar(v) => convert_f64(v),
+        _ => Err(ast::Error::Value),
+    }
+}
+
+/// Get the values of a single row or a single column.
+fn get_vector<C>(fetcher: &mut C, arg: CalcVertex) -> Result<Vec<Value>, ast::Error>
+where
+    C: Connector,
+{
+    let range = ConditionRange::from_calc_value(fetcher.get_calc_value(arg))?;
+    if range.size.0 != 1 && range.size.1 != 1 {
+        return Err(ast::Error::Na);
+    }
+    Ok(range.values)
+}
+
+fn get_modes<C>(
+    fetcher: &mut C,
+    match_mode: Option<CalcVertex>,
+    search_mode: Option<CalcVertex>,
+) -> Result<(MatchMode, SearchMode), ast::Error>
+where
+    C: Connector,
+{
+    let match_mode = match match_mode {
+        Some(arg) => match get_num(fetcher, arg)? as i64 {
+            0 => MatchMode::Exact,
+            -1 => MatchMode::ExactOrSmaller,
+            1 => MatchMode::ExactOrLarger,
+            2 => MatchMode::Wildcard,
+            _ => return Err(ast::Error::Value),
+        },
+        None => MatchMode::Exact,
+    };
+    let search_mode = match search_mode {
+        Some(arg) => match get_num(fetcher, arg)? as i64 {
+            1 => SearchMode::FirstToLast,
+            -1 => SearchMode::LastToFirst,
+            2 => SearchMode::BinaryAscending,
+            -2 => SearchMode::BinaryDescending,
+            _ => return Err(ast::Error::Value),
+        },
+        None => SearchMode::FirstToLast,
+    };
+    // Wildcards are not supported by the binary search.
+    let binary = matches!(
+        search_mode,
+        SearchMode::BinaryAscending | SearchMode::BinaryDescending
+    );
+    if binary && match_mode == MatchMode::Wildcard {
+        return Err(ast::Error::Value);
+    }
+    Ok((match_mode, search_mode))
+}
+
+// Lookup functions return 0 rather than a blank when the found cell is empty.
+fn get_result(value: Value) -> CalcVertex {
+    match value {
+        Value::Blank => CalcVertex::from_number(0.),
+        _ => CalcVertex::Value(CalcValue::Scalar(value)),
+    }
+}
+
+/// Only values of the same type are comparable in lookups. Texts are compared
+/// case-insensitively.
+fn cmp_value(lhs: &Value, rhs: &Value) -> Option<Ordering> {
+    match (lhs, rhs) {
+        (Value::Number(l), Value::Number(r)) => l.partial_cmp(r),
+        (Value::Text(l), Value::Text(r)) => Some(l.to_lowercase().cmp(&r.to_lowercase())),
+        (Value::Boolean(l), Value::Boolean(r)) => Some(l.cmp(r)),
+        _ => None,
+    }
+}
+
+fn is_exact(value: &Value, lookup_value: &Value, wildcard: bool) -> bool {
+    match (value, lookup_value) {
+        (Value::Text(t), Value::Text(p)) if wildcard => match_text_pattern(p, t),
+        _ => cmp_value(value, lookup_value) == Some(Ordering::Equal),
+    }
+}
+
+fn search(
+    values: &[Value],
+    lookup_value: &Value,
+    match_mode: MatchMode,
+    search_mode: SearchMode,
+) -> Option<usize> {
+    match search_mode {
+        SearchMode::FirstToLast => linear_search(values, lookup_value, match_mode, false),
+        SearchMode::LastToFirst => linear_search(values, lookup_value, match_mode, true),
+        SearchMode::BinaryAscending => binary_search(values, lookup_value, match_mode, false),
+        SearchMode::BinaryDescending => binary_search(values, lookup_value, match_mode, true),
+    }
+}
+
+fn linear_search(
+    values: &[Value],
+    lookup_value: &Value,
+    match_mode: MatchMode,
+    reverse: bool,
+) -> Option<usize> {
+    let indices: Box<dyn Iterator<Item = usize>> = if reverse {
+        Box::new((0..values.len()).rev())
+    } else {
+        Box::new(0..values.len())
+    };
+    let wildcard = match_mode == MatchMode::Wildcard;
+    let mut best: Option<usize> = None;
+    for i in indices {
+        let v = &values[i];
+        if is_exact(v, lookup_value, wildcard) {
+            return Some(i);
+        }
+        let target = match match_mode {
+            MatchMode::ExactOrSmaller => Ordering::Less,
+            MatchMode::ExactOrLarger => Ordering::Greater,
+            _ => continue,
+        };
+        if cmp_value(v, lookup_value) != Some(target) {
+            continue;
+        }
+        let better = match best {
+            Some(b) => cmp_value(v, &values[b]) == Some(target.reverse()),
+            None => true,
+        };
+        if better {
+            best = Some(i);
+        }
+    }
+    best
+}
+
+fn binary_search(
+    values: &[Value],
+    lookup_value: &Value,
+    match_mode: MatchMode,
+    descending: bool,
+) -> Option<usize> {
+    let pos = approximate_search(values, lookup_value, descending);
+    let exact = pos.filter(|p| cmp_value(&values[*p], lookup_value) == Some(Ordering::Equal));
+    if exact.is_some() {
+        return exact;
+    }
+    // `pos` is at the last value before the lookup value in the sorted order.
+    let next = || {
+        let n = pos.map_or(0, |p| p + 1);
+        if n < values.len() && cmp_value(&values[n], lookup_value).is_some() {
+            Some(n)
+        } else {
+            None
+        }
+    };
+    match (match_mode, descending) {
+        (MatchMode::ExactOrSmaller, false) | (MatchMode::ExactOrLarger, true) => pos,
+        (MatchMode::ExactOrSmaller, true) | (MatchMode::ExactOrLarger, false) => next(),
+        _ => None,
+    }
+}
+
+/// Binary search on sorted values like Excel does. For the ascending order it
+/// finds the last value which is less than or equal to the lookup value and
+/// for the descending order the last value which is greater than or equal to
+/// it. Values of other types are skipped.
+fn approximate_search(values: &[Value], lookup_value: &Value, descending: bool) -> Option<usize> {
+    let mut lo = 0;
+    let mut hi = values.len();
+    let mut result = None;
+    while lo < hi {
+        let mid = (lo + hi) / 2;
+        let mut m = mid;
+        while m > lo && cmp_value(&values[m], lookup_value).is_none() {
+            m -= 1;
+        }
+        match cmp_value(&values[m], lookup_value) {
+            None => lo = mid + 1,
+            Some(ord) => {
+                let before = if descending {
+                    ord != Ordering::Less
+                } else {
+                    ord != Ordering::Greater
+                };
+                if before {
+                    result = Some(m);
+                    lo = mid + 1;
+                } else {
+                    hi = m;
+                }
+            }
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{approximate_search, linear_search, MatchMode, Value};
+
+    fn nums(v: &[f64]) -> Vec<Value> {
+        v.iter().map(|n| Value::Number(*n)).collect()
+    }
+
+    #[test]
+    fn approximate_search_test() {
+        let values = nums(&[1., 3., 5., 7.]);
+        let lookup = Value::Number(6.);
+        assert_eq!(approximate_search(&values, &lookup, false), Some(2));
+        let lookup = Value::Number(0.);
+        assert_eq!(approximate_search(&values, &lookup, false), None);
+        let mut values = nums(&[1., 3., 5., 7.]);
+        values.insert(2, Value::Text(String::from("a")));
+        let lookup = Value::Number(5.);
+        assert_eq!(approximate_search(&values, &lookup, false), Some(3));
+        let values = nums(&[7., 5., 3., 1.]);
+        let lookup = Value::Number(4.);
+        assert_eq!(approximate_search(&values, &lookup, true), Some(1));
+    }
+
+    #[test]
+    fn linear_search_test() {
+        let values = nums(&[5., 1., 7., 3.]);
+        let lookup = Value::Number(4.);
+        assert_eq!(
+            linear_search(&values, &lookup, MatchMode::ExactOrSmaller, false),
+            Some(3)
+        );
+        assert_eq!(
+            linear_search(&values, &lookup, MatchMode::ExactOrLarger, false),
+            Some(0)
+        );
+        assert_eq!(
+            linear_search(&values, &lookup, MatchMode::Exact, false),
+            None
+        );
+        let values = vec![
+            Value::Text(String::from("Apple")),
+            Value::Text(String::from("banana")),
+        ];
+        let lookup = Value::Text(String::from("B*"));
+        assert_eq!(
+            linear_search(&values, &lookup, MatchMode::Wildcard, false),
+            Some(1)
+        );
+    }
+}
@@ -0,0 +1,150 @@
+use crate::calc_engine::calculator::calc_vertex::{CalcValue, CalcVertex, Value};
+use crate::calc_engine::connector::Connector;
+use logisheets_parser::ast;
+
+pub fn calc_max<C>(args: Vec<CalcVertex>, fetcher: &mut C) -> CalcVertex
+where
+    C: Connector,
+{
+    calc(args, fetcher, false, f64::max)
+}
+
+pub fn calc_min<C>(args: Vec<CalcVertex>, fetcher: &mut C) -> CalcVertex
+where
+    C: Connector,
+{
+    calc(args, fetcher, false, f64::min)
+}
+
+pub fn calc_maxa<C>(args: Vec<CalcVertex>, fetcher: &mut C) -> CalcVertex
+where
+    C: Connector,
+{
+    calc(args, fetcher, true, f64::max)
+}
+
+pub fn calc_mina<C>(args: Vec<CalcVertex>, fetcher: &mut C) -> CalcVertex
+where
+    C: Connector,
+{
+    calc(args, fetcher, true, f64::min)
+}
+
+fn calc<C, F>(args: Vec<CalcVertex>, fetcher: &mut C, a_variant: bool, func: F) -> CalcVertex
+where
+    C: Connector,
+    F: Fn(f64, f64) -> f64,
+{
+    assert_or_return!(!args.is_empty(), ast::Error::Unspecified);
+    let result = args.into_iter().try_fold(None, |prev: Option<f64>, arg| {
+        // Values typed directly into the argument list are converted, while
+        // values coming from references are filtered by their types.
+        let is_ref = matches!(arg, CalcVertex::Reference(_));
+        let value = fetcher.get_calc_value(arg);
+        let nums = get_nums(value, is_ref, a_variant)?;
+        let r = nums.into_iter().fold(prev, |p, n| match p {
+            Some(p) => Some(func(p, n)),
+            None => Some(n),
+        });
+        Ok(r)
+    });
+    match result {
+        Ok(r) => CalcVertex::from_number(r.unwrap_or(0.)),
+        Err(e) => CalcVertex::from_error(e),
+    }
+}
+
+fn get_nums(value: CalcValue, is_ref: bool, a_variant: bool) -> Result<Vec<f64>, ast::Error> {
+    match value {
+        CalcValue::Scalar(v) => {
+            if is_ref {
+                Ok(get_ref_num(v, a_variant)?.into_iter().collect())
+            } else {
+                get_direct_num(v).map(|n| vec![n])
+            }
+        }
+        CalcValue::Range(r) => r.into_iter().try_fold(vec![], |mut prev, v| {
+            if let Some(n) = get_ref_num(v, a_variant)? {
+                prev.push(n);
+            }
+            Ok(prev)
+        }),
+        CalcValue::Cube(c) => c.into_iter().try_fold(vec![], |mut prev, v| {
+            if let Some(n) = get_ref_num(v, a_variant)? {
+                prev.push(n);
+            }
+            Ok(prev)
+        }),
+        CalcValue::Union(values) => values.into_iter().try_fold(vec![], |mut prev, v| {
+            prev.extend(get_nums(*v, true, a_variant)?);
+            Ok(prev)
+        }),
+    }
+}
+
+fn get_direct_num(value: Value) -> Result<f64, ast::Error> {
+    match value {
+        Value::Blank => Ok(0.),
+        Value::Number(n) => Ok(n),
+        Value::Text(t) => t.trim().parse::<f64>().map_err(|_| ast::Error::Value),
+        Value::Boolean(b) => Ok(if b { 1. } else { 0. }),
+        Value::Error(e) => Err(e),
+        Value::Date(_) => Err(ast::Error::Value),
+    }
+}
+
+// MAX and MIN only look at numbers in references. MAXA and MINA also take
+// booleans and evaluate text as 0.
+fn get_ref_num(value: Value, a_variant: bool) -> Result<Option<f64>, ast::Error> {
+    match value {
+        Value::Number(n) => Ok(Some(n)),
+        Value::Error(e) => Err(e),
+        Value::Boolean(b) if a_variant => Ok(Some(if b { 1. } else { 0. })),
+        Value::Text(_) if a_variant => Ok(Some(0.)),
+        Value::Date(_) => Err(ast::Error::Value),
+        _ => Ok(None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::utils::tests_utils::TestFetcher;
+    use super::{CalcValue, CalcVertex, Value};
+    use logisheets_base::matrix_value::MatrixValue;
+    use logisheets_parser::ast;
+
+    fn range() -> CalcVertex {
+        CalcVertex::Value(CalcValue::Range(MatrixValue::from(vec![
+            vec![Value::Text(String::from("100"))],
+            vec![Value::Boolean(true)],
+            vec![Value::Number(-2.)],
+        ])))
+    }
+
+    #[test]
+    fn max_min_test() {
+        let mut fetcher = TestFetcher {};
+        let args = vec![range(), CalcVertex::from_string(String::from("-5"))];
+        let result = super::calc_min(args, &mut fetcher);
+        assert!(matches!(
+            result,
+            CalcVertex::Value(CalcValue::Scalar(Value::Number(n))) if (n + 5.).abs() < 1e-10
+        ));
+        let result = super::calc_max(vec![range()], &mut fetcher);
+        assert!(matches!(
+            result,
+            CalcVertex::Value(CalcValue::Scalar(Value::Number(n))) if (n + 2.).abs() < 1e-10
+        ));
+        let result = super::calc_maxa(vec![range()], &mut fetcher);
+        assert!(matches!(
+            result,
+            CalcVertex::Value(CalcValue::Scalar(Value::Number(n))) if (n - 1.).abs() < 1e-10
+        ));
+        let args = vec![range(), CalcVertex::from_string(String::from("a"))];
+        let result = super::calc_max(args, &mut fetcher);
+        assert!(matches!(
+            result,
+            CalcVertex::Value(CalcValue::Scalar(Value::Error(ast::Error::Value)))
+        ));
+    }
+}
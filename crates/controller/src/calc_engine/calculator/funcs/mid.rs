@@ -0,0 +1,48 @@
+use super::len::take_bytes;
+use super::utils::{get_scalar_f64, get_scalar_text};
+use crate::calc_engine::calculator::calc_vertex::CalcVertex;
+use crate::calc_engine::connector::Connector;
+use logisheets_parser::ast;
+use unicode_segmentation::UnicodeSegmentation;
+
+pub fn calc_mid<C>(args: Vec<CalcVertex>, fetcher: &mut C) -> CalcVertex
+where
+    C: Connector,
+{
+    calc(args, fetcher, |s, start, n| {
+        s.graphemes(true).skip(start).take(n).collect()
+    })
+}
+
+/// MIDB counts the positions in bytes, see `len::char_bytes`.
+pub fn calc_midb<C>(args: Vec<CalcVertex>, fetcher: &mut C) -> CalcVertex
+where
+    C: Connector,
+{
+    calc(args, fetcher, |s, start, n| {
+        take_bytes(&s, start, start.saturating_add(n))
+    })
+}
+
+fn calc<C, F>(args: Vec<CalcVertex>, fetcher: &mut C, func: F) -> CalcVertex
+where
+    C: Connector,
+    F: Fn(String, usize, usize) -> String,
+{
+    assert_or_return!(args.len() == 3, ast::Error::Unspecified);
+    let mut args_iter = args.into_iter();
+    let text = match get_scalar_text(fetcher, args_iter.next().unwrap()) {
+        Ok(t) => t,
+        Err(e) => return CalcVertex::from_error(e),
+    };
+    let start = match get_scalar_f64(fetcher, args_iter.next().unwrap()) {
+        Ok(n) => n.trunc(),
+        Err(e) => return CalcVertex::from_error(e),
+    };
+    let n = match get_scalar_f64(fetcher, args_iter.next().unwrap()) {
+        Ok(n) => n.trunc(),
+        Err(e) => return CalcVertex::from_error(e),
+    };
+    assert_or_return!(start >= 1. && n >= 0., ast::Error::Value);
+    CalcVertex::from_string(func(text, start as usize - 1, n as usize))
+}
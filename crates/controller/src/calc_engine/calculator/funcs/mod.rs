@@ -1,11 +1,14 @@
 #[macro_use]
 mod macros;
+mod aggregate_if;
 mod and;
+mod arith;
 mod asyncs;
 mod average;
 mod bits;
 mod bonds;
 mod boolean;
+mod charcode;
 mod complex;
 mod concatenate;
 mod condition;
@@ -15,9 +18,12 @@ mod cumipmt;
 mod datetime;
 mod delta;
 mod distribution;
+mod dynamic_array;
 mod effect;
 mod exact;
 mod fact;
+mod find;
+mod fixed;
 mod fvpv;
 mod gcdlcm;
 mod gestep;
@@ -26,13 +32,20 @@ mod iferror;
 mod ifs;
 mod im;
 mod index;
+mod indirect;
 mod irr;
 mod iserr;
+mod lambda;
 mod leftright;
 mod len;
+mod lookup;
+mod maxmin;
+mod mid;
 mod na;
+mod native;
 mod norm_s_dist;
 mod npv;
+mod offset;
 mod pduration;
 mod permutation;
 mod pi;
@@ -41,177 +54,374 @@ mod quotient;
 mod rand;
 mod rank;
 mod rept;
+mod round;
 mod row;
 mod scalar_number;
 mod scalar_text;
 mod sln;
+mod substitute;
 mod sum;
 mod switch;
+mod text;
+mod textjoin;
 mod utils;
-mod vlookup;
+mod value;
 
+use logisheets_astchecker::get_builtin_func;
 use logisheets_parser::ast;
 
 use super::calc_vertex::{CalcValue, CalcVertex, Value};
 use crate::calc_engine::connector::Connector;
 
+/// Whether the function is built in, which means it has a signature.
+fn is_builtin_func(name: &str) -> bool {
+    get_builtin_func(name).is_some()
+}
+
+// The results of these functions can change without any change of the cells
+// in their formulas.
+pub const VOLATILE_FUNCS: [&str; 6] = ["INDIRECT", "NOW", "OFFSET", "RAND", "RANDARRAY", "TODAY"];
+
+pub fn is_volatile_func(name: &str) -> bool {
+    let name = name.to_uppercase();
+    VOLATILE_FUNCS.contains(&strip_func_prefix(&name))
+}
+
+/// Whether the function is built in or registered.
+pub fn has_func<C>(name: &str, fetcher: &C) -> bool
+where
+    C: Connector,
+{
+    let name = name.to_uppercase();
+    let name = strip_func_prefix(&name);
+    fetcher.get_native_func(name).is_some()
+        || fetcher.get_async_func(name).is_some()
+        || is_builtin_func(name)
+}
+
+// Functions introduced after Excel 2007 are saved with a `_xlfn.` prefix.
+// Some of them, like `_xlfn._xlws.SORT`, have both prefixes.
+fn strip_func_prefix(name: &str) -> &str {
+    let name = name.strip_prefix("_XLFN.").unwrap_or(name);
+    name.strip_prefix("_XLWS.").unwrap_or(name)
+}
+
 pub fn function_calculate<C>(name: &str, args: Vec<CalcVertex>, fetcher: &mut C) -> CalcVertex
 where
     C: Connector,
 {
-    if fetcher.is_async_func(name) {
-        return asyncs::calc(name, args, fetcher);
+    let name = name.to_uppercase();
+    let name = strip_func_prefix(&name);
+    if let Some(func) = fetcher.get_native_func(name) {
+        return native::calc(&func, args, fetcher);
+    }
+    if let Some(func) = fetcher.get_async_func(name) {
+        return asyncs::calc(name, &func, args, fetcher);
     }
-    match name.to_uppercase().as_str() {
-        "ABS" => scalar_number::calc_abs(args, fetcher),
-        "ACOS" => scalar_number::calc_acos(args, fetcher),
-        "ACOSH" => scalar_number::calc_acosh(args, fetcher),
-        "AND" => and::calc(args, fetcher),
-        "ASIN" => scalar_number::calc_asin(args, fetcher),
-        "ASINH" => scalar_number::calc_asinh(args, fetcher),
-        "ATAN" => scalar_number::calc_atan(args, fetcher),
-        "ATANH" => scalar_number::calc_atanh(args, fetcher),
-        "AVERAGE" => average::calc_average(args, fetcher),
-        "BIN2DEC" => bits::hob2dec::calc_bin2dec(args, fetcher),
-        "BIN2HEX" => bits::hob2hob::calc_bin2hex(args, fetcher),
-        "BIN2OCT" => bits::hob2hob::calc_bin2oct(args, fetcher),
-        "BITAND" => bits::bit::calc_bitand(args, fetcher),
-        "BITLSHIFT" => bits::bit::calc_bitlshift(args, fetcher),
-        "BITOR" => bits::bit::calc_bitor(args, fetcher),
-        "BITRSHIFT" => bits::bit::calc_bitrshift(args, fetcher),
-        "BITXOR" => bits::bit::calc_bitxor(args, fetcher),
-        "CHISQ.DIST" => distribution::chisqdist::calc_chisqdist(args, fetcher),
-        "CHISQ.DIST.RT" => distribution::chisqdist::calc_chisqdist_rt(args, fetcher),
-        "COMBIN" => permutation::calc_combine(args, fetcher),
-        "COMPLEX" => complex::calc(args, fetcher),
-        "CONCATENATE" => concatenate::calc(args, fetcher),
-        "COS" => scalar_number::calc_cos(args, fetcher),
-        "COT" => scalar_number::calc_cot(args, fetcher),
-        "COTH" => scalar_number::calc_coth(args, fetcher),
-        "COUNT" => count::calc(args, fetcher),
-        "COUNTIF" => countif::calc(args, fetcher),
-        "COUPNCD" => bonds::coupncd::calc(args, fetcher),
-        "COUPNUM" => bonds::coupnum::calc(args, fetcher),
-        "COUPPCD" => bonds::couppcd::calc(args, fetcher),
-        "CSC" => scalar_number::calc_csc(args, fetcher),
-        "CUMIPMT" => cumipmt::cumipmt(args, fetcher),
-        "DATE" => datetime::date::calc(args, fetcher),
-        "DAY" => datetime::ymd::calc_day(args, fetcher),
-        "DAYS" => datetime::days::calc(args, fetcher),
-        "DEC2BIN" => bits::dec2hob::calc_dec2bin(args, fetcher),
-        "DEC2HEX" => bits::dec2hob::calc_dec2hex(args, fetcher),
-        "DEC2OCT" => bits::dec2hob::calc_dec2oct(args, fetcher),
-        "DEGREES" => scalar_number::calc_degrees(args, fetcher),
-        "DELTA" => delta::calc(args, fetcher),
-        "EDATE" => datetime::edate::calc(args, fetcher),
-        "EFFECT" => effect::effect(args, fetcher),
-        "EOMONTH" => datetime::eomonth::calc(args, fetcher),
-        "EVEN" => scalar_number::calc_even(args, fetcher),
-        "EXACT" => exact::calc(args, fetcher),
-        "EXP" => scalar_number::calc_exp(args, fetcher),
-        "FACT" => fact::calc(args, fetcher),
-        "FACTDOUBLE" => scalar_number::calc_factdouble(args, fetcher),
-        "FALSE" => boolean::calc_false(args),
-        "FV" => fvpv::fv(args, fetcher),
-        "GAMMA" => scalar_number::calc_gamma(args, fetcher),
-        "GAMMALN" => scalar_number::calc_gammaln(args, fetcher),
-        "GAMMALN.PRECISE" => scalar_number::calc_gammaln(args, fetcher),
-        "GAMMA.DIST" => distribution::gammadist::calc_gammadist(args, fetcher),
-        "GAMMADIST" => distribution::gammadist::calc_gammadist(args, fetcher),
-        "GCD" => gcdlcm::calc_gcd(args, fetcher),
-        "GEOMEAN" => average::calc_geomean(args, fetcher),
-        "GESTEP" => gestep::calc(args, fetcher),
-        "HARMEAN" => average::calc_harmean(args, fetcher),
-        "HEX2BIN" => bits::hob2hob::calc_hex2bin(args, fetcher),
-        "HEX2DEC" => bits::hob2dec::calc_hex2dec(args, fetcher),
-        "HEX2OCT" => bits::hob2hob::calc_hex2oct(args, fetcher),
-        "HOUR" => datetime::hms::calc_hour(args, fetcher),
-        "IF" => if_plugin::calc(args, fetcher),
-        "IFERROR" => iferror::calc(args, fetcher),
-        "IFS" => ifs::calc(args, fetcher),
-        "IMABS" => im::calc_imabs(args, fetcher),
-        "IMAGINARY" => im::calc_imaginary(args, fetcher),
-        "IMCONJUGATE" => im::calc_imconjugate(args, fetcher),
-        "IMCOS" => im::calc_imcos(args, fetcher),
-        "IMCOSH" => im::calc_imcosh(args, fetcher),
-        "IMCOT" => im::calc_imcot(args, fetcher),
-        "IMCSC" => im::calc_imcsc(args, fetcher),
-        "IMEXP" => im::calc_imexp(args, fetcher),
-        "IMLN" => im::calc_imln(args, fetcher),
-        "IMLOG10" => im::calc_imlog10(args, fetcher),
-        "IMLOG2" => im::calc_imlog2(args, fetcher),
-        "IMREAL" => im::calc_imreal(args, fetcher),
-        "IMSEC" => im::calc_imsec(args, fetcher),
-        "IMSIN" => im::calc_imsin(args, fetcher),
-        "IMSINH" => im::calc_imsinh(args, fetcher),
-        "IMTAN" => im::calc_imtan(args, fetcher),
-        "IMTANH" => im::calc_imtanh(args, fetcher),
-        "INDEX" => index::calc(args, fetcher),
-        "IPMT" => pmt::ipmt(args, fetcher),
-        "IRR" => irr::calc(args, fetcher),
-        "ISERR" => iserr::calc(args, fetcher, iserr::IsErrType::ExceptNa),
-        "ISERROR" => iserr::calc(args, fetcher, iserr::IsErrType::All),
-        "ISNA" => iserr::calc(args, fetcher, iserr::IsErrType::Na),
-        "LCM" => gcdlcm::calc_lcm(args, fetcher),
-        "LEFT" => leftright::calc_left(args, fetcher),
-        "LEN" => len::calc_len(args, fetcher),
-        "LENB" => len::calc_lenb(args, fetcher),
-        "LN" => scalar_number::calc_ln(args, fetcher),
-        "LOG10" => scalar_number::calc_log10(args, fetcher),
-        "LOWER" => scalar_text::calc_lower(args, fetcher),
-        "MINUTE" => datetime::hms::calc_minute(args, fetcher),
-        "MONTH" => datetime::ymd::calc_month(args, fetcher),
-        "NA" => na::calc(args),
-        "NOMINAL" => effect::nominal(args, fetcher),
-        "NORM.DIST" => distribution::normdist::calc_normdist(args, fetcher),
-        "NORM.S.DIST" => norm_s_dist::calc(args, fetcher),
-        "NORM.S.INV" => distribution::norminv::calc_normsinv(args, fetcher),
-        "NORMDIST" => distribution::normdist::calc_normdist(args, fetcher),
-        "NORMINV" => distribution::norminv::calc_norminv(args, fetcher),
-        "NORMSDIST" => scalar_number::calc_normsdist(args, fetcher),
-        "NORMSINV" => distribution::norminv::calc_normsinv(args, fetcher),
-        "NOW" => datetime::now::calc(args),
-        "NPV" => npv::calc(args, fetcher),
-        "OCT2BIN" => bits::hob2hob::calc_oct2bin(args, fetcher),
-        "OCT2DEC" => bits::hob2dec::calc_oct2dec(args, fetcher),
-        "OCT2HEX" => bits::hob2hob::calc_oct2hex(args, fetcher),
-        "ODD" => scalar_number::calc_odd(args, fetcher),
-        "PDURATION" => pduration::pduration(args, fetcher),
-        "PERMUT" => permutation::calc_permut(args, fetcher),
-        "PI" => pi::calc(args),
-        "PMT" => pmt::pmt(args, fetcher),
-        "PPMT" => pmt::ppmt(args, fetcher),
-        "PV" => fvpv::pv(args, fetcher),
-        "QUOTIENT" => quotient::calc(args, fetcher),
-        "RADIANS" => scalar_number::calc_radians(args, fetcher),
-        "RAND" => rand::calc(args),
-        "RANK" => rank::calc_rank(args, fetcher),
-        "RANK.AVG" => rank::calc_rank_avg(args, fetcher),
-        "RANK.EQ" => rank::calc_rank(args, fetcher),
-        "REPT" => rept::calc(args, fetcher),
-        "RIGHT" => leftright::calc_right(args, fetcher),
-        "ROW" => row::calc(args, fetcher),
-        "RRI" => pduration::rri(args, fetcher),
-        "SECOND" => datetime::hms::calc_second(args, fetcher),
-        "SIGN" => scalar_number::calc_sign(args, fetcher),
-        "SIN" => scalar_number::calc_sin(args, fetcher),
-        "SLN" => sln::sln(args, fetcher),
-        "SQRT" => scalar_number::calc_sqrt(args, fetcher),
-        "SQRTPI" => scalar_number::calc_sqrtpi(args, fetcher),
-        "STDEV" => distribution::statistics::calc_stdev(args, fetcher),
-        "SUM" => sum::calc(args, fetcher),
-        "SWITCH" => switch::calc(args, fetcher),
-        "TAN" => scalar_number::calc_tan(args, fetcher),
-        "TANH" => scalar_number::calc_tanh(args, fetcher),
-        "TIME" => datetime::time::calc(args, fetcher),
-        "TODAY" => datetime::today::calc(args),
-        "TRIM" => scalar_text::calc_trim(args, fetcher),
-        "TRUE" => boolean::calc_true(args),
-        "UPPER" => scalar_text::calc_upper(args, fetcher),
-        "VAR" => distribution::statistics::calc_var(args, fetcher),
-        "VAR.S" => distribution::statistics::calc_var(args, fetcher),
-        "WEEKDAY" => datetime::weekday::calc(args, fetcher),
-        "YEAR" => datetime::ymd::calc_year(args, fetcher),
-        _ => CalcVertex::from_error(ast::Error::Name),
+    calc_builtin(name, args, fetcher)
+}
+
+// Generate `calc_builtin` and `CALC_FUNCS`, the names of the functions
+// calculated here, from the same table. The tests check `CALC_FUNCS` against
+// the signatures, which decide the built-in functions.
+macro_rules! builtin_funcs {
+    ($args:ident, $fetcher:ident; $($name:literal => $calc:expr,)*) => {
+        #[cfg(test)]
+        const CALC_FUNCS: &[&str] = &[$($name),*];
+
+        fn calc_builtin<C>(name: &str, $args: Vec<CalcVertex>, $fetcher: &mut C) -> CalcVertex
+        where
+            C: Connector,
+        {
+            match name {
+                $($name => $calc,)*
+                _ => CalcVertex::from_error(ast::Error::Name),
+            }
+        }
+    };
+}
+
+// Sorted by the names, which are in upper case.
+builtin_funcs! {
+    args, fetcher;
+    "ABS" => scalar_number::calc_abs(args, fetcher),
+    "ACOS" => scalar_number::calc_acos(args, fetcher),
+    "ACOSH" => scalar_number::calc_acosh(args, fetcher),
+    "ANCHORARRAY" => dynamic_array::calc_anchorarray(args, fetcher),
+    "AND" => and::calc(args, fetcher),
+    "ASIN" => scalar_number::calc_asin(args, fetcher),
+    "ASINH" => scalar_number::calc_asinh(args, fetcher),
+    "ATAN" => scalar_number::calc_atan(args, fetcher),
+    "ATANH" => scalar_number::calc_atanh(args, fetcher),
+    "AVERAGE" => average::calc_average(args, fetcher),
+    "AVERAGEIF" => aggregate_if::calc_averageif(args, fetcher),
+    "AVERAGEIFS" => aggregate_if::calc_averageifs(args, fetcher),
+    "BIN2DEC" => bits::hob2dec::calc_bin2dec(args, fetcher),
+    "BIN2HEX" => bits::hob2hob::calc_bin2hex(args, fetcher),
+    "BIN2OCT" => bits::hob2hob::calc_bin2oct(args, fetcher),
+    "BITAND" => bits::bit::calc_bitand(args, fetcher),
+    "BITLSHIFT" => bits::bit::calc_bitlshift(args, fetcher),
+    "BITOR" => bits::bit::calc_bitor(args, fetcher),
+    "BITRSHIFT" => bits::bit::calc_bitrshift(args, fetcher),
+    "BITXOR" => bits::bit::calc_bitxor(args, fetcher),
+    "BYCOL" => lambda::calc_bycol(args, fetcher),
+    "BYROW" => lambda::calc_byrow(args, fetcher),
+    "CEILING" => round::calc_ceiling(args, fetcher),
+    "CEILING.MATH" => round::calc_ceiling_math(args, fetcher),
+    "CEILING.PRECISE" => round::calc_ceiling_precise(args, fetcher),
+    "CHAR" => charcode::calc_char(args, fetcher),
+    "CHISQ.DIST" => distribution::chisqdist::calc_chisqdist(args, fetcher),
+    "CHISQ.DIST.RT" => distribution::chisqdist::calc_chisqdist_rt(args, fetcher),
+    "CHOOSE" => lookup::calc_choose(args, fetcher),
+    "CLEAN" => scalar_text::calc_clean(args, fetcher),
+    "CODE" => charcode::calc_code(args, fetcher),
+    "COMBIN" => permutation::calc_combine(args, fetcher),
+    "COMPLEX" => complex::calc(args, fetcher),
+    "CONCAT" => textjoin::calc_concat(args, fetcher),
+    "CONCATENATE" => concatenate::calc(args, fetcher),
+    "COS" => scalar_number::calc_cos(args, fetcher),
+    "COT" => scalar_number::calc_cot(args, fetcher),
+    "COTH" => scalar_number::calc_coth(args, fetcher),
+    "COUNT" => count::calc(args, fetcher),
+    "COUNTIF" => countif::calc(args, fetcher),
+    "COUNTIFS" => countif::calc_countifs(args, fetcher),
+    "COUPNCD" => bonds::coupncd::calc(args, fetcher),
+    "COUPNUM" => bonds::coupnum::calc(args, fetcher),
+    "COUPPCD" => bonds::couppcd::calc(args, fetcher),
+    "CSC" => scalar_number::calc_csc(args, fetcher),
+    "CUMIPMT" => cumipmt::cumipmt(args, fetcher),
+    "DATE" => datetime::date::calc(args, fetcher),
+    "DAY" => datetime::ymd::calc_day(args, fetcher),
+    "DAYS" => datetime::days::calc(args, fetcher),
+    "DEC2BIN" => bits::dec2hob::calc_dec2bin(args, fetcher),
+    "DEC2HEX" => bits::dec2hob::calc_dec2hex(args, fetcher),
+    "DEC2OCT" => bits::dec2hob::calc_dec2oct(args, fetcher),
+    "DEGREES" => scalar_number::calc_degrees(args, fetcher),
+    "DELTA" => delta::calc(args, fetcher),
+    "DOLLAR" => fixed::calc_dollar(args, fetcher),
+    "EDATE" => datetime::edate::calc(args, fetcher),
+    "EFFECT" => effect::effect(args, fetcher),
+    "EOMONTH" => datetime::eomonth::calc(args, fetcher),
+    "EVEN" => scalar_number::calc_even(args, fetcher),
+    "EXACT" => exact::calc(args, fetcher),
+    "EXP" => scalar_number::calc_exp(args, fetcher),
+    "FACT" => fact::calc(args, fetcher),
+    "FACTDOUBLE" => scalar_number::calc_factdouble(args, fetcher),
+    "FALSE" => boolean::calc_false(args),
+    "FILTER" => dynamic_array::calc_filter(args, fetcher),
+    "FIND" => find::calc_find(args, fetcher),
+    "FINDB" => find::calc_findb(args, fetcher),
+    "FIXED" => fixed::calc_fixed(args, fetcher),
+    "FLOOR" => round::calc_floor(args, fetcher),
+    "FLOOR.MATH" => round::calc_floor_math(args, fetcher),
+    "FLOOR.PRECISE" => round::calc_floor_precise(args, fetcher),
+    "FV" => fvpv::fv(args, fetcher),
+    "GAMMA" => scalar_number::calc_gamma(args, fetcher),
+    "GAMMA.DIST" => distribution::gammadist::calc_gammadist(args, fetcher),
+    "GAMMADIST" => distribution::gammadist::calc_gammadist(args, fetcher),
+    "GAMMALN" => scalar_number::calc_gammaln(args, fetcher),
+    "GAMMALN.PRECISE" => scalar_number::calc_gammaln(args, fetcher),
+    "GCD" => gcdlcm::calc_gcd(args, fetcher),
+    "GEOMEAN" => average::calc_geomean(args, fetcher),
+    "GESTEP" => gestep::calc(args, fetcher),
+    "HARMEAN" => average::calc_harmean(args, fetcher),
+    "HEX2BIN" => bits::hob2hob::calc_hex2bin(args, fetcher),
+    "HEX2DEC" => bits::hob2dec::calc_hex2dec(args, fetcher),
+    "HEX2OCT" => bits::hob2hob::calc_hex2oct(args, fetcher),
+    "HLOOKUP" => lookup::calc_hlookup(args, fetcher),
+    "HOUR" => datetime::hms::calc_hour(args, fetcher),
+    "IF" => if_plugin::calc(args, fetcher),
+    "IFERROR" => iferror::calc(args, fetcher),
+    "IFS" => ifs::calc(args, fetcher),
+    "IMABS" => im::calc_imabs(args, fetcher),
+    "IMAGINARY" => im::calc_imaginary(args, fetcher),
+    "IMCONJUGATE" => im::calc_imconjugate(args, fetcher),
+    "IMCOS" => im::calc_imcos(args, fetcher),
+    "IMCOSH" => im::calc_imcosh(args, fetcher),
+    "IMCOT" => im::calc_imcot(args, fetcher),
+    "IMCSC" => im::calc_imcsc(args, fetcher),
+    "IMEXP" => im::calc_imexp(args, fetcher),
+    "IMLN" => im::calc_imln(args, fetcher),
+    "IMLOG10" => im::calc_imlog10(args, fetcher),
+    "IMLOG2" => im::calc_imlog2(args, fetcher),
+    "IMREAL" => im::calc_imreal(args, fetcher),
+    "IMSEC" => im::calc_imsec(args, fetcher),
+    "IMSIN" => im::calc_imsin(args, fetcher),
+    "IMSINH" => im::calc_imsinh(args, fetcher),
+    "IMTAN" => im::calc_imtan(args, fetcher),
+    "IMTANH" => im::calc_imtanh(args, fetcher),
+    "INDEX" => index::calc(args, fetcher),
+    "INDIRECT" => indirect::calc(args, fetcher),
+    "INT" => round::calc_int(args, fetcher),
+    "IPMT" => pmt::ipmt(args, fetcher),
+    "IRR" => irr::calc(args, fetcher),
+    "ISERR" => iserr::calc(args, fetcher, iserr::IsErrType::ExceptNa),
+    "ISERROR" => iserr::calc(args, fetcher, iserr::IsErrType::All),
+    "ISNA" => iserr::calc(args, fetcher, iserr::IsErrType::Na),
+    "ISO.CEILING" => round::calc_ceiling_precise(args, fetcher),
+    "LCM" => gcdlcm::calc_lcm(args, fetcher),
+    "LEFT" => leftright::calc_left(args, fetcher),
+    "LEFTB" => leftright::calc_leftb(args, fetcher),
+    "LEN" => len::calc_len(args, fetcher),
+    "LENB" => len::calc_lenb(args, fetcher),
+    "LN" => scalar_number::calc_ln(args, fetcher),
+    "LOG" => arith::calc_log(args, fetcher),
+    "LOG10" => scalar_number::calc_log10(args, fetcher),
+    "LOOKUP" => lookup::calc_lookup(args, fetcher),
+    "LOWER" => scalar_text::calc_lower(args, fetcher),
+    "MAKEARRAY" => lambda::calc_makearray(args, fetcher),
+    "MAP" => lambda::calc_map(args, fetcher),
+    "MATCH" => lookup::calc_match(args, fetcher),
+    "MAX" => maxmin::calc_max(args, fetcher),
+    "MAXA" => maxmin::calc_maxa(args, fetcher),
+    "MAXIFS" => aggregate_if::calc_maxifs(args, fetcher),
+    "MID" => mid::calc_mid(args, fetcher),
+    "MIDB" => mid::calc_midb(args, fetcher),
+    "MIN" => maxmin::calc_min(args, fetcher),
+    "MINA" => maxmin::calc_mina(args, fetcher),
+    "MINIFS" => aggregate_if::calc_minifs(args, fetcher),
+    "MINUTE" => datetime::hms::calc_minute(args, fetcher),
+    "MOD" => arith::calc_mod(args, fetcher),
+    "MONTH" => datetime::ymd::calc_month(args, fetcher),
+    "MROUND" => round::calc_mround(args, fetcher),
+    "NA" => na::calc(args),
+    "NOMINAL" => effect::nominal(args, fetcher),
+    "NORM.DIST" => distribution::normdist::calc_normdist(args, fetcher),
+    "NORM.INV" => distribution::norminv::calc_norminv(args, fetcher),
+    "NORM.S.DIST" => norm_s_dist::calc(args, fetcher),
+    "NORM.S.INV" => distribution::norminv::calc_normsinv(args, fetcher),
+    "NORMDIST" => distribution::normdist::calc_normdist(args, fetcher),
+    "NORMINV" => distribution::norminv::calc_norminv(args, fetcher),
+    "NORMSDIST" => scalar_number::calc_normsdist(args, fetcher),
+    "NORMSINV" => distribution::norminv::calc_normsinv(args, fetcher),
+    "NOW" => datetime::now::calc(args),
+    "NPV" => npv::calc(args, fetcher),
+    "OCT2BIN" => bits::hob2hob::calc_oct2bin(args, fetcher),
+    "OCT2DEC" => bits::hob2dec::calc_oct2dec(args, fetcher),
+    "OCT2HEX" => bits::hob2hob::calc_oct2hex(args, fetcher),
+    "ODD" => scalar_number::calc_odd(args, fetcher),
+    "OFFSET" => offset::calc(args, fetcher),
+    "PDURATION" => pduration::pduration(args, fetcher),
+    "PERMUT" => permutation::calc_permut(args, fetcher),
+    "PI" => pi::calc(args),
+    "PMT" => pmt::pmt(args, fetcher),
+    "POWER" => arith::calc_power(args, fetcher),
+    "PPMT" => pmt::ppmt(args, fetcher),
+    "PROPER" => scalar_text::calc_proper(args, fetcher),
+    "PV" => fvpv::pv(args, fetcher),
+    "QUOTIENT" => quotient::calc(args, fetcher),
+    "RADIANS" => scalar_number::calc_radians(args, fetcher),
+    "RAND" => rand::calc(args),
+    "RANDARRAY" => dynamic_array::calc_randarray(args, fetcher),
+    "RANK" => rank::calc_rank(args, fetcher),
+    "RANK.AVG" => rank::calc_rank_avg(args, fetcher),
+    "RANK.EQ" => rank::calc_rank(args, fetcher),
+    "REDUCE" => lambda::calc_reduce(args, fetcher),
+    "REPLACE" => substitute::calc_replace(args, fetcher),
+    "REPT" => rept::calc(args, fetcher),
+    "RIGHT" => leftright::calc_right(args, fetcher),
+    "RIGHTB" => leftright::calc_rightb(args, fetcher),
+    "ROUND" => round::calc_round(args, fetcher),
+    "ROUNDDOWN" => round::calc_rounddown(args, fetcher),
+    "ROUNDUP" => round::calc_roundup(args, fetcher),
+    "ROW" => row::calc(args, fetcher),
+    "RRI" => pduration::rri(args, fetcher),
+    "SCAN" => lambda::calc_scan(args, fetcher),
+    "SEARCH" => find::calc_search(args, fetcher),
+    "SECOND" => datetime::hms::calc_second(args, fetcher),
+    "SEQUENCE" => dynamic_array::calc_sequence(args, fetcher),
+    "SIGN" => scalar_number::calc_sign(args, fetcher),
+    "SIN" => scalar_number::calc_sin(args, fetcher),
+    "SLN" => sln::sln(args, fetcher),
+    "SORT" => dynamic_array::calc_sort(args, fetcher),
+    "SORTBY" => dynamic_array::calc_sortby(args, fetcher),
+    "SQRT" => scalar_number::calc_sqrt(args, fetcher),
+    "SQRTPI" => scalar_number::calc_sqrtpi(args, fetcher),
+    "STDEV" => distribution::statistics::calc_stdev(args, fetcher),
+    "STDEV.S" => distribution::statistics::calc_stdev(args, fetcher),
+    "SUBSTITUTE" => substitute::calc_substitute(args, fetcher),
+    "SUM" => sum::calc(args, fetcher),
+    "SUMIF" => aggregate_if::calc_sumif(args, fetcher),
+    "SUMIFS" => aggregate_if::calc_sumifs(args, fetcher),
+    "SWITCH" => switch::calc(args, fetcher),
+    "TAN" => scalar_number::calc_tan(args, fetcher),
+    "TANH" => scalar_number::calc_tanh(args, fetcher),
+    "TEXT" => text::calc(args, fetcher),
+    "TEXTJOIN" => textjoin::calc_textjoin(args, fetcher),
+    "TIME" => datetime::time::calc(args, fetcher),
+    "TODAY" => datetime::today::calc(args),
+    "TRIM" => scalar_text::calc_trim(args, fetcher),
+    "TRUE" => boolean::calc_true(args),
+    "TRUNC" => round::calc_trunc(args, fetcher),
+    "UNICHAR" => charcode::calc_unichar(args, fetcher),
+    "UNICODE" => charcode::calc_unicode(args, fetcher),
+    "UNIQUE" => dynamic_array::calc_unique(args, fetcher),
+    "UPPER" => scalar_text::calc_upper(args, fetcher),
+    "VALUE" => value::calc(args, fetcher),
+    "VAR" => distribution::statistics::calc_var(args, fetcher),
+    "VAR.S" => distribution::statistics::calc_var(args, fetcher),
+    "VLOOKUP" => lookup::calc_vlookup(args, fetcher),
+    "WEEKDAY" => datetime::weekday::calc(args, fetcher),
+    "XLOOKUP" => lookup::calc_xlookup(args, fetcher),
+    "XMATCH" => lookup::calc_xmatch(args, fetcher),
+    "YEAR" => datetime::ymd::calc_year(args, fetcher),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{is_builtin_func, CALC_FUNCS};
+    use logisheets_astchecker::get_func_prefix;
+
+    #[test]
+    fn builtin_funcs_test() {
+        assert!(CALC_FUNCS.windows(2).all(|w| w[0] < w[1]));
+        assert!(is_builtin_func("_xlfn.textjoin"));
+        assert!(is_builtin_func("let"));
+        assert!(!is_builtin_func("FOO"));
+        // Every function with a signature is calculated. LAMBDA and LET are
+        // evaluated by the calculator itself since their arguments are not
+        // calculated first.
+        CALC_FUNCS
+            .iter()
+            .for_each(|name| assert!(is_builtin_func(name), "{}", name));
+        logisheets_astchecker::get_builtin_funcs()
+            .iter()
+            .map(|f| f.name.as_str())
+            .filter(|name| !["LAMBDA", "LET"].contains(name))
+            .for_each(|name| assert!(CALC_FUNCS.binary_search(&name).is_ok(), "{}", name));
+        // The functions added after Excel 2007 are saved with the prefix.
+        [
+            "BITLSHIFT",
+            "CEILING.MATH",
+            "CHISQ.DIST",
+            "CONCAT",
+            "DAYS",
+            "FLOOR.MATH",
+            "GAMMALN.PRECISE",
+            "IFS",
+            "LAMBDA",
+            "LET",
+            "MAXIFS",
+            "MINIFS",
+            "NORM.INV",
+            "NORM.S.DIST",
+            "RANDARRAY",
+            "RANK.AVG",
+            "SEQUENCE",
+            "SORTBY",
+            "STDEV.S",
+            "SWITCH",
+            "TEXTJOIN",
+            "UNICHAR",
+            "UNIQUE",
+            "VAR.S",
+            "XLOOKUP",
+            "XMATCH",
+        ]
+        .iter()
+        .for_each(|name| assert_eq!(get_func_prefix(name), "_xlfn.", "{}", name));
+        assert_eq!(get_func_prefix("FILTER"), "_xlfn._xlws.");
+        assert_eq!(get_func_prefix("SORT"), "_xlfn._xlws.");
+        assert_eq!(get_func_prefix("SUM"), "");
     }
 }
@@ -0,0 +1,74 @@
+use super::{CalcValue, CalcVertex, Value};
+use crate::calc_engine::connector::Connector;
+use crate::settings::func_registry::{NativeFunc, NativeValue};
+use logisheets_base::datetime::get_serial_num_by_datetime;
+use logisheets_base::matrix_value::MatrixValue;
+use logisheets_parser::ast;
+
+/// Call a function registered by the embedders with the values of the
+/// arguments.
+pub fn calc<C>(func: &NativeFunc, args: Vec<CalcVertex>, fetcher: &mut C) -> CalcVertex
+where
+    C: Connector,
+{
+    assert_or_return!(func.arg_count.check(args.len()), ast::Error::Unspecified);
+    let date1904 = fetcher.is_date1904();
+    let args = args
+        .into_iter()
+        .map(|arg| match fetcher.get_calc_value(arg) {
+            CalcValue::Scalar(v) => to_native(v, date1904),
+            CalcValue::Range(r) => {
+                let (rows, cols) = r.get_used_size();
+                let array = (0..rows)
+                    .map(|i| {
+                        (0..cols)
+                            .map(|j| match r.visit(i, j) {
+                                Ok(v) => to_native(v.clone(), date1904),
+                                Err(v) => to_native(v, date1904),
+                            })
+                            .collect()
+                    })
+                    .collect();
+                NativeValue::Array(array)
+            }
+            CalcValue::Cube(_) | CalcValue::Union(_) => {
+                NativeValue::Error(ast::Error::Value.get_err_str().to_string())
+            }
+        })
+        .collect();
+    match (func.func)(args) {
+        NativeValue::Array(array) => {
+            let array = array
+                .into_iter()
+                .map(|row| row.into_iter().map(from_native).collect())
+                .collect();
+            CalcVertex::Value(CalcValue::Range(MatrixValue::from(array)))
+        }
+        v => CalcVertex::Value(CalcValue::Scalar(from_native(v))),
+    }
+}
+
+fn to_native(v: Value, date1904: bool) -> NativeValue {
+    match v {
+        Value::Blank => NativeValue::Blank,
+        Value::Number(n) => NativeValue::Number(n),
+        Value::Text(t) => NativeValue::Text(t),
+        Value::Boolean(b) => NativeValue::Boolean(b),
+        Value::Error(e) => NativeValue::Error(e.get_err_str().to_string()),
+        Value::Date(d) => {
+            NativeValue::Number(get_serial_num_by_datetime(&d.naive_local(), date1904))
+        }
+    }
+}
+
+// An array can not be an element of an array.
+fn from_native(v: NativeValue) -> Value {
+    match v {
+        NativeValue::Blank => Value::Blank,
+        NativeValue::Number(n) => Value::Number(n),
+        NativeValue::Text(t) => Value::Text(t),
+        NativeValue::Boolean(b) => Value::Boolean(b),
+        NativeValue::Error(e) => Value::Error(ast::Error::from_err_str(&e)),
+        NativeValue::Array(_) => Value::Error(ast::Error::Value),
+    }
+}
@@ -0,0 +1,133 @@
+use super::utils::convert_f64;
+use crate::calc_engine::calculator::calc_vertex::{
+    CalcReference, CalcValue, CalcVertex, ColRange, Reference, RowRange,
+};
+use crate::calc_engine::connector::Connector;
+use logisheets_base::Addr;
+use logisheets_parser::ast;
+
+/// OFFSET(reference, rows, cols, [height], [width])
+///
+/// The result is a reference which is not in the formula, so it is
+/// registered to the connector as a dynamic dependency.
+pub fn calc<C>(args: Vec<CalcVertex>, fetcher: &mut C) -> CalcVertex
+where
+    C: Connector,
+{
+    assert_or_return!(args.len() >= 3 && args.len() <= 5, ast::Error::Unspecified);
+    let mut args_iter = args.into_iter();
+    let reference = match args_iter.next().unwrap() {
+        CalcVertex::Reference(r) => r,
+        _ => return CalcVertex::from_error(ast::Error::Value),
+    };
+    assert_or_return!(reference.from_sheet.is_none(), ast::Error::Value);
+    let nums = args_iter.try_fold(vec![], |mut prev, arg| {
+        let n = match fetcher.get_calc_value(arg) {
+            CalcValue::Scalar(v) => convert_f64(v)?,
+            _ => return Err(ast::Error::Value),
+        };
+        prev.push(n.trunc() as i64);
+        Ok(prev)
+    });
+    let nums = match nums {
+        Ok(nums) => nums,
+        Err(e) => return CalcVertex::from_error(e),
+    };
+    let (rows, cols) = (nums[0], nums[1]);
+    let height = nums.get(2).copied();
+    let width = nums.get(3).copied();
+    match offset(reference, rows, cols, height, width) {
+        Some(r) => {
+            fetcher.add_dynamic_dep(&r);
+            CalcVertex::Reference(r)
+        }
+        None => CalcVertex::from_error(ast::Error::Ref),
+    }
+}
+
+fn offset(
+    reference: CalcReference,
+    rows: i64,
+    cols: i64,
+    height: Option<i64>,
+    width: Option<i64>,
+) -> Option<CalcReference> {
+    let new_reference = match reference.reference {
+        Reference::Addr(addr) => shift_range(addr, 1, 1, rows, cols, height, width)?,
+        Reference::Range(start, end) => {
+            let h = end.row - start.row + 1;
+            let w = end.col - start.col + 1;
+            shift_range(start, h, w, rows, cols, height, width)?
+        }
+        Reference::ColumnRange(cr) => {
+            if rows != 0 || height.is_some() {
+                return None;
+            }
+            let w = width.unwrap_or((cr.end - cr.start + 1) as i64);
+            if w < 1 {
+                return None;
+            }
+            let start = shift(cr.start, cols)?;
+            Reference::ColumnRange(ColRange {
+                start,
+                end: start + w as usize - 1,
+            })
+        }
+        Reference::RowRange(rr) => {
+            if cols != 0 || width.is_some() {
+                return None;
+            }
+            let h = height.unwrap_or((rr.end - rr.start + 1) as i64);
+            if h < 1 {
+                return None;
+            }
+            let start = shift(rr.start, rows)?;
+            Reference::RowRange(RowRange {
+                start,
+                end: start + h as usize - 1,
+            })
+        }
+    };
+    Some(CalcReference {
+        from_sheet: None,
+        sheet: reference.sheet,
+        reference: new_reference,
+    })
+}
+
+fn shift_range(
+    start: Addr,
+    h: usize,
+    w: usize,
+    rows: i64,
+    cols: i64,
+    height: Option<i64>,
+    width: Option<i64>,
+) -> Option<Reference> {
+    let height = height.unwrap_or(h as i64);
+    let width = width.unwrap_or(w as i64);
+    if height < 1 || width < 1 {
+        return None;
+    }
+    let row = shift(start.row, rows)?;
+    let col = shift(start.col, cols)?;
+    let start = Addr { row, col };
+    if height == 1 && width == 1 {
+        Some(Reference::Addr(start))
+    } else {
+        let end = Addr {
+            row: row + height as usize - 1,
+            col: col + width as usize - 1,
+        };
+        Some(Reference::Range(start, end))
+    }
+}
+
+fn shift(base: usize, delta: i64) -> Option<usize> {
+    let r = base as i64 + delta;
+    if r < 0 {
+        None
+    } else {
+        Some(r as usize)
+    }
+}
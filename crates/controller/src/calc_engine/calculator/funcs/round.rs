@@ -0,0 +1,237 @@
+use super::utils::get_f64_args;
+use crate::calc_engine::calculator::calc_vertex::CalcVertex;
+use crate::calc_engine::connector::Connector;
+use logisheets_parser::ast;
+
+pub fn calc_round<C>(args: Vec<CalcVertex>, fetcher: &mut C) -> CalcVertex
+where
+    C: Connector,
+{
+    calc_digits(args, fetcher, None, f64::round)
+}
+
+pub fn calc_roundup<C>(args: Vec<CalcVertex>, fetcher: &mut C) -> CalcVertex
+where
+    C: Connector,
+{
+    calc_digits(args, fetcher, None, |n| n.signum() * n.abs().ceil())
+}
+
+pub fn calc_rounddown<C>(args: Vec<CalcVertex>, fetcher: &mut C) -> CalcVertex
+where
+    C: Connector,
+{
+    calc_digits(args, fetcher, None, f64::trunc)
+}
+
+pub fn calc_trunc<C>(args: Vec<CalcVertex>, fetcher: &mut C) -> CalcVertex
+where
+    C: Connector,
+{
+    calc_digits(args, fetcher, Some(0.), f64::trunc)
+}
+
+pub fn calc_int<C>(args: Vec<CalcVertex>, fetcher: &mut C) -> CalcVertex
+where
+    C: Connector,
+{
+    match get_f64_args(args, fetcher, &[None]) {
+        Ok(nums) => CalcVertex::from_number(nums[0].floor()),
+        Err(e) => CalcVertex::from_error(e),
+    }
+}
+
+pub fn calc_mround<C>(args: Vec<CalcVertex>, fetcher: &mut C) -> CalcVertex
+where
+    C: Connector,
+{
+    let nums = match get_f64_args(args, fetcher, &[None, None]) {
+        Ok(nums) => nums,
+        Err(e) => return CalcVertex::from_error(e),
+    };
+    let (n, multiple) = (nums[0], nums[1]);
+    if multiple == 0. {
+        return CalcVertex::from_number(0.);
+    }
+    assert_or_return!(n * multiple >= 0., ast::Error::Num);
+    let res = clean(n / multiple).round() * multiple;
+    CalcVertex::from_number(clean(res))
+}
+
+pub fn calc_ceiling<C>(args: Vec<CalcVertex>, fetcher: &mut C) -> CalcVertex
+where
+    C: Connector,
+{
+    let nums = match get_f64_args(args, fetcher, &[None, None]) {
+        Ok(nums) => nums,
+        Err(e) => return CalcVertex::from_error(e),
+    };
+    let (n, significance) = (nums[0], nums[1]);
+    if n == 0. || significance == 0. {
+        return CalcVertex::from_number(0.);
+    }
+    assert_or_return!(n < 0. || significance > 0., ast::Error::Num);
+    let res = clean(n / significance).ceil() * significance;
+    CalcVertex::from_number(clean(res))
+}
+
+pub fn calc_floor<C>(args: Vec<CalcVertex>, fetcher: &mut C) -> CalcVertex
+where
+    C: Connector,
+{
+    let nums = match get_f64_args(args, fetcher, &[None, None]) {
+        Ok(nums) => nums,
+        Err(e) => return CalcVertex::from_error(e),
+    };
+    let (n, significance) = (nums[0], nums[1]);
+    if n == 0. {
+        return CalcVertex::from_number(0.);
+    }
+    assert_or_return!(significance != 0., ast::Error::Div0);
+    assert_or_return!(n < 0. || significance > 0., ast::Error::Num);
+    let res = clean(n / significance).floor() * significance;
+    CalcVertex::from_number(clean(res))
+}
+
+/// CEILING.MATH(number, [significance], [mode]). A nonzero mode rounds
+/// negative numbers away from zero.
+pub fn calc_ceiling_math<C>(args: Vec<CalcVertex>, fetcher: &mut C) -> CalcVertex
+where
+    C: Connector,
+{
+    calc_math(args, fetcher, f64::ceil, f64::floor)
+}
+
+/// FLOOR.MATH(number, [significance], [mode]). A nonzero mode rounds
+/// negative numbers toward zero.
+pub fn calc_floor_math<C>(args: Vec<CalcVertex>, fetcher: &mut C) -> CalcVertex
+where
+    C: Connector,
+{
+    calc_math(args, fetcher, f64::floor, f64::ceil)
+}
+
+/// CEILING.PRECISE and ISO.CEILING ignore the sign of the significance.
+pub fn calc_ceiling_precise<C>(args: Vec<CalcVertex>, fetcher: &mut C) -> CalcVertex
+where
+    C: Connector,
+{
+    calc_precise(args, fetcher, f64::ceil)
+}
+
+pub fn calc_floor_precise<C>(args: Vec<CalcVertex>, fetcher: &mut C) -> CalcVertex
+where
+    C: Connector,
+{
+    calc_precise(args, fetcher, f64::floor)
+}
+
+fn calc_digits<C, F>(
+    args: Vec<CalcVertex>,
+    fetcher: &mut C,
+    default_digits: Option<f64>,
+    func: F,
+) -> CalcVertex
+where
+    C: Connector,
+    F: Fn(f64) -> f64,
+{
+    match get_f64_args(args, fetcher, &[None, default_digits]) {
+        Ok(nums) => CalcVertex::from_number(round_to_digits(nums[0], nums[1], func)),
+        Err(e) => CalcVertex::from_error(e),
+    }
+}
+
+fn calc_math<C, F, G>(
+    args: Vec<CalcVertex>,
+    fetcher: &mut C,
+    func: F,
+    neg_mode_func: G,
+) -> CalcVertex
+where
+    C: Connector,
+    F: Fn(f64) -> f64,
+    G: Fn(f64) -> f64,
+{
+    let nums = match get_f64_args(args, fetcher, &[None, Some(1.), Some(0.)]) {
+        Ok(nums) => nums,
+        Err(e) => return CalcVertex::from_error(e),
+    };
+    let (n, significance, mode) = (nums[0], nums[1].abs(), nums[2]);
+    if significance == 0. {
+        return CalcVertex::from_number(0.);
+    }
+    let q = clean(n / significance);
+    let res = if n < 0. && mode != 0. {
+        neg_mode_func(q)
+    } else {
+        func(q)
+    };
+    CalcVertex::from_number(clean(res * significance))
+}
+
+fn calc_precise<C, F>(args: Vec<CalcVertex>, fetcher: &mut C, func: F) -> CalcVertex
+where
+    C: Connector,
+    F: Fn(f64) -> f64,
+{
+    let nums = match get_f64_args(args, fetcher, &[None, Some(1.)]) {
+        Ok(nums) => nums,
+        Err(e) => return CalcVertex::from_error(e),
+    };
+    let (n, significance) = (nums[0], nums[1].abs());
+    if significance == 0. {
+        return CalcVertex::from_number(0.);
+    }
+    let res = func(clean(n / significance)) * significance;
+    CalcVertex::from_number(clean(res))
+}
+
+pub fn round_to_digits<F>(n: f64, digits: f64, func: F) -> f64
+where
+    F: Fn(f64) -> f64,
+{
+    // 10^digits overflows beyond 308, and no number has digits so far from
+    // the decimal point.
+    let digits = digits.trunc();
+    if digits > 308. {
+        return n;
+    } else if digits < -308. {
+        return 0.;
+    }
+    let digits = digits as i32;
+    if digits >= 0 {
+        let factor = 10_f64.powi(digits);
+        let scaled = n * factor;
+        if !scaled.is_finite() {
+            return n;
+        }
+        func(clean(scaled)) / factor
+    } else {
+        let factor = 10_f64.powi(-digits);
+        func(clean(n / factor)) * factor
+    }
+}
+
+/// Drops the binary noise beyond the 15 significant digits Excel keeps, so
+/// that 2.675 * 100 is treated as 267.5 rather than 267.49999999999997.
+fn clean(n: f64) -> f64 {
+    if !n.is_finite() {
+        return n;
+    }
+    format!("{:.14e}", n).parse::<f64>().unwrap_or(n)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{clean, round_to_digits};
+
+    #[test]
+    fn round_to_digits_test() {
+        assert_eq!(round_to_digits(2.675, 2., f64::round), 2.68);
+        assert_eq!(round_to_digits(-2.5, 0., f64::round), -3.);
+        assert_eq!(round_to_digits(1234.5, -2., f64::round), 1200.);
+        assert_eq!(round_to_digits(-3.14159, 3., f64::trunc), -3.141);
+        assert_eq!(clean(0.1 + 0.2), 0.3);
+    }
+}
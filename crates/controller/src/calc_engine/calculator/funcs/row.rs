@@ -22,6 +22,7 @@ where
                 Reference::Range(_, _) => todo!(),
             },
             CalcVertex::Union(_) => todo!(),
+            CalcVertex::Lambda(_) => CalcVertex::from_error(ast::Error::Value),
         },
         None => CalcVertex::from_number(fetcher.get_curr_addr().row as f64 + 1_f64),
     }
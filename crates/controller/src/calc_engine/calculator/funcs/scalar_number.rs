@@ -7,7 +7,7 @@ use logisheets_parser::ast;
 fn calc<C, F>(args: Vec<CalcVertex>, fetcher: &mut C, func: F) -> CalcVertex
 where
     C: Connector,
-    F: 'static + Fn(f64) -> f64,
+    F: 'static + Fn(f64) -> f64 + Send + Sync,
 {
     if args.len() != 1 {
         return CalcVertex::from_error(ast::Error::Unspecified);
@@ -6,7 +6,7 @@ use logisheets_parser::ast;
 pub fn calc<C, F>(args: Vec<CalcVertex>, fetcher: &mut C, func: F) -> CalcVertex
 where
     C: Connector,
-    F: 'static + Fn(&str) -> String,
+    F: 'static + Fn(&str) -> String + Send + Sync,
 {
     if args.len() != 1 {
         return CalcVertex::from_error(ast::Error::Unspecified);
@@ -77,3 +77,46 @@ where
 {
     calc(args, fetcher, |a| a.to_lowercase().to_string())
 }
+
+/// Capitalizes the letters that follow a non-letter and lowers the others.
+pub fn calc_proper<C>(args: Vec<CalcVertex>, fetcher: &mut C) -> CalcVertex
+where
+    C: Connector,
+{
+    calc(args, fetcher, proper)
+}
+
+/// Removes the nonprintable chars, 0 to 31, from the text.
+pub fn calc_clean<C>(args: Vec<CalcVertex>, fetcher: &mut C) -> CalcVertex
+where
+    C: Connector,
+{
+    calc(args, fetcher, |a| {
+        a.chars().filter(|c| *c as u32 >= 32).collect()
+    })
+}
+
+fn proper(text: &str) -> String {
+    let mut prev_is_letter = false;
+    text.chars().fold(String::new(), |mut prev, c| {
+        if prev_is_letter {
+            prev.extend(c.to_lowercase());
+        } else {
+            prev.extend(c.to_uppercase());
+        }
+        prev_is_letter = c.is_alphabetic();
+        prev
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::proper;
+
+    #[test]
+    fn proper_test() {
+        assert_eq!(proper("this is a TITLE"), "This Is A Title");
+        assert_eq!(proper("2-way street"), "2-Way Street");
+        assert_eq!(proper("76BudGet"), "76Budget");
+    }
+}
@@ -0,0 +1,81 @@
+use super::utils::{get_scalar_f64, get_scalar_text};
+use crate::calc_engine::calculator::calc_vertex::CalcVertex;
+use crate::calc_engine::connector::Connector;
+use logisheets_parser::ast;
+use unicode_segmentation::UnicodeSegmentation;
+
+/// SUBSTITUTE(text, old_text, new_text, [instance_num])
+///
+/// Without `instance_num` every occurrence is replaced.
+pub fn calc_substitute<C>(args: Vec<CalcVertex>, fetcher: &mut C) -> CalcVertex
+where
+    C: Connector,
+{
+    assert_or_return!(args.len() == 3 || args.len() == 4, ast::Error::Unspecified);
+    let mut args_iter = args.into_iter();
+    let mut texts = vec![];
+    for arg in args_iter.by_ref().take(3) {
+        match get_scalar_text(fetcher, arg) {
+            Ok(t) => texts.push(t),
+            Err(e) => return CalcVertex::from_error(e),
+        }
+    }
+    let instance = match args_iter.next() {
+        Some(arg) => match get_scalar_f64(fetcher, arg) {
+            Ok(n) => Some(n.trunc()),
+            Err(e) => return CalcVertex::from_error(e),
+        },
+        None => None,
+    };
+    let (text, old, new) = (&texts[0], &texts[1], &texts[2]);
+    if old.is_empty() {
+        return CalcVertex::from_string(text.clone());
+    }
+    match instance {
+        None => CalcVertex::from_string(text.replace(old.as_str(), new)),
+        Some(n) => {
+            assert_or_return!(n >= 1., ast::Error::Value);
+            let res = match text.match_indices(old.as_str()).nth(n as usize - 1) {
+                Some((idx, _)) => {
+                    let mut res = text[..idx].to_string();
+                    res.push_str(new);
+                    res.push_str(&text[idx + old.len()..]);
+                    res
+                }
+                None => text.clone(),
+            };
+            CalcVertex::from_string(res)
+        }
+    }
+}
+
+/// REPLACE(old_text, start_num, num_chars, new_text)
+pub fn calc_replace<C>(args: Vec<CalcVertex>, fetcher: &mut C) -> CalcVertex
+where
+    C: Connector,
+{
+    assert_or_return!(args.len() == 4, ast::Error::Unspecified);
+    let mut args_iter = args.into_iter();
+    let old_text = match get_scalar_text(fetcher, args_iter.next().unwrap()) {
+        Ok(t) => t,
+        Err(e) => return CalcVertex::from_error(e),
+    };
+    let start = match get_scalar_f64(fetcher, args_iter.next().unwrap()) {
+        Ok(n) => n.trunc(),
+        Err(e) => return CalcVertex::from_error(e),
+    };
+    let n = match get_scalar_f64(fetcher, args_iter.next().unwrap()) {
+        Ok(n) => n.trunc(),
+        Err(e) => return CalcVertex::from_error(e),
+    };
+    let new_text = match get_scalar_text(fetcher, args_iter.next().unwrap()) {
+        Ok(t) => t,
+        Err(e) => return CalcVertex::from_error(e),
+    };
+    assert_or_return!(start >= 1. && n >= 0., ast::Error::Value);
+    let graphemes = old_text.graphemes(true).collect::<Vec<_>>();
+    let start = (start as usize - 1).min(graphemes.len());
+    let end = start.saturating_add(n as usize).min(graphemes.len());
+    let res = graphemes[..start].concat() + &new_text + &graphemes[end..].concat();
+    CalcVertex::from_string(res)
+}
@@ -0,0 +1,41 @@
+use super::utils::get_scalar_text;
+use crate::calc_engine::calculator::calc_vertex::{CalcValue, CalcVertex, Value};
+use crate::calc_engine::connector::Connector;
+use crate::num_fmt::{format_number, format_text};
+use logisheets_parser::ast;
+
+/// TEXT(value, format_text) renders the value with a number format code.
+/// A text which looks like a number is formatted as a number.
+pub fn calc<C>(args: Vec<CalcVertex>, fetcher: &mut C) -> CalcVertex
+where
+    C: Connector,
+{
+    assert_or_return!(args.len() == 2, ast::Error::Unspecified);
+    let mut args_iter = args.into_iter();
+    let value = match fetcher.get_calc_value(args_iter.next().unwrap()) {
+        CalcValue::Scalar(v) => v,
+        _ => return CalcVertex::from_error(ast::Error::Value),
+    };
+    let code = match get_scalar_text(fetcher, args_iter.next().unwrap()) {
+        Ok(t) => t,
+        Err(e) => return CalcVertex::from_error(e),
+    };
+    let date1904 = fetcher.is_date1904();
+    let res = match value {
+        Value::Blank => format_number(&code, 0., date1904),
+        Value::Number(n) => format_number(&code, n, date1904),
+        Value::Text(t) => match t.trim().parse::<f64>() {
+            Ok(n) => format_number(&code, n, date1904),
+            Err(_) => format_text(&code, &t),
+        },
+        Value::Boolean(b) => {
+            return CalcVertex::from_string(String::from(if b { "TRUE" } else { "FALSE" }))
+        }
+        Value::Error(e) => return CalcVertex::from_error(e),
+        Value::Date(_) => return CalcVertex::from_error(ast::Error::Value),
+    };
+    match res {
+        Ok(v) => CalcVertex::from_string(v.text),
+        Err(_) => CalcVertex::from_error(ast::Error::Value),
+    }
+}
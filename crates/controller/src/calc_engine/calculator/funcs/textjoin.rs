@@ -0,0 +1,80 @@
+use super::condition::ConditionRange;
+use super::utils::{convert_f64, convert_text};
+use crate::calc_engine::calculator::calc_vertex::{CalcValue, CalcVertex};
+use crate::calc_engine::connector::Connector;
+use logisheets_parser::ast;
+
+// The max length of a cell text.
+const MAX_LEN: usize = 32767;
+
+/// CONCAT(text1, [text2], ...) accepts ranges, which CONCATENATE does not.
+pub fn calc_concat<C>(args: Vec<CalcVertex>, fetcher: &mut C) -> CalcVertex
+where
+    C: Connector,
+{
+    assert_or_return!(!args.is_empty(), ast::Error::Unspecified);
+    let texts = match get_texts(args, fetcher, false) {
+        Ok(texts) => texts,
+        Err(e) => return CalcVertex::from_error(e),
+    };
+    join(texts, &[String::new()])
+}
+
+/// TEXTJOIN(delimiter, ignore_empty, text1, [text2], ...)
+///
+/// If the delimiter is a range, its values are used in turn.
+pub fn calc_textjoin<C>(args: Vec<CalcVertex>, fetcher: &mut C) -> CalcVertex
+where
+    C: Connector,
+{
+    assert_or_return!(args.len() >= 3, ast::Error::Unspecified);
+    let mut args_iter = args.into_iter();
+    let delimiters = match get_texts(vec![args_iter.next().unwrap()], fetcher, false) {
+        Ok(d) => d,
+        Err(e) => return CalcVertex::from_error(e),
+    };
+    let ignore_empty = match fetcher.get_calc_value(args_iter.next().unwrap()) {
+        CalcValue::Scalar(v) => match convert_f64(v) {
+            Ok(n) => n != 0.,
+            Err(e) => return CalcVertex::from_error(e),
+        },
+        _ => return CalcVertex::from_error(ast::Error::Value),
+    };
+    let texts = match get_texts(args_iter.collect(), fetcher, ignore_empty) {
+        Ok(texts) => texts,
+        Err(e) => return CalcVertex::from_error(e),
+    };
+    join(texts, &delimiters)
+}
+
+fn get_texts<C>(
+    args: Vec<CalcVertex>,
+    fetcher: &mut C,
+    ignore_empty: bool,
+) -> Result<Vec<String>, ast::Error>
+where
+    C: Connector,
+{
+    args.into_iter().try_fold(vec![], |mut prev, arg| {
+        let range = ConditionRange::from_calc_value(fetcher.get_calc_value(arg))?;
+        for v in range.values {
+            let t = convert_text(v)?;
+            if !(ignore_empty && t.is_empty()) {
+                prev.push(t);
+            }
+        }
+        Ok(prev)
+    })
+}
+
+fn join(texts: Vec<String>, delimiters: &[String]) -> CalcVertex {
+    let mut res = String::new();
+    texts.into_iter().enumerate().for_each(|(i, t)| {
+        if i > 0 && !delimiters.is_empty() {
+            res.push_str(&delimiters[(i - 1) % delimiters.len()]);
+        }
+        res.push_str(&t);
+    });
+    assert_or_return!(res.chars().count() <= MAX_LEN, ast::Error::Value);
+    CalcVertex::from_string(res)
+}
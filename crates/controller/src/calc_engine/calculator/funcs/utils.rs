@@ -1,6 +1,7 @@
 use logisheets_parser::ast;
 
-use super::{CalcValue, Value};
+use super::{CalcValue, CalcVertex, Value};
+use crate::calc_engine::connector::Connector;
 
 pub enum ConditionResult {
     True,
@@ -53,7 +54,44 @@ pub fn convert_f64(value: Value) -> Result<f64, ast::Error> {
             }
         }
         Value::Error(e) => Err(e),
-        Value::Date(_) => todo!(),
+        Value::Date(_) => Err(ast::Error::Value),
+    }
+}
+
+pub fn convert_text(value: Value) -> Result<String, ast::Error> {
+    match value {
+        Value::Blank => Ok(String::from("")),
+        Value::Number(n) => Ok(n.to_string()),
+        Value::Text(t) => Ok(t),
+        Value::Boolean(b) => {
+            if b {
+                Ok(String::from("TRUE"))
+            } else {
+                Ok(String::from("FALSE"))
+            }
+        }
+        Value::Error(e) => Err(e),
+        Value::Date(_) => Err(ast::Error::Value),
+    }
+}
+
+pub fn get_scalar_text<C>(fetcher: &mut C, arg: CalcVertex) -> Result<String, ast::Error>
+where
+    C: Connector,
+{
+    match fetcher.get_calc_value(arg) {
+        CalcValue::Scalar(v) => convert_text(v),
+        _ => Err(ast::Error::Value),
+    }
+}
+
+pub fn get_scalar_f64<C>(fetcher: &mut C, arg: CalcVertex) -> Result<f64, ast::Error>
+where
+    C: Connector,
+{
+    match fetcher.get_calc_value(arg) {
+        CalcValue::Scalar(v) => convert_f64(v),
+        _ => Err(ast::Error::Value),
     }
 }
 
@@ -136,17 +174,46 @@ pub fn get_nums_from_value(value: CalcValue) -> Result<Vec<f64>, ast::Error> {
     }
 }
 
+/// Converts the arguments of a function taking scalar numbers only.
+/// `defaults` has one entry per parameter and `None` marks a required one.
+pub fn get_f64_args<C>(
+    args: Vec<CalcVertex>,
+    fetcher: &mut C,
+    defaults: &[Option<f64>],
+) -> Result<Vec<f64>, ast::Error>
+where
+    C: Connector,
+{
+    if args.len() > defaults.len() {
+        return Err(ast::Error::Unspecified);
+    }
+    let mut args_iter = args.into_iter();
+    defaults.iter().try_fold(vec![], |mut prev, default| {
+        let n = match (args_iter.next(), default) {
+            (Some(arg), _) => match fetcher.get_calc_value(arg) {
+                CalcValue::Scalar(v) => convert_f64(v)?,
+                _ => return Err(ast::Error::Value),
+            },
+            (None, Some(d)) => *d,
+            (None, None) => return Err(ast::Error::Unspecified),
+        };
+        prev.push(n);
+        Ok(prev)
+    })
+}
+
 #[cfg(test)]
 pub mod tests_utils {
     use logisheets_base::async_func::{AsyncCalcResult, AsyncFuncCommitTrait, Task};
     use logisheets_base::get_active_sheet::GetActiveSheetTrait;
     use logisheets_base::get_curr_addr::GetCurrAddrTrait;
     use logisheets_base::set_curr_cell::SetCurrCellTrait;
-    use logisheets_base::SheetId;
+    use logisheets_base::{NameId, SheetId};
     use logisheets_parser::ast;
 
-    use crate::calc_engine::calculator::calc_vertex::{CalcValue, CalcVertex};
+    use crate::calc_engine::calculator::calc_vertex::{CalcReference, CalcValue, CalcVertex};
     use crate::calc_engine::connector::Connector;
+    use crate::settings::func_registry::{AsyncFunc, NativeFunc};
     use crate::CellId;
     use anyhow::Result;
 
@@ -193,6 +260,7 @@ pub mod tests_utils {
                 CalcVertex::Value(v) => v,
                 CalcVertex::Reference(_) => panic!(),
                 CalcVertex::Union(_) => todo!(),
+                CalcVertex::Lambda(_) => todo!(),
             }
         }
 
@@ -225,10 +293,6 @@ pub mod tests_utils {
             todo!()
         }
 
-        fn is_async_func(&self, _func_name: &str) -> bool {
-            false
-        }
-
         fn get_range(
             &self,
             _sheet_id: &logisheets_base::SheetId,
@@ -236,5 +300,37 @@ pub mod tests_utils {
         ) -> logisheets_base::Range {
             todo!()
         }
+
+        fn get_sheet_id_by_name(&self, _name: &str) -> Option<SheetId> {
+            None
+        }
+
+        fn add_dynamic_dep(&mut self, _reference: &CalcReference) {}
+
+        fn add_volatile(&mut self) {}
+
+        fn is_date1904(&self) -> bool {
+            false
+        }
+
+        fn get_spill(&mut self, _sheet_id: SheetId, _cell_id: &CellId) -> Option<(usize, usize)> {
+            None
+        }
+
+        fn get_defined_name(&self, _name_id: &NameId) -> Option<ast::Node> {
+            None
+        }
+
+        fn get_name_id(&self, _name: &str) -> Option<NameId> {
+            None
+        }
+
+        fn get_native_func(&self, _name: &str) -> Option<NativeFunc> {
+            None
+        }
+
+        fn get_async_func(&self, _name: &str) -> Option<AsyncFunc> {
+            None
+        }
     }
 }
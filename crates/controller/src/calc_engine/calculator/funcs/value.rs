@@ -0,0 +1,95 @@
+use crate::calc_engine::calculator::calc_vertex::{CalcValue, CalcVertex, Value};
+use crate::calc_engine::connector::Connector;
+use logisheets_parser::ast;
+
+/// VALUE(text) accepts the currency sign, thousands separators, a trailing
+/// percent sign and a negative number in parentheses.
+pub fn calc<C>(args: Vec<CalcVertex>, fetcher: &mut C) -> CalcVertex
+where
+    C: Connector,
+{
+    assert_or_return!(args.len() == 1, ast::Error::Unspecified);
+    let arg = args.into_iter().next().unwrap();
+    match fetcher.get_calc_value(arg) {
+        CalcValue::Scalar(v) => match v {
+            Value::Blank => CalcVertex::from_number(0.),
+            Value::Number(n) => CalcVertex::from_number(n),
+            Value::Text(t) => match parse_value(&t) {
+                Some(n) => CalcVertex::from_number(n),
+                None => CalcVertex::from_error(ast::Error::Value),
+            },
+            Value::Error(e) => CalcVertex::from_error(e),
+            _ => CalcVertex::from_error(ast::Error::Value),
+        },
+        _ => CalcVertex::from_error(ast::Error::Value),
+    }
+}
+
+fn parse_value(text: &str) -> Option<f64> {
+    let mut t = text.trim();
+    if t.is_empty() {
+        return Some(0.);
+    }
+    let mut negative = false;
+    if t.starts_with('(') && t.ends_with(')') {
+        negative = true;
+        t = t[1..t.len() - 1].trim();
+    }
+    let mut percent = false;
+    if let Some(s) = t.strip_suffix('%') {
+        percent = true;
+        t = s.trim_end();
+    }
+    if let Some(s) = t.strip_prefix('-') {
+        negative = !negative;
+        t = s;
+    } else if let Some(s) = t.strip_prefix('+') {
+        t = s;
+    }
+    if let Some(s) = t.strip_prefix('$') {
+        t = s;
+    }
+    if t.is_empty() || !t.starts_with(|c: char| c.is_ascii_digit() || c == '.') {
+        return None;
+    }
+    // Thousands separators are only allowed in the integer part.
+    let (int_part, rest) = match t.find(['.', 'e', 'E']) {
+        Some(idx) => t.split_at(idx),
+        None => (t, ""),
+    };
+    let int_part = int_part.replace(',', "");
+    if !int_part.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    if !rest
+        .chars()
+        .all(|c| c.is_ascii_digit() || matches!(c, '.' | 'e' | 'E' | '+' | '-'))
+    {
+        return None;
+    }
+    let mut n = format!("{}{}", int_part, rest).parse::<f64>().ok()?;
+    if percent {
+        n /= 100.;
+    }
+    if negative {
+        n = -n;
+    }
+    Some(n)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_value;
+
+    #[test]
+    fn parse_value_test() {
+        assert_eq!(parse_value(" 1,234.5 "), Some(1234.5));
+        assert_eq!(parse_value("$1,000"), Some(1000.));
+        assert_eq!(parse_value("-12%"), Some(-0.12));
+        assert_eq!(parse_value("($5.25)"), Some(-5.25));
+        assert_eq!(parse_value("1.5E3"), Some(1500.));
+        assert_eq!(parse_value("inf"), None);
+        assert_eq!(parse_value("1.2.3"), None);
+        assert_eq!(parse_value("abc"), None);
+    }
+}
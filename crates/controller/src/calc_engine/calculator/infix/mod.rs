@@ -134,7 +134,7 @@ pub fn calc_infix(
 
 fn calc<F>(lhs: CalcVertex, rhs: CalcVertex, func: F, fetcher: &mut dyn Connector) -> CalcVertex
 where
-    F: 'static + Fn(&Value, &Value) -> Value,
+    F: 'static + Fn(&Value, &Value) -> Value + Send + Sync,
 {
     let (lhs_value, rhs_value) = (fetcher.get_calc_value(lhs), fetcher.get_calc_value(rhs));
     let value = match (lhs_value, rhs_value) {
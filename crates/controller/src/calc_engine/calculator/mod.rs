@@ -4,3 +4,5 @@ mod compare;
 mod funcs;
 pub mod infix;
 mod math;
+
+pub use funcs::VOLATILE_FUNCS;
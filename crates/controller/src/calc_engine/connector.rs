@@ -1,12 +1,14 @@
 use anyhow::Result;
 use logisheets_base::{
     async_func::AsyncFuncCommitTrait, get_active_sheet::GetActiveSheetTrait,
-    get_curr_addr::GetCurrAddrTrait, set_curr_cell::SetCurrCellTrait, CellId, FuncId, Range,
-    RangeId, SheetId, TextId,
+    get_curr_addr::GetCurrAddrTrait, set_curr_cell::SetCurrCellTrait, CellId, FuncId, NameId,
+    Range, RangeId, SheetId, TextId,
 };
 use logisheets_parser::ast;
 
-use super::calculator::calc_vertex::{CalcValue, CalcVertex};
+use crate::settings::func_registry::{AsyncFunc, NativeFunc};
+
+use super::calculator::calc_vertex::{CalcReference, CalcValue, CalcVertex};
 
 pub trait Connector:
     AsyncFuncCommitTrait + GetActiveSheetTrait + GetCurrAddrTrait + SetCurrCellTrait
@@ -18,6 +20,55 @@ pub trait Connector:
     fn get_cell_idx(&mut self, sheet_id: SheetId, cell_id: &CellId) -> Result<(usize, usize)>;
     fn get_cell_id(&mut self, sheet_id: SheetId, row: usize, col: usize) -> Result<CellId>;
     fn commit_calc_values(&mut self, vertex: (SheetId, CellId), result: CalcValue);
-    fn is_async_func(&self, func_name: &str) -> bool;
     fn get_range(&self, sheet_id: &SheetId, range: &RangeId) -> Range;
+    fn get_sheet_id_by_name(&self, name: &str) -> Option<SheetId>;
+    // Record a reference that the current cell depends on but which is not
+    // in its formula, like the result of OFFSET or INDIRECT.
+    fn add_dynamic_dep(&mut self, reference: &CalcReference);
+    // Record that the current cell calls a volatile function. Those called
+    // in the defined names and the user functions are found only here.
+    fn add_volatile(&mut self);
+    // Whether the workbook uses the 1904 date system.
+    fn is_date1904(&self) -> bool;
+    // The size of the array spilled from the cell. A blocked spill has none.
+    fn get_spill(&mut self, sheet_id: SheetId, cell_id: &CellId) -> Option<(usize, usize)>;
+    // The formula of a defined name in this workbook.
+    fn get_defined_name(&self, name_id: &NameId) -> Option<ast::Node>;
+    // Find a defined name in this workbook. Names are case-insensitive.
+    fn get_name_id(&self, name: &str) -> Option<NameId>;
+    // The function registered by the embedders.
+    fn get_native_func(&self, name: &str) -> Option<NativeFunc>;
+    // The function calculated by the embedders out of the transaction.
+    fn get_async_func(&self, name: &str) -> Option<AsyncFunc>;
+}
+
+/// A connector whose formulas can be calculated on a copy of the data, so
+/// that the formulas independent of each other can be calculated at the
+/// same time.
+#[cfg(feature = "parallel")]
+pub trait ParallelConnector: Connector + Sync {
+    /// What is recorded during the calculation besides the values, like the
+    /// dynamic dependencies and the async tasks.
+    type Records: Send;
+
+    // Calculate the formulas in order without committing their values.
+    fn calc_on_copy(
+        &self,
+        formulas: &[(SheetId, CellId, &ast::Node)],
+    ) -> (Vec<CalcValue>, Self::Records);
+    fn merge_records(&mut self, records: Self::Records);
 }
+
+/// The connector needed by the calculation engine. Only the `parallel`
+/// feature needs it to be a `ParallelConnector`.
+#[cfg(feature = "parallel")]
+pub trait EngineConnector: ParallelConnector {}
+
+#[cfg(feature = "parallel")]
+impl<T: ParallelConnector> EngineConnector for T {}
+
+#[cfg(not(feature = "parallel"))]
+pub trait EngineConnector: Connector {}
+
+#[cfg(not(feature = "parallel"))]
+impl<T: Connector> EngineConnector for T {}
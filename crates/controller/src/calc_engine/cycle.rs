@@ -10,11 +10,26 @@ use super::{
     connector::Connector,
 };
 
+/// How the formulas in a circular reference are calculated.
+#[derive(Debug, Clone)]
+pub struct CycleReport {
+    pub vertices: Vec<Vertex>,
+    /// The times the formulas are calculated. It is 0 if the iterative
+    /// calculation is off.
+    pub iterations: u16,
+    /// The largest change of the numbers in the last iteration.
+    pub max_change: f64,
+    /// Whether the values changed less than the maximum change before
+    /// reaching the iteration limit.
+    pub converged: bool,
+}
+
 pub struct CycleCalculator<'a, C>
 where
     C: Connector,
 {
     pub vertices: Vec<Vertex>,
+    pub iterate: bool,
     pub error: f32,
     pub iter_limit: u16,
     pub connector: &'a mut C,
@@ -26,17 +41,30 @@ impl<'a, C> CycleCalculator<'a, C>
 where
     C: Connector,
 {
-    pub fn start(self) {
+    pub fn start(self) -> CycleReport {
         let mut times = 0_u16;
         let mut finish = false;
+        let mut max_change = 0_f64;
         let connector = self.connector;
         let formulas = self.formulas;
         let nodes = self
             .vertices
-            .into_iter()
-            .map(|v| get_cell_id_from_vertex(&v, connector))
-            .flatten()
+            .iter()
+            .filter_map(|v| get_cell_id_from_vertex(v, connector))
+            .filter(|v| formulas.contains_key(v))
             .collect::<Vec<_>>();
+        if !self.iterate {
+            nodes.into_iter().for_each(|v| {
+                let error = CalcValue::Scalar(Value::Error(ast::Error::Calc));
+                connector.commit_calc_values(v, error);
+            });
+            return CycleReport {
+                vertices: self.vertices,
+                iterations: 0,
+                max_change,
+                converged: false,
+            };
+        }
         let error = self.error;
         let mut last_calc = vec![CalcValue::Scalar(Value::Blank); nodes.len()];
         while times < self.iter_limit && !finish {
@@ -61,9 +89,25 @@ where
             {
                 finish = true;
             }
+            max_change = last_calc
+                .iter()
+                .zip(this_calc.iter())
+                .fold(0_f64, |prev, (l, t)| match (l, t) {
+                    (
+                        CalcValue::Scalar(Value::Number(n1)),
+                        CalcValue::Scalar(Value::Number(n2)),
+                    ) => prev.max((n1 - n2).abs()),
+                    _ => prev,
+                });
             times += 1;
             last_calc = this_calc;
         }
+        CycleReport {
+            vertices: self.vertices,
+            iterations: times,
+            max_change,
+            converged: finish,
+        }
     }
 }
 
@@ -83,3 +127,48 @@ fn meet_error(v1: &CalcValue, v2: &CalcValue, error: f32) -> bool {
         _ => false,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::controller::edit_action::EditAction;
+    use crate::controller::Controller;
+    use crate::test_utils::{get_value, input};
+    use logisheets_base::CellValue;
+
+    #[test]
+    fn circular_ref_test() {
+        use logisheets_base::Error;
+        let mut wb = Controller::default();
+        input(&mut wb, 0, 0, 0, "=B1+1");
+        input(&mut wb, 0, 0, 1, "=A1");
+        assert!(matches!(
+            get_value(&mut wb, 0, 0, 0),
+            CellValue::Error(Error::Calc)
+        ));
+        let circular_refs = wb.get_circular_refs();
+        assert_eq!(circular_refs.len(), 1);
+        assert_eq!(circular_refs[0].cells.len(), 2);
+        assert_eq!(circular_refs[0].iterations, 0);
+
+        input(&mut wb, 0, 0, 0, "1");
+        assert!(wb.get_circular_refs().is_empty());
+        assert!(matches!(get_value(&mut wb, 0, 0, 1), CellValue::Number(n) if n == 1.));
+
+        wb.settings.calc_config.iterate = true;
+        input(&mut wb, 0, 0, 2, "=C1/2+1");
+        let circular_refs = wb.get_circular_refs();
+        assert_eq!(circular_refs.len(), 1);
+        assert_eq!(circular_refs[0].cells.len(), 1);
+        assert!(circular_refs[0].converged);
+        assert!(circular_refs[0].iterations > 1);
+        assert!(
+            matches!(get_value(&mut wb, 0, 0, 2), CellValue::Number(n) if (n - 2.).abs() < 0.1)
+        );
+
+        // The reports are kept with the undo history.
+        input(&mut wb, 0, 0, 2, "2");
+        assert!(wb.get_circular_refs().is_empty());
+        wb.handle_action(EditAction::Undo);
+        assert_eq!(wb.get_circular_refs().len(), 1);
+    }
+}
@@ -2,6 +2,9 @@ mod calc_order;
 pub mod calculator;
 pub mod connector;
 mod cycle;
+#[cfg(feature = "parallel")]
+mod parallel;
+mod range_rdeps;
 use logisheets_base::{Addr, BlockRange, NormalRange, Range};
 
 use crate::formula_manager::{FormulaManager, Vertex};
@@ -9,64 +12,228 @@ use crate::settings::CalcConfig;
 use crate::{CellId, SheetId};
 use calc_order::{calc_order, CalcUnit};
 
-use self::connector::Connector;
+use self::connector::{Connector, EngineConnector};
+use calculator::calc_vertex::CalcValue;
 use calculator::calculator::calc;
 use cycle::CycleCalculator;
+pub use cycle::CycleReport;
+use range_rdeps::RangeRdeps;
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet, VecDeque};
 
 pub struct CalcEngine<'a, C>
 where
     C: Connector,
 {
     pub formula_manager: &'a FormulaManager,
-    pub dirty_vertices: std::collections::HashSet<Vertex>,
+    pub dirty_vertices: HashSet<Vertex>,
     pub config: CalcConfig,
     pub connector: C,
 }
 
 impl<'a, C> CalcEngine<'a, C>
 where
-    C: Connector,
+    C: EngineConnector,
 {
-    pub fn start(self) {
-        let graph = &self.formula_manager.graph;
-        let rdeps_fetcher = |v: &Vertex| match graph.get_rdeps(v) {
-            Some(rdeps) => rdeps.iter().map(|r| r.clone()).collect(),
-            None => vec![],
-        };
-        let order = calc_order(&rdeps_fetcher, self.dirty_vertices);
-        let formulas = &self.formula_manager.formulas;
-        let names = &self.formula_manager.names;
-        let CalcConfig { iter_limit, error } = self.config;
-        let mut connector = self.connector;
+    /// Calculate the dirty vertices and the formulas depending on them.
+    /// Return the vertices calculated and the circular references found.
+    pub fn start(self) -> (HashSet<Vertex>, Vec<CycleReport>) {
+        let formula_manager = self.formula_manager;
+        let config = self.config;
+        let connector = RefCell::new(self.connector);
+        let mut dirty_vertices = self.dirty_vertices;
+        let mut calculated = HashSet::new();
+        let mut cycles = vec![];
+        // The cells around an array result are not known before it is
+        // calculated. When it spills into a different area, the formulas
+        // over the new area are calculated in another round.
+        for _ in 0..SPILL_ROUNDS {
+            let (order, _rdeps) = get_order(formula_manager, &connector, dirty_vertices);
+            order.iter().for_each(|unit| match unit {
+                CalcUnit::Cycle(vertices) => calculated.extend(vertices.iter().cloned()),
+                CalcUnit::Node(vertex) => {
+                    calculated.insert(vertex.clone());
+                }
+            });
+            #[cfg(not(feature = "parallel"))]
+            let spilled = calc_round(formula_manager, &config, &connector, order, &mut cycles);
+            #[cfg(feature = "parallel")]
+            let spilled = parallel::calc_round(
+                formula_manager,
+                &config,
+                &connector,
+                order,
+                &_rdeps,
+                &mut cycles,
+            );
+            if spilled.is_empty() {
+                break;
+            }
+            let mut range_rdeps = RangeRdeps::new();
+            dirty_vertices = spilled
+                .iter()
+                .flat_map(|v| range_rdeps.get(v, formula_manager, &mut *connector.borrow_mut()))
+                .collect();
+        }
+        (calculated, cycles)
+    }
 
+    /// Calculate the input formulas only, as in the manual calculation mode.
+    /// Return them and the other formula cells which would be calculated.
+    pub fn start_manual(self, inputs: &HashSet<Vertex>) -> (HashSet<Vertex>, HashSet<Vertex>) {
+        let formula_manager = self.formula_manager;
+        let connector = RefCell::new(self.connector);
+        let (order, _) = get_order(formula_manager, &connector, self.dirty_vertices);
+        let connector = &mut *connector.borrow_mut();
+        let mut calculated = HashSet::new();
+        let mut stale = HashSet::new();
         order.into_iter().for_each(|unit| match unit {
-            CalcUnit::Cycle(vertices) => {
-                let cycle_calc = CycleCalculator {
-                    vertices,
-                    error,
-                    iter_limit,
-                    connector: &mut connector,
-                    names: &names,
-                    formulas: &formulas,
-                };
-                cycle_calc.start();
+            CalcUnit::Node(vertex) if inputs.contains(&vertex) => {
+                calc_node(formula_manager, connector, &vertex);
+                calculated.insert(vertex);
             }
             CalcUnit::Node(vertex) => {
-                if let Some((sheet_id, cell_id)) = get_cell_id_from_vertex(&vertex, &mut connector)
-                {
-                    if let Some(ast_node) = formulas.get(&(sheet_id, cell_id)) {
-                        let curr_sheet = sheet_id;
-                        let (row, col) = connector.get_cell_idx(sheet_id, &cell_id).unwrap();
-                        connector.set_curr_cell(curr_sheet, Addr { row, col });
-                        let v = calc(&ast_node, &mut connector);
-                        connector.commit_calc_values((sheet_id, cell_id), v);
-                    }
-                }
+                stale.insert(vertex);
             }
+            CalcUnit::Cycle(vertices) => stale.extend(vertices),
+        });
+        stale.retain(|v| match get_cell_id_from_vertex(v, connector) {
+            Some(cell) => formula_manager.formulas.contains_key(&cell),
+            None => false,
         });
+        (calculated, stale)
     }
 }
 
+const SPILL_ROUNDS: usize = 8;
+
+// Returns the calculation order and the reverse dependencies of the vertices
+// in it.
+fn get_order<C>(
+    formula_manager: &FormulaManager,
+    connector: &RefCell<C>,
+    dirty_vertices: HashSet<Vertex>,
+) -> (VecDeque<CalcUnit<Vertex>>, HashMap<Vertex, Vec<Vertex>>)
+where
+    C: Connector,
+{
+    let graph = &formula_manager.graph;
+    let dynamic_graph = &formula_manager.dynamic_graph;
+    let range_rdeps = RefCell::new(RangeRdeps::new());
+    let fetched = RefCell::new(HashMap::<Vertex, Vec<Vertex>>::new());
+    let rdeps_fetcher = |v: &Vertex| {
+        if let Some(rdeps) = fetched.borrow().get(v) {
+            return rdeps.clone();
+        }
+        let mut rdeps: Vec<Vertex> = match graph.get_rdeps(v) {
+            Some(rdeps) => rdeps.iter().map(|r| r.clone()).collect(),
+            None => vec![],
+        };
+        if let Some(dynamic_rdeps) = dynamic_graph.get_rdeps(v) {
+            dynamic_rdeps.iter().for_each(|r| {
+                if !rdeps.contains(r) {
+                    rdeps.push(r.clone())
+                }
+            });
+        }
+        range_rdeps
+            .borrow_mut()
+            .get(v, formula_manager, &mut *connector.borrow_mut())
+            .into_iter()
+            .for_each(|r| {
+                if !rdeps.contains(&r) {
+                    rdeps.push(r)
+                }
+            });
+        fetched.borrow_mut().insert(v.clone(), rdeps.clone());
+        rdeps
+    };
+    let order = calc_order(&rdeps_fetcher, dirty_vertices);
+    (order, fetched.into_inner())
+}
+
+// Returns the formula cells whose spilled areas are changed.
+#[cfg(not(feature = "parallel"))]
+fn calc_round<C>(
+    formula_manager: &FormulaManager,
+    config: &CalcConfig,
+    connector: &RefCell<C>,
+    order: VecDeque<CalcUnit<Vertex>>,
+    cycles: &mut Vec<CycleReport>,
+) -> Vec<Vertex>
+where
+    C: Connector,
+{
+    let mut connector = connector.borrow_mut();
+    let connector = &mut *connector;
+    let mut spilled = vec![];
+
+    order.into_iter().for_each(|unit| match unit {
+        CalcUnit::Cycle(vertices) => {
+            cycles.push(calc_cycle(formula_manager, config, connector, vertices))
+        }
+        CalcUnit::Node(vertex) => {
+            if calc_node(formula_manager, connector, &vertex) {
+                spilled.push(vertex);
+            }
+        }
+    });
+    spilled
+}
+
+// Calculate a formula cell and commit its value. Returns true if its spilled
+// area is changed.
+fn calc_node<C>(formula_manager: &FormulaManager, connector: &mut C, vertex: &Vertex) -> bool
+where
+    C: Connector,
+{
+    let (sheet_id, cell_id) = match get_cell_id_from_vertex(vertex, connector) {
+        Some(cell) => cell,
+        None => return false,
+    };
+    match formula_manager.formulas.get(&(sheet_id, cell_id)) {
+        Some(ast_node) => {
+            let (row, col) = connector.get_cell_idx(sheet_id, &cell_id).unwrap();
+            connector.set_curr_cell(sheet_id, Addr { row, col });
+            let v = calc(ast_node, connector);
+            commit(connector, (sheet_id, cell_id), v)
+        }
+        None => false,
+    }
+}
+
+fn calc_cycle<C>(
+    formula_manager: &FormulaManager,
+    config: &CalcConfig,
+    connector: &mut C,
+    vertices: Vec<Vertex>,
+) -> CycleReport
+where
+    C: Connector,
+{
+    let cycle_calc = CycleCalculator {
+        vertices,
+        iterate: config.iterate,
+        error: config.error,
+        iter_limit: config.iter_limit,
+        connector,
+        names: &formula_manager.names,
+        formulas: &formula_manager.formulas,
+    };
+    cycle_calc.start()
+}
+
+// Commit the value of a formula cell. Returns true if its spilled area is
+// changed.
+fn commit<C>(connector: &mut C, (sheet_id, cell_id): (SheetId, CellId), v: CalcValue) -> bool
+where
+    C: Connector,
+{
+    let last_spill = connector.get_spill(sheet_id, &cell_id);
+    connector.commit_calc_values((sheet_id, cell_id), v);
+    connector.get_spill(sheet_id, &cell_id) != last_spill
+}
+
 fn get_cell_id_from_vertex<C>(v: &Vertex, connector: &mut C) -> Option<(SheetId, CellId)>
 where
     C: Connector,
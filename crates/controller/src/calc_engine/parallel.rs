@@ -0,0 +1,182 @@
+use std::cell::RefCell;
+use std::collections::{HashMap, VecDeque};
+
+use logisheets_parser::ast;
+use rayon::prelude::*;
+
+use super::calc_order::CalcUnit;
+use super::connector::ParallelConnector;
+use super::cycle::CycleReport;
+use super::{calc_cycle, calc_node, commit, get_cell_id_from_vertex};
+use crate::formula_manager::{FormulaManager, Vertex};
+use crate::settings::CalcConfig;
+use crate::{CellId, SheetId};
+
+// Copying the data for a thread does not pay off for a few formulas.
+const MIN_PARALLEL_FORMULAS: usize = 32;
+
+// Returns the formula cells whose spilled areas are changed.
+//
+// The units are grouped into levels, and a unit only depends on the units in
+// the levels before it. The formulas in the same level are calculated in
+// parallel on copies of the data, and their values are committed one by one.
+pub fn calc_round<C>(
+    formula_manager: &FormulaManager,
+    config: &CalcConfig,
+    connector: &RefCell<C>,
+    order: VecDeque<CalcUnit<Vertex>>,
+    rdeps: &HashMap<Vertex, Vec<Vertex>>,
+    cycles: &mut Vec<CycleReport>,
+) -> Vec<Vertex>
+where
+    C: ParallelConnector,
+{
+    let mut spilled = vec![];
+    get_levels(order, rdeps).into_iter().for_each(|level| {
+        let mut nodes = vec![];
+        level.into_iter().for_each(|unit| match unit {
+            CalcUnit::Cycle(vertices) => cycles.push(calc_cycle(
+                formula_manager,
+                config,
+                &mut *connector.borrow_mut(),
+                vertices,
+            )),
+            CalcUnit::Node(vertex) => nodes.push(vertex),
+        });
+        let formulas = get_formulas(formula_manager, &mut *connector.borrow_mut(), nodes);
+        if formulas.len() < MIN_PARALLEL_FORMULAS {
+            let connector = &mut *connector.borrow_mut();
+            formulas.into_iter().for_each(|(vertex, _, _, _)| {
+                if calc_node(formula_manager, connector, &vertex) {
+                    spilled.push(vertex);
+                }
+            });
+            return;
+        }
+        let tasks = formulas
+            .iter()
+            .map(|(_, sheet_id, cell_id, node)| (*sheet_id, *cell_id, *node))
+            .collect::<Vec<_>>();
+        let chunk_size =
+            (tasks.len() + rayon::current_num_threads() - 1) / rayon::current_num_threads();
+        let results = {
+            let connector = &*connector.borrow();
+            tasks
+                .par_chunks(chunk_size)
+                .map(|chunk| connector.calc_on_copy(chunk))
+                .collect::<Vec<_>>()
+        };
+        let connector = &mut *connector.borrow_mut();
+        let mut formulas = formulas.into_iter();
+        results.into_iter().for_each(|(values, records)| {
+            values.into_iter().for_each(|v| {
+                let (vertex, sheet_id, cell_id, _) = formulas.next().unwrap();
+                if commit(connector, (sheet_id, cell_id), v) {
+                    spilled.push(vertex);
+                }
+            });
+            connector.merge_records(records);
+        });
+    });
+    spilled
+}
+
+fn get_formulas<'a, C>(
+    formula_manager: &'a FormulaManager,
+    connector: &mut C,
+    nodes: Vec<Vertex>,
+) -> Vec<(Vertex, SheetId, CellId, &'a ast::Node)>
+where
+    C: ParallelConnector,
+{
+    nodes
+        .into_iter()
+        .filter_map(|vertex| {
+            let (sheet_id, cell_id) = get_cell_id_from_vertex(&vertex, connector)?;
+            let node = formula_manager.formulas.get(&(sheet_id, cell_id))?;
+            Some((vertex, sheet_id, cell_id, node))
+        })
+        .collect()
+}
+
+// Put every unit in the level after the latest level of the units it depends
+// on. The order is topological, so the units depended on come first.
+fn get_levels(
+    order: VecDeque<CalcUnit<Vertex>>,
+    rdeps: &HashMap<Vertex, Vec<Vertex>>,
+) -> Vec<Vec<CalcUnit<Vertex>>> {
+    let mut min_levels: HashMap<Vertex, usize> = HashMap::new();
+    let mut levels: Vec<Vec<CalcUnit<Vertex>>> = vec![];
+    order.into_iter().for_each(|unit| {
+        let vertices = match &unit {
+            CalcUnit::Cycle(vertices) => vertices.clone(),
+            CalcUnit::Node(vertex) => vec![vertex.clone()],
+        };
+        let level = vertices
+            .iter()
+            .filter_map(|v| min_levels.get(v))
+            .max()
+            .copied()
+            .unwrap_or(0);
+        vertices
+            .iter()
+            .filter_map(|v| rdeps.get(v))
+            .flatten()
+            .filter(|r| !vertices.contains(r))
+            .for_each(|r| {
+                let min_level = min_levels.entry(r.clone()).or_insert(0);
+                *min_level = std::cmp::max(*min_level, level + 1);
+            });
+        if levels.len() <= level {
+            levels.resize_with(level + 1, Vec::new);
+        }
+        levels[level].push(unit);
+    });
+    levels
+}
+
+#[cfg(test)]
+mod tests {
+    use super::get_levels;
+    use crate::calc_engine::calc_order::CalcUnit;
+    use crate::formula_manager::Vertex;
+    use std::collections::{HashMap, VecDeque};
+
+    #[test]
+    fn levels_test() {
+        let v = |id| Vertex::Range(0, id);
+        // 1 <- 2 <- 4, 3 is independent and 5 is in a cycle with 6.
+        let order = VecDeque::from(vec![
+            CalcUnit::Node(v(1)),
+            CalcUnit::Node(v(3)),
+            CalcUnit::Node(v(2)),
+            CalcUnit::Cycle(vec![v(5), v(6)]),
+            CalcUnit::Node(v(4)),
+        ]);
+        let mut rdeps = HashMap::new();
+        rdeps.insert(v(1), vec![v(2)]);
+        rdeps.insert(v(2), vec![v(4), v(5)]);
+        rdeps.insert(v(5), vec![v(6)]);
+        rdeps.insert(v(6), vec![v(5)]);
+        let levels = get_levels(order, &rdeps)
+            .into_iter()
+            .map(|level| {
+                level
+                    .into_iter()
+                    .map(|unit| match unit {
+                        CalcUnit::Node(v) => vec![v],
+                        CalcUnit::Cycle(vs) => vs,
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            levels,
+            vec![
+                vec![vec![v(1)], vec![v(3)]],
+                vec![vec![v(2)]],
+                vec![vec![v(5), v(6)], vec![v(4)]],
+            ]
+        );
+    }
+}
@@ -0,0 +1,147 @@
+use std::collections::HashMap;
+
+use logisheets_base::{BlockRange, CellId, NormalRange, Range, RangeId, RefAbs, SheetId};
+use logisheets_parser::ast;
+
+use super::calculator::calc_vertex::{CalcVertex, Reference};
+use super::connector::Connector;
+use super::get_cell_id_from_vertex;
+use crate::formula_manager::{FormulaManager, Vertex};
+
+// The graph has no edge from a cell to the ranges containing it. A range is
+// dirty when a cell in it is input, but a formula in the range can be
+// recalculated in the same round and the range must wait for it. So do the
+// ranges over the cells that the array result of a formula spills into.
+pub struct RangeRdeps {
+    // Ranges over more than one cell which have reverse dependencies, by
+    // sheet.
+    areas: HashMap<SheetId, Vec<RangeId>>,
+    bounds: HashMap<(SheetId, RangeId), Option<Reference>>,
+    cache: HashMap<Vertex, Vec<Vertex>>,
+}
+
+impl RangeRdeps {
+    pub fn new() -> Self {
+        RangeRdeps {
+            areas: HashMap::new(),
+            bounds: HashMap::new(),
+            cache: HashMap::new(),
+        }
+    }
+
+    /// Get the ranges over the cell and its spilled cells if it has a
+    /// formula, except the cell itself.
+    pub fn get<C>(&mut self, v: &Vertex, manager: &FormulaManager, connector: &mut C) -> Vec<Vertex>
+    where
+        C: Connector,
+    {
+        if let Some(res) = self.cache.get(v) {
+            return res.clone();
+        }
+        let res = self.find(v, manager, connector);
+        self.cache.insert(v.clone(), res.clone());
+        res
+    }
+
+    fn find<C>(&mut self, v: &Vertex, manager: &FormulaManager, connector: &mut C) -> Vec<Vertex>
+    where
+        C: Connector,
+    {
+        let (sheet_id, cell_id) = match get_cell_id_from_vertex(v, connector) {
+            Some(c) => c,
+            None => return vec![],
+        };
+        if !manager.formulas.contains_key(&(sheet_id, cell_id)) {
+            return vec![];
+        }
+        let (row, col) = match connector.get_cell_idx(sheet_id, &cell_id) {
+            Ok(idx) => idx,
+            Err(_) => return vec![],
+        };
+        let (rows, cols) = connector.get_spill(sheet_id, &cell_id).unwrap_or((1, 1));
+        let (row_end, col_end) = (row + rows - 1, col + cols - 1);
+        let areas = self
+            .areas
+            .entry(sheet_id)
+            .or_insert_with(|| {
+                manager
+                    .range_manager
+                    .get_area_ids(&sheet_id)
+                    .into_iter()
+                    .filter(|r| has_rdeps(manager, &Vertex::Range(sheet_id, *r)))
+                    .collect()
+            })
+            .clone();
+        let mut result = areas
+            .into_iter()
+            .filter(|r| {
+                let bounds = self
+                    .bounds
+                    .entry((sheet_id, *r))
+                    .or_insert_with(|| get_bounds(sheet_id, *r, connector));
+                match bounds {
+                    Some(Reference::Addr(a)) => {
+                        (a.row, a.col) != (row, col)
+                            && (row..=row_end).contains(&a.row)
+                            && (col..=col_end).contains(&a.col)
+                    }
+                    Some(Reference::Range(start, end)) => {
+                        start.row <= row_end
+                            && end.row >= row
+                            && start.col <= col_end
+                            && end.col >= col
+                    }
+                    Some(Reference::RowRange(r)) => r.start <= row_end && r.end >= row,
+                    Some(Reference::ColumnRange(c)) => c.start <= col_end && c.end >= col,
+                    None => false,
+                }
+            })
+            .map(|r| Vertex::Range(sheet_id, r))
+            .collect::<Vec<_>>();
+        // The single cells are looked up directly in the spilled area.
+        (row..=row_end)
+            .flat_map(|r| (col..=col_end).map(move |c| (r, c)))
+            .filter(|idx| *idx != (row, col))
+            .for_each(|(r, c)| {
+                let range = match connector.get_cell_id(sheet_id, r, c) {
+                    Ok(CellId::NormalCell(n)) => Range::Normal(NormalRange::Single(n)),
+                    Ok(CellId::BlockCell(b)) => Range::Block(BlockRange::Single(b)),
+                    Err(_) => return,
+                };
+                if let Some(range_id) = manager.range_manager.get_range_id_assert(&sheet_id, &range)
+                {
+                    let v = Vertex::Range(sheet_id, range_id);
+                    if has_rdeps(manager, &v) {
+                        result.push(v);
+                    }
+                }
+            });
+        result
+    }
+}
+
+fn has_rdeps(manager: &FormulaManager, v: &Vertex) -> bool {
+    [&manager.graph, &manager.dynamic_graph]
+        .iter()
+        .any(|g| g.get_rdeps(v).into_iter().any(|r| !r.is_empty()))
+}
+
+fn get_bounds<C>(sheet_id: SheetId, range_id: RangeId, connector: &mut C) -> Option<Reference>
+where
+    C: Connector,
+{
+    let cell_ref = ast::CellReference::Mut(ast::RangeDisplay {
+        sheet_id,
+        range_id,
+        ref_abs: RefAbs {
+            start_row: false,
+            start_col: false,
+            end_row: false,
+            end_col: false,
+        },
+    });
+    match connector.convert(&cell_ref) {
+        CalcVertex::Reference(r) => Some(r.reference),
+        _ => None,
+    }
+}
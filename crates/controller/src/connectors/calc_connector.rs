@@ -7,7 +7,7 @@ use logisheets_base::get_curr_addr::GetCurrAddrTrait;
 use logisheets_base::set_curr_cell::SetCurrCellTrait;
 use logisheets_base::{
     matrix_value::{cross_product_usize, MatrixValue},
-    Addr, CellId, CellValue, Error, FuncId, NameId, SheetId, TextId,
+    Addr, CellId, CellValue, Error, FuncId, NameId, RangeId, SheetId, TextId,
 };
 use logisheets_base::{BlockRange, CubeCross, NormalRange, Range};
 use logisheets_parser::ast;
@@ -22,12 +22,15 @@ use crate::{
     },
     calc_engine::connector::Connector,
     cell::Cell,
-    container::DataContainer,
+    container::{DataContainer, Spill},
     ext_book_manager::ExtBooksManager,
-    id_manager::{FuncIdManager, TextIdManager},
+    id_manager::{FuncIdManager, NameIdManager, SheetIdManager, TextIdManager},
     navigator::Navigator,
+    settings::func_registry::{AsyncFunc, FuncRegistry, NativeFunc},
     workbook::sheet_pos_manager::SheetPosManager,
 };
+#[cfg(feature = "parallel")]
+use crate::{calc_engine::calculator::calculator::calc, calc_engine::connector::ParallelConnector};
 
 pub struct CalcConnector<'a> {
     pub formula_manager: &'a FormulaManager,
@@ -36,13 +39,97 @@ pub struct CalcConnector<'a> {
     pub ext_links: &'a mut ExtBooksManager,
     pub text_id_manager: &'a mut TextIdManager,
     pub func_id_manager: &'a FuncIdManager,
+    pub name_id_manager: &'a NameIdManager,
     pub names_storage: HashMap<NameId, CalcValue>,
     pub cells_stroage: HashMap<(SheetId, CellId), CalcValue>,
     pub sheet_pos_manager: &'a SheetPosManager,
+    pub sheet_id_manager: &'a SheetIdManager,
+    // The ranges resolved at runtime, grouped by the cells using them. A cell
+    // calculated without using any dynamic references gets an empty list.
+    pub dynamic_deps: &'a mut HashMap<(SheetId, CellId), Vec<(SheetId, Range)>>,
+    // The cells calling volatile functions in their calculation.
+    pub volatile_cells: &'a mut HashSet<(SheetId, CellId)>,
     pub async_func_manager: &'a mut AsyncFuncManager,
-    pub async_funcs: &'a HashSet<String>,
+    pub func_registry: &'a FuncRegistry,
     pub active_sheet: SheetId,
     pub curr_addr: Addr,
+    pub date1904: bool,
+}
+
+/// The managers a calculation reads but never changes.
+#[derive(Clone, Copy)]
+pub struct CalcSources<'a> {
+    pub formula_manager: &'a FormulaManager,
+    pub func_id_manager: &'a FuncIdManager,
+    pub name_id_manager: &'a NameIdManager,
+    pub sheet_pos_manager: &'a SheetPosManager,
+    pub sheet_id_manager: &'a SheetIdManager,
+    pub func_registry: &'a FuncRegistry,
+    pub date1904: bool,
+}
+
+/// A copy of the data a calculation changes, to calculate without touching
+/// the workbook. The navigator and the container are persistent data
+/// structures and cheap to clone.
+pub struct CalcCopy {
+    pub navigator: Navigator,
+    pub container: DataContainer,
+    pub ext_links: ExtBooksManager,
+    pub text_id_manager: TextIdManager,
+    pub dynamic_deps: HashMap<(SheetId, CellId), Vec<(SheetId, Range)>>,
+    pub volatile_cells: HashSet<(SheetId, CellId)>,
+    pub async_func_manager: AsyncFuncManager,
+}
+
+impl CalcCopy {
+    pub fn new(
+        navigator: &Navigator,
+        container: &DataContainer,
+        ext_links: &ExtBooksManager,
+        text_id_manager: &TextIdManager,
+        async_func_manager: &AsyncFuncManager,
+    ) -> Self {
+        CalcCopy {
+            navigator: navigator.clone(),
+            container: container.clone(),
+            ext_links: ext_links.clone(),
+            text_id_manager: text_id_manager.clone(),
+            dynamic_deps: HashMap::new(),
+            volatile_cells: HashSet::new(),
+            async_func_manager: AsyncFuncManager {
+                values: async_func_manager.values.clone(),
+                ..Default::default()
+            },
+        }
+    }
+
+    pub fn get_connector<'a>(
+        &'a mut self,
+        sources: CalcSources<'a>,
+        active_sheet: SheetId,
+        curr_addr: Addr,
+    ) -> CalcConnector<'a> {
+        CalcConnector {
+            formula_manager: sources.formula_manager,
+            navigator: &mut self.navigator,
+            container: &mut self.container,
+            ext_links: &mut self.ext_links,
+            text_id_manager: &mut self.text_id_manager,
+            func_id_manager: sources.func_id_manager,
+            name_id_manager: sources.name_id_manager,
+            names_storage: HashMap::new(),
+            cells_stroage: HashMap::new(),
+            sheet_pos_manager: sources.sheet_pos_manager,
+            sheet_id_manager: sources.sheet_id_manager,
+            dynamic_deps: &mut self.dynamic_deps,
+            volatile_cells: &mut self.volatile_cells,
+            async_func_manager: &mut self.async_func_manager,
+            func_registry: sources.func_registry,
+            active_sheet,
+            curr_addr,
+            date1904: sources.date1904,
+        }
+    }
 }
 
 impl<'a> GetActiveSheetTrait for CalcConnector<'a> {
@@ -71,8 +158,12 @@ impl<'a> AsyncFuncCommitTrait for CalcConnector<'a> {
         cell_id: CellId,
         task: Task,
     ) -> Option<AsyncCalcResult> {
+        let ttl = self
+            .func_registry
+            .get_async(&task.async_func)
+            .and_then(|f| f.ttl);
         self.async_func_manager
-            .query_or_commit(task, sheet_id, cell_id)
+            .query_or_commit(task, sheet_id, cell_id, ttl)
     }
 }
 
@@ -80,110 +171,14 @@ impl<'a> Connector for CalcConnector<'a> {
     fn convert(&mut self, cr: &ast::CellReference) -> CalcVertex {
         match cr {
             ast::CellReference::Mut(range_display) => {
-                let sheet_id = range_display.sheet_id;
-                let range_id = range_display.range_id;
-                let range = self
-                    .formula_manager
-                    .range_manager
-                    .get_range(&sheet_id, &range_id);
-                match range {
-                    Some(range) => match range {
-                        Range::Normal(nomral_range) => match nomral_range {
-                            NormalRange::Single(normal_cell_id) => {
-                                let (row, col) = self
-                                    .navigator
-                                    .fetch_normal_cell_idx(&sheet_id, &normal_cell_id)
-                                    .unwrap();
-                                CalcVertex::Reference(CalcReference {
-                                    from_sheet: None,
-                                    sheet: sheet_id,
-                                    reference: Reference::Addr(Addr { row, col }),
-                                })
-                            }
-                            NormalRange::RowRange(start_id, end_id) => {
-                                let start =
-                                    self.navigator.fetch_row_idx(&sheet_id, &start_id).unwrap();
-                                let end = self.navigator.fetch_row_idx(&sheet_id, &end_id).unwrap();
-                                CalcVertex::Reference(CalcReference {
-                                    from_sheet: None,
-                                    sheet: sheet_id,
-                                    reference: Reference::RowRange(RowRange { start, end }),
-                                })
-                            }
-                            NormalRange::ColRange(start_id, end_id) => {
-                                let start =
-                                    self.navigator.fetch_col_idx(&sheet_id, &start_id).unwrap();
-                                let end = self.navigator.fetch_col_idx(&sheet_id, &end_id).unwrap();
-                                CalcVertex::Reference(CalcReference {
-                                    from_sheet: None,
-                                    sheet: sheet_id,
-                                    reference: Reference::ColumnRange(ColRange { start, end }),
-                                })
-                            }
-                            NormalRange::AddrRange(start, end) => {
-                                let (start_row, start_col) = self
-                                    .navigator
-                                    .fetch_normal_cell_idx(&sheet_id, &start)
-                                    .unwrap();
-                                let (end_row, end_col) = self
-                                    .navigator
-                                    .fetch_normal_cell_idx(&sheet_id, &end)
-                                    .unwrap();
-                                CalcVertex::Reference(CalcReference {
-                                    from_sheet: None,
-                                    sheet: sheet_id,
-                                    reference: Reference::Range(
-                                        Addr {
-                                            row: start_row,
-                                            col: start_col,
-                                        },
-                                        Addr {
-                                            row: end_row,
-                                            col: end_col,
-                                        },
-                                    ),
-                                })
-                            }
-                        },
-                        Range::Block(block_range) => match block_range {
-                            BlockRange::Single(block_cell_id) => {
-                                let (row, col) = self
-                                    .navigator
-                                    .fetch_cell_idx(&sheet_id, &CellId::BlockCell(block_cell_id))
-                                    .unwrap();
-                                CalcVertex::Reference(CalcReference {
-                                    from_sheet: None,
-                                    sheet: sheet_id,
-                                    reference: Reference::Addr(Addr { row, col }),
-                                })
-                            }
-                            BlockRange::AddrRange(start, end) => {
-                                let (start_row, start_col) = self
-                                    .navigator
-                                    .fetch_cell_idx(&sheet_id, &CellId::BlockCell(start))
-                                    .unwrap();
-                                let (end_row, end_col) = self
-                                    .navigator
-                                    .fetch_cell_idx(&sheet_id, &CellId::BlockCell(end))
-                                    .unwrap();
-                                CalcVertex::Reference(CalcReference {
-                                    from_sheet: None,
-                                    sheet: sheet_id,
-                                    reference: Reference::Range(
-                                        Addr {
-                                            row: start_row,
-                                            col: start_col,
-                                        },
-                                        Addr {
-                                            row: end_row,
-                                            col: end_col,
-                                        },
-                                    ),
-                                })
-                            }
-                        },
-                    },
-                    None => panic!("can not find the range id"),
+                self.convert_range(range_display.sheet_id, range_display.range_id)
+            }
+            ast::CellReference::Table(table_ref) => {
+                let vertex = self.convert_range(table_ref.sheet_id, table_ref.range_id);
+                if table_ref.specifiers.contains(&ast::TableSpecifier::ThisRow) {
+                    self.pick_curr_row(vertex)
+                } else {
+                    vertex
                 }
             }
             ast::CellReference::UnMut(cube) => {
@@ -233,6 +228,8 @@ impl<'a> Connector for CalcConnector<'a> {
     fn get_calc_value(&mut self, vertex: CalcVertex) -> CalcValue {
         match vertex {
             CalcVertex::Value(v) => v,
+            // A LAMBDA can not be the result of a cell.
+            CalcVertex::Lambda(_) => CalcValue::Scalar(Value::Error(ast::Error::Calc)),
             CalcVertex::Union(union) => {
                 let values = union
                     .into_iter()
@@ -311,12 +308,29 @@ impl<'a> Connector for CalcConnector<'a> {
         let sheet_id = vertex.0;
         let cell_id = vertex.1;
         let cell_idx = self.navigator.fetch_cell_idx(&sheet_id, &cell_id).unwrap();
+        self.dynamic_deps.entry(vertex).or_default();
+        let result = match result {
+            CalcValue::Range(r) if r.get_avail_size() == (1, 1) => {
+                let v = match r.visit(0, 0) {
+                    Ok(v) => v.clone(),
+                    Err(v) => v,
+                };
+                CalcValue::Scalar(v)
+            }
+            _ => result,
+        };
+        let sheet = self.container.get_sheet_container(sheet_id);
+        if let Some(spill) = sheet.spills.get(&cell_id).filter(|s| s.cse).copied() {
+            self.fill_cse(sheet_id, cell_idx, spill, result);
+            return;
+        }
+        self.clear_spill(sheet_id, &cell_id);
         match result {
             CalcValue::Scalar(v) => {
                 let cell_value = value_to_cell_value(v, &mut |t| self.text_id_manager.get_id(&t));
                 self.set_cell_value(sheet_id, cell_idx.0, cell_idx.1, cell_value);
             }
-            CalcValue::Range(_) => unreachable!(),
+            CalcValue::Range(r) => self.spill(sheet_id, cell_id, cell_idx, r),
             CalcValue::Union(_) => {
                 self.set_cell_value(
                     sheet_id,
@@ -329,8 +343,8 @@ impl<'a> Connector for CalcConnector<'a> {
         }
     }
 
-    fn is_async_func(&self, func_name: &str) -> bool {
-        self.async_funcs.get(func_name).is_some()
+    fn get_async_func(&self, name: &str) -> Option<AsyncFunc> {
+        self.func_registry.get_async(name).cloned()
     }
 
     fn get_range(&self, sheet_id: &SheetId, range: &u32) -> Range {
@@ -339,9 +353,325 @@ impl<'a> Connector for CalcConnector<'a> {
             .get_range(sheet_id, range)
             .unwrap()
     }
+
+    fn get_sheet_id_by_name(&self, name: &str) -> Option<SheetId> {
+        let name = name.to_lowercase();
+        self.sheet_id_manager
+            .ids
+            .iter()
+            .find(|(n, _)| n.to_lowercase() == name)
+            .map(|(_, id)| *id)
+            .filter(|id| self.sheet_pos_manager.get_sheet_idx(id).is_some())
+    }
+
+    fn add_dynamic_dep(&mut self, reference: &CalcReference) {
+        if reference.from_sheet.is_some() {
+            return;
+        }
+        let sheet_id = reference.sheet;
+        let range = match &reference.reference {
+            Reference::Addr(addr) => self
+                .navigator
+                .fetch_cell_id(&sheet_id, addr.row, addr.col)
+                .ok()
+                .map(|cell_id| match cell_id {
+                    CellId::NormalCell(c) => Range::Normal(NormalRange::Single(c)),
+                    CellId::BlockCell(c) => Range::Block(BlockRange::Single(c)),
+                }),
+            Reference::ColumnRange(cr) => {
+                let start = self.navigator.fetch_col_id(&sheet_id, cr.start);
+                let end = self.navigator.fetch_col_id(&sheet_id, cr.end);
+                match (start, end) {
+                    (Ok(s), Ok(e)) => Some(Range::Normal(NormalRange::ColRange(s, e))),
+                    _ => None,
+                }
+            }
+            Reference::RowRange(rr) => {
+                let start = self.navigator.fetch_row_id(&sheet_id, rr.start);
+                let end = self.navigator.fetch_row_id(&sheet_id, rr.end);
+                match (start, end) {
+                    (Ok(s), Ok(e)) => Some(Range::Normal(NormalRange::RowRange(s, e))),
+                    _ => None,
+                }
+            }
+            Reference::Range(start, end) => {
+                let start = self
+                    .navigator
+                    .fetch_cell_id(&sheet_id, start.row, start.col);
+                let end = self.navigator.fetch_cell_id(&sheet_id, end.row, end.col);
+                match (start, end) {
+                    (Ok(CellId::NormalCell(s)), Ok(CellId::NormalCell(e))) => {
+                        Some(Range::Normal(NormalRange::AddrRange(s, e)))
+                    }
+                    (Ok(CellId::BlockCell(s)), Ok(CellId::BlockCell(e)))
+                        if s.block_id == e.block_id =>
+                    {
+                        Some(Range::Block(BlockRange::AddrRange(s, e)))
+                    }
+                    // Ranges across the border of a block can not be
+                    // represented by the range manager.
+                    _ => None,
+                }
+            }
+        };
+        let curr_cell = self.navigator.fetch_cell_id(
+            &self.active_sheet,
+            self.curr_addr.row,
+            self.curr_addr.col,
+        );
+        if let (Some(range), Ok(cell_id)) = (range, curr_cell) {
+            self.dynamic_deps
+                .entry((self.active_sheet, cell_id))
+                .or_default()
+                .push((sheet_id, range));
+        }
+    }
+
+    fn add_volatile(&mut self) {
+        let curr_cell = self.navigator.fetch_cell_id(
+            &self.active_sheet,
+            self.curr_addr.row,
+            self.curr_addr.col,
+        );
+        if let Ok(cell_id) = curr_cell {
+            self.volatile_cells.insert((self.active_sheet, cell_id));
+        }
+    }
+
+    fn is_date1904(&self) -> bool {
+        self.date1904
+    }
+
+    fn get_spill(&mut self, sheet_id: SheetId, cell_id: &CellId) -> Option<(usize, usize)> {
+        let sheet = self.container.get_sheet_container(sheet_id);
+        match sheet.spills.get(cell_id) {
+            Some(s) if !s.blocked => Some((s.rows, s.cols)),
+            _ => None,
+        }
+    }
+
+    fn get_defined_name(&self, name_id: &NameId) -> Option<ast::Node> {
+        self.formula_manager.names.get(name_id).cloned()
+    }
+
+    fn get_native_func(&self, name: &str) -> Option<NativeFunc> {
+        self.func_registry.get(name).cloned()
+    }
+
+    fn get_name_id(&self, name: &str) -> Option<NameId> {
+        if let Some(id) = self.name_id_manager.find_local_id(self.active_sheet, name) {
+            return Some(id);
+        }
+        self.name_id_manager
+            .ids
+            .iter()
+            .find(|((book, n), _)| *book == 0 && n.eq_ignore_ascii_case(name))
+            .map(|(_, id)| *id)
+    }
+}
+
+#[cfg(feature = "parallel")]
+impl<'a> ParallelConnector for CalcConnector<'a> {
+    type Records = (
+        HashMap<(SheetId, CellId), Vec<(SheetId, Range)>>,
+        HashSet<(SheetId, CellId)>,
+        AsyncFuncManager,
+    );
+
+    fn calc_on_copy(
+        &self,
+        formulas: &[(SheetId, CellId, &ast::Node)],
+    ) -> (Vec<CalcValue>, Self::Records) {
+        let mut copy = CalcCopy::new(
+            self.navigator,
+            self.container,
+            self.ext_links,
+            self.text_id_manager,
+            self.async_func_manager,
+        );
+        let sources = CalcSources {
+            formula_manager: self.formula_manager,
+            func_id_manager: self.func_id_manager,
+            name_id_manager: self.name_id_manager,
+            sheet_pos_manager: self.sheet_pos_manager,
+            sheet_id_manager: self.sheet_id_manager,
+            func_registry: self.func_registry,
+            date1904: self.date1904,
+        };
+        let mut connector = copy.get_connector(sources, self.active_sheet, self.curr_addr);
+        let values = formulas
+            .iter()
+            .map(
+                |(sheet_id, cell_id, node)| match connector.get_cell_idx(*sheet_id, cell_id) {
+                    Ok((row, col)) => {
+                        connector.set_curr_cell(*sheet_id, Addr { row, col });
+                        calc(node, &mut connector)
+                    }
+                    Err(_) => CalcValue::Scalar(Value::Error(ast::Error::Ref)),
+                },
+            )
+            .collect();
+        (
+            values,
+            (
+                copy.dynamic_deps,
+                copy.volatile_cells,
+                copy.async_func_manager,
+            ),
+        )
+    }
+
+    fn merge_records(&mut self, records: Self::Records) {
+        let (dynamic_deps, volatile_cells, mut async_func_manager) = records;
+        dynamic_deps.into_iter().for_each(|(cell, deps)| {
+            self.dynamic_deps.entry(cell).or_default().extend(deps);
+        });
+        self.volatile_cells.extend(volatile_cells);
+        let (tasks, dirties) = async_func_manager.get_calc_tasks();
+        self.async_func_manager.queue.extend(tasks);
+        self.async_func_manager.dirties.extend(dirties);
+        async_func_manager
+            .readers
+            .into_iter()
+            .for_each(|(task, cells)| {
+                let readers = self.async_func_manager.readers.entry(task).or_default();
+                readers.extend(cells);
+            });
+    }
 }
 
 impl<'a> CalcConnector<'a> {
+    fn convert_range(&mut self, sheet_id: SheetId, range_id: RangeId) -> CalcVertex {
+        let range = self
+            .formula_manager
+            .range_manager
+            .get_range(&sheet_id, &range_id);
+        match range {
+            Some(range) => match range {
+                Range::Normal(nomral_range) => match nomral_range {
+                    NormalRange::Single(normal_cell_id) => {
+                        let (row, col) = self
+                            .navigator
+                            .fetch_normal_cell_idx(&sheet_id, &normal_cell_id)
+                            .unwrap();
+                        CalcVertex::Reference(CalcReference {
+                            from_sheet: None,
+                            sheet: sheet_id,
+                            reference: Reference::Addr(Addr { row, col }),
+                        })
+                    }
+                    NormalRange::RowRange(start_id, end_id) => {
+                        let start = self.navigator.fetch_row_idx(&sheet_id, &start_id).unwrap();
+                        let end = self.navigator.fetch_row_idx(&sheet_id, &end_id).unwrap();
+                        CalcVertex::Reference(CalcReference {
+                            from_sheet: None,
+                            sheet: sheet_id,
+                            reference: Reference::RowRange(RowRange { start, end }),
+                        })
+                    }
+                    NormalRange::ColRange(start_id, end_id) => {
+                        let start = self.navigator.fetch_col_idx(&sheet_id, &start_id).unwrap();
+                        let end = self.navigator.fetch_col_idx(&sheet_id, &end_id).unwrap();
+                        CalcVertex::Reference(CalcReference {
+                            from_sheet: None,
+                            sheet: sheet_id,
+                            reference: Reference::ColumnRange(ColRange { start, end }),
+                        })
+                    }
+                    NormalRange::AddrRange(start, end) => {
+                        let (start_row, start_col) = self
+                            .navigator
+                            .fetch_normal_cell_idx(&sheet_id, &start)
+                            .unwrap();
+                        let (end_row, end_col) = self
+                            .navigator
+                            .fetch_normal_cell_idx(&sheet_id, &end)
+                            .unwrap();
+                        CalcVertex::Reference(CalcReference {
+                            from_sheet: None,
+                            sheet: sheet_id,
+                            reference: Reference::Range(
+                                Addr {
+                                    row: start_row,
+                                    col: start_col,
+                                },
+                                Addr {
+                                    row: end_row,
+                                    col: end_col,
+                                },
+                            ),
+                        })
+                    }
+                },
+                Range::Block(block_range) => match block_range {
+                    BlockRange::Single(block_cell_id) => {
+                        let (row, col) = self
+                            .navigator
+                            .fetch_cell_idx(&sheet_id, &CellId::BlockCell(block_cell_id))
+                            .unwrap();
+                        CalcVertex::Reference(CalcReference {
+                            from_sheet: None,
+                            sheet: sheet_id,
+                            reference: Reference::Addr(Addr { row, col }),
+                        })
+                    }
+                    BlockRange::AddrRange(start, end) => {
+                        let (start_row, start_col) = self
+                            .navigator
+                            .fetch_cell_idx(&sheet_id, &CellId::BlockCell(start))
+                            .unwrap();
+                        let (end_row, end_col) = self
+                            .navigator
+                            .fetch_cell_idx(&sheet_id, &CellId::BlockCell(end))
+                            .unwrap();
+                        CalcVertex::Reference(CalcReference {
+                            from_sheet: None,
+                            sheet: sheet_id,
+                            reference: Reference::Range(
+                                Addr {
+                                    row: start_row,
+                                    col: start_col,
+                                },
+                                Addr {
+                                    row: end_row,
+                                    col: end_col,
+                                },
+                            ),
+                        })
+                    }
+                },
+            },
+            None => panic!("can not find the range id"),
+        }
+    }
+
+    // Pick the row of the formula from the reference, like Excel does for
+    // `#This Row`.
+    fn pick_curr_row(&self, vertex: CalcVertex) -> CalcVertex {
+        let row = self.curr_addr.row;
+        match vertex {
+            CalcVertex::Reference(r) if r.sheet == self.active_sheet => {
+                let reference = match r.reference {
+                    Reference::Addr(a) if a.row == row => Reference::Addr(a),
+                    Reference::Range(s, e) if s.row <= row && row <= e.row => {
+                        if s.col == e.col {
+                            Reference::Addr(Addr { row, col: s.col })
+                        } else {
+                            Reference::Range(Addr { row, col: s.col }, Addr { row, col: e.col })
+                        }
+                    }
+                    _ => return CalcVertex::from_error(ast::Error::Value),
+                };
+                CalcVertex::Reference(CalcReference {
+                    from_sheet: None,
+                    sheet: r.sheet,
+                    reference,
+                })
+            }
+            _ => CalcVertex::from_error(ast::Error::Value),
+        }
+    }
+
     fn get_sheet_ids(&self, start: SheetId, end: SheetId) -> Vec<SheetId> {
         let start_idx = self.sheet_pos_manager.get_sheet_idx(&start);
         let end_idx = self.sheet_pos_manager.get_sheet_idx(&end);
@@ -376,7 +706,7 @@ impl<'a> CalcConnector<'a> {
             let idx = self.navigator.fetch_cell_idx(&sheet_id, id);
             if let Ok((r, c)) = idx {
                 if r >= row_start && r <= row_end {
-                    let v = Value::from_cell_value(cell.value.clone(), &|t| {
+                    let v = Value::from_cell_value(cell.value.clone(), self.date1904, &|t| {
                         self.text_id_manager.get_string(t)
                     });
                     matrix.insert(r - row_start, c, v);
@@ -403,7 +733,7 @@ impl<'a> CalcConnector<'a> {
             let idx = self.navigator.fetch_cell_idx(&sheet_id, id);
             if let Ok((r, c)) = idx {
                 if c >= col_start && c <= col_end {
-                    let v = Value::from_cell_value(cell.value.clone(), &|t| {
+                    let v = Value::from_cell_value(cell.value.clone(), self.date1904, &|t| {
                         self.text_id_manager.get_string(t)
                     });
                     matrix.insert(r, c - col_start, v);
@@ -431,7 +761,8 @@ impl<'a> CalcConnector<'a> {
             .collect::<Vec<_>>();
         if start_row == end_row && start_col == end_col {
             let cv = values.into_iter().next().unwrap();
-            let value = Value::from_cell_value(cv, &|t| self.text_id_manager.get_string(t));
+            let value =
+                Value::from_cell_value(cv, self.date1904, &|t| self.text_id_manager.get_string(t));
             CalcValue::Scalar(value)
         } else {
             let mut res: Vec<Vec<Value>> = Vec::with_capacity(end_row - start_row + 1);
@@ -439,9 +770,10 @@ impl<'a> CalcConnector<'a> {
             (start_row..end_row + 1).into_iter().for_each(|_| {
                 let mut row = Vec::<Value>::new();
                 (start_col..end_col + 1).into_iter().for_each(|_| {
-                    let value = Value::from_cell_value(values.next().unwrap(), &|t| {
-                        self.text_id_manager.get_string(t)
-                    });
+                    let value =
+                        Value::from_cell_value(values.next().unwrap(), self.date1904, &|t| {
+                            self.text_id_manager.get_string(t)
+                        });
                     row.push(value);
                 });
                 res.push(row)
@@ -486,6 +818,142 @@ impl<'a> CalcConnector<'a> {
     }
 }
 
+impl<'a> CalcConnector<'a> {
+    // Write the array into the cells from the formula cell, or #SPILL! if any
+    // of them is not empty.
+    fn spill(
+        &mut self,
+        sheet_id: SheetId,
+        cell_id: CellId,
+        (row, col): (usize, usize),
+        values: MatrixValue<Value>,
+    ) {
+        let (rows, cols) = values.get_avail_size();
+        if rows == 0 || cols == 0 {
+            self.set_cell_value(sheet_id, row, col, CellValue::Error(Error::Calc));
+            return;
+        }
+        let blocked = cross_product_usize(row, row + rows - 1, col, col + cols - 1)
+            .into_iter()
+            .filter(|p| *p != (row, col))
+            .any(|(r, c)| self.is_occupied(sheet_id, r, c));
+        let spill = Spill {
+            rows,
+            cols,
+            blocked,
+            cse: false,
+        };
+        self.container
+            .get_sheet_container(sheet_id)
+            .spills
+            .insert(cell_id, spill);
+        if blocked {
+            self.set_cell_value(sheet_id, row, col, CellValue::Error(Error::Spill));
+            return;
+        }
+        cross_product_usize(0, rows - 1, 0, cols - 1)
+            .into_iter()
+            .for_each(|(i, j)| {
+                let v = match values.visit(i, j) {
+                    Ok(v) => v.clone(),
+                    Err(v) => v,
+                };
+                let cell_value = value_to_cell_value(v, &mut |t| self.text_id_manager.get_id(&t));
+                self.set_cell_value(sheet_id, row + i, col + j, cell_value);
+            });
+    }
+
+    // Write the result of a legacy array formula into its fixed area. A row
+    // or a column is repeated to fill it and the rest gets #N/A like Excel.
+    fn fill_cse(
+        &mut self,
+        sheet_id: SheetId,
+        (row, col): (usize, usize),
+        spill: Spill,
+        result: CalcValue,
+    ) {
+        cross_product_usize(0, spill.rows - 1, 0, spill.cols - 1)
+            .into_iter()
+            .for_each(|(i, j)| {
+                let v = match &result {
+                    CalcValue::Scalar(v) => v.clone(),
+                    CalcValue::Range(r) => {
+                        let (rows, cols) = r.get_avail_size();
+                        let i = if rows == 1 { 0 } else { i };
+                        let j = if cols == 1 { 0 } else { j };
+                        if i < rows && j < cols {
+                            match r.visit(i, j) {
+                                Ok(v) => v.clone(),
+                                Err(v) => v,
+                            }
+                        } else {
+                            Value::Error(ast::Error::Na)
+                        }
+                    }
+                    _ => Value::Error(ast::Error::Value),
+                };
+                let cell_value = value_to_cell_value(v, &mut |t| self.text_id_manager.get_id(&t));
+                self.set_cell_value(sheet_id, row + i, col + j, cell_value);
+            });
+    }
+
+    // Remove the values spilled from the cell last time.
+    fn clear_spill(&mut self, sheet_id: SheetId, cell_id: &CellId) {
+        let spill = match self
+            .container
+            .get_sheet_container(sheet_id)
+            .spills
+            .remove(cell_id)
+        {
+            Some(s) if !s.blocked => s,
+            _ => return,
+        };
+        let (row, col) = match self.navigator.fetch_cell_idx(&sheet_id, cell_id) {
+            Ok(idx) => idx,
+            Err(_) => return,
+        };
+        cross_product_usize(row, row + spill.rows - 1, col, col + spill.cols - 1)
+            .into_iter()
+            .filter(|p| *p != (row, col))
+            .for_each(|(r, c)| {
+                if let Ok(cid) = self.navigator.fetch_cell_id(&sheet_id, r, c) {
+                    if let Some(cell) = self.container.get_cell(sheet_id, &cid) {
+                        cell.value = CellValue::Blank;
+                    }
+                }
+            });
+    }
+
+    fn is_occupied(&mut self, sheet_id: SheetId, row: usize, col: usize) -> bool {
+        let cell_id = match self.navigator.fetch_cell_id(&sheet_id, row, col) {
+            Ok(id) => id,
+            Err(_) => return true,
+        };
+        if self
+            .formula_manager
+            .formulas
+            .contains_key(&(sheet_id, cell_id))
+        {
+            return true;
+        }
+        let sheet = self.container.get_sheet_container(sheet_id).clone();
+        if let Some(cell) = sheet.cells.get(&cell_id) {
+            if !matches!(cell.value, CellValue::Blank) {
+                return true;
+            }
+        }
+        sheet.spills.iter().any(|(anchor, spill)| {
+            if spill.blocked {
+                return false;
+            }
+            match self.navigator.fetch_cell_idx(&sheet_id, anchor) {
+                Ok(idx) => spill.contains(idx, row, col),
+                Err(_) => false,
+            }
+        })
+    }
+}
+
 fn value_to_cell_value<F>(value: Value, text_converter: &mut F) -> CellValue
 where
     F: FnMut(String) -> TextId,
@@ -505,6 +973,8 @@ where
             ast::Error::Ref => CellValue::Error(Error::Ref),
             ast::Error::Value => CellValue::Error(Error::Value),
             ast::Error::GettingData => CellValue::Error(Error::GettingData),
+            ast::Error::Spill => CellValue::Error(Error::Spill),
+            ast::Error::Calc => CellValue::Error(Error::Calc),
         },
         Value::Date(d) => CellValue::Date(d),
     }
@@ -4,7 +4,7 @@ use crate::{
     navigator::Navigator,
 };
 use anyhow::Result;
-use logisheets_base::{id_fetcher::IdFetcherTrait, ExtBookId, SheetId};
+use logisheets_base::{id_fetcher::IdFetcherTrait, ExtBookId, NameId, SheetId};
 
 pub struct IdFetcher<'a> {
     pub sheet_id_manager: &'a mut SheetIdManager,
@@ -53,6 +53,10 @@ impl<'a> IdFetcherTrait for IdFetcher<'a> {
         self.name_id_manager.get_id(&(book_id, name.to_owned()))
     }
 
+    fn fetch_local_name_id(&mut self, sheet_id: &SheetId, name: &str) -> Option<NameId> {
+        self.name_id_manager.find_local_id(*sheet_id, name)
+    }
+
     fn fetch_ext_book_id(&mut self, book: &str) -> logisheets_base::ExtBookId {
         self.external_links_manager.fetch_ext_book_id(book)
     }
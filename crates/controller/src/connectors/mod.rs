@@ -4,7 +4,7 @@ mod index_fetcher;
 mod name_fetcher;
 mod vertex_connector;
 
-pub use calc_connector::CalcConnector;
+pub use calc_connector::{CalcConnector, CalcCopy, CalcSources};
 pub use id_fetcher::IdFetcher;
 pub use index_fetcher::IndexFetcher;
 pub use name_fetcher::NameFetcher;
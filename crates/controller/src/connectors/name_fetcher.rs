@@ -1,5 +1,5 @@
 use logisheets_base::{
-    name_fetcher::NameFetcherTrait, CellId, ExtBookId, ExtRef, NameId, Range, SheetId,
+    name_fetcher::NameFetcherTrait, CellId, ExtBookId, ExtRef, NameId, Range, SheetId, TableId,
 };
 
 use crate::{
@@ -44,6 +44,10 @@ impl<'a> NameFetcherTrait for NameFetcher<'a> {
             .unwrap_or(String::from(""))
     }
 
+    fn fetch_book_idx(&self, book_id: &ExtBookId) -> usize {
+        self.external_links_manager.get_book_idx(book_id)
+    }
+
     fn fetch_defined_name(&self, nid: &NameId) -> String {
         match self.name_id_manager.get_string(nid) {
             Some((_, name)) => name,
@@ -51,6 +55,17 @@ impl<'a> NameFetcherTrait for NameFetcher<'a> {
         }
     }
 
+    fn fetch_name_scope(&self, nid: &NameId) -> Option<SheetId> {
+        self.name_id_manager.get_scope(nid)
+    }
+
+    fn fetch_table_name(&self, table_id: &TableId) -> String {
+        self.formula_manager
+            .table_manager
+            .get_table_name(table_id)
+            .unwrap_or_default()
+    }
+
     fn fetch_cell_idx(&mut self, sheet_id: &SheetId, cell_id: &CellId) -> (usize, usize) {
         self.navigator.fetch_cell_idx(sheet_id, cell_id).unwrap()
     }
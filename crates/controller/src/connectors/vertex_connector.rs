@@ -118,6 +118,10 @@ impl<'a> IdFetcherTrait for VertexConnector<'a> {
         self.get_id_fetcher().fetch_name_id(workbook, name)
     }
 
+    fn fetch_local_name_id(&mut self, sheet_id: &SheetId, name: &str) -> Option<NameId> {
+        self.get_id_fetcher().fetch_local_name_id(sheet_id, name)
+    }
+
     fn fetch_ext_book_id(&mut self, book: &str) -> ExtBookId {
         self.get_id_fetcher().fetch_ext_book_id(book)
     }
@@ -18,6 +18,8 @@ impl DataContainer {
         if let Some(container) = res.data.get_mut(&sheet_id) {
             cids.iter().for_each(|cid| {
                 container.cells.remove(cid);
+                container.spills.remove(cid);
+                container.shared_formulas.remove(cid);
             });
             res
         } else {
@@ -85,4 +87,29 @@ pub struct SheetDataContainer {
     pub cells: HashMap<CellId, Cell>,
     pub row_info: RowInfoManager,
     pub col_info: ColInfoManager,
+    // The formula cells whose results are arrays.
+    pub spills: HashMap<CellId, Spill>,
+    // The shared formulas loaded from the file, by their master cells. The
+    // cells that still have the formula of the master are saved as shared.
+    pub shared_formulas: HashMap<CellId, Vec<CellId>>,
+}
+
+/// The area that an array result spills into, starting from its formula
+/// cell. The values in the area are stored in the cells like the others.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Spill {
+    pub rows: usize,
+    pub cols: usize,
+    // Other cells are in the area and the formula cell shows #SPILL!.
+    pub blocked: bool,
+    // A legacy array formula, entered with Ctrl+Shift+Enter. Its area is
+    // fixed and the result is fit into it instead of spilling.
+    pub cse: bool,
+}
+
+impl Spill {
+    pub fn contains(&self, anchor: (usize, usize), row: usize, col: usize) -> bool {
+        (anchor.0..anchor.0 + self.rows).contains(&row)
+            && (anchor.1..anchor.1 + self.cols).contains(&col)
+    }
 }
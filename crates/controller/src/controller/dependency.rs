@@ -0,0 +1,511 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use logisheets_base::{BlockRange, CellId, CubeCross, NormalRange, Range, SheetId};
+
+use crate::formula_manager::Vertex;
+
+use crate::navigator::Navigator;
+
+use super::display::{Dependency, DependencyRange};
+use super::status::Status;
+
+type Bounds = Option<(usize, usize)>;
+
+// The cells covered by a reference. The rows or the columns are none if the
+// whole columns or rows are covered.
+#[derive(Debug, Clone)]
+struct Area {
+    sheets: Vec<SheetId>,
+    rows: Bounds,
+    cols: Bounds,
+}
+
+impl Area {
+    fn intersects(&self, other: &Area) -> bool {
+        self.sheets.iter().any(|s| other.sheets.contains(s))
+            && overlaps(self.rows, other.rows)
+            && overlaps(self.cols, other.cols)
+    }
+}
+
+// The number of the rows in a bucket of the area index.
+const INDEX_BUCKET_ROWS: usize = 1024;
+
+/// The vertices grouped by the sheets and the rows they cover, to find the
+/// ones intersecting an area without checking all of them.
+#[derive(Default)]
+struct AreaIndex {
+    buckets: HashMap<(SheetId, usize), Vec<Vertex>>,
+    // The vertices covering whole columns, which are in every bucket.
+    whole_columns: HashMap<SheetId, Vec<Vertex>>,
+    sheets: HashMap<SheetId, Vec<Vertex>>,
+}
+
+impl AreaIndex {
+    fn add(&mut self, v: &Vertex, area: &Area) {
+        area.sheets.iter().for_each(|s| {
+            self.sheets.entry(*s).or_default().push(v.clone());
+            match area.rows {
+                Some((start, end)) => (start / INDEX_BUCKET_ROWS..=end / INDEX_BUCKET_ROWS)
+                    .for_each(|b| self.buckets.entry((*s, b)).or_default().push(v.clone())),
+                None => self.whole_columns.entry(*s).or_default().push(v.clone()),
+            }
+        });
+    }
+
+    // The vertices which may intersect the area.
+    fn get_candidates(&self, area: &Area) -> HashSet<Vertex> {
+        let mut result = HashSet::new();
+        area.sheets.iter().for_each(|s| match area.rows {
+            Some((start, end)) => {
+                (start / INDEX_BUCKET_ROWS..=end / INDEX_BUCKET_ROWS).for_each(|b| {
+                    result.extend(self.buckets.get(&(*s, b)).into_iter().flatten().cloned())
+                });
+                result.extend(self.whole_columns.get(s).into_iter().flatten().cloned());
+            }
+            None => result.extend(self.sheets.get(s).into_iter().flatten().cloned()),
+        });
+        result
+    }
+}
+
+/// Find the precedents and the dependents of the cells. Besides the
+/// dependency graph, the references resolved in the last calculation, like
+/// the results of OFFSET, and the references in the defined names are
+/// followed.
+pub struct DependencyFinder<'a> {
+    status: &'a Status,
+    // A copy to look up the indexes, which caches them.
+    navigator: Navigator,
+    areas: HashMap<Vertex, Option<Area>>,
+    // Built when first used.
+    formula_index: Option<AreaIndex>,
+    reference_index: Option<AreaIndex>,
+    name_rdeps: Option<HashMap<Vertex, Vec<Vertex>>>,
+}
+
+impl<'a> DependencyFinder<'a> {
+    pub fn new(status: &'a Status) -> Self {
+        DependencyFinder {
+            status,
+            navigator: status.navigator.clone(),
+            areas: HashMap::new(),
+            formula_index: None,
+            reference_index: None,
+            name_rdeps: None,
+        }
+    }
+
+    /// The references in the formulas of the cells in the range. If
+    /// `transitive`, the references in the formulas of the cells referred
+    /// to are included, and so on.
+    pub fn get_precedents(
+        &mut self,
+        sheet_id: SheetId,
+        (row_start, col_start, row_end, col_end): (usize, usize, usize, usize),
+        transitive: bool,
+    ) -> Vec<Dependency> {
+        let area = Area {
+            sheets: vec![sheet_id],
+            rows: Some((row_start, row_end)),
+            cols: Some((col_start, col_end)),
+        };
+        let mut queue: VecDeque<Vertex> = self.get_formulas_in(&area).into();
+        let mut expanded = HashSet::new();
+        let mut found = HashSet::new();
+        let mut result = vec![];
+        while let Some(v) = queue.pop_front() {
+            if !expanded.insert(v.clone()) {
+                continue;
+            }
+            for dep in self.get_deps(&v) {
+                if !found.insert(dep.clone()) {
+                    continue;
+                }
+                if transitive {
+                    match &dep {
+                        Vertex::Name(_) => queue.push_back(dep.clone()),
+                        _ => {
+                            if let Some(area) = self.get_area(&dep) {
+                                queue.extend(self.get_formulas_in(&area));
+                            }
+                        }
+                    }
+                }
+                result.push(dep);
+            }
+        }
+        result.iter().filter_map(|v| self.convert(v)).collect()
+    }
+
+    /// The formula cells and the defined names referring to the cells in the
+    /// range. If `transitive`, the ones referring to them are included, and
+    /// so on.
+    pub fn get_dependents(
+        &mut self,
+        sheet_id: SheetId,
+        (row_start, col_start, row_end, col_end): (usize, usize, usize, usize),
+        transitive: bool,
+    ) -> Vec<Dependency> {
+        let area = Area {
+            sheets: vec![sheet_id],
+            rows: Some((row_start, row_end)),
+            cols: Some((col_start, col_end)),
+        };
+        let mut queue: VecDeque<Vertex> = self.get_references_to(&area).into();
+        let mut expanded = HashSet::new();
+        let mut found = HashSet::new();
+        let mut result = vec![];
+        while let Some(v) = queue.pop_front() {
+            if !expanded.insert(v.clone()) {
+                continue;
+            }
+            for rdep in self.get_rdeps(&v) {
+                if !found.insert(rdep.clone()) {
+                    continue;
+                }
+                if transitive {
+                    match &rdep {
+                        Vertex::Name(_) => queue.push_back(rdep.clone()),
+                        _ => {
+                            if let Some(area) = self.get_area(&rdep) {
+                                queue.extend(self.get_references_to(&area));
+                            }
+                        }
+                    }
+                }
+                result.push(rdep);
+            }
+        }
+        result.iter().filter_map(|v| self.convert(v)).collect()
+    }
+
+    fn get_deps(&self, v: &Vertex) -> HashSet<Vertex> {
+        let formula_manager = &self.status.formula_manager;
+        let mut deps = HashSet::new();
+        if let Vertex::Name(name_id) = v {
+            deps.extend(formula_manager.get_name_deps(name_id));
+        }
+        [&formula_manager.graph, &formula_manager.dynamic_graph]
+            .iter()
+            .filter_map(|g| g.get_deps(v))
+            .for_each(|d| deps.extend(d.iter().cloned()));
+        deps
+    }
+
+    fn get_rdeps(&mut self, v: &Vertex) -> HashSet<Vertex> {
+        let formula_manager = &self.status.formula_manager;
+        let mut rdeps = HashSet::new();
+        [&formula_manager.graph, &formula_manager.dynamic_graph]
+            .iter()
+            .filter_map(|g| g.get_rdeps(v))
+            .for_each(|r| rdeps.extend(r.iter().cloned()));
+        if let Some(names) = self.get_name_rdeps().get(v) {
+            rdeps.extend(names.iter().cloned());
+        }
+        rdeps
+    }
+
+    // The defined names by the references in their formulas.
+    fn get_name_rdeps(&mut self) -> &HashMap<Vertex, Vec<Vertex>> {
+        let formula_manager = &self.status.formula_manager;
+        self.name_rdeps.get_or_insert_with(|| {
+            let mut name_rdeps: HashMap<Vertex, Vec<Vertex>> = HashMap::new();
+            formula_manager.names.keys().for_each(|name_id| {
+                formula_manager
+                    .get_name_deps(name_id)
+                    .into_iter()
+                    .for_each(|dep| {
+                        name_rdeps
+                            .entry(dep)
+                            .or_default()
+                            .push(Vertex::Name(*name_id))
+                    })
+            });
+            name_rdeps
+        })
+    }
+
+    fn get_formulas_in(&mut self, area: &Area) -> Vec<Vertex> {
+        let index = match self.formula_index.take() {
+            Some(index) => index,
+            None => {
+                let mut index = AreaIndex::default();
+                self.status
+                    .formula_manager
+                    .get_formula_vertices(None)
+                    .iter()
+                    .for_each(|v| {
+                        if let Some(a) = self.get_area(v) {
+                            index.add(v, &a);
+                        }
+                    });
+                index
+            }
+        };
+        let result = self.filter_intersecting(index.get_candidates(area), area);
+        self.formula_index = Some(index);
+        result
+    }
+
+    fn get_references_to(&mut self, area: &Area) -> Vec<Vertex> {
+        let index = match self.reference_index.take() {
+            Some(index) => index,
+            None => {
+                let formula_manager = &self.status.formula_manager;
+                let mut references = formula_manager
+                    .graph
+                    .rdeps
+                    .keys()
+                    .chain(formula_manager.dynamic_graph.rdeps.keys())
+                    .cloned()
+                    .collect::<HashSet<_>>();
+                references.extend(self.get_name_rdeps().keys().cloned());
+                let mut index = AreaIndex::default();
+                references.iter().for_each(|v| {
+                    if let Some(a) = self.get_area(v) {
+                        index.add(v, &a);
+                    }
+                });
+                index
+            }
+        };
+        let result = self.filter_intersecting(index.get_candidates(area), area);
+        self.reference_index = Some(index);
+        result
+    }
+
+    fn filter_intersecting(&mut self, candidates: HashSet<Vertex>, area: &Area) -> Vec<Vertex> {
+        candidates
+            .into_iter()
+            .filter(|v| matches!(self.get_area(v), Some(a) if a.intersects(area)))
+            .collect()
+    }
+
+    fn get_area(&mut self, v: &Vertex) -> Option<Area> {
+        if let Some(area) = self.areas.get(v) {
+            return area.clone();
+        }
+        let area = self.find_area(v);
+        self.areas.insert(v.clone(), area.clone());
+        area
+    }
+
+    fn find_area(&mut self, v: &Vertex) -> Option<Area> {
+        let status = self.status;
+        match v {
+            Vertex::Range(sheet_id, range_id) => {
+                let range = status
+                    .formula_manager
+                    .range_manager
+                    .get_range(sheet_id, range_id)?;
+                let navigator = &mut self.navigator;
+                let (rows, cols) = match range {
+                    Range::Normal(NormalRange::Single(c)) => {
+                        let (row, col) = navigator.fetch_normal_cell_idx(sheet_id, &c).ok()?;
+                        (Some((row, row)), Some((col, col)))
+                    }
+                    Range::Normal(NormalRange::RowRange(start, end)) => {
+                        let start = navigator.fetch_row_idx(sheet_id, &start).ok()?;
+                        let end = navigator.fetch_row_idx(sheet_id, &end).ok()?;
+                        (Some((start, end)), None)
+                    }
+                    Range::Normal(NormalRange::ColRange(start, end)) => {
+                        let start = navigator.fetch_col_idx(sheet_id, &start).ok()?;
+                        let end = navigator.fetch_col_idx(sheet_id, &end).ok()?;
+                        (None, Some((start, end)))
+                    }
+                    Range::Normal(NormalRange::AddrRange(start, end)) => {
+                        let start = navigator.fetch_normal_cell_idx(sheet_id, &start).ok()?;
+                        let end = navigator.fetch_normal_cell_idx(sheet_id, &end).ok()?;
+                        (Some((start.0, end.0)), Some((start.1, end.1)))
+                    }
+                    Range::Block(BlockRange::Single(c)) => {
+                        let (row, col) = navigator
+                            .fetch_cell_idx(sheet_id, &CellId::BlockCell(c))
+                            .ok()?;
+                        (Some((row, row)), Some((col, col)))
+                    }
+                    Range::Block(BlockRange::AddrRange(start, end)) => {
+                        let start = navigator
+                            .fetch_cell_idx(sheet_id, &CellId::BlockCell(start))
+                            .ok()?;
+                        let end = navigator
+                            .fetch_cell_idx(sheet_id, &CellId::BlockCell(end))
+                            .ok()?;
+                        (Some((start.0, end.0)), Some((start.1, end.1)))
+                    }
+                };
+                Some(Area {
+                    sheets: vec![*sheet_id],
+                    rows,
+                    cols,
+                })
+            }
+            Vertex::Cube(cube_id) => {
+                let cube = status.formula_manager.cube_manager.get_cube(cube_id)?;
+                let sheet_pos_manager = &status.sheet_pos_manager;
+                let from = sheet_pos_manager.get_sheet_idx(&cube.from_sheet)?;
+                let to = sheet_pos_manager.get_sheet_idx(&cube.to_sheet)?;
+                let sheets = (from.min(to)..=from.max(to))
+                    .filter_map(|idx| sheet_pos_manager.get_sheet_id(idx))
+                    .collect();
+                let (rows, cols) = get_cross_bounds(&cube.cross);
+                Some(Area { sheets, rows, cols })
+            }
+            // External references and defined names do not cover the cells
+            // in this workbook.
+            Vertex::Ext(_) | Vertex::Name(_) => None,
+        }
+    }
+
+    fn convert(&mut self, v: &Vertex) -> Option<Dependency> {
+        let range = match v {
+            Vertex::Name(name_id) => {
+                let (_, name) = self.status.name_id_manager.get_string(name_id)?;
+                return Some(Dependency::Name(name));
+            }
+            Vertex::Range(sheet_id, _) => {
+                let area = self.get_area(v)?;
+                new_range(
+                    None,
+                    None,
+                    self.get_sheet_name(sheet_id),
+                    area.rows,
+                    area.cols,
+                )
+            }
+            Vertex::Cube(cube_id) => {
+                let status = self.status;
+                let cube = status.formula_manager.cube_manager.get_cube(cube_id)?;
+                let (rows, cols) = get_cross_bounds(&cube.cross);
+                new_range(
+                    None,
+                    Some(self.get_sheet_name(&cube.from_sheet)),
+                    self.get_sheet_name(&cube.to_sheet),
+                    rows,
+                    cols,
+                )
+            }
+            Vertex::Ext(ext_ref_id) => {
+                let status = self.status;
+                let ext_ref = status
+                    .formula_manager
+                    .ext_ref_manager
+                    .get_ext_ref(ext_ref_id)?;
+                let book = status
+                    .external_links_manager
+                    .fetch_book_name(&ext_ref.ext_book);
+                let (rows, cols) = get_cross_bounds(&ext_ref.cross);
+                new_range(
+                    book,
+                    ext_ref.from_sheet.map(|s| self.get_sheet_name(&s)),
+                    self.get_sheet_name(&ext_ref.to_sheet),
+                    rows,
+                    cols,
+                )
+            }
+        };
+        Some(Dependency::Range(range))
+    }
+
+    fn get_sheet_name(&self, sheet_id: &SheetId) -> String {
+        self.status
+            .sheet_id_manager
+            .get_string(sheet_id)
+            .unwrap_or_default()
+    }
+}
+
+fn new_range(
+    book: Option<String>,
+    from_sheet: Option<String>,
+    sheet: String,
+    rows: Bounds,
+    cols: Bounds,
+) -> DependencyRange {
+    DependencyRange {
+        book,
+        from_sheet,
+        sheet,
+        row_start: rows.map(|r| r.0),
+        col_start: cols.map(|c| c.0),
+        row_end: rows.map(|r| r.1),
+        col_end: cols.map(|c| c.1),
+    }
+}
+
+fn get_cross_bounds(cross: &CubeCross) -> (Bounds, Bounds) {
+    match cross {
+        CubeCross::Single(row, col) => (Some((*row, *row)), Some((*col, *col))),
+        CubeCross::RowRange(start, end) => (Some((*start, *end)), None),
+        CubeCross::ColRange(start, end) => (None, Some((*start, *end))),
+        CubeCross::AddrRange(start, end) => {
+            (Some((start.row, end.row)), Some((start.col, end.col)))
+        }
+    }
+}
+
+fn overlaps(a: Bounds, b: Bounds) -> bool {
+    match (a, b) {
+        (Some((s1, e1)), Some((s2, e2))) => s1 <= e2 && s2 <= e1,
+        _ => true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::controller::Controller;
+    use crate::test_utils::{get_value, input};
+    use logisheets_base::CellValue;
+
+    #[test]
+    fn dependency_test() {
+        use crate::controller::display::Dependency;
+        let mut wb = Controller::default();
+        input(&mut wb, 0, 0, 0, "1");
+        input(&mut wb, 0, 0, 1, "=A1*2");
+        input(&mut wb, 0, 0, 2, "=SUM(A1:B1)");
+        input(&mut wb, 0, 0, 3, "=C1+1");
+        let to_cols = |deps: Vec<Dependency>| {
+            let mut cols = deps
+                .into_iter()
+                .map(|d| match d {
+                    Dependency::Range(r) => {
+                        assert_eq!(r.sheet, "Sheet1");
+                        (r.col_start.unwrap(), r.col_end.unwrap())
+                    }
+                    Dependency::Name(_) => unreachable!(),
+                })
+                .collect::<Vec<_>>();
+            cols.sort();
+            cols
+        };
+        let precedents = wb.get_precedents(0, (0, 3), (0, 3), false).unwrap();
+        assert_eq!(to_cols(precedents), vec![(2, 2)]);
+        let precedents = wb.get_precedents(0, (0, 3), (0, 3), true).unwrap();
+        assert_eq!(to_cols(precedents), vec![(0, 0), (0, 1), (2, 2)]);
+        let dependents = wb.get_dependents(0, (0, 0), (0, 0), false).unwrap();
+        assert_eq!(to_cols(dependents), vec![(1, 1), (2, 2)]);
+        let dependents = wb.get_dependents(0, (0, 0), (0, 0), true).unwrap();
+        assert_eq!(to_cols(dependents), vec![(1, 1), (2, 2), (3, 3)]);
+
+        input(&mut wb, 0, 900, 4, "=SUM(D:D)");
+        let precedents = wb.get_precedents(0, (900, 4), (900, 4), false).unwrap();
+        assert!(matches!(&precedents[..], [Dependency::Range(r)] if r.row_start.is_none()));
+        let dependents = wb.get_dependents(0, (0, 0), (0, 0), true).unwrap();
+        assert_eq!(to_cols(dependents), vec![(1, 1), (2, 2), (3, 3), (4, 4)]);
+    }
+
+    #[test]
+    fn value_overwrites_formula_test() {
+        let mut wb = Controller::default();
+        input(&mut wb, 0, 0, 0, "1");
+        input(&mut wb, 0, 0, 1, "=A1*2");
+        input(&mut wb, 0, 0, 1, "5");
+        input(&mut wb, 0, 0, 0, "3");
+        assert!(matches!(get_value(&mut wb, 0, 0, 1), CellValue::Number(n) if n == 5.));
+        let precedents = wb.get_precedents(0, (0, 1), (0, 1), false).unwrap();
+        assert!(precedents.is_empty());
+    }
+}
@@ -146,6 +146,8 @@ pub enum Value {
     Bool(bool),
     Number(f64),
     Error(String),
+    /// A date and time in RFC 3339.
+    Date(String),
     Empty,
 }
 
@@ -157,6 +159,20 @@ pub struct CellFormulaValue {
     pub col: usize,
     pub formula: String,
     pub value: Value,
+    pub formatted: FormattedValue,
+    /// Whether the formula needs to be calculated in the manual calculation
+    /// mode.
+    pub stale: bool,
+}
+
+/// The text shown in a cell after applying its number format and the
+/// color specified by the format, like `Red` or `Color10`.
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(file_name = "formatted_value.ts", rename_all = "camelCase")]
+#[serde(rename_all = "camelCase")]
+pub struct FormattedValue {
+    pub text: String,
+    pub color: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, TS)]
@@ -183,3 +199,172 @@ pub struct SheetStyles {
     pub sheet_idx: usize,
     pub styles: Vec<CellStyle>,
 }
+
+/// The steps of calculating a formula. Every sub-expression is shown with
+/// its value and the sub-expressions calculated for it.
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(file_name = "eval_trace.ts", rename_all = "camelCase")]
+#[serde(rename_all = "camelCase")]
+pub struct EvalTrace {
+    pub expression: String,
+    pub value: EvalValue,
+    /// The reference fetched, like `Sheet1!A1:B2`.
+    pub reference: Option<String>,
+    /// Whether the error is produced by this function or operator rather
+    /// than passed from its arguments.
+    pub error_origin: bool,
+    /// Whether volatile functions like RAND are called for the value, so
+    /// that it may differ from the one shown in the cell.
+    pub volatile: bool,
+    pub children: Vec<EvalTrace>,
+}
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(file_name = "eval_value.ts", rename_all = "camelCase")]
+#[serde(rename_all = "camelCase")]
+pub enum EvalValue {
+    Scalar(Value),
+    Array(Vec<Vec<Value>>),
+    Union(Vec<EvalValue>),
+    Lambda,
+    /// The values of the large ranges, like whole columns, are not fetched.
+    Omitted,
+}
+
+/// A precedent or a dependent of the cells.
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(file_name = "dependency.ts", rename_all = "camelCase")]
+#[serde(rename_all = "camelCase")]
+pub enum Dependency {
+    Range(DependencyRange),
+    /// A defined name.
+    Name(String),
+}
+
+/// A cell or a range. The rows are omitted for whole columns, and so are
+/// the columns for whole rows.
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(file_name = "dependency_range.ts", rename_all = "camelCase")]
+#[serde(rename_all = "camelCase")]
+pub struct DependencyRange {
+    /// The external workbook the range is in.
+    pub book: Option<String>,
+    /// The first sheet of a 3D reference.
+    pub from_sheet: Option<String>,
+    pub sheet: String,
+    pub row_start: Option<usize>,
+    pub col_start: Option<usize>,
+    pub row_end: Option<usize>,
+    pub col_end: Option<usize>,
+}
+
+/// A circular reference found in the last calculation.
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(file_name = "circular_ref.ts", rename_all = "camelCase")]
+#[serde(rename_all = "camelCase")]
+pub struct CircularRef {
+    /// The formula cells in the circular reference.
+    pub cells: Vec<CircularRefCell>,
+    /// The times the formulas are calculated. It is 0 if the iterative
+    /// calculation is off and the cells get `#CALC!`.
+    pub iterations: u16,
+    /// The largest change of the numbers in the last iteration.
+    pub max_change: f64,
+    pub converged: bool,
+}
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(file_name = "circular_ref_cell.ts", rename_all = "camelCase")]
+#[serde(rename_all = "camelCase")]
+pub struct CircularRefCell {
+    pub sheet_idx: usize,
+    pub row: usize,
+    pub col: usize,
+}
+
+/// A problem found in a formula. `start` and `end` are the byte offsets of
+/// the part of the formula where the problem is.
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(file_name = "formula_diagnostic.ts", rename_all = "camelCase")]
+#[serde(rename_all = "camelCase")]
+pub struct FormulaDiagnostic {
+    pub start: usize,
+    pub end: usize,
+    /// The tokens which could have been written at `start`, if the formula
+    /// can not be parsed.
+    pub expected: Vec<String>,
+    pub message: String,
+}
+
+/// A token of a formula. `start` and `end` are the byte offsets of `text` in
+/// the formula.
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(file_name = "formula_token.ts", rename_all = "camelCase")]
+#[serde(rename_all = "camelCase")]
+pub struct FormulaToken {
+    pub kind: FormulaTokenKind,
+    pub text: String,
+    pub start: usize,
+    pub end: usize,
+    /// The cells denoted by a reference token, if it can be resolved.
+    pub reference: Option<DependencyRange>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, TS)]
+#[ts(file_name = "formula_token_kind.ts", rename_all = "camelCase")]
+#[serde(rename_all = "camelCase")]
+pub enum FormulaTokenKind {
+    Function,
+    Reference,
+    Name,
+    Number,
+    Text,
+    Boolean,
+    Error,
+    Operator,
+    Separator,
+    OpenParen,
+    CloseParen,
+    OpenBrace,
+    CloseBrace,
+    /// The part of a partially typed formula which can not be lexed.
+    Unknown,
+}
+
+/// The signature and the description of a function, for the autocomplete and
+/// the parameter tooltips.
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(file_name = "function_info.ts", rename_all = "camelCase")]
+#[serde(rename_all = "camelCase")]
+pub struct FunctionInfo {
+    pub name: String,
+    pub description: String,
+    pub args: Vec<FunctionArg>,
+}
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(file_name = "function_arg.ts", rename_all = "camelCase")]
+#[serde(rename_all = "camelCase")]
+pub struct FunctionArg {
+    pub name: String,
+    pub arg_type: FunctionArgType,
+    pub optional: bool,
+    /// Whether the argument is in the group which can be repeated at the end.
+    pub repeated: bool,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, TS)]
+#[ts(file_name = "function_arg_type.ts", rename_all = "camelCase")]
+#[serde(rename_all = "camelCase")]
+pub enum FunctionArgType {
+    Any,
+    Number,
+    Text,
+    Logical,
+    /// Only a reference is accepted, not a constant.
+    Reference,
+    /// A reference or an array constant.
+    Array,
+    /// A LAMBDA.
+    Function,
+}
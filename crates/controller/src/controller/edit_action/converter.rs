@@ -1,4 +1,5 @@
 use logisheets_base::{BlockId, CellValue, TextId};
+use logisheets_parser::r1c1_to_a1;
 
 use super::style_payload::{StyleUpdate, StyleUpdateType};
 use super::{
@@ -17,6 +18,7 @@ use crate::payloads::sheet_process::{
 };
 use crate::payloads::sheet_shift::{SheetRenamePayload, SheetShiftPayload, SheetShiftType};
 use crate::payloads::Process;
+use crate::settings::ReferenceStyle;
 use crate::workbook::sheet_pos_manager::SheetPosManager;
 
 pub struct Converter<'a> {
@@ -24,6 +26,7 @@ pub struct Converter<'a> {
     pub navigator: &'a mut Navigator,
     pub container: &'a mut DataContainer,
     pub text_id_manager: &'a mut TextIdManager,
+    pub reference_style: ReferenceStyle,
 }
 
 impl<'a> Converter<'a> {
@@ -167,6 +170,14 @@ impl<'a> Converter<'a> {
             content,
         } = input;
         let sheet_id = self.sheet_pos_manager.get_sheet_id(sheet_idx)?;
+        // The formulas in R1C1 are saved in A1. The ones referring to the cells
+        // out of the sheet are rejected.
+        let content = match content.strip_prefix('=') {
+            Some(f) if self.reference_style == ReferenceStyle::R1C1 => {
+                format!("={}", r1c1_to_a1(f, row, col)?)
+            }
+            _ => content,
+        };
         let payload = get_input_payload(row, col, content, &mut |t| self.text_id_manager.get_id(t));
         Some(Process::Sheet(SheetProcess { sheet_id, payload }))
     }
@@ -306,3 +317,67 @@ fn get_style_payload(sut: StyleUpdateType) -> Vec<CellStylePayload> {
     // todo!()
     result
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::controller::display::{DisplayPatch, DisplayRequest};
+    use crate::controller::Controller;
+    use crate::test_utils::input;
+
+    #[test]
+    fn r1c1_reference_style_test() {
+        use crate::settings::ReferenceStyle;
+        use crate::Workbook;
+        let mut wb = Controller::default();
+        wb.settings.reference_style = ReferenceStyle::R1C1;
+        input(&mut wb, 0, 0, 0, "1");
+        input(&mut wb, 0, 1, 1, "=R[-1]C[-1]+R1C1");
+        input(&mut wb, 0, 2, 3, "=SUM(C[-3], r1:R2, RC1)");
+        // Out of the sheet.
+        input(&mut wb, 0, 1, 2, "=R[-2]C");
+        input(&mut wb, 0, 1, 2, "=R2000000C1");
+        assert_eq!(wb.status.formula_manager.formulas.len(), 2);
+        let response = wb.get_display_response(DisplayRequest {
+            sheet_idx: 0,
+            version: 0,
+        });
+        let formulas = response
+            .patches
+            .iter()
+            .flat_map(|p| match p {
+                DisplayPatch::Values(v) => v.values.clone(),
+                _ => vec![],
+            })
+            .filter(|v| !v.formula.is_empty())
+            .map(|v| v.formula)
+            .collect::<std::collections::HashSet<_>>();
+        assert!(formulas.contains("R[-1]C[-1] + R1C1"));
+        assert!(formulas.contains("SUM(C[-3], R1:R2, RC1)"));
+
+        // `C` and `RC1` are references in the R1C1 style. `A1` is kept in
+        // the A1 notation when the formula is converted, so it is resolved
+        // like it.
+        let tokens = wb.get_formula_tokens(0, 2, 3, "C+RC1+A1").unwrap();
+        let r = tokens[0].reference.as_ref().unwrap();
+        assert_eq!(
+            (r.row_start, r.col_start, r.col_end),
+            (None, Some(3), Some(3))
+        );
+        let r = tokens[2].reference.as_ref().unwrap();
+        assert_eq!((r.row_start, r.col_start), (Some(2), Some(0)));
+        let r = tokens[4].reference.as_ref().unwrap();
+        assert_eq!((r.row_start, r.col_start), (Some(0), Some(0)));
+
+        let saved = wb.save().unwrap();
+        let reloaded = Controller::from_file(String::from("r1c1"), &saved).unwrap();
+        assert_eq!(reloaded.settings.reference_style, ReferenceStyle::R1C1);
+        let mut book = Workbook {
+            controller: reloaded,
+        };
+        let mut sheet = book.get_sheet_by_idx(0).unwrap();
+        assert_eq!(sheet.get_formula(1, 1).unwrap(), "R[-1]C[-1] + R1C1");
+        book.controller.settings.reference_style = ReferenceStyle::A1;
+        let mut sheet = book.get_sheet_by_idx(0).unwrap();
+        assert_eq!(sheet.get_formula(1, 1).unwrap(), "A1 + $A$1");
+    }
+}
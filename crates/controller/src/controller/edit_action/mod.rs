@@ -19,6 +19,24 @@ pub enum EditAction {
     Undo,
     Redo,
     Payloads(PayloadsAction),
+    Recalc(RecalcAction),
+}
+
+/// Calculate the formulas without any change of the cells, like pressing F9
+/// in Excel. They work in the manual calculation mode as well. The results
+/// are not undoable.
+#[derive(Debug, Serialize, TS)]
+#[ts(file_name = "recalc_action.ts")]
+pub enum RecalcAction {
+    /// The formulas using functions like NOW and RAND.
+    Volatile,
+    /// All the formulas in the sheet of the index.
+    Sheet(usize),
+    /// Build the dependency graph again and calculate all the formulas.
+    All,
+    /// The stale formulas in the manual calculation mode and the volatile
+    /// ones.
+    Now,
 }
 
 #[derive(Debug, Serialize, TS)]
@@ -0,0 +1,143 @@
+use logisheets_astchecker::{get_builtin_func, get_builtin_funcs, ArgType, FuncInfo};
+
+use crate::settings::func_registry::{FuncRegistry, NativeFunc};
+
+use super::display::{FunctionArg, FunctionArgType, FunctionInfo};
+
+/// The built-in functions and the registered ones, sorted by name. A
+/// registered function replaces the built-in one of the same name. The async
+/// functions are not included since their signatures are unknown.
+pub fn get_functions(func_registry: &FuncRegistry) -> Vec<FunctionInfo> {
+    let mut result = get_builtin_funcs()
+        .iter()
+        .filter(|f| !is_registered(func_registry, &f.name))
+        .map(convert_builtin)
+        .chain(
+            func_registry
+                .get_all()
+                .map(|(name, f)| convert_native(name, f)),
+        )
+        .collect::<Vec<_>>();
+    result.sort_by(|a, b| a.name.cmp(&b.name));
+    result
+}
+
+/// Find a function by its name, which is case-insensitive.
+pub fn get_function(func_registry: &FuncRegistry, name: &str) -> Option<FunctionInfo> {
+    match func_registry.get(name) {
+        Some(f) => Some(convert_native(&name.to_uppercase(), f)),
+        None if func_registry.get_async(name).is_some() => None,
+        None => get_builtin_func(name).map(convert_builtin),
+    }
+}
+
+/// Check a call of the function with `count` arguments, and return the
+/// message if the function is unknown or the number of arguments is wrong.
+/// The arguments of the async functions are not checked.
+pub fn check_call(func_registry: &FuncRegistry, name: &str, count: usize) -> Option<String> {
+    let arg_count = match func_registry.get(name) {
+        Some(f) => Some(&f.arg_count),
+        None if func_registry.get_async(name).is_some() => None,
+        None => match get_builtin_func(name) {
+            Some(f) => Some(&f.arg_count),
+            None => return Some(format!("unknown function {}", name)),
+        },
+    };
+    match arg_count {
+        Some(c) if !c.check(count) => Some(format!("wrong number of arguments for {}", name)),
+        _ => None,
+    }
+}
+
+pub fn is_registered(func_registry: &FuncRegistry, name: &str) -> bool {
+    func_registry.get(name).is_some() || func_registry.get_async(name).is_some()
+}
+
+fn convert_builtin(info: &FuncInfo) -> FunctionInfo {
+    let args = info
+        .args
+        .iter()
+        .map(|a| FunctionArg {
+            name: a.name.clone(),
+            arg_type: convert_arg_type(a.arg_type),
+            optional: a.optional,
+            repeated: a.repeated,
+        })
+        .collect();
+    FunctionInfo {
+        name: info.name.clone(),
+        description: info.description.clone(),
+        args,
+    }
+}
+
+// The arguments after the required ones are optional.
+fn convert_native(name: &str, f: &NativeFunc) -> FunctionInfo {
+    let required = f.arg_count.ge.or(f.arg_count.eq).unwrap_or(0) as usize;
+    let repeat_start = f
+        .args
+        .iter()
+        .position(|a| a.start_repeated.unwrap_or(false))
+        .unwrap_or(f.args.len());
+    let args = f
+        .args
+        .iter()
+        .enumerate()
+        .map(|(i, a)| FunctionArg {
+            name: a.arg_name.clone(),
+            arg_type: if a.ref_only.unwrap_or(false) {
+                FunctionArgType::Reference
+            } else {
+                FunctionArgType::Any
+            },
+            optional: i >= required,
+            repeated: i >= repeat_start,
+        })
+        .collect();
+    FunctionInfo {
+        name: name.to_string(),
+        description: String::new(),
+        args,
+    }
+}
+
+fn convert_arg_type(t: ArgType) -> FunctionArgType {
+    match t {
+        ArgType::Any => FunctionArgType::Any,
+        ArgType::Number => FunctionArgType::Number,
+        ArgType::Text => FunctionArgType::Text,
+        ArgType::Logical => FunctionArgType::Logical,
+        ArgType::Reference => FunctionArgType::Reference,
+        ArgType::Array => FunctionArgType::Array,
+        ArgType::Function => FunctionArgType::Function,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::controller::Controller;
+    use crate::test_utils::{get_value, input};
+    use logisheets_base::CellValue;
+
+    #[test]
+    fn builtin_signatures_test() {
+        use crate::controller::display::FunctionArgType;
+        let mut wb = Controller::default();
+        // The formulas with wrong arguments are rejected.
+        input(&mut wb, 0, 0, 0, "=ROUND(1.25, 1)");
+        input(&mut wb, 0, 0, 0, "=ROUND(1.25)");
+        input(&mut wb, 0, 0, 1, "=SUM()");
+        input(&mut wb, 0, 0, 2, "=1+COUNTIF({1,2},1)");
+        assert!(matches!(get_value(&mut wb, 0, 0, 0), CellValue::Number(n) if n == 1.3));
+        assert_eq!(wb.status.formula_manager.formulas.len(), 1);
+
+        let functions = wb.get_functions();
+        assert!(functions.windows(2).all(|w| w[0].name < w[1].name));
+        assert!(functions.iter().any(|f| f.name == "XLOOKUP"));
+        let f = wb.get_function("sumifs").unwrap();
+        assert!(!f.description.is_empty());
+        assert!(matches!(f.args[0].arg_type, FunctionArgType::Reference));
+        assert!(f.args[3].optional && f.args[3].repeated);
+        assert!(wb.get_function("FOO").is_none());
+    }
+}
@@ -1,26 +1,41 @@
 use logisheets_base::async_func::{AsyncCalcResult, Task};
-use logisheets_base::{CellId, SheetId};
+use logisheets_base::{BlockRange, CellId, NormalRange, Range, SheetId};
 
 use logisheets_workbook::prelude::{read, SerdeErr};
+use logisheets_workbook::writer::write;
+mod dependency;
 pub mod display;
 pub mod edit_action;
+pub(crate) mod functions;
 pub mod status;
 pub mod style;
+mod tokenizer;
+mod tracer;
 mod transaction;
 mod viewer;
 use crate::file_loader2::load;
+use crate::file_saver::save;
+use crate::formula_check::check_formula;
 use crate::payloads::sheet_shift::{SheetShiftPayload, SheetShiftType};
 use crate::payloads::Process;
+use crate::settings::func_registry::{AsyncReturnType, NativeValue};
 use crate::settings::Settings;
 use anyhow::Result;
-use edit_action::{ActionEffect, Converter};
+use edit_action::{ActionEffect, Converter, RecalcAction};
+use logisheets_astchecker::{ArgCount, ArgDef, AstChecker};
 use status::Status;
+use std::time::Duration;
 use transaction::{Transaction, TransactionContext};
 use viewer::SheetViewer;
 
-use self::display::{DisplayRequest, DisplayResponse};
+use self::display::{
+    CircularRef, CircularRefCell, Dependency, DisplayRequest, DisplayResponse, EvalTrace,
+    FormulaDiagnostic, FormulaToken, FunctionInfo,
+};
 use crate::async_func_manager::AsyncFuncManager;
+use crate::formula_manager::Vertex;
 use edit_action::EditAction;
+use std::collections::HashSet;
 
 pub struct Controller {
     pub status: Status,
@@ -51,8 +66,10 @@ impl Default for Controller {
 }
 
 impl Controller {
-    pub fn save(&self) -> Vec<u8> {
-        vec![]
+    pub fn save(&self) -> Result<Vec<u8>, SerdeErr> {
+        let workbook = save(self);
+        let buf = write(workbook)?;
+        Ok(buf)
     }
 
     pub fn from(status: Status, book_name: String, settings: Settings) -> Self {
@@ -74,6 +91,189 @@ impl Controller {
         }
     }
 
+    /// Register a function implemented in Rust under the name. It takes
+    /// effect in the formulas calculated afterwards.
+    pub fn register_func<F>(&mut self, name: &str, arg_count: ArgCount, args: Vec<ArgDef>, func: F)
+    where
+        F: Fn(Vec<NativeValue>) -> NativeValue + Send + Sync + 'static,
+    {
+        self.settings
+            .func_registry
+            .register(name, arg_count, args, func)
+    }
+
+    /// Register a function calculated by the embedders. The cells using it
+    /// get `#GETTING_DATA` until the results are sent back by
+    /// `handle_async_calc_results`.
+    pub fn register_async_func(
+        &mut self,
+        name: &str,
+        return_type: AsyncReturnType,
+        ttl: Option<Duration>,
+    ) {
+        self.settings
+            .func_registry
+            .register_async(name, return_type, ttl)
+    }
+
+    /// The checker of the function arguments in formulas, which knows the
+    /// signatures of the built-in functions and the registered ones.
+    pub fn get_ast_checker(&self) -> AstChecker {
+        let func_registry = &self.settings.func_registry;
+        let mut checker = func_registry.get_builtin_checker().clone();
+        func_registry
+            .get_signatures()
+            .into_iter()
+            .for_each(|(name, signature)| checker.insert(&name, signature));
+        checker
+    }
+
+    /// The built-in functions and the registered ones with their signatures
+    /// and descriptions, sorted by name.
+    pub fn get_functions(&self) -> Vec<FunctionInfo> {
+        functions::get_functions(&self.settings.func_registry)
+    }
+
+    /// Find a function by its name, which is case-insensitive.
+    pub fn get_function(&self, name: &str) -> Option<FunctionInfo> {
+        functions::get_function(&self.settings.func_registry, name)
+    }
+
+    /// Check the formula, which has SKIPPED the '=', for the syntax errors,
+    /// the unknown functions and the wrong numbers of arguments.
+    pub fn check_formula(&self, formula: &str) -> Vec<FormulaDiagnostic> {
+        let func_registry = &self.settings.func_registry;
+        check_formula(formula, |name, count| {
+            functions::check_call(func_registry, name, count)
+        })
+    }
+
+    /// The formula cells to calculate in the manual calculation mode.
+    pub fn get_stale_cells(&self) -> HashSet<(SheetId, CellId)> {
+        self.status
+            .stale_vertices
+            .iter()
+            .filter_map(|v| self.get_formula_cell(v))
+            .collect()
+    }
+
+    /// The circular references found in the calculations. The ones broken
+    /// since are not included.
+    pub fn get_circular_refs(&mut self) -> Vec<CircularRef> {
+        let reports = self.status.circular_refs.clone();
+        reports
+            .into_iter()
+            .filter_map(|report| {
+                let cells = report
+                    .vertices
+                    .iter()
+                    .filter_map(|v| {
+                        let (sheet_id, cell_id) = self.get_formula_cell(v)?;
+                        let sheet_idx = self.status.sheet_pos_manager.get_sheet_idx(&sheet_id)?;
+                        let (row, col) = self
+                            .status
+                            .navigator
+                            .fetch_cell_idx(&sheet_id, &cell_id)
+                            .ok()?;
+                        Some(CircularRefCell {
+                            sheet_idx,
+                            row,
+                            col,
+                        })
+                    })
+                    .collect::<Vec<_>>();
+                if cells.is_empty() {
+                    return None;
+                }
+                Some(CircularRef {
+                    cells,
+                    iterations: report.iterations,
+                    max_change: report.max_change,
+                    converged: report.converged,
+                })
+            })
+            .collect()
+    }
+
+    fn get_formula_cell(&self, v: &Vertex) -> Option<(SheetId, CellId)> {
+        let range_manager = &self.status.formula_manager.range_manager;
+        match v {
+            Vertex::Range(sheet_id, range_id) => {
+                match range_manager.get_range(sheet_id, range_id)? {
+                    Range::Normal(NormalRange::Single(c)) => {
+                        Some((*sheet_id, CellId::NormalCell(c)))
+                    }
+                    Range::Block(BlockRange::Single(c)) => Some((*sheet_id, CellId::BlockCell(c))),
+                    _ => None,
+                }
+            }
+            _ => None,
+        }
+    }
+
+    /// Calculate the formula of the cell step by step without changing the
+    /// workbook. Return `None` if the cell has no formula.
+    pub fn evaluate_formula(
+        &mut self,
+        sheet_idx: usize,
+        row: usize,
+        col: usize,
+    ) -> Option<EvalTrace> {
+        let sheet_id = self.get_sheet_id_by_idx(sheet_idx)?;
+        let cell_id = self
+            .status
+            .navigator
+            .fetch_cell_id(&sheet_id, row, col)
+            .ok()?;
+        tracer::trace_formula(self, sheet_id, cell_id)
+    }
+
+    /// The references in the formulas of the cells from `start` to `end`,
+    /// given as (row, col). If `transitive`, the references in the formulas
+    /// of the cells referred to are included, and so on.
+    pub fn get_precedents(
+        &self,
+        sheet_idx: usize,
+        start: (usize, usize),
+        end: (usize, usize),
+        transitive: bool,
+    ) -> Option<Vec<Dependency>> {
+        let sheet_id = self.get_sheet_id_by_idx(sheet_idx)?;
+        let mut finder = dependency::DependencyFinder::new(&self.status);
+        Some(finder.get_precedents(sheet_id, (start.0, start.1, end.0, end.1), transitive))
+    }
+
+    /// The formula cells and the defined names referring to the cells from
+    /// `start` to `end`, given as (row, col). If `transitive`, the ones
+    /// referring to them are included, and so on.
+    pub fn get_dependents(
+        &self,
+        sheet_idx: usize,
+        start: (usize, usize),
+        end: (usize, usize),
+        transitive: bool,
+    ) -> Option<Vec<Dependency>> {
+        let sheet_id = self.get_sheet_id_by_idx(sheet_idx)?;
+        let mut finder = dependency::DependencyFinder::new(&self.status);
+        Some(finder.get_dependents(sheet_id, (start.0, start.1, end.0, end.1), transitive))
+    }
+
+    /// Tokenize the formula, which has SKIPPED the '=' and may be partially
+    /// typed, as if it is in the cell at (`row`, `col`). The references are
+    /// resolved to the cells they denote, so that they can be highlighted.
+    pub fn get_formula_tokens(
+        &mut self,
+        sheet_idx: usize,
+        row: usize,
+        col: usize,
+        formula: &str,
+    ) -> Option<Vec<FormulaToken>> {
+        let sheet_id = self.get_sheet_id_by_idx(sheet_idx)?;
+        Some(tokenizer::get_formula_tokens(
+            self, sheet_id, row, col, formula,
+        ))
+    }
+
     pub fn get_sheet_id_by_idx(&self, idx: usize) -> Option<SheetId> {
         self.status.sheet_pos_manager.get_sheet_id(idx)
     }
@@ -99,6 +299,7 @@ impl Controller {
                     navigator: &mut self.status.navigator,
                     container: &mut self.status.container,
                     text_id_manager: &mut self.status.text_id_manager,
+                    reference_style: self.settings.reference_style,
                 };
                 let proc = c.convert_edit_payloads(action.payloads);
                 self.handle_process(proc, action.undoable).ok()?;
@@ -109,6 +310,23 @@ impl Controller {
                     dirtys: dirties,
                 })
             }
+            EditAction::Recalc(action) => {
+                let proc = match action {
+                    RecalcAction::Volatile => Process::RecalcVolatile,
+                    RecalcAction::Sheet(idx) => {
+                        Process::RecalcSheet(self.get_sheet_id_by_idx(idx)?)
+                    }
+                    RecalcAction::All => Process::RecalcAll,
+                    RecalcAction::Now => Process::RecalcStale,
+                };
+                self.handle_process(vec![proc], false).ok()?;
+                let (tasks, dirties) = self.async_func_manager.get_calc_tasks();
+                Some(ActionEffect {
+                    sheets: vec![],
+                    async_tasks: tasks,
+                    dirtys: dirties,
+                })
+            }
         }
     }
 
@@ -126,11 +344,37 @@ impl Controller {
         Some(ActionEffect::default())
     }
 
-    fn handle_process(&mut self, proc: Vec<Process>, undoable: bool) -> Result<()> {
+    /// Calculate the cells whose async results are older than the TTLs of
+    /// their functions. The embedders call it periodically, and the tasks
+    /// committed again are returned.
+    pub fn recalc_expired_async_funcs(&mut self) -> Option<ActionEffect> {
+        let func_registry = &self.settings.func_registry;
+        let cells = self
+            .async_func_manager
+            .take_expired(|t| func_registry.get_async(&t.async_func).and_then(|f| f.ttl));
+        if cells.is_empty() {
+            return Some(ActionEffect::default());
+        }
+        self.handle_process(vec![Process::Recalc(cells)], false)
+            .ok()?;
+        let (tasks, dirties) = self.async_func_manager.get_calc_tasks();
+        Some(ActionEffect {
+            sheets: vec![],
+            async_tasks: tasks,
+            dirtys: dirties,
+        })
+    }
+
+    pub(crate) fn handle_process(&mut self, proc: Vec<Process>, undoable: bool) -> Result<()> {
+        // The arguments of the registered functions are checked when they are
+        // calculated, since they can be registered again with other
+        // signatures.
         let context = TransactionContext {
             book_name: &self.curr_book_name,
             calc_config: self.settings.calc_config.clone(),
-            async_funcs: &self.settings.async_funcs,
+            func_registry: &self.settings.func_registry,
+            ast_checker: self.settings.func_registry.get_builtin_checker(),
+            date1904: self.settings.date1904,
         };
         let transcation = Transaction {
             async_func_manager: &mut self.async_func_manager,
@@ -1,9 +1,10 @@
-use im::HashMap;
+use im::{HashMap, HashSet, Vector};
 
+use crate::calc_engine::CycleReport;
 use crate::cell_attachments::CellAttachmentsManager;
 use crate::container::DataContainer;
 use crate::ext_book_manager::ExtBooksManager;
-use crate::formula_manager::FormulaManager;
+use crate::formula_manager::{FormulaManager, Vertex};
 use crate::id_manager::FuncIdManager;
 use crate::id_manager::NameIdManager;
 use crate::id_manager::SheetIdManager;
@@ -26,6 +27,10 @@ pub struct Status {
     pub sheet_pos_manager: SheetPosManager,
     pub style_manager: StyleManager,
     pub cell_attachment_manager: CellAttachmentsManager,
+    /// The formulas to calculate in the manual calculation mode.
+    pub stale_vertices: HashSet<Vertex>,
+    /// The circular references found in the calculations.
+    pub circular_refs: Vector<CycleReport>,
 }
 
 impl Default for Status {
@@ -44,6 +49,8 @@ impl Default for Status {
             sheet_pos_manager: SheetPosManager::default(),
             style_manager: StyleManager::default(),
             cell_attachment_manager: CellAttachmentsManager::default(),
+            stale_vertices: HashSet::new(),
+            circular_refs: Vector::new(),
         }
     }
 }
@@ -0,0 +1,158 @@
+use logisheets_base::SheetId;
+use logisheets_lexer::{tokenize_recoverable, TokenKind};
+use logisheets_parser::{resolve_r1c1_reference, resolve_reference, ReferenceArea};
+
+use crate::connectors::IndexFetcher;
+use crate::settings::ReferenceStyle;
+
+use super::display::{DependencyRange, FormulaToken, FormulaTokenKind};
+use super::Controller;
+
+/// Tokenize the formula as if it is in the cell. The reference tokens are
+/// resolved to the cells they denote, in the reference style of the book.
+pub fn get_formula_tokens(
+    controller: &mut Controller,
+    sheet_id: SheetId,
+    row: usize,
+    col: usize,
+    formula: &str,
+) -> Vec<FormulaToken> {
+    let r1c1 = controller.settings.reference_style == ReferenceStyle::R1C1;
+    let book_name = &controller.curr_book_name;
+    let status = &mut controller.status;
+    let curr_sheet = status
+        .sheet_id_manager
+        .get_string(&sheet_id)
+        .unwrap_or_default();
+    let mut result = vec![];
+    for t in tokenize_recoverable(formula) {
+        // In the R1C1 style, `R` and `C` alone are lexed as names, and `R1`
+        // or `RC1` as A1 references.
+        let r1c1_area = match t.kind {
+            TokenKind::Reference | TokenKind::Name if r1c1 => {
+                resolve_r1c1_reference(&t.text, row, col)
+            }
+            _ => None,
+        };
+        let kind = match r1c1_area {
+            Some(_) => TokenKind::Reference,
+            None => t.kind,
+        };
+        let reference = match (r1c1_area, t.kind) {
+            (Some(area), _) => Some(convert_area(area, book_name, &curr_sheet)),
+            (None, TokenKind::Reference) => resolve_reference(&t.text, row, col, |name| {
+                let fetcher = &mut IndexFetcher {
+                    navigator: &mut status.navigator,
+                    sheet_pos_manager: &status.sheet_pos_manager,
+                };
+                let table = status.formula_manager.table_manager.fetch_table(
+                    name,
+                    Some((sheet_id, row, col)),
+                    fetcher,
+                )?;
+                let sheet = status.sheet_id_manager.get_string(&table.sheet_id)?;
+                Some((sheet, table))
+            })
+            .map(|area| convert_area(area, book_name, &curr_sheet)),
+            _ => None,
+        };
+        result.push(FormulaToken {
+            kind: convert_kind(kind),
+            text: t.text,
+            start: t.start,
+            end: t.end,
+            reference,
+        });
+    }
+    result
+}
+
+fn convert_area(area: ReferenceArea, book_name: &str, curr_sheet: &str) -> DependencyRange {
+    DependencyRange {
+        book: area.workbook.filter(|b| b != book_name),
+        from_sheet: area.from_sheet,
+        sheet: area.sheet.unwrap_or_else(|| curr_sheet.to_string()),
+        row_start: area.rows.map(|r| r.0),
+        col_start: area.cols.map(|c| c.0),
+        row_end: area.rows.map(|r| r.1),
+        col_end: area.cols.map(|c| c.1),
+    }
+}
+
+fn convert_kind(kind: TokenKind) -> FormulaTokenKind {
+    match kind {
+        TokenKind::Function => FormulaTokenKind::Function,
+        TokenKind::Reference => FormulaTokenKind::Reference,
+        TokenKind::Name => FormulaTokenKind::Name,
+        TokenKind::Number => FormulaTokenKind::Number,
+        TokenKind::Text => FormulaTokenKind::Text,
+        TokenKind::Boolean => FormulaTokenKind::Boolean,
+        TokenKind::Error => FormulaTokenKind::Error,
+        TokenKind::Operator => FormulaTokenKind::Operator,
+        TokenKind::Separator => FormulaTokenKind::Separator,
+        TokenKind::OpenParen => FormulaTokenKind::OpenParen,
+        TokenKind::CloseParen => FormulaTokenKind::CloseParen,
+        TokenKind::OpenBrace => FormulaTokenKind::OpenBrace,
+        TokenKind::CloseBrace => FormulaTokenKind::CloseBrace,
+        TokenKind::Unknown => FormulaTokenKind::Unknown,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::controller::Controller;
+
+    #[test]
+    fn get_formula_tokens_test() {
+        use crate::controller::display::FormulaTokenKind;
+        use crate::table_manager::TableDef;
+        let mut wb = Controller::default();
+        let tokens = wb
+            .get_formula_tokens(0, 0, 0, "SUM(B2:A1, Sheet2!C3) + ")
+            .unwrap();
+        let kinds = tokens.iter().map(|t| t.kind).collect::<Vec<_>>();
+        assert!(matches!(
+            kinds.as_slice(),
+            [
+                FormulaTokenKind::Function,
+                FormulaTokenKind::OpenParen,
+                FormulaTokenKind::Reference,
+                FormulaTokenKind::Separator,
+                FormulaTokenKind::Reference,
+                FormulaTokenKind::CloseParen,
+                FormulaTokenKind::Operator,
+            ]
+        ));
+        let r = tokens[2].reference.as_ref().unwrap();
+        assert_eq!(r.sheet, "Sheet1");
+        assert_eq!((r.row_start, r.row_end), (Some(0), Some(1)));
+        assert_eq!((r.col_start, r.col_end), (Some(0), Some(1)));
+        let r = tokens[4].reference.as_ref().unwrap();
+        assert_eq!(r.sheet, "Sheet2");
+        assert_eq!((r.row_start, r.col_start), (Some(2), Some(2)));
+        assert!(tokens[6].reference.is_none());
+
+        let sheet_id = wb.get_sheet_id_by_idx(0).unwrap();
+        let navigator = &mut wb.status.navigator;
+        let start = navigator.fetch_cell_id(&sheet_id, 0, 0).unwrap();
+        let end = navigator.fetch_cell_id(&sheet_id, 4, 1).unwrap();
+        wb.status.formula_manager.table_manager.add_table(TableDef {
+            name: String::from("Table1"),
+            sheet_id,
+            start,
+            end,
+            header_row_count: 1,
+            totals_row_count: 1,
+            columns: vec![String::from("Item"), String::from("Price")],
+        });
+        let tokens = wb
+            .get_formula_tokens(0, 2, 1, "[@Price]*Table1[Pri")
+            .unwrap();
+        let r = tokens[0].reference.as_ref().unwrap();
+        assert_eq!((r.row_start, r.row_end), (Some(2), Some(2)));
+        assert_eq!((r.col_start, r.col_end), (Some(1), Some(1)));
+        assert_eq!(tokens[2].text, "Table1[Pri");
+        assert!(tokens[2].reference.is_none());
+        assert!(wb.get_formula_tokens(1, 0, 0, "A1").is_none());
+    }
+}
@@ -0,0 +1,219 @@
+use logisheets_base::name_fetcher::NameFetcherTrait;
+use logisheets_base::{index_to_column_label, Addr, CellId, SheetId};
+use logisheets_parser::ast;
+use logisheets_parser::unparse::{quote_sheet_prefix, Stringify};
+
+use crate::calc_engine::calculator::calc_vertex::{CalcReference, CalcValue, Reference, Value};
+use crate::calc_engine::calculator::calculator::{trace, CalcTrace};
+use crate::connectors::{CalcCopy, CalcSources, NameFetcher};
+
+use super::display::{EvalTrace, EvalValue, Value as DisplayValue};
+use super::Controller;
+
+/// Calculate the formula of the cell on a copy of the data and record the
+/// value of every sub-expression. Nothing in the workbook is changed.
+pub fn trace_formula(
+    controller: &Controller,
+    sheet_id: SheetId,
+    cell_id: CellId,
+) -> Option<EvalTrace> {
+    let status = &controller.status;
+    let node = status.formula_manager.formulas.get(&(sheet_id, cell_id))?;
+    let mut copy = CalcCopy::new(
+        &status.navigator,
+        &status.container,
+        &status.external_links_manager,
+        &status.text_id_manager,
+        &controller.async_func_manager,
+    );
+    // Show the results received even if they are expired, rather than
+    // commit the tasks again.
+    copy.async_func_manager.keep_expired = true;
+    let (row, col) = copy.navigator.fetch_cell_idx(&sheet_id, &cell_id).ok()?;
+    let sources = CalcSources {
+        formula_manager: &status.formula_manager,
+        func_id_manager: &status.func_id_manager,
+        name_id_manager: &status.name_id_manager,
+        sheet_pos_manager: &status.sheet_pos_manager,
+        sheet_id_manager: &status.sheet_id_manager,
+        func_registry: &controller.settings.func_registry,
+        date1904: controller.settings.date1904,
+    };
+    let connector = &mut copy.get_connector(sources, sheet_id, Addr { row, col });
+    let calc_trace = trace(node, connector);
+    let mut name_fetcher = NameFetcher {
+        func_manager: &status.func_id_manager,
+        sheet_id_manager: &status.sheet_id_manager,
+        external_links_manager: &status.external_links_manager,
+        text_id_manager: &status.text_id_manager,
+        name_id_manager: &status.name_id_manager,
+        navigator: &mut copy.navigator,
+        formula_manager: &status.formula_manager,
+    };
+    Some(convert_trace(calc_trace, &mut name_fetcher, sheet_id))
+}
+
+fn convert_trace(t: CalcTrace, name_fetcher: &mut NameFetcher, sheet_id: SheetId) -> EvalTrace {
+    let expression = t.node.unparse(name_fetcher, sheet_id);
+    let reference = t.reference.map(|r| convert_reference(&r, name_fetcher));
+    let error_origin = matches!(t.node.pure, ast::PureNode::Func(_))
+        && is_error(&t.value)
+        && !t.children.iter().any(|c| is_error(&c.value));
+    let value = match t.value {
+        Some(v) => convert_calc_value(v),
+        None if reference.is_some() => EvalValue::Omitted,
+        None => EvalValue::Lambda,
+    };
+    let children = t
+        .children
+        .into_iter()
+        .map(|c| convert_trace(c, name_fetcher, sheet_id))
+        .collect();
+    EvalTrace {
+        expression,
+        value,
+        reference,
+        error_origin,
+        volatile: t.volatile,
+        children,
+    }
+}
+
+fn is_error(value: &Option<CalcValue>) -> bool {
+    matches!(value, Some(CalcValue::Scalar(Value::Error(_))))
+}
+
+fn convert_reference(r: &CalcReference, name_fetcher: &mut NameFetcher) -> String {
+    let sheet = match r.from_sheet {
+        Some(from) => format!(
+            "{}:{}",
+            name_fetcher.fetch_sheet_name(&from),
+            name_fetcher.fetch_sheet_name(&r.sheet)
+        ),
+        None => name_fetcher.fetch_sheet_name(&r.sheet),
+    };
+    let addr = |a: &Addr| format!("{}{}", index_to_column_label(a.col), a.row + 1);
+    let range = match &r.reference {
+        Reference::Addr(a) => addr(a),
+        Reference::ColumnRange(c) => format!(
+            "{}:{}",
+            index_to_column_label(c.start),
+            index_to_column_label(c.end)
+        ),
+        Reference::RowRange(r) => format!("{}:{}", r.start + 1, r.end + 1),
+        Reference::Range(start, end) => format!("{}:{}", addr(start), addr(end)),
+    };
+    format!("{}!{}", quote_sheet_prefix(&sheet), range)
+}
+
+fn convert_calc_value(v: CalcValue) -> EvalValue {
+    match v {
+        CalcValue::Scalar(v) => EvalValue::Scalar(convert_value(v)),
+        CalcValue::Range(m) => {
+            let (rows, cols) = m.get_avail_size();
+            let values = (0..rows)
+                .map(|r| {
+                    (0..cols)
+                        .map(|c| match m.visit(r, c) {
+                            Ok(v) => convert_value(v.clone()),
+                            Err(v) => convert_value(v),
+                        })
+                        .collect()
+                })
+                .collect();
+            EvalValue::Array(values)
+        }
+        // 3D references are not calculated yet.
+        CalcValue::Cube(_) => EvalValue::Array(vec![]),
+        CalcValue::Union(values) => {
+            EvalValue::Union(values.into_iter().map(|v| convert_calc_value(*v)).collect())
+        }
+    }
+}
+
+fn convert_value(v: Value) -> DisplayValue {
+    match v {
+        Value::Blank => DisplayValue::Empty,
+        Value::Number(n) => DisplayValue::Number(n),
+        Value::Text(t) => DisplayValue::Str(t),
+        Value::Boolean(b) => DisplayValue::Bool(b),
+        Value::Error(e) => DisplayValue::Error(e.get_err_str().to_string()),
+        Value::Date(d) => DisplayValue::Date(d.to_rfc3339()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::controller::edit_action::{EditAction, EditPayload, PayloadsAction};
+    use crate::controller::Controller;
+    use crate::test_utils::input;
+
+    #[test]
+    fn evaluate_formula_test() {
+        use crate::controller::display::{EvalValue, Value};
+        let mut wb = Controller::default();
+        input(&mut wb, 0, 0, 0, "1");
+        input(&mut wb, 0, 0, 1, "0");
+        input(&mut wb, 0, 0, 2, "=IF(A1>0,A1/B1,0)");
+        assert!(wb.evaluate_formula(0, 0, 0).is_none());
+        let trace = wb.evaluate_formula(0, 0, 2).unwrap();
+        assert_eq!(trace.expression, "IF(A1 > 0, A1 / B1, 0)");
+        assert!(!trace.error_origin);
+        assert_eq!(trace.children.len(), 3);
+        let div = &trace.children[1];
+        assert_eq!(div.expression, "A1 / B1");
+        assert!(div.error_origin);
+        assert!(matches!(&div.value, EvalValue::Scalar(Value::Error(e)) if e == "#DIV/0!"));
+        let b1 = &div.children[1];
+        assert_eq!(b1.reference.as_deref(), Some("Sheet1!B1"));
+        assert!(matches!(b1.value, EvalValue::Scalar(Value::Number(n)) if n == 0.));
+        assert!(!trace.volatile);
+
+        input(&mut wb, 0, 0, 3, "=SUM(A:A)+RAND()");
+        let trace = wb.evaluate_formula(0, 0, 3).unwrap();
+        assert!(trace.volatile);
+        let sum = &trace.children[0];
+        assert!(!sum.volatile);
+        assert!(matches!(sum.value, EvalValue::Scalar(Value::Number(n)) if n == 1.));
+        assert_eq!(sum.children[0].reference.as_deref(), Some("Sheet1!A:A"));
+        assert!(matches!(sum.children[0].value, EvalValue::Omitted));
+    }
+
+    #[test]
+    fn quoted_sheet_name_test() {
+        use crate::controller::display::{EvalValue, Value};
+        use crate::controller::edit_action::{SheetRename, SheetShift};
+        use crate::Workbook;
+        let mut wb = Controller::default();
+        wb.handle_action(EditAction::Payloads(PayloadsAction {
+            payloads: vec![
+                EditPayload::SheetShift(SheetShift {
+                    idx: 1,
+                    insert: true,
+                }),
+                EditPayload::SheetRename(SheetRename {
+                    old_name: String::from("Sheet2"),
+                    new_name: String::from("It's mine"),
+                }),
+            ],
+            undoable: true,
+        }));
+        input(&mut wb, 1, 0, 0, "2");
+        input(&mut wb, 0, 0, 0, "='It''s mine'!A1+1");
+        let trace = wb.evaluate_formula(0, 0, 0).unwrap();
+        assert!(matches!(trace.value, EvalValue::Scalar(Value::Number(n)) if n == 3.));
+        assert_eq!(
+            trace.children[0].reference.as_deref(),
+            Some("'It''s mine'!A1")
+        );
+
+        let saved = wb.save().unwrap();
+        let reloaded = Controller::from_file(String::from("quoted"), &saved).unwrap();
+        let mut book = Workbook {
+            controller: reloaded,
+        };
+        let mut sheet = book.get_sheet_by_idx(0).unwrap();
+        assert_eq!(sheet.get_formula(0, 0).unwrap(), "'It''s mine'!A1 + 1");
+        assert!(matches!(sheet.get_value(0, 0).unwrap(), Value::Number(n) if n == 3.));
+    }
+}
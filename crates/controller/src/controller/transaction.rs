@@ -1,24 +1,31 @@
 use anyhow::Result;
+use logisheets_astchecker::AstChecker;
 use std::collections::{HashMap, HashSet};
 
-use logisheets_base::{Addr, BlockRange, CellId, NormalRange, Range, SheetId};
+use logisheets_base::matrix_value::cross_product_usize;
+use logisheets_base::{Addr, BlockRange, CellId, CellValue, NormalRange, Range, SheetId};
 
 use super::status::Status;
 use crate::async_func_manager::AsyncFuncManager;
 use crate::calc_engine::CalcEngine;
-use crate::connectors::{CalcConnector, VertexConnector};
+use crate::connectors::{CalcConnector, IdFetcher, VertexConnector};
+use crate::container::{DataContainer, Spill};
 use crate::data_executor::DataExecutor;
-use crate::formula_manager::{FormulaExecContext, Vertex};
+use crate::formula_manager::{FormulaExecContext, FormulaManager, Vertex};
+use crate::navigator::Navigator;
 use crate::payloads::name::NamePayload;
-use crate::payloads::sheet_process::SheetProcess;
+use crate::payloads::sheet_process::{CellChange, CellPayload, SheetPayload, SheetProcess};
 use crate::payloads::sheet_shift::{SheetRenamePayload, SheetShiftPayload};
 use crate::payloads::Process;
-use crate::settings::CalcConfig;
+use crate::settings::{func_registry::FuncRegistry, CalcConfig, CalcMode};
 
 pub struct TransactionContext<'a> {
     pub book_name: &'a str,
     pub calc_config: CalcConfig,
-    pub async_funcs: &'a HashSet<String>,
+    pub func_registry: &'a FuncRegistry,
+    /// Checks the function arguments of the input formulas.
+    pub ast_checker: &'a AstChecker,
+    pub date1904: bool,
 }
 
 pub struct Transaction<'a> {
@@ -34,10 +41,36 @@ impl<'a> Transaction<'a> {
         let mut async_func_manager = self.async_func_manager;
         let mut status = self.status;
         let mut calc_nodes = HashSet::<Vertex>::new();
+        // The input formulas are calculated even in the manual mode.
+        let mut input_formulas = HashSet::<Vertex>::new();
+        // The formulas are calculated on demand in the manual mode.
+        let forced = self.proc.iter().any(|p| {
+            matches!(
+                p,
+                Process::RecalcVolatile
+                    | Process::RecalcSheet(_)
+                    | Process::RecalcAll
+                    | Process::RecalcStale
+            )
+        });
+        let manual = context.calc_config.calc_mode == CalcMode::Manual && !forced;
         for proc in self.proc {
+            if let Process::RecalcStale = proc {
+                calc_nodes.extend(status.stale_vertices.iter().cloned());
+            }
+            let input = match &proc {
+                Process::Sheet(SheetProcess {
+                    sheet_id,
+                    payload: SheetPayload::Formula(p),
+                }) => Some((*sheet_id, p.row, p.col)),
+                _ => None,
+            };
             let (new_status, nodes) = handle(status, proc, &context)?;
             calc_nodes.extend(nodes);
             status = new_status;
+            if let Some((sheet_id, row, col)) = input {
+                input_formulas.extend(get_cell_vertex(&mut status, sheet_id, row, col));
+            }
         }
         let Status {
             mut navigator,
@@ -50,22 +83,34 @@ impl<'a> Transaction<'a> {
             sheet_pos_manager,
             style_manager,
             cell_attachment_manager,
-            formula_manager,
+            mut formula_manager,
+            mut stale_vertices,
+            mut circular_refs,
         } = status;
+        if !manual {
+            calc_nodes.extend(formula_manager.volatile_vertices.iter().cloned());
+        }
+        let mut dynamic_deps = HashMap::new();
+        let mut volatile_cells = HashSet::new();
         let connector = CalcConnector {
             navigator: &mut navigator,
             container: &mut container,
             ext_links: &mut external_links_manager,
             text_id_manager: &mut text_id_manager,
             func_id_manager: &mut func_id_manager,
+            name_id_manager: &name_id_manager,
             names_storage: HashMap::new(),
             cells_stroage: HashMap::new(),
             sheet_pos_manager: &sheet_pos_manager,
+            sheet_id_manager: &sheet_id_manager,
+            dynamic_deps: &mut dynamic_deps,
+            volatile_cells: &mut volatile_cells,
             async_func_manager: &mut async_func_manager,
             active_sheet: 0,
             curr_addr: Addr::default(),
-            async_funcs: &context.async_funcs,
+            func_registry: context.func_registry,
             formula_manager: &formula_manager,
+            date1904: context.date1904,
         };
         let calc_engine = CalcEngine {
             config: context.calc_config,
@@ -73,7 +118,28 @@ impl<'a> Transaction<'a> {
             formula_manager: &formula_manager,
             dirty_vertices: calc_nodes,
         };
-        calc_engine.start();
+        if manual {
+            let (calculated, stale) = calc_engine.start_manual(&input_formulas);
+            stale_vertices.retain(|v| !calculated.contains(v));
+            stale_vertices.extend(stale);
+        } else {
+            let (calculated, cycles) = calc_engine.start();
+            stale_vertices.retain(|v| !calculated.contains(v));
+            circular_refs.retain(|c| !c.vertices.iter().any(|v| calculated.contains(v)));
+            circular_refs.extend(cycles);
+            formula_manager.update_volatile_vertices(&calculated, volatile_cells);
+        }
+        // The circular references are broken if their formulas are removed.
+        circular_refs.retain(|c| {
+            c.vertices
+                .iter()
+                .all(|v| is_formula_vertex(&formula_manager, v))
+        });
+        dynamic_deps
+            .into_iter()
+            .for_each(|((sheet_id, cell_id), deps)| {
+                formula_manager.set_dynamic_deps(sheet_id, cell_id, deps)
+            });
         Ok(Status {
             navigator,
             container,
@@ -86,10 +152,26 @@ impl<'a> Transaction<'a> {
             style_manager,
             cell_attachment_manager,
             formula_manager,
+            stale_vertices,
+            circular_refs,
         })
     }
 }
 
+fn is_formula_vertex(formula_manager: &FormulaManager, v: &Vertex) -> bool {
+    let cell = match v {
+        Vertex::Range(sheet_id, range_id) => {
+            match formula_manager.range_manager.get_range(sheet_id, range_id) {
+                Some(Range::Normal(NormalRange::Single(c))) => (*sheet_id, CellId::NormalCell(c)),
+                Some(Range::Block(BlockRange::Single(c))) => (*sheet_id, CellId::BlockCell(c)),
+                _ => return false,
+            }
+        }
+        _ => return false,
+    };
+    formula_manager.formulas.contains_key(&cell)
+}
+
 fn handle(
     status: Status,
     proc: Process,
@@ -106,13 +188,36 @@ fn handle(
             Ok((handle_sheet_rename_payload(status, rename), HashSet::new()))
         }
         Process::Recalc(dirty) => Ok(handle_recalc_proc(status, dirty)),
+        Process::RecalcVolatile => Ok((status, HashSet::new())),
+        Process::RecalcSheet(sheet_id) => {
+            let calc_nodes = status.formula_manager.get_formula_vertices(Some(sheet_id));
+            Ok((status, calc_nodes))
+        }
+        Process::RecalcAll => Ok(handle_recalc_all_proc(status)),
+        Process::RecalcStale => Ok((status, HashSet::new())),
     }
 }
 
+fn handle_recalc_all_proc(status: Status) -> (Status, HashSet<Vertex>) {
+    let mut status = status;
+    let mut id_fetcher = IdFetcher {
+        sheet_id_manager: &mut status.sheet_id_manager,
+        text_id_manager: &mut status.text_id_manager,
+        func_id_manager: &mut status.func_id_manager,
+        name_id_manager: &mut status.name_id_manager,
+        external_links_manager: &mut status.external_links_manager,
+        navigator: &mut status.navigator,
+    };
+    status.formula_manager.rebuild_graph(&mut id_fetcher);
+    let calc_nodes = status.formula_manager.get_formula_vertices(None);
+    (status, calc_nodes)
+}
+
 fn handle_recalc_proc(status: Status, dirty: Vec<(SheetId, CellId)>) -> (Status, HashSet<Vertex>) {
+    // The cells can be removed after they are recorded.
     let calc_nodes = dirty
         .into_iter()
-        .map(|(sheet_id, cell_id)| {
+        .filter_map(|(sheet_id, cell_id)| {
             let range = match cell_id {
                 CellId::NormalCell(nid) => Range::Normal(NormalRange::Single(nid)),
                 CellId::BlockCell(bid) => Range::Block(BlockRange::Single(bid)),
@@ -120,14 +225,28 @@ fn handle_recalc_proc(status: Status, dirty: Vec<(SheetId, CellId)>) -> (Status,
             let range_id = status
                 .formula_manager
                 .range_manager
-                .get_range_id_assert(&sheet_id, &range)
-                .unwrap();
-            Vertex::Range(sheet_id, range_id)
+                .get_range_id_assert(&sheet_id, &range)?;
+            Some(Vertex::Range(sheet_id, range_id))
         })
         .collect();
     (status, calc_nodes)
 }
 
+fn get_cell_vertex(
+    status: &mut Status,
+    sheet_id: SheetId,
+    row: usize,
+    col: usize,
+) -> Option<Vertex> {
+    let range = match status.navigator.fetch_cell_id(&sheet_id, row, col).ok()? {
+        CellId::NormalCell(nid) => Range::Normal(NormalRange::Single(nid)),
+        CellId::BlockCell(bid) => Range::Block(BlockRange::Single(bid)),
+    };
+    let range_manager = &status.formula_manager.range_manager;
+    let range_id = range_manager.get_range_id_assert(&sheet_id, &range)?;
+    Some(Vertex::Range(sheet_id, range_id))
+}
+
 fn handle_sheet_rename_payload(status: Status, payload: SheetRenamePayload) -> Status {
     let mut res = status;
     res.sheet_id_manager
@@ -148,6 +267,8 @@ fn handle_sheet_shift_payload(status: Status, payload: SheetShiftPayload) -> Sta
         sheet_pos_manager,
         style_manager,
         cell_attachment_manager,
+        stale_vertices,
+        circular_refs,
     } = status;
     let sheet_pos = sheet_pos_manager.execute(&payload, &mut sheet_id_manager);
     Status {
@@ -162,6 +283,8 @@ fn handle_sheet_shift_payload(status: Status, payload: SheetShiftPayload) -> Sta
         sheet_pos_manager: sheet_pos,
         style_manager,
         cell_attachment_manager,
+        stale_vertices,
+        circular_refs,
     }
 }
 
@@ -190,8 +313,19 @@ fn handle_sheet_proc(
         mut sheet_pos_manager,
         style_manager,
         cell_attachment_manager,
+        stale_vertices,
+        circular_refs,
     } = status;
     let mut old_navigator = navigator.clone();
+    let input = match &proc.payload {
+        SheetPayload::Cell(CellPayload {
+            row,
+            col,
+            change: CellChange::Value(_),
+        }) => Some((*row, *col)),
+        SheetPayload::Formula(p) => Some((p.row, p.col)),
+        _ => None,
+    };
     let data_executor = DataExecutor {
         navigator,
         container,
@@ -207,9 +341,10 @@ fn handle_sheet_proc(
     let active_sheet = proc.sheet_id;
     let FormulaExecContext {
         manager: formula_manager,
-        dirty_vertices,
+        mut dirty_vertices,
     } = formula_manager.execute_sheet_proc(
         proc,
+        context.ast_checker,
         &mut VertexConnector {
             book_name: context.book_name,
             active_sheet,
@@ -224,6 +359,16 @@ fn handle_sheet_proc(
             external_links_manager: &mut external_links_manager,
         },
     )?;
+    if let Some((row, col)) = input {
+        dirty_vertices.extend(block_spills(
+            &mut new_container,
+            &mut new_navigator,
+            &formula_manager,
+            active_sheet,
+            row,
+            col,
+        ));
+    }
     let status = Status {
         navigator: new_navigator,
         formula_manager,
@@ -236,6 +381,168 @@ fn handle_sheet_proc(
         sheet_pos_manager,
         style_manager: new_style_manager,
         cell_attachment_manager,
+        stale_vertices,
+        circular_refs,
     };
     Ok((status, dirty_vertices))
 }
+
+// A cell input in the area of an array result blocks it. The other spilled
+// values are removed and the formula is calculated again to get #SPILL!.
+// The input cell is also checked against the blocked areas so that clearing
+// it lets the array spill again.
+fn block_spills(
+    container: &mut DataContainer,
+    navigator: &mut Navigator,
+    formula_manager: &FormulaManager,
+    sheet_id: SheetId,
+    row: usize,
+    col: usize,
+) -> Vec<Vertex> {
+    let spills = container.get_sheet_container(sheet_id).spills.clone();
+    spills
+        .into_iter()
+        .filter_map(|(anchor, spill)| {
+            let (anchor_row, anchor_col) = navigator.fetch_cell_idx(&sheet_id, &anchor).ok()?;
+            if !spill.contains((anchor_row, anchor_col), row, col) {
+                return None;
+            }
+            if !spill.blocked {
+                cross_product_usize(
+                    anchor_row,
+                    anchor_row + spill.rows - 1,
+                    anchor_col,
+                    anchor_col + spill.cols - 1,
+                )
+                .into_iter()
+                .filter(|p| *p != (anchor_row, anchor_col) && *p != (row, col))
+                .for_each(|(r, c)| {
+                    if let Ok(cid) = navigator.fetch_cell_id(&sheet_id, r, c) {
+                        if let Some(cell) = container.get_cell(sheet_id, &cid) {
+                            cell.value = CellValue::Blank;
+                        }
+                    }
+                });
+            }
+            let spills = &mut container.get_sheet_container(sheet_id).spills;
+            if (anchor_row, anchor_col) == (row, col) {
+                // The formula is replaced and the array is gone.
+                spills.remove(&anchor);
+                return None;
+            }
+            if spill.cse {
+                // A legacy array can not be changed in part. It becomes a
+                // dynamic one, which is blocked by the input.
+                spills.remove(&anchor);
+            } else if !spill.blocked {
+                let blocked = Spill {
+                    blocked: true,
+                    ..spill
+                };
+                spills.insert(anchor, blocked);
+            }
+            let range = match anchor {
+                CellId::NormalCell(c) => Range::Normal(NormalRange::Single(c)),
+                CellId::BlockCell(c) => Range::Block(BlockRange::Single(c)),
+            };
+            let range_id = formula_manager
+                .range_manager
+                .get_range_id_assert(&sheet_id, &range)?;
+            Some(Vertex::Range(sheet_id, range_id))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::controller::display::{DisplayPatch, DisplayRequest};
+    use crate::controller::edit_action::{
+        CellInput, EditAction, EditPayload, PayloadsAction, RecalcAction,
+    };
+    use crate::controller::Controller;
+    use crate::test_utils::{get_value, input};
+    use logisheets_base::CellValue;
+
+    #[test]
+    fn recalc_volatile_test() {
+        let mut wb = Controller::default();
+        input(&mut wb, 0, 0, 0, "=RAND()");
+        let first = get_value(&mut wb, 0, 0, 0);
+        wb.handle_action(EditAction::Recalc(RecalcAction::Volatile));
+        let second = get_value(&mut wb, 0, 0, 0);
+        assert!(matches!(
+            (first, second),
+            (CellValue::Number(a), CellValue::Number(b)) if a != b
+        ));
+    }
+
+    #[test]
+    fn manual_calc_mode_test() {
+        use crate::settings::CalcMode;
+        let mut wb = Controller::default();
+        wb.settings.calc_config.calc_mode = CalcMode::Manual;
+        input(&mut wb, 0, 0, 0, "1");
+        input(&mut wb, 0, 0, 1, "=A1+1");
+        input(&mut wb, 0, 0, 2, "=B1*2");
+        // The input formulas are calculated.
+        assert!(wb.get_stale_cells().is_empty());
+        assert!(matches!(get_value(&mut wb, 0, 0, 2), CellValue::Number(n) if n == 4.));
+
+        input(&mut wb, 0, 0, 0, "2");
+        assert!(matches!(get_value(&mut wb, 0, 0, 2), CellValue::Number(n) if n == 4.));
+        assert_eq!(wb.get_stale_cells().len(), 2);
+        wb.handle_action(EditAction::Undo);
+        assert!(wb.get_stale_cells().is_empty());
+        wb.handle_action(EditAction::Redo);
+        assert_eq!(wb.get_stale_cells().len(), 2);
+        let response = wb.get_display_response(DisplayRequest {
+            sheet_idx: 0,
+            version: 0,
+        });
+        let stale = response
+            .patches
+            .iter()
+            .flat_map(|p| match p {
+                DisplayPatch::Values(v) => v.values.clone(),
+                _ => vec![],
+            })
+            .filter(|v| v.stale)
+            .count();
+        assert_eq!(stale, 2);
+        wb.handle_action(EditAction::Recalc(RecalcAction::Now));
+        assert!(matches!(get_value(&mut wb, 0, 0, 2), CellValue::Number(n) if n == 6.));
+
+        let saved = wb.save().unwrap();
+        let reloaded = Controller::from_file(String::from("manual"), &saved).unwrap();
+        assert_eq!(reloaded.settings.calc_config.calc_mode, CalcMode::Manual);
+    }
+
+    #[test]
+    fn independent_formulas_test() {
+        let mut wb = Controller::default();
+        // More formulas than `MIN_PARALLEL_FORMULAS`, so that they are
+        // calculated on copies of the data with the `parallel` feature.
+        let inputs = |f: &dyn Fn(usize) -> (usize, String)| {
+            EditAction::Payloads(PayloadsAction {
+                payloads: (0..40)
+                    .map(|row| {
+                        let (col, content) = f(row);
+                        EditPayload::CellInput(CellInput {
+                            sheet_idx: 0,
+                            row,
+                            col,
+                            content,
+                        })
+                    })
+                    .collect(),
+                undoable: true,
+            })
+        };
+        wb.handle_action(inputs(&|row| (1, format!("=A{}*2", row + 1))));
+        input(&mut wb, 0, 0, 2, "=SUM(B1:B40)");
+        wb.handle_action(inputs(&|row| (0, (row + 1).to_string())));
+        assert!(matches!(get_value(&mut wb, 0, 0, 2), CellValue::Number(n) if n == 1640.));
+        wb.handle_action(inputs(&|row| (0, ((row + 1) * 10).to_string())));
+        assert!(matches!(get_value(&mut wb, 0, 0, 2), CellValue::Number(n) if n == 16400.));
+    }
+}
@@ -1,4 +1,5 @@
-use logisheets_base::{CellValue, SheetId};
+use logisheets_base::{rst_to_plain_text, CellValue, SheetId};
+use logisheets_parser::unparse::unparse_r1c1;
 use logisheets_parser::unparse::Stringify;
 
 use crate::connectors::NameFetcher;
@@ -6,6 +7,8 @@ use crate::controller::display::{
     SheetColInfo, SheetComments, SheetMergeCells, SheetRowInfo, SheetStyles, SheetValues, Value,
 };
 use crate::id_manager::TextIdManager;
+use crate::num_fmt::format_value;
+use crate::settings::ReferenceStyle;
 
 use super::display::{
     BlockInfo, CellFormulaValue, CellStyle, ColInfo, Comment, DisplayPatch, DisplayResponse,
@@ -39,6 +42,7 @@ impl SheetViewer {
     }
 
     fn load_sheet(&mut self, controller: &mut Controller, sheet_id: SheetId) {
+        let stale_cells = controller.get_stale_cells();
         let s = &controller.status.container;
         let navigator = &mut controller.status.navigator;
         let style_manager = &controller.status.style_manager;
@@ -83,6 +87,8 @@ impl SheetViewer {
             let style_converter = StyleConverter {
                 theme_manager: &controller.settings.theme,
             };
+            let date1904 = controller.settings.date1904;
+            let reference_style = controller.settings.reference_style;
             sheet_data.cells.iter().for_each(|(cell_id, cell)| {
                 let coord = navigator.fetch_cell_idx(&sheet_id, cell_id);
                 if coord.is_err() {
@@ -90,6 +96,7 @@ impl SheetViewer {
                 }
                 let (row, col) = coord.unwrap();
                 let raw_style = style_manager.get_cell_style(cell.style);
+                let num_fmt = raw_style.formatter.clone();
                 self.styles.push(CellStyle {
                     row,
                     col,
@@ -107,11 +114,27 @@ impl SheetViewer {
 
                 let (formula, has_formula) =
                     match formula_manager.formulas.get(&(sheet_id, cell_id.clone())) {
-                        Some(n) => (n.unparse(&mut name_fetcher, sheet_id), true),
+                        Some(n) => {
+                            let f = match reference_style {
+                                ReferenceStyle::A1 => n.unparse(&mut name_fetcher, sheet_id),
+                                ReferenceStyle::R1C1 => {
+                                    unparse_r1c1(n, &mut name_fetcher, sheet_id, row, col)
+                                }
+                            };
+                            (f, true)
+                        }
                         None => (String::from(""), false),
                     };
-                let v = convert_value(row, col, &cell.value, formula, has_formula, text_id_manager);
-                self.values.push(v);
+                let value = convert_value(&cell.value, has_formula, text_id_manager);
+                let formatted = format_value(&value, &num_fmt, date1904);
+                self.values.push(CellFormulaValue {
+                    row,
+                    col,
+                    formula,
+                    value,
+                    formatted,
+                    stale: stale_cells.contains(&(sheet_id, *cell_id)),
+                });
             });
         }
         let cell_attachments = &controller.status.cell_attachment_manager;
@@ -233,15 +256,8 @@ fn get_default_row_height() -> f64 {
     14.25
 }
 
-fn convert_value(
-    row: usize,
-    col: usize,
-    cv: &CellValue,
-    formula: String,
-    has_formula: bool,
-    text_id_manager: &TextIdManager,
-) -> CellFormulaValue {
-    let value = match cv {
+fn convert_value(cv: &CellValue, has_formula: bool, text_id_manager: &TextIdManager) -> Value {
+    match cv {
         CellValue::Blank => {
             if has_formula {
                 Value::Number(0_f64)
@@ -250,7 +266,7 @@ fn convert_value(
             }
         }
         CellValue::Boolean(b) => Value::Bool(*b),
-        CellValue::Date(_) => todo!(),
+        CellValue::Date(d) => Value::Date(d.to_rfc3339()),
         CellValue::Error(e) => Value::Error(e.to_string()),
         CellValue::String(s) => Value::Str(
             text_id_manager
@@ -258,13 +274,7 @@ fn convert_value(
                 .unwrap_or(String::from("Error")),
         ),
         CellValue::Number(n) => Value::Number(*n),
-        CellValue::InlineStr(_) => todo!(),
+        CellValue::InlineStr(rst) => Value::Str(rst_to_plain_text(rst)),
         CellValue::FormulaStr(s) => Value::Str(s.clone()),
-    };
-    CellFormulaValue {
-        row,
-        col,
-        formula,
-        value,
     }
 }
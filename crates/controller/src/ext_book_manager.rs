@@ -28,6 +28,28 @@ impl ExtBooksManager {
         self.book_id_manager.get_id(name)
     }
 
+    /// The books in the order they are written in the file. The books only
+    /// referred to in the formulas come after the loaded ones.
+    pub fn get_book_ids(&self) -> Vec<ExtBookId> {
+        let mut rest = self
+            .book_id_manager
+            .ids
+            .values()
+            .filter(|id| !self.orders.contains(id))
+            .copied()
+            .collect::<Vec<_>>();
+        rest.sort();
+        self.orders.iter().copied().chain(rest).collect()
+    }
+
+    /// The 1-based index of the book in the file, or 0 if the book is unknown.
+    pub fn get_book_idx(&self, id: &ExtBookId) -> usize {
+        self.get_book_ids()
+            .iter()
+            .position(|b| b == id)
+            .map_or(0, |p| p + 1)
+    }
+
     fn check(&mut self, book_id: ExtBookId, sheet_id: SheetId) {
         if !self.books.contains_key(&book_id) {
             let b = ExtBook::new_with_sheet_id(sheet_id);
@@ -7,7 +7,7 @@ use anyhow::Result;
 
 use logisheets_base::{
     id_fetcher::IdFetcherTrait, index_fetcher::IndexFetcherTrait, BlockCellId, ColId, ExtBookId,
-    NormalCellId, RowId, SheetId,
+    NameId, NormalCellId, RowId, SheetId,
 };
 use logisheets_workbook::workbook::Workbook;
 
@@ -51,7 +51,12 @@ impl<'a> IdFetcherTrait for Fetcher<'a> {
         self.name_id_manager.get_id(&(book_id, name.to_owned()))
     }
 
+    fn fetch_local_name_id(&mut self, sheet_id: &SheetId, name: &str) -> Option<NameId> {
+        self.name_id_manager.find_local_id(*sheet_id, name)
+    }
+
     fn fetch_ext_book_id(&mut self, book: &str) -> ExtBookId {
+        // The books are 1-based indexes of the external references.
         let idx = book.parse::<usize>().unwrap_or(0);
         if let Some(link) = self
             .workbook
@@ -59,9 +64,8 @@ impl<'a> IdFetcherTrait for Fetcher<'a> {
             .workbook_part
             .external_references
             .as_ref()
-            .unwrap()
-            .external_references
-            .get(idx)
+            .zip(idx.checked_sub(1))
+            .and_then(|(refs, idx)| refs.external_references.get(idx))
         {
             let id = &link.id;
             match self.workbook.xl.external_links.get(id) {
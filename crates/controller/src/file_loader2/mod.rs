@@ -6,19 +6,24 @@ mod styles;
 mod utils;
 mod vertex;
 
-use logisheets_base::id_fetcher::SheetIdFetcherTrait;
+use logisheets_base::{id_fetcher::SheetIdFetcherTrait, SheetId};
 use logisheets_workbook::prelude::*;
+use logisheets_workbook::rtypes::{RType, CALC_CHAIN};
 use sheet::load_comments;
+use std::collections::HashMap;
 
 use crate::{
+    connectors::VertexConnector,
     controller::{status::Status, Controller},
     file_loader2::{
         external_links::load_external_link,
-        sheet::{load_cols, load_merge_cells, load_sheet_data, load_sheet_format_pr},
+        sheet::{load_cols, load_merge_cells, load_sheet_data, load_sheet_format_pr, load_table},
         styles::StyleLoader,
+        vertex::load_defined_name,
     },
     id_manager::SheetIdManager,
-    settings::Settings,
+    payloads::Process,
+    settings::{BookPassthrough, CalcMode, ReferenceStyle, Settings, SheetPassthrough},
     theme_manager::ThemeManager,
 };
 pub struct SheetIdFetcher<'a> {
@@ -32,6 +37,21 @@ impl<'a> SheetIdFetcherTrait for SheetIdFetcher<'a> {
 }
 
 pub fn load(wb: Workbook, book_name: String) -> Controller {
+    let mut wb = wb;
+    let mut passthrough = BookPassthrough {
+        store: std::mem::take(&mut wb.passthrough),
+        workbook_pr: wb.xl.workbook_part.workbook_pr.clone(),
+        book_views: wb.xl.workbook_part.book_views.clone(),
+        pivot_caches: wb.xl.workbook_part.pivot_caches.clone(),
+        sheets: HashMap::new(),
+        metadata: wb.xl.metadata.as_ref().map(|(_, m)| m.clone()),
+        defined_names: wb.xl.workbook_part.defined_names.clone(),
+        tables: HashMap::new(),
+        dxfs: wb.xl.styles.1.dxfs.clone(),
+        table_styles: wb.xl.styles.1.table_styles.clone(),
+        colors: wb.xl.styles.1.colors.clone(),
+    };
+    remove_calc_chain(&mut passthrough.store);
     let Status {
         mut navigator,
         mut container,
@@ -44,6 +64,8 @@ pub fn load(wb: Workbook, book_name: String) -> Controller {
         mut style_manager,
         mut cell_attachment_manager,
         mut formula_manager,
+        stale_vertices,
+        circular_refs,
     } = Status::default();
     let mut sheet_id_fetcher = SheetIdFetcher {
         sheet_id_manager: &mut sheet_id_manager,
@@ -58,10 +80,61 @@ pub fn load(wb: Workbook, book_name: String) -> Controller {
     }
     let mut settings = Settings::default();
     if let Some(calc_pr) = &wb.xl.workbook_part.calc_pr {
+        settings.calc_config.iterate = calc_pr.iterate;
         settings.calc_config.iter_limit = calc_pr.iterate_count as u16;
         settings.calc_config.error = calc_pr.iterate_delta as f32;
+        settings.calc_config.calc_mode = match calc_pr.calc_mode {
+            StCalcMode::Auto => CalcMode::Auto,
+            StCalcMode::AutoNoTable => CalcMode::AutoNoTable,
+            StCalcMode::Manual => CalcMode::Manual,
+        };
+        settings.reference_style = match calc_pr.ref_mode {
+            StRefMode::A1 => ReferenceStyle::A1,
+            StRefMode::R1C1 => ReferenceStyle::R1C1,
+        };
+    }
+    if let Some(workbook_pr) = &wb.xl.workbook_part.workbook_pr {
+        settings.date1904 = workbook_pr.date1904;
     }
     let mut style_loader = StyleLoader::new(&mut style_manager, &wb.xl.styles.1);
+    // The names only used in a sheet go before the names of the workbook in
+    // that sheet, so they are registered before loading the formulas.
+    let sheets = &wb.xl.workbook_part.sheets.sheets;
+    let local_names = match &wb.xl.workbook_part.defined_names {
+        Some(defined_names) => defined_names
+            .names
+            .iter()
+            .filter_map(|n| {
+                let ct_sheet = sheets.get(n.local_sheet_id? as usize)?;
+                let sheet_id = sheet_id_manager.get_id(&ct_sheet.name);
+                name_id_manager.get_local_id(&(sheet_id, n.name.clone()));
+                Some((n.name.clone(), n.local_sheet_id?, sheet_id))
+            })
+            .collect::<Vec<_>>(),
+        None => vec![],
+    };
+    // The formulas may refer to the tables in the sheets after theirs.
+    wb.xl
+        .workbook_part
+        .sheets
+        .sheets
+        .iter()
+        .for_each(|ct_sheet| {
+            let sheet_id = sheet_id_manager.get_id(&ct_sheet.name);
+            if let Some(ws) = wb.xl.worksheets.get(&ct_sheet.id) {
+                ws.tables.iter().for_each(|t| {
+                    let id = load_table(
+                        sheet_id,
+                        t,
+                        &mut navigator,
+                        &mut formula_manager.table_manager,
+                    );
+                    if let Some(id) = id {
+                        passthrough.tables.insert(id, t.clone());
+                    }
+                });
+            }
+        });
     wb.xl
         .workbook_part
         .sheets
@@ -76,6 +149,17 @@ pub fn load(wb: Workbook, book_name: String) -> Controller {
             }
             let id = &ct_sheet.id;
             if let Some(ws) = wb.xl.worksheets.get(id) {
+                let relationships = passthrough
+                    .store
+                    .worksheet_relationships
+                    .remove(id)
+                    .unwrap_or_default();
+                load_sheet_passthrough(
+                    &mut passthrough,
+                    sheet_id,
+                    relationships,
+                    &ws.worksheet_part,
+                );
                 if let Some(comments) = &ws.comments {
                     load_comments(
                         sheet_id,
@@ -122,6 +206,50 @@ pub fn load(wb: Workbook, book_name: String) -> Controller {
                 )
             }
         });
+    if let Some(defined_names) = &wb.xl.workbook_part.defined_names {
+        defined_names.names.iter().for_each(|n| {
+            let scope = match n.local_sheet_id {
+                Some(idx) => match local_names
+                    .iter()
+                    .find(|(name, i, _)| *i == idx && *name == n.name)
+                {
+                    Some((_, _, sheet_id)) => Some(*sheet_id),
+                    // The sheet of the name does not exist.
+                    None => return,
+                },
+                None => None,
+            };
+            // The references without sheet names in a name only used in a
+            // sheet refer to that sheet.
+            let active_sheet = match scope {
+                Some(sheet_id) => sheet_id,
+                None => match sheet_pos_manager.get_sheet_id(0) {
+                    Some(sheet_id) => sheet_id,
+                    None => return,
+                },
+            };
+            let mut vertex_connector = VertexConnector {
+                book_name: &book_name,
+                active_sheet,
+                container: &mut container,
+                sheet_pos_manager: &mut sheet_pos_manager,
+                sheet_id_manager: &mut sheet_id_manager,
+                text_id_manager: &mut text_id_manager,
+                func_id_manager: &mut func_id_manager,
+                name_id_manager: &mut name_id_manager,
+                id_navigator: &mut navigator.clone(),
+                idx_navigator: &mut navigator,
+                external_links_manager: &mut external_links_manager,
+            };
+            load_defined_name(
+                &mut formula_manager,
+                &n.name,
+                scope,
+                &n.value,
+                &mut vertex_connector,
+            )
+        });
+    }
     let status = Status {
         navigator,
         formula_manager,
@@ -134,9 +262,161 @@ pub fn load(wb: Workbook, book_name: String) -> Controller {
         sheet_pos_manager,
         style_manager,
         cell_attachment_manager,
+        stale_vertices,
+        circular_refs,
     };
     if let Some(theme) = wb.xl.theme {
         settings.theme = ThemeManager::from(theme.1);
     }
-    Controller::from(status, book_name, settings)
+    settings.passthrough = passthrough;
+    let full_calc_on_load = match &wb.xl.workbook_part.calc_pr {
+        Some(calc_pr) => calc_pr.full_calc_on_load,
+        None => false,
+    };
+    let mut controller = Controller::from(status, book_name, settings);
+    if full_calc_on_load {
+        // The cached values are not trusted.
+        let _ = controller.handle_process(vec![Process::RecalcAll], false);
+    }
+    controller
+}
+
+fn load_sheet_passthrough(
+    passthrough: &mut BookPassthrough,
+    sheet_id: SheetId,
+    relationships: Vec<CtRelationship>,
+    worksheet_part: &WorksheetPart,
+) {
+    let sheet = SheetPassthrough {
+        relationships,
+        sheet_pr: worksheet_part.sheet_pr.clone(),
+        sheet_views: worksheet_part.sheet_views.clone(),
+        sheet_protection: worksheet_part.sheet_protection.clone(),
+        auto_filter: worksheet_part.auto_filter.clone(),
+        conditional_formatting: worksheet_part.conditional_formatting.clone(),
+        data_validations: worksheet_part.data_validations.clone(),
+        hyperlinks: worksheet_part.hyperlinks.clone(),
+        print_options: worksheet_part.print_options.clone(),
+        page_margins: worksheet_part.page_margins.clone(),
+        page_setup: worksheet_part.page_setup.clone(),
+        header_footer: worksheet_part.header_footer.clone(),
+        drawing: worksheet_part.drawing.clone(),
+        legacy_drawing: worksheet_part.legacy_drawing.clone(),
+        legacy_drawing_hf: worksheet_part.legacy_drawing_hf.clone(),
+        picture: worksheet_part.picture.clone(),
+    };
+    passthrough.sheets.insert(sheet_id, sheet);
+}
+
+// The calculation chain would be out of date once the workbook is modified.
+// Spreadsheet applications will rebuild it if it is missing.
+fn remove_calc_chain(store: &mut Passthrough) {
+    let parts = &mut store.parts;
+    store.workbook_relationships.retain(|r| {
+        if RType(&r.ty) == CALC_CHAIN {
+            parts.remove(&r.target);
+            false
+        } else {
+            true
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::controller::edit_action::{EditAction, RecalcAction};
+    use crate::controller::Controller;
+    use crate::test_utils::{defined_name, get_value, input};
+    use logisheets_base::CellValue;
+    use logisheets_workbook::prelude::read;
+
+    #[test]
+    fn defined_name_lambda_test() {
+        use std::fs;
+        let buf = fs::read("../../tests/lambda.xlsx").unwrap();
+        let mut wb = Controller::from_file(String::from("lambda"), &buf).unwrap();
+        input(&mut wb, 0, 50, 0, "=DOUBLE(TEN)+TEN");
+        assert!(matches!(get_value(&mut wb, 0, 50, 0), CellValue::Number(n) if n == 30.));
+        let saved = wb.save().unwrap();
+        let reloaded = Controller::from_file(String::from("lambda"), &saved).unwrap();
+        assert_eq!(reloaded.status.formula_manager.names.len(), 2);
+    }
+
+    #[test]
+    fn local_defined_name_test() {
+        use logisheets_workbook::prelude::CtDefinedNames;
+        let mut book = crate::file_saver::save(&Controller::default());
+        book.xl.workbook_part.defined_names = Some(CtDefinedNames {
+            names: vec![
+                defined_name("Rate", "2", None),
+                defined_name("Rate", "3", Some(0)),
+                defined_name("Twice", "_xlfn.LAMBDA(_xlpm.x, _xlpm.x*Rate)", Some(0)),
+                // The built-in functions can not be shadowed.
+                defined_name("ABS", "LAMBDA(x, 100)", None),
+            ],
+        });
+        let buf = logisheets_workbook::writer::write(book).unwrap();
+        let mut wb = Controller::from_file(String::from("names"), &buf).unwrap();
+        input(&mut wb, 0, 0, 0, "=Rate+Twice(1)");
+        input(&mut wb, 0, 1, 0, "=ABS(-1)");
+        assert!(matches!(get_value(&mut wb, 0, 0, 0), CellValue::Number(n) if n == 6.));
+        assert!(matches!(get_value(&mut wb, 0, 1, 0), CellValue::Number(n) if n == 1.));
+        let book = crate::file_saver::save(&wb);
+        let names = book.xl.workbook_part.defined_names.unwrap().names;
+        let values = names
+            .iter()
+            .map(|n| (n.name.as_str(), n.local_sheet_id, n.value.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            values,
+            vec![
+                ("ABS", None, "_xlfn.LAMBDA(_xlpm.x, 100)"),
+                ("Rate", None, "2"),
+                ("Rate", Some(0), "3"),
+                (
+                    "Twice",
+                    Some(0),
+                    "_xlfn.LAMBDA(_xlpm.x, _xlpm.x * Sheet1!Rate)"
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn full_calc_on_load_test() {
+        use super::load;
+        let mut wb = Controller::default();
+        input(&mut wb, 0, 0, 0, "1");
+        input(&mut wb, 0, 0, 1, "=A1+1");
+        let saved = wb.save().unwrap();
+        // Make the cached value of B1 stale.
+        let stale = |full_calc_on_load: bool| {
+            let mut workbook = read(&saved).unwrap();
+            if let Some(calc_pr) = &mut workbook.xl.workbook_part.calc_pr {
+                calc_pr.full_calc_on_load = full_calc_on_load;
+            }
+            workbook.xl.worksheets.values_mut().for_each(|ws| {
+                ws.worksheet_part
+                    .sheet_data
+                    .rows
+                    .iter_mut()
+                    .flat_map(|r| r.cells.iter_mut())
+                    .filter(|c| c.f.is_some())
+                    .for_each(|c| {
+                        if let Some(v) = &mut c.v {
+                            v.value = String::from("100");
+                        }
+                    });
+            });
+            load(workbook, String::from("stale"))
+        };
+        let mut cached = stale(false);
+        assert!(matches!(get_value(&mut cached, 0, 0, 1), CellValue::Number(n) if n == 100.));
+        cached.handle_action(EditAction::Recalc(RecalcAction::Sheet(0)));
+        assert!(matches!(get_value(&mut cached, 0, 0, 1), CellValue::Number(n) if n == 2.));
+        let mut calculated = stale(true);
+        assert!(matches!(get_value(&mut calculated, 0, 0, 1), CellValue::Number(n) if n == 2.));
+        calculated.handle_action(EditAction::Recalc(RecalcAction::All));
+        assert!(matches!(get_value(&mut calculated, 0, 0, 1), CellValue::Number(n) if n == 2.));
+    }
 }
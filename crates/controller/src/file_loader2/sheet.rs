@@ -1,16 +1,18 @@
-use logisheets_base::{CellId, CellValue, SheetId};
+use logisheets_base::{rst_to_plain_text, CellId, CellValue, SheetId, TableId};
 use logisheets_workbook::prelude::*;
+use std::collections::HashMap;
 
 use crate::{
     cell::Cell,
     cell_attachments::{comment::Comment, CellAttachmentsManager},
     connectors::VertexConnector,
-    container::{col_info_manager::ColInfo, row_info_manager::RowInfo, DataContainer},
+    container::{col_info_manager::ColInfo, row_info_manager::RowInfo, DataContainer, Spill},
     ext_book_manager::ExtBooksManager,
     formula_manager::FormulaManager,
     id_manager::{FuncIdManager, NameIdManager, SheetIdManager, TextIdManager},
     navigator::Navigator,
     settings::Settings,
+    table_manager::{TableDef, TableManager},
     workbook::sheet_pos_manager::SheetPosManager,
 };
 
@@ -110,6 +112,37 @@ pub fn load_comments(
         })
 }
 
+pub fn load_table(
+    sheet_id: SheetId,
+    table: &TablePart,
+    navigator: &mut Navigator,
+    table_manager: &mut TableManager,
+) -> Option<TableId> {
+    let ((start_row, start_col), (end_row, end_col)) = parse_range(&table.reference)?;
+    let start = navigator.fetch_cell_id(&sheet_id, start_row, start_col);
+    let end = navigator.fetch_cell_id(&sheet_id, end_row, end_col);
+    if let (Ok(start), Ok(end)) = (start, end) {
+        let columns = table
+            .table_columns
+            .table_columns
+            .iter()
+            .map(|c| c.name.clone())
+            .collect();
+        let id = table_manager.add_table(TableDef {
+            name: table.display_name.clone(),
+            sheet_id,
+            start,
+            end,
+            header_row_count: table.header_row_count as usize,
+            totals_row_count: table.totals_row_count as usize,
+            columns,
+        });
+        Some(id)
+    } else {
+        None
+    }
+}
+
 pub fn load_sheet_data(
     sheet_id: SheetId,
     book_name: &str,
@@ -126,88 +159,154 @@ pub fn load_sheet_data(
     style_loader: &mut StyleLoader,
     workbook: &Workbook,
 ) {
+    let date1904 = workbook
+        .xl
+        .workbook_part
+        .workbook_pr
+        .as_ref()
+        .map(|p| p.date1904)
+        .unwrap_or(false);
+    // Only the array formulas with this cell metadata spill. The others are
+    // the legacy ones whose areas are fixed.
+    let dynamic_array_cm = workbook
+        .xl
+        .metadata
+        .as_ref()
+        .and_then(|(_, m)| m.get_dynamic_array_cm());
+    // The master cells of the shared formulas, by their indexes.
+    let mut shared_masters = HashMap::<u32, CellId>::new();
+    // A row without the index follows the previous one.
+    let mut next_row = 1;
     sheet_data.rows.iter().for_each(|row| {
         let style_id = style_loader.load_xf(row.s);
-        if let Some(idx) = row.r {
-            if idx >= 1 {
-                let row_info = RowInfo {
-                    collapsed: row.collapsed,
-                    custom_format: row.custom_format,
-                    hidden: row.hidden,
-                    ht: row.ht,
-                    outline_level: row.outline_level,
-                    style: style_id,
-                };
-                let id = navigator.fetch_row_id(&sheet_id, idx as usize - 1).unwrap();
-                container.set_row_info(sheet_id, id, row_info);
-            }
+        let idx = row.r.unwrap_or(next_row);
+        next_row = idx + 1;
+        if idx >= 1 {
+            let row_info = RowInfo {
+                collapsed: row.collapsed,
+                custom_format: row.custom_format,
+                hidden: row.hidden,
+                ht: row.ht,
+                outline_level: row.outline_level,
+                style: style_id,
+            };
+            let id = navigator.fetch_row_id(&sheet_id, idx as usize - 1).unwrap();
+            container.set_row_info(sheet_id, id, row_info);
         }
+        // So does a cell without the reference.
+        let mut next_col = 0;
         row.cells.iter().for_each(|ct_cell| {
-            if let Some(r) = &ct_cell.r {
-                if let Some((row, col)) = parse_cell(r) {
-                    let cv = CellValue::from_cell(ct_cell, |idx| {
-                        let rst = workbook.xl.sst.as_ref().unwrap().1.si.get(idx).unwrap();
-                        let string = rst_to_plain_text(rst);
-                        text_id_manager.get_id(&string)
-                    });
-                    let id = navigator.fetch_cell_id(&sheet_id, row, col).unwrap();
-                    let style_id = style_loader.load_xf(ct_cell.s);
-                    let cell = Cell {
-                        value: cv,
-                        style: style_id,
+            let pos = match &ct_cell.r {
+                Some(r) => parse_cell(r),
+                None if idx >= 1 => Some((idx as usize - 1, next_col)),
+                None => None,
+            };
+            if let Some((row, col)) = pos {
+                next_col = col + 1;
+                let cv = CellValue::from_cell(ct_cell, date1904, |idx| {
+                    let rst = workbook.xl.sst.as_ref().unwrap().1.si.get(idx).unwrap();
+                    let string = rst_to_plain_text(rst);
+                    text_id_manager.get_id(&string)
+                });
+                let id = navigator.fetch_cell_id(&sheet_id, row, col).unwrap();
+                let style_id = style_loader.load_xf(ct_cell.s);
+                let cell = Cell {
+                    value: cv,
+                    style: style_id,
+                };
+                container.add_cell(sheet_id, id, cell);
+                if let Some(formula) = &ct_cell.f {
+                    let mut vertex_connector = VertexConnector {
+                        book_name,
+                        active_sheet: sheet_id,
+                        container,
+                        sheet_pos_manager,
+                        sheet_id_manager,
+                        text_id_manager,
+                        func_id_manager,
+                        name_id_manager,
+                        id_navigator: &mut navigator.clone(),
+                        idx_navigator: navigator,
+                        external_links_manager: ext_books_manager,
                     };
-                    container.add_cell(sheet_id, id, cell);
-                    if let Some(formula) = &ct_cell.f {
-                        let mut vertex_connector = VertexConnector {
-                            book_name,
-                            active_sheet: sheet_id,
-                            container,
-                            sheet_pos_manager,
-                            sheet_id_manager,
-                            text_id_manager,
-                            func_id_manager,
-                            name_id_manager,
-                            id_navigator: &mut navigator.clone(),
-                            idx_navigator: navigator,
-                            external_links_manager: ext_books_manager,
-                        };
-                        if let Some(f) = &formula.formula {
-                            if let Some(reference) = &formula.reference {
-                                if let Some(((row_start, col_start), (row_end, col_end))) =
-                                    parse_range(reference)
-                                {
-                                    load_shared_formulas(
-                                        formula_manager,
-                                        sheet_id,
-                                        row_start,
-                                        col_start,
-                                        row_start,
-                                        col_start,
-                                        row_end,
-                                        col_end,
-                                        f,
-                                        &mut vertex_connector,
-                                    )
-                                } else if let Some((row_idx, col_idx)) = parse_cell(reference) {
-                                    load_normal_formula(
-                                        formula_manager,
-                                        sheet_id,
-                                        row_idx,
-                                        col_idx,
-                                        f,
-                                        &mut vertex_connector,
-                                    )
-                                }
-                            } else {
+                    if let Some(f) = &formula.formula {
+                        if formula.t == StCellFormulaType::Array {
+                            // The reference of an array formula is the area
+                            // of its result.
+                            load_normal_formula(
+                                formula_manager,
+                                sheet_id,
+                                row,
+                                col,
+                                f,
+                                &mut vertex_connector,
+                            );
+                            if let Some(((row_start, col_start), (row_end, col_end))) =
+                                formula.reference.as_ref().and_then(|r| parse_range(r))
+                            {
+                                let spill = Spill {
+                                    rows: row_end - row_start + 1,
+                                    cols: col_end - col_start + 1,
+                                    blocked: false,
+                                    cse: dynamic_array_cm != Some(ct_cell.cm),
+                                };
+                                container
+                                    .get_sheet_container(sheet_id)
+                                    .spills
+                                    .insert(id, spill);
+                            }
+                        } else if let Some(reference) = &formula.reference {
+                            if let Some(((row_start, col_start), (row_end, col_end))) =
+                                parse_range(reference)
+                            {
+                                load_shared_formulas(
+                                    formula_manager,
+                                    sheet_id,
+                                    row_start,
+                                    col_start,
+                                    row_start,
+                                    col_start,
+                                    row_end,
+                                    col_end,
+                                    f,
+                                    &mut vertex_connector,
+                                )
+                            } else if let Some((row_idx, col_idx)) = parse_cell(reference) {
                                 load_normal_formula(
                                     formula_manager,
                                     sheet_id,
-                                    row,
-                                    col,
+                                    row_idx,
+                                    col_idx,
                                     f,
                                     &mut vertex_connector,
                                 )
                             }
+                        } else {
+                            load_normal_formula(
+                                formula_manager,
+                                sheet_id,
+                                row,
+                                col,
+                                f,
+                                &mut vertex_connector,
+                            )
+                        }
+                    }
+                    // Keep the cells of the shared formulas so that they are
+                    // saved as shared again.
+                    if let Some(si) = formula
+                        .si
+                        .filter(|_| formula.t == StCellFormulaType::Shared)
+                    {
+                        let shared_formulas =
+                            &mut container.get_sheet_container(sheet_id).shared_formulas;
+                        if formula.reference.is_some() && formula.formula.is_some() {
+                            shared_masters.insert(si, id);
+                            shared_formulas.insert(id, vec![]);
+                        } else if let Some(master) = shared_masters.get(&si) {
+                            if let Some(cells) = shared_formulas.get_mut(master) {
+                                cells.push(id);
+                            }
                         }
                     }
                 }
@@ -225,17 +324,3 @@ pub fn load_sheet_format_pr(
         .sheet_format_pr
         .insert(sheet_id, sheet_format_pr.clone());
 }
-
-fn rst_to_plain_text(rst: &CtRst) -> String {
-    match &rst.t {
-        Some(p) => p.value.to_string(),
-        None => {
-            let mut result = String::from("");
-            rst.r.iter().for_each(|relt| {
-                let s = relt.t.value.to_string();
-                result.push_str(s.as_str());
-            });
-            result
-        }
-    }
-}
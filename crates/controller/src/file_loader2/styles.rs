@@ -1,3 +1,4 @@
+use crate::num_fmt::get_builtin_num_fmt;
 use crate::style_manager::{xf_manager::CtrlXf, StyleManager};
 use logisheets_base::StyleId;
 use logisheets_workbook::prelude::*;
@@ -13,11 +14,49 @@ pub struct StyleLoader<'a> {
 
 impl<'a> StyleLoader<'a> {
     pub fn new(manager: &'a mut StyleManager, part: &'a StylesheetPart) -> Self {
-        StyleLoader {
+        let mut loader = StyleLoader {
             manager,
             part,
             xf_cache: HashMap::new(),
             cell_xf_cache: HashMap::new(),
+        };
+        loader.load_all();
+        loader
+    }
+
+    // Register the styles in the order they appear in the stylesheet, so that
+    // they are saved with the same indices. The default styles are replaced
+    // by the first ones in the stylesheet.
+    fn load_all(&mut self) {
+        let part = self.part;
+        if let Some(fonts) = part.fonts.as_ref().filter(|f| !f.fonts.is_empty()) {
+            self.manager.font_manager.clear();
+            fonts.fonts.iter().for_each(|f| {
+                self.manager.font_manager.get_id(f);
+            });
+        }
+        if let Some(fills) = part.fills.as_ref().filter(|f| !f.fills.is_empty()) {
+            self.manager.fill_manager.clear();
+            fills.fills.iter().for_each(|f| {
+                self.manager.fill_manager.get_id(f);
+            });
+        }
+        if let Some(borders) = part.borders.as_ref().filter(|b| !b.borders.is_empty()) {
+            self.manager.border_manager.clear();
+            borders.borders.iter().for_each(|b| {
+                self.manager.border_manager.get_id(b);
+            });
+        }
+        if let Some(num_fmts) = &part.num_fmts {
+            num_fmts.num_fmts.iter().for_each(|f| {
+                self.manager.num_fmt_manager.get_id(&f.format_code);
+            });
+        }
+        if let Some(cell_xfs) = part.cell_xfs.as_ref().filter(|x| !x.xfs.is_empty()) {
+            self.manager.cell_xfs_manager.clear();
+            (0..cell_xfs.xfs.len()).for_each(|idx| {
+                self.load_xf(idx as u32);
+            });
         }
     }
 
@@ -60,13 +99,16 @@ impl<'a> StyleLoader<'a> {
             } else {
                 0
             };
+            // Built-in formats are not written in the stylesheet.
             let num_fmt_id = if let Some(idx) = xf.num_fmt_id {
-                if let Some(num_fmts) = &self.part.num_fmts {
-                    if let Some(f) = num_fmts.num_fmts.get(idx as usize) {
-                        self.manager.num_fmt_manager.get_id(&f.format_code)
-                    } else {
-                        0
-                    }
+                let custom =
+                    self.part.num_fmts.as_ref().and_then(|num_fmts| {
+                        num_fmts.num_fmts.iter().find(|f| f.num_fmt_id == idx)
+                    });
+                if let Some(f) = custom {
+                    self.manager.num_fmt_manager.get_id(&f.format_code)
+                } else if let Some(code) = get_builtin_num_fmt(idx).filter(|_| idx != 0) {
+                    self.manager.num_fmt_manager.get_id(&code.to_string())
                 } else {
                     0
                 }
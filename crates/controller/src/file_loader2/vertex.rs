@@ -5,7 +5,7 @@ use logisheets_base::{
     id_fetcher::{IdFetcherTrait, VertexFetcherTrait},
     index_fetcher::IndexFetcherTrait,
     CellId, ColId, Cube, ExtBookId, ExtRef, ExtRefId, FuncId, NameId, NormalRange, Range, RowId,
-    SheetId, TextId,
+    SheetId, Table, TextId,
 };
 use logisheets_parser::{ast, context::ContextTrait, Parser};
 
@@ -26,9 +26,35 @@ pub fn load_normal_formula<'a, 'b>(
             .range_manager
             .get_range_id(&sheet_id, &range);
 
-        let ast_node = parse_formula(formula_manager, connector, f);
+        let ast_node = parse_formula(formula_manager, connector, f, (row, col));
 
-        formula_manager.add_ast_node(sheet_id, cid, range_id, ast_node)
+        formula_manager.add_ast_node(sheet_id, cid, range_id, ast_node, connector)
+    }
+}
+
+/// Load the name of the workbook, or the one only used in the sheet of
+/// `scope`.
+pub fn load_defined_name<'a>(
+    formula_manager: &mut FormulaManager,
+    name: &str,
+    scope: Option<SheetId>,
+    f: &str,
+    connector: &'a mut VertexConnector<'a>,
+) {
+    let name_id = match scope {
+        Some(sheet_id) => connector
+            .name_id_manager
+            .get_local_id(&(sheet_id, name.to_string())),
+        None => connector.fetch_name_id(&None, name),
+    };
+    let parser = Parser {};
+    let mut context = Context {
+        formula_manager,
+        vertex_connector: connector,
+        curr_cell: None,
+    };
+    if let Some(ast_node) = parser.parse(f, &mut context) {
+        formula_manager.names.insert(name_id, ast_node);
     }
 }
 
@@ -36,11 +62,13 @@ fn parse_formula<'a: 'c, 'b, 'c>(
     formula_manager: &'b mut FormulaManager,
     connector: &'c mut VertexConnector<'a>,
     f: &str,
+    curr_cell: (usize, usize),
 ) -> ast::Node {
     let parser = Parser {};
     let mut context = Context {
         formula_manager,
         vertex_connector: connector,
+        curr_cell: Some(curr_cell),
     };
     parser.parse(f, &mut context).unwrap()
 }
@@ -57,7 +85,12 @@ pub fn load_shared_formulas<'a, 'b>(
     master_formula: &str,
     connector: &'a mut VertexConnector<'a>,
 ) {
-    let master_ast = parse_formula(formula_manager, connector, master_formula);
+    let master_ast = parse_formula(
+        formula_manager,
+        connector,
+        master_formula,
+        (master_row, master_col),
+    );
     for row in row_start..row_end + 1 {
         for col in col_start..col_end + 1 {
             let cid = connector.fetch_cell_id(&sheet_id, row, col).unwrap();
@@ -76,7 +109,7 @@ pub fn load_shared_formulas<'a, 'b>(
                 let range_id = formula_manager
                     .range_manager
                     .get_range_id(&sheet_id, &range);
-                formula_manager.add_ast_node(sheet_id, cid, range_id, n)
+                formula_manager.add_ast_node(sheet_id, cid, range_id, n, connector)
             } else {
                 unreachable!()
             }
@@ -131,7 +164,7 @@ fn shift_pure_node<'a, 'b>(
                 );
             });
         }
-        ast::PureNode::Value(_) => {}
+        ast::PureNode::Value(_) | ast::PureNode::Local(_) => {}
         ast::PureNode::Reference(cell_ref) => {
             shift_cell_reference(
                 formula_manager,
@@ -193,6 +226,8 @@ fn shift_cell_reference(
 struct Context<'a, 'b, 'c> {
     formula_manager: &'b mut FormulaManager,
     vertex_connector: &'a mut VertexConnector<'c>,
+    // The row and the column of the formula. It is none for defined names.
+    curr_cell: Option<(usize, usize)>,
 }
 
 impl<'a, 'b, 'c> IdFetcherTrait for Context<'a, 'b, 'c> {
@@ -222,6 +257,10 @@ impl<'a, 'b, 'c> IdFetcherTrait for Context<'a, 'b, 'c> {
         self.vertex_connector.fetch_name_id(workbook, name)
     }
 
+    fn fetch_local_name_id(&mut self, sheet_id: &SheetId, name: &str) -> Option<NameId> {
+        self.vertex_connector.fetch_local_name_id(sheet_id, name)
+    }
+
     fn fetch_ext_book_id(&mut self, book: &str) -> ExtBookId {
         self.vertex_connector.fetch_ext_book_id(book)
     }
@@ -261,6 +300,14 @@ impl<'a, 'b, 'c> VertexFetcherTrait for Context<'a, 'b, 'c> {
     fn fetch_ext_ref_id(&mut self, ext_ref: &ExtRef) -> ExtRefId {
         self.formula_manager.ext_ref_manager.get_ext_ref_id(ext_ref)
     }
+
+    fn fetch_table(&mut self, name: Option<&str>) -> Option<Table> {
+        let sheet_id = self.vertex_connector.get_active_sheet();
+        let curr_cell = self.curr_cell.map(|(row, col)| (sheet_id, row, col));
+        self.formula_manager
+            .table_manager
+            .fetch_table(name, curr_cell, self.vertex_connector)
+    }
 }
 
 impl<'a, 'b, 'c> ContextTrait for Context<'a, 'b, 'c> {}
@@ -0,0 +1,116 @@
+use logisheets_base::{Addr, SheetId};
+use logisheets_workbook::prelude::*;
+
+use super::utils::to_cell_ref;
+use crate::{
+    calc_engine::calculator::calc_vertex::Value, controller::status::Status,
+    ext_book_manager::ExtBook,
+};
+
+/// Save the external books in the order of their indexes in the formulas.
+/// The sheet names and the cached values of a book are kept.
+pub fn save_external_links(status: &Status) -> Vec<ExternalLink> {
+    let manager = &status.external_links_manager;
+    manager
+        .get_book_ids()
+        .into_iter()
+        .map(|book_id| {
+            let target = manager.fetch_book_name(&book_id).unwrap_or_default();
+            let (sheet_names, sheet_data_set) = match manager.books.get(&book_id) {
+                Some(book) => (save_sheet_names(book, status), save_sheet_data_set(book)),
+                None => (None, None),
+            };
+            let external_book = CtExternalBook {
+                sheet_names,
+                defined_names: None,
+                sheet_data_set,
+                id: String::from("rId1"),
+            };
+            ExternalLink {
+                external_link_part: ExternalLinkPart {
+                    external_book: Some(external_book),
+                },
+                target,
+            }
+        })
+        .collect()
+}
+
+fn save_sheet_names(book: &ExtBook, status: &Status) -> Option<CtExternalSheetNames> {
+    if book.sheets.is_empty() {
+        return None;
+    }
+    let names = book
+        .sheets
+        .iter()
+        .map(|sheet_id| CtExternalSheetName {
+            val: status
+                .sheet_id_manager
+                .get_string(sheet_id)
+                .unwrap_or_default(),
+        })
+        .collect();
+    Some(CtExternalSheetNames { names })
+}
+
+fn save_sheet_data_set(book: &ExtBook) -> Option<CtExternalSheetDataSet> {
+    if book.data_set.is_empty() {
+        return None;
+    }
+    let data = book
+        .sheets
+        .iter()
+        .enumerate()
+        .filter_map(|(idx, sheet_id)| {
+            let rows = save_rows(book, *sheet_id);
+            if rows.is_empty() {
+                return None;
+            }
+            Some(CtExternalSheetData {
+                rows,
+                sheet_id: idx as u32,
+                refresh_error: false,
+            })
+        })
+        .collect();
+    Some(CtExternalSheetDataSet { data })
+}
+
+fn save_rows(book: &ExtBook, sheet_id: SheetId) -> Vec<CtExternalRow> {
+    let mut cells = book
+        .data_set
+        .iter()
+        .filter(|((s, _), _)| *s == sheet_id)
+        .filter_map(|((_, addr), value)| {
+            let (t, v) = match value {
+                Value::Number(n) => (StCellType::N, n.to_string()),
+                Value::Text(s) => (StCellType::Str, s.clone()),
+                Value::Boolean(b) => (StCellType::B, String::from(if *b { "1" } else { "0" })),
+                Value::Error(e) => (StCellType::E, e.get_err_str().to_string()),
+                Value::Blank | Value::Date(_) => return None,
+            };
+            Some((*addr, t, v))
+        })
+        .collect::<Vec<_>>();
+    cells.sort_by_key(|(Addr { row, col }, _, _)| (*row, *col));
+    let mut rows = Vec::<CtExternalRow>::new();
+    cells.into_iter().for_each(|(addr, t, v)| {
+        let cell = CtExternalCell {
+            v: Some(PlainTextString {
+                value: v,
+                space: None,
+            }),
+            r: Some(to_cell_ref(addr.row, addr.col)),
+            t,
+            vm: 0,
+        };
+        match rows.last_mut() {
+            Some(row) if row.r == addr.row as u32 + 1 => row.cells.push(cell),
+            _ => rows.push(CtExternalRow {
+                cells: vec![cell],
+                r: addr.row as u32 + 1,
+            }),
+        }
+    });
+    rows
+}
@@ -0,0 +1,538 @@
+mod external_links;
+mod names;
+mod sheet;
+mod sst;
+mod styles;
+mod utils;
+
+use logisheets_workbook::prelude::*;
+use logisheets_workbook::workbook::{DocProps, Worksheet, Xl};
+use std::collections::{HashMap, HashSet};
+
+use crate::{
+    controller::Controller,
+    file_saver::{
+        external_links::save_external_links,
+        names::save_defined_names,
+        sheet::{save_cols, save_comments, save_merge_cells, save_sheet_data, save_tables},
+        sst::SstSaver,
+        styles::save_style_manager,
+    },
+    settings::{CalcMode, ReferenceStyle},
+};
+
+pub fn save(controller: &Controller) -> Workbook {
+    let status = &controller.status;
+    let settings = &controller.settings;
+    let passthrough = &settings.passthrough;
+    let mut store = passthrough.store.clone();
+    // Keep the ids of the unrecognized relationships since they may be
+    // referenced in the workbook part.
+    let used_rids = store
+        .workbook_relationships
+        .iter()
+        .map(|r| r.id.clone())
+        .collect::<HashSet<_>>();
+    let mut rid = 0_usize;
+    let mut next_rid = || loop {
+        rid += 1;
+        let id = format!("rId{}", rid);
+        if !used_rids.contains(&id) {
+            return id;
+        }
+    };
+    let mut navigator = status.navigator.clone();
+    let mut sst_saver = SstSaver::new(&status.text_id_manager);
+    let mut sheets = Vec::<CtSheet>::new();
+    let mut metadata = passthrough.metadata.clone().unwrap_or_default();
+    let dynamic_array_cm = metadata.get_or_add_dynamic_array_cm();
+    let mut has_dynamic_array = false;
+    let mut worksheets = HashMap::<String, Worksheet>::new();
+    // The table ids are unique in the workbook.
+    let mut next_table_id = passthrough.tables.values().map(|t| t.id).max().unwrap_or(0) + 1;
+    status
+        .sheet_pos_manager
+        .pos
+        .iter()
+        .enumerate()
+        .for_each(|(idx, sheet_id)| {
+            let sheet_id = *sheet_id;
+            let name = status
+                .sheet_id_manager
+                .get_string(&sheet_id)
+                .unwrap_or(format!("Sheet{}", idx + 1));
+            let rid = next_rid();
+            let state = if status.sheet_pos_manager.hiddens.contains(&sheet_id) {
+                StSheetState::Hidden
+            } else {
+                StSheetState::Visible
+            };
+            sheets.push(CtSheet {
+                name,
+                sheet_id: idx as u32 + 1,
+                state,
+                id: rid.clone(),
+            });
+            let sheet_data = save_sheet_data(
+                sheet_id,
+                status,
+                &mut navigator,
+                &mut sst_saver,
+                dynamic_array_cm,
+            );
+            has_dynamic_array = has_dynamic_array
+                || sheet_data
+                    .rows
+                    .iter()
+                    .flat_map(|r| r.cells.iter())
+                    .any(|c| c.cm == dynamic_array_cm);
+            let mut worksheet_part = new_worksheet_part(
+                sheet_data,
+                save_cols(sheet_id, &status.container, &mut navigator),
+                save_merge_cells(sheet_id, &mut navigator, &status.cell_attachment_manager),
+                settings.sheet_format_pr.get(&sheet_id).cloned(),
+            );
+            if let Some(p) = passthrough.sheets.get(&sheet_id) {
+                worksheet_part.sheet_pr = p.sheet_pr.clone();
+                worksheet_part.sheet_views = p.sheet_views.clone();
+                worksheet_part.sheet_protection = p.sheet_protection.clone();
+                worksheet_part.auto_filter = p.auto_filter.clone();
+                worksheet_part.conditional_formatting = p.conditional_formatting.clone();
+                worksheet_part.data_validations = p.data_validations.clone();
+                worksheet_part.hyperlinks = p.hyperlinks.clone();
+                worksheet_part.print_options = p.print_options.clone();
+                worksheet_part.page_margins = p.page_margins.clone();
+                worksheet_part.page_setup = p.page_setup.clone();
+                worksheet_part.header_footer = p.header_footer.clone();
+                worksheet_part.drawing = p.drawing.clone();
+                worksheet_part.legacy_drawing = p.legacy_drawing.clone();
+                worksheet_part.legacy_drawing_hf = p.legacy_drawing_hf.clone();
+                worksheet_part.picture = p.picture.clone();
+                if !p.relationships.is_empty() {
+                    store
+                        .worksheet_relationships
+                        .insert(rid.clone(), p.relationships.clone());
+                }
+            }
+            let comments = save_comments(sheet_id, &mut navigator, &status.cell_attachment_manager);
+            let tables = save_tables(
+                sheet_id,
+                status,
+                &mut navigator,
+                &passthrough.tables,
+                &mut next_table_id,
+            );
+            worksheets.insert(
+                rid,
+                Worksheet {
+                    worksheet_part,
+                    comments,
+                    tables,
+                },
+            );
+        });
+    let mut stylesheet = save_style_manager(&status.style_manager);
+    stylesheet.dxfs = passthrough.dxfs.clone();
+    stylesheet.table_styles = passthrough.table_styles.clone();
+    stylesheet.colors = passthrough.colors.clone();
+    let styles = (next_rid(), stylesheet);
+    let theme = settings.theme.get_theme().map(|t| (next_rid(), t.clone()));
+    let sst = sst_saver.into_part().map(|sst| (next_rid(), sst));
+    // The metadata part is written if it is loaded or any array spills.
+    let metadata = if passthrough.metadata.is_some() || has_dynamic_array {
+        Some((next_rid(), metadata))
+    } else {
+        None
+    };
+    let mut external_links = HashMap::<String, ExternalLink>::new();
+    let external_references = save_external_links(status)
+        .into_iter()
+        .map(|link| {
+            let id = next_rid();
+            external_links.insert(id.clone(), link);
+            CtExternalReference { id }
+        })
+        .collect::<Vec<_>>();
+    let external_references = if external_references.is_empty() {
+        None
+    } else {
+        Some(CtExternalReferences {
+            external_references,
+        })
+    };
+    let calc_config = &settings.calc_config;
+    let calc_pr = CtCalcPr {
+        // Let the spreadsheet applications recalculate the values.
+        calc_id: 0,
+        calc_mode: match calc_config.calc_mode {
+            CalcMode::Auto => StCalcMode::Auto,
+            CalcMode::AutoNoTable => StCalcMode::AutoNoTable,
+            CalcMode::Manual => StCalcMode::Manual,
+        },
+        full_calc_on_load: false,
+        ref_mode: match settings.reference_style {
+            ReferenceStyle::A1 => StRefMode::A1,
+            ReferenceStyle::R1C1 => StRefMode::R1C1,
+        },
+        iterate: calc_config.iterate,
+        iterate_count: calc_config.iter_limit as u32,
+        iterate_delta: calc_config.error as f64,
+        full_precision: true,
+        calc_completed: true,
+        calc_on_save: true,
+        concurrent_calc: true,
+        concurrent_manual_calc: true,
+        force_full_calc: None,
+    };
+    let workbook_pr = match &passthrough.workbook_pr {
+        Some(workbook_pr) => Some(CtWorkbookPr {
+            date1904: settings.date1904,
+            ..workbook_pr.clone()
+        }),
+        None if settings.date1904 => Some(CtWorkbookPr {
+            date1904: true,
+            show_objects: StObjects::All,
+            show_border_unselected_tables: true,
+            filter_privacy: false,
+            prompted_solutions: false,
+            show_ink_annotation: true,
+            backup_file: false,
+            save_external_link_values: true,
+            update_links: StUpdateLinks::UserSet,
+            code_name: None,
+            hide_pivot_field_list: false,
+            show_pivot_chart_filter: false,
+            allow_refresh_query: false,
+            publish_items: false,
+            check_compatibility: false,
+            auto_compress_pictures: true,
+            refresh_all_connections: true,
+            default_theme_version: None,
+        }),
+        None => None,
+    };
+    // The active sheet may have been deleted.
+    let last_sheet = sheets.len().saturating_sub(1) as u32;
+    let book_views = passthrough.book_views.clone().map(|mut book_views| {
+        book_views.views.iter_mut().for_each(|v| {
+            v.active_tab = v.active_tab.min(last_sheet);
+            v.first_sheet = v.first_sheet.min(last_sheet);
+        });
+        book_views
+    });
+    let defined_names = save_defined_names(status, &mut navigator, &passthrough.defined_names);
+    let workbook_part = WorkbookPart {
+        file_version: None,
+        file_sharing: None,
+        workbook_pr,
+        workbook_protection: None,
+        book_views,
+        sheets: CtSheets { sheets },
+        function_groups: None,
+        external_references,
+        defined_names,
+        calc_pr: Some(calc_pr),
+        ole_size: None,
+        custom_workbook_views: None,
+        pivot_caches: passthrough.pivot_caches.clone(),
+        smart_tag_pr: None,
+        smart_tag_types: None,
+        web_publishing: None,
+        file_recovery_pr: None,
+        web_publish_objects: None,
+        conformance: None,
+    };
+    Workbook {
+        xl: Xl {
+            workbook_part,
+            styles,
+            sst,
+            worksheets,
+            external_links,
+            theme,
+            metadata,
+        },
+        doc_props: DocProps::default(),
+        passthrough: store,
+    }
+}
+
+fn new_worksheet_part(
+    sheet_data: CtSheetData,
+    cols: Option<CtCols>,
+    merge_cells: Option<CtMergeCells>,
+    sheet_format_pr: Option<CtSheetFormatPr>,
+) -> WorksheetPart {
+    WorksheetPart {
+        sheet_pr: None,
+        dimension: None,
+        sheet_views: None,
+        sheet_format_pr,
+        cols,
+        sheet_data,
+        sheet_calc_pr: None,
+        sheet_protection: None,
+        protected_ranges: None,
+        scenarios: None,
+        auto_filter: None,
+        sort_state: None,
+        data_consolidate: None,
+        custom_sheet_views: None,
+        merge_cells,
+        phonetic_pr: None,
+        conditional_formatting: vec![],
+        data_validations: None,
+        hyperlinks: None,
+        print_options: None,
+        page_margins: None,
+        page_setup: None,
+        header_footer: None,
+        row_breaks: None,
+        col_breaks: None,
+        custom_properties: None,
+        cell_watches: None,
+        ignored_errors: None,
+        smart_tags: None,
+        drawing: None,
+        legacy_drawing: None,
+        legacy_drawing_hf: None,
+        drawing_hf: None,
+        picture: None,
+        controls: None,
+        web_publish_items: None,
+        table_parts: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::controller::edit_action::{
+        ColShift, EditAction, EditPayload, PayloadsAction, RowShift,
+    };
+    use crate::controller::Controller;
+    use crate::test_utils::{get_value, input};
+    use logisheets_base::CellValue;
+
+    #[test]
+    fn save_test() {
+        use std::fs;
+        let buf = fs::read("../../tests/6.xlsx").unwrap();
+        let controller = Controller::from_file(String::from("6"), &buf).unwrap();
+        let saved = controller.save().unwrap();
+        let reloaded = Controller::from_file(String::from("6"), &saved).unwrap();
+        assert_eq!(
+            controller.status.sheet_pos_manager.pos,
+            reloaded.status.sheet_pos_manager.pos
+        );
+        assert_eq!(
+            controller.status.formula_manager.formulas.len(),
+            reloaded.status.formula_manager.formulas.len()
+        );
+        let cell_cnt = |c: &Controller| {
+            c.status
+                .container
+                .data
+                .values()
+                .map(|d| d.cells.len())
+                .sum::<usize>()
+        };
+        assert_eq!(cell_cnt(&controller), cell_cnt(&reloaded));
+    }
+
+    #[test]
+    fn save_defined_names_test() {
+        use std::fs;
+        let buf = fs::read("../../tests/calc_test.xlsx").unwrap();
+        let mut wb = Controller::from_file(String::from("calc_test"), &buf).unwrap();
+        let sheet_id = wb.get_sheet_id_by_name("datebase").unwrap();
+        let sheet_idx = wb
+            .status
+            .sheet_pos_manager
+            .get_sheet_idx(&sheet_id)
+            .unwrap();
+        wb.handle_action(EditAction::Payloads(PayloadsAction {
+            payloads: vec![EditPayload::RowShift(RowShift {
+                sheet_idx,
+                row: 0,
+                count: 1,
+                insert: true,
+            })],
+            undoable: true,
+        }));
+        let book = super::save(&wb);
+        let names = book.xl.workbook_part.defined_names.unwrap().names;
+        assert_eq!(names.len(), 1);
+        assert_eq!(names[0].name, "星球名称");
+        assert_eq!(names[0].value, "datebase!$B$4:$B$15");
+    }
+
+    #[test]
+    fn save_array_formula_test() {
+        use logisheets_workbook::prelude::StCellFormulaType;
+        let mut wb = Controller::default();
+        input(&mut wb, 0, 0, 0, "1");
+        input(&mut wb, 0, 1, 0, "2");
+        input(&mut wb, 0, 2, 0, "3");
+        input(&mut wb, 0, 0, 1, "=UNIQUE(A1:A3)");
+        input(&mut wb, 0, 0, 2, "=SUM(B1#)");
+        input(&mut wb, 0, 0, 3, "=_xlfn._xlws.SORT(A1:A3)");
+        input(&mut wb, 0, 0, 4, "=BYROW(A1:A3, LAMBDA(r, SUM(r)))");
+        let mut book = super::save(&wb);
+        let cm = book.xl.metadata.as_ref().unwrap().1.get_dynamic_array_cm();
+        let worksheet = book.xl.worksheets.values_mut().next().unwrap();
+        let cells = &mut worksheet.worksheet_part.sheet_data.rows[0].cells;
+        let f = cells[1].f.as_ref().unwrap();
+        assert_eq!(f.formula.as_ref().unwrap(), "_xlfn.UNIQUE(A1:A3)");
+        assert_eq!(f.t, StCellFormulaType::Array);
+        assert_eq!(Some(cells[1].cm), cm);
+        let f = cells[2].f.as_ref().unwrap();
+        assert_eq!(f.formula.as_ref().unwrap(), "SUM(_xlfn.ANCHORARRAY(B1))");
+        let f = cells[3].f.as_ref().unwrap();
+        assert_eq!(f.formula.as_ref().unwrap(), "_xlfn._xlws.SORT(A1:A3)");
+        let f = cells[4].f.as_ref().unwrap();
+        assert_eq!(
+            f.formula.as_ref().unwrap(),
+            "_xlfn.BYROW(A1:A3, _xlfn.LAMBDA(_xlpm.r, SUM(_xlpm.r)))"
+        );
+
+        // Without the dynamic array metadata, it is a legacy array formula
+        // whose area is fixed.
+        cells[1].cm = 0;
+        let buf = logisheets_workbook::writer::write(book).unwrap();
+        let mut reloaded = Controller::from_file(String::from("array"), &buf).unwrap();
+        input(&mut reloaded, 0, 2, 0, "2");
+        assert!(matches!(
+            get_value(&mut reloaded, 0, 2, 1),
+            CellValue::Error(_)
+        ));
+
+        let buf = wb.save().unwrap();
+        let mut reloaded = Controller::from_file(String::from("array"), &buf).unwrap();
+        input(&mut reloaded, 0, 2, 0, "2");
+        assert!(matches!(
+            get_value(&mut reloaded, 0, 2, 1),
+            CellValue::Blank
+        ));
+    }
+
+    #[test]
+    fn save_external_link_test() {
+        use crate::Workbook;
+        let mut wb = Controller::default();
+        input(&mut wb, 0, 0, 0, "=[Book2]Sheet1!A1");
+        input(&mut wb, 0, 1, 0, "=[Book3]Data!B2");
+        let book = super::save(&wb);
+        let worksheet = book.xl.worksheets.values().next().unwrap();
+        let rows = &worksheet.worksheet_part.sheet_data.rows;
+        let f = rows[0].cells[0].f.as_ref().unwrap();
+        assert_eq!(f.formula.as_ref().unwrap(), "[1]Sheet1!A1");
+        let f = rows[1].cells[0].f.as_ref().unwrap();
+        assert_eq!(f.formula.as_ref().unwrap(), "[2]Data!B2");
+        let references = book.xl.workbook_part.external_references.as_ref().unwrap();
+        let targets = references
+            .external_references
+            .iter()
+            .map(|r| book.xl.external_links.get(&r.id).unwrap().target.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(targets, vec!["Book2", "Book3"]);
+
+        let buf = logisheets_workbook::writer::write(book).unwrap();
+        let reloaded = Controller::from_file(String::from("external"), &buf).unwrap();
+        let mut book = Workbook {
+            controller: reloaded,
+        };
+        let mut sheet = book.get_sheet_by_idx(0).unwrap();
+        assert_eq!(sheet.get_formula(0, 0).unwrap(), "[Book2]Sheet1!A1");
+        assert_eq!(sheet.get_formula(1, 0).unwrap(), "[Book3]Data!B2");
+    }
+
+    #[test]
+    fn save_table_test() {
+        use crate::connectors::IndexFetcher;
+        use crate::table_manager::TableDef;
+        let mut wb = Controller::default();
+        let payload = |p: EditPayload| {
+            EditAction::Payloads(PayloadsAction {
+                payloads: vec![p],
+                undoable: true,
+            })
+        };
+        let sheet_id = wb.get_sheet_id_by_idx(0).unwrap();
+        let navigator = &mut wb.status.navigator;
+        let start = navigator.fetch_cell_id(&sheet_id, 0, 0).unwrap();
+        let end = navigator.fetch_cell_id(&sheet_id, 4, 1).unwrap();
+        wb.status.formula_manager.table_manager.add_table(TableDef {
+            name: String::from("Table1"),
+            sheet_id,
+            start,
+            end,
+            header_row_count: 1,
+            totals_row_count: 1,
+            columns: vec![String::from("Item"), String::from("Price")],
+        });
+        wb.handle_action(payload(EditPayload::RowShift(RowShift {
+            sheet_idx: 0,
+            row: 2,
+            count: 1,
+            insert: true,
+        })));
+        wb.handle_action(payload(EditPayload::ColShift(ColShift {
+            sheet_idx: 0,
+            col: 1,
+            count: 2,
+            insert: true,
+        })));
+        // Inserting before the table only moves it.
+        wb.handle_action(payload(EditPayload::ColShift(ColShift {
+            sheet_idx: 0,
+            col: 0,
+            count: 1,
+            insert: true,
+        })));
+
+        let book = super::save(&wb);
+        let tables = &book.xl.worksheets.values().next().unwrap().tables;
+        assert_eq!(tables.len(), 1);
+        let table = &tables[0];
+        assert_eq!(table.display_name, "Table1");
+        assert_eq!(table.reference, "B1:E6");
+        assert_eq!(table.auto_filter.as_ref().unwrap().reference, "B1:E5");
+        let columns = &table.table_columns.table_columns;
+        let names = columns.iter().map(|c| c.name.as_str()).collect::<Vec<_>>();
+        assert_eq!(names, vec!["Item", "Column1", "Column2", "Price"]);
+        assert_eq!(table.table_columns.count, 4);
+
+        let buf = logisheets_workbook::writer::write(book).unwrap();
+        let mut reloaded = Controller::from_file(String::from("table"), &buf).unwrap();
+        let sheet_id = reloaded.get_sheet_id_by_idx(0).unwrap();
+        let table_manager = &reloaded.status.formula_manager.table_manager;
+        let id = table_manager.get_table_id("Table1").unwrap();
+        let table = table_manager
+            .get_table(
+                &id,
+                &mut IndexFetcher {
+                    navigator: &mut reloaded.status.navigator,
+                    sheet_pos_manager: &reloaded.status.sheet_pos_manager,
+                },
+            )
+            .unwrap();
+        assert_eq!(table.sheet_id, sheet_id);
+        assert_eq!((table.start.row, table.start.col), (0, 1));
+        assert_eq!((table.end.row, table.end.col), (5, 4));
+        assert_eq!(table.totals_row_count, 1);
+
+        // The names of the deleted columns are removed.
+        reloaded.handle_action(payload(EditPayload::ColShift(ColShift {
+            sheet_idx: 0,
+            col: 2,
+            count: 2,
+            insert: false,
+        })));
+        let book = super::save(&reloaded);
+        let table = &book.xl.worksheets.values().next().unwrap().tables[0];
+        assert_eq!(table.reference, "B1:C6");
+        let columns = &table.table_columns.table_columns;
+        let names = columns.iter().map(|c| c.name.as_str()).collect::<Vec<_>>();
+        assert_eq!(names, vec!["Item", "Price"]);
+    }
+}
@@ -0,0 +1,101 @@
+use logisheets_astchecker::get_func_prefix;
+use logisheets_base::{ExtBookId, SheetId};
+use logisheets_parser::unparse;
+use logisheets_workbook::prelude::*;
+
+use crate::{connectors::NameFetcher, controller::status::Status, navigator::Navigator};
+
+// A defined name does not belong to any sheet, so the references in it are
+// printed with their sheet names.
+const NO_SHEET: SheetId = SheetId::MAX;
+
+/// Save the names in the formula manager, including the ones only used in
+/// a sheet. The attributes of a loaded name, like `hidden`, are kept, and
+/// the names that could not be parsed are saved as they are loaded.
+pub fn save_defined_names(
+    status: &Status,
+    navigator: &mut Navigator,
+    loaded: &Option<CtDefinedNames>,
+) -> Option<CtDefinedNames> {
+    let loaded = loaded.as_ref().map(|n| n.names.as_slice()).unwrap_or(&[]);
+    let name_id_manager = &status.name_id_manager;
+    let global_names = name_id_manager
+        .ids
+        .iter()
+        .filter(|((book_id, _), _)| *book_id == 0 as ExtBookId)
+        .map(|((_, name), name_id)| (name, None, name_id));
+    // The names of the deleted sheets are gone.
+    let local_names = name_id_manager
+        .local_ids
+        .iter()
+        .filter_map(|((sheet_id, name), name_id)| {
+            let idx = status.sheet_pos_manager.get_sheet_idx(sheet_id)?;
+            Some((name, Some(idx as u32), name_id))
+        });
+    let mut names = global_names
+        .chain(local_names)
+        .filter_map(|(name, local_sheet_id, name_id)| {
+            let node = status.formula_manager.names.get(name_id)?;
+            let mut name_fetcher = NameFetcher {
+                func_manager: &status.func_id_manager,
+                sheet_id_manager: &status.sheet_id_manager,
+                external_links_manager: &status.external_links_manager,
+                text_id_manager: &status.text_id_manager,
+                name_id_manager,
+                navigator,
+                formula_manager: &status.formula_manager,
+            };
+            let value =
+                unparse::unparse_for_file(node, &mut name_fetcher, NO_SHEET, get_func_prefix);
+            let mut defined_name = loaded
+                .iter()
+                .find(|n| n.local_sheet_id == local_sheet_id && n.name.eq_ignore_ascii_case(name))
+                .cloned()
+                .unwrap_or_else(|| new_defined_name(name.clone()));
+            defined_name.value = value;
+            defined_name.local_sheet_id = local_sheet_id;
+            Some(defined_name)
+        })
+        .collect::<Vec<_>>();
+    loaded.iter().for_each(|n| {
+        let parsed = match n.local_sheet_id {
+            Some(_) => name_id_manager
+                .local_ids
+                .keys()
+                .any(|(_, name)| name.eq_ignore_ascii_case(&n.name)),
+            None => false,
+        };
+        let saved = names
+            .iter()
+            .any(|s| s.local_sheet_id == n.local_sheet_id && s.name.eq_ignore_ascii_case(&n.name));
+        if !saved && !parsed {
+            names.push(n.clone());
+        }
+    });
+    if names.is_empty() {
+        return None;
+    }
+    names.sort_by_key(|n| (n.name.to_lowercase(), n.local_sheet_id));
+    Some(CtDefinedNames { names })
+}
+
+fn new_defined_name(name: String) -> CtDefinedName {
+    CtDefinedName {
+        value: String::new(),
+        name,
+        comment: None,
+        comment_menu: None,
+        description: None,
+        help: None,
+        status_bar: None,
+        local_sheet_id: None,
+        hidden: false,
+        function: false,
+        vb_procedure: false,
+        xlm: false,
+        function_group_id: None,
+        shortcut_key: None,
+        publish_to_server: false,
+        workbook_parameter: false,
+    }
+}
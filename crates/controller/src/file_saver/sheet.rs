@@ -0,0 +1,525 @@
+use logisheets_astchecker::get_func_prefix;
+use logisheets_base::{CellId, CellValue, SheetId, TableId};
+use logisheets_parser::unparse;
+use logisheets_workbook::prelude::*;
+use std::collections::{BTreeMap, HashMap};
+
+use crate::{
+    cell::Cell,
+    cell_attachments::CellAttachmentsManager,
+    connectors::{IndexFetcher, NameFetcher},
+    container::{col_info_manager::ColInfo, row_info_manager::RowInfo, DataContainer, Spill},
+    controller::status::Status,
+    navigator::Navigator,
+};
+
+use super::{
+    sst::{plain_text, SstSaver},
+    utils::{to_cell_ref, to_range_ref},
+};
+
+pub fn save_cols(
+    sheet_id: SheetId,
+    container: &DataContainer,
+    navigator: &mut Navigator,
+) -> Option<CtCols> {
+    let sheet_data = container.data.get(&sheet_id)?;
+    let mut cols = sheet_data
+        .col_info
+        .get_all_col_info()
+        .into_iter()
+        .filter_map(|(col_id, info)| {
+            let idx = navigator.fetch_col_idx(&sheet_id, &col_id).ok()?;
+            Some(to_ct_col(idx as u32 + 1, info))
+        })
+        .collect::<Vec<_>>();
+    if cols.is_empty() {
+        return None;
+    }
+    cols.sort_by_key(|c| c.min);
+    // Adjacent columns with the same settings are saved as one, as they are
+    // loaded.
+    let cols = cols.into_iter().fold(Vec::<CtCol>::new(), |mut prev, c| {
+        match prev.last_mut() {
+            Some(last) if last.max + 1 == c.min && same_col_settings(last, &c) => {
+                last.max = c.max;
+            }
+            _ => prev.push(c),
+        }
+        prev
+    });
+    Some(CtCols { cols })
+}
+
+fn same_col_settings(c1: &CtCol, c2: &CtCol) -> bool {
+    c1.width == c2.width
+        && c1.style == c2.style
+        && c1.hidden == c2.hidden
+        && c1.best_fit == c2.best_fit
+        && c1.custom_width == c2.custom_width
+        && c1.outline_level == c2.outline_level
+        && c1.collapsed == c2.collapsed
+}
+
+pub fn save_merge_cells(
+    sheet_id: SheetId,
+    navigator: &mut Navigator,
+    cell_attachment_manager: &CellAttachmentsManager,
+) -> Option<CtMergeCells> {
+    let merge_cells = cell_attachment_manager.merge_cells.data.get(&sheet_id)?;
+    let mut refs = merge_cells
+        .iter()
+        .filter_map(|(start, end)| {
+            let start = navigator.fetch_normal_cell_idx(&sheet_id, start).ok()?;
+            let end = navigator.fetch_normal_cell_idx(&sheet_id, end).ok()?;
+            Some((start, end))
+        })
+        .collect::<Vec<_>>();
+    if refs.is_empty() {
+        return None;
+    }
+    refs.sort();
+    let merge_cells = refs
+        .into_iter()
+        .map(|(start, end)| CtMergeCell {
+            reference: to_range_ref(start, end),
+        })
+        .collect::<Vec<_>>();
+    Some(CtMergeCells {
+        count: merge_cells.len() as u32,
+        merge_cells,
+    })
+}
+
+pub fn save_comments(
+    sheet_id: SheetId,
+    navigator: &mut Navigator,
+    cell_attachment_manager: &CellAttachmentsManager,
+) -> Option<Comments> {
+    let comments = &cell_attachment_manager.comments;
+    let sheet_comments = comments.data.get(&sheet_id)?;
+    let mut list = sheet_comments
+        .comments
+        .iter()
+        .filter_map(|(cell_id, c)| {
+            let (row, col) = navigator.fetch_cell_idx(&sheet_id, cell_id).ok()?;
+            Some(((row, col), c))
+        })
+        .collect::<Vec<_>>();
+    if list.is_empty() {
+        return None;
+    }
+    list.sort_by_key(|(pos, _)| *pos);
+    // Author ids are allocated from 0 and therefore they can be used as
+    // the indexes directly.
+    let mut authors = comments
+        .authors
+        .ids
+        .iter()
+        .map(|(name, id)| (*id, name))
+        .collect::<Vec<_>>();
+    authors.sort_by_key(|(id, _)| *id);
+    let authors = authors
+        .into_iter()
+        .map(|(_, name)| plain_text(name))
+        .collect::<Vec<_>>();
+    let comments = list
+        .into_iter()
+        .map(|((row, col), c)| CtComment {
+            text: CtRst {
+                t: Some(plain_text(&c.text)),
+                r: vec![],
+                r_ph: vec![],
+                phonetic_pr: None,
+            },
+            comment_pr: None,
+            reference: to_cell_ref(row, col),
+            author_id: c.author as u32,
+            shape_id: None,
+            guid: None,
+        })
+        .collect::<Vec<_>>();
+    Some(Comments {
+        authors: CtAuthors { authors },
+        comment_list: CtCommentList { comments },
+    })
+}
+
+/// Build the table parts from the table manager. The loaded parts are kept
+/// for the attributes we do not handle, while the ranges and the columns are
+/// the current ones.
+pub fn save_tables(
+    sheet_id: SheetId,
+    status: &Status,
+    navigator: &mut Navigator,
+    loaded: &HashMap<TableId, TablePart>,
+    next_table_id: &mut u32,
+) -> Vec<TablePart> {
+    let table_manager = &status.formula_manager.table_manager;
+    let mut fetcher = IndexFetcher {
+        navigator,
+        sheet_pos_manager: &status.sheet_pos_manager,
+    };
+    table_manager
+        .get_sheet_tables(sheet_id)
+        .into_iter()
+        .filter_map(|id| {
+            let table = table_manager.get_table(&id, &mut fetcher)?;
+            let name = table_manager.get_table_name(&id)?;
+            let reference = to_range_ref(
+                (table.start.row, table.start.col),
+                (table.end.row, table.end.col),
+            );
+            let filter_end_row = table.end.row.saturating_sub(table.totals_row_count);
+            let filter_ref = to_range_ref(
+                (table.start.row, table.start.col),
+                (filter_end_row.max(table.start.row), table.end.col),
+            );
+            let mut part = match loaded.get(&id) {
+                Some(p) => p.clone(),
+                None => {
+                    let id = *next_table_id;
+                    *next_table_id += 1;
+                    new_table_part(id, &name)
+                }
+            };
+            let old_columns = std::mem::take(&mut part.table_columns.table_columns);
+            let mut next_column_id = old_columns.iter().map(|c| c.id).max().unwrap_or(0) + 1;
+            let columns = table
+                .columns
+                .iter()
+                .map(|name| {
+                    match old_columns
+                        .iter()
+                        .find(|c| c.name.eq_ignore_ascii_case(name))
+                    {
+                        Some(c) => c.clone(),
+                        None => {
+                            let id = next_column_id;
+                            next_column_id += 1;
+                            new_table_column(id, name)
+                        }
+                    }
+                })
+                .collect::<Vec<_>>();
+            // The filter columns are referred to by their positions.
+            let auto_filter = part.auto_filter.take().map(|mut f| {
+                f.filter_columns = f
+                    .filter_columns
+                    .into_iter()
+                    .filter_map(|mut c| {
+                        let name = &old_columns.get(c.col_id as usize)?.name;
+                        let pos = columns.iter().position(|col| &col.name == name)?;
+                        c.col_id = pos as u32;
+                        Some(c)
+                    })
+                    .collect();
+                // The sort conditions refer to the old range.
+                if f.reference != filter_ref {
+                    f.sort_state = None;
+                }
+                f.reference = filter_ref.clone();
+                f
+            });
+            part.auto_filter = if table.header_row_count > 0 {
+                Some(auto_filter.unwrap_or(CtAutoFilter {
+                    filter_columns: vec![],
+                    sort_state: None,
+                    reference: filter_ref,
+                }))
+            } else {
+                None
+            };
+            part.display_name = name;
+            part.reference = reference;
+            part.header_row_count = table.header_row_count as u32;
+            part.totals_row_count = table.totals_row_count as u32;
+            part.table_columns = CtTableColumns {
+                count: columns.len() as u32,
+                table_columns: columns,
+            };
+            Some(part)
+        })
+        .collect()
+}
+
+fn new_table_part(id: u32, name: &str) -> TablePart {
+    TablePart {
+        auto_filter: None,
+        table_columns: CtTableColumns {
+            table_columns: vec![],
+            count: 0,
+        },
+        table_style_info: None,
+        id,
+        name: Some(name.to_string()),
+        display_name: name.to_string(),
+        reference: String::new(),
+        header_row_count: 1,
+        totals_row_count: 0,
+        totals_row_shown: true,
+    }
+}
+
+fn new_table_column(id: u32, name: &str) -> CtTableColumn {
+    CtTableColumn {
+        calculated_column_formula: None,
+        totals_row_formula: None,
+        id,
+        name: name.to_string(),
+        totals_row_function: None,
+        totals_row_label: None,
+        data_dxf_id: None,
+    }
+}
+
+pub fn save_sheet_data(
+    sheet_id: SheetId,
+    status: &Status,
+    navigator: &mut Navigator,
+    sst_saver: &mut SstSaver,
+    dynamic_array_cm: u32,
+) -> CtSheetData {
+    let mut rows = BTreeMap::<usize, CtRow>::new();
+    let sheet_data = match status.container.data.get(&sheet_id) {
+        Some(d) => d,
+        None => return CtSheetData { rows: vec![] },
+    };
+    sheet_data
+        .row_info
+        .get_all_row_info()
+        .into_iter()
+        .for_each(|(row_id, info)| {
+            if let Ok(idx) = navigator.fetch_row_idx(&sheet_id, &row_id) {
+                rows.insert(idx, new_ct_row(idx, Some(info)));
+            }
+        });
+    // Sort the cells by their positions so that the shared string table is
+    // built in a stable order.
+    let mut cells = BTreeMap::<(usize, usize), (&Cell, Option<String>)>::new();
+    sheet_data.cells.iter().for_each(|(cell_id, cell)| {
+        let (row, col) = match navigator.fetch_cell_idx(&sheet_id, cell_id) {
+            Ok(idx) => idx,
+            Err(_) => return,
+        };
+        let formula = match status.formula_manager.formulas.get(&(sheet_id, *cell_id)) {
+            Some(node) => {
+                let mut name_fetcher = NameFetcher {
+                    func_manager: &status.func_id_manager,
+                    sheet_id_manager: &status.sheet_id_manager,
+                    external_links_manager: &status.external_links_manager,
+                    text_id_manager: &status.text_id_manager,
+                    name_id_manager: &status.name_id_manager,
+                    navigator,
+                    formula_manager: &status.formula_manager,
+                };
+                Some(unparse::unparse_for_file(
+                    node,
+                    &mut name_fetcher,
+                    sheet_id,
+                    get_func_prefix,
+                ))
+            }
+            None => None,
+        };
+        cells.insert((row, col), (cell, formula));
+    });
+    let mut spills = HashMap::<(usize, usize), &Spill>::new();
+    sheet_data.spills.iter().for_each(|(cell_id, spill)| {
+        if let Ok(idx) = navigator.fetch_cell_idx(&sheet_id, cell_id) {
+            spills.insert(idx, spill);
+        }
+    });
+    let shared = get_shared_formulas(sheet_id, status, navigator);
+    cells.into_iter().for_each(|((row, col), (cell, formula))| {
+        let mut ct_cell = to_ct_cell(row, col, cell, formula, sst_saver);
+        if let (Some(f), Some(spill)) = (&mut ct_cell.f, spills.get(&(row, col))) {
+            // Save it as an array formula. A blocked one only covers its own
+            // cell. The dynamic ones are marked by the cell metadata.
+            let (rows, cols) = if spill.blocked {
+                (1, 1)
+            } else {
+                (spill.rows, spill.cols)
+            };
+            f.t = StCellFormulaType::Array;
+            f.reference = Some(to_range_ref((row, col), (row + rows - 1, col + cols - 1)));
+            if !spill.cse {
+                ct_cell.cm = dynamic_array_cm;
+            }
+        } else if let (Some(f), Some((si, reference))) = (&mut ct_cell.f, shared.get(&(row, col))) {
+            // Only the master cell has the formula and the area.
+            f.t = StCellFormulaType::Shared;
+            f.si = Some(*si);
+            f.reference = reference.clone();
+            if reference.is_none() {
+                f.formula = None;
+            }
+        }
+        rows.entry(row)
+            .or_insert_with(|| new_ct_row(row, None))
+            .cells
+            .push(ct_cell)
+    });
+    CtSheetData {
+        rows: rows.into_values().collect(),
+    }
+}
+
+// Get the shared formulas loaded from the file whose cells still have the
+// formula of their master cells, by the positions of the cells. The indexes
+// are given in the order of the master cells, and only the master cells have
+// the areas.
+fn get_shared_formulas(
+    sheet_id: SheetId,
+    status: &Status,
+    navigator: &mut Navigator,
+) -> HashMap<(usize, usize), (u32, Option<String>)> {
+    let sheet_data = match status.container.data.get(&sheet_id) {
+        Some(d) => d,
+        None => return HashMap::new(),
+    };
+    let mut groups = sheet_data
+        .shared_formulas
+        .iter()
+        .filter(|(master, _)| !sheet_data.spills.contains_key(*master))
+        .filter_map(|(master, cells)| {
+            let (start, formula) = get_r1c1_formula(sheet_id, master, status, navigator)?;
+            let cells = cells
+                .iter()
+                .filter(|c| !sheet_data.spills.contains_key(*c))
+                .filter_map(|c| get_r1c1_formula(sheet_id, c, status, navigator))
+                .filter(|((row, col), f)| *row >= start.0 && *col >= start.1 && f == &formula)
+                .map(|(pos, _)| pos)
+                .collect::<Vec<_>>();
+            if cells.is_empty() {
+                None
+            } else {
+                Some((start, cells))
+            }
+        })
+        .collect::<Vec<_>>();
+    groups.sort();
+    let mut result = HashMap::new();
+    groups
+        .into_iter()
+        .enumerate()
+        .for_each(|(si, (start, cells))| {
+            let end = cells
+                .iter()
+                .fold(start, |(row, col), (r, c)| (row.max(*r), col.max(*c)));
+            result.insert(start, (si as u32, Some(to_range_ref(start, end))));
+            cells.into_iter().for_each(|pos| {
+                result.insert(pos, (si as u32, None));
+            });
+        });
+    result
+}
+
+// The formulas in the R1C1 notation are the same if they are shifted from
+// each other.
+fn get_r1c1_formula(
+    sheet_id: SheetId,
+    cell_id: &CellId,
+    status: &Status,
+    navigator: &mut Navigator,
+) -> Option<((usize, usize), String)> {
+    let (row, col) = navigator.fetch_cell_idx(&sheet_id, cell_id).ok()?;
+    let node = status.formula_manager.formulas.get(&(sheet_id, *cell_id))?;
+    let mut name_fetcher = NameFetcher {
+        func_manager: &status.func_id_manager,
+        sheet_id_manager: &status.sheet_id_manager,
+        external_links_manager: &status.external_links_manager,
+        text_id_manager: &status.text_id_manager,
+        name_id_manager: &status.name_id_manager,
+        navigator,
+        formula_manager: &status.formula_manager,
+    };
+    let formula = unparse::unparse_r1c1(node, &mut name_fetcher, sheet_id, row, col);
+    Some(((row, col), formula))
+}
+
+fn to_ct_cell(
+    row: usize,
+    col: usize,
+    cell: &Cell,
+    formula: Option<String>,
+    sst_saver: &mut SstSaver,
+) -> CtCell {
+    let (t, v, is) = match &cell.value {
+        CellValue::Blank => (StCellType::N, None, None),
+        CellValue::Boolean(b) => {
+            let v = if *b { "1" } else { "0" };
+            (StCellType::B, Some(v.to_string()), None)
+        }
+        CellValue::Date(d) => (StCellType::D, Some(d.to_rfc3339()), None),
+        CellValue::Error(e) => (StCellType::E, Some(e.to_string()), None),
+        CellValue::String(text_id) => {
+            let idx = sst_saver.get_index(*text_id);
+            (StCellType::S, Some(idx.to_string()), None)
+        }
+        CellValue::Number(n) => (StCellType::N, Some(n.to_string()), None),
+        CellValue::InlineStr(rst) => (StCellType::InlineStr, None, Some(rst.clone())),
+        CellValue::FormulaStr(s) => (StCellType::Str, Some(s.clone()), None),
+    };
+    CtCell {
+        f: formula.map(|f| CtFormula {
+            formula: Some(f),
+            t: StCellFormulaType::Normal,
+            aca: false,
+            reference: None,
+            dt_2d: false,
+            del1: false,
+            del2: false,
+            r1: None,
+            r2: None,
+            ca: false,
+            si: None,
+            bx: false,
+        }),
+        v: v.map(|v| PlainTextString {
+            value: v,
+            space: None,
+        }),
+        is,
+        r: Some(to_cell_ref(row, col)),
+        s: cell.style,
+        t,
+        cm: 0,
+        vm: 0,
+        ph: false,
+    }
+}
+
+fn new_ct_row(idx: usize, info: Option<&RowInfo>) -> CtRow {
+    let info = info.cloned().unwrap_or_default();
+    CtRow {
+        cells: vec![],
+        r: Some(idx as u32 + 1),
+        spans: None,
+        s: info.style,
+        custom_format: info.custom_format,
+        ht: info.ht,
+        hidden: info.hidden,
+        custom_height: info.ht.is_some(),
+        outline_level: info.outline_level,
+        collapsed: info.collapsed,
+        thick_top: false,
+        thick_bot: false,
+        ph: false,
+    }
+}
+
+fn to_ct_col(idx: u32, info: &ColInfo) -> CtCol {
+    CtCol {
+        min: idx,
+        max: idx,
+        width: info.width,
+        style: info.style,
+        hidden: info.hidden,
+        best_fit: info.best_fit,
+        custom_width: info.custom_width,
+        phonetic: false,
+        outline_level: info.outline_level as u32,
+        collapsed: info.collapsed,
+    }
+}
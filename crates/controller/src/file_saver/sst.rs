@@ -0,0 +1,69 @@
+use logisheets_base::TextId;
+use logisheets_workbook::prelude::*;
+use std::collections::HashMap;
+
+use crate::id_manager::TextIdManager;
+
+pub struct SstSaver<'a> {
+    texts: HashMap<TextId, &'a str>,
+    indexes: HashMap<TextId, u32>,
+    si: Vec<CtRst>,
+    count: u32,
+}
+
+impl<'a> SstSaver<'a> {
+    pub fn new(text_id_manager: &'a TextIdManager) -> Self {
+        let texts = text_id_manager
+            .ids
+            .iter()
+            .map(|(s, id)| (*id, s.as_str()))
+            .collect::<HashMap<_, _>>();
+        SstSaver {
+            texts,
+            indexes: HashMap::new(),
+            si: vec![],
+            count: 0,
+        }
+    }
+
+    // Get the index of this text in the shared string table.
+    pub fn get_index(&mut self, text_id: TextId) -> u32 {
+        self.count += 1;
+        if let Some(idx) = self.indexes.get(&text_id) {
+            return *idx;
+        }
+        let text = self.texts.get(&text_id).map_or("", |s| s);
+        let idx = self.si.len() as u32;
+        self.si.push(CtRst {
+            t: Some(plain_text(text)),
+            r: vec![],
+            r_ph: vec![],
+            phonetic_pr: None,
+        });
+        self.indexes.insert(text_id, idx);
+        idx
+    }
+
+    pub fn into_part(self) -> Option<SstPart> {
+        if self.si.is_empty() {
+            return None;
+        }
+        Some(SstPart {
+            count: Some(self.count),
+            unique_count: Some(self.si.len() as u32),
+            si: self.si,
+        })
+    }
+}
+
+pub fn plain_text(s: &str) -> PlainTextString {
+    let space = if s.trim() != s {
+        Some(String::from("preserve"))
+    } else {
+        None
+    };
+    PlainTextString {
+        value: s.to_string(),
+        space,
+    }
+}
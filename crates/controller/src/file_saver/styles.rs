@@ -0,0 +1,126 @@
+use crate::num_fmt::get_builtin_num_fmt_id;
+use crate::style_manager::{xf_manager::CtrlXf, StyleManager};
+use logisheets_workbook::prelude::*;
+
+// Number formats whose ids are less than 164 are built-in ones.
+const CUSTOM_NUM_FMT_START: u32 = 164;
+
+pub fn save_style_manager(manager: &StyleManager) -> StylesheetPart {
+    let fonts = manager
+        .font_manager
+        .get_all_data()
+        .into_iter()
+        .map(|(_, f)| f.clone())
+        .collect::<Vec<_>>();
+    let fills = manager
+        .fill_manager
+        .get_all_data()
+        .into_iter()
+        .map(|(_, f)| f.clone())
+        .collect::<Vec<_>>();
+    let borders = manager
+        .border_manager
+        .get_all_data()
+        .into_iter()
+        .map(|(_, b)| b.clone())
+        .collect::<Vec<_>>();
+    let num_fmts = manager
+        .num_fmt_manager
+        .get_all_data()
+        .into_iter()
+        .filter(|(id, code)| *id != 0 && get_builtin_num_fmt_id(code).is_none())
+        .map(|(id, code)| CtNumFmt {
+            num_fmt_id: to_num_fmt_id(manager, id),
+            format_code: code.clone(),
+        })
+        .collect::<Vec<_>>();
+    let cell_style_xfs = manager
+        .cell_style_xfs_manager
+        .get_all_data()
+        .into_iter()
+        .map(|(_, xf)| to_ct_xf(manager, xf, None))
+        .collect::<Vec<_>>();
+    let cell_xfs = manager
+        .cell_xfs_manager
+        .get_all_data()
+        .into_iter()
+        .map(|(_, xf)| to_ct_xf(manager, xf, Some(0)))
+        .collect::<Vec<_>>();
+    StylesheetPart {
+        num_fmts: if num_fmts.is_empty() {
+            None
+        } else {
+            Some(CtNumFmts {
+                count: num_fmts.len() as u32,
+                num_fmts,
+            })
+        },
+        fonts: Some(CtFonts {
+            count: fonts.len() as u32,
+            fonts,
+        }),
+        fills: Some(CtFills {
+            count: fills.len() as u32,
+            fills,
+        }),
+        borders: Some(CtBorders {
+            count: borders.len() as u32,
+            borders,
+        }),
+        cell_style_xfs: Some(CtCellStyleXfs {
+            count: cell_style_xfs.len() as u32,
+            xfs: cell_style_xfs,
+        }),
+        cell_xfs: Some(CtCellXfs {
+            count: cell_xfs.len() as u32,
+            xfs: cell_xfs,
+        }),
+        cell_styles: Some(CtCellStyles {
+            count: 1,
+            cell_styles: vec![CtCellStyle {
+                name: Some(String::from("Normal")),
+                xf_id: 0,
+                builtin_id: Some(0),
+                i_level: None,
+                custom_builtin: None,
+            }],
+        }),
+        dxfs: None,
+        table_styles: None,
+        colors: None,
+        ext_lst: None,
+    }
+}
+
+// The num fmt id 0 is the `General` format. The codes of built-in formats
+// use their built-in ids and the others are stored as custom formats.
+fn to_num_fmt_id(manager: &StyleManager, id: u32) -> StNumFmtId {
+    if id == 0 {
+        return 0;
+    }
+    manager
+        .num_fmt_manager
+        .get_data(id)
+        .and_then(|code| get_builtin_num_fmt_id(code))
+        .unwrap_or(CUSTOM_NUM_FMT_START + id - 1)
+}
+
+fn to_ct_xf(manager: &StyleManager, xf: &CtrlXf, xf_id: Option<StCellStyleXfId>) -> CtXf {
+    CtXf {
+        alignment: xf.alignment.clone(),
+        protction: xf.protection.clone(),
+        num_fmt_id: xf.num_fmt_id.map(|id| to_num_fmt_id(manager, id)),
+        font_id: xf.font_id,
+        fill_id: xf.fill_id,
+        border_id: xf.border_id,
+        xf_id,
+        quote_prefix: false,
+        pivot_button: false,
+        apply_number_format: xf.apply_number_format,
+        apply_font: xf.apply_font,
+        apply_fill: xf.apply_fill,
+        apply_border: xf.apply_border,
+        apply_alignment: xf.apply_alignment,
+        apply_protection: xf.apply_protection,
+    }
+}
@@ -0,0 +1,32 @@
+use logisheets_base::index_to_column_label;
+
+// (0, 0) => A1
+pub fn to_cell_ref(row: usize, col: usize) -> String {
+    format!("{}{}", index_to_column_label(col), row + 1)
+}
+
+// ((0, 0), (1, 1)) => A1:B2
+pub fn to_range_ref(start: (usize, usize), end: (usize, usize)) -> String {
+    format!(
+        "{}:{}",
+        to_cell_ref(start.0, start.1),
+        to_cell_ref(end.0, end.1)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{to_cell_ref, to_range_ref};
+
+    #[test]
+    fn to_cell_ref_test() {
+        assert_eq!(to_cell_ref(0, 0), "A1");
+        assert_eq!(to_cell_ref(19, 26), "AA20");
+        assert_eq!(to_cell_ref(8, 0), "A9");
+    }
+
+    #[test]
+    fn to_range_ref_test() {
+        assert_eq!(to_range_ref((1, 0), (3, 1)), "A2:B4");
+    }
+}
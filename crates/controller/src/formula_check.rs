@@ -0,0 +1,165 @@
+use std::collections::HashSet;
+
+use logisheets_lexer::{try_lex, Rule};
+use pest::iterators::Pair;
+
+use crate::controller::display::FormulaDiagnostic;
+
+/// Check the formula, which has SKIPPED the '='. `check_func` is called with
+/// the name and the number of arguments of every function called, and returns
+/// the message if the call is wrong.
+pub fn check_formula<F>(formula: &str, mut check_func: F) -> Vec<FormulaDiagnostic>
+where
+    F: FnMut(&str, usize) -> Option<String>,
+{
+    let tokens = match try_lex(formula) {
+        Ok(tokens) => tokens,
+        Err(e) => {
+            return vec![FormulaDiagnostic {
+                start: e.start,
+                end: e.end,
+                expected: e.expected.iter().map(|r| format!("{:?}", r)).collect(),
+                message: e.message,
+            }]
+        }
+    };
+    let mut locals = HashSet::new();
+    collect_locals(tokens.clone(), &mut locals);
+    let mut result = vec![];
+    check_calls(tokens, &locals, &mut check_func, &mut result);
+    result
+}
+
+fn check_calls<F>(
+    pair: Pair<Rule>,
+    locals: &HashSet<String>,
+    check_func: &mut F,
+    result: &mut Vec<FormulaDiagnostic>,
+) where
+    F: FnMut(&str, usize) -> Option<String>,
+{
+    if pair.as_rule() == Rule::function_call {
+        let (name, args) = get_call(pair.clone());
+        if !locals.contains(&name.as_str().to_uppercase()) {
+            if let Some(message) = check_func(name.as_str(), args.len()) {
+                let span = name.as_span();
+                result.push(FormulaDiagnostic {
+                    start: span.start(),
+                    end: span.end(),
+                    expected: vec![],
+                    message,
+                });
+            }
+        }
+    }
+    pair.into_inner()
+        .for_each(|p| check_calls(p, locals, check_func, result));
+}
+
+// The names bound by LET and LAMBDA, in upper case. They can be called like
+// functions if they are bound to LAMBDAs.
+fn collect_locals(pair: Pair<Rule>, locals: &mut HashSet<String>) {
+    if pair.as_rule() == Rule::function_call {
+        let (name, args) = get_call(pair.clone());
+        let upper = name.as_str().to_uppercase();
+        let cnt = args.len();
+        let is_binding: Option<fn(usize) -> bool> =
+            match upper.strip_prefix("_XLFN.").unwrap_or(&upper) {
+                "LET" => Some(|i| i % 2 == 0),
+                "LAMBDA" => Some(|_| true),
+                _ => None,
+            };
+        if let Some(is_binding) = is_binding {
+            args.iter()
+                .enumerate()
+                .filter(|(i, _)| i + 1 < cnt && is_binding(*i))
+                .filter_map(|(_, a)| get_local_name(a))
+                .for_each(|n| {
+                    locals.insert(n);
+                });
+        }
+    }
+    pair.into_inner().for_each(|p| collect_locals(p, locals));
+}
+
+fn get_call(pair: Pair<Rule>) -> (Pair<Rule>, Vec<Pair<Rule>>) {
+    let mut inner = pair.into_inner();
+    let name = inner.next().unwrap();
+    let args = inner.filter(|p| p.as_rule() != Rule::call_args).collect();
+    (name, args)
+}
+
+fn get_local_name(pair: &Pair<Rule>) -> Option<String> {
+    if pair.as_rule() != Rule::expression {
+        return None;
+    }
+    let mut inner = pair.clone().into_inner();
+    match (inner.next(), inner.next()) {
+        (Some(p), None) if p.as_rule() == Rule::name => Some(p.as_str().trim().to_uppercase()),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::controller::Controller;
+
+    #[test]
+    fn check_formula_test() {
+        use crate::{ArgCount, ArgDef, NativeValue};
+        let mut wb = Controller::default();
+        let d = wb.check_formula("SUM(1, (2)");
+        assert_eq!(d.len(), 1);
+        assert_eq!((d[0].start, d[0].end), (3, 4));
+        assert_eq!(d[0].message, "unbalanced parenthesis");
+        let d = wb.check_formula("1+");
+        assert_eq!(d[0].message, "unexpected end of the formula");
+        assert!(!d[0].expected.is_empty());
+
+        let d = wb.check_formula("SUM(1, FOO(2))");
+        assert_eq!(d.len(), 1);
+        assert_eq!((d[0].start, d[0].end), (7, 10));
+        assert_eq!(d[0].message, "unknown function FOO");
+        assert!(wb
+            .check_formula("LET(f, LAMBDA(x, x + 1), f(2))")
+            .is_empty());
+        assert!(wb
+            .check_formula("_xlfn.TEXTJOIN(\",\", TRUE, A1:A3)")
+            .is_empty());
+        let d = wb.check_formula("ROUND(1.5)");
+        assert_eq!(d[0].message, "wrong number of arguments for ROUND");
+        // The checks without a workbook, like the ones of `formula_diagnose`.
+        assert_eq!(
+            crate::check_formula("IF(A1, 1, 2, 3)")[0].message,
+            "wrong number of arguments for IF"
+        );
+        assert_eq!(
+            crate::check_formula("ABS()")[0].message,
+            "wrong number of arguments for ABS"
+        );
+        assert!(crate::check_formula("_xlfn.CONCAT(1, 2)").is_empty());
+        // Checking a formula does not register the names.
+        wb.check_formula("FOO(1) + BAR()");
+        assert!(wb.status.func_id_manager.has("FOO").is_none());
+        assert!(wb.status.func_id_manager.has("BAR").is_none());
+
+        let arg_count = ArgCount {
+            le: Some(2),
+            ge: Some(1),
+            eq: None,
+            odd: None,
+            even: None,
+        };
+        let args = vec![ArgDef {
+            arg_name: String::from("value"),
+            ref_only: None,
+            start_repeated: None,
+        }];
+        wb.register_func("double", arg_count, args, |_| NativeValue::Number(0.));
+        assert!(wb.check_formula("DOUBLE(1)").is_empty());
+        let d = wb.check_formula("1 + double(1, 2, 3)");
+        assert_eq!(d.len(), 1);
+        assert_eq!((d[0].start, d[0].end), (4, 10));
+        assert_eq!(d[0].message, "wrong number of arguments for double");
+    }
+}
@@ -0,0 +1,8 @@
+use logisheets_base::SheetId;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum FormulaError {
+    #[error("wrong arguments of {3} in the formula with sheet:{0}, row:{1} and col:{2}")]
+    WrongArguments(SheetId, usize, usize, String),
+}
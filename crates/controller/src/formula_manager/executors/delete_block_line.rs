@@ -51,6 +51,9 @@ where
         cube_manager,
         ext_ref_manager,
         names,
+        dynamic_graph,
+        volatile_vertices,
+        table_manager,
     } = exec_ctx.manager;
 
     let mut dirty_vertices = HashSet::new();
@@ -77,6 +80,9 @@ where
         cube_manager,
         ext_ref_manager,
         names,
+        dynamic_graph,
+        volatile_vertices,
+        table_manager,
     };
 
     Ok(FormulaExecContext {
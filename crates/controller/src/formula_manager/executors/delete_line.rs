@@ -35,8 +35,17 @@ where
         cube_manager,
         ext_ref_manager,
         names,
+        dynamic_graph,
+        volatile_vertices,
+        mut table_manager,
     } = exec_ctx.manager;
 
+    if is_horizontal {
+        table_manager.delete_rows(sheet_id, idx, cnt as usize, ctx);
+    } else {
+        table_manager.delete_columns(sheet_id, idx, cnt as usize, ctx);
+    }
+
     let blocks = ctx.get_blocks_across_line(sheet_id, idx, cnt as usize, is_horizontal)?;
     let mut range_exec_ctx = RangeExecContext::new(range_manager);
     let mut cube_exec_ctx = CubeExecContext::new(cube_manager);
@@ -109,6 +118,9 @@ where
         cube_manager,
         ext_ref_manager,
         names,
+        dynamic_graph,
+        volatile_vertices,
+        table_manager,
     };
 
     Ok(FormulaExecContext {
@@ -1,4 +1,5 @@
 use anyhow::Result;
+use logisheets_astchecker::AstChecker;
 use logisheets_base::{
     block_affect::BlockAffectTrait,
     get_active_sheet::GetActiveSheetTrait,
@@ -6,18 +7,20 @@ use logisheets_base::{
     id_fetcher::{IdFetcherTrait, VertexFetcherTrait},
     index_fetcher::IndexFetcherTrait,
     BlockRange, CellId, ColId, Cube, CubeId, ExtBookId, ExtRef, ExtRefId, FuncId, NameId,
-    NormalRange, Range, RangeId, RowId, SheetId, TextId,
+    NormalRange, Range, RangeId, RowId, SheetId, Table, TextId,
 };
 use logisheets_parser::Parser;
 use logisheets_parser::{ast, context::ContextTrait as ParserContextTrait};
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 
 use crate::{
+    calc_engine::calculator::VOLATILE_FUNCS,
     cube_manager::{CubeExecContext, CubeManger},
     ext_ref_manager::ExtRefManager,
-    formula_manager::{FormulaExecContext, FormulaManager, Vertex},
+    formula_manager::{errors::FormulaError, FormulaExecContext, FormulaManager, Vertex},
     payloads::sheet_process::{FormulaPayload, SheetPayload, SheetProcess},
     range_manager::{RangeExecContext, RangeManager},
+    table_manager::TableManager,
 };
 
 pub fn input_formula<C>(
@@ -26,6 +29,7 @@ pub fn input_formula<C>(
     row: usize,
     col: usize,
     formula: String,
+    checker: &AstChecker,
     ctx: &mut C,
 ) -> Result<FormulaExecContext>
 where
@@ -42,6 +46,9 @@ where
         mut cube_manager,
         names,
         mut ext_ref_manager,
+        dynamic_graph,
+        mut volatile_vertices,
+        table_manager,
     } = exec_ctx.manager;
 
     let cell_id = ctx.fetch_cell_id(&sheet, row, col).unwrap(); // todo
@@ -50,6 +57,9 @@ where
         range_manager: &mut range_manager,
         cube_manager: &mut cube_manager,
         ext_ref_manager: &mut ext_ref_manager,
+        table_manager: &table_manager,
+        curr_cell: (sheet, row, col),
+        func_names: HashMap::new(),
         ctx,
     };
 
@@ -61,6 +71,7 @@ where
     let this_vertex = Vertex::Range(sheet, range_id);
 
     let parser = Parser {};
+    // The formulas which can not be parsed are ignored.
     let ast = parser.parse(&formula, &mut parser_context);
     if ast.is_none() {
         let manager = FormulaManager {
@@ -70,6 +81,9 @@ where
             cube_manager,
             ext_ref_manager,
             names,
+            dynamic_graph,
+            volatile_vertices,
+            table_manager,
         };
         return Ok(FormulaExecContext {
             manager,
@@ -77,6 +91,12 @@ where
         });
     }
     let ast = ast.unwrap();
+    let func_names = parser_context.func_names;
+    let fetch_func_name = |id: &FuncId| func_names.get(id).cloned();
+    if let Err(e) = checker.func_check(&ast, &fetch_func_name) {
+        let name = fetch_func_name(&e.id).unwrap_or_default();
+        return Err(FormulaError::WrongArguments(sheet, row, col, name).into());
+    }
 
     let mut new_formula_deps = HashSet::<Vertex>::new();
     get_all_vertices_from_ast(&ast, &mut new_formula_deps);
@@ -101,6 +121,12 @@ where
         .into_iter()
         .for_each(|new_dep| graph.add_dep(this_vertex.clone(), new_dep));
 
+    if has_volatile_func(&ast, ctx) {
+        volatile_vertices.insert(this_vertex.clone());
+    } else {
+        volatile_vertices.remove(&this_vertex);
+    }
+
     formulas.insert((sheet, cell_id), ast);
 
     let process = SheetProcess {
@@ -139,6 +165,9 @@ where
             cube_manager: new_cube_manager,
             ext_ref_manager,
             names,
+            dynamic_graph,
+            volatile_vertices,
+            table_manager,
         },
         dirty_vertices,
     })
@@ -146,16 +175,22 @@ where
 
 // This method is only used in loading a file (especially for shared formula).
 // So there are somethings different from inputting a formula.
-pub fn add_ast_node(
+pub fn add_ast_node<C>(
     manager: &mut FormulaManager,
     sheet_id: SheetId,
     cell_id: CellId,
     range_id: RangeId,
     ast: ast::Node,
-) {
+    ctx: &mut C,
+) where
+    C: IdFetcherTrait,
+{
     let this_vertex = Vertex::Range(sheet_id, range_id);
     let mut new_formula_deps = HashSet::<Vertex>::new();
     get_all_vertices_from_ast(&ast, &mut new_formula_deps);
+    if has_volatile_func(&ast, ctx) {
+        manager.volatile_vertices.insert(this_vertex.clone());
+    }
     manager.formulas.insert((sheet_id, cell_id), ast);
 
     new_formula_deps
@@ -163,14 +198,45 @@ pub fn add_ast_node(
         .for_each(|new_dep| manager.graph.add_dep(this_vertex.clone(), new_dep));
 }
 
-fn get_all_vertices_from_ast(ast: &ast::Node, vertices: &mut HashSet<Vertex>) {
+// The volatile functions called through the defined names and the user
+// functions are found when calculating.
+fn has_volatile_func<C>(ast: &ast::Node, ctx: &mut C) -> bool
+where
+    C: IdFetcherTrait,
+{
+    let func_ids = VOLATILE_FUNCS
+        .iter()
+        .flat_map(|f| {
+            vec![
+                ctx.fetch_func_id(f),
+                ctx.fetch_func_id(&format!("_xlfn.{}", f)),
+            ]
+        })
+        .collect::<Vec<_>>();
+    contains_func(ast, &func_ids)
+}
+
+fn contains_func(ast: &ast::Node, func_ids: &[FuncId]) -> bool {
+    match &ast.pure {
+        ast::PureNode::Func(func) => {
+            let found = match &func.op {
+                ast::Operator::Function(id) => func_ids.contains(id),
+                _ => false,
+            };
+            found || func.args.iter().any(|n| contains_func(n, func_ids))
+        }
+        _ => false,
+    }
+}
+
+pub fn get_all_vertices_from_ast(ast: &ast::Node, vertices: &mut HashSet<Vertex>) {
     match &ast.pure {
         ast::PureNode::Func(func) => {
             func.args
                 .iter()
                 .for_each(|n| get_all_vertices_from_ast(n, vertices));
         }
-        ast::PureNode::Value(_) => {}
+        ast::PureNode::Value(_) | ast::PureNode::Local(_) => {}
         ast::PureNode::Reference(reference) => match reference {
             ast::CellReference::Mut(r) => {
                 let sheet_id = r.sheet_id;
@@ -192,6 +258,10 @@ fn get_all_vertices_from_ast(ast: &ast::Node, vertices: &mut HashSet<Vertex>) {
                 let vertex = Vertex::Name(*name);
                 vertices.insert(vertex);
             }
+            ast::CellReference::Table(t) => {
+                let vertex = Vertex::Range(t.sheet_id, t.range_id);
+                vertices.insert(vertex);
+            }
         },
     }
 }
@@ -200,12 +270,17 @@ struct ParserContext<'a, C> {
     range_manager: &'a mut RangeManager,
     cube_manager: &'a mut CubeManger,
     ext_ref_manager: &'a mut ExtRefManager,
+    table_manager: &'a TableManager,
+    // The sheet, the row and the column of the formula.
+    curr_cell: (SheetId, usize, usize),
+    // The names of the functions called in the formula.
+    func_names: HashMap<FuncId, String>,
     ctx: &'a mut C,
 }
 
 impl<'a, C> GetBookNameTrait for ParserContext<'a, C>
 where
-    C: IdFetcherTrait + GetActiveSheetTrait + GetBookNameTrait,
+    C: IdFetcherTrait + IndexFetcherTrait + GetActiveSheetTrait + GetBookNameTrait,
 {
     fn get_book_name(&self) -> &str {
         self.ctx.get_book_name()
@@ -214,7 +289,7 @@ where
 
 impl<'a, C> GetActiveSheetTrait for ParserContext<'a, C>
 where
-    C: IdFetcherTrait + GetActiveSheetTrait + GetBookNameTrait,
+    C: IdFetcherTrait + IndexFetcherTrait + GetActiveSheetTrait + GetBookNameTrait,
 {
     fn get_active_sheet(&self) -> SheetId {
         self.ctx.get_active_sheet()
@@ -223,7 +298,7 @@ where
 
 impl<'a, C> IdFetcherTrait for ParserContext<'a, C>
 where
-    C: IdFetcherTrait + GetActiveSheetTrait + GetBookNameTrait,
+    C: IdFetcherTrait + IndexFetcherTrait + GetActiveSheetTrait + GetBookNameTrait,
 {
     fn fetch_row_id(&mut self, sheet_id: &SheetId, row_idx: usize) -> Result<RowId> {
         self.ctx.fetch_row_id(sheet_id, row_idx)
@@ -250,6 +325,10 @@ where
         self.ctx.fetch_name_id(workbook, name)
     }
 
+    fn fetch_local_name_id(&mut self, sheet_id: &SheetId, name: &str) -> Option<NameId> {
+        self.ctx.fetch_local_name_id(sheet_id, name)
+    }
+
     fn fetch_ext_book_id(&mut self, book: &str) -> ExtBookId {
         self.ctx.fetch_ext_book_id(book)
     }
@@ -259,13 +338,15 @@ where
     }
 
     fn fetch_func_id(&mut self, func_name: &str) -> FuncId {
-        self.ctx.fetch_func_id(func_name)
+        let id = self.ctx.fetch_func_id(func_name);
+        self.func_names.insert(id, func_name.to_string());
+        id
     }
 }
 
 impl<'a, C> VertexFetcherTrait for ParserContext<'a, C>
 where
-    C: IdFetcherTrait + GetActiveSheetTrait + GetBookNameTrait,
+    C: IdFetcherTrait + IndexFetcherTrait + GetActiveSheetTrait + GetBookNameTrait,
 {
     fn fetch_range_id(&mut self, sheet_id: &SheetId, range: &Range) -> RangeId {
         self.range_manager.get_range_id(sheet_id, range)
@@ -278,9 +359,14 @@ where
     fn fetch_ext_ref_id(&mut self, ext_ref: &ExtRef) -> ExtRefId {
         self.ext_ref_manager.get_ext_ref_id(ext_ref)
     }
+
+    fn fetch_table(&mut self, name: Option<&str>) -> Option<Table> {
+        self.table_manager
+            .fetch_table(name, Some(self.curr_cell), self.ctx)
+    }
 }
 
 impl<'a, C> ParserContextTrait for ParserContext<'a, C> where
-    C: IdFetcherTrait + GetActiveSheetTrait + GetBookNameTrait
+    C: IdFetcherTrait + IndexFetcherTrait + GetActiveSheetTrait + GetBookNameTrait
 {
 }
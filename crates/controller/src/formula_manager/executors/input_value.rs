@@ -1,13 +1,13 @@
 use anyhow::Result;
 use logisheets_base::{
     block_affect::BlockAffectTrait, id_fetcher::IdFetcherTrait, index_fetcher::IndexFetcherTrait,
-    CellValue,
+    BlockRange, CellId, CellValue, NormalRange, Range,
 };
 use std::collections::HashSet;
 
 use crate::{
     cube_manager::CubeExecContext,
-    formula_manager::{FormulaExecContext, FormulaManager},
+    formula_manager::{FormulaExecContext, FormulaManager, Vertex},
     payloads::sheet_process::{CellChange, CellPayload, SheetPayload, SheetProcess},
     range_manager::RangeExecContext,
     SheetId,
@@ -27,14 +27,37 @@ where
     C: IdFetcherTrait + IndexFetcherTrait + BlockAffectTrait,
 {
     let FormulaManager {
-        graph,
-        formulas,
+        mut graph,
+        mut formulas,
         range_manager,
         cube_manager,
         ext_ref_manager,
         names,
+        mut dynamic_graph,
+        mut volatile_vertices,
+        table_manager,
     } = exec_ctx.manager;
 
+    // The value replaces the formula of the cell, and so do the references
+    // in it.
+    if let Ok(cell_id) = ctx.fetch_cell_id(&sheet_id, row, col) {
+        if formulas.remove(&(sheet_id, cell_id)).is_some() {
+            let range = match cell_id {
+                CellId::NormalCell(c) => Range::Normal(NormalRange::Single(c)),
+                CellId::BlockCell(c) => Range::Block(BlockRange::Single(c)),
+            };
+            if let Some(range_id) = range_manager.get_range_id_assert(&sheet_id, &range) {
+                let vertex = Vertex::Range(sheet_id, range_id);
+                for g in [&mut graph, &mut dynamic_graph].iter_mut() {
+                    if let Some(deps) = g.get_deps(&vertex).cloned() {
+                        deps.iter().for_each(|dep| g.remove_dep(&vertex, dep));
+                    }
+                }
+                volatile_vertices.remove(&vertex);
+            }
+        }
+    }
+
     let sp = SheetProcess {
         sheet_id,
         payload: SheetPayload::Cell(CellPayload {
@@ -69,6 +92,9 @@ where
         cube_manager,
         ext_ref_manager,
         names,
+        dynamic_graph,
+        volatile_vertices,
+        table_manager,
     };
 
     Ok(FormulaExecContext {
@@ -60,6 +60,9 @@ where
         cube_manager,
         ext_ref_manager,
         names,
+        dynamic_graph,
+        volatile_vertices,
+        table_manager,
     } = exec_ctx.manager;
 
     let range_exec_ctx = range_manager.execute_sheet_proc(removed_proc.clone(), ctx)?;
@@ -105,6 +108,9 @@ where
         cube_manager,
         ext_ref_manager,
         names,
+        dynamic_graph,
+        volatile_vertices,
+        table_manager,
     };
 
     Ok(FormulaExecContext {
@@ -33,7 +33,14 @@ where
         cube_manager,
         ext_ref_manager,
         names,
+        dynamic_graph,
+        volatile_vertices,
+        mut table_manager,
     } = exec_ctx.manager;
+
+    if !is_horizontal {
+        table_manager.insert_columns(sheet_id, idx, cnt as usize, old_ctx);
+    }
     let mut dirty_vertices = exec_ctx.dirty_vertices;
 
     let sp = SheetProcess {
@@ -73,6 +80,9 @@ where
         cube_manager,
         ext_ref_manager,
         names,
+        dynamic_graph,
+        volatile_vertices,
+        table_manager,
     };
 
     Ok(FormulaExecContext {
@@ -11,7 +11,7 @@ mod utils;
 pub use create_block::create_block;
 pub use delete_block_line::delete_block_line;
 pub use delete_line::delete_line;
-pub use input_formula::{add_ast_node, input_formula};
+pub use input_formula::{add_ast_node, get_all_vertices_from_ast, input_formula};
 pub use input_value::input_value;
 pub use insert_block_line::insert_block_line;
 pub use insert_line::insert_line;
@@ -1,13 +1,15 @@
+pub mod errors;
 mod executors;
 pub mod graph;
 
 use anyhow::Result;
 use graph::Graph;
-use im::HashMap;
+use im::{HashMap, HashSet};
+use logisheets_astchecker::AstChecker;
 use logisheets_base::{
     block_affect::BlockAffectTrait, get_active_sheet::GetActiveSheetTrait,
     get_book_name::GetBookNameTrait, id_fetcher::IdFetcherTrait, index_fetcher::IndexFetcherTrait,
-    CubeId, ExtRefId, NameId, RangeId, SheetId,
+    BlockRange, CubeId, ExtRefId, NameId, NormalRange, Range, RangeId, SheetId,
 };
 use logisheets_parser::ast;
 
@@ -21,12 +23,13 @@ use crate::{
         SheetPayload, SheetProcess,
     },
     range_manager::RangeManager,
+    table_manager::TableManager,
     CellId,
 };
 
 use self::executors::{
-    add_ast_node, create_block, delete_block_line, delete_line, input_formula, input_value,
-    insert_block_line, insert_line, move_block,
+    add_ast_node, create_block, delete_block_line, delete_line, get_all_vertices_from_ast,
+    input_formula, input_value, insert_block_line, insert_line, move_block,
 };
 
 #[derive(Debug, Clone)]
@@ -37,6 +40,13 @@ pub struct FormulaManager {
     pub cube_manager: CubeManger,
     pub ext_ref_manager: ExtRefManager,
     pub names: HashMap<NameId, ast::Node>,
+    // Dependencies which can only be known when calculating, like the
+    // references returned by OFFSET and INDIRECT. They are kept apart from
+    // `graph` because they are replaced every time the formula is calculated.
+    pub dynamic_graph: Graph<Vertex>,
+    // Formulas that should be calculated in every transaction.
+    pub volatile_vertices: HashSet<Vertex>,
+    pub table_manager: TableManager,
 }
 
 impl FormulaManager {
@@ -48,24 +58,130 @@ impl FormulaManager {
             cube_manager: CubeManger::new(),
             ext_ref_manager: ExtRefManager::new(),
             names: HashMap::new(),
+            dynamic_graph: Graph::<Vertex>::new(),
+            volatile_vertices: HashSet::new(),
+            table_manager: TableManager::new(),
         }
     }
 
     // Only used in loading a file. In a loading file process, we do not
     // need to find out the dirty vertex.
-    pub fn add_ast_node(
+    pub fn add_ast_node<C>(
         &mut self,
         sheet_id: SheetId,
         cell_id: CellId,
         range_id: RangeId,
         ast: ast::Node,
+        ctx: &mut C,
+    ) where
+        C: IdFetcherTrait,
+    {
+        add_ast_node(self, sheet_id, cell_id, range_id, ast, ctx)
+    }
+
+    /// Replace the dependencies of a cell that were resolved in its last
+    /// calculation.
+    pub fn set_dynamic_deps(
+        &mut self,
+        sheet_id: SheetId,
+        cell_id: CellId,
+        deps: Vec<(SheetId, Range)>,
     ) {
-        add_ast_node(self, sheet_id, cell_id, range_id, ast)
+        let range = get_cell_range(cell_id);
+        let this_vertex = match self.range_manager.get_range_id_assert(&sheet_id, &range) {
+            Some(range_id) => Vertex::Range(sheet_id, range_id),
+            None => return,
+        };
+        if let Some(old_deps) = self.dynamic_graph.get_deps(&this_vertex).cloned() {
+            old_deps.iter().for_each(|old_dep| {
+                self.dynamic_graph.remove_dep(&this_vertex, old_dep);
+                let unused = |g: &Graph<Vertex>| {
+                    g.get_rdeps(old_dep).into_iter().all(|r| r.is_empty())
+                        && g.get_deps(old_dep).into_iter().all(|d| d.is_empty())
+                };
+                if unused(&self.graph) && unused(&self.dynamic_graph) {
+                    if let Vertex::Range(sheet_id, range_id) = old_dep {
+                        self.range_manager.remove_range_id(sheet_id, range_id);
+                    }
+                }
+            });
+        }
+        deps.into_iter().for_each(|(sheet_id, range)| {
+            let range_id = self.range_manager.get_range_id(&sheet_id, &range);
+            self.dynamic_graph
+                .add_dep(this_vertex.clone(), Vertex::Range(sheet_id, range_id));
+        });
+    }
+
+    /// Mark the calculated formulas volatile if they called volatile
+    /// functions, maybe in the defined names or the user functions.
+    pub fn update_volatile_vertices(
+        &mut self,
+        calculated: &std::collections::HashSet<Vertex>,
+        volatile_cells: std::collections::HashSet<(SheetId, CellId)>,
+    ) {
+        calculated.iter().for_each(|v| {
+            self.volatile_vertices.remove(v);
+        });
+        volatile_cells.into_iter().for_each(|(sheet_id, cell_id)| {
+            let range = get_cell_range(cell_id);
+            if let Some(range_id) = self.range_manager.get_range_id_assert(&sheet_id, &range) {
+                let vertex = Vertex::Range(sheet_id, range_id);
+                if calculated.contains(&vertex) {
+                    self.volatile_vertices.insert(vertex);
+                }
+            }
+        });
+    }
+
+    /// The vertices of the formulas in the sheet, or in all the sheets if
+    /// `sheet_id` is none.
+    pub fn get_formula_vertices(
+        &self,
+        sheet_id: Option<SheetId>,
+    ) -> std::collections::HashSet<Vertex> {
+        self.formulas
+            .keys()
+            .filter(|(s, _)| sheet_id.is_none() || sheet_id == Some(*s))
+            .filter_map(|(s, cell_id)| {
+                let range = get_cell_range(*cell_id);
+                let range_id = self.range_manager.get_range_id_assert(s, &range)?;
+                Some(Vertex::Range(*s, range_id))
+            })
+            .collect()
+    }
+
+    /// The references in the formula of the defined name. The defined names
+    /// are not in the graph.
+    pub fn get_name_deps(&self, name_id: &NameId) -> std::collections::HashSet<Vertex> {
+        let mut deps = std::collections::HashSet::new();
+        if let Some(node) = self.names.get(name_id) {
+            get_all_vertices_from_ast(node, &mut deps);
+        }
+        deps
+    }
+
+    /// Build the dependency graph again from the formulas. The dependencies
+    /// known in the last calculation are dropped.
+    pub fn rebuild_graph<C>(&mut self, ctx: &mut C)
+    where
+        C: IdFetcherTrait,
+    {
+        self.graph = Graph::new();
+        self.dynamic_graph = Graph::new();
+        self.volatile_vertices = HashSet::new();
+        let formulas = self.formulas.clone();
+        formulas.into_iter().for_each(|((sheet_id, cell_id), ast)| {
+            let range = get_cell_range(cell_id);
+            let range_id = self.range_manager.get_range_id(&sheet_id, &range);
+            add_ast_node(self, sheet_id, cell_id, range_id, ast, ctx);
+        });
     }
 
     pub fn execute_sheet_proc<C>(
         self,
         proc: SheetProcess,
+        checker: &AstChecker,
         ctx: &mut C,
     ) -> Result<FormulaExecContext>
     where
@@ -96,7 +212,7 @@ impl FormulaManager {
                 ShiftPayload::Range(_) => todo!(),
             },
             SheetPayload::Formula(fp) => {
-                input_formula(exec_ctx, sheet_id, fp.row, fp.col, fp.formula, ctx)
+                input_formula(exec_ctx, sheet_id, fp.row, fp.col, fp.formula, checker, ctx)
             }
             SheetPayload::Block(block_payload) => match block_payload {
                 BlockPayload::Create(payload) => create_block(
@@ -179,6 +295,13 @@ impl FormulaExecContext {
     }
 }
 
+fn get_cell_range(cell_id: CellId) -> Range {
+    match cell_id {
+        CellId::NormalCell(c) => Range::Normal(NormalRange::Single(c)),
+        CellId::BlockCell(c) => Range::Block(BlockRange::Single(c)),
+    }
+}
+
 #[derive(Debug, Clone, Hash, PartialEq, Eq)]
 pub enum Vertex {
     Range(SheetId, RangeId),
@@ -186,3 +309,37 @@ pub enum Vertex {
     Ext(ExtRefId),
     Name(NameId),
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::controller::Controller;
+    use crate::test_utils::{defined_name, get_value, input};
+    use logisheets_base::CellValue;
+
+    #[test]
+    fn volatile_name_test() {
+        use logisheets_workbook::prelude::CtDefinedNames;
+        let mut book = crate::file_saver::save(&Controller::default());
+        book.xl.workbook_part.defined_names = Some(CtDefinedNames {
+            names: vec![
+                defined_name("Roll", "RAND()", None),
+                defined_name("Noise", "_xlfn.LAMBDA(_xlpm.x, _xlpm.x+RAND())", None),
+            ],
+        });
+        let buf = logisheets_workbook::writer::write(book).unwrap();
+        let mut wb = Controller::from_file(String::from("names"), &buf).unwrap();
+        input(&mut wb, 0, 0, 0, "=Roll");
+        input(&mut wb, 0, 1, 0, "=Noise(1)");
+        input(&mut wb, 0, 2, 0, "=1+1");
+        assert_eq!(wb.status.formula_manager.volatile_vertices.len(), 2);
+        let get_number = |wb: &mut Controller, row: usize| match get_value(wb, 0, row, 0) {
+            CellValue::Number(n) => n,
+            _ => panic!("a number is expected"),
+        };
+        let old = (get_number(&mut wb, 0), get_number(&mut wb, 1));
+        input(&mut wb, 0, 3, 0, "1");
+        let new = (get_number(&mut wb, 0), get_number(&mut wb, 1));
+        assert!(old.0 != new.0 && old.1 != new.1);
+        assert!(new.1 >= 1. && new.1 < 2.);
+    }
+}
@@ -1,10 +1,13 @@
 use im::hashmap::HashMap;
-use logisheets_base::{ExtBookId, NameId};
+use logisheets_base::{ExtBookId, NameId, SheetId};
 
 #[derive(Debug, Clone)]
 pub struct NameIdManager {
     pub next_available: NameId,
     pub ids: HashMap<(ExtBookId, String), NameId>,
+    /// The names only used in a sheet of this workbook. In that sheet they go
+    /// before the names of the workbook with the same text.
+    pub local_ids: HashMap<(SheetId, String), NameId>,
 }
 
 impl NameIdManager {
@@ -12,6 +15,7 @@ impl NameIdManager {
         NameIdManager {
             next_available: start,
             ids: HashMap::new(),
+            local_ids: HashMap::new(),
         }
     }
 
@@ -29,10 +33,42 @@ impl NameIdManager {
         }
     }
 
+    pub fn get_local_id(&mut self, value: &(SheetId, String)) -> NameId {
+        match self.local_ids.get(value) {
+            Some(r) => *r,
+            None => {
+                let r = self.next_available;
+                self.local_ids.insert(value.to_owned(), r);
+                self.next_available += 1;
+                r
+            }
+        }
+    }
+
+    pub fn find_local_id(&self, sheet_id: SheetId, name: &str) -> Option<NameId> {
+        self.local_ids
+            .iter()
+            .find(|((s, n), _)| *s == sheet_id && n.eq_ignore_ascii_case(name))
+            .map(|(_, id)| *id)
+    }
+
+    /// The sheet that the name is only used in, or none if it is a name of
+    /// the workbook.
+    pub fn get_scope(&self, key: &NameId) -> Option<SheetId> {
+        self.local_ids
+            .iter()
+            .find(|&(_, v)| v == key)
+            .map(|((sheet_id, _), _)| *sheet_id)
+    }
+
     pub fn get_string(&self, key: &NameId) -> Option<(ExtBookId, String)> {
         match self.ids.iter().find(|&(_, v)| v == key) {
             Some(r) => Some(r.0.clone()),
-            None => None,
+            None => self
+                .local_ids
+                .iter()
+                .find(|&(_, v)| v == key)
+                .map(|((_, name), _)| (0, name.clone())),
         }
     }
 }
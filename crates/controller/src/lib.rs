@@ -13,32 +13,49 @@ mod data_executor;
 mod ext_book_manager;
 mod ext_ref_manager;
 mod file_loader2;
+mod file_saver;
+mod formula_check;
 mod formula_manager;
 mod id_manager;
 mod navigator;
+mod num_fmt;
 mod payloads;
 mod range_manager;
 mod settings;
 mod style_manager;
+mod table_manager;
+#[cfg(test)]
+mod test_utils;
 mod theme_manager;
 mod workbook;
 
 use connectors::NameFetcher;
 pub use controller::{
-    display::{Comment, MergeCell, Value},
+    display::{
+        CircularRef, CircularRefCell, Comment, Dependency, DependencyRange, EvalTrace, EvalValue,
+        FormattedValue, FormulaDiagnostic, FormulaToken, FormulaTokenKind, FunctionArg,
+        FunctionArgType, FunctionInfo, MergeCell, Value,
+    },
     style::{Border, BorderPr, Fill, Font, Style},
     Controller,
 };
 use controller::{edit_action::EditAction, style::StyleConverter};
 use logisheets_parser::unparse;
+pub use logisheets_parser::{a1_to_r1c1, r1c1_to_a1};
 pub use logisheets_workbook::prelude::SerdeErr;
 
+pub use logisheets_astchecker::{ArgCount, ArgDef, AstChecker};
+pub use settings::func_registry::{
+    AsyncFunc, AsyncReturnType, FuncRegistry, NativeFunc, NativeValue,
+};
+
 pub use logisheets_base::async_func::AsyncCalcResult;
 pub use logisheets_base::async_func::AsyncErr;
 pub use logisheets_base::async_func::Task;
 pub use logisheets_base::BlockId;
 pub use logisheets_base::CellId;
 pub use logisheets_base::SheetId;
+pub use settings::{CalcMode, ReferenceStyle};
 
 // Has SKIPPED the '='
 pub fn lex_success(f: &str) -> bool {
@@ -49,6 +66,15 @@ pub fn lex_success(f: &str) -> bool {
     }
 }
 
+/// Check the formula, which has SKIPPED the '=', without a workbook. Only
+/// the built-in functions are known.
+pub fn check_formula(f: &str) -> Vec<FormulaDiagnostic> {
+    let func_registry = settings::func_registry::FuncRegistry::default();
+    formula_check::check_formula(f, |name, count| {
+        controller::functions::check_call(&func_registry, name, count)
+    })
+}
+
 #[derive(Debug)]
 pub enum Err {
     SerdeErr(SerdeErr),
@@ -136,7 +162,7 @@ impl<'a> Worksheet<'a> {
                 let v = match value {
                     logisheets_base::CellValue::Blank => Value::Empty,
                     logisheets_base::CellValue::Boolean(b) => Value::Bool(*b),
-                    logisheets_base::CellValue::Date(_) => Value::Empty,
+                    logisheets_base::CellValue::Date(d) => Value::Date(d.to_rfc3339()),
                     logisheets_base::CellValue::Error(e) => Value::Error(e.to_string()),
                     logisheets_base::CellValue::String(s) => {
                         let text = self.controller.status.text_id_manager.get_string(s);
@@ -146,7 +172,9 @@ impl<'a> Worksheet<'a> {
                         }
                     }
                     logisheets_base::CellValue::Number(n) => Value::Number(*n),
-                    logisheets_base::CellValue::InlineStr(_) => Value::Empty,
+                    logisheets_base::CellValue::InlineStr(rst) => {
+                        Value::Str(logisheets_base::rst_to_plain_text(rst))
+                    }
                     logisheets_base::CellValue::FormulaStr(s) => Value::Str(s.to_string()),
                 };
                 Ok(v)
@@ -181,8 +209,18 @@ impl<'a> Worksheet<'a> {
                     navigator: &mut self.controller.status.navigator,
                     formula_manager: &self.controller.status.formula_manager,
                 };
-                let f = unparse::unparse(node, &mut name_fetcher, self.sheet_id);
-                Ok(f)
+                match self.controller.settings.reference_style {
+                    ReferenceStyle::A1 => {
+                        Ok(unparse::unparse(node, &mut name_fetcher, self.sheet_id))
+                    }
+                    ReferenceStyle::R1C1 => Ok(unparse::unparse_r1c1(
+                        node,
+                        &mut name_fetcher,
+                        self.sheet_id,
+                        row,
+                        col,
+                    )),
+                }
             } else {
                 Ok(String::from(""))
             }
@@ -251,6 +289,33 @@ impl<'a> Worksheet<'a> {
         }
     }
 
+    /// Get the text shown in the cell after applying its number format.
+    pub fn get_formatted_value(&mut self, row: usize, col: usize) -> Result<FormattedValue, Err> {
+        let value = self.get_value(row, col)?;
+        let cell_id = self
+            .controller
+            .status
+            .navigator
+            .fetch_cell_id(&self.sheet_id, row, col)
+            .map_err(|_| Err::NotFound)?;
+        let style_id = self
+            .controller
+            .status
+            .container
+            .get_cell(self.sheet_id, &cell_id)
+            .map_or(0, |c| c.style);
+        let raw_style = self
+            .controller
+            .status
+            .style_manager
+            .get_cell_style(style_id);
+        Ok(num_fmt::format_value(
+            &value,
+            &raw_style.formatter,
+            self.controller.settings.date1904,
+        ))
+    }
+
     pub fn get_merge_cells(&mut self) -> Vec<MergeCell> {
         let merges = self
             .controller
@@ -339,6 +404,59 @@ impl<'a> Worksheet<'a> {
         }
     }
 
+    /// The references in the formulas of the cells from `start` to `end`,
+    /// given as (row, col). The references of the cells referred to are
+    /// included if `transitive`.
+    pub fn get_precedents(
+        &self,
+        start: (usize, usize),
+        end: (usize, usize),
+        transitive: bool,
+    ) -> Result<Vec<Dependency>, Err> {
+        let idx = self.get_sheet_idx()?;
+        self.controller
+            .get_precedents(idx, start, end, transitive)
+            .ok_or(Err::NotFound)
+    }
+
+    /// The formula cells and the defined names referring to the cells from
+    /// `start` to `end`, given as (row, col). The ones referring to them are
+    /// included if `transitive`.
+    pub fn get_dependents(
+        &self,
+        start: (usize, usize),
+        end: (usize, usize),
+        transitive: bool,
+    ) -> Result<Vec<Dependency>, Err> {
+        let idx = self.get_sheet_idx()?;
+        self.controller
+            .get_dependents(idx, start, end, transitive)
+            .ok_or(Err::NotFound)
+    }
+
+    /// Tokenize the formula, which has SKIPPED the '=' and may be partially
+    /// typed, as if it is in the cell at (`row`, `col`). The references are
+    /// resolved to the cells they denote.
+    pub fn get_formula_tokens(
+        &mut self,
+        row: usize,
+        col: usize,
+        formula: &str,
+    ) -> Result<Vec<FormulaToken>, Err> {
+        let idx = self.get_sheet_idx()?;
+        self.controller
+            .get_formula_tokens(idx, row, col, formula)
+            .ok_or(Err::NotFound)
+    }
+
+    fn get_sheet_idx(&self) -> Result<usize, Err> {
+        self.controller
+            .status
+            .sheet_pos_manager
+            .get_sheet_idx(&self.sheet_id)
+            .ok_or(Err::NotFound)
+    }
+
     /// Get the dimension of the sheet.
     pub fn get_sheet_dimension(&mut self) -> (usize, usize) {
         let sheet_container = self
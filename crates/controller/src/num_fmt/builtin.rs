@@ -0,0 +1,46 @@
+// The built-in number formats which are not written in the styles part.
+// The codes are the en-US ones.
+const BUILTIN_NUM_FMTS: [(u32, &str); 28] = [
+    (0, "General"),
+    (1, "0"),
+    (2, "0.00"),
+    (3, "#,##0"),
+    (4, "#,##0.00"),
+    (9, "0%"),
+    (10, "0.00%"),
+    (11, "0.00E+00"),
+    (12, "# ?/?"),
+    (13, "# ??/??"),
+    (14, "m/d/yyyy"),
+    (15, "d-mmm-yy"),
+    (16, "d-mmm"),
+    (17, "mmm-yy"),
+    (18, "h:mm AM/PM"),
+    (19, "h:mm:ss AM/PM"),
+    (20, "h:mm"),
+    (21, "h:mm:ss"),
+    (22, "m/d/yyyy h:mm"),
+    (37, "#,##0 ;(#,##0)"),
+    (38, "#,##0 ;[Red](#,##0)"),
+    (39, "#,##0.00;(#,##0.00)"),
+    (40, "#,##0.00;[Red](#,##0.00)"),
+    (45, "mm:ss"),
+    (46, "[h]:mm:ss"),
+    (47, "mmss.0"),
+    (48, "##0.0E+0"),
+    (49, "@"),
+];
+
+pub fn get_builtin_num_fmt(id: u32) -> Option<&'static str> {
+    BUILTIN_NUM_FMTS
+        .iter()
+        .find(|(i, _)| *i == id)
+        .map(|(_, code)| *code)
+}
+
+pub fn get_builtin_num_fmt_id(code: &str) -> Option<u32> {
+    BUILTIN_NUM_FMTS
+        .iter()
+        .find(|(_, c)| *c == code)
+        .map(|(id, _)| *id)
+}
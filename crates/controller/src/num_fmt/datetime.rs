@@ -0,0 +1,178 @@
+use logisheets_base::datetime::{get_date_by_serial_num_1900, EasyDate};
+
+use super::errors::NumFmtError;
+use super::tokens::{DateToken, Elapsed, Token};
+
+const MONTHS: [&str; 12] = [
+    "January",
+    "February",
+    "March",
+    "April",
+    "May",
+    "June",
+    "July",
+    "August",
+    "September",
+    "October",
+    "November",
+    "December",
+];
+
+const WEEKDAYS: [&str; 7] = [
+    "Sunday",
+    "Monday",
+    "Tuesday",
+    "Wednesday",
+    "Thursday",
+    "Friday",
+    "Saturday",
+];
+
+// The serial number of 9999-12-31 plus 1.
+const MAX_SERIAL: f64 = 2958466.;
+
+/// Renders a serial number in the 1900 date system with the tokens of a
+/// date or time section.
+pub fn format_datetime(tokens: &[Token], n: f64) -> Result<String, NumFmtError> {
+    if !(0. ..MAX_SERIAL).contains(&n) {
+        return Err(NumFmtError::InvalidDate(n));
+    }
+    let sub_digits = tokens
+        .iter()
+        .filter_map(|t| match t {
+            Token::Date(DateToken::SubSecond(c)) => Some((*c).min(3)),
+            _ => None,
+        })
+        .max()
+        .unwrap_or(0);
+    let scale = 10_u64.pow(sub_digits as u32);
+    // Round to the precision shown so that 59.999 seconds do not show 59.
+    let total = (n * 86400. * scale as f64).round() as u64;
+    let total_seconds = total / scale;
+    let sub_second = total % scale;
+    let days = total_seconds / 86400;
+    let seconds = total_seconds % 86400;
+    let (hour, minute, second) = (seconds / 3600, seconds / 60 % 60, seconds % 60);
+    let date = if days == 0 {
+        EasyDate {
+            year: 1900,
+            month: 1,
+            day: 0,
+        }
+    } else {
+        get_date_by_serial_num_1900(days as u32)
+    };
+    let has_am_pm = tokens
+        .iter()
+        .any(|t| matches!(t, Token::Date(DateToken::AmPm { .. })));
+    let res = tokens
+        .iter()
+        .map(|t| match t {
+            Token::Date(d) => match d {
+                DateToken::Year(c) if *c <= 2 => format!("{:02}", date.year % 100),
+                DateToken::Year(_) => format!("{:04}", date.year),
+                DateToken::Month(c) => {
+                    let name = MONTHS[date.month as usize - 1];
+                    match c {
+                        1 => date.month.to_string(),
+                        2 => format!("{:02}", date.month),
+                        3 => name[..3].to_string(),
+                        5 => name[..1].to_string(),
+                        _ => name.to_string(),
+                    }
+                }
+                DateToken::Day(c) => {
+                    let name = WEEKDAYS[((days + 6) % 7) as usize];
+                    match c {
+                        1 => date.day.to_string(),
+                        2 => format!("{:02}", date.day),
+                        3 => name[..3].to_string(),
+                        _ => name.to_string(),
+                    }
+                }
+                DateToken::Hour(c) => {
+                    let h = if has_am_pm {
+                        (hour + 11) % 12 + 1
+                    } else {
+                        hour
+                    };
+                    pad(h, *c)
+                }
+                DateToken::Minute(c) => pad(minute, *c),
+                DateToken::Second(c) => pad(second, *c),
+                DateToken::Elapsed(unit, c) => {
+                    let v = match unit {
+                        Elapsed::Hour => total_seconds / 3600,
+                        Elapsed::Minute => total_seconds / 60,
+                        Elapsed::Second => total_seconds,
+                    };
+                    format!("{:0width$}", v, width = c)
+                }
+                DateToken::SubSecond(c) => {
+                    let digits = format!("{:0width$}", sub_second, width = sub_digits);
+                    format!(".{:0<width$}", digits, width = c)
+                }
+                DateToken::AmPm { short, lower } => {
+                    let s = match (hour < 12, short) {
+                        (true, false) => "AM",
+                        (false, false) => "PM",
+                        (true, true) => "A",
+                        (false, true) => "P",
+                    };
+                    if *lower {
+                        s.to_lowercase()
+                    } else {
+                        s.to_string()
+                    }
+                }
+            },
+            t => t.as_literal().to_string(),
+        })
+        .collect();
+    Ok(res)
+}
+
+fn pad(v: u64, count: usize) -> String {
+    if count >= 2 {
+        format!("{:02}", v)
+    } else {
+        v.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::tokens::parse_sections;
+    use super::format_datetime;
+
+    fn fmt(code: &str, n: f64) -> String {
+        let sections = parse_sections(code).unwrap();
+        format_datetime(&sections[0].tokens, n).unwrap()
+    }
+
+    #[test]
+    fn date_test() {
+        assert_eq!(fmt("yyyy-mm-dd", 44927.), "2023-01-01");
+        assert_eq!(fmt("m/d/yy", 44927.), "1/1/23");
+        assert_eq!(fmt("dddd, mmmm d", 44927.), "Sunday, January 1");
+        assert_eq!(fmt("ddd mmm", 1.), "Sun Jan");
+        assert_eq!(fmt("yyyy-mm-dd", 60.), "1900-02-29");
+        assert_eq!(fmt("yyyy-mm-dd", 0.), "1900-01-00");
+    }
+
+    #[test]
+    fn time_test() {
+        assert_eq!(fmt("h:mm:ss", 0.5), "12:00:00");
+        assert_eq!(fmt("h:mm AM/PM", 0.75), "6:00 PM");
+        assert_eq!(fmt("hh:mm a/p", 0.25), "06:00 a");
+        assert_eq!(fmt("[h]:mm", 1.5), "36:00");
+        assert_eq!(fmt("mm:ss.00", 1.5 / 86400.), "00:01.50");
+        assert_eq!(fmt("[mm]:ss", 2. / 24.), "120:00");
+    }
+
+    #[test]
+    fn invalid_date_test() {
+        let sections = parse_sections("yyyy").unwrap();
+        assert!(format_datetime(&sections[0].tokens, -1.).is_err());
+    }
+}
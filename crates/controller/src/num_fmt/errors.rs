@@ -0,0 +1,13 @@
+use thiserror::Error;
+
+#[derive(Debug, Error, PartialEq)]
+pub enum NumFmtError {
+    #[error("a number format has at most 4 sections")]
+    TooManySections,
+    #[error("missing the closing {0}")]
+    Unclosed(char),
+    #[error("invalid bracket content: [{0}]")]
+    InvalidBracket(String),
+    #[error("the value cannot be shown as a date: {0}")]
+    InvalidDate(f64),
+}
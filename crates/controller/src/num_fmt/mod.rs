@@ -0,0 +1,191 @@
+//! Renders cell values to the texts shown in the cells with the number
+//! format codes, like `#,##0.00;[Red](#,##0.00)` or `yyyy-mm-dd h:mm`.
+
+mod builtin;
+mod datetime;
+mod errors;
+mod number;
+mod tokens;
+
+pub use builtin::{get_builtin_num_fmt, get_builtin_num_fmt_id};
+pub use errors::NumFmtError;
+pub use number::format_general;
+
+use crate::controller::display::{FormattedValue, Value};
+use logisheets_base::datetime::{get_serial_num_by_datetime, parse_iso_datetime};
+use tokens::{parse_sections, Section, Token};
+
+// The serial number of 1904-01-01 in the 1900 date system.
+const DATE1904_OFFSET: f64 = 1462.;
+
+/// Renders a displayed value. An invalid code or a number which does not fit
+/// the code falls back to `General`. Dates are rendered as their serial
+/// numbers in the date system of the workbook.
+pub fn format_value(value: &Value, code: &str, date1904: bool) -> FormattedValue {
+    let plain = |text: String| FormattedValue { text, color: None };
+    match value {
+        Value::Str(t) => format_text(code, t).unwrap_or_else(|_| plain(t.clone())),
+        Value::Bool(b) => plain(String::from(if *b { "TRUE" } else { "FALSE" })),
+        Value::Number(n) => {
+            format_number(code, *n, date1904).unwrap_or_else(|_| plain(format_general(*n)))
+        }
+        Value::Error(e) => plain(e.clone()),
+        Value::Date(d) => match parse_iso_datetime(d, date1904) {
+            Some(dt) => {
+                let n = get_serial_num_by_datetime(&dt.naive_local(), date1904);
+                format_number(code, n, date1904).unwrap_or_else(|_| plain(d.clone()))
+            }
+            None => plain(d.clone()),
+        },
+        Value::Empty => plain(String::new()),
+    }
+}
+
+/// Date and time sections read the number as a serial number in the 1904
+/// date system if `date1904` is set.
+pub fn format_number(code: &str, n: f64, date1904: bool) -> Result<FormattedValue, NumFmtError> {
+    if is_general(code) {
+        return Ok(FormattedValue {
+            text: format_general(n),
+            color: None,
+        });
+    }
+    let sections = parse_sections(code)?;
+    let (section, keep_sign) = choose_section(&sections, n);
+    let text = if section.is_datetime() {
+        let n = if keep_sign { n } else { n.abs() };
+        let n = if date1904 { n + DATE1904_OFFSET } else { n };
+        datetime::format_datetime(&section.tokens, n)?
+    } else if !section.has_number() && section.has_text() {
+        // A text section shows a number as if it were `General`.
+        format_general(if keep_sign { n } else { n.abs() })
+    } else {
+        let text = number::format_number(&section.tokens, n.abs());
+        if keep_sign && n < 0. {
+            format!("-{}", text)
+        } else {
+            text
+        }
+    };
+    Ok(FormattedValue {
+        text,
+        color: section.color.clone(),
+    })
+}
+
+/// Texts use the 4th section, or the only section if it contains `@`.
+/// Otherwise they are shown unchanged.
+pub fn format_text(code: &str, t: &str) -> Result<FormattedValue, NumFmtError> {
+    let unchanged = FormattedValue {
+        text: t.to_string(),
+        color: None,
+    };
+    if is_general(code) {
+        return Ok(unchanged);
+    }
+    let sections = parse_sections(code)?;
+    let section = match sections.len() {
+        4 => &sections[3],
+        1 if sections[0].has_text() => &sections[0],
+        _ => return Ok(unchanged),
+    };
+    let text = section
+        .tokens
+        .iter()
+        .map(|token| match token {
+            Token::Text => t,
+            token => token.as_literal(),
+        })
+        .collect();
+    Ok(FormattedValue {
+        text,
+        color: section.color.clone(),
+    })
+}
+
+fn is_general(code: &str) -> bool {
+    code.is_empty() || code.eq_ignore_ascii_case("general")
+}
+
+// Returns the section for the number and whether the number keeps its minus
+// sign. A negative number loses its sign when it has a section of its own.
+fn choose_section(sections: &[Section], n: f64) -> (&Section, bool) {
+    let numbers = if sections.len() == 4 {
+        &sections[..3]
+    } else {
+        sections
+    };
+    if numbers.iter().any(|s| s.condition.is_some()) {
+        let matched = numbers
+            .iter()
+            .enumerate()
+            .find(|(_, s)| match &s.condition {
+                Some(c) => c.matches(n),
+                None => true,
+            });
+        return match matched {
+            Some((idx, s)) => (s, idx != 1),
+            None => (numbers.last().unwrap(), true),
+        };
+    }
+    match numbers.len() {
+        1 => (&numbers[0], true),
+        2 if n < 0. => (&numbers[1], false),
+        2 => (&numbers[0], true),
+        _ if n > 0. => (&numbers[0], true),
+        _ if n < 0. => (&numbers[1], false),
+        _ => (&numbers[2], true),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{format_number, format_text, format_value};
+    use crate::controller::display::Value;
+
+    fn fmt(code: &str, n: f64) -> String {
+        format_number(code, n, false).unwrap().text
+    }
+
+    #[test]
+    fn sections_test() {
+        assert_eq!(fmt("0.00", -1.5), "-1.50");
+        assert_eq!(fmt("0.00;(0.00)", -1.5), "(1.50)");
+        assert_eq!(fmt("0;-0;\"zero\"", 0.), "zero");
+        assert_eq!(fmt("General", -0.5), "-0.5");
+        assert_eq!(fmt("[<1]\"small\";\"big\"", 0.5), "small");
+        assert_eq!(fmt("[<1]\"small\";\"big\"", 5.), "big");
+        let v = format_number("#,##0;[Red]-#,##0", -1234., false).unwrap();
+        assert_eq!(v.text, "-1,234");
+        assert_eq!(v.color.as_deref(), Some("Red"));
+    }
+
+    #[test]
+    fn text_test() {
+        let t = |code: &str, t: &str| format_text(code, t).unwrap().text;
+        assert_eq!(t("0;0;0;\"<\"@\">\"", "abc"), "<abc>");
+        assert_eq!(t("\"Name: \"@", "abc"), "Name: abc");
+        assert_eq!(t("0.00", "abc"), "abc");
+    }
+
+    #[test]
+    fn invalid_test() {
+        assert!(format_number("yyyy", -1., false).is_err());
+        assert!(format_number("[Foo]0", 1., false).is_err());
+    }
+
+    #[test]
+    fn date1904_test() {
+        assert_eq!(
+            format_number("yyyy-mm-dd", 0., true).unwrap().text,
+            "1904-01-01"
+        );
+        let date = Value::Date(String::from("2023-01-01T12:00:00+00:00"));
+        assert_eq!(
+            format_value(&date, "yyyy-mm-dd h:mm", true).text,
+            "2023-01-01 12:00"
+        );
+        assert_eq!(format_value(&date, "General", false).text, "44927.5");
+        assert_eq!(format_value(&date, "General", true).text, "43465.5");
+    }
+}
@@ -0,0 +1,465 @@
+use super::tokens::{Placeholder, Token};
+
+/// Renders a number the way the `General` format does. At most 10
+/// significant digits are shown and large or tiny numbers are shown in the
+/// scientific notation.
+pub fn format_general(n: f64) -> String {
+    if n == 0. {
+        return String::from("0");
+    }
+    let sign = if n < 0. { "-" } else { "" };
+    let abs = n.abs();
+    if !(1e-9..1e11).contains(&abs) {
+        let s = format!("{:.5e}", abs);
+        let (mantissa, exp) = s.split_once('e').unwrap();
+        let mantissa = trim_decimal_zeros(mantissa);
+        let exp = exp.parse::<i32>().unwrap();
+        let exp_sign = if exp < 0 { '-' } else { '+' };
+        return format!("{}{}E{}{:02}", sign, mantissa, exp_sign, exp.abs());
+    }
+    let int_len = if abs < 1. {
+        1
+    } else {
+        abs.log10().floor() as usize + 1
+    };
+    let (int, frac) = round_digits(abs, 10_usize.saturating_sub(int_len));
+    let int = if int.is_empty() {
+        String::from("0")
+    } else {
+        int
+    };
+    let frac = frac.trim_end_matches('0');
+    if frac.is_empty() {
+        format!("{}{}", sign, int)
+    } else {
+        format!("{}{}.{}", sign, int, frac)
+    }
+}
+
+/// Renders a non-negative number with the tokens of a number section.
+pub fn format_number(tokens: &[Token], n: f64) -> String {
+    let n = tokens
+        .iter()
+        .filter(|t| matches!(t, Token::Percent))
+        .fold(n, |n, _| n * 100.);
+    if let Some(idx) = tokens
+        .iter()
+        .position(|t| matches!(t, Token::Exponent { .. }))
+    {
+        return format_scientific(tokens, n, idx);
+    }
+    if let Some(idx) = find_fraction_slash(tokens) {
+        return format_fraction(tokens, n, idx);
+    }
+    format_decimal(tokens, n)
+}
+
+fn format_decimal(tokens: &[Token], n: f64) -> String {
+    let mut outputs = vec![None; tokens.len()];
+    let point = find_point(tokens, tokens.len());
+    let int_idx = digit_indices(tokens, 0, point);
+    let frac_idx = digit_indices(tokens, point, tokens.len());
+    let (grouping, scale) = get_commas(tokens, &int_idx, point);
+    let n = (0..scale).fold(n, |n, _| n / 1000.);
+    let (int, frac) = round_digits(n, frac_idx.len());
+    fill_integer(tokens, &int_idx, &int, grouping, &mut outputs);
+    fill_decimal(tokens, &frac_idx, &frac, &mut outputs);
+    render(tokens, &outputs, n)
+}
+
+// Engineering formats like `##0.0E+0` keep the exponent a multiple of the
+// count of integer placeholders.
+fn format_scientific(tokens: &[Token], n: f64, exp_idx: usize) -> String {
+    let mut outputs = vec![None; tokens.len()];
+    let point = find_point(tokens, exp_idx);
+    let int_idx = digit_indices(tokens, 0, point);
+    let frac_idx = digit_indices(tokens, point, exp_idx);
+    let exp_digits = digit_indices(tokens, exp_idx, tokens.len());
+    let int_count = int_idx.len().max(1);
+    let engineering = int_count > 1
+        && int_idx
+            .iter()
+            .any(|i| tokens[*i] == Token::Digit(Placeholder::Hash));
+    let step = if engineering { int_count as i32 } else { 1 };
+    let max_int_len = if engineering { step } else { int_count as i32 };
+    let mut exp = if n == 0. {
+        0
+    } else {
+        let s = format!("{:.14e}", n);
+        s.split_once('e').unwrap().1.parse::<i32>().unwrap()
+    };
+    exp = exp.div_euclid(step) * step - (max_int_len - step);
+    let (mut int, mut frac) = round_digits(n / 10_f64.powi(exp), frac_idx.len());
+    if int.len() as i32 > max_int_len {
+        exp += step;
+        let r = round_digits(n / 10_f64.powi(exp), frac_idx.len());
+        int = r.0;
+        frac = r.1;
+    }
+    fill_integer(tokens, &int_idx, &int, false, &mut outputs);
+    fill_decimal(tokens, &frac_idx, &frac, &mut outputs);
+    let exp_text = exp.abs().to_string();
+    let exp_text = if exp == 0 { "" } else { exp_text.as_str() };
+    fill_integer(tokens, &exp_digits, exp_text, false, &mut outputs);
+    let sign = match (exp < 0, &tokens[exp_idx]) {
+        (true, _) => "-",
+        (false, Token::Exponent { plus: true }) => "+",
+        _ => "",
+    };
+    outputs[exp_idx] = Some(format!("E{}", sign));
+    render(tokens, &outputs, n)
+}
+
+// Formats like `# ?/?`, `?/8` and `0 ??/??`. The denominator is either
+// the placeholders after the slash or a fixed number.
+fn format_fraction(tokens: &[Token], n: f64, slash: usize) -> String {
+    let mut outputs = vec![None; tokens.len()];
+    let den_idx = contiguous_digits(tokens, slash + 1..tokens.len());
+    let fixed_den = if den_idx.is_empty() {
+        match tokens.get(slash + 1) {
+            Some(Token::Literal(s)) => {
+                let digits = s
+                    .chars()
+                    .take_while(|c| c.is_ascii_digit())
+                    .collect::<String>();
+                digits.parse::<u32>().ok().filter(|d| *d > 0)
+            }
+            _ => None,
+        }
+    } else {
+        None
+    };
+    let num_idx = contiguous_digits(tokens, (0..slash).rev());
+    let num_start = num_idx.iter().min().copied().unwrap_or(slash);
+    let int_idx = digit_indices(tokens, 0, num_start);
+    let (mut whole, frac) = if int_idx.is_empty() {
+        (0., n)
+    } else {
+        (n.trunc(), n.fract())
+    };
+    let (mut num, den) = match fixed_den {
+        Some(d) => ((frac * d as f64).round(), d as f64),
+        None => best_fraction(frac, 10_u32.pow(den_idx.len() as u32) - 1),
+    };
+    if !int_idx.is_empty() && num == den {
+        whole += 1.;
+        num = 0.;
+    }
+    let whole_text = if whole == 0. && num != 0. {
+        String::new()
+    } else {
+        format!("{}", whole)
+    };
+    fill_integer(tokens, &int_idx, &whole_text, false, &mut outputs);
+    fill_integer(tokens, &num_idx, &format!("{}", num), false, &mut outputs);
+    if let Some(first) = den_idx.first() {
+        let den_text = format!("{}", den);
+        let padding = den_idx
+            .iter()
+            .skip(den_text.len())
+            .filter(|i| tokens[**i] == Token::Digit(Placeholder::Question))
+            .count();
+        outputs[*first] = Some(format!("{}{}", den_text, " ".repeat(padding)));
+        den_idx
+            .iter()
+            .skip(1)
+            .for_each(|i| outputs[*i] = Some(String::new()));
+    }
+    outputs[slash] = Some(String::from("/"));
+    // A whole number hides its fraction part behind the spaces.
+    if !int_idx.is_empty() && num == 0. {
+        let end = den_idx.last().copied().unwrap_or(slash + 1);
+        (num_start..=end.min(tokens.len() - 1)).for_each(|i| {
+            let len = match &outputs[i] {
+                Some(s) => s.chars().count(),
+                None => tokens[i].as_literal().chars().count(),
+            };
+            outputs[i] = Some(" ".repeat(len));
+        });
+    }
+    render(tokens, &outputs, n)
+}
+
+fn best_fraction(x: f64, max_den: u32) -> (f64, f64) {
+    let (num, den, _) = (1..=max_den.max(1))
+        .map(|d| {
+            let d = d as f64;
+            let num = (x * d).round();
+            (num, d, (x - num / d).abs())
+        })
+        .fold((x.round(), 1., f64::MAX), |prev, curr| {
+            if curr.2 < prev.2 {
+                curr
+            } else {
+                prev
+            }
+        });
+    (num, den)
+}
+
+fn find_fraction_slash(tokens: &[Token]) -> Option<usize> {
+    let idx = tokens.iter().position(|t| matches!(t, Token::Slash))?;
+    let before = idx > 0 && matches!(tokens[idx - 1], Token::Digit(_));
+    let after = match tokens.get(idx + 1) {
+        Some(Token::Digit(_)) => true,
+        Some(Token::Literal(s)) => s.starts_with(|c: char| c.is_ascii_digit()),
+        _ => false,
+    };
+    if before && after {
+        Some(idx)
+    } else {
+        None
+    }
+}
+
+fn find_point(tokens: &[Token], end: usize) -> usize {
+    tokens[..end]
+        .iter()
+        .position(|t| matches!(t, Token::DecimalPoint))
+        .unwrap_or(end)
+}
+
+fn digit_indices(tokens: &[Token], start: usize, end: usize) -> Vec<usize> {
+    (start..end)
+        .filter(|i| matches!(tokens[*i], Token::Digit(_)))
+        .collect()
+}
+
+fn contiguous_digits<I>(tokens: &[Token], range: I) -> Vec<usize>
+where
+    I: Iterator<Item = usize>,
+{
+    let mut res = range
+        .take_while(|i| matches!(tokens[*i], Token::Digit(_)))
+        .collect::<Vec<_>>();
+    res.sort_unstable();
+    res
+}
+
+// Returns whether the integer part uses thousands separators and how many
+// times the number is divided by 1000 because of the trailing commas.
+fn get_commas(tokens: &[Token], int_idx: &[usize], point: usize) -> (bool, usize) {
+    let (first, last) = match (int_idx.first(), int_idx.last()) {
+        (Some(f), Some(l)) => (*f, *l),
+        _ => return (false, 0),
+    };
+    let grouping = (first..last).any(|i| matches!(tokens[i], Token::Comma));
+    let scale = (last..point)
+        .filter(|i| matches!(tokens[*i], Token::Comma))
+        .count();
+    (grouping, scale)
+}
+
+// Rounds a non-negative number to `decimals` digits after the decimal point
+// half away from zero on its 15 significant digits. Returns the integer
+// digits without leading zeros and exactly `decimals` fraction digits.
+fn round_digits(n: f64, decimals: usize) -> (String, String) {
+    let s = format!("{:.14e}", n);
+    let (mantissa, exp) = s.split_once('e').unwrap();
+    let mut digits = mantissa
+        .chars()
+        .filter(|c| c.is_ascii_digit())
+        .map(|c| c as u8 - b'0')
+        .collect::<Vec<_>>();
+    let mut point = exp.parse::<i32>().unwrap() + 1;
+    let keep = point + decimals as i32;
+    if keep < 0 {
+        digits.clear();
+    } else if (keep as usize) < digits.len() {
+        let round_up = digits[keep as usize] >= 5;
+        digits.truncate(keep as usize);
+        if round_up {
+            let carried = digits.iter_mut().rev().try_for_each(|d| {
+                if *d == 9 {
+                    *d = 0;
+                    Ok(())
+                } else {
+                    *d += 1;
+                    Err(())
+                }
+            });
+            if carried.is_ok() {
+                digits.insert(0, 1);
+                point += 1;
+            }
+        }
+    }
+    let digit_at = |i: i32| -> char {
+        let d = if i < 0 {
+            0
+        } else {
+            digits.get(i as usize).copied().unwrap_or(0)
+        };
+        (b'0' + d) as char
+    };
+    let int = (0..point.max(0))
+        .map(digit_at)
+        .skip_while(|c| *c == '0')
+        .collect::<String>();
+    let frac = (point..point + decimals as i32)
+        .map(digit_at)
+        .collect::<String>();
+    (int, frac)
+}
+
+// Fills the integer placeholders from the right. The leftmost placeholder
+// takes all the remaining digits.
+fn fill_integer(
+    tokens: &[Token],
+    idx: &[usize],
+    digits: &str,
+    grouping: bool,
+    outputs: &mut [Option<String>],
+) {
+    let mut digits = digits.chars().collect::<Vec<_>>();
+    let mut filled = idx
+        .iter()
+        .rev()
+        .enumerate()
+        .map(|(j, i)| {
+            let s = if j == idx.len() - 1 {
+                digits.drain(..).collect::<String>()
+            } else {
+                digits.pop().map(String::from).unwrap_or_default()
+            };
+            if !s.is_empty() {
+                return (*i, s);
+            }
+            let s = match tokens[*i] {
+                Token::Digit(Placeholder::Zero) => String::from("0"),
+                Token::Digit(Placeholder::Question) => String::from(" "),
+                _ => String::new(),
+            };
+            (*i, s)
+        })
+        .collect::<Vec<_>>();
+    filled.reverse();
+    if !grouping {
+        filled.into_iter().for_each(|(i, s)| outputs[i] = Some(s));
+        return;
+    }
+    let text = filled.iter().map(|(_, s)| s.as_str()).collect::<String>();
+    let number_start = text.find(|c: char| c != ' ').unwrap_or(text.len());
+    let (spaces, number) = text.split_at(number_start);
+    let number = number.chars().collect::<Vec<_>>();
+    let groups = number
+        .rchunks(3)
+        .rev()
+        .map(|c| c.iter().collect::<String>())
+        .collect::<Vec<_>>();
+    filled
+        .iter()
+        .for_each(|(i, _)| outputs[*i] = Some(String::new()));
+    outputs[filled[0].0] = Some(format!("{}{}", spaces, groups.join(",")));
+}
+
+// Fills the fraction placeholders from the left. Trailing zeros are hidden
+// by `#` and replaced with spaces by `?`.
+fn fill_decimal(tokens: &[Token], idx: &[usize], digits: &str, outputs: &mut [Option<String>]) {
+    let mut hiding = true;
+    let digits = digits.chars().collect::<Vec<_>>();
+    idx.iter().zip(digits).rev().for_each(|(i, c)| {
+        let s = if hiding && c == '0' {
+            match tokens[*i] {
+                Token::Digit(Placeholder::Hash) => String::new(),
+                Token::Digit(Placeholder::Question) => String::from(" "),
+                _ => {
+                    hiding = false;
+                    String::from("0")
+                }
+            }
+        } else {
+            hiding = false;
+            c.to_string()
+        };
+        outputs[*i] = Some(s);
+    });
+}
+
+fn render(tokens: &[Token], outputs: &[Option<String>], n: f64) -> String {
+    tokens
+        .iter()
+        .zip(outputs.iter())
+        .map(|(t, o)| match (o, t) {
+            (Some(s), _) => s.clone(),
+            (None, Token::Comma) => String::new(),
+            (None, Token::General) => format_general(n),
+            (None, t) => t.as_literal().to_string(),
+        })
+        .collect()
+}
+
+fn trim_decimal_zeros(s: &str) -> &str {
+    if s.contains('.') {
+        s.trim_end_matches('0').trim_end_matches('.')
+    } else {
+        s
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::tokens::parse_sections;
+    use super::{format_general, format_number, round_digits};
+
+    fn fmt(code: &str, n: f64) -> String {
+        let sections = parse_sections(code).unwrap();
+        format_number(&sections[0].tokens, n)
+    }
+
+    #[test]
+    fn round_digits_test() {
+        assert_eq!(
+            round_digits(2.675, 2),
+            (String::from("2"), String::from("68"))
+        );
+        assert_eq!(round_digits(0.6, 0), (String::from("1"), String::new()));
+        assert_eq!(
+            round_digits(9.995, 2),
+            (String::from("10"), String::from("00"))
+        );
+        assert_eq!(
+            round_digits(0.0004, 3),
+            (String::new(), String::from("000"))
+        );
+    }
+
+    #[test]
+    fn general_test() {
+        assert_eq!(format_general(1. / 3.), "0.333333333");
+        assert_eq!(format_general(-1234.5), "-1234.5");
+        assert_eq!(format_general(123456789012.), "1.23457E+11");
+        assert_eq!(format_general(0.00000000012), "1.2E-10");
+    }
+
+    #[test]
+    fn decimal_test() {
+        assert_eq!(fmt("0.00", 3.14159), "3.14");
+        assert_eq!(fmt("#,##0", 1234567.8), "1,234,568");
+        assert_eq!(fmt("#,##0.00", 12.5), "12.50");
+        assert_eq!(fmt("#.##", 0.5), ".5");
+        assert_eq!(fmt("000", 7.), "007");
+        assert_eq!(fmt("0.0%", 0.125), "12.5%");
+        assert_eq!(fmt("#,##0,\"K\"", 12345.), "12K");
+        assert_eq!(fmt("$#,##0.00", 1000.), "$1,000.00");
+        assert_eq!(fmt("0.0??", 1.5), "1.5  ");
+        assert_eq!(fmt("000-0000", 5551234.), "555-1234");
+    }
+
+    #[test]
+    fn scientific_test() {
+        assert_eq!(fmt("0.00E+00", 12345.), "1.23E+04");
+        assert_eq!(fmt("0.00E+00", 0.00012), "1.20E-04");
+        assert_eq!(fmt("##0.0E+0", 12345.), "12.3E+3");
+        assert_eq!(fmt("0.0E+0", 9.99), "1.0E+1");
+    }
+
+    #[test]
+    fn fraction_test() {
+        assert_eq!(fmt("# ?/?", 1.5), "1 1/2");
+        assert_eq!(fmt("# ??/??", 3.14159), "3 14/99");
+        assert_eq!(fmt("?/?", 0.75), "3/4");
+        assert_eq!(fmt("# ?/8", 2.25), "2 2/8");
+        assert_eq!(fmt("# ?/?", 2.), "2    ");
+    }
+}
@@ -0,0 +1,451 @@
+use super::errors::NumFmtError;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Placeholder {
+    Zero,
+    Hash,
+    Question,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Elapsed {
+    Hour,
+    Minute,
+    Second,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DateToken {
+    Year(usize),
+    Month(usize),
+    Day(usize),
+    Hour(usize),
+    Minute(usize),
+    Second(usize),
+    // `[h]`, `[mm]` and `[ss]` with their widths.
+    Elapsed(Elapsed, usize),
+    // The digits of the fraction of a second, like `.00` in `ss.00`.
+    SubSecond(usize),
+    AmPm { short: bool, lower: bool },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Token {
+    Literal(String),
+    Digit(Placeholder),
+    DecimalPoint,
+    Comma,
+    Percent,
+    Exponent { plus: bool },
+    Slash,
+    Text,
+    General,
+    Date(DateToken),
+}
+
+impl Token {
+    /// The text of the token when it is not used as a placeholder.
+    pub fn as_literal(&self) -> &str {
+        match self {
+            Token::Literal(s) => s,
+            Token::Digit(Placeholder::Zero) => "0",
+            Token::Digit(Placeholder::Hash) => "#",
+            Token::Digit(Placeholder::Question) => "?",
+            Token::DecimalPoint => ".",
+            Token::Comma => ",",
+            Token::Percent => "%",
+            Token::Slash => "/",
+            _ => "",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CmpOp {
+    Eq,
+    Neq,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Condition {
+    pub op: CmpOp,
+    pub value: f64,
+}
+
+impl Condition {
+    pub fn matches(&self, n: f64) -> bool {
+        match self.op {
+            CmpOp::Eq => n == self.value,
+            CmpOp::Neq => n != self.value,
+            CmpOp::Lt => n < self.value,
+            CmpOp::Le => n <= self.value,
+            CmpOp::Gt => n > self.value,
+            CmpOp::Ge => n >= self.value,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Section {
+    pub tokens: Vec<Token>,
+    pub color: Option<String>,
+    pub condition: Option<Condition>,
+}
+
+impl Section {
+    pub fn is_datetime(&self) -> bool {
+        self.tokens.iter().any(|t| matches!(t, Token::Date(_)))
+    }
+
+    pub fn has_text(&self) -> bool {
+        self.tokens.iter().any(|t| matches!(t, Token::Text))
+    }
+
+    pub fn has_number(&self) -> bool {
+        self.tokens
+            .iter()
+            .any(|t| matches!(t, Token::Digit(_) | Token::General))
+    }
+}
+
+const COLORS: [&str; 8] = [
+    "Black", "Blue", "Cyan", "Green", "Magenta", "Red", "White", "Yellow",
+];
+
+pub fn parse_sections(code: &str) -> Result<Vec<Section>, NumFmtError> {
+    let sections = split_sections(code)?
+        .into_iter()
+        .map(|s| parse_section(&s))
+        .collect::<Result<Vec<_>, _>>()?;
+    if sections.len() > 4 {
+        return Err(NumFmtError::TooManySections);
+    }
+    Ok(sections)
+}
+
+fn split_sections(code: &str) -> Result<Vec<String>, NumFmtError> {
+    let mut sections = vec![];
+    let mut curr = String::new();
+    let mut chars = code.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            ';' => sections.push(std::mem::take(&mut curr)),
+            '"' | '[' => {
+                let end = if c == '"' { '"' } else { ']' };
+                curr.push(c);
+                loop {
+                    match chars.next() {
+                        Some(n) => {
+                            curr.push(n);
+                            if n == end {
+                                break;
+                            }
+                        }
+                        None => return Err(NumFmtError::Unclosed(end)),
+                    }
+                }
+            }
+            '\\' | '_' | '*' => {
+                curr.push(c);
+                if let Some(n) = chars.next() {
+                    curr.push(n);
+                }
+            }
+            _ => curr.push(c),
+        }
+    }
+    sections.push(curr);
+    Ok(sections)
+}
+
+fn parse_section(s: &str) -> Result<Section, NumFmtError> {
+    let chars = s.chars().collect::<Vec<_>>();
+    let mut section = Section::default();
+    let mut tokens = vec![];
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        i += 1;
+        match c {
+            '"' => {
+                let start = i;
+                while chars[i] != '"' {
+                    i += 1;
+                }
+                tokens.push(Token::Literal(chars[start..i].iter().collect()));
+                i += 1;
+            }
+            '\\' => {
+                if let Some(n) = chars.get(i) {
+                    tokens.push(Token::Literal(n.to_string()));
+                    i += 1;
+                }
+            }
+            // `_x` leaves a space as wide as `x`.
+            '_' => {
+                tokens.push(Token::Literal(String::from(" ")));
+                i += 1;
+            }
+            // `*x` fills the cell with `x`, which is not a part of the text.
+            '*' => i += 1,
+            '[' => {
+                let start = i;
+                while chars[i] != ']' {
+                    i += 1;
+                }
+                let content = chars[start..i].iter().collect::<String>();
+                i += 1;
+                parse_bracket(&content, &mut section, &mut tokens)?;
+            }
+            '0' => tokens.push(Token::Digit(Placeholder::Zero)),
+            '#' => tokens.push(Token::Digit(Placeholder::Hash)),
+            '?' => tokens.push(Token::Digit(Placeholder::Question)),
+            '.' => {
+                let zeros = chars[i..].iter().take_while(|c| **c == '0').count();
+                if zeros > 0 && last_is_second(&tokens) {
+                    tokens.push(Token::Date(DateToken::SubSecond(zeros)));
+                    i += zeros;
+                } else {
+                    tokens.push(Token::DecimalPoint);
+                }
+            }
+            ',' => tokens.push(Token::Comma),
+            '%' => tokens.push(Token::Percent),
+            '/' => tokens.push(Token::Slash),
+            '@' => tokens.push(Token::Text),
+            'E' | 'e' if matches!(chars.get(i), Some('+') | Some('-')) => {
+                tokens.push(Token::Exponent {
+                    plus: chars[i] == '+',
+                });
+                i += 1;
+            }
+            'G' | 'g' if starts_with_ignore_case(&chars[i - 1..], "general") => {
+                tokens.push(Token::General);
+                i += 6;
+            }
+            'A' | 'a' if starts_with_ignore_case(&chars[i - 1..], "am/pm") => {
+                tokens.push(Token::Date(DateToken::AmPm {
+                    short: false,
+                    lower: c == 'a',
+                }));
+                i += 4;
+            }
+            'A' | 'a' if starts_with_ignore_case(&chars[i - 1..], "a/p") => {
+                tokens.push(Token::Date(DateToken::AmPm {
+                    short: true,
+                    lower: c == 'a',
+                }));
+                i += 2;
+            }
+            'y' | 'Y' | 'm' | 'M' | 'd' | 'D' | 'h' | 'H' | 's' | 'S' => {
+                let lower = c.to_ascii_lowercase();
+                let mut count = 1;
+                while i < chars.len() && chars[i].to_ascii_lowercase() == lower {
+                    count += 1;
+                    i += 1;
+                }
+                let t = match lower {
+                    'y' => DateToken::Year(count),
+                    'm' => DateToken::Month(count),
+                    'd' => DateToken::Day(count),
+                    'h' => DateToken::Hour(count),
+                    _ => DateToken::Second(count),
+                };
+                tokens.push(Token::Date(t));
+            }
+            _ => tokens.push(Token::Literal(c.to_string())),
+        }
+    }
+    resolve_minutes(&mut tokens);
+    section.tokens = merge_literals(tokens);
+    Ok(section)
+}
+
+fn parse_bracket(
+    content: &str,
+    section: &mut Section,
+    tokens: &mut Vec<Token>,
+) -> Result<(), NumFmtError> {
+    let lower = content.to_lowercase();
+    if let Some(color) = COLORS.iter().find(|c| c.to_lowercase() == lower) {
+        section.color = Some(color.to_string());
+        return Ok(());
+    }
+    if let Some(n) = lower.strip_prefix("color") {
+        return match n.parse::<u8>() {
+            Ok(n) if (1..=56).contains(&n) => {
+                section.color = Some(format!("Color{}", n));
+                Ok(())
+            }
+            _ => Err(NumFmtError::InvalidBracket(content.to_string())),
+        };
+    }
+    // `[$€-407]` is a currency symbol with a locale and `[$-409]` is a
+    // locale only.
+    if let Some(currency) = content.strip_prefix('$') {
+        let symbol = match currency.find('-') {
+            Some(idx) => &currency[..idx],
+            None => currency,
+        };
+        if !symbol.is_empty() {
+            tokens.push(Token::Literal(symbol.to_string()));
+        }
+        return Ok(());
+    }
+    if let Some(condition) = parse_condition(content) {
+        section.condition = Some(condition);
+        return Ok(());
+    }
+    let first = lower.chars().next();
+    if let Some(f) = first {
+        if lower.chars().all(|c| c == f) {
+            let unit = match f {
+                'h' => Some(Elapsed::Hour),
+                'm' => Some(Elapsed::Minute),
+                's' => Some(Elapsed::Second),
+                _ => None,
+            };
+            if let Some(unit) = unit {
+                tokens.push(Token::Date(DateToken::Elapsed(unit, lower.len())));
+                return Ok(());
+            }
+        }
+    }
+    Err(NumFmtError::InvalidBracket(content.to_string()))
+}
+
+fn parse_condition(content: &str) -> Option<Condition> {
+    let ops = [
+        ("<=", CmpOp::Le),
+        (">=", CmpOp::Ge),
+        ("<>", CmpOp::Neq),
+        ("<", CmpOp::Lt),
+        (">", CmpOp::Gt),
+        ("=", CmpOp::Eq),
+    ];
+    ops.iter().find_map(|(s, op)| {
+        let value = content.strip_prefix(s)?.trim().parse::<f64>().ok()?;
+        Some(Condition { op: *op, value })
+    })
+}
+
+fn starts_with_ignore_case(chars: &[char], s: &str) -> bool {
+    chars.len() >= s.len()
+        && chars
+            .iter()
+            .zip(s.chars())
+            .all(|(a, b)| a.to_ascii_lowercase() == b)
+}
+
+fn last_is_second(tokens: &[Token]) -> bool {
+    matches!(
+        tokens.last(),
+        Some(Token::Date(DateToken::Second(_)))
+            | Some(Token::Date(DateToken::Elapsed(Elapsed::Second, _)))
+    )
+}
+
+// `m` and `mm` mean minutes rather than months when they follow hours or
+// precede seconds.
+fn resolve_minutes(tokens: &mut [Token]) {
+    let date_positions = tokens
+        .iter()
+        .enumerate()
+        .filter_map(|(i, t)| match t {
+            Token::Date(d) => Some((i, *d)),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    date_positions
+        .iter()
+        .enumerate()
+        .for_each(|(j, (i, d))| match d {
+            DateToken::Month(n) if *n <= 2 => {
+                let after_hour = j > 0
+                    && matches!(
+                        date_positions[j - 1].1,
+                        DateToken::Hour(_) | DateToken::Elapsed(Elapsed::Hour, _)
+                    );
+                let before_second = matches!(
+                    date_positions.get(j + 1),
+                    Some((_, DateToken::Second(_)))
+                        | Some((_, DateToken::Elapsed(Elapsed::Second, _)))
+                );
+                if after_hour || before_second {
+                    tokens[*i] = Token::Date(DateToken::Minute(*n));
+                }
+            }
+            _ => {}
+        });
+}
+
+fn merge_literals(tokens: Vec<Token>) -> Vec<Token> {
+    tokens.into_iter().fold(vec![], |mut prev, t| {
+        match (prev.last_mut(), t) {
+            (Some(Token::Literal(last)), Token::Literal(s)) => last.push_str(&s),
+            (_, t) => prev.push(t),
+        }
+        prev
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_sections, CmpOp, DateToken, Placeholder, Token};
+
+    #[test]
+    fn parse_sections_test() {
+        let sections = parse_sections("[Red][<=100]0.0;\"a;b\"@").unwrap();
+        assert_eq!(sections.len(), 2);
+        assert_eq!(sections[0].color.as_deref(), Some("Red"));
+        assert_eq!(sections[0].condition.unwrap().op, CmpOp::Le);
+        assert_eq!(
+            sections[0].tokens,
+            vec![
+                Token::Digit(Placeholder::Zero),
+                Token::DecimalPoint,
+                Token::Digit(Placeholder::Zero)
+            ]
+        );
+        assert_eq!(
+            sections[1].tokens,
+            vec![Token::Literal(String::from("a;b")), Token::Text]
+        );
+    }
+
+    #[test]
+    fn minutes_test() {
+        let sections = parse_sections("h:mm m/d [mm]:ss.00").unwrap();
+        let dates = sections[0]
+            .tokens
+            .iter()
+            .filter_map(|t| match t {
+                Token::Date(d) => Some(*d),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            dates,
+            vec![
+                DateToken::Hour(1),
+                DateToken::Minute(2),
+                DateToken::Month(1),
+                DateToken::Day(1),
+                DateToken::Elapsed(super::Elapsed::Minute, 2),
+                DateToken::Second(2),
+                DateToken::SubSecond(2),
+            ]
+        );
+    }
+
+    #[test]
+    fn invalid_test() {
+        assert!(parse_sections("0;0;0;0;0").is_err());
+        assert!(parse_sections("\"abc").is_err());
+        assert!(parse_sections("[Purple]0").is_err());
+    }
+}
@@ -14,4 +14,12 @@ pub enum Process {
     SheetShift(SheetShiftPayload),
     SheetRename(SheetRenamePayload),
     Recalc(Vec<(SheetId, CellId)>),
+    // The volatile formulas are calculated in every transaction, so nothing
+    // else is calculated.
+    RecalcVolatile,
+    RecalcSheet(SheetId),
+    // Build the dependency graph again and calculate all the formulas.
+    RecalcAll,
+    // The stale formulas in the manual calculation mode.
+    RecalcStale,
 }
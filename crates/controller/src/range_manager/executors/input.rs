@@ -19,7 +19,10 @@ where
 {
     let mut normal_func = |range: &NormalRange, _: &RangeId| -> RangeUpdateType {
         match range {
-            NormalRange::Single(_) => RangeUpdateType::None,
+            NormalRange::Single(id) => match ctx.fetch_normal_cell_index(&sheet, id) {
+                Ok(idx) if idx == (row, col) => RangeUpdateType::Dirty,
+                _ => RangeUpdateType::None,
+            },
             NormalRange::RowRange(start, end) => {
                 let start_idx = ctx.fetch_row_index(&sheet, start).unwrap();
                 let end_idx = ctx.fetch_row_index(&sheet, end).unwrap();
@@ -32,7 +35,7 @@ where
             NormalRange::ColRange(start, end) => {
                 let start_idx = ctx.fetch_col_index(&sheet, start).unwrap();
                 let end_idx = ctx.fetch_col_index(&sheet, end).unwrap();
-                if start_idx <= row && row <= end_idx {
+                if start_idx <= col && col <= end_idx {
                     RangeUpdateType::Dirty
                 } else {
                     RangeUpdateType::None
@@ -53,7 +56,10 @@ where
 
     let mut block_range_func = |range: &BlockRange, _: &RangeId| -> RangeUpdateType {
         match range {
-            BlockRange::Single(_) => RangeUpdateType::None,
+            BlockRange::Single(id) => match ctx.fetch_block_cell_index(&sheet, id) {
+                Ok(idx) if idx == (row, col) => RangeUpdateType::Dirty,
+                _ => RangeUpdateType::None,
+            },
             BlockRange::AddrRange(start, end) => {
                 let (start_row, start_col) = ctx.fetch_block_cell_index(&sheet, start).unwrap();
                 let (end_row, end_col) = ctx.fetch_block_cell_index(&sheet, end).unwrap();
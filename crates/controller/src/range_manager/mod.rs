@@ -56,6 +56,14 @@ impl RangeManager {
         }
     }
 
+    /// The ids of the ranges in the sheet which are not single cells.
+    pub fn get_area_ids(&self, sheet_id: &SheetId) -> Vec<RangeId> {
+        match self.data.get(sheet_id) {
+            Some(sheet_manager) => sheet_manager.area_ids.iter().copied().collect(),
+            None => vec![],
+        }
+    }
+
     pub fn execute_sheet_proc<C>(self, proc: SheetProcess, ctx: &mut C) -> Result<RangeExecContext>
     where
         C: IdFetcherTrait + IndexFetcherTrait + BlockAffectTrait,
@@ -223,6 +231,8 @@ pub struct SheetRangeManager {
     normal_range_to_id: HashMap<NormalRange, RangeId>,
     id_to_block_range: HashMap<RangeId, BlockRange>,
     block_range_to_id: HashMap<BlockRange, RangeId>,
+    // The ids of the ranges which are not single cells.
+    area_ids: HashSet<RangeId>,
     next_id: RangeId,
 }
 
@@ -233,6 +243,7 @@ impl SheetRangeManager {
             normal_range_to_id: HashMap::new(),
             id_to_block_range: HashMap::new(),
             block_range_to_id: HashMap::new(),
+            area_ids: HashSet::new(),
             next_id: 0,
         }
     }
@@ -261,6 +272,7 @@ impl SheetRangeManager {
         if let Some(range) = self.id_to_block_range.remove(range_id) {
             self.block_range_to_id.remove(&range);
         }
+        self.area_ids.remove(range_id);
     }
 
     pub fn get_range_id(&mut self, range: &Range) -> RangeId {
@@ -270,6 +282,9 @@ impl SheetRangeManager {
                 None => {
                     let r = normal_range.clone();
                     let id = self.next_id;
+                    if !matches!(r, NormalRange::Single(_)) {
+                        self.area_ids.insert(id);
+                    }
                     self.normal_range_to_id.insert(r.clone(), id);
                     self.id_to_normal_range.insert(id, r);
                     self.next_id += 1;
@@ -281,6 +296,9 @@ impl SheetRangeManager {
                 None => {
                     let r = block_range.clone();
                     let id = self.next_id;
+                    if !matches!(r, BlockRange::Single(_)) {
+                        self.area_ids.insert(id);
+                    }
                     self.block_range_to_id.insert(r.clone(), id);
                     self.id_to_block_range.insert(id, r);
                     self.next_id += 1;
@@ -398,6 +416,7 @@ impl SheetRangeExecContext {
             if let Some(data) = manager.id_to_normal_range.get(&range_id) {
                 manager.normal_range_to_id.remove(data);
                 manager.id_to_normal_range.remove(&range_id);
+                manager.area_ids.remove(&range_id);
                 calc_updates.add_removed_range(range_id);
             }
         });
@@ -445,6 +464,7 @@ impl SheetRangeExecContext {
             if let Some(data) = manager.id_to_normal_range.get(&range_id) {
                 manager.normal_range_to_id.remove(data);
                 manager.id_to_normal_range.remove(&range_id);
+                manager.area_ids.remove(&range_id);
                 calc_updates.add_removed_range(range_id);
             }
         });
@@ -0,0 +1,241 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use logisheets_astchecker::{
+    get_builtin_checker, get_builtin_func, ArgCount, ArgDef, AstChecker, FuncSignature,
+};
+
+/// A calculated argument or the result of a native function.
+#[derive(Debug, Clone, PartialEq)]
+pub enum NativeValue {
+    Blank,
+    Number(f64),
+    Text(String),
+    Boolean(bool),
+    /// An error value like `#N/A`.
+    Error(String),
+    /// The values of a range by rows. A result of an array spills.
+    Array(Vec<Vec<NativeValue>>),
+}
+
+pub type NativeFuncImpl = dyn Fn(Vec<NativeValue>) -> NativeValue + Send + Sync;
+
+/// A function implemented in Rust and the signature to check its arguments.
+#[derive(Clone)]
+pub struct NativeFunc {
+    pub arg_count: ArgCount,
+    pub args: Vec<ArgDef>,
+    pub func: Arc<NativeFuncImpl>,
+}
+
+/// How the result string of an async function is decoded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AsyncReturnType {
+    Number,
+    Text,
+    Boolean,
+    /// An array written like an array constant, e.g. `{1,"a";TRUE,2}`. It
+    /// spills.
+    Matrix,
+}
+
+/// A function calculated by the embedders out of the transaction, like
+/// fetching data from the web.
+#[derive(Debug, Clone)]
+pub struct AsyncFunc {
+    pub return_type: AsyncReturnType,
+    /// How long a result is reused for the same arguments. The results never
+    /// expire if it is none.
+    pub ttl: Option<Duration>,
+}
+
+/// The functions registered by the embedders. They are looked up before the
+/// built-in functions so that they can be replaced.
+#[derive(Clone, Default)]
+pub struct FuncRegistry {
+    funcs: HashMap<String, NativeFunc>,
+    async_funcs: HashMap<String, AsyncFunc>,
+    // The checker of the built-in functions which are not replaced. It is
+    // none if no built-in function is replaced.
+    checker: Option<Arc<AstChecker>>,
+}
+
+impl FuncRegistry {
+    /// Register a function under the name, which is case-insensitive. The
+    /// function registered before under the same name is replaced.
+    pub fn register<F>(&mut self, name: &str, arg_count: ArgCount, args: Vec<ArgDef>, func: F)
+    where
+        F: Fn(Vec<NativeValue>) -> NativeValue + Send + Sync + 'static,
+    {
+        let native = NativeFunc {
+            arg_count,
+            args,
+            func: Arc::new(func),
+        };
+        self.funcs.insert(name.to_uppercase(), native);
+        self.update_checker();
+    }
+
+    pub fn unregister(&mut self, name: &str) -> bool {
+        let removed = self.funcs.remove(&name.to_uppercase()).is_some();
+        self.update_checker();
+        removed
+    }
+
+    pub fn get(&self, name: &str) -> Option<&NativeFunc> {
+        self.funcs.get(&name.to_uppercase())
+    }
+
+    /// The registered functions with their names in upper case.
+    pub fn get_all(&self) -> impl Iterator<Item = (&str, &NativeFunc)> {
+        self.funcs.iter().map(|(name, f)| (name.as_str(), f))
+    }
+
+    /// Register an async function under the name, which is case-insensitive.
+    pub fn register_async(
+        &mut self,
+        name: &str,
+        return_type: AsyncReturnType,
+        ttl: Option<Duration>,
+    ) {
+        let func = AsyncFunc { return_type, ttl };
+        self.async_funcs.insert(name.to_uppercase(), func);
+        self.update_checker();
+    }
+
+    pub fn unregister_async(&mut self, name: &str) -> bool {
+        let removed = self.async_funcs.remove(&name.to_uppercase()).is_some();
+        self.update_checker();
+        removed
+    }
+
+    pub fn get_async(&self, name: &str) -> Option<&AsyncFunc> {
+        self.async_funcs.get(&name.to_uppercase())
+    }
+
+    /// The signatures for `AstChecker`, keyed by the names.
+    pub fn get_signatures(&self) -> HashMap<String, FuncSignature> {
+        self.funcs
+            .iter()
+            .map(|(name, f)| {
+                let signature = FuncSignature {
+                    arg_count: f.arg_count.clone(),
+                    args: f.args.clone(),
+                };
+                (name.clone(), signature)
+            })
+            .collect()
+    }
+
+    /// The checker of the built-in functions which are not replaced by the
+    /// registered ones. It is only rebuilt when the functions are registered
+    /// or unregistered.
+    pub fn get_builtin_checker(&self) -> &AstChecker {
+        match &self.checker {
+            Some(checker) => checker,
+            None => get_builtin_checker(),
+        }
+    }
+
+    fn update_checker(&mut self) {
+        let mut replaced = self
+            .funcs
+            .keys()
+            .chain(self.async_funcs.keys())
+            .filter(|name| get_builtin_func(name).is_some())
+            .peekable();
+        if replaced.peek().is_none() {
+            self.checker = None;
+            return;
+        }
+        let mut checker = get_builtin_checker().clone();
+        replaced.for_each(|name| {
+            checker.remove(name);
+        });
+        self.checker = Some(Arc::new(checker));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::controller::Controller;
+    use crate::test_utils::{get_value, input};
+    use logisheets_base::CellValue;
+
+    #[test]
+    fn register_func_test() {
+        use crate::{ArgCount, ArgDef, NativeValue};
+        let mut wb = Controller::default();
+        let arg_count = ArgCount {
+            le: None,
+            ge: None,
+            eq: Some(1),
+            odd: None,
+            even: None,
+        };
+        let args = vec![ArgDef {
+            arg_name: String::from("values"),
+            ref_only: None,
+            start_repeated: None,
+        }];
+        wb.register_func("sumsq", arg_count, args, |args| match &args[0] {
+            NativeValue::Number(n) => NativeValue::Number(n * n),
+            NativeValue::Array(rows) => NativeValue::Number(
+                rows.iter()
+                    .flatten()
+                    .map(|v| match v {
+                        NativeValue::Number(n) => n * n,
+                        _ => 0.,
+                    })
+                    .sum(),
+            ),
+            _ => NativeValue::Error(String::from("#VALUE!")),
+        });
+        let arg_count = ArgCount {
+            le: None,
+            ge: None,
+            eq: Some(1),
+            odd: None,
+            even: None,
+        };
+        let args = vec![ArgDef {
+            arg_name: String::from("values"),
+            ref_only: None,
+            start_repeated: None,
+        }];
+        wb.register_func("rowcount", arg_count, args, |args| match &args[0] {
+            NativeValue::Array(rows) => NativeValue::Number(rows.len() as f64),
+            _ => NativeValue::Error(String::from("#VALUE!")),
+        });
+        input(&mut wb, 0, 0, 0, "3");
+        input(&mut wb, 0, 1, 0, "4");
+        input(&mut wb, 0, 0, 1, "=SUMSQ(A1:A2)");
+        input(&mut wb, 0, 1, 1, "=SumSq(2)");
+        input(&mut wb, 0, 2, 1, "=SUMSQ(1, 2)");
+        input(&mut wb, 0, 3, 1, "=ROWCOUNT(A:A)");
+        // The edits calling the built-in functions with wrong arguments are
+        // rejected.
+        input(&mut wb, 0, 4, 1, "5");
+        assert!(input(&mut wb, 0, 4, 1, "=ABS(1, 2)").is_none());
+        assert!(input(&mut wb, 0, 4, 1, "=SUM(ABS())").is_none());
+        assert!(wb.get_ast_checker().check_arg_count("_xlfn.abs", 1));
+        assert!(matches!(get_value(&mut wb, 0, 0, 1), CellValue::Number(n) if n == 25.));
+        assert!(matches!(get_value(&mut wb, 0, 1, 1), CellValue::Number(n) if n == 4.));
+        assert!(matches!(get_value(&mut wb, 0, 2, 1), CellValue::Error(_)));
+        assert!(matches!(get_value(&mut wb, 0, 3, 1), CellValue::Number(n) if n == 2.));
+        assert!(matches!(get_value(&mut wb, 0, 4, 1), CellValue::Number(n) if n == 5.));
+        let checker = wb.get_ast_checker();
+        let func_id = wb.status.func_id_manager.get_func_id("sumsq");
+        let func_id_manager = &wb.status.func_id_manager;
+        let node = logisheets_parser::ast::Node {
+            pure: logisheets_parser::ast::PureNode::Func(logisheets_parser::ast::Func {
+                op: logisheets_parser::ast::Operator::Function(func_id),
+                args: vec![],
+            }),
+            bracket: false,
+        };
+        let fetch_func_name = |id: &logisheets_base::FuncId| func_id_manager.get_string(id);
+        assert!(checker.func_check(&node, &fetch_func_name).is_err());
+    }
+}
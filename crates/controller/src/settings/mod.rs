@@ -1,42 +1,135 @@
-use std::collections::{HashMap, HashSet};
+use std::collections::HashMap;
 
-use logisheets_base::SheetId;
-use logisheets_workbook::prelude::CtSheetFormatPr;
+use logisheets_base::{SheetId, TableId};
+use logisheets_workbook::prelude::{
+    CtAutoFilter, CtBookViews, CtColors, CtConditionalFormatting, CtDataValidations,
+    CtDefinedNames, CtDrawing, CtDxfs, CtHeaderFooter, CtHyperlinks, CtLegacyDrawing,
+    CtPageMargins, CtPageSetup, CtPivotCaches, CtPrintOptions, CtRelationship,
+    CtSheetBackgroundPicture, CtSheetFormatPr, CtSheetPr, CtSheetProtection, CtSheetViews,
+    CtTableStyles, CtWorkbookPr, MetadataPart, Passthrough, TablePart,
+};
 
 use crate::theme_manager::ThemeManager;
 
+pub mod func_registry;
+use func_registry::{AsyncReturnType, FuncRegistry};
+
 pub struct Settings {
     pub sheet_format_pr: HashMap<SheetId, CtSheetFormatPr>,
     pub calc_config: CalcConfig,
-    pub async_funcs: HashSet<String>, // function names in upper case.
+    pub func_registry: FuncRegistry,
     pub theme: ThemeManager,
+    pub passthrough: BookPassthrough,
+    /// Whether the serial numbers of dates count from 1904-01-01 instead of
+    /// 1900-01-01.
+    pub date1904: bool,
+    /// The notation of the references in the formulas read and input.
+    pub reference_style: ReferenceStyle,
 }
 
 impl Default for Settings {
     fn default() -> Self {
         let calc_config = CalcConfig::default();
         let sheet_format_pr = HashMap::<SheetId, CtSheetFormatPr>::new();
-        let afuncs = vec!["BAIDUHOTSEARCH".to_string()];
+        let mut func_registry = FuncRegistry::default();
+        func_registry.register_async("BAIDUHOTSEARCH", AsyncReturnType::Text, None);
         Settings {
             sheet_format_pr,
             calc_config,
-            async_funcs: afuncs.into_iter().collect(),
+            func_registry,
             theme: ThemeManager::default(),
+            passthrough: BookPassthrough::default(),
+            date1904: false,
+            reference_style: ReferenceStyle::A1,
         }
     }
 }
 
 #[derive(Debug, Clone)]
 pub struct CalcConfig {
+    /// Whether the circular references are calculated iteratively. If not,
+    /// the cells in them get errors.
+    pub iterate: bool,
     pub iter_limit: u16,
     pub error: f32,
+    pub calc_mode: CalcMode,
 }
 
 impl Default for CalcConfig {
     fn default() -> Self {
         CalcConfig {
+            iterate: false,
             iter_limit: 1000,
             error: 0.01,
+            calc_mode: CalcMode::Auto,
         }
     }
 }
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CalcMode {
+    Auto,
+    /// Data tables are not supported, so it works like `Auto`.
+    AutoNoTable,
+    /// The formulas are only calculated on demand. The cells that need to be
+    /// calculated are kept as stale.
+    Manual,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReferenceStyle {
+    A1,
+    /// The references are relative to the cell of the formula, like
+    /// `R[-1]C`.
+    R1C1,
+}
+
+/// The parts that we do not handle, like charts and pivot tables, and the
+/// elements that refer to them. They are written back unchanged when saving.
+#[derive(Debug, Clone, Default)]
+pub struct BookPassthrough {
+    /// The relationships of the worksheets in the store are moved to `sheets`.
+    pub store: Passthrough,
+    /// The properties of the workbook. Its `date1904` is taken from the
+    /// settings when saving.
+    pub workbook_pr: Option<CtWorkbookPr>,
+    pub book_views: Option<CtBookViews>,
+    pub pivot_caches: Option<CtPivotCaches>,
+    pub sheets: HashMap<SheetId, SheetPassthrough>,
+    /// The cell metadata part. The array formulas that spill refer to the
+    /// dynamic array properties in it by their `cm`.
+    pub metadata: Option<MetadataPart>,
+    /// The defined names in the file. Their attributes are kept when saving,
+    /// and the ones that can not be parsed are saved as they are.
+    pub defined_names: Option<CtDefinedNames>,
+    /// The loaded table parts. Their ranges and columns are taken from the
+    /// table manager when saving.
+    pub tables: HashMap<TableId, TablePart>,
+    /// The differential formats in the stylesheet, which the conditional
+    /// formats and the table styles refer to by their indexes.
+    pub dxfs: Option<CtDxfs>,
+    pub table_styles: Option<CtTableStyles>,
+    pub colors: Option<CtColors>,
+}
+
+/// The elements of a worksheet part that we do not handle. The references in
+/// them are not updated when the rows or the columns are changed.
+#[derive(Debug, Clone, Default)]
+pub struct SheetPassthrough {
+    pub relationships: Vec<CtRelationship>,
+    pub sheet_pr: Option<CtSheetPr>,
+    pub sheet_views: Option<CtSheetViews>,
+    pub sheet_protection: Option<CtSheetProtection>,
+    pub auto_filter: Option<CtAutoFilter>,
+    pub conditional_formatting: Vec<CtConditionalFormatting>,
+    pub data_validations: Option<CtDataValidations>,
+    pub hyperlinks: Option<CtHyperlinks>,
+    pub print_options: Option<CtPrintOptions>,
+    pub page_margins: Option<CtPageMargins>,
+    pub page_setup: Option<CtPageSetup>,
+    pub header_footer: Option<CtHeaderFooter>,
+    pub drawing: Option<CtDrawing>,
+    pub legacy_drawing: Option<CtLegacyDrawing>,
+    pub legacy_drawing_hf: Option<CtLegacyDrawing>,
+    pub picture: Option<CtSheetBackgroundPicture>,
+}
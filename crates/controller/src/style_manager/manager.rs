@@ -38,6 +38,22 @@ where
         self.id_to_data.get(&id)
     }
 
+    // Get all the data in the order of their ids.
+    pub fn get_all_data(&self) -> Vec<(I, &T)> {
+        let mut result = self
+            .id_to_data
+            .iter()
+            .map(|(id, data)| (*id, data))
+            .collect::<Vec<_>>();
+        result.sort_by_key(|(id, _)| id.to_u64().unwrap_or(0));
+        result
+    }
+
+    // Remove all the data, including the default one, and start from 0 again.
+    pub fn clear(&mut self) {
+        *self = Manager::new(NumCast::from(0usize).unwrap());
+    }
+
     fn registry(&mut self, data: T) -> I {
         let r = self.next_available;
         self.data_to_id.insert(data.clone(), self.next_available);
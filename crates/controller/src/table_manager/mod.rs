@@ -0,0 +1,434 @@
+use im::HashMap;
+use logisheets_base::{
+    id_fetcher::IdFetcherTrait, index_fetcher::IndexFetcherTrait, Addr, CellId, SheetId, Table,
+    TableId,
+};
+
+/// A table whose corners are kept as the cell ids, so that it grows when
+/// rows or columns are inserted inside it.
+#[derive(Debug, Clone)]
+pub struct TableDef {
+    pub name: String,
+    pub sheet_id: SheetId,
+    pub start: CellId,
+    pub end: CellId,
+    pub header_row_count: usize,
+    pub totals_row_count: usize,
+    pub columns: Vec<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct TableManager {
+    tables: HashMap<TableId, TableDef>,
+    next_id: TableId,
+}
+
+impl TableManager {
+    pub fn new() -> Self {
+        TableManager {
+            tables: HashMap::new(),
+            next_id: 0,
+        }
+    }
+
+    pub fn add_table(&mut self, table: TableDef) -> TableId {
+        let id = self.next_id;
+        self.tables.insert(id, table);
+        self.next_id += 1;
+        id
+    }
+
+    /// Table names are case-insensitive.
+    pub fn get_table_id(&self, name: &str) -> Option<TableId> {
+        self.tables
+            .iter()
+            .find(|(_, t)| t.name.eq_ignore_ascii_case(name))
+            .map(|(id, _)| *id)
+    }
+
+    pub fn get_table_name(&self, table_id: &TableId) -> Option<String> {
+        Some(self.tables.get(table_id)?.name.clone())
+    }
+
+    /// Returns none if a corner of the table has been deleted.
+    pub fn get_table<F>(&self, table_id: &TableId, fetcher: &mut F) -> Option<Table>
+    where
+        F: IndexFetcherTrait,
+    {
+        let t = self.tables.get(table_id)?;
+        let start = fetcher.fetch_cell_index(&t.sheet_id, &t.start).ok()?;
+        let end = fetcher.fetch_cell_index(&t.sheet_id, &t.end).ok()?;
+        Some(Table {
+            id: *table_id,
+            sheet_id: t.sheet_id,
+            start: Addr {
+                row: start.0,
+                col: start.1,
+            },
+            end: Addr {
+                row: end.0,
+                col: end.1,
+            },
+            header_row_count: t.header_row_count,
+            totals_row_count: t.totals_row_count,
+            columns: t.columns.clone(),
+        })
+    }
+
+    /// Find the table of a table reference by its name. If the name is
+    /// omitted, it is the table that the formula at `curr_cell` is in.
+    pub fn fetch_table<F>(
+        &self,
+        name: Option<&str>,
+        curr_cell: Option<(SheetId, usize, usize)>,
+        fetcher: &mut F,
+    ) -> Option<Table>
+    where
+        F: IndexFetcherTrait,
+    {
+        match name {
+            Some(name) => {
+                let table_id = self.get_table_id(name)?;
+                self.get_table(&table_id, fetcher)
+            }
+            None => {
+                let (sheet_id, row, col) = curr_cell?;
+                self.find_table_at(sheet_id, row, col, fetcher)
+            }
+        }
+    }
+
+    /// The tables in the sheet, ordered by their ids.
+    pub fn get_sheet_tables(&self, sheet_id: SheetId) -> Vec<TableId> {
+        let mut ids = self
+            .tables
+            .iter()
+            .filter(|(_, t)| t.sheet_id == sheet_id)
+            .map(|(id, _)| *id)
+            .collect::<Vec<_>>();
+        ids.sort_unstable();
+        ids
+    }
+
+    /// The corners of a table move with the cells, so a table grows by itself
+    /// when columns are inserted inside it. Here we only name the new columns.
+    /// The fetcher should return the indexes before the insertion.
+    pub fn insert_columns<F>(&mut self, sheet_id: SheetId, idx: usize, cnt: usize, fetcher: &mut F)
+    where
+        F: IndexFetcherTrait,
+    {
+        for id in self.get_sheet_tables(sheet_id) {
+            let table = match self.get_table(&id, fetcher) {
+                Some(t) => t,
+                None => continue,
+            };
+            if idx <= table.start.col || idx > table.end.col {
+                continue;
+            }
+            let def = self.tables.get_mut(&id).unwrap();
+            let pos = (idx - table.start.col).min(def.columns.len());
+            (0..cnt).for_each(|i| {
+                let name = new_column_name(&def.columns);
+                def.columns.insert(pos + i, name);
+            });
+        }
+    }
+
+    /// Like the spreadsheet applications, a table shrinks when its first or
+    /// last rows are deleted, and it is removed when all its rows are. The
+    /// header row and the totals row are gone if they are deleted.
+    /// The fetcher should return the indexes before the deletion.
+    pub fn delete_rows<F>(&mut self, sheet_id: SheetId, idx: usize, cnt: usize, fetcher: &mut F)
+    where
+        F: IdFetcherTrait + IndexFetcherTrait,
+    {
+        for id in self.get_sheet_tables(sheet_id) {
+            let table = match self.get_table(&id, fetcher) {
+                Some(t) => t,
+                None => continue,
+            };
+            let (start, end) = match shrink(table.start.row, table.end.row, idx, cnt) {
+                Some(rows) => rows,
+                None => {
+                    self.tables.remove(&id);
+                    continue;
+                }
+            };
+            let def = self.tables.get_mut(&id).unwrap();
+            if start != table.start.row {
+                def.header_row_count = 0;
+            }
+            if end != table.end.row {
+                def.totals_row_count = 0;
+            }
+            self.move_corners(&id, (start, table.start.col), (end, table.end.col), fetcher);
+        }
+    }
+
+    /// Remove the names of the deleted columns. The corners of a table move
+    /// inward when its first or last columns are deleted, and the table is
+    /// removed when all its columns are.
+    /// The fetcher should return the indexes before the deletion.
+    pub fn delete_columns<F>(&mut self, sheet_id: SheetId, idx: usize, cnt: usize, fetcher: &mut F)
+    where
+        F: IdFetcherTrait + IndexFetcherTrait,
+    {
+        for id in self.get_sheet_tables(sheet_id) {
+            let table = match self.get_table(&id, fetcher) {
+                Some(t) => t,
+                None => continue,
+            };
+            let (start, end) = match shrink(table.start.col, table.end.col, idx, cnt) {
+                Some(cols) => cols,
+                None => {
+                    self.tables.remove(&id);
+                    continue;
+                }
+            };
+            let from = idx.max(table.start.col);
+            let to = (idx + cnt).min(table.end.col + 1);
+            if from >= to {
+                continue;
+            }
+            let def = self.tables.get_mut(&id).unwrap();
+            let from = (from - table.start.col).min(def.columns.len());
+            let to = (to - table.start.col).min(def.columns.len());
+            def.columns.drain(from..to);
+            self.move_corners(&id, (table.start.row, start), (table.end.row, end), fetcher);
+        }
+    }
+
+    fn move_corners<F>(
+        &mut self,
+        table_id: &TableId,
+        start: (usize, usize),
+        end: (usize, usize),
+        fetcher: &mut F,
+    ) where
+        F: IdFetcherTrait,
+    {
+        let def = match self.tables.get_mut(table_id) {
+            Some(def) => def,
+            None => return,
+        };
+        let sheet_id = def.sheet_id;
+        if let Ok(cell_id) = fetcher.fetch_cell_id(&sheet_id, start.0, start.1) {
+            def.start = cell_id;
+        }
+        if let Ok(cell_id) = fetcher.fetch_cell_id(&sheet_id, end.0, end.1) {
+            def.end = cell_id;
+        }
+    }
+
+    /// Find the table that the cell is in.
+    pub fn find_table_at<F>(
+        &self,
+        sheet_id: SheetId,
+        row: usize,
+        col: usize,
+        fetcher: &mut F,
+    ) -> Option<Table>
+    where
+        F: IndexFetcherTrait,
+    {
+        self.tables
+            .iter()
+            .filter(|(_, t)| t.sheet_id == sheet_id)
+            .filter_map(|(id, _)| self.get_table(id, fetcher))
+            .find(|t| {
+                t.start.row <= row && row <= t.end.row && t.start.col <= col && col <= t.end.col
+            })
+    }
+}
+
+// The first and the last lines from `start` to `end` that are not deleted, or
+// none if all of them are deleted.
+fn shrink(start: usize, end: usize, idx: usize, cnt: usize) -> Option<(usize, usize)> {
+    let deleted = |line: usize| idx <= line && line < idx + cnt;
+    let start = if deleted(start) { idx + cnt } else { start };
+    let end = if deleted(end) {
+        idx.checked_sub(1)?
+    } else {
+        end
+    };
+    if start > end {
+        None
+    } else {
+        Some((start, end))
+    }
+}
+
+// Like the spreadsheet applications, the new columns are named `Column1`,
+// `Column2` and so on, skipping the names in use.
+fn new_column_name(columns: &[String]) -> String {
+    (1..)
+        .map(|i| format!("Column{}", i))
+        .find(|n| !columns.iter().any(|c| c.eq_ignore_ascii_case(n)))
+        .unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::controller::edit_action::{
+        ColShift, EditAction, EditPayload, PayloadsAction, RowShift,
+    };
+    use crate::controller::Controller;
+    use crate::test_utils::{get_value, input};
+    use logisheets_base::CellValue;
+
+    #[test]
+    fn table_reference_test() {
+        use super::TableDef;
+        use crate::connectors::NameFetcher;
+        use logisheets_parser::unparse::Stringify;
+        let mut wb = Controller::default();
+        let payload = |p: EditPayload| {
+            EditAction::Payloads(PayloadsAction {
+                payloads: vec![p],
+                undoable: true,
+            })
+        };
+        // A1:B5, with a header row and a totals row.
+        input(&mut wb, 0, 0, 0, "Item");
+        input(&mut wb, 0, 0, 1, "Price");
+        input(&mut wb, 0, 1, 1, "1");
+        input(&mut wb, 0, 2, 1, "2");
+        input(&mut wb, 0, 3, 1, "3");
+        input(&mut wb, 0, 4, 0, "Total");
+        let sheet_id = wb.get_sheet_id_by_idx(0).unwrap();
+        let navigator = &mut wb.status.navigator;
+        let start = navigator.fetch_cell_id(&sheet_id, 0, 0).unwrap();
+        let end = navigator.fetch_cell_id(&sheet_id, 4, 1).unwrap();
+        wb.status.formula_manager.table_manager.add_table(TableDef {
+            name: String::from("Table1"),
+            sheet_id,
+            start,
+            end,
+            header_row_count: 1,
+            totals_row_count: 1,
+            columns: vec![String::from("Item"), String::from("Price")],
+        });
+
+        input(&mut wb, 0, 0, 3, "=SUM(Table1[Price])");
+        input(&mut wb, 0, 2, 2, "=Table1[@Price]*2");
+        assert!(matches!(get_value(&mut wb, 0, 0, 3), CellValue::Number(n) if n == 6.));
+        assert!(matches!(get_value(&mut wb, 0, 2, 2), CellValue::Number(n) if n == 4.));
+
+        // The table grows when a row is inserted inside it.
+        wb.handle_action(payload(EditPayload::RowShift(RowShift {
+            sheet_idx: 0,
+            row: 2,
+            count: 1,
+            insert: true,
+        })));
+        input(&mut wb, 0, 2, 1, "10");
+        assert!(matches!(get_value(&mut wb, 0, 0, 3), CellValue::Number(n) if n == 16.));
+        assert!(matches!(get_value(&mut wb, 0, 3, 2), CellValue::Number(n) if n == 4.));
+
+        let status = &wb.status;
+        let cell_id = status
+            .navigator
+            .clone()
+            .fetch_cell_id(&sheet_id, 0, 3)
+            .unwrap();
+        let node = status
+            .formula_manager
+            .formulas
+            .get(&(sheet_id, cell_id))
+            .unwrap();
+        let mut navigator = status.navigator.clone();
+        let mut name_fetcher = NameFetcher {
+            func_manager: &status.func_id_manager,
+            sheet_id_manager: &status.sheet_id_manager,
+            external_links_manager: &status.external_links_manager,
+            text_id_manager: &status.text_id_manager,
+            name_id_manager: &status.name_id_manager,
+            navigator: &mut navigator,
+            formula_manager: &status.formula_manager,
+        };
+        assert_eq!(
+            node.unparse(&mut name_fetcher, sheet_id),
+            "SUM(Table1[Price])"
+        );
+    }
+
+    #[test]
+    fn shrink_table_test() {
+        use super::TableDef;
+        let mut wb = Controller::default();
+        let payload = |p: EditPayload| {
+            EditAction::Payloads(PayloadsAction {
+                payloads: vec![p],
+                undoable: true,
+            })
+        };
+        let sheet_id = wb.get_sheet_id_by_idx(0).unwrap();
+        let navigator = &mut wb.status.navigator;
+        let start = navigator.fetch_cell_id(&sheet_id, 0, 0).unwrap();
+        let end = navigator.fetch_cell_id(&sheet_id, 4, 1).unwrap();
+        wb.status.formula_manager.table_manager.add_table(TableDef {
+            name: String::from("Table1"),
+            sheet_id,
+            start,
+            end,
+            header_row_count: 1,
+            totals_row_count: 1,
+            columns: vec![String::from("Item"), String::from("Price")],
+        });
+        let get_table = |wb: &Controller| {
+            let book = crate::file_saver::save(wb);
+            let mut tables = book.xl.worksheets.into_iter().next().unwrap().1.tables;
+            assert_eq!(tables.len(), 1);
+            tables.remove(0)
+        };
+
+        // Deleting the last row removes the totals row.
+        wb.handle_action(payload(EditPayload::RowShift(RowShift {
+            sheet_idx: 0,
+            row: 4,
+            count: 1,
+            insert: false,
+        })));
+        let table = get_table(&wb);
+        assert_eq!(table.reference, "A1:B4");
+        assert_eq!(table.totals_row_count, 0);
+        assert_eq!(table.auto_filter.unwrap().reference, "A1:B4");
+
+        wb.handle_action(payload(EditPayload::ColShift(ColShift {
+            sheet_idx: 0,
+            col: 0,
+            count: 1,
+            insert: false,
+        })));
+        let table = get_table(&wb);
+        assert_eq!(table.reference, "A1:A4");
+        let columns = &table.table_columns.table_columns;
+        let names = columns.iter().map(|c| c.name.as_str()).collect::<Vec<_>>();
+        assert_eq!(names, vec!["Price"]);
+
+        // Deleting the first row removes the header row.
+        wb.handle_action(payload(EditPayload::RowShift(RowShift {
+            sheet_idx: 0,
+            row: 0,
+            count: 1,
+            insert: false,
+        })));
+        let table = get_table(&wb);
+        assert_eq!(table.reference, "A1:A3");
+        assert_eq!(table.header_row_count, 0);
+        assert!(table.auto_filter.is_none());
+
+        // The table is removed with all its rows.
+        wb.handle_action(payload(EditPayload::RowShift(RowShift {
+            sheet_idx: 0,
+            row: 0,
+            count: 3,
+            insert: false,
+        })));
+        let book = crate::file_saver::save(&wb);
+        let worksheet = book.xl.worksheets.values().next().unwrap();
+        assert!(worksheet.tables.is_empty());
+        let table_manager = &wb.status.formula_manager.table_manager;
+        assert!(table_manager.get_table_id("Table1").is_none());
+    }
+}
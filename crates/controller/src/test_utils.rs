@@ -0,0 +1,60 @@
+use crate::controller::edit_action::{
+    ActionEffect, CellInput, EditAction, EditPayload, PayloadsAction,
+};
+use crate::controller::Controller;
+use logisheets_base::CellValue;
+use logisheets_workbook::prelude::CtDefinedName;
+
+pub fn input(
+    wb: &mut Controller,
+    sheet_idx: usize,
+    row: usize,
+    col: usize,
+    content: &str,
+) -> Option<ActionEffect> {
+    wb.handle_action(EditAction::Payloads(PayloadsAction {
+        payloads: vec![EditPayload::CellInput(CellInput {
+            sheet_idx,
+            row,
+            col,
+            content: content.to_string(),
+        })],
+        undoable: true,
+    }))
+}
+
+pub fn get_value(wb: &mut Controller, sheet_idx: usize, row: usize, col: usize) -> CellValue {
+    let sheet_id = wb.get_sheet_id_by_idx(sheet_idx).unwrap();
+    let cell_id = wb
+        .status
+        .navigator
+        .fetch_cell_id(&sheet_id, row, col)
+        .unwrap();
+    wb.status
+        .container
+        .get_cell(sheet_id, &cell_id)
+        .unwrap()
+        .value
+        .clone()
+}
+
+pub fn defined_name(name: &str, value: &str, local_sheet_id: Option<u32>) -> CtDefinedName {
+    CtDefinedName {
+        value: value.to_string(),
+        name: name.to_string(),
+        comment: None,
+        comment_menu: None,
+        description: None,
+        help: None,
+        status_bar: None,
+        local_sheet_id,
+        hidden: false,
+        function: false,
+        vb_procedure: false,
+        xlm: false,
+        function_group_id: None,
+        shortcut_key: None,
+        publish_to_server: false,
+        workbook_parameter: false,
+    }
+}
@@ -193,6 +193,10 @@ fn exec_check_num(ctx: &mut ExecContext, check_num: CheckNum, line: usize) -> Op
                     line,
                     msg: format!("expect number, found error: {}", e),
                 }),
+                Value::Date(d) => Some(ExecError {
+                    line,
+                    msg: format!("expect number, found date: {}", d),
+                }),
                 Value::Empty => Some(ExecError {
                     line,
                     msg: "empty is found".to_string(),
@@ -246,6 +250,10 @@ fn exec_check_string(
                     line,
                     msg: "expect string, found error".to_string(),
                 }),
+                Value::Date(_) => Some(ExecError {
+                    line,
+                    msg: "expect string, found date".to_string(),
+                }),
                 Value::Empty => Some(ExecError {
                     line,
                     msg: "empty is found".to_string(),
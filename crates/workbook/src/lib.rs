@@ -3,22 +3,28 @@ pub mod reader;
 pub mod rtypes;
 pub mod workbook;
 pub mod writer;
-#[cfg(test)]
-mod zipdiff;
+pub mod zipdiff;
 use thiserror::Error;
 use xmlserde::*;
 
 pub mod prelude {
     pub use super::ooxml::comments::*;
     pub use super::ooxml::complex_types::*;
+    pub use super::ooxml::external_links::*;
+    pub use super::ooxml::metadata::MetadataPart;
+    pub use super::ooxml::relationships::CtRelationship;
     pub use super::ooxml::simple_types::*;
     pub use super::ooxml::sst::SstPart;
     pub use super::ooxml::style_sheet::StylesheetPart;
+    pub use super::ooxml::table::*;
     pub use super::ooxml::theme::*;
     pub use super::ooxml::workbook::*;
     pub use super::ooxml::worksheet::*;
     pub use super::reader::*;
-    pub use super::workbook::Workbook;
+    pub use super::workbook::{ExternalLink, Passthrough, RawPart, Workbook};
+    pub use super::zipdiff::{
+        zipdiff, zipdiff_with, zipdiff_with_each, PartDiff, Whitelist, ZipDiff,
+    };
     pub use super::SerdeErr;
 }
 
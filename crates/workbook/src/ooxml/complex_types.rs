@@ -106,7 +106,7 @@ pub struct CtPhoneticRun {
     pub eb: u32,
 }
 
-#[derive(XmlSerialize, XmlDeserialize, Debug)]
+#[derive(XmlSerialize, XmlDeserialize, Debug, Clone)]
 pub struct CtColors {
     #[xmlserde(name = b"indexedColors", ty = "child")]
     pub indexed_colors: Option<CtIndexedColors>,
@@ -114,19 +114,19 @@ pub struct CtColors {
     pub mru_colors: Option<CtMruColors>,
 }
 
-#[derive(XmlSerialize, XmlDeserialize, Debug)]
+#[derive(XmlSerialize, XmlDeserialize, Debug, Clone)]
 pub struct CtIndexedColors {
     #[xmlserde(name = b"rgbColor", ty = "child")]
     pub rgb_color: Vec<CtRgbColor>,
 }
 
-#[derive(XmlSerialize, XmlDeserialize, Debug)]
+#[derive(XmlSerialize, XmlDeserialize, Debug, Clone)]
 pub struct CtRgbColor {
     #[xmlserde(name = b"rgb", ty = "attr")]
     pub rgb: Option<StUnsignedIntHex>,
 }
 
-#[derive(XmlSerialize, XmlDeserialize, Debug)]
+#[derive(XmlSerialize, XmlDeserialize, Debug, Clone)]
 pub struct CtMruColors {
     #[xmlserde(name = b"color", ty = "child")]
     pub color: Vec<CtColor>,
@@ -462,7 +462,7 @@ pub struct CtFontFamily {
     pub val: StFontFamily,
 }
 
-#[derive(Debug, XmlSerialize, XmlDeserialize)]
+#[derive(Debug, Clone, XmlSerialize, XmlDeserialize)]
 pub struct CtTableStyle {
     #[xmlserde(name = b"name", ty = "attr")]
     pub name: String,
@@ -476,7 +476,7 @@ pub struct CtTableStyle {
     pub table_style_elements: Vec<CtTableStyleElement>,
 }
 
-#[derive(Debug, XmlSerialize, XmlDeserialize)]
+#[derive(Debug, Clone, XmlSerialize, XmlDeserialize)]
 pub struct CtTableStyles {
     #[xmlserde(name = b"tableStyle", ty = "child", vec_size = "count")]
     pub table_styles: Vec<CtTableStyle>,
@@ -488,7 +488,7 @@ pub struct CtTableStyles {
     pub default_pivot_style: Option<String>,
 }
 
-#[derive(Debug, XmlSerialize, XmlDeserialize)]
+#[derive(Debug, Clone, XmlSerialize, XmlDeserialize)]
 pub struct CtTableStyleElement {
     #[xmlserde(name = b"type", ty = "attr")]
     pub ty: StTableStyleType,
@@ -498,7 +498,7 @@ pub struct CtTableStyleElement {
     pub dxf_id: Option<StDxfId>,
 }
 
-#[derive(Debug, XmlSerialize, XmlDeserialize)]
+#[derive(Debug, Clone, XmlSerialize, XmlDeserialize)]
 pub struct CtDxfs {
     #[xmlserde(name = b"count", ty = "attr", default = "default_zero_u32")]
     pub count: u32,
@@ -506,7 +506,7 @@ pub struct CtDxfs {
     pub dxfs: Vec<CtDxf>,
 }
 
-#[derive(Debug, XmlSerialize, XmlDeserialize)]
+#[derive(Debug, Clone, XmlSerialize, XmlDeserialize)]
 pub struct CtDxf {
     #[xmlserde(name = b"font", ty = "child")]
     pub font: Option<CtFont>,
@@ -522,7 +522,7 @@ pub struct CtDxf {
     pub protection: Option<CtCellProtection>,
 }
 
-#[derive(Debug, XmlSerialize, XmlDeserialize)]
+#[derive(Debug, Clone, XmlSerialize, XmlDeserialize)]
 pub struct CtNumFmt {
     #[xmlserde(name = b"numFmtId", ty = "attr")]
     pub num_fmt_id: StNumFmtId,
@@ -684,14 +684,16 @@ pub struct CtFileVersion {
     pub code_name: Option<String>,
 }
 
-#[derive(Debug, XmlSerialize, XmlDeserialize)]
+#[derive(Debug, Clone, XmlSerialize, XmlDeserialize)]
 pub struct CtDefinedNames {
     #[xmlserde(name = b"definedName", ty = "child")]
     pub names: Vec<CtDefinedName>,
 }
 
-#[derive(Debug, XmlSerialize, XmlDeserialize)]
+#[derive(Debug, Clone, XmlSerialize, XmlDeserialize)]
 pub struct CtDefinedName {
+    #[xmlserde(ty = "text", default = "empty_string")]
+    pub value: String,
     #[xmlserde(name = b"name", ty = "attr")]
     pub name: String,
     #[xmlserde(name = b"comment", ty = "attr")]
@@ -774,7 +776,7 @@ pub struct CtSmartTagPr {
     pub show: StSmartTagShow,
 }
 
-#[derive(Debug, XmlDeserialize, XmlSerialize)]
+#[derive(Debug, Clone, XmlDeserialize, XmlSerialize)]
 pub struct CtWorkbookPr {
     #[xmlserde(name = b"date1904", ty = "attr", default = "default_false")]
     pub date1904: bool,
@@ -882,13 +884,13 @@ pub struct CtOleSize {
     pub reference: StRef,
 }
 
-#[derive(Debug, XmlDeserialize, XmlSerialize)]
+#[derive(Debug, Clone, XmlDeserialize, XmlSerialize)]
 pub struct CtPivotCaches {
-    #[xmlserde(name = b"pivot_cache", ty = "child")]
+    #[xmlserde(name = b"pivotCache", ty = "child")]
     pub pivot_caches: Vec<CtPivotCache>,
 }
 
-#[derive(Debug, XmlDeserialize, XmlSerialize)]
+#[derive(Debug, Clone, XmlDeserialize, XmlSerialize)]
 pub struct CtPivotCache {
     #[xmlserde(name = b"cacheId", ty = "attr")]
     pub cache_id: u32,
@@ -982,13 +984,13 @@ pub struct CtFunctionGroup {
     pub name: String,
 }
 
-#[derive(Debug, XmlDeserialize, XmlSerialize)]
+#[derive(Debug, Clone, XmlDeserialize, XmlSerialize)]
 pub struct CtBookViews {
     #[xmlserde(name = b"workbookView", ty = "child")]
     pub views: Vec<CtBookView>,
 }
 
-#[derive(Debug, XmlDeserialize, XmlSerialize)]
+#[derive(Debug, Clone, XmlDeserialize, XmlSerialize)]
 pub struct CtBookView {
     #[xmlserde(name = b"visibility", ty = "attr", default = "st_visibility_visible")]
     pub visibility: StVisibility,
@@ -1102,7 +1104,7 @@ pub struct CtSheet {
     pub id: String,
 }
 
-#[derive(Debug, XmlSerialize, XmlDeserialize)]
+#[derive(Debug, Clone, XmlSerialize, XmlDeserialize)]
 pub struct CtSheetPr {
     #[xmlserde(name = b"tabColor", ty = "child")]
     pub tab_color: Option<CtColor>,
@@ -1134,7 +1136,7 @@ pub struct CtSheetPr {
     pub enable_format_conditions_calculation: bool,
 }
 
-#[derive(Debug, XmlSerialize, XmlDeserialize)]
+#[derive(Debug, Clone, XmlSerialize, XmlDeserialize)]
 pub struct CtOutlinePr {
     #[xmlserde(name = b"applyStyles", ty = "attr", default = "default_false")]
     pub apply_styles: bool,
@@ -1146,7 +1148,7 @@ pub struct CtOutlinePr {
     pub show_outline_symbols: bool,
 }
 
-#[derive(Debug, XmlSerialize, XmlDeserialize)]
+#[derive(Debug, Clone, XmlSerialize, XmlDeserialize)]
 pub struct CtPageSetupPr {
     #[xmlserde(name = b"autoPageBreaks", ty = "attr", default = "default_true")]
     pub auto_page_breaks: bool,
@@ -1160,13 +1162,13 @@ pub struct CtSheetDimension {
     pub reference: StRef,
 }
 
-#[derive(Debug, XmlSerialize, XmlDeserialize)]
+#[derive(Debug, Clone, XmlSerialize, XmlDeserialize)]
 pub struct CtSheetViews {
     #[xmlserde(name = b"sheetView", ty = "child")]
     pub sheet_views: Vec<CtSheetView>,
 }
 
-#[derive(Debug, XmlSerialize, XmlDeserialize)]
+#[derive(Debug, Clone, XmlSerialize, XmlDeserialize)]
 pub struct CtSheetView {
     #[xmlserde(name = b"pane", ty = "child")]
     pub pane: Option<CtPane>,
@@ -1222,7 +1224,7 @@ pub struct CtSheetView {
     pub workbook_view_id: u32,
 }
 
-#[derive(Debug, XmlSerialize, XmlDeserialize)]
+#[derive(Debug, Clone, XmlSerialize, XmlDeserialize)]
 pub struct CtPane {
     #[xmlserde(name = b"xSplit", ty = "attr", default = "default_zero_f64")]
     pub x_split: f64,
@@ -1236,7 +1238,7 @@ pub struct CtPane {
     pub state: StPaneState,
 }
 
-#[derive(Debug, XmlSerialize, XmlDeserialize)]
+#[derive(Debug, Clone, XmlSerialize, XmlDeserialize)]
 pub struct CtSelection {
     #[xmlserde(name = b"pane", ty = "attr", default = "st_pane_top_left")]
     pub pane: StPane,
@@ -1248,7 +1250,7 @@ pub struct CtSelection {
     pub sqref: Option<String>, // todo
 }
 
-#[derive(Debug, XmlSerialize, XmlDeserialize)]
+#[derive(Debug, Clone, XmlSerialize, XmlDeserialize)]
 pub struct CtPivotSelection {
     #[xmlserde(name = b"pivotArea", ty = "child")]
     pub pivot_area: CtPivotArea,
@@ -1288,7 +1290,7 @@ pub struct CtPivotSelection {
     pub id: Option<String>,
 }
 
-#[derive(Debug, XmlSerialize, XmlDeserialize)]
+#[derive(Debug, Clone, XmlSerialize, XmlDeserialize)]
 pub struct CtPivotArea {
     #[xmlserde(name = b"references", ty = "child")]
     pub references: Option<CtPivotAreaReferences>,
@@ -1322,7 +1324,7 @@ pub struct CtPivotArea {
     pub field_position: Option<u32>,
 }
 
-#[derive(Debug, XmlSerialize, XmlDeserialize)]
+#[derive(Debug, Clone, XmlSerialize, XmlDeserialize)]
 pub struct CtPivotAreaReferences {
     #[xmlserde(name = b"count", ty = "attr", default = "default_zero_u32")]
     pub count: u32,
@@ -1330,7 +1332,7 @@ pub struct CtPivotAreaReferences {
     pub references: Vec<CtPivotAreaReference>,
 }
 
-#[derive(Debug, XmlSerialize, XmlDeserialize)]
+#[derive(Debug, Clone, XmlSerialize, XmlDeserialize)]
 pub struct CtPivotAreaReference {
     #[xmlserde(name = b"x", ty = "child", vec_size = "count")]
     pub xs: Vec<CtIndex>,
@@ -1370,7 +1372,7 @@ pub struct CtPivotAreaReference {
     pub std_var_p_subtotal: bool,
 }
 
-#[derive(Debug, XmlSerialize, XmlDeserialize)]
+#[derive(Debug, Clone, XmlSerialize, XmlDeserialize)]
 pub struct CtIndex {
     #[xmlserde(name = b"v", ty = "attr")]
     pub v: u32,
@@ -1554,7 +1556,7 @@ pub struct CtBreak {
     pub pt: bool,
 }
 
-#[derive(Debug, XmlSerialize, XmlDeserialize)]
+#[derive(Debug, Clone, XmlSerialize, XmlDeserialize)]
 pub struct CtTableParts {
     #[xmlserde(name = b"tablePart", ty = "child", vec_size = "count")]
     pub parts: Vec<CtTablePart>,
@@ -1562,7 +1564,7 @@ pub struct CtTableParts {
     pub count: u32,
 }
 
-#[derive(Debug, XmlSerialize, XmlDeserialize)]
+#[derive(Debug, Clone, XmlSerialize, XmlDeserialize)]
 pub struct CtTablePart {
     #[xmlserde(name = b"r:id", ty = "attr")]
     pub id: String,
@@ -1602,15 +1604,21 @@ pub struct CtCellSmartTagPr {
     pub value: String,
 }
 
-#[derive(Debug, XmlSerialize, XmlDeserialize)]
+#[derive(Debug, Clone, XmlSerialize, XmlDeserialize)]
 pub struct CtDrawing {
     #[xmlserde(name = b"r:id", ty = "attr")]
     pub id: String,
 }
 
+#[derive(Debug, Clone, XmlSerialize, XmlDeserialize)]
+pub struct CtLegacyDrawing {
+    #[xmlserde(name = b"r:id", ty = "attr")]
+    pub id: String,
+}
+
 #[derive(Debug, XmlSerialize, XmlDeserialize)]
 pub struct CtDrawingHF {
-    #[xmlserde(name = b"id", ty = "attr")]
+    #[xmlserde(name = b"r:id", ty = "attr")]
     pub id: String,
     #[xmlserde(name = b"lho", ty = "attr")]
     pub lho: Option<u32>,
@@ -1650,7 +1658,7 @@ pub struct CtDrawingHF {
     pub rff: Option<u32>,
 }
 
-#[derive(Debug, XmlSerialize, XmlDeserialize)]
+#[derive(Debug, Clone, XmlSerialize, XmlDeserialize)]
 pub struct CtSheetProtection {
     #[xmlserde(name = b"algorithmName", ty = "attr")]
     pub algorithm_name: Option<String>,
@@ -1788,7 +1796,7 @@ pub struct CtObjectPr {
     pub id: Option<String>,
 }
 
-#[derive(Debug, XmlSerialize, XmlDeserialize)]
+#[derive(Debug, Clone, XmlSerialize, XmlDeserialize)]
 pub struct CtPrintOptions {
     #[xmlserde(name = b"horizontalCentered", ty = "attr", default = "default_false")]
     pub horizontal_centered: bool,
@@ -1802,7 +1810,7 @@ pub struct CtPrintOptions {
     pub grid_lines_set: bool,
 }
 
-#[derive(Debug, XmlSerialize, XmlDeserialize)]
+#[derive(Debug, Clone, XmlSerialize, XmlDeserialize)]
 pub struct CtPageSetup {
     #[xmlserde(name = b"paperSize", ty = "attr", default = "default_one_u32")]
     pub paper_size: u32,
@@ -1876,7 +1884,7 @@ pub struct CtWebPublishItem {
     pub auto_republish: bool,
 }
 
-#[derive(Debug, XmlSerialize, XmlDeserialize)]
+#[derive(Debug, Clone, XmlSerialize, XmlDeserialize)]
 pub struct CtSortState {
     #[xmlserde(name = b"sortCondition", ty = "child")]
     pub condictions: Vec<CtSortCondition>,
@@ -1886,7 +1894,7 @@ pub struct CtSortState {
     pub case_sensitive: bool,
 }
 
-#[derive(Debug, XmlSerialize, XmlDeserialize)]
+#[derive(Debug, Clone, XmlSerialize, XmlDeserialize)]
 pub struct CtSortCondition {
     #[xmlserde(name = b"descending", ty = "attr", default = "default_false")]
     pub descending: bool,
@@ -1904,7 +1912,7 @@ pub struct CtSortCondition {
     pub icon_id: Option<u32>,
 }
 
-#[derive(Debug, XmlSerialize, XmlDeserialize)]
+#[derive(Debug, Clone, XmlSerialize, XmlDeserialize)]
 pub struct CtPageMargins {
     #[xmlserde(name = b"left", ty = "attr")]
     pub left: f64,
@@ -1920,7 +1928,7 @@ pub struct CtPageMargins {
     pub footer: f64,
 }
 
-#[derive(Debug, XmlSerialize, XmlDeserialize)]
+#[derive(Debug, Clone, XmlSerialize, XmlDeserialize)]
 pub struct CtAutoFilter {
     #[xmlserde(name = b"filterColumn", ty = "child")]
     pub filter_columns: Vec<CtFilterColumn>,
@@ -1930,7 +1938,7 @@ pub struct CtAutoFilter {
     pub reference: StRef,
 }
 
-#[derive(Debug, XmlSerialize, XmlDeserialize)]
+#[derive(Debug, Clone, XmlSerialize, XmlDeserialize)]
 pub struct CtFilterColumn {
     // Choice start todo!
     #[xmlserde(name = b"filters", ty = "child")]
@@ -1954,7 +1962,7 @@ pub struct CtFilterColumn {
     pub show_button: bool,
 }
 
-#[derive(Debug, XmlSerialize, XmlDeserialize)]
+#[derive(Debug, Clone, XmlSerialize, XmlDeserialize)]
 pub struct CtFilters {
     #[xmlserde(name = b"filter", ty = "child")]
     pub filters: Vec<CtFilter>,
@@ -1966,7 +1974,7 @@ pub struct CtFilters {
     pub calendar_type: StCalendarType,
 }
 
-#[derive(Debug, XmlSerialize, XmlDeserialize)]
+#[derive(Debug, Clone, XmlSerialize, XmlDeserialize)]
 pub struct CtDateGroupItem {
     #[xmlserde(name = b"year", ty = "attr")]
     pub year: u16,
@@ -1984,13 +1992,13 @@ pub struct CtDateGroupItem {
     pub date_time_grouping: StDateTimeGrouping,
 }
 
-#[derive(Debug, XmlSerialize, XmlDeserialize)]
+#[derive(Debug, Clone, XmlSerialize, XmlDeserialize)]
 pub struct CtFilter {
     #[xmlserde(name = b"val", ty = "attr")]
     pub val: String,
 }
 
-#[derive(Debug, XmlSerialize, XmlDeserialize)]
+#[derive(Debug, Clone, XmlSerialize, XmlDeserialize)]
 pub struct CtCustomFilters {
     #[xmlserde(name = b"customFilter", ty = "child")]
     pub filters: Vec<CtCustomFilter>,
@@ -1998,7 +2006,7 @@ pub struct CtCustomFilters {
     pub and: bool,
 }
 
-#[derive(Debug, XmlSerialize, XmlDeserialize)]
+#[derive(Debug, Clone, XmlSerialize, XmlDeserialize)]
 pub struct CtCustomFilter {
     #[xmlserde(name = b"operator", ty = "attr", default = "st_filter_operator_equal")]
     pub operator: StFilterOperator,
@@ -2006,7 +2014,7 @@ pub struct CtCustomFilter {
     pub val: String,
 }
 
-#[derive(Debug, XmlSerialize, XmlDeserialize)]
+#[derive(Debug, Clone, XmlSerialize, XmlDeserialize)]
 pub struct CtTop10 {
     #[xmlserde(name = b"top", ty = "attr", default = "default_true")]
     pub top: bool,
@@ -2018,7 +2026,7 @@ pub struct CtTop10 {
     pub filter_val: Option<f64>,
 }
 
-#[derive(Debug, XmlSerialize, XmlDeserialize)]
+#[derive(Debug, Clone, XmlSerialize, XmlDeserialize)]
 pub struct CtColorFilter {
     #[xmlserde(name = b"dxfId", ty = "attr")]
     pub dxf_id: Option<StDxfId>,
@@ -2026,7 +2034,7 @@ pub struct CtColorFilter {
     pub cell_color: bool,
 }
 
-#[derive(Debug, XmlSerialize, XmlDeserialize)]
+#[derive(Debug, Clone, XmlSerialize, XmlDeserialize)]
 pub struct CtIconFilter {
     #[xmlserde(name = b"iconSet", ty = "attr")]
     pub icon_set: StIconSetType,
@@ -2034,7 +2042,7 @@ pub struct CtIconFilter {
     pub icon_id: Option<u32>,
 }
 
-#[derive(Debug, XmlSerialize, XmlDeserialize)]
+#[derive(Debug, Clone, XmlSerialize, XmlDeserialize)]
 pub struct CtDynamicFilter {
     #[xmlserde(name = b"type", ty = "attr")]
     pub ty: StDynamicFilterType,
@@ -2046,13 +2054,13 @@ pub struct CtDynamicFilter {
     pub max_val_iso: Option<String>,
 }
 
-#[derive(Debug, XmlSerialize, XmlDeserialize)]
+#[derive(Debug, Clone, XmlSerialize, XmlDeserialize)]
 pub struct CtHyperlinks {
     #[xmlserde(name = b"hyperlink", ty = "child")]
     pub links: Vec<CtHyperlink>,
 }
 
-#[derive(Debug, XmlSerialize, XmlDeserialize)]
+#[derive(Debug, Clone, XmlSerialize, XmlDeserialize)]
 pub struct CtHyperlink {
     #[xmlserde(name = b"ref", ty = "attr")]
     pub reference: StRef,
@@ -2146,7 +2154,7 @@ pub struct CtInputCells {
     pub num_fmt_id: Option<StNumFmtId>,
 }
 
-#[derive(Debug, XmlSerialize, XmlDeserialize)]
+#[derive(Debug, Clone, XmlSerialize, XmlDeserialize)]
 pub struct CtHeaderFooter {
     #[xmlserde(name = b"oddHeader", ty = "child")]
     pub odd_header: Option<PlainTextString>,
@@ -2170,7 +2178,7 @@ pub struct CtHeaderFooter {
     pub align_with_margins: bool,
 }
 
-#[derive(Debug, XmlSerialize, XmlDeserialize)]
+#[derive(Debug, Clone, XmlSerialize, XmlDeserialize)]
 pub struct CtDataValidations {
     #[xmlserde(name = b"dataValidation", ty = "child", vec_size = "count")]
     pub data_validations: Vec<CtDataValidation>,
@@ -2184,7 +2192,7 @@ pub struct CtDataValidations {
     pub count: u32,
 }
 
-#[derive(Debug, XmlSerialize, XmlDeserialize)]
+#[derive(Debug, Clone, XmlSerialize, XmlDeserialize)]
 pub struct CtDataValidation {
     #[xmlserde(name = b"formula1", ty = "child")]
     pub formula1: Option<PlainTextString>,
@@ -2226,7 +2234,7 @@ pub struct CtDataValidation {
     pub sqref: String,
 }
 
-#[derive(Debug, XmlSerialize, XmlDeserialize)]
+#[derive(Debug, Clone, XmlSerialize, XmlDeserialize)]
 pub struct CtConditionalFormatting {
     #[xmlserde(name = b"cfRule", ty = "child")]
     pub cf_rules: Vec<CtCfRule>,
@@ -2236,7 +2244,7 @@ pub struct CtConditionalFormatting {
     pub sqref: String,
 }
 
-#[derive(Debug, XmlSerialize, XmlDeserialize)]
+#[derive(Debug, Clone, XmlSerialize, XmlDeserialize)]
 pub struct CtCfRule {
     #[xmlserde(name = b"formula", ty = "child")]
     pub formulas: Vec<PlainTextString>,
@@ -2274,7 +2282,7 @@ pub struct CtCfRule {
     pub equal_average: bool,
 }
 
-#[derive(Debug, XmlSerialize, XmlDeserialize)]
+#[derive(Debug, Clone, XmlSerialize, XmlDeserialize)]
 pub struct CtDataBar {
     #[xmlserde(name = b"cfvo", ty = "child")]
     pub cfvos: Vec<CtCfvo>, // must has 2 elements
@@ -2288,7 +2296,7 @@ pub struct CtDataBar {
     pub show_value: bool,
 }
 
-#[derive(Debug, XmlSerialize, XmlDeserialize)]
+#[derive(Debug, Clone, XmlSerialize, XmlDeserialize)]
 pub struct CtIconSet {
     #[xmlserde(name = b"cfvo", ty = "child")]
     pub cfvos: Vec<CtCfvo>, // at least 2 elements
@@ -2306,7 +2314,7 @@ pub struct CtIconSet {
     pub reverse: bool,
 }
 
-#[derive(Debug, XmlSerialize, XmlDeserialize)]
+#[derive(Debug, Clone, XmlSerialize, XmlDeserialize)]
 pub struct CtCfvo {
     #[xmlserde(name = b"type", ty = "attr")]
     pub ty: StCfvoType,
@@ -2316,7 +2324,7 @@ pub struct CtCfvo {
     pub gte: bool,
 }
 
-#[derive(Debug, XmlSerialize, XmlDeserialize)]
+#[derive(Debug, Clone, XmlSerialize, XmlDeserialize)]
 pub struct CtColorScale {
     #[xmlserde(name = b"cfvo", ty = "child")]
     pub cfvos: Vec<CtCfvo>, // at least 2
@@ -2406,7 +2414,7 @@ pub struct CtCustomSheetView {
     pub top_left_cell: Option<StCellRef>,
 }
 
-#[derive(Debug, XmlSerialize, XmlDeserialize)]
+#[derive(Debug, Clone, XmlSerialize, XmlDeserialize)]
 pub struct CtSheetBackgroundPicture {
     #[xmlserde(name = b"r:id", ty = "attr")]
     pub id: String,
@@ -0,0 +1,379 @@
+use super::defaults::*;
+use xmlserde::Unparsed;
+use xmlserde_derives::{XmlDeserialize, XmlSerialize};
+
+/// The name of the metadata type of the dynamic arrays.
+const DYNAMIC_ARRAY_TYPE: &str = "XLDAPR";
+const DYNAMIC_ARRAY_EXT_URI: &str = "{bdbb8cdc-fa1e-496e-a857-3c3f30c029c3}";
+
+#[derive(Debug, Clone, XmlSerialize, XmlDeserialize)]
+#[xmlserde(root = b"metadata")]
+#[xmlserde(with_ns = b"http://schemas.openxmlformats.org/spreadsheetml/2006/main")]
+#[xmlserde(with_custom_ns(
+    b"xda",
+    b"http://schemas.microsoft.com/office/spreadsheetml/2017/dynamicarray"
+))]
+pub struct MetadataPart {
+    #[xmlserde(name = b"metadataTypes", ty = "child")]
+    pub metadata_types: Option<CtMetadataTypes>,
+    #[xmlserde(name = b"metadataStrings", ty = "child")]
+    pub metadata_strings: Option<Unparsed>,
+    #[xmlserde(name = b"mdxMetadata", ty = "child")]
+    pub mdx_metadata: Option<Unparsed>,
+    #[xmlserde(name = b"futureMetadata", ty = "child")]
+    pub future_metadata: Vec<CtFutureMetadata>,
+    #[xmlserde(name = b"cellMetadata", ty = "child")]
+    pub cell_metadata: Option<CtMetadataBlocks>,
+    #[xmlserde(name = b"valueMetadata", ty = "child")]
+    pub value_metadata: Option<CtMetadataBlocks>,
+    #[xmlserde(name = b"extLst", ty = "child")]
+    pub ext_lst: Option<Unparsed>,
+}
+
+impl MetadataPart {
+    pub fn new() -> Self {
+        MetadataPart {
+            metadata_types: None,
+            metadata_strings: None,
+            mdx_metadata: None,
+            future_metadata: vec![],
+            cell_metadata: None,
+            value_metadata: None,
+            ext_lst: None,
+        }
+    }
+
+    /// The `cm` of the cells whose array formulas are dynamic arrays, or
+    /// none if there is no such cell metadata.
+    pub fn get_dynamic_array_cm(&self) -> Option<u32> {
+        let t = self.get_dynamic_array_type()?;
+        let v = self.get_dynamic_array_block()?;
+        let blocks = &self.cell_metadata.as_ref()?.bk;
+        blocks
+            .iter()
+            .position(|bk| bk.rc.iter().any(|rc| rc.t == t && rc.v == v))
+            .map(|idx| idx as u32 + 1)
+    }
+
+    /// Find the `cm` of the dynamic arrays, or add the metadata of them if
+    /// it is missing.
+    pub fn get_or_add_dynamic_array_cm(&mut self) -> u32 {
+        if let Some(cm) = self.get_dynamic_array_cm() {
+            return cm;
+        }
+        let t = match self.get_dynamic_array_type() {
+            Some(t) => t,
+            None => {
+                let types = self.metadata_types.get_or_insert(CtMetadataTypes {
+                    count: 0,
+                    metadata_type: vec![],
+                });
+                types.metadata_type.push(CtMetadataType::dynamic_array());
+                types.count = types.metadata_type.len() as u32;
+                types.count
+            }
+        };
+        let v = match self.get_dynamic_array_block() {
+            Some(v) => v,
+            None => {
+                let dynamic_array_block = CtFutureMetadataBlock {
+                    ext_lst: Some(CtFutureMetadataExtensionList {
+                        ext: vec![CtFutureMetadataExtension {
+                            uri: DYNAMIC_ARRAY_EXT_URI.to_string(),
+                            dynamic_array_properties: Some(CtDynamicArrayProperties {
+                                f_dynamic: true,
+                                f_collapsed: false,
+                            }),
+                            rich_value_block: None,
+                        }],
+                    }),
+                };
+                let future = match self
+                    .future_metadata
+                    .iter()
+                    .position(|f| f.name == DYNAMIC_ARRAY_TYPE)
+                {
+                    Some(idx) => &mut self.future_metadata[idx],
+                    None => {
+                        self.future_metadata.push(CtFutureMetadata {
+                            name: DYNAMIC_ARRAY_TYPE.to_string(),
+                            count: 0,
+                            bk: vec![],
+                        });
+                        self.future_metadata.last_mut().unwrap()
+                    }
+                };
+                future.bk.push(dynamic_array_block);
+                future.count = future.bk.len() as u32;
+                future.count - 1
+            }
+        };
+        let cell_metadata = self.cell_metadata.get_or_insert(CtMetadataBlocks {
+            count: 0,
+            bk: vec![],
+        });
+        cell_metadata.bk.push(CtMetadataBlock {
+            rc: vec![CtMetadataRecord { t, v }],
+        });
+        cell_metadata.count = cell_metadata.bk.len() as u32;
+        cell_metadata.count
+    }
+
+    // The 1-based index of the metadata type of the dynamic arrays.
+    fn get_dynamic_array_type(&self) -> Option<u32> {
+        self.metadata_types
+            .as_ref()?
+            .metadata_type
+            .iter()
+            .position(|t| t.name == DYNAMIC_ARRAY_TYPE)
+            .map(|idx| idx as u32 + 1)
+    }
+
+    // The 0-based index of the future metadata block which marks the
+    // dynamic arrays.
+    fn get_dynamic_array_block(&self) -> Option<u32> {
+        self.future_metadata
+            .iter()
+            .find(|f| f.name == DYNAMIC_ARRAY_TYPE)?
+            .bk
+            .iter()
+            .position(|bk| match &bk.ext_lst {
+                Some(ext_lst) => ext_lst
+                    .ext
+                    .iter()
+                    .any(|e| match &e.dynamic_array_properties {
+                        Some(p) => p.f_dynamic,
+                        None => false,
+                    }),
+                None => false,
+            })
+            .map(|idx| idx as u32)
+    }
+}
+
+impl Default for MetadataPart {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Debug, Clone, XmlSerialize, XmlDeserialize)]
+pub struct CtMetadataTypes {
+    #[xmlserde(name = b"count", ty = "attr", default = "default_zero_u32")]
+    pub count: u32,
+    #[xmlserde(name = b"metadataType", ty = "child")]
+    pub metadata_type: Vec<CtMetadataType>,
+}
+
+#[derive(Debug, Clone, XmlSerialize, XmlDeserialize)]
+pub struct CtMetadataType {
+    #[xmlserde(name = b"name", ty = "attr")]
+    pub name: String,
+    #[xmlserde(name = b"minSupportedVersion", ty = "attr")]
+    pub min_supported_version: u32,
+    #[xmlserde(name = b"ghostRow", ty = "attr", default = "default_false")]
+    pub ghost_row: bool,
+    #[xmlserde(name = b"ghostCol", ty = "attr", default = "default_false")]
+    pub ghost_col: bool,
+    #[xmlserde(name = b"edit", ty = "attr", default = "default_false")]
+    pub edit: bool,
+    #[xmlserde(name = b"delete", ty = "attr", default = "default_false")]
+    pub delete: bool,
+    #[xmlserde(name = b"copy", ty = "attr", default = "default_false")]
+    pub copy: bool,
+    #[xmlserde(name = b"pasteAll", ty = "attr", default = "default_false")]
+    pub paste_all: bool,
+    #[xmlserde(name = b"pasteFormulas", ty = "attr", default = "default_false")]
+    pub paste_formulas: bool,
+    #[xmlserde(name = b"pasteValues", ty = "attr", default = "default_false")]
+    pub paste_values: bool,
+    #[xmlserde(name = b"pasteFormats", ty = "attr", default = "default_false")]
+    pub paste_formats: bool,
+    #[xmlserde(name = b"pasteComments", ty = "attr", default = "default_false")]
+    pub paste_comments: bool,
+    #[xmlserde(name = b"pasteDataValidation", ty = "attr", default = "default_false")]
+    pub paste_data_validation: bool,
+    #[xmlserde(name = b"pasteBorders", ty = "attr", default = "default_false")]
+    pub paste_borders: bool,
+    #[xmlserde(name = b"pasteColWidths", ty = "attr", default = "default_false")]
+    pub paste_col_widths: bool,
+    #[xmlserde(name = b"pasteNumberFormats", ty = "attr", default = "default_false")]
+    pub paste_number_formats: bool,
+    #[xmlserde(name = b"merge", ty = "attr", default = "default_false")]
+    pub merge: bool,
+    #[xmlserde(name = b"splitFirst", ty = "attr", default = "default_false")]
+    pub split_first: bool,
+    #[xmlserde(name = b"splitAll", ty = "attr", default = "default_false")]
+    pub split_all: bool,
+    #[xmlserde(name = b"rowColShift", ty = "attr", default = "default_false")]
+    pub row_col_shift: bool,
+    #[xmlserde(name = b"clearAll", ty = "attr", default = "default_false")]
+    pub clear_all: bool,
+    #[xmlserde(name = b"clearFormats", ty = "attr", default = "default_false")]
+    pub clear_formats: bool,
+    #[xmlserde(name = b"clearContents", ty = "attr", default = "default_false")]
+    pub clear_contents: bool,
+    #[xmlserde(name = b"clearComments", ty = "attr", default = "default_false")]
+    pub clear_comments: bool,
+    #[xmlserde(name = b"assign", ty = "attr", default = "default_false")]
+    pub assign: bool,
+    #[xmlserde(name = b"coerce", ty = "attr", default = "default_false")]
+    pub coerce: bool,
+    #[xmlserde(name = b"adjust", ty = "attr", default = "default_false")]
+    pub adjust: bool,
+    #[xmlserde(name = b"cellMeta", ty = "attr", default = "default_false")]
+    pub cell_meta: bool,
+}
+
+impl CtMetadataType {
+    // The metadata type of the dynamic arrays as Excel writes it.
+    fn dynamic_array() -> Self {
+        CtMetadataType {
+            name: DYNAMIC_ARRAY_TYPE.to_string(),
+            min_supported_version: 120000,
+            ghost_row: false,
+            ghost_col: false,
+            edit: false,
+            delete: false,
+            copy: true,
+            paste_all: true,
+            paste_formulas: false,
+            paste_values: true,
+            paste_formats: false,
+            paste_comments: false,
+            paste_data_validation: false,
+            paste_borders: false,
+            paste_col_widths: false,
+            paste_number_formats: false,
+            merge: true,
+            split_first: true,
+            split_all: false,
+            row_col_shift: true,
+            clear_all: false,
+            clear_formats: true,
+            clear_contents: false,
+            clear_comments: true,
+            assign: true,
+            coerce: true,
+            adjust: false,
+            cell_meta: true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, XmlSerialize, XmlDeserialize)]
+pub struct CtFutureMetadata {
+    #[xmlserde(name = b"name", ty = "attr")]
+    pub name: String,
+    #[xmlserde(name = b"count", ty = "attr", default = "default_zero_u32")]
+    pub count: u32,
+    #[xmlserde(name = b"bk", ty = "child")]
+    pub bk: Vec<CtFutureMetadataBlock>,
+}
+
+#[derive(Debug, Clone, XmlSerialize, XmlDeserialize)]
+pub struct CtFutureMetadataBlock {
+    #[xmlserde(name = b"extLst", ty = "child")]
+    pub ext_lst: Option<CtFutureMetadataExtensionList>,
+}
+
+#[derive(Debug, Clone, XmlSerialize, XmlDeserialize)]
+pub struct CtFutureMetadataExtensionList {
+    #[xmlserde(name = b"ext", ty = "child")]
+    pub ext: Vec<CtFutureMetadataExtension>,
+}
+
+#[derive(Debug, Clone, XmlSerialize, XmlDeserialize)]
+pub struct CtFutureMetadataExtension {
+    #[xmlserde(name = b"uri", ty = "attr")]
+    pub uri: String,
+    #[xmlserde(name = b"xda:dynamicArrayProperties", ty = "child")]
+    pub dynamic_array_properties: Option<CtDynamicArrayProperties>,
+    // The rich values, like the pictures in cells.
+    #[xmlserde(name = b"xlrd:rvb", ty = "child")]
+    pub rich_value_block: Option<Unparsed>,
+}
+
+#[derive(Debug, Clone, XmlSerialize, XmlDeserialize)]
+pub struct CtDynamicArrayProperties {
+    #[xmlserde(name = b"fDynamic", ty = "attr", default = "default_false")]
+    pub f_dynamic: bool,
+    #[xmlserde(name = b"fCollapsed", ty = "attr", default = "default_false")]
+    pub f_collapsed: bool,
+}
+
+#[derive(Debug, Clone, XmlSerialize, XmlDeserialize)]
+pub struct CtMetadataBlocks {
+    #[xmlserde(name = b"count", ty = "attr", default = "default_zero_u32")]
+    pub count: u32,
+    #[xmlserde(name = b"bk", ty = "child")]
+    pub bk: Vec<CtMetadataBlock>,
+}
+
+#[derive(Debug, Clone, XmlSerialize, XmlDeserialize)]
+pub struct CtMetadataBlock {
+    #[xmlserde(name = b"rc", ty = "child")]
+    pub rc: Vec<CtMetadataRecord>,
+}
+
+#[derive(Debug, Clone, XmlSerialize, XmlDeserialize)]
+pub struct CtMetadataRecord {
+    #[xmlserde(name = b"t", ty = "attr")]
+    pub t: u32,
+    #[xmlserde(name = b"v", ty = "attr")]
+    pub v: u32,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::MetadataPart;
+    use crate::ooxml::test_utils::in_one_line;
+    use crate::{xml_deserialize_from_str, xml_serialize_with_decl};
+
+    const XML: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<metadata xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main" xmlns:xda="http://schemas.microsoft.com/office/spreadsheetml/2017/dynamicarray">
+    <metadataTypes count="1">
+        <metadataType name="XLDAPR" minSupportedVersion="120000" copy="1" pasteAll="1" pasteValues="1" merge="1" splitFirst="1" rowColShift="1" clearFormats="1" clearComments="1" assign="1" coerce="1" cellMeta="1"/>
+    </metadataTypes>
+    <futureMetadata name="XLDAPR" count="1">
+        <bk>
+            <extLst>
+                <ext uri="{bdbb8cdc-fa1e-496e-a857-3c3f30c029c3}">
+                    <xda:dynamicArrayProperties fDynamic="1" fCollapsed="0"/>
+                </ext>
+            </extLst>
+        </bk>
+    </futureMetadata>
+    <cellMetadata count="1">
+        <bk>
+            <rc t="1" v="0"/>
+        </bk>
+    </cellMetadata>
+</metadata>"#;
+
+    #[test]
+    fn test() {
+        let r = xml_deserialize_from_str::<MetadataPart>(XML);
+        match r {
+            Ok(metadata) => {
+                assert_eq!(metadata.get_dynamic_array_cm(), Some(1));
+                let r = xml_serialize_with_decl(metadata);
+                // `fCollapsed="0"` is the default one.
+                let expected = in_one_line(XML).replace(r#" fCollapsed="0""#, "");
+                assert_eq!(expected, r);
+            }
+            Err(e) => panic!("{:?}", e),
+        }
+    }
+
+    #[test]
+    fn add_dynamic_array_cm_test() {
+        let mut metadata = MetadataPart::new();
+        assert_eq!(metadata.get_dynamic_array_cm(), None);
+        assert_eq!(metadata.get_or_add_dynamic_array_cm(), 1);
+        assert_eq!(metadata.get_or_add_dynamic_array_cm(), 1);
+        let r = xml_serialize_with_decl(metadata);
+        let expected = in_one_line(XML).replace(r#" fCollapsed="0""#, "");
+        assert_eq!(expected, r);
+    }
+}
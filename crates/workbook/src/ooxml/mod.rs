@@ -7,10 +7,12 @@ pub mod doc_props;
 pub mod drawings;
 pub mod enum_groups;
 pub mod external_links;
+pub mod metadata;
 pub mod relationships;
 pub mod simple_types;
 pub mod sst;
 pub mod style_sheet;
+pub mod table;
 #[cfg(test)]
 pub mod test_utils;
 pub mod theme;
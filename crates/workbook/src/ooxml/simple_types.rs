@@ -228,7 +228,7 @@ xml_serde_enum! {
 }
 
 xml_serde_enum! {
-    #[derive(Debug, PartialEq, Eq)]
+    #[derive(Debug, Clone, PartialEq, Eq)]
     StDateTimeGrouping {
         Year => "year",
         Month => "month",
@@ -262,7 +262,7 @@ pub type StDxfId = u32;
 
 xml_serde_enum! {
     /// 18.18.26
-    #[derive(Debug, PartialEq, Eq)]
+    #[derive(Debug, Clone, PartialEq, Eq)]
     StDynamicFilterType {
         Null => "null",
         AboveAverage => "aboveAverage",
@@ -340,7 +340,7 @@ xml_serde_enum! {
 pub type StFillId = u32;
 
 xml_serde_enum! {
-    #[derive(Debug, PartialEq, Eq)]
+    #[derive(Debug, Clone, PartialEq, Eq)]
     StFilterOperator {
         Equal => "equal",
         LessThan => "lessThan",
@@ -450,7 +450,7 @@ xml_serde_enum! {
 
 xml_serde_enum! {
     /// 18.18.42
-    #[derive(Debug, PartialEq, Eq)]
+    #[derive(Debug, Clone, PartialEq, Eq)]
     StIconSetType {
         ThreeArrows => "3Arrows",
         ThreeArrowsGray => "3ArrowsGray",
@@ -851,7 +851,7 @@ xml_serde_enum! {
 
 xml_serde_enum! {
     /// 18.18.72
-    #[derive(Debug, PartialEq, Eq)]
+    #[derive(Debug, Clone, PartialEq, Eq)]
     StSortBy {
         Value => "value",
         CellColor => "cellColor",
@@ -862,7 +862,7 @@ xml_serde_enum! {
 
 xml_serde_enum! {
     /// 18.18.73
-    #[derive(Debug, PartialEq, Eq)]
+    #[derive(Debug, Clone, PartialEq, Eq)]
     StSortMethod {
         Stroke => "stroke",
         PinYin => "pinYin",
@@ -1003,7 +1003,7 @@ xml_serde_enum! {
 
 xml_serde_enum! {
     /// 18.18.83
-    #[derive(Debug, PartialEq, Eq)]
+    #[derive(Debug, Clone, PartialEq, Eq)]
     StTotalsRowFunction {
         None => "none",
         Sum => "sum",
@@ -1150,7 +1150,7 @@ xml_serde_enum! {
 pub type StPositiveUniversalMeasure = String;
 
 xml_serde_enum! {
-    #[derive(Debug, PartialEq, Eq)]
+    #[derive(Debug, Clone, PartialEq, Eq)]
     StCalendarType {
         Gregorian => "gregorian",
         GregorianUs => "gregorianUs",
@@ -1170,7 +1170,7 @@ xml_serde_enum! {
 }
 
 xml_serde_enum! {
-    #[derive(Debug, PartialEq, Eq)]
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
     StTargetMode {
         External => "External",
         Internal => "Internal",
@@ -0,0 +1,102 @@
+use super::complex_types::*;
+use super::defaults::*;
+use super::simple_types::*;
+use xmlserde_derives::{XmlDeserialize, XmlSerialize};
+
+#[derive(Debug, Clone, XmlSerialize, XmlDeserialize)]
+#[xmlserde(with_ns = b"http://schemas.openxmlformats.org/spreadsheetml/2006/main")]
+#[xmlserde(root = b"table")]
+pub struct TablePart {
+    #[xmlserde(name = b"autoFilter", ty = "child")]
+    pub auto_filter: Option<CtAutoFilter>,
+    #[xmlserde(name = b"tableColumns", ty = "child")]
+    pub table_columns: CtTableColumns,
+    #[xmlserde(name = b"tableStyleInfo", ty = "child")]
+    pub table_style_info: Option<CtTableStyleInfo>,
+    #[xmlserde(name = b"id", ty = "attr")]
+    pub id: u32,
+    #[xmlserde(name = b"name", ty = "attr")]
+    pub name: Option<String>,
+    #[xmlserde(name = b"displayName", ty = "attr")]
+    pub display_name: String,
+    #[xmlserde(name = b"ref", ty = "attr")]
+    pub reference: StRef,
+    #[xmlserde(name = b"headerRowCount", ty = "attr", default = "default_one_u32")]
+    pub header_row_count: u32,
+    #[xmlserde(name = b"totalsRowCount", ty = "attr", default = "default_zero_u32")]
+    pub totals_row_count: u32,
+    #[xmlserde(name = b"totalsRowShown", ty = "attr", default = "default_true")]
+    pub totals_row_shown: bool,
+}
+
+#[derive(Debug, Clone, XmlSerialize, XmlDeserialize)]
+pub struct CtTableColumns {
+    #[xmlserde(name = b"tableColumn", ty = "child", vec_size = "count")]
+    pub table_columns: Vec<CtTableColumn>,
+    #[xmlserde(name = b"count", ty = "attr", default = "default_zero_u32")]
+    pub count: u32,
+}
+
+#[derive(Debug, Clone, XmlSerialize, XmlDeserialize)]
+pub struct CtTableColumn {
+    #[xmlserde(name = b"calculatedColumnFormula", ty = "child")]
+    pub calculated_column_formula: Option<PlainTextString>,
+    #[xmlserde(name = b"totalsRowFormula", ty = "child")]
+    pub totals_row_formula: Option<PlainTextString>,
+    #[xmlserde(name = b"id", ty = "attr")]
+    pub id: u32,
+    #[xmlserde(name = b"name", ty = "attr")]
+    pub name: String,
+    #[xmlserde(name = b"totalsRowFunction", ty = "attr")]
+    pub totals_row_function: Option<StTotalsRowFunction>,
+    #[xmlserde(name = b"totalsRowLabel", ty = "attr")]
+    pub totals_row_label: Option<String>,
+    #[xmlserde(name = b"dataDxfId", ty = "attr")]
+    pub data_dxf_id: Option<StDxfId>,
+}
+
+#[derive(Debug, Clone, XmlSerialize, XmlDeserialize)]
+pub struct CtTableStyleInfo {
+    #[xmlserde(name = b"name", ty = "attr")]
+    pub name: Option<String>,
+    #[xmlserde(name = b"showFirstColumn", ty = "attr")]
+    pub show_first_column: Option<bool>,
+    #[xmlserde(name = b"showLastColumn", ty = "attr")]
+    pub show_last_column: Option<bool>,
+    #[xmlserde(name = b"showRowStripes", ty = "attr")]
+    pub show_row_stripes: Option<bool>,
+    #[xmlserde(name = b"showColumnStripes", ty = "attr")]
+    pub show_column_stripes: Option<bool>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TablePart;
+    use crate::ooxml::simple_types::StTotalsRowFunction;
+    use crate::xml_deserialize_from_str;
+    #[test]
+    fn test1() {
+        let xml = include_str!("../../examples/table1.xml");
+        let r = xml_deserialize_from_str::<TablePart>(xml);
+        match r {
+            Ok(table) => {
+                assert_eq!(table.display_name, "Table1");
+                assert_eq!(table.reference, "B2:D7");
+                assert_eq!(table.header_row_count, 1);
+                assert_eq!(table.totals_row_count, 1);
+                assert_eq!(table.table_columns.table_columns.len(), 3);
+                let column = &table.table_columns.table_columns[2];
+                assert_eq!(column.name, "Total Price");
+                assert_eq!(
+                    column.calculated_column_formula.as_ref().unwrap().value,
+                    "Table1[[#This Row],[Price]]*2"
+                );
+                assert_eq!(column.totals_row_function, Some(StTotalsRowFunction::Sum));
+                let style = table.table_style_info.unwrap();
+                assert_eq!(style.name.as_deref(), Some("TableStyleMedium2"));
+                assert_eq!(style.show_row_stripes, Some(true));
+            }
+            Err(e) => panic!("{:?}", e),
+        }
+    }
+}
@@ -18,7 +18,6 @@ use xmlserde_derives::{XmlDeserialize, XmlSerialize};
 ))]
 #[xmlserde(with_custom_ns(b"mc", b"http://schemas.openxmlformats.org/markup-compatibility/2006"))]
 #[xmlserde(with_custom_ns(b"etc", b"http://www.wps.cn/officeDocument/2017/etCustomData"))]
-// Some worksheet contains an legacyDrawing element. We just ignored it.
 pub struct WorksheetPart {
     #[xmlserde(name = b"sheetPr", ty = "child")]
     pub sheet_pr: Option<CtSheetPr>,
@@ -80,6 +79,10 @@ pub struct WorksheetPart {
     pub smart_tags: Option<CtSmartTags>,
     #[xmlserde(name = b"drawing", ty = "child")]
     pub drawing: Option<CtDrawing>,
+    #[xmlserde(name = b"legacyDrawing", ty = "child")]
+    pub legacy_drawing: Option<CtLegacyDrawing>,
+    #[xmlserde(name = b"legacyDrawingHF", ty = "child")]
+    pub legacy_drawing_hf: Option<CtLegacyDrawing>,
     #[xmlserde(name = b"drawingHF", ty = "child")]
     pub drawing_hf: Option<CtDrawingHF>,
     #[xmlserde(name = b"picture", ty = "child")]
@@ -5,17 +5,28 @@ use crate::ooxml::doc_props::DocPropCore;
 use crate::ooxml::doc_props::DocPropCustom;
 use crate::ooxml::theme::ThemePart;
 use crate::ooxml::{
-    comments::Comments, external_links::ExternalLinkPart, relationships::Relationships,
-    sst::SstPart, style_sheet::StylesheetPart, workbook::WorkbookPart, worksheet::WorksheetPart,
+    comments::Comments,
+    content_types::ContentTypes,
+    external_links::ExternalLinkPart,
+    metadata::MetadataPart,
+    relationships::{CtRelationship, Relationships},
+    simple_types::StTargetMode,
+    sst::SstPart,
+    style_sheet::StylesheetPart,
+    table::TablePart,
+    workbook::WorkbookPart,
+    worksheet::WorksheetPart,
 };
 use crate::workbook::Id;
 use crate::workbook::Xl;
+use crate::workbook::{Passthrough, RawPart};
 use std::collections::HashMap;
 use std::{
     io::{BufReader, Cursor, Read, Seek},
     path::PathBuf,
     str::FromStr,
 };
+use tracing::error;
 use zip::ZipArchive;
 
 use crate::workbook::DocProps;
@@ -31,6 +42,8 @@ pub fn read(buf: &[u8]) -> Result<Workbook, SerdeErr> {
     let mut archive = ZipArchive::new(reader)?;
     let root = "_rels/.rels";
     let relationships = de_relationships(root, &mut archive)?;
+    let content_types = de_content_types("[Content_Types].xml", &mut archive).ok();
+    let mut passthrough = Passthrough::default();
     let mut xl = Err(SerdeErr::Custom(String::from(
         "Cannot find the workbook part",
     )));
@@ -43,7 +56,9 @@ pub fn read(buf: &[u8]) -> Result<Workbook, SerdeErr> {
         .for_each(|p| match RType(&p.ty) {
             WORKBOOK => {
                 let target = &p.target;
-                xl = de_xl(target, &mut archive);
+                xl = de_xl(target, &mut archive, &mut passthrough, &content_types);
+                passthrough.workbook_content_type =
+                    get_content_type(target, &content_types).filter(|t| t != XLSX_CONTENT_TYPE);
             }
             DOC_PROP_APP => {
                 let target = &p.target;
@@ -52,7 +67,7 @@ pub fn read(buf: &[u8]) -> Result<Workbook, SerdeErr> {
                         doc_prop_app = Some(app);
                     }
                     Err(e) => {
-                        println!("parsing file: {:?} but meet error:{:?}", target, e)
+                        error!("parsing file: {:?} but meet error:{:?}", target, e)
                     }
                 }
             }
@@ -63,7 +78,7 @@ pub fn read(buf: &[u8]) -> Result<Workbook, SerdeErr> {
                         doc_prop_core = Some(core);
                     }
                     Err(e) => {
-                        println!("parsing file: {:?} but meet error:{:?}", target, e)
+                        error!("parsing file: {:?} but meet error:{:?}", target, e)
                     }
                 }
             }
@@ -74,11 +89,20 @@ pub fn read(buf: &[u8]) -> Result<Workbook, SerdeErr> {
                         doc_prop_custom = Some(custom);
                     }
                     Err(e) => {
-                        println!("parsing file: {:?} but meet error:{:?}", target, e)
+                        error!("parsing file: {:?} but meet error:{:?}", target, e)
                     }
                 }
             }
-            _ => {}
+            _ => {
+                let r = de_unknown_relationship(
+                    p,
+                    root,
+                    &mut archive,
+                    &mut passthrough,
+                    &content_types,
+                );
+                passthrough.root_relationships.push(r);
+            }
         });
     let xl = xl?;
     let doc_props = DocProps {
@@ -86,7 +110,11 @@ pub fn read(buf: &[u8]) -> Result<Workbook, SerdeErr> {
         custom: doc_prop_custom,
         core: doc_prop_core,
     };
-    Ok(Workbook { xl, doc_props })
+    Ok(Workbook {
+        xl,
+        doc_props,
+        passthrough,
+    })
 }
 
 fn de_external_link<R: Read + Seek>(
@@ -116,13 +144,19 @@ fn de_external_link<R: Read + Seek>(
     })
 }
 
-fn de_xl<R: Read + Seek>(path: &str, archive: &mut ZipArchive<R>) -> Result<Xl, SerdeErr> {
+fn de_xl<R: Read + Seek>(
+    path: &str,
+    archive: &mut ZipArchive<R>,
+    passthrough: &mut Passthrough,
+    content_types: &Option<ContentTypes>,
+) -> Result<Xl, SerdeErr> {
     let workbook_part = de_workbook_part(path, archive)?;
     let mut styles = Option::<(Id, StylesheetPart)>::None;
     let mut sst = Option::<(Id, SstPart)>::None;
     let mut worksheets = HashMap::<Id, Worksheet>::new();
     let mut external_links = HashMap::<Id, ExternalLink>::new();
     let mut theme = Option::<(Id, ThemePart)>::None;
+    let mut metadata = Option::<(Id, MetadataPart)>::None;
     let path_buf = get_rels(path)?;
     let rels = path_buf.to_str();
     if rels.is_none() {
@@ -139,12 +173,17 @@ fn de_xl<R: Read + Seek>(path: &str, archive: &mut ZipArchive<R>) -> Result<Xl,
                 let target = &r.target;
                 let path = get_target_abs_path(rels, target);
                 if let Some(s) = path.to_str() {
-                    match de_worksheet(s, archive) {
-                        Ok(w) => {
+                    match de_worksheet(s, archive, passthrough, content_types) {
+                        Ok((w, unknown)) => {
+                            if !unknown.is_empty() {
+                                passthrough
+                                    .worksheet_relationships
+                                    .insert(id.clone(), unknown);
+                            }
                             worksheets.insert(id, w);
                         }
                         Err(e) => {
-                            println!("parsing file: {:?} but meet error:{:?}", s, e)
+                            error!("parsing file: {:?} but meet error:{:?}", s, e)
                         }
                     }
                 }
@@ -159,7 +198,7 @@ fn de_xl<R: Read + Seek>(path: &str, archive: &mut ZipArchive<R>) -> Result<Xl,
                             external_links.insert(id, w);
                         }
                         Err(e) => {
-                            println!("parsing file: {:?} but meet error:{:?}", s, e)
+                            error!("parsing file: {:?} but meet error:{:?}", s, e)
                         }
                     }
                 }
@@ -174,7 +213,7 @@ fn de_xl<R: Read + Seek>(path: &str, archive: &mut ZipArchive<R>) -> Result<Xl,
                             sst = Some((id, w));
                         }
                         Err(e) => {
-                            println!("parsing file: {:?} but meet error:{:?}", s, e)
+                            error!("parsing file: {:?} but meet error:{:?}", s, e)
                         }
                     }
                 }
@@ -189,7 +228,7 @@ fn de_xl<R: Read + Seek>(path: &str, archive: &mut ZipArchive<R>) -> Result<Xl,
                             styles = Some((id, w));
                         }
                         Err(e) => {
-                            println!("parsing file: {:?} but meet error:{:?}", s, e)
+                            error!("parsing file: {:?} but meet error:{:?}", s, e)
                         }
                     }
                 }
@@ -204,12 +243,30 @@ fn de_xl<R: Read + Seek>(path: &str, archive: &mut ZipArchive<R>) -> Result<Xl,
                             theme = Some((id, w));
                         }
                         Err(e) => {
-                            println!("parsing file: {:?} but meet error:{:?}", s, e)
+                            error!("parsing file: {:?} but meet error:{:?}", s, e)
                         }
                     }
                 }
             }
-            _ => {}
+            METADATA => {
+                let target = &r.target;
+                let id = r.id;
+                let path = get_target_abs_path(rels, target);
+                if let Some(s) = path.to_str() {
+                    match de_metadata(s, archive) {
+                        Ok(w) => {
+                            metadata = Some((id, w));
+                        }
+                        Err(e) => {
+                            error!("parsing file: {:?} but meet error:{:?}", s, e)
+                        }
+                    }
+                }
+            }
+            _ => {
+                let r = de_unknown_relationship(r, rels, archive, passthrough, content_types);
+                passthrough.workbook_relationships.push(r);
+            }
         });
     Ok(Xl {
         workbook_part,
@@ -218,15 +275,21 @@ fn de_xl<R: Read + Seek>(path: &str, archive: &mut ZipArchive<R>) -> Result<Xl,
         worksheets,
         external_links,
         theme,
+        metadata,
     })
 }
 
+/// Returns the worksheet and its unrecognized relationships.
 fn de_worksheet<R: Read + Seek>(
     path: &str,
     archive: &mut ZipArchive<R>,
-) -> Result<Worksheet, SerdeErr> {
+    passthrough: &mut Passthrough,
+    content_types: &Option<ContentTypes>,
+) -> Result<(Worksheet, Vec<CtRelationship>), SerdeErr> {
     let worksheet_part = de_worksheet_part(path, archive)?;
     let mut comments = Option::<Comments>::None;
+    let mut tables = Vec::<TablePart>::new();
+    let mut unknown = Vec::<CtRelationship>::new();
     let path_buf = get_rels(path)?;
     let rels = path_buf.to_str();
     if rels.is_none() {
@@ -235,10 +298,14 @@ fn de_worksheet<R: Read + Seek>(
     let rels = rels.unwrap();
     let result = de_relationships(rels, archive);
     if result.is_err() {
-        return Ok(Worksheet {
-            worksheet_part,
-            comments,
-        });
+        return Ok((
+            Worksheet {
+                worksheet_part,
+                comments,
+                tables,
+            },
+            unknown,
+        ));
     }
     let relationships = result.unwrap();
     relationships
@@ -257,12 +324,150 @@ fn de_worksheet<R: Read + Seek>(
                     }
                 }
             }
-            _ => {}
+            TABLE => {
+                let path = get_target_abs_path(rels, &r.target);
+                let table = path.to_str().and_then(|p| de_table(p, archive).ok());
+                match table {
+                    Some(t) => tables.push(t),
+                    None => {
+                        let r =
+                            de_unknown_relationship(r, rels, archive, passthrough, content_types);
+                        unknown.push(r);
+                    }
+                }
+            }
+            _ => {
+                let r = de_unknown_relationship(r, rels, archive, passthrough, content_types);
+                unknown.push(r);
+            }
         });
-    Ok(Worksheet {
-        worksheet_part,
-        comments,
-    })
+    Ok((
+        Worksheet {
+            worksheet_part,
+            comments,
+            tables,
+        },
+        unknown,
+    ))
+}
+
+/// Keep the target of an unrecognized relationship and all the parts it
+/// refers to as raw parts. Returns the relationship whose target is replaced
+/// by the absolute path.
+fn de_unknown_relationship<R: Read + Seek>(
+    r: CtRelationship,
+    rels: &str,
+    archive: &mut ZipArchive<R>,
+    passthrough: &mut Passthrough,
+    content_types: &Option<ContentTypes>,
+) -> CtRelationship {
+    if r.target_mode == StTargetMode::External {
+        return r;
+    }
+    let path = get_internal_target_path(rels, &r.target);
+    de_raw_part(&path, archive, passthrough, content_types);
+    CtRelationship {
+        id: r.id,
+        ty: r.ty,
+        target: path,
+        target_mode: r.target_mode,
+    }
+}
+
+fn de_raw_part<R: Read + Seek>(
+    path: &str,
+    archive: &mut ZipArchive<R>,
+    passthrough: &mut Passthrough,
+    content_types: &Option<ContentTypes>,
+) {
+    if passthrough.parts.contains_key(path) {
+        return;
+    }
+    let data = match read_bytes(path, archive) {
+        Ok(d) => d,
+        Err(e) => {
+            error!("reading file: {:?} but meet error:{:?}", path, e);
+            return;
+        }
+    };
+    passthrough.parts.insert(
+        path.to_string(),
+        RawPart {
+            content_type: get_content_type(path, content_types),
+            data,
+        },
+    );
+    let rels = match get_rels(path).map(|p| p.to_str().map(String::from)) {
+        Ok(Some(rels)) => rels,
+        _ => return,
+    };
+    let data = match read_bytes(&rels, archive) {
+        Ok(d) => d,
+        Err(_) => return,
+    };
+    let relationships = xml_deserialize_from_reader::<Relationships, _>(Cursor::new(&data));
+    passthrough.parts.insert(
+        rels.clone(),
+        RawPart {
+            content_type: None,
+            data,
+        },
+    );
+    if let Ok(relationships) = relationships {
+        relationships
+            .relationships
+            .into_iter()
+            .filter(|r| r.target_mode == StTargetMode::Internal)
+            .for_each(|r| {
+                let p = get_internal_target_path(&rels, &r.target);
+                de_raw_part(&p, archive, passthrough, content_types)
+            });
+    }
+}
+
+fn read_bytes<R: Read + Seek>(
+    path: &str,
+    archive: &mut ZipArchive<R>,
+) -> Result<Vec<u8>, SerdeErr> {
+    let mut file = archive.by_name(path)?;
+    let mut data = Vec::<u8>::new();
+    file.read_to_end(&mut data)?;
+    Ok(data)
+}
+
+/// Find the content type of a part from its override. Parts using the default
+/// content types of their extensions get `None` if the extension is `.rels`
+/// or `.xml`, because these default ones are always written.
+fn get_content_type(path: &str, content_types: &Option<ContentTypes>) -> Option<String> {
+    let content_types = content_types.as_ref()?;
+    let part_name = format!("/{}", path.trim_start_matches('/'));
+    if let Some(o) = content_types
+        .overides
+        .iter()
+        .find(|o| o.part_name.eq_ignore_ascii_case(&part_name))
+    {
+        return Some(o.content_type.clone());
+    }
+    let ext = path.rsplit('.').next()?;
+    if ext.eq_ignore_ascii_case("rels") || ext.eq_ignore_ascii_case("xml") {
+        return None;
+    }
+    content_types
+        .defaults
+        .iter()
+        .find(|d| d.extension.eq_ignore_ascii_case(ext))
+        .map(|d| d.content_type.clone())
+}
+
+/// Targets of the relationships may be absolute paths starting with `/`.
+pub(crate) fn get_internal_target_path(rels: &str, target: &str) -> String {
+    match target.strip_prefix('/') {
+        Some(p) => p.to_string(),
+        None => get_target_abs_path(rels, target)
+            .to_str()
+            .map(String::from)
+            .unwrap_or_default(),
+    }
 }
 
 macro_rules! define_de_func {
@@ -280,19 +485,25 @@ macro_rules! define_de_func {
 }
 
 define_de_func!(de_relationships, Relationships);
+define_de_func!(de_content_types, ContentTypes);
 define_de_func!(de_external_link_part, ExternalLinkPart);
 define_de_func!(de_workbook_part, WorkbookPart);
 define_de_func!(de_worksheet_part, WorksheetPart);
 define_de_func!(de_comments, Comments);
+define_de_func!(de_table, TablePart);
 define_de_func!(de_sst, SstPart);
 define_de_func!(de_style_part, StylesheetPart);
 define_de_func!(de_theme, ThemePart);
+define_de_func!(de_metadata, MetadataPart);
 define_de_func!(de_doc_prop_custom, DocPropCustom);
 define_de_func!(de_doc_prop_app, DocPropApp);
 define_de_func!(de_doc_prop_core, DocPropCore);
 
+const XLSX_CONTENT_TYPE: &str =
+    "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet.main+xml";
+
 /// Given a path `/foo/test.xml`, find its relationships `/foo/_rels/test.xml.rels`
-fn get_rels(path: &str) -> Result<PathBuf, SerdeErr> {
+pub(crate) fn get_rels(path: &str) -> Result<PathBuf, SerdeErr> {
     let p = PathBuf::from_str(path);
     if p.is_err() {
         return Err(SerdeErr::Custom(format!(
@@ -9,12 +9,20 @@ pub const SST: RType =
     RType("http://schemas.openxmlformats.org/officeDocument/2006/relationships/sharedStrings");
 pub const EXT_LINK: RType =
     RType("http://schemas.openxmlformats.org/officeDocument/2006/relationships/externalLink");
+pub const EXT_LINK_PATH: RType =
+    RType("http://schemas.openxmlformats.org/officeDocument/2006/relationships/externalLinkPath");
 pub const STYLE: RType =
     RType("http://schemas.openxmlformats.org/officeDocument/2006/relationships/styles");
 pub const COMMENTS: RType =
     RType("http://schemas.openxmlformats.org/officeDocument/2006/relationships/comments");
+pub const TABLE: RType =
+    RType("http://schemas.openxmlformats.org/officeDocument/2006/relationships/table");
 pub const THEME: RType =
     RType("http://schemas.openxmlformats.org/officeDocument/2006/relationships/theme");
+pub const METADATA: RType =
+    RType("http://schemas.openxmlformats.org/officeDocument/2006/relationships/sheetMetadata");
+pub const CALC_CHAIN: RType =
+    RType("http://schemas.openxmlformats.org/officeDocument/2006/relationships/calcChain");
 pub const DOC_PROP_APP: RType = RType(
     "http://schemas.openxmlformats.org/officeDocument/2006/relationships/extended-properties",
 );
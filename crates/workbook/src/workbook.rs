@@ -1,12 +1,15 @@
 use crate::ooxml::comments::Comments;
 use crate::ooxml::doc_props::{DocPropApp, DocPropCore, DocPropCustom};
 use crate::ooxml::external_links::*;
+use crate::ooxml::metadata::MetadataPart;
+use crate::ooxml::relationships::CtRelationship;
 use crate::ooxml::sst::SstPart;
 use crate::ooxml::style_sheet::StylesheetPart;
+use crate::ooxml::table::TablePart;
 use crate::ooxml::theme::ThemePart;
 use crate::ooxml::workbook::WorkbookPart;
 use crate::ooxml::worksheet::WorksheetPart;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 
 use crate::SerdeErr;
 
@@ -16,6 +19,7 @@ pub type Id = String;
 pub struct Workbook {
     pub xl: Xl,
     pub doc_props: DocProps,
+    pub passthrough: Passthrough,
 }
 
 #[derive(Debug)]
@@ -26,12 +30,16 @@ pub struct Xl {
     pub worksheets: HashMap<Id, Worksheet>,
     pub external_links: HashMap<Id, ExternalLink>,
     pub theme: Option<(Id, ThemePart)>,
+    pub metadata: Option<(Id, MetadataPart)>,
 }
 
 #[derive(Debug)]
 pub struct Worksheet {
     pub worksheet_part: WorksheetPart,
     pub comments: Option<Comments>,
+    /// The table parts of this sheet. The writer numbers them again and
+    /// rebuilds the `tableParts` of the worksheet part from them.
+    pub tables: Vec<TablePart>,
 }
 
 #[derive(Debug)]
@@ -53,6 +61,49 @@ impl DocProps {
     }
 }
 
+/// Parts of the package that we do not parse, like drawings, charts, pivot
+/// caches, VBA projects and custom xml. They are kept as they are and
+/// written back unchanged.
+///
+/// The targets of the internal relationships here are the absolute paths of
+/// the parts in the package, so that they can be written back no matter
+/// where the source parts are placed.
+#[derive(Debug, Default, Clone)]
+pub struct Passthrough {
+    /// Raw parts indexed by their paths. The relationship parts of them are
+    /// also kept here.
+    pub parts: BTreeMap<String, RawPart>,
+    /// Unrecognized relationships of the package.
+    pub root_relationships: Vec<CtRelationship>,
+    /// Unrecognized relationships of the workbook part. Their ids are kept
+    /// because they may be referenced in the workbook part.
+    pub workbook_relationships: Vec<CtRelationship>,
+    /// Unrecognized relationships of the worksheets, indexed by the ids of
+    /// the worksheets in the workbook part.
+    pub worksheet_relationships: HashMap<Id, Vec<CtRelationship>>,
+    /// The content type of the workbook part if it is not a `.xlsx` one, for
+    /// example, a macro-enabled workbook.
+    pub workbook_content_type: Option<String>,
+}
+
+impl Passthrough {
+    pub fn is_empty(&self) -> bool {
+        self.parts.is_empty()
+            && self.root_relationships.is_empty()
+            && self.workbook_relationships.is_empty()
+            && self.worksheet_relationships.is_empty()
+            && self.workbook_content_type.is_none()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct RawPart {
+    /// `None` if the content type is decided by the default ones, like
+    /// `.rels` files.
+    pub content_type: Option<String>,
+    pub data: Vec<u8>,
+}
+
 impl Workbook {
     pub fn get_sheet_by_name(&self, name: &str) -> Option<&Worksheet> {
         let sheet = self
@@ -1,41 +1,213 @@
 use std::collections::HashSet;
 use std::io::{Cursor, Read};
 
-use xmldiff::diff;
+use xmldiff::{diff, Diff, Element, ElementKind};
 use zip::ZipArchive;
 
-pub fn zipdiff(zip1: &[u8], zip2: &[u8]) {
-    let mut archive1 = ZipArchive::new(Cursor::new(zip1)).unwrap();
-    let mut archive2 = ZipArchive::new(Cursor::new(zip2)).unwrap();
-    let files1 = archive1
-        .file_names()
-        .map(|s| String::from(s))
-        .collect::<HashSet<_>>();
-    let files2 = archive2
-        .file_names()
-        .map(|s| String::from(s))
-        .collect::<HashSet<_>>();
+use crate::SerdeErr;
+
+/// The differences between 2 .xlsx files.
+#[derive(Debug, Default)]
+pub struct ZipDiff {
+    /// Parts that exist in the first file but not in the second one.
+    pub missing_parts: Vec<String>,
+    /// Parts that exist in the second file but not in the first one.
+    pub new_parts: Vec<String>,
+    /// Non-xml parts whose bytes are changed.
+    pub changed_binary_parts: Vec<String>,
+    pub part_diffs: Vec<PartDiff>,
+}
+
+/// The differences found in an xml part.
+#[derive(Debug)]
+pub struct PartDiff {
+    pub path: String,
+    pub diffs: Vec<Diff>,
+}
 
-    for x in files1.difference(&files2) {
-        println!("missing files: {}", x);
+impl PartDiff {
+    pub fn attribute_changes(&self) -> Vec<&Diff> {
+        self.filter_by_kind(|k| matches!(k, ElementKind::Attribute))
     }
-    for y in files2.difference(&files1) {
-        println!("importing files: {}", y);
+
+    pub fn element_changes(&self) -> Vec<&Diff> {
+        self.filter_by_kind(|k| !matches!(k, ElementKind::Attribute))
     }
-    for p in files1.intersection(&files2) {
-        let mut file1 = archive1.by_name(p).unwrap();
-        let mut file2 = archive2.by_name(p).unwrap();
-        let mut content1 = Vec::<u8>::new();
-        let mut content2 = Vec::<u8>::new();
-        file1.read_to_end(&mut content1).unwrap();
-        file2.read_to_end(&mut content2).unwrap();
-        let str1 = String::from_utf8(content1).unwrap();
-        let str2 = String::from_utf8(content2).unwrap();
-        let diffs = diff(&str1, &str2);
-        if diffs.len() > 0 {
-            println!("Find diffs in file: {}", p);
-            println!("{:?}", diffs);
+
+    fn filter_by_kind<F>(&self, f: F) -> Vec<&Diff>
+    where
+        F: Fn(ElementKind) -> bool,
+    {
+        self.diffs
+            .iter()
+            .filter(|d| f(get_element(d).kind))
+            .collect()
+    }
+}
+
+/// Differences that are expected and should be ignored.
+#[derive(Debug, Default)]
+pub struct Whitelist {
+    /// Ignore the parts whose path starts with any of these prefixes.
+    pub parts: Vec<String>,
+    /// Ignore the attributes or elements with these tags in any part.
+    pub tags: Vec<String>,
+    /// Ignore the attributes or elements with these tags that only exist in
+    /// the second file.
+    pub created_tags: Vec<String>,
+}
+
+impl ZipDiff {
+    pub fn is_empty(&self) -> bool {
+        self.missing_parts.is_empty()
+            && self.new_parts.is_empty()
+            && self.changed_binary_parts.is_empty()
+            && self.part_diffs.is_empty()
+    }
+
+    /// Remove the differences in the whitelist.
+    pub fn filter(self, whitelist: &Whitelist) -> Self {
+        let accepted_part = |p: &String| whitelist.parts.iter().any(|w| p.starts_with(w));
+        let accepted_tag = |d: &Diff| match d {
+            Diff::Create(e) if whitelist.created_tags.contains(&e.tag) => true,
+            _ => whitelist.tags.contains(&get_element(d).tag),
+        };
+        let part_diffs = self
+            .part_diffs
+            .into_iter()
+            .filter(|pd| !accepted_part(&pd.path))
+            .filter_map(|pd| {
+                let diffs = pd
+                    .diffs
+                    .into_iter()
+                    .filter(|d| !accepted_tag(d))
+                    .collect::<Vec<_>>();
+                if diffs.is_empty() {
+                    None
+                } else {
+                    Some(PartDiff {
+                        path: pd.path,
+                        diffs,
+                    })
+                }
+            })
+            .collect();
+        ZipDiff {
+            missing_parts: without(self.missing_parts, accepted_part),
+            new_parts: without(self.new_parts, accepted_part),
+            changed_binary_parts: without(self.changed_binary_parts, accepted_part),
+            part_diffs,
         }
     }
-    return;
+}
+
+pub fn zipdiff(zip1: &[u8], zip2: &[u8]) -> Result<ZipDiff, SerdeErr> {
+    zipdiff_with(zip1, zip2, |_, content| content)
+}
+
+/// Compare 2 .xlsx files after normalizing the xml parts of both, like the
+/// spacing of the formulas. `normalize` is called with the path and the
+/// content of a part.
+pub fn zipdiff_with<F>(zip1: &[u8], zip2: &[u8], normalize: F) -> Result<ZipDiff, SerdeErr>
+where
+    F: Fn(&str, String) -> String,
+{
+    zipdiff_with_each(zip1, zip2, &normalize, &normalize)
+}
+
+/// Like `zipdiff_with`, but the parts of each file are normalized by its own
+/// function. It is for the parts that depend on the other parts of the same
+/// file, like the style indexes of the cells.
+pub fn zipdiff_with_each<F1, F2>(
+    zip1: &[u8],
+    zip2: &[u8],
+    normalize1: F1,
+    normalize2: F2,
+) -> Result<ZipDiff, SerdeErr>
+where
+    F1: Fn(&str, String) -> String,
+    F2: Fn(&str, String) -> String,
+{
+    let mut archive1 = ZipArchive::new(Cursor::new(zip1))?;
+    let mut archive2 = ZipArchive::new(Cursor::new(zip2))?;
+    let files1 = get_file_names(&archive1);
+    let files2 = get_file_names(&archive2);
+
+    let mut result = ZipDiff::default();
+    files1
+        .iter()
+        .filter(|f| !files2.contains(*f))
+        .for_each(|f| {
+            result.missing_parts.push(f.clone());
+        });
+    files2
+        .iter()
+        .filter(|f| !files1.contains(*f))
+        .for_each(|f| {
+            result.new_parts.push(f.clone());
+        });
+    for p in files1.iter().filter(|f| files2.contains(*f)) {
+        let content1 = read_file(&mut archive1, p)?;
+        let content2 = read_file(&mut archive2, p)?;
+        if content1 == content2 {
+            continue;
+        }
+        match (String::from_utf8(content1), String::from_utf8(content2)) {
+            (Ok(str1), Ok(str2)) if is_xml(p) => {
+                let str1 = normalize1(p, str1);
+                let str2 = normalize2(p, str2);
+                if str1 == str2 {
+                    continue;
+                }
+                let diffs = diff(&str1, &str2);
+                if !diffs.is_empty() {
+                    result.part_diffs.push(PartDiff {
+                        path: p.clone(),
+                        diffs,
+                    });
+                }
+            }
+            _ => result.changed_binary_parts.push(p.clone()),
+        }
+    }
+    Ok(result)
+}
+
+fn get_file_names(archive: &ZipArchive<Cursor<&[u8]>>) -> Vec<String> {
+    let mut names = archive
+        .file_names()
+        // Directories have no content.
+        .filter(|s| !s.ends_with('/'))
+        .map(String::from)
+        .collect::<HashSet<_>>()
+        .into_iter()
+        .collect::<Vec<_>>();
+    names.sort();
+    names
+}
+
+fn read_file(archive: &mut ZipArchive<Cursor<&[u8]>>, name: &str) -> Result<Vec<u8>, SerdeErr> {
+    let mut file = archive.by_name(name)?;
+    let mut content = Vec::<u8>::new();
+    file.read_to_end(&mut content)?;
+    Ok(content)
+}
+
+fn is_xml(path: &str) -> bool {
+    path.ends_with(".xml") || path.ends_with(".rels") || path.ends_with(".vml")
+}
+
+fn get_element(d: &Diff) -> &Element {
+    match d {
+        Diff::Delete(e) => e,
+        Diff::Create(e) => e,
+        Diff::Update(e) => e,
+    }
+}
+
+fn without<F>(parts: Vec<String>, accepted: F) -> Vec<String>
+where
+    F: Fn(&String) -> bool,
+{
+    parts.into_iter().filter(|p| !accepted(p)).collect()
 }
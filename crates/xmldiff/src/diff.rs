@@ -1,17 +1,40 @@
-use crate::xml_data::{Decl, KeyValue, Object, Value, XmlData};
+use crate::xml_data::{Decl, KeyValue, Object, Value, XmlData, TEXT_KEY};
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum ElementKind {
+    Attribute,
+    Text,
+    Node,
+}
 
 #[derive(Debug, PartialEq)]
 pub struct Element {
     pub tag: String,
     pub positon: usize,
+    pub kind: ElementKind,
 }
 
 impl From<KeyValue> for Element {
     fn from(kv: KeyValue) -> Self {
+        let kind = get_kind(&kv.key, &kv.value);
         Element {
             tag: kv.key,
             positon: kv.position,
+            kind,
+        }
+    }
+}
+
+fn get_kind(key: &str, value: &Value) -> ElementKind {
+    match value {
+        Value::AttrOrText(_) => {
+            if key == TEXT_KEY {
+                ElementKind::Text
+            } else {
+                ElementKind::Attribute
+            }
         }
+        _ => ElementKind::Node,
     }
 }
 
@@ -32,6 +55,7 @@ fn compare_decl(decl1: Option<Decl>, decl2: Option<Decl>) -> Vec<Diff> {
     let element = Element {
         tag: String::from("Decl"),
         positon: 0,
+        kind: ElementKind::Node,
     };
     match (decl1, decl2) {
         (None, None) => vec![],
@@ -98,23 +122,68 @@ pub fn compare_object(obj1: Object, obj2: Object) -> Vec<Diff> {
 }
 
 fn compare_value(tag: String, pos1: usize, v1: Value, v2: Value) -> Vec<Diff> {
+    let kind = get_kind(&tag, &v1);
+    let update = Element {
+        tag,
+        positon: pos1,
+        kind,
+    };
     match (v1, v2) {
         (Value::Element(obj1), Value::Element(obj2)) => compare_object(obj1, obj2),
         (Value::AttrOrText(v1), Value::AttrOrText(v2)) => {
             if v1 == v2 {
                 vec![]
             } else {
-                vec![Diff::Update(Element { tag, positon: pos1 })]
+                vec![Diff::Update(update)]
             }
         }
         (Value::Array(arr1), Value::Array(arr2)) => {
             if arr1.len() != arr2.len() {
-                vec![Diff::Update(Element { tag, positon: pos1 })]
+                vec![Diff::Update(update)]
             } else {
-                // FIXME: check the element of the array.
-                vec![]
+                arr1.into_iter()
+                    .zip(arr2)
+                    .flat_map(|(obj1, obj2)| compare_object(obj1, obj2))
+                    .collect()
             }
         }
-        _ => vec![Diff::Update(Element { tag, positon: pos1 })],
+        _ => vec![Diff::Update(update)],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Diff, ElementKind};
+    use crate::diff;
+
+    #[test]
+    fn diff_attr_and_text() {
+        let xml1 = r#"<root a="1"><b>x</b></root>"#;
+        let xml2 = r#"<root a="2"><b>y</b><c/></root>"#;
+        let diffs = diff(xml1, xml2);
+        assert_eq!(diffs.len(), 3);
+        assert!(diffs.iter().any(|d| matches!(
+            d,
+            Diff::Update(e) if e.tag == "a" && e.kind == ElementKind::Attribute
+        )));
+        assert!(diffs.iter().any(|d| matches!(
+            d,
+            Diff::Update(e) if e.kind == ElementKind::Text
+        )));
+        assert!(diffs.iter().any(|d| matches!(
+            d,
+            Diff::Create(e) if e.tag == "c" && e.kind == ElementKind::Node
+        )));
+    }
+
+    #[test]
+    fn diff_array_items() {
+        let xml1 = r#"<root><row r="1"/><row r="2"/></root>"#;
+        let xml2 = r#"<root><row r="1"/><row r="3"/></root>"#;
+        let diffs = diff(xml1, xml2);
+        assert_eq!(diffs.len(), 1);
+        assert!(matches!(&diffs[0], Diff::Update(e) if e.tag == "r"));
+        let xml3 = r#"<root><row r="1"/><row r="2"/></root>"#;
+        assert!(diff(xml1, xml3).is_empty());
     }
 }
@@ -2,6 +2,8 @@ use std::collections::HashMap;
 
 use quick_xml::{events::*, Reader};
 
+pub const TEXT_KEY: &str = "__text__";
+
 #[derive(Debug)]
 pub struct Error {}
 
@@ -143,7 +145,7 @@ pub fn get_object(
                 let str = get_string(t.escaped());
                 if str != "" {
                     key_values.push(KeyValue {
-                        key: String::from("__text__"),
+                        key: String::from(TEXT_KEY),
                         value: Box::new(Value::AttrOrText(str)),
                         position,
                     });
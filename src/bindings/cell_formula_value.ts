@@ -1,4 +1,5 @@
 // DO NOT EDIT. CODE GENERATED BY gents.
+import {FormattedValue} from './formatted_value'
 import {Value} from './value'
 
 export interface CellFormulaValue {
@@ -6,4 +7,6 @@ export interface CellFormulaValue {
     col: number
     formula: string
     value: Value
+    formatted: FormattedValue
+    stale: boolean
 }
@@ -0,0 +1,9 @@
+// DO NOT EDIT. CODE GENERATED BY gents.
+import {CircularRefCell} from './circular_ref_cell'
+
+export interface CircularRef {
+    cells: readonly CircularRefCell[]
+    iterations: number
+    maxChange: number
+    converged: boolean
+}
@@ -0,0 +1,7 @@
+// DO NOT EDIT. CODE GENERATED BY gents.
+
+export interface CircularRefCell {
+    sheetIdx: number
+    row: number
+    col: number
+}
@@ -0,0 +1,6 @@
+// DO NOT EDIT. CODE GENERATED BY gents.
+import {DependencyRange} from './dependency_range'
+
+export type Dependency =
+    | {range: DependencyRange}
+    | {name: string}
@@ -0,0 +1,11 @@
+// DO NOT EDIT. CODE GENERATED BY gents.
+
+export interface DependencyRange {
+    book: string | null
+    fromSheet: string | null
+    sheet: string
+    rowStart: number | null
+    colStart: number | null
+    rowEnd: number | null
+    colEnd: number | null
+}
@@ -1,7 +1,9 @@
 // DO NOT EDIT. CODE GENERATED BY gents.
 import {PayloadsAction} from './payloads_action'
+import {RecalcAction} from './recalc_action'
 
 export type EditAction =
     | 'Undo'
     | 'Redo'
     | {Payloads: PayloadsAction}
+    | {Recalc: RecalcAction}
@@ -0,0 +1,11 @@
+// DO NOT EDIT. CODE GENERATED BY gents.
+import {EvalValue} from './eval_value'
+
+export interface EvalTrace {
+    expression: string
+    value: EvalValue
+    reference: string | null
+    errorOrigin: boolean
+    volatile: boolean
+    children: readonly EvalTrace[]
+}
@@ -0,0 +1,9 @@
+// DO NOT EDIT. CODE GENERATED BY gents.
+import {Value} from './value'
+
+export type EvalValue =
+    | {scalar: Value}
+    | {array: readonly (readonly Value[])[]}
+    | {union: readonly EvalValue[]}
+    | 'lambda'
+    | 'omitted'
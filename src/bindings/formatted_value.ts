@@ -0,0 +1,6 @@
+// DO NOT EDIT. CODE GENERATED BY gents.
+
+export interface FormattedValue {
+    text: string
+    color: string | null
+}
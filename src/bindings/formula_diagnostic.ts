@@ -0,0 +1,8 @@
+// DO NOT EDIT. CODE GENERATED BY gents.
+
+export interface FormulaDiagnostic {
+    start: number
+    end: number
+    expected: readonly string[]
+    message: string
+}
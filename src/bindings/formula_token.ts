@@ -0,0 +1,11 @@
+// DO NOT EDIT. CODE GENERATED BY gents.
+import {DependencyRange} from './dependency_range'
+import {FormulaTokenKind} from './formula_token_kind'
+
+export interface FormulaToken {
+    kind: FormulaTokenKind
+    text: string
+    start: number
+    end: number
+    reference: DependencyRange | null
+}
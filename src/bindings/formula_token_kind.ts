@@ -0,0 +1,17 @@
+// DO NOT EDIT. CODE GENERATED BY gents.
+
+export type FormulaTokenKind =
+    | 'function'
+    | 'reference'
+    | 'name'
+    | 'number'
+    | 'text'
+    | 'boolean'
+    | 'error'
+    | 'operator'
+    | 'separator'
+    | 'openParen'
+    | 'closeParen'
+    | 'openBrace'
+    | 'closeBrace'
+    | 'unknown'
@@ -0,0 +1,9 @@
+// DO NOT EDIT. CODE GENERATED BY gents.
+import {FunctionArgType} from './function_arg_type'
+
+export interface FunctionArg {
+    name: string
+    argType: FunctionArgType
+    optional: boolean
+    repeated: boolean
+}
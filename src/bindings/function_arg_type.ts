@@ -0,0 +1,10 @@
+// DO NOT EDIT. CODE GENERATED BY gents.
+
+export type FunctionArgType =
+    | 'any'
+    | 'number'
+    | 'text'
+    | 'logical'
+    | 'reference'
+    | 'array'
+    | 'function'
@@ -0,0 +1,8 @@
+// DO NOT EDIT. CODE GENERATED BY gents.
+import {FunctionArg} from './function_arg'
+
+export interface FunctionInfo {
+    name: string
+    description: string
+    args: readonly FunctionArg[]
+}
@@ -12,20 +12,33 @@ export * from './cell_id'
 export * from './cell_input'
 export * from './cell_protection'
 export * from './cell_style'
+export * from './circular_ref'
+export * from './circular_ref_cell'
 export * from './col_info'
 export * from './col_shift'
 export * from './color'
 export * from './comment'
 export * from './create_block'
+export * from './dependency'
+export * from './dependency_range'
 export * from './display_patch'
 export * from './display_request'
 export * from './display_response'
 export * from './edit_action'
+export * from './eval_trace'
+export * from './eval_value'
 export * from './fill'
 export * from './font'
 export * from './font_family'
 export * from './font_name'
 export * from './font_scheme'
+export * from './formatted_value'
+export * from './formula_diagnostic'
+export * from './formula_token'
+export * from './formula_token_kind'
+export * from './function_arg'
+export * from './function_arg_type'
+export * from './function_info'
 export * from './gradient_fill'
 export * from './gradient_stop'
 export * from './line_shift_in_block'
@@ -35,6 +48,7 @@ export * from './normal_cell_id'
 export * from './pattern_fill'
 export * from './payload'
 export * from './payloads_action'
+export * from './recalc_action'
 export * from './row_info'
 export * from './row_shift'
 export * from './set_col_width'
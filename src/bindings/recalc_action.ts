@@ -0,0 +1,7 @@
+// DO NOT EDIT. CODE GENERATED BY gents.
+
+export type RecalcAction =
+    | 'Volatile'
+    | {Sheet: number}
+    | 'All'
+    | 'Now'
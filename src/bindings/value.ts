@@ -5,4 +5,5 @@ export type Value =
     | {bool: boolean}
     | {number: number}
     | {error: string}
+    | {date: string}
     | 'empty'
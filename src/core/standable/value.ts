@@ -6,6 +6,7 @@ export class StandardValue {
         | {$case: 'number'; number: number}
         | {$case: 'bool'; bool: boolean}
         | {$case: 'error'; error: string}
+        | {$case: 'date'; date: string}
     get value() {
         if (this.cellValueOneof?.$case === 'str') return this.cellValueOneof.str
         else if (this.cellValueOneof?.$case === 'bool')
@@ -14,6 +15,8 @@ export class StandardValue {
             return this.cellValueOneof.error
         else if (this.cellValueOneof?.$case === 'number')
             return this.cellValueOneof.number
+        else if (this.cellValueOneof?.$case === 'date')
+            return this.cellValueOneof.date
         else return ''
     }
     get valueStr() {
@@ -29,6 +32,8 @@ export class StandardValue {
             v.cellValueOneof = {$case: 'number', number: value.number as number}
         else if (hasOwnProperty(value, 'error'))
             v.cellValueOneof = {$case: 'error', error: value.error as string}
+        else if (hasOwnProperty(value, 'date'))
+            v.cellValueOneof = {$case: 'date', date: value.date as string}
         return v
     }
 }
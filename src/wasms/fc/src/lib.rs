@@ -10,3 +10,37 @@ pub fn formula_check(f: &str) -> bool {
     // web_sys::console::log_1(&r.to_string().into());
     r
 }
+
+/// Returns the `FormulaDiagnostic`s of the formula. The offsets are in the
+/// formula as it is given, including the '='.
+#[wasm_bindgen]
+pub fn formula_diagnose(f: &str) -> JsValue {
+    use logisheets_controller::check_formula;
+    let trimmed = f.trim_start();
+    let body = trimmed.strip_prefix('=').unwrap_or(trimmed).trim_start();
+    let offset = f.len() - body.len();
+    let diagnostics = check_formula(body.trim_end())
+        .into_iter()
+        .map(|mut d| {
+            d.start += offset;
+            d.end += offset;
+            d
+        })
+        .collect::<Vec<_>>();
+    JsValue::from_serde(&diagnostics).unwrap()
+}
+
+/// Convert the formula, which has SKIPPED the '=', from A1 to R1C1 relative
+/// to the cell at `row` and `col`. It is undefined if it can not be lexed.
+#[wasm_bindgen]
+pub fn formula_a1_to_r1c1(f: &str, row: usize, col: usize) -> Option<String> {
+    logisheets_controller::a1_to_r1c1(f, row, col)
+}
+
+/// Convert the formula, which has SKIPPED the '=', from R1C1 relative to the
+/// cell at `row` and `col` to A1. It is undefined if a reference is out of
+/// the sheet.
+#[wasm_bindgen]
+pub fn formula_r1c1_to_a1(f: &str, row: usize, col: usize) -> Option<String> {
+    logisheets_controller::r1c1_to_a1(f, row, col)
+}
@@ -7,9 +7,10 @@ use logisheets_controller::controller::edit_action::{
     PayloadsAction, RowShift,
 };
 use logisheets_controller::controller::{display::DisplayRequest, Controller};
-use logisheets_controller::{AsyncCalcResult, AsyncErr, Task};
+use logisheets_controller::{AsyncCalcResult, AsyncErr, AsyncReturnType, ReferenceStyle, Task};
 use serde::{Deserialize, Serialize};
 use std::sync::Mutex;
+use std::time::Duration;
 use wasm_bindgen::prelude::*;
 
 lazy_static! {
@@ -23,7 +24,12 @@ pub fn read_file(name: String, buf: &[u8]) -> ReadFileResult {
     let mut old_ctrl = CONTROLLER.lock().unwrap();
     let ctrl = Controller::from_file(name, buf);
     match ctrl {
-        Ok(c) => {
+        Ok(mut c) => {
+            // The registered functions are kept for the new workbook.
+            std::mem::swap(
+                &mut c.settings.func_registry,
+                &mut old_ctrl.settings.func_registry,
+            );
             *old_ctrl = c;
             ReadFileResult::Ok
         }
@@ -43,6 +49,17 @@ pub fn redo() -> bool {
     ctrl.redo()
 }
 
+#[wasm_bindgen]
+/// Read and input the formulas in the R1C1 notation, or in A1 if it is false.
+pub fn set_r1c1_style(r1c1: bool) {
+    let mut ctrl = CONTROLLER.lock().unwrap();
+    ctrl.settings.reference_style = if r1c1 {
+        ReferenceStyle::R1C1
+    } else {
+        ReferenceStyle::A1
+    };
+}
+
 #[wasm_bindgen]
 pub fn transaction_start() -> TransactionStartResult {
     TransactionStartResult::Ok
@@ -145,6 +162,97 @@ pub fn get_patches(sheet_idx: u32, version: u32) -> JsValue {
     }
 }
 
+#[wasm_bindgen]
+/// logisheets_controller::EvalTrace, or null if the cell has no formula
+pub fn evaluate_formula(sheet_idx: usize, row: usize, col: usize) -> JsValue {
+    let mut ctrl = CONTROLLER.lock().unwrap();
+    let trace = ctrl.evaluate_formula(sheet_idx, row, col);
+    JsValue::from_serde(&trace).unwrap()
+}
+
+#[wasm_bindgen]
+/// logisheets_controller::Dependency[]
+pub fn get_precedents(
+    sheet_idx: usize,
+    row_start: usize,
+    col_start: usize,
+    row_end: usize,
+    col_end: usize,
+    transitive: bool,
+) -> JsValue {
+    let ctrl = CONTROLLER.lock().unwrap();
+    let precedents = ctrl.get_precedents(
+        sheet_idx,
+        (row_start, col_start),
+        (row_end, col_end),
+        transitive,
+    );
+    JsValue::from_serde(&precedents.unwrap_or_default()).unwrap()
+}
+
+#[wasm_bindgen]
+/// logisheets_controller::Dependency[]
+pub fn get_dependents(
+    sheet_idx: usize,
+    row_start: usize,
+    col_start: usize,
+    row_end: usize,
+    col_end: usize,
+    transitive: bool,
+) -> JsValue {
+    let ctrl = CONTROLLER.lock().unwrap();
+    let dependents = ctrl.get_dependents(
+        sheet_idx,
+        (row_start, col_start),
+        (row_end, col_end),
+        transitive,
+    );
+    JsValue::from_serde(&dependents.unwrap_or_default()).unwrap()
+}
+
+/// Register a function calculated in JS. Its results are reused for
+/// `ttl_ms` milliseconds, or forever if it is omitted.
+#[wasm_bindgen]
+pub fn register_async_func(name: String, return_type: AsyncFuncReturnType, ttl_ms: Option<u32>) {
+    let return_type = match return_type {
+        AsyncFuncReturnType::Number => AsyncReturnType::Number,
+        AsyncFuncReturnType::Text => AsyncReturnType::Text,
+        AsyncFuncReturnType::Boolean => AsyncReturnType::Boolean,
+        AsyncFuncReturnType::Matrix => AsyncReturnType::Matrix,
+    };
+    let ttl = ttl_ms.map(|ms| Duration::from_millis(ms as u64));
+    let mut ctrl = CONTROLLER.lock().unwrap();
+    ctrl.register_async_func(&name, return_type, ttl);
+}
+
+/// Calculate the cells whose async results have expired. It is called
+/// periodically, and the output is the JSON format of `TransactionEndResult`.
+#[wasm_bindgen]
+pub fn recalc_expired_async_funcs() -> JsValue {
+    let mut ctrl = CONTROLLER.lock().unwrap();
+    let result = match ctrl.recalc_expired_async_funcs() {
+        Some(effect) => {
+            let async_id = if effect.async_tasks.len() > 0 {
+                let t = PendingTask {
+                    tasks: effect.async_tasks.clone(),
+                    dirtys: effect.dirtys.clone(),
+                };
+                ASYNC_HELPER.lock().unwrap().add_pending_task(t)
+            } else {
+                0
+            };
+            TransactionEndResult {
+                sheet_idx: effect.sheets,
+                tasks: effect.async_tasks,
+                async_id,
+                code: TransactionCode::Ok,
+            }
+        }
+        None => TransactionEndResult::from_err_code(TransactionCode::Err),
+    };
+    JsValue::from_serde(&result).unwrap()
+}
+
 #[wasm_bindgen]
 pub fn cell_input(sheet_idx: usize, row: usize, col: usize, content: String) {
     let mut payloads = PAYLOADS.lock().unwrap();
@@ -246,6 +354,14 @@ pub fn block_input(sheet_idx: usize, block_id: usize, row: usize, col: usize, in
     payloads.push(EditPayload::BlockInput(bi));
 }
 
+#[wasm_bindgen]
+pub enum AsyncFuncReturnType {
+    Number,
+    Text,
+    Boolean,
+    Matrix,
+}
+
 #[wasm_bindgen]
 pub enum ReadFileResult {
     Ok,
@@ -298,3 +414,45 @@ fn parse_async_value(s: String) -> AsyncCalcResult {
         _ => Ok(s),
     }
 }
+
+#[wasm_bindgen]
+/// logisheets_controller::CircularRef[]
+pub fn get_circular_refs() -> JsValue {
+    let mut ctrl = CONTROLLER.lock().unwrap();
+    let circular_refs = ctrl.get_circular_refs();
+    JsValue::from_serde(&circular_refs).unwrap()
+}
+
+#[wasm_bindgen]
+/// logisheets_controller::FormulaToken[]
+/// The formula has SKIPPED the '=' and may be partially typed.
+pub fn get_formula_tokens(sheet_idx: usize, row: usize, col: usize, formula: String) -> JsValue {
+    let mut ctrl = CONTROLLER.lock().unwrap();
+    let tokens = ctrl.get_formula_tokens(sheet_idx, row, col, &formula);
+    JsValue::from_serde(&tokens.unwrap_or_default()).unwrap()
+}
+
+#[wasm_bindgen]
+/// logisheets_controller::FormulaDiagnostic[]
+/// The formula has SKIPPED the '='.
+pub fn check_formula(formula: String) -> JsValue {
+    let ctrl = CONTROLLER.lock().unwrap();
+    let diagnostics = ctrl.check_formula(&formula);
+    JsValue::from_serde(&diagnostics).unwrap()
+}
+
+#[wasm_bindgen]
+/// logisheets_controller::FunctionInfo[]
+pub fn get_functions() -> JsValue {
+    let ctrl = CONTROLLER.lock().unwrap();
+    let functions = ctrl.get_functions();
+    JsValue::from_serde(&functions).unwrap()
+}
+
+#[wasm_bindgen]
+/// logisheets_controller::FunctionInfo, or null if the function is unknown
+pub fn get_function(name: String) -> JsValue {
+    let ctrl = CONTROLLER.lock().unwrap();
+    let function = ctrl.get_function(&name);
+    JsValue::from_serde(&function).unwrap()
+}
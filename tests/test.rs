@@ -107,3 +107,327 @@ mod calc_test {
         let _ = Workbook::from_file(&mut buf, String::from("calc_test")).unwrap();
     }
 }
+
+#[cfg(test)]
+mod round_trip {
+    use glob::glob;
+    use logisheets::{zipdiff_with_each, Whitelist, Workbook};
+    use std::fs;
+
+    // The differences between a file and the saved one that are expected.
+    fn whitelist(file: &str) -> Whitelist {
+        let parts = vec![
+            // Document properties are not loaded.
+            "docProps/",
+            // The relationships and the part names are generated again.
+            "[Content_Types].xml",
+            "_rels/.rels",
+            "xl/_rels/workbook.xml.rels",
+            // Extra color schemes are not kept.
+            "xl/theme/",
+        ];
+        let mut tags = vec![
+            // Only the namespaces we use are declared.
+            "mc:Ignorable",
+            "xmlns:etc",
+            "xmlns:mc",
+            "xmlns:x14",
+            "xmlns:x14ac",
+            "xmlns:xdr",
+            "xmlns:xr",
+            "xmlns:xr2",
+            "xmlns:xr3",
+            // The revision ids are not kept.
+            "xr:uid",
+            // Hints that spreadsheet applications compute again.
+            "calcId",
+            "dimension",
+            "fileVersion",
+            "spans",
+            "iterateCount",
+            "iterateDelta",
+            // The relationship ids are generated again.
+            "r:id",
+            // The named cell styles and the extensions of the stylesheet are
+            // not kept. The cell formats are compared by `normalize`.
+            "cellStyleXfs",
+            "cellStyles",
+            "extLst",
+        ];
+        match file {
+            // The volatile formulas are not marked.
+            "calc_test.xlsx" => tags.push("ca"),
+            // Row heights are saved as custom heights.
+            "builtin_style.xlsx" => tags.push("customHeight"),
+            _ => {}
+        }
+        Whitelist {
+            parts: parts.into_iter().map(String::from).collect(),
+            tags: tags.into_iter().map(String::from).collect(),
+            // The rows and the cells are saved with their references.
+            created_tags: vec![String::from("r")],
+        }
+    }
+
+    // The cell formats of a file, resolved from the number formats, the fonts,
+    // the fills and the borders they refer to.
+    fn get_cell_formats(buf: &[u8]) -> Vec<String> {
+        let book = logisheets_workbook::workbook::Workbook::from_file(buf).unwrap();
+        let styles = book.xl.styles.1;
+        let num_fmts = styles.num_fmts.map(|n| n.num_fmts).unwrap_or_default();
+        let fonts = styles.fonts.map(|f| f.fonts).unwrap_or_default();
+        let fills = styles.fills.map(|f| f.fills).unwrap_or_default();
+        let borders = styles.borders.map(|b| b.borders).unwrap_or_default();
+        let xfs = styles.cell_xfs.map(|x| x.xfs).unwrap_or_default();
+        let idx = |id: Option<u32>| id.unwrap_or(0) as usize;
+        xfs.iter()
+            .map(|xf| {
+                let num_fmt_id = xf.num_fmt_id.unwrap_or(0);
+                let num_fmt = num_fmts
+                    .iter()
+                    .find(|f| f.num_fmt_id == num_fmt_id)
+                    .map_or(num_fmt_id.to_string(), |f| f.format_code.clone());
+                format!(
+                    "{:?}",
+                    (
+                        num_fmt,
+                        fonts.get(idx(xf.font_id)),
+                        fills.get(idx(xf.fill_id)),
+                        borders.get(idx(xf.border_id)),
+                        &xf.alignment,
+                        &xf.protction,
+                    )
+                )
+            })
+            .collect()
+    }
+
+    // The formulas of the cells are compared after parsing them, since their
+    // spacing and array constants are not kept. The cached numbers are
+    // compared by their values and the defined names without the spaces.
+    // The style indexes are replaced by the ids of the formats they resolve
+    // to in `formats`, since identical formats are merged.
+    fn normalize(path: &str, content: String, formats: &[usize]) -> String {
+        // The default format is the same as no format.
+        let format = |s: &str| match s.parse::<usize>().ok().and_then(|i| formats.get(i)) {
+            Some(id) if Some(id) == formats.first() => None,
+            Some(id) => Some(id.to_string()),
+            None => Some(s.to_string()),
+        };
+        if path.starts_with("xl/worksheets/") {
+            let content = map_texts(&content, "f", |_| String::new());
+            let content = map_texts(&content, "v", |v| match v.parse::<f64>() {
+                Ok(n) => n.to_string(),
+                Err(_) => v.to_string(),
+            });
+            let content = map_attrs(&content, "c", "s", format);
+            let content = map_attrs(&content, "row", "s", format);
+            map_attrs(&content, "col", "style", format)
+        } else if path == "xl/styles.xml" {
+            let mut content = content;
+            for tag in ["numFmts", "fonts", "fills", "borders", "cellXfs"].iter() {
+                content = remove_element(&content, tag);
+            }
+            let mut ids = formats.to_vec();
+            ids.sort_unstable();
+            ids.dedup();
+            let xfs = ids
+                .iter()
+                .map(|id| format!("<xf format=\"{}\"/>", id))
+                .collect::<String>();
+            content.replace(
+                "</styleSheet>",
+                &format!("<cellXfs>{}</cellXfs></styleSheet>", xfs),
+            )
+        } else if path == "xl/workbook.xml" {
+            map_texts(&content, "definedName", remove_spaces)
+        } else {
+            content
+        }
+    }
+
+    // Replace the texts of the elements with this tag.
+    fn map_texts<F>(xml: &str, tag: &str, f: F) -> String
+    where
+        F: Fn(&str) -> String,
+    {
+        let open = format!("<{}", tag);
+        let close = format!("</{}>", tag);
+        let mut result = String::new();
+        let mut rest = xml;
+        while let Some(start) = rest.find(&open) {
+            let (head, tail) = rest.split_at(start + open.len());
+            result.push_str(head);
+            rest = tail;
+            // Skip the tags like `<formula1>` that start with this one.
+            if !rest.starts_with(|c: char| c == ' ' || c == '>') {
+                continue;
+            }
+            let (attrs, tail) = match rest.find('>') {
+                Some(i) => rest.split_at(i + 1),
+                None => break,
+            };
+            result.push_str(attrs);
+            rest = tail;
+            if attrs.ends_with("/>") {
+                continue;
+            }
+            if let Some(end) = rest.find(&close) {
+                result.push_str(&f(&rest[..end]));
+                rest = &rest[end..];
+            }
+        }
+        result.push_str(rest);
+        result
+    }
+
+    // Replace the values of the attribute in the elements with this tag. The
+    // attribute is removed if `f` returns none.
+    fn map_attrs<F>(xml: &str, tag: &str, attr: &str, f: F) -> String
+    where
+        F: Fn(&str) -> Option<String>,
+    {
+        let open = format!("<{}", tag);
+        let key = format!(" {}=\"", attr);
+        let mut result = String::new();
+        let mut rest = xml;
+        while let Some(start) = rest.find(&open) {
+            let (head, tail) = rest.split_at(start + open.len());
+            result.push_str(head);
+            rest = tail;
+            if !rest.starts_with(|c: char| c == ' ' || c == '>' || c == '/') {
+                continue;
+            }
+            let (attrs, tail) = match rest.find('>') {
+                Some(i) => rest.split_at(i),
+                None => break,
+            };
+            rest = tail;
+            let value = attrs.find(&key).and_then(|i| {
+                let start = i + key.len();
+                let end = start + attrs[start..].find('"')?;
+                Some((i, start, end))
+            });
+            match value {
+                Some((i, start, end)) => {
+                    result.push_str(&attrs[..i]);
+                    if let Some(v) = f(&attrs[start..end]) {
+                        result.push_str(&format!("{}{}\"", key, v));
+                    }
+                    result.push_str(&attrs[end + 1..]);
+                }
+                None => result.push_str(attrs),
+            }
+        }
+        result.push_str(rest);
+        result
+    }
+
+    // Remove the elements with this tag.
+    fn remove_element(xml: &str, tag: &str) -> String {
+        let open = format!("<{}", tag);
+        let close = format!("</{}>", tag);
+        let mut result = String::new();
+        let mut rest = xml;
+        while let Some(start) = rest.find(&open) {
+            let tail = &rest[start + open.len()..];
+            if !tail.starts_with(|c: char| c == ' ' || c == '>' || c == '/') {
+                result.push_str(&rest[..start + open.len()]);
+                rest = tail;
+                continue;
+            }
+            result.push_str(&rest[..start]);
+            rest = match tail.find('>') {
+                Some(i) if tail[..i].ends_with('/') => &tail[i + 1..],
+                _ => match tail.find(&close) {
+                    Some(i) => &tail[i + close.len()..],
+                    None => "",
+                },
+            };
+        }
+        result.push_str(rest);
+        result
+    }
+
+    // Remove the spaces out of the strings and the quoted sheet names.
+    fn remove_spaces(formula: &str) -> String {
+        let formula = formula
+            .replace("&quot;", "\"")
+            .replace("&apos;", "'")
+            .replace("&gt;", ">");
+        let mut quote = None;
+        formula
+            .chars()
+            .filter(|c| {
+                match quote {
+                    Some(q) if q == *c => quote = None,
+                    None if *c == '"' || *c == '\'' => quote = Some(*c),
+                    _ => {}
+                }
+                quote.is_some() || !c.is_whitespace()
+            })
+            .collect()
+    }
+
+    // Get the formulas of all the cells, after parsing them.
+    fn get_formulas(wb: &mut Workbook) -> Vec<(usize, usize, usize, String)> {
+        let mut result = vec![];
+        let mut idx = 0;
+        while let Ok(mut ws) = wb.get_sheet_by_idx(idx) {
+            let (rows, cols) = ws.get_sheet_dimension();
+            for r in 0..=rows {
+                for c in 0..=cols {
+                    let f = ws.get_formula(r, c).unwrap();
+                    if !f.is_empty() {
+                        result.push((idx, r, c, f));
+                    }
+                }
+            }
+            idx += 1;
+        }
+        result
+    }
+
+    // Saving a workbook should keep the parts of the file it is loaded from,
+    // except the differences in the whitelist.
+    #[test]
+    fn test_save_and_reload() {
+        let files = glob("tests/*.xlsx").expect("");
+        files.into_iter().for_each(|p| {
+            let path = p.unwrap();
+            let buf = fs::read(&path).unwrap();
+            let mut wb = Workbook::from_file(&buf, String::from("round_trip")).unwrap();
+            let saved = wb.controller.save().unwrap();
+            let file = path.file_name().unwrap().to_str().unwrap();
+            // The formats of both files are numbered together, so the same
+            // formats get the same ids.
+            let formats = (get_cell_formats(&buf), get_cell_formats(&saved));
+            let mut all = formats.0.iter().chain(&formats.1).collect::<Vec<_>>();
+            all.sort();
+            all.dedup();
+            let ids = |formats: &[String]| {
+                formats
+                    .iter()
+                    .map(|f| all.binary_search(&f).unwrap())
+                    .collect::<Vec<_>>()
+            };
+            let (formats1, formats2) = (ids(&formats.0), ids(&formats.1));
+            let diff = zipdiff_with_each(
+                &buf,
+                &saved,
+                |p, c| normalize(p, c, &formats1),
+                |p, c| normalize(p, c, &formats2),
+            )
+            .unwrap()
+            .filter(&whitelist(file));
+            assert!(diff.is_empty(), "{}: {:?}", path.display(), diff);
+            let mut reloaded = Workbook::from_file(&saved, String::from("round_trip")).unwrap();
+            assert_eq!(
+                get_formulas(&mut wb),
+                get_formulas(&mut reloaded),
+                "{}",
+                path.display()
+            );
+        });
+    }
+}